@@ -0,0 +1,23 @@
+//! Procedural macros used by the B-Rad Coin wallet crate.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Error, ItemFn};
+
+/// Run an async command's body through `command_metrics::track`, recorded under the function's
+/// name. The function must return `CommandResult`.
+#[proc_macro_attribute]
+pub fn tracked(_args: TokenStream, item: TokenStream) -> TokenStream {
+    let ItemFn { attrs, vis, sig, block } = parse_macro_input!(item as ItemFn);
+    if sig.asyncness.is_none() {
+        return Error::new_spanned(sig.fn_token, "only async commands can be tracked").to_compile_error().into();
+    }
+    let name = sig.ident.to_string();
+    quote! {
+        #(#attrs)*
+        #vis #sig {
+            crate::command_metrics::track(#name, async move #block).await
+        }
+    }
+    .into()
+}
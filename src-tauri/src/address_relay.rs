@@ -0,0 +1,165 @@
+//! Peer address gossip: answering GetAddr and taking in Addr messages.
+//!
+//! GetAddr is answered with a random sample of addresses seen recently, so a peer cannot map our
+//! whole address store with one request. Addresses from Addr messages are sanitised before they
+//! are stored: unroutable ones are dropped and implausible timestamps are replaced, since a peer
+//! could otherwise make its addresses look freshest. Each peer has a budget of addresses that
+//! refills slowly, so no single peer can flood the store with addresses it controls.
+
+use crate::network_service::{AddressFamily, PeerAddress};
+use rand::seq::SliceRandom;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+
+/// Most addresses in one Addr message; larger messages are rejected
+pub const MAX_ADDR_PER_MESSAGE: usize = 1_000;
+
+/// Share of recent addresses returned for one GetAddr, in percent
+const GETADDR_SAMPLE_PERCENT: usize = 23;
+
+/// Addresses seen within this many seconds are offered to peers
+const RECENT_SECS: u64 = 3 * 24 * 60 * 60;
+
+/// Addresses not seen for this long are not stored
+const MAX_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Timestamps this far in the future are not believed
+const MAX_FUTURE_SECS: u64 = 10 * 60;
+
+/// Age given to an address whose timestamp is not believed
+const PENALTY_AGE_SECS: u64 = 5 * 24 * 60 * 60;
+
+/// Addresses kept in the store; the least recently seen are dropped first
+pub const MAX_KNOWN_ADDRESSES: usize = 20_000;
+
+/// Addresses a peer's budget refills by per second
+const BUDGET_REFILL_PER_SEC: f64 = 0.1;
+
+/// Addresses a peer may send at once; also its budget when it connects, so one full answer to
+/// our GetAddr is accepted
+const BUDGET_MAX: f64 = MAX_ADDR_PER_MESSAGE as f64;
+
+/// How many more addresses a peer may push into the store
+#[derive(Debug, Clone)]
+pub struct AddrBudget {
+    tokens: f64,
+    updated: u64,
+}
+
+impl Default for AddrBudget {
+    fn default() -> Self {
+        Self { tokens: BUDGET_MAX, updated: 0 }
+    }
+}
+
+impl AddrBudget {
+    /// Take up to `wanted` addresses from the budget at `now` (Unix seconds) and return how many
+    /// may be processed
+    pub fn take(&mut self, wanted: usize, now: u64) -> usize {
+        if self.updated != 0 {
+            let elapsed = now.saturating_sub(self.updated) as f64;
+            self.tokens = (self.tokens + elapsed * BUDGET_REFILL_PER_SEC).min(BUDGET_MAX);
+        }
+        self.updated = now;
+
+        let granted = (self.tokens.floor() as usize).min(wanted);
+        self.tokens -= granted as f64;
+        granted
+    }
+}
+
+/// A random sample of recently seen addresses to answer GetAddr with
+pub fn sample_for_getaddr(known: &HashSet<PeerAddress>, now: u64) -> Vec<PeerAddress> {
+    let mut recent: Vec<PeerAddress> = known
+        .iter()
+        .filter(|address| now.saturating_sub(address.last_seen) <= RECENT_SECS)
+        .cloned()
+        .collect();
+    let count = (recent.len() * GETADDR_SAMPLE_PERCENT / 100).clamp(recent.len().min(1), MAX_ADDR_PER_MESSAGE);
+    recent.shuffle(&mut rand::rng());
+    recent.truncate(count);
+    recent
+}
+
+/// Check an address received from a peer. Returns None for addresses not worth storing.
+pub fn sanitize(mut address: PeerAddress, now: u64) -> Option<PeerAddress> {
+    if address.ip.is_unspecified() || address.ip.is_multicast() || address.port == 0 {
+        return None;
+    }
+    // The family is derived from the address, not taken on trust
+    address.family = AddressFamily::of(address.ip);
+    if address.last_seen > now + MAX_FUTURE_SECS || address.last_seen == 0 {
+        address.last_seen = now.saturating_sub(PENALTY_AGE_SECS);
+    }
+    if now.saturating_sub(address.last_seen) > MAX_AGE_SECS {
+        return None;
+    }
+    Some(address)
+}
+
+/// Add addresses to the store, keeping one entry per address with the newest timestamp and
+/// dropping the least recently seen addresses beyond `MAX_KNOWN_ADDRESSES`. Returns how many
+/// addresses were new.
+pub fn merge(known: &mut HashSet<PeerAddress>, addresses: Vec<PeerAddress>) -> usize {
+    let mut by_socket: HashMap<SocketAddr, PeerAddress> = known
+        .drain()
+        .map(|address| (SocketAddr::new(address.ip, address.port), address))
+        .collect();
+
+    let mut added = 0;
+    for address in addresses {
+        match by_socket.entry(SocketAddr::new(address.ip, address.port)) {
+            std::collections::hash_map::Entry::Occupied(mut entry) => {
+                if address.last_seen > entry.get().last_seen {
+                    entry.insert(address);
+                }
+            }
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                entry.insert(address);
+                added += 1;
+            }
+        }
+    }
+
+    let mut addresses: Vec<PeerAddress> = by_socket.into_values().collect();
+    if addresses.len() > MAX_KNOWN_ADDRESSES {
+        addresses.sort_by_key(|address| std::cmp::Reverse(address.last_seen));
+        addresses.truncate(MAX_KNOWN_ADDRESSES);
+    }
+    known.extend(addresses);
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network_constants::create_peer_address;
+
+    #[test]
+    fn test_addresses_are_sanitised_merged_and_rate_limited() {
+        let now = 1_700_000_000;
+        let address = |ip: &str, last_seen: u64| PeerAddress {
+            last_seen,
+            ..create_peer_address(ip.parse().unwrap(), 8333, 1)
+        };
+
+        // A timestamp from the future is replaced; stale and unroutable addresses are dropped
+        let future = sanitize(address("10.0.0.1", now + 3600), now).unwrap();
+        assert_eq!(future.last_seen, now - PENALTY_AGE_SECS);
+        assert!(sanitize(address("10.0.0.2", now - MAX_AGE_SECS - 1), now).is_none());
+        assert!(sanitize(address("0.0.0.0", now), now).is_none());
+
+        let mut known = HashSet::new();
+        assert_eq!(merge(&mut known, vec![address("10.0.0.1", now - 100), address("10.0.0.3", now)]), 2);
+        assert_eq!(merge(&mut known, vec![address("10.0.0.1", now)]), 0);
+        assert_eq!(known.len(), 2);
+        assert!(known.iter().all(|address| address.last_seen == now));
+        assert_eq!(sample_for_getaddr(&known, now).len(), 1);
+
+        // The first full message is accepted, after that the budget refills slowly
+        let mut budget = AddrBudget::default();
+        assert_eq!(budget.take(MAX_ADDR_PER_MESSAGE, now), MAX_ADDR_PER_MESSAGE);
+        assert_eq!(budget.take(10, now + 1), 0);
+        assert_eq!(budget.take(10, now + 61), 6);
+    }
+}
@@ -0,0 +1,68 @@
+use log::{debug, warn};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Path of the temporary file used while replacing `path`
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Path of the last known good copy of `path`
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// Replace a file so that a crash leaves either the old or the new contents, never a truncated file.
+/// The data is written to a temporary file, fsynced, then renamed over the target.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let tmp = temp_path(path);
+    {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+
+    if let Err(e) = fs::rename(&tmp, path) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+
+    sync_parent_dir(path);
+    debug!("Atomically wrote {}", path.display());
+    Ok(())
+}
+
+/// Like `write_atomic`, but first keeps the current file as `<name>.bak`
+pub fn write_atomic_with_backup(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if path.exists() {
+        if let Err(e) = fs::copy(path, backup_path(path)) {
+            warn!("Failed to back up {}: {}", path.display(), e);
+        }
+    }
+    write_atomic(path, contents)
+}
+
+/// Persist the rename itself. Directories cannot be opened for syncing on Windows, so this is best effort.
+fn sync_parent_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = fs::File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
@@ -0,0 +1,178 @@
+//! Tamper-evident audit log of security-sensitive operations.
+//!
+//! Unlock attempts, private key exports, changes to security settings and push API access are
+//! appended to `audit.log` in the data directory, one JSON record per line, separately from the
+//! debug log. Each record carries the SHA-256 hash of the record before it, so editing, inserting
+//! or removing a record breaks the chain from that point on. Records cut off the end of the file
+//! leave a valid chain behind, which the chain alone cannot reveal.
+
+use crate::paths::PathProvider;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// File name of the audit log in the data directory
+pub const AUDIT_LOG_FILE: &str = "audit.log";
+
+/// Previous hash of the first record
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Sequence number and hash of the last record written, read from the file on first use
+static CHAIN_TIP: Mutex<Option<(u64, String)>> = Mutex::new(None);
+
+/// One audited operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub timestamp: i64,
+    pub event: String,
+    pub details: String,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditRecord {
+    fn new(seq: u64, timestamp: i64, event: &str, details: &str, prev_hash: &str) -> Self {
+        let mut record = Self {
+            seq,
+            timestamp,
+            event: event.to_string(),
+            details: details.to_string(),
+            prev_hash: prev_hash.to_string(),
+            hash: String::new(),
+        };
+        record.hash = record.compute_hash();
+        record
+    }
+
+    fn compute_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.seq.to_le_bytes());
+        hasher.update(self.timestamp.to_le_bytes());
+        // Length-prefixed so text cannot be moved between fields without changing the hash
+        for field in [&self.event, &self.details, &self.prev_hash] {
+            hasher.update((field.len() as u64).to_le_bytes());
+            hasher.update(field.as_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// Result of checking the hash chain
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditVerification {
+    pub valid: bool,
+    /// Lines in the file
+    pub entries: usize,
+    /// Line number, from 1, of the first record that breaks the chain
+    pub first_invalid_line: Option<usize>,
+    pub error: Option<String>,
+}
+
+/// Parse the contents of an audit log and check its chain. Returns the readable records, oldest
+/// first, and the outcome of the check.
+pub fn parse_and_verify(contents: &str) -> (Vec<AuditRecord>, AuditVerification) {
+    let mut records = Vec::new();
+    let mut failure: Option<(usize, String)> = None;
+    let mut prev_hash = GENESIS_HASH.to_string();
+    let mut entries = 0;
+
+    for (index, line) in contents.lines().filter(|line| !line.trim().is_empty()).enumerate() {
+        entries += 1;
+        let line_number = index + 1;
+        let record: AuditRecord = match serde_json::from_str(line) {
+            Ok(record) => record,
+            Err(e) => {
+                failure.get_or_insert((line_number, format!("Record is not readable: {}", e)));
+                continue;
+            }
+        };
+        if failure.is_none() {
+            if record.seq != index as u64 {
+                failure = Some((line_number, format!("Expected record {}, found {}", index, record.seq)));
+            } else if record.prev_hash != prev_hash {
+                failure = Some((line_number, "Record does not follow the one before it".to_string()));
+            } else if record.compute_hash() != record.hash {
+                failure = Some((line_number, "Record was modified after it was written".to_string()));
+            }
+        }
+        prev_hash = record.hash.clone();
+        records.push(record);
+    }
+
+    let verification = AuditVerification {
+        valid: failure.is_none(),
+        entries,
+        first_invalid_line: failure.as_ref().map(|(line, _)| *line),
+        error: failure.map(|(_, error)| error),
+    };
+    (records, verification)
+}
+
+/// Read and verify the audit log at `path`. A missing file is an empty, valid log.
+pub fn read(path: &Path) -> io::Result<(Vec<AuditRecord>, AuditVerification)> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    Ok(parse_and_verify(&contents))
+}
+
+/// Append a record to the audit log in the data directory
+pub fn append(event: &str, details: &str) -> io::Result<()> {
+    let path = PathProvider::data_dir()
+        .map(|dir| dir.join(AUDIT_LOG_FILE))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Failed to determine app data directory"))?;
+
+    let mut tip = CHAIN_TIP.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if tip.is_none() {
+        let (records, _) = read(&path)?;
+        *tip = records.last().map(|record| (record.seq, record.hash.clone()));
+    }
+    let record = match tip.as_ref() {
+        Some((seq, hash)) => AuditRecord::new(seq + 1, chrono::Utc::now().timestamp(), event, details, hash),
+        None => AuditRecord::new(0, chrono::Utc::now().timestamp(), event, details, GENESIS_HASH),
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    file.write_all(format!("{}\n", serde_json::to_string(&record)?).as_bytes())?;
+    file.sync_data()?;
+
+    *tip = Some((record.seq, record.hash));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tampering_breaks_the_chain() {
+        let first = AuditRecord::new(0, 1_700_000_000, "wallet_unlocked", "wallet=main", GENESIS_HASH);
+        let second = AuditRecord::new(1, 1_700_000_060, "private_key_exported", "wallet=main", &first.hash);
+        let third = AuditRecord::new(2, 1_700_000_120, "wallet_password_set", "wallet=main", &second.hash);
+        let lines = |records: &[&AuditRecord]| {
+            records.iter().map(|record| serde_json::to_string(record).unwrap() + "\n").collect::<String>()
+        };
+
+        let (records, verification) = parse_and_verify(&lines(&[&first, &second, &third]));
+        assert!(verification.valid);
+        assert_eq!(records.len(), 3);
+
+        let mut edited = second.clone();
+        edited.details = "wallet=other".to_string();
+        let (_, verification) = parse_and_verify(&lines(&[&first, &edited, &third]));
+        assert_eq!(verification.first_invalid_line, Some(2));
+
+        let (_, verification) = parse_and_verify(&lines(&[&first, &third]));
+        assert!(!verification.valid);
+        assert_eq!(verification.first_invalid_line, Some(2));
+    }
+}
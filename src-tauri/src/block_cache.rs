@@ -0,0 +1,174 @@
+//! Decoded blocks kept in memory by height.
+//!
+//! Wallet scans, the explorer and reorg checks read the same recent blocks again and again, and
+//! every read from the store decodes the whole block. The cache keeps the most recently used
+//! blocks up to a byte budget, counted by their encoded size. The budget is a setting and shrinks
+//! while the system is short of memory.
+
+use crate::blockchain_database::Block;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+struct CachedBlock {
+    block: Block,
+    /// Encoded size, what the entry counts against the budget
+    bytes: usize,
+    /// Position in `recency`
+    used_at: u64,
+}
+
+/// Counters of the block cache, for `get_database_stats`
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockCacheStats {
+    pub entries: usize,
+    pub bytes: usize,
+    pub capacity_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Share of lookups answered from the cache, 0.0 before the first lookup
+    pub hit_rate: f64,
+}
+
+/// Least recently used blocks are evicted first
+pub struct BlockCache {
+    entries: HashMap<u64, CachedBlock>,
+    /// Heights by last use (use counter -> height), oldest first
+    recency: BTreeMap<u64, u64>,
+    uses: u64,
+    bytes: usize,
+    capacity_bytes: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl BlockCache {
+    pub fn new(capacity_bytes: usize) -> Self {
+        BlockCache {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            uses: 0,
+            bytes: 0,
+            capacity_bytes,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// The cached block at `height`, counted as a hit or a miss
+    pub fn get(&mut self, height: u64) -> Option<Block> {
+        let Some(entry) = self.entries.get_mut(&height) else {
+            self.misses += 1;
+            return None;
+        };
+        self.hits += 1;
+        self.recency.remove(&entry.used_at);
+        self.uses += 1;
+        entry.used_at = self.uses;
+        self.recency.insert(self.uses, height);
+        Some(entry.block.clone())
+    }
+
+    /// Cache a block, replacing any other at its height. Blocks larger than the whole budget are
+    /// not cached.
+    pub fn insert(&mut self, block: &Block, bytes: usize) {
+        self.remove(block.height);
+        if bytes > self.capacity_bytes {
+            return;
+        }
+        self.uses += 1;
+        self.recency.insert(self.uses, block.height);
+        self.entries.insert(
+            block.height,
+            CachedBlock {
+                block: block.clone(),
+                bytes,
+                used_at: self.uses,
+            },
+        );
+        self.bytes += bytes;
+        self.evict_to(self.capacity_bytes);
+    }
+
+    pub fn remove(&mut self, height: u64) {
+        if let Some(entry) = self.entries.remove(&height) {
+            self.recency.remove(&entry.used_at);
+            self.bytes -= entry.bytes;
+        }
+    }
+
+    /// Change the budget, evicting blocks until the cache fits
+    pub fn set_capacity(&mut self, capacity_bytes: usize) {
+        self.capacity_bytes = capacity_bytes;
+        self.evict_to(capacity_bytes);
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity_bytes
+    }
+
+    pub fn stats(&self) -> BlockCacheStats {
+        let lookups = self.hits + self.misses;
+        BlockCacheStats {
+            entries: self.entries.len(),
+            bytes: self.bytes,
+            capacity_bytes: self.capacity_bytes,
+            hits: self.hits,
+            misses: self.misses,
+            hit_rate: if lookups == 0 { 0.0 } else { self.hits as f64 / lookups as f64 },
+        }
+    }
+
+    fn evict_to(&mut self, capacity_bytes: usize) {
+        while self.bytes > capacity_bytes {
+            let Some((_, height)) = self.recency.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&height) {
+                self.bytes -= entry.bytes;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(height: u64) -> Block {
+        Block {
+            height,
+            hash: format!("{:064x}", height),
+            previous_hash: String::new(),
+            timestamp: 0,
+            nonce: 0,
+            difficulty: 1,
+            transactions: Vec::new(),
+            merkle_root: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_least_recently_used_blocks_are_evicted_to_fit_the_budget() {
+        let mut cache = BlockCache::new(300);
+        for height in 0..3 {
+            cache.insert(&block(height), 100);
+        }
+        assert!(cache.get(0).is_some());
+
+        // Height 1 is now the least recently used
+        cache.insert(&block(3), 100);
+        assert!(cache.get(1).is_none());
+        assert!(cache.get(0).is_some());
+
+        cache.set_capacity(100);
+        assert_eq!(cache.stats().entries, 1);
+        assert!(cache.get(0).is_some());
+        cache.insert(&block(4), 1000);
+        assert!(cache.get(4).is_none());
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (3, 2));
+        assert_eq!(stats.bytes, 100);
+        assert!((stats.hit_rate - 0.6).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,481 @@
+//! Storage backends of the blockchain database.
+//!
+//! `BlockchainDatabase` keeps its records in ten key-value trees and only needs a few things
+//! from the storage engine: point reads, ordered prefix scans, tree sizes and an atomic commit of
+//! writes spanning several trees. `BlockStore` is that contract, so the engine can be changed
+//! without touching the database's callers. Sled is the original backend; redb is the
+//! alternative. A data directory holds one store, and `copy_store` moves an existing one to
+//! another backend and checks every record made it.
+
+use anyhow::{Context, Result};
+use log::{debug, error, info};
+use redb::ReadableTableMetadata;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+
+/// Trees of the database, in the order of the writes passed to `BlockStore::apply`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeId {
+    Blocks,
+    Transactions,
+    Utxos,
+    Addresses,
+    Metadata,
+    Filters,
+    AddressTxs,
+    MinedBlocks,
+    BlockStats,
+    BlockIndex,
+}
+
+impl TreeId {
+    pub const ALL: [TreeId; 10] = [
+        TreeId::Blocks,
+        TreeId::Transactions,
+        TreeId::Utxos,
+        TreeId::Addresses,
+        TreeId::Metadata,
+        TreeId::Filters,
+        TreeId::AddressTxs,
+        TreeId::MinedBlocks,
+        TreeId::BlockStats,
+        TreeId::BlockIndex,
+    ];
+
+    /// Name of the tree in the store
+    pub fn name(self) -> &'static str {
+        match self {
+            TreeId::Blocks => "blocks",
+            TreeId::Transactions => "transactions",
+            TreeId::Utxos => "utxos",
+            TreeId::Addresses => "addresses",
+            TreeId::Metadata => "metadata",
+            TreeId::Filters => "filters",
+            TreeId::AddressTxs => "address_txs",
+            TreeId::MinedBlocks => "mined_blocks",
+            TreeId::BlockStats => "block_stats",
+            TreeId::BlockIndex => "block_index",
+        }
+    }
+}
+
+/// Writes to one tree; a None value removes the key
+pub type TreeWrites = BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+
+/// Called by `BlockStore::scan` with each key and value; returning false stops the scan
+pub type ScanVisitor<'a> = dyn FnMut(&[u8], &[u8]) -> Result<bool> + 'a;
+
+/// The storage engine under the blockchain database
+pub trait BlockStore: Send + Sync {
+    fn backend(&self) -> StoreBackend;
+
+    fn get(&self, tree: TreeId, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    /// Visit the entries of `tree` whose key starts with `prefix` in key order, or in reverse,
+    /// until `visit` returns false
+    fn scan(
+        &self,
+        tree: TreeId,
+        prefix: &[u8],
+        reverse: bool,
+        visit: &mut ScanVisitor<'_>,
+    ) -> Result<()>;
+
+    fn len(&self, tree: TreeId) -> Result<usize>;
+
+    fn is_empty(&self, tree: TreeId) -> Result<bool> {
+        Ok(self.len(tree)? == 0)
+    }
+
+    /// Apply the writes of every tree, indexed by `TreeId`, in one atomic and durable commit
+    fn apply(&self, writes: &[TreeWrites]) -> Result<()>;
+
+    fn flush(&self) -> Result<()>;
+
+    /// Bytes the store takes on disk
+    fn size_on_disk(&self) -> Result<u64>;
+}
+
+/// Storage engines a blockchain database can use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreBackend {
+    Sled,
+    Redb,
+}
+
+impl StoreBackend {
+    /// Where this backend keeps its store in a data directory
+    pub fn path(self, data_dir: &Path) -> PathBuf {
+        match self {
+            StoreBackend::Sled => data_dir.join("blockchain.db"),
+            StoreBackend::Redb => data_dir.join("blockchain.redb"),
+        }
+    }
+
+    /// The backend of the store in a data directory. A directory holds a single store, moved
+    /// aside by a migration, so a redb file means redb; anything else is sled.
+    pub fn detect(data_dir: &Path) -> Self {
+        if StoreBackend::Redb.path(data_dir).exists() {
+            StoreBackend::Redb
+        } else {
+            StoreBackend::Sled
+        }
+    }
+
+    /// Open the store at `path`, letting the engine cache up to `cache_bytes` of it in memory
+    pub fn open(self, path: &Path, cache_bytes: u64) -> Result<Box<dyn BlockStore>> {
+        Ok(match self {
+            StoreBackend::Sled => Box::new(SledStore::open(path, cache_bytes)?),
+            StoreBackend::Redb => Box::new(RedbStore::open(path, cache_bytes)?),
+        })
+    }
+}
+
+/// End of the key range that starts with `prefix`, or None when it runs to the last key
+fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
+}
+
+/// Store on sled, one sled tree per `TreeId`
+pub struct SledStore {
+    db: sled::Db,
+    trees: Vec<sled::Tree>,
+}
+
+impl SledStore {
+    pub fn open(path: &Path, cache_bytes: u64) -> Result<Self> {
+        debug!("Opening sled database at {}", path.display());
+        let db = match sled::Config::new().path(path).cache_capacity(cache_bytes).open() {
+            Ok(db) => {
+                info!("Sled database opened");
+                db
+            },
+            Err(e) => {
+                error!("Failed to open sled database at {}: {}", path.display(), e);
+                debug!(
+                    "Path exists: {}, parent exists: {}",
+                    path.exists(),
+                    path.parent().map(|p| p.exists()).unwrap_or(false)
+                );
+
+                // Check if this looks like a database lock error
+                let error_msg = e.to_string().to_lowercase();
+                if error_msg.contains("lock") ||
+                   error_msg.contains("in use") ||
+                   error_msg.contains("already") ||
+                   error_msg.contains("resource busy") ||
+                   error_msg.contains("cannot acquire") {
+                    return Err(anyhow::anyhow!(
+                        "Database is currently in use by another process. Please ensure no other instances of B-Rad Coin are running and try again."
+                    ));
+                }
+
+                return Err(anyhow::anyhow!("Failed to open blockchain database: {}", e));
+            }
+        };
+
+        debug!("Opening database trees");
+        let trees = TreeId::ALL
+            .iter()
+            .map(|tree| db.open_tree(tree.name()).with_context(|| format!("Failed to open {} tree", tree.name())))
+            .collect::<Result<Vec<_>>>()?;
+        debug!("All database trees opened");
+        Ok(Self { db, trees })
+    }
+
+    fn tree(&self, tree: TreeId) -> &sled::Tree {
+        &self.trees[tree as usize]
+    }
+}
+
+impl BlockStore for SledStore {
+    fn backend(&self) -> StoreBackend {
+        StoreBackend::Sled
+    }
+
+    fn get(&self, tree: TreeId, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.tree(tree).get(key)?.map(|value| value.to_vec()))
+    }
+
+    fn scan(
+        &self,
+        tree: TreeId,
+        prefix: &[u8],
+        reverse: bool,
+        visit: &mut ScanVisitor<'_>,
+    ) -> Result<()> {
+        let mut entries = self.tree(tree).scan_prefix(prefix);
+        loop {
+            let entry = if reverse { entries.next_back() } else { entries.next() };
+            let Some(entry) = entry else {
+                return Ok(());
+            };
+            let (key, value) = entry?;
+            if !visit(&key, &value)? {
+                return Ok(());
+            }
+        }
+    }
+
+    fn len(&self, tree: TreeId) -> Result<usize> {
+        Ok(self.tree(tree).len())
+    }
+
+    fn is_empty(&self, tree: TreeId) -> Result<bool> {
+        Ok(self.tree(tree).is_empty())
+    }
+
+    fn apply(&self, writes: &[TreeWrites]) -> Result<()> {
+        use sled::transaction::{ConflictableTransactionError, Transactional};
+
+        let batches: Vec<sled::Batch> = writes
+            .iter()
+            .map(|writes| {
+                let mut batch = sled::Batch::default();
+                for (key, value) in writes {
+                    match value {
+                        Some(value) => batch.insert(key.as_slice(), value.as_slice()),
+                        None => batch.remove(key.as_slice()),
+                    }
+                }
+                batch
+            })
+            .collect();
+        self.trees
+            .as_slice()
+            .transaction(|trees| {
+                for (tree, batch) in trees.iter().zip(&batches) {
+                    tree.apply_batch(batch)?;
+                }
+                Ok::<_, ConflictableTransactionError<()>>(())
+            })
+            .map_err(|e| anyhow::anyhow!("Failed to commit staged blockchain writes: {:?}", e))?;
+        self.db.flush().context("Failed to flush committed blockchain writes")?;
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+
+    fn size_on_disk(&self) -> Result<u64> {
+        Ok(self.db.size_on_disk()?)
+    }
+}
+
+type RedbTable = redb::TableDefinition<'static, &'static [u8], &'static [u8]>;
+
+/// Store in a single redb file, one table per `TreeId`
+pub struct RedbStore {
+    db: redb::Database,
+    path: PathBuf,
+}
+
+impl RedbStore {
+    pub fn open(path: &Path, cache_bytes: u64) -> Result<Self> {
+        let db = redb::Builder::new()
+            .set_cache_size(usize::try_from(cache_bytes).unwrap_or(usize::MAX))
+            .create(path)
+            .with_context(|| format!("Failed to open redb store {:?}", path))?;
+        // Create every table up front, so reads never find one missing
+        let txn = db.begin_write()?;
+        for tree in TreeId::ALL {
+            txn.open_table(Self::table(tree))?;
+        }
+        txn.commit()?;
+        Ok(Self { db, path: path.to_path_buf() })
+    }
+
+    fn table(tree: TreeId) -> RedbTable {
+        redb::TableDefinition::new(tree.name())
+    }
+}
+
+impl BlockStore for RedbStore {
+    fn backend(&self) -> StoreBackend {
+        StoreBackend::Redb
+    }
+
+    fn get(&self, tree: TreeId, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(Self::table(tree))?;
+        Ok(table.get(key)?.map(|value| value.value().to_vec()))
+    }
+
+    fn scan(
+        &self,
+        tree: TreeId,
+        prefix: &[u8],
+        reverse: bool,
+        visit: &mut ScanVisitor<'_>,
+    ) -> Result<()> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(Self::table(tree))?;
+        let end = prefix_end(prefix);
+        let upper = match &end {
+            Some(end) => Bound::Excluded(end.as_slice()),
+            None => Bound::Unbounded,
+        };
+        let mut entries = table.range::<&[u8]>((Bound::Included(prefix), upper))?;
+        loop {
+            let entry = if reverse { entries.next_back() } else { entries.next() };
+            let Some(entry) = entry else {
+                return Ok(());
+            };
+            let (key, value) = entry?;
+            if !visit(key.value(), value.value())? {
+                return Ok(());
+            }
+        }
+    }
+
+    fn len(&self, tree: TreeId) -> Result<usize> {
+        let txn = self.db.begin_read()?;
+        Ok(txn.open_table(Self::table(tree))?.len()? as usize)
+    }
+
+    fn apply(&self, writes: &[TreeWrites]) -> Result<()> {
+        let txn = self.db.begin_write()?;
+        for (tree, writes) in TreeId::ALL.into_iter().zip(writes) {
+            if writes.is_empty() {
+                continue;
+            }
+            let mut table = txn.open_table(Self::table(tree))?;
+            for (key, value) in writes {
+                match value {
+                    Some(value) => {
+                        table.insert(key.as_slice(), value.as_slice())?;
+                    }
+                    None => {
+                        table.remove(key.as_slice())?;
+                    }
+                }
+            }
+        }
+        txn.commit().context("Failed to commit staged blockchain writes")?;
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        // Every redb commit is already durable
+        Ok(())
+    }
+
+    fn size_on_disk(&self) -> Result<u64> {
+        Ok(std::fs::metadata(&self.path)?.len())
+    }
+}
+
+/// Records copied per commit into the target store
+const COPY_BATCH_RECORDS: usize = 10_000;
+
+/// What `copy_store` copied
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreCopy {
+    pub from: StoreBackend,
+    pub to: StoreBackend,
+    /// Records copied, by tree name
+    pub records: BTreeMap<&'static str, usize>,
+    pub verified: bool,
+}
+
+/// Copy every record of `source` into the empty store `target`, then read each back from
+/// `target` and check both trees hold the same number of records
+pub fn copy_store(source: &dyn BlockStore, target: &dyn BlockStore) -> Result<StoreCopy> {
+    for tree in TreeId::ALL {
+        if !target.is_empty(tree)? {
+            return Err(anyhow::anyhow!("The target store already holds {} records", tree.name()));
+        }
+    }
+
+    let mut records = BTreeMap::new();
+    for tree in TreeId::ALL {
+        let mut writes: Vec<TreeWrites> = vec![TreeWrites::new(); TreeId::ALL.len()];
+        let mut copied = 0;
+        source.scan(tree, &[], false, &mut |key, value| {
+            writes[tree as usize].insert(key.to_vec(), Some(value.to_vec()));
+            copied += 1;
+            if writes[tree as usize].len() >= COPY_BATCH_RECORDS {
+                target.apply(&writes)?;
+                writes[tree as usize].clear();
+            }
+            Ok(true)
+        })?;
+        target.apply(&writes)?;
+        info!("Copied {} {} records to the {:?} store", copied, tree.name(), target.backend());
+        records.insert(tree.name(), copied);
+    }
+
+    for tree in TreeId::ALL {
+        source.scan(tree, &[], false, &mut |key, value| {
+            if target.get(tree, key)?.as_deref() != Some(value) {
+                return Err(anyhow::anyhow!("A {} record differs after the copy", tree.name()));
+            }
+            Ok(true)
+        })?;
+        if target.len(tree)? != records[tree.name()] {
+            return Err(anyhow::anyhow!("The copied {} tree has a different number of records", tree.name()));
+        }
+    }
+
+    Ok(StoreCopy {
+        from: source.backend(),
+        to: target.backend(),
+        records,
+        verified: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_end_covers_every_key_with_the_prefix() {
+        assert_eq!(prefix_end(b"ab"), Some(b"ac".to_vec()));
+        assert_eq!(prefix_end(&[1, 0xff]), Some(vec![2]));
+        assert_eq!(prefix_end(&[0xff, 0xff]), None);
+        assert_eq!(prefix_end(&[]), None);
+    }
+
+    #[test]
+    fn test_copy_store_from_sled_to_redb() {
+        let dir = std::env::temp_dir().join(format!("bradcoin_copy_store_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let sled = StoreBackend::Sled.open(&StoreBackend::Sled.path(&dir), 1 << 20).unwrap();
+        let redb = StoreBackend::Redb.open(&StoreBackend::Redb.path(&dir), 1 << 20).unwrap();
+
+        let mut writes: Vec<TreeWrites> = vec![TreeWrites::new(); TreeId::ALL.len()];
+        for i in 0u8..3 {
+            writes[TreeId::Blocks as usize].insert(vec![i], Some(vec![i; 4]));
+        }
+        writes[TreeId::Metadata as usize].insert(b"height".to_vec(), Some(vec![2]));
+        sled.apply(&writes).unwrap();
+
+        let copy = copy_store(sled.as_ref(), redb.as_ref()).unwrap();
+        assert!(copy.verified);
+        assert_eq!((copy.from, copy.to), (StoreBackend::Sled, StoreBackend::Redb));
+        assert_eq!(copy.records["blocks"], 3);
+        assert_eq!(copy.records["metadata"], 1);
+        assert_eq!(copy.records["utxos"], 0);
+        assert_eq!(redb.get(TreeId::Blocks, &[1]).unwrap(), Some(vec![1; 4]));
+
+        // The target must start empty
+        assert!(copy_store(sled.as_ref(), redb.as_ref()).is_err());
+
+        drop((sled, redb));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,15 +1,24 @@
 use std::path::PathBuf;
-use std::sync::Arc;
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use sled::{Db, Tree};
 use tokio::sync::RwLock;
-use log::{info, error, warn};
+use log::{debug, info, error, warn};
 
 use bincode::{Decode, Encode};
 
+use crate::block_cache::{BlockCache, BlockCacheStats};
+use crate::block_store::{self, BlockStore, StoreBackend, StoreCopy, TreeId};
+use crate::chain_params::ChainParams;
+use crate::event_bus::{BackendEvent, EventBus};
+use crate::file_lock::{FileLock, LockError, BLOCKCHAIN_LOCK_FILE};
+use crate::sync_progress::SyncProgress;
+
 /// Block data structure
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
 pub struct Block {
@@ -61,18 +70,334 @@ pub struct UTXO {
     pub block_height: u64,
 }
 
-/// Blockchain database service using Sled
+/// A block mined by this node, kept for the mining income history
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct MinedBlock {
+    pub height: u64,
+    pub hash: String,
+    /// Address the coinbase pays
+    pub address: String,
+    pub timestamp: u64,
+    /// Subsidy plus fees, as paid by the coinbase
+    pub reward: u64,
+    /// Replaced at its height by another block, so the reward was lost
+    pub orphaned: bool,
+}
+
+/// Difficulty and timing of one block, kept for charts
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct BlockStats {
+    pub height: u64,
+    pub timestamp: u64,
+    pub difficulty: u64,
+    /// Seconds since the parent block; 0 for genesis or when the clock went backwards
+    pub solve_time: u64,
+    pub transaction_count: u32,
+}
+
+/// A block of the block tree, which holds the active chain and every branch seen beside it
+#[derive(Debug, Clone, Encode, Decode)]
+struct BlockIndexEntry {
+    height: u64,
+    previous_hash: String,
+    /// Work of the chain up to and including this block
+    chain_work: u128,
+    /// Failed validation, or descends from a block that did
+    invalid: bool,
+}
+
+/// How a tip of the block tree relates to the active chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainTipStatus {
+    /// The tip of the active chain
+    Active,
+    /// A branch that was not connected, as far as checked valid
+    ValidFork,
+    /// A branch containing a block that failed validation
+    Invalid,
+}
+
+/// A block with no known children, for the developer page
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainTip {
+    pub hash: String,
+    pub height: u64,
+    /// Cumulative work in decimal, which can exceed what a JavaScript number holds exactly
+    pub chain_work: String,
+    /// Blocks between the tip and where its branch leaves the active chain; 0 for the active tip
+    pub branch_length: u64,
+    pub status: ChainTipStatus,
+}
+
+/// Staged blocks are committed once this many are waiting
+pub const BATCH_MAX_BLOCKS: usize = 256;
+
+/// Staged writes are committed once they hold this many bytes
+pub const BATCH_MAX_BYTES: usize = 8 * 1024 * 1024;
+
+/// Staged writes are committed at the latest this long after the first of them
+pub const BATCH_MAX_AGE: Duration = Duration::from_secs(1);
+
+/// Staged writes are committed once they hold this many bytes while memory is short
+const LOW_MEMORY_BATCH_MAX_BYTES: usize = 1024 * 1024;
+
+/// Default cache of the storage engine, in MiB
+pub const DEFAULT_STORE_CACHE_MB: u32 = 64;
+
+/// Default budget of decoded blocks kept in memory, in MiB
+pub const DEFAULT_BLOCK_CACHE_MB: u32 = 16;
+
+/// Cache of the storage engine when the database is opened while memory is short
+const LOW_MEMORY_STORE_CACHE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Budget of decoded blocks while memory is short
+const LOW_MEMORY_BLOCK_CACHE_BYTES: usize = 1024 * 1024;
+
+/// Largest cache sizes the settings accept, in MiB
+const MAX_CACHE_MB: u32 = 4096;
+
+/// Memory the database may use for caches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DatabaseTuning {
+    /// Cache of the storage engine; fixed once the store is open
+    pub store_cache_bytes: u64,
+    /// Budget of decoded blocks kept in memory; can change while the database is open
+    pub block_cache_bytes: usize,
+    /// Whether memory is short, so the caches start small
+    pub low_memory: bool,
+}
+
+impl Default for DatabaseTuning {
+    fn default() -> Self {
+        DatabaseTuning {
+            store_cache_bytes: mb_to_bytes(DEFAULT_STORE_CACHE_MB) as u64,
+            block_cache_bytes: mb_to_bytes(DEFAULT_BLOCK_CACHE_MB),
+            low_memory: false,
+        }
+    }
+}
+
+impl DatabaseTuning {
+    /// Tuning from the cache settings, in MiB
+    pub fn from_settings(store_cache_mb: u32, block_cache_mb: u32, low_memory: bool) -> Self {
+        DatabaseTuning {
+            store_cache_bytes: mb_to_bytes(store_cache_mb) as u64,
+            block_cache_bytes: mb_to_bytes(block_cache_mb),
+            low_memory,
+        }
+    }
+
+    fn effective_store_cache_bytes(&self) -> u64 {
+        if self.low_memory {
+            self.store_cache_bytes.min(LOW_MEMORY_STORE_CACHE_BYTES)
+        } else {
+            self.store_cache_bytes
+        }
+    }
+}
+
+pub fn mb_to_bytes(mb: u32) -> usize {
+    mb as usize * 1024 * 1024
+}
+
+/// Validate a cache size setting, in MiB
+pub fn validate_cache_mb(name: &str, mb: u32) -> Result<(), String> {
+    if mb == 0 || mb > MAX_CACHE_MB {
+        return Err(format!("{} must be between 1 and {} MiB", name, MAX_CACHE_MB));
+    }
+    Ok(())
+}
+
+/// Size and cache use of the blockchain database, for `get_database_stats`
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseStats {
+    pub backend: StoreBackend,
+    /// Committed records per tree
+    pub trees: BTreeMap<&'static str, usize>,
+    pub size_on_disk: u64,
+    /// Cache of the storage engine, as opened
+    pub store_cache_bytes: u64,
+    pub block_cache: BlockCacheStats,
+    /// Bytes of writes staged for the next commit
+    pub staged_bytes: usize,
+    pub low_memory: bool,
+}
+
+/// Blocks indexed per step of the address index backfill
+const ADDRESS_INDEX_BACKFILL_STEP: u64 = 500;
+
+/// Metadata key of the first height the address index has not covered yet
+const ADDRESS_INDEX_NEXT_KEY: &[u8] = b"address_index_next";
+
+/// Metadata key of the initial block download progress
+const SYNC_PROGRESS_KEY: &[u8] = b"sync_progress";
+
+/// Metadata key of the hashes of the block tree's tips
+const CHAIN_TIPS_KEY: &[u8] = b"chain_tips";
+
+/// Version of the database layout this build writes
+pub const SCHEMA_VERSION: u32 = 4;
+
+/// Metadata key of the layout version of the stored data. Databases from before it was recorded
+/// are version 1.
+const SCHEMA_VERSION_KEY: &[u8] = b"schema_version";
+
+/// Metadata key of the migration under way
+const MIGRATION_MARKER_KEY: &[u8] = b"migration_marker";
+
+/// Blocks migrated per committed step
+const MIGRATION_STEP: u64 = 500;
+
+/// A migration under way. It is committed with each step's writes, so a migration interrupted
+/// by a crash or a quit resumes after the last step that reached the disk.
+#[derive(Debug, Clone, Encode, Decode)]
+struct MigrationMarker {
+    to_version: u32,
+    /// Blocks below this height are migrated
+    next_height: u64,
+}
+
+/// An upgrade of the stored data to the next layout version, applied to every stored block
+struct Migration {
+    to_version: u32,
+    description: &'static str,
+    migrate_block: fn(&BlockchainDatabase, &mut WriteBatch, &Block) -> Result<()>,
+}
+
+/// Migrations in version order. Version 1 is the layout before any were recorded.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        to_version: 2,
+        description: "Building compact block filters",
+        migrate_block: BlockchainDatabase::stage_block_filter,
+    },
+    Migration {
+        to_version: 3,
+        description: "Computing block statistics",
+        migrate_block: BlockchainDatabase::stage_block_stats,
+    },
+    Migration {
+        to_version: 4,
+        description: "Indexing the block tree",
+        migrate_block: BlockchainDatabase::stage_block_index,
+    },
+];
+
+/// Progress of a schema migration while the database opens
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationProgress {
+    /// Version the stored data had when the database was opened
+    pub from_version: u32,
+    /// Version the running migration upgrades to
+    pub to_version: u32,
+    pub description: &'static str,
+    /// Blocks below this height are migrated
+    pub migrated_height: u64,
+    pub tip_height: u64,
+    /// Whether the migration picked up where an interrupted run stopped
+    pub resumed: bool,
+    pub complete: bool,
+}
+
+/// Metadata key of a block that arrived ahead of its parent
+fn stashed_block_key(height: u64) -> Vec<u8> {
+    format!("sync_block_{}", height).into_bytes()
+}
+
+/// A transaction touching an address, from the address index
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressTx {
+    pub txid: String,
+    pub block_height: u64,
+}
+
+/// How far the address index covers the chain
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressIndexStatus {
+    pub enabled: bool,
+    /// Blocks below this height are indexed
+    pub indexed_height: u64,
+    pub tip_height: u64,
+    /// Enabled and covering every block
+    pub complete: bool,
+}
+
+/// Writes staged for the next commit, by `TreeId`
+#[derive(Debug, Default)]
+struct WriteBatch {
+    writes: [block_store::TreeWrites; 10],
+    blocks: usize,
+    bytes: usize,
+    started: Option<Instant>,
+}
+
+impl WriteBatch {
+    fn insert(&mut self, tree: TreeId, key: &[u8], value: Vec<u8>) {
+        self.bytes += key.len() + value.len();
+        self.started.get_or_insert_with(Instant::now);
+        self.writes[tree as usize].insert(key.to_vec(), Some(value));
+    }
+
+    fn remove(&mut self, tree: TreeId, key: &[u8]) {
+        self.bytes += key.len();
+        self.started.get_or_insert_with(Instant::now);
+        self.writes[tree as usize].insert(key.to_vec(), None);
+    }
+
+    /// The staged value of a key: Some(None) if it is staged for removal, None if untouched
+    fn get(&self, tree: TreeId, key: &[u8]) -> Option<Option<&[u8]>> {
+        self.writes[tree as usize].get(key).map(|value| value.as_deref())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.writes.iter().all(BTreeMap::is_empty)
+    }
+
+    /// Whether the batch is big or old enough to commit
+    fn is_ready(&self, max_bytes: usize) -> bool {
+        self.blocks >= BATCH_MAX_BLOCKS
+            || self.bytes >= max_bytes
+            || self.started.is_some_and(|started| started.elapsed() >= BATCH_MAX_AGE)
+    }
+}
+
+/// Blockchain database service, on any `BlockStore` backend.
+///
+/// Stored blocks, with their transactions and UTXO changes, are staged in memory and committed to
+/// the store in one atomic transaction once enough have gathered or the oldest has waited
+/// `BATCH_MAX_AGE`, so initial sync does not pay a disk flush per block. Reads see staged writes.
+/// `flush` and `close` commit whatever is staged.
+///
+/// Decoded blocks are cached by height. While memory is short the cache and the write batch
+/// shrink; the storage engine's own cache is sized when the store opens.
 pub struct BlockchainDatabase {
-    db: Db,
-    blocks: Tree,
-    transactions: Tree,
-    utxos: Tree,
-    addresses: Tree,
-    metadata: Tree,
+    /// Held for the lifetime of the database so another process cannot open the same directory
+    _dir_lock: FileLock,
+    data_dir: PathBuf,
+    store: Box<dyn BlockStore>,
+    /// Cache size the store was opened with, also used for stores opened by `migrate_store`
+    store_cache_bytes: u64,
+    address_index_enabled: AtomicBool,
+    address_index_backfilling: AtomicBool,
+    pending: Mutex<WriteBatch>,
+    /// Initial block download progress; staged with the blocks it describes. Locked after
+    /// `pending`.
+    sync_progress: Mutex<SyncProgress>,
+    /// Locked after `pending`
+    block_cache: Mutex<BlockCache>,
+    /// Block cache budget from the settings, restored when memory is no longer short
+    block_cache_bytes: AtomicUsize,
+    low_memory: AtomicBool,
 }
 
 impl BlockchainDatabase {    /// Create new blockchain database
     pub fn new(data_dir: PathBuf) -> Result<Self> {
+        Self::open_tuned(data_dir, DatabaseTuning::default())
+    }
+
+    /// Create new blockchain database with caches sized by `tuning`
+    pub fn open_tuned(data_dir: PathBuf, tuning: DatabaseTuning) -> Result<Self> {
         let db_path = data_dir.join("blockchain.db");
         
         println!("Initializing blockchain database at: {:?}", db_path);
@@ -89,102 +414,255 @@ impl BlockchainDatabase {    /// Create new blockchain database
             }
         }
 
-        println!("Opening sled database...");
-        let db = match sled::open(&db_path) {
-            Ok(db) => {
-                println!("Sled database opened successfully");
-                db
-            },
-            Err(e) => {
-                println!("Failed to open sled database: {}", e);
-                println!("Attempted path: {:?}", db_path);
-                println!("Path exists: {}", db_path.exists());
-                println!("Parent exists: {}", db_path.parent().map(|p| p.exists()).unwrap_or(false));
-                
-                // Check if this looks like a database lock error
-                let error_msg = e.to_string().to_lowercase();
-                if error_msg.contains("lock") || 
-                   error_msg.contains("in use") || 
-                   error_msg.contains("already") ||
-                   error_msg.contains("resource busy") ||
-                   error_msg.contains("cannot acquire") {
-                    return Err(anyhow::anyhow!(
-                        "Database is currently in use by another process. Please ensure no other instances of B-Rad Coin are running and try again."
-                    ));
-                }
-                
-                return Err(anyhow::anyhow!("Failed to open blockchain database: {}", e));
+        // Fail with a clear message before sled reports its own lock error
+        let dir_lock = match FileLock::acquire(&data_dir.join(BLOCKCHAIN_LOCK_FILE), "Blockchain database") {
+            Ok(lock) => lock,
+            Err(LockError::InUse { pid, .. }) => {
+                error!("Blockchain directory {:?} is locked by process {:?}", data_dir, pid);
+                return Err(anyhow::anyhow!(
+                    "Database is currently in use by another process. Please ensure no other instances of B-Rad Coin are running and try again."
+                ));
             }
+            Err(e) => return Err(anyhow::anyhow!("Failed to lock blockchain data directory: {}", e)),
         };
 
-        println!("Opening database trees...");
-        let blocks = db.open_tree("blocks")
-            .context("Failed to open blocks tree")?;
-        let transactions = db.open_tree("transactions")
-            .context("Failed to open transactions tree")?;
-        let utxos = db.open_tree("utxos")
-            .context("Failed to open UTXOs tree")?;
-        let addresses = db.open_tree("addresses")
-            .context("Failed to open addresses tree")?;
-        let metadata = db.open_tree("metadata")
-            .context("Failed to open metadata tree")?;
-        println!("All database trees opened successfully");
+        let backend = StoreBackend::detect(&data_dir);
+        info!("Opening {:?} blockchain store", backend);
+        let store_cache_bytes = tuning.effective_store_cache_bytes();
+        let store = backend.open(&backend.path(&data_dir), store_cache_bytes)?;
+
+        // Progress that fails to decode is dropped; sync then starts again from the chain tip
+        let sync_progress = match store.get(TreeId::Metadata, SYNC_PROGRESS_KEY) {
+            Ok(Some(bytes)) => bincode::serde::decode_from_slice(&bytes, bincode::config::standard())
+                .map(|(progress, _)| progress)
+                .unwrap_or_else(|e| {
+                    warn!("Discarding unreadable sync progress: {}", e);
+                    SyncProgress::default()
+                }),
+            _ => SyncProgress::default(),
+        };
+        if sync_progress.outstanding() > 0 {
+            info!(
+                "Resuming block download: {} blocks outstanding up to header {}",
+                sync_progress.outstanding(),
+                sync_progress.best_header_height
+            );
+        }
 
         Ok(Self {
-            db,
-            blocks,
-            transactions,
-            utxos,
-            addresses,
-            metadata,
+            _dir_lock: dir_lock,
+            data_dir,
+            store,
+            store_cache_bytes,
+            address_index_enabled: AtomicBool::new(false),
+            address_index_backfilling: AtomicBool::new(false),
+            pending: Mutex::new(WriteBatch::default()),
+            sync_progress: Mutex::new(sync_progress),
+            block_cache: Mutex::new(BlockCache::new(if tuning.low_memory {
+                tuning.block_cache_bytes.min(LOW_MEMORY_BLOCK_CACHE_BYTES)
+            } else {
+                tuning.block_cache_bytes
+            })),
+            block_cache_bytes: AtomicUsize::new(tuning.block_cache_bytes),
+            low_memory: AtomicBool::new(tuning.low_memory),
         })
     }
 
+    /// Read a key, preferring a staged write over the stored value
+    fn read_with(&self, pending: &WriteBatch, tree: TreeId, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        match pending.get(tree, key) {
+            Some(staged) => Ok(staged.map(<[u8]>::to_vec)),
+            None => self.store.get(tree, key),
+        }
+    }
+
+    fn read(&self, tree: TreeId, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let pending = self.pending.lock().unwrap();
+        self.read_with(&pending, tree, key)
+    }
+
+    /// Write every staged change to the store in one atomic transaction and flush it to disk.
+    /// The staged changes are kept if the commit fails.
+    pub fn commit_pending(&self) -> Result<()> {
+        let mut pending = self.pending.lock().unwrap();
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        self.store.apply(&pending.writes)?;
+
+        debug!("Committed {} staged blocks ({} bytes) to the blockchain database", pending.blocks, pending.bytes);
+        *pending = WriteBatch::default();
+        Ok(())
+    }
+
+    /// Commit staged writes if the batch is big or old enough
+    fn commit_if_ready(&self) -> Result<()> {
+        let max_bytes = if self.low_memory.load(Ordering::SeqCst) {
+            LOW_MEMORY_BATCH_MAX_BYTES
+        } else {
+            BATCH_MAX_BYTES
+        };
+        let ready = self.pending.lock().unwrap().is_ready(max_bytes);
+        if ready {
+            self.commit_pending()?;
+        }
+        Ok(())
+    }
+
+    /// Write the network's genesis block if the store has no blocks yet.
+    /// Returns true if it was written.
+    pub fn ensure_genesis(&self, params: &ChainParams) -> Result<bool> {
+        if !self.store.is_empty(TreeId::Blocks)? {
+            return Ok(false);
+        }
+        let genesis = params.genesis_block();
+        info!("Writing {} genesis block {}", params.name, genesis.hash);
+        self.store_block(&genesis)?;
+        self.commit_pending()?;
+        Ok(true)
+    }
+
     /// Get the current block height
     pub fn get_block_height(&self) -> Result<u64> {
-        if let Some(height_bytes) = self.metadata.get("block_height")? {            let height = bincode::decode_from_slice(&height_bytes, bincode::config::standard())?.0;
+        let pending = self.pending.lock().unwrap();
+        self.block_height_with(&pending)
+    }
+
+    fn block_height_with(&self, pending: &WriteBatch) -> Result<u64> {
+        if let Some(height_bytes) = self.read_with(pending, TreeId::Metadata, b"block_height")? {
+            let height = bincode::decode_from_slice(&height_bytes, bincode::config::standard())?.0;
             Ok(height)
         } else {
             Ok(0)
         }
     }
 
-    /// Set the current block height
-    pub fn set_block_height(&self, height: u64) -> Result<()> {        let height_bytes = bincode::encode_to_vec(&height, bincode::config::standard())?;
-        self.metadata.insert("block_height", height_bytes)?;
-        self.db.flush()?;
+    /// Store a block in the database. The block is staged and committed with the next batch.
+    pub fn store_block(&self, block: &Block) -> Result<()> {
+        {
+            let mut pending = self.pending.lock().unwrap();
+            let block_key = format!("height_{}", block.height);
+            let block_bytes = bincode::encode_to_vec(block, bincode::config::standard())?;
+
+            // A block of ours replaced by another at the same height lost its reward
+            if let Some(replaced) = self.block_by_height_with(&pending, block.height)? {
+                if replaced.hash != block.hash {
+                    self.stage_mined_block_status(&mut pending, &replaced, true)?;
+                    self.stage_mined_block_status(&mut pending, block, false)?;
+                }
+            }
+
+            self.block_cache.lock().unwrap().insert(block, block_bytes.len());
+            pending.insert(TreeId::Blocks, block_key.as_bytes(), block_bytes);
+
+            // Store by hash as well for quick lookup
+            let hash_key = format!("hash_{}", block.hash);
+            pending.insert(TreeId::Blocks, hash_key.as_bytes(), bincode::encode_to_vec(block.height, bincode::config::standard())?);
+
+            // Update block height if this is the newest block
+            let current_height = self.block_height_with(&pending)?;
+            if block.height > current_height {
+                let height_bytes = bincode::encode_to_vec(block.height, bincode::config::standard())?;
+                pending.insert(TreeId::Metadata, b"block_height", height_bytes);
+
+                let mut progress = self.sync_progress.lock().unwrap();
+                if progress.is_downloaded(block.height) {
+                    pending.remove(TreeId::Metadata, &stashed_block_key(block.height));
+                }
+                progress.mark_connected(block.height);
+                Self::stage_sync_progress(&mut pending, &progress)?;
+            }
+
+            // Store transactions from this block
+            for transaction in &block.transactions {
+                self.store_transaction(&mut pending, transaction, block.height)?;
+            }
+
+            self.stage_block_filter(&mut pending, block)?;
+            self.stage_block_stats(&mut pending, block)?;
+            self.stage_block_index(&mut pending, block)?;
+
+            if self.address_index_enabled.load(Ordering::SeqCst) {
+                self.index_block_addresses(&mut pending, block)?;
+                // Advance the covered height only if the backfill has reached this block
+                let next = self.address_index_next_with(&pending)?;
+                if block.height <= next {
+                    let next_bytes = bincode::encode_to_vec(next.max(block.height + 1), bincode::config::standard())?;
+                    pending.insert(TreeId::Metadata, ADDRESS_INDEX_NEXT_KEY, next_bytes);
+                }
+            }
+            pending.blocks += 1;
+        }
+
+        self.commit_if_ready()
+    }
+
+    fn stage_sync_progress(pending: &mut WriteBatch, progress: &SyncProgress) -> Result<()> {
+        let bytes = bincode::serde::encode_to_vec(progress, bincode::config::standard())?;
+        pending.insert(TreeId::Metadata, SYNC_PROGRESS_KEY, bytes);
         Ok(())
     }
 
-    /// Store a block in the database
-    pub fn store_block(&self, block: &Block) -> Result<()> {
-        let block_key = format!("height_{}", block.height);        let block_bytes = bincode::encode_to_vec(block, bincode::config::standard())?;
-        
-        self.blocks.insert(block_key.as_bytes(), block_bytes)?;
-        
-        // Store by hash as well for quick lookup
-        let hash_key = format!("hash_{}", block.hash);
-        self.blocks.insert(hash_key.as_bytes(), bincode::encode_to_vec(&block.height, bincode::config::standard())?)?;
-        
-        // Update block height if this is the newest block
-        let current_height = self.get_block_height()?;
-        if block.height > current_height {
-            self.set_block_height(block.height)?;
-        }
+    /// A copy of the initial block download progress
+    pub fn sync_progress(&self) -> SyncProgress {
+        self.sync_progress.lock().unwrap().clone()
+    }
 
-        // Store transactions from this block
-        for transaction in &block.transactions {
-            self.store_transaction(transaction, block.height)?;
+    /// Height of the best header sync has validated
+    pub fn best_header_height(&self) -> u64 {
+        self.sync_progress.lock().unwrap().best_header_height
+    }
+
+    /// Change the initial block download progress. The change is staged and committed with the
+    /// next batch.
+    pub fn update_sync_progress<R>(&self, update: impl FnOnce(&mut SyncProgress) -> R) -> Result<R> {
+        let mut pending = self.pending.lock().unwrap();
+        let mut progress = self.sync_progress.lock().unwrap();
+        let result = update(&mut progress);
+        Self::stage_sync_progress(&mut pending, &progress)?;
+        Ok(result)
+    }
+
+    /// Keep a requested block that arrived before its parent until it can be connected
+    pub fn stash_block(&self, block: &Block) -> Result<()> {
+        let mut pending = self.pending.lock().unwrap();
+        let mut progress = self.sync_progress.lock().unwrap();
+        if !progress.is_expected(block.height, &block.hash) {
+            return Err(anyhow::anyhow!("Block {} at height {} was not requested", block.hash, block.height));
         }
+        let block_bytes = bincode::encode_to_vec(block, bincode::config::standard())?;
+        pending.insert(TreeId::Metadata, &stashed_block_key(block.height), block_bytes);
+        progress.mark_downloaded(block.height);
+        Self::stage_sync_progress(&mut pending, &progress)
+    }
 
-        self.db.flush()?;
-        Ok(())
+    /// The stashed block at a height, if it is the one sync expects there
+    pub fn get_stashed_block(&self, height: u64) -> Result<Option<Block>> {
+        let pending = self.pending.lock().unwrap();
+        if !self.sync_progress.lock().unwrap().is_downloaded(height) {
+            return Ok(None);
+        }
+        match self.read_with(&pending, TreeId::Metadata, &stashed_block_key(height))? {
+            Some(bytes) => Ok(Some(bincode::decode_from_slice(&bytes, bincode::config::standard())?.0)),
+            None => Ok(None),
+        }
     }
 
     /// Get a block by height
     pub fn get_block_by_height(&self, height: u64) -> Result<Option<Block>> {
+        let pending = self.pending.lock().unwrap();
+        self.block_by_height_with(&pending, height)
+    }
+
+    fn block_by_height_with(&self, pending: &WriteBatch, height: u64) -> Result<Option<Block>> {
+        // Stored blocks are cached as they are staged, so the cache is never behind the batch
+        if let Some(block) = self.block_cache.lock().unwrap().get(height) {
+            return Ok(Some(block));
+        }
         let block_key = format!("height_{}", height);
-        if let Some(block_bytes) = self.blocks.get(block_key.as_bytes())? {            let block = bincode::decode_from_slice(&block_bytes, bincode::config::standard())?.0;
+        if let Some(block_bytes) = self.read_with(pending, TreeId::Blocks, block_key.as_bytes())? {            let block = bincode::decode_from_slice(&block_bytes, bincode::config::standard())?.0;
+            self.block_cache.lock().unwrap().insert(&block, block_bytes.len());
             Ok(Some(block))
         } else {
             Ok(None)
@@ -194,27 +672,476 @@ impl BlockchainDatabase {    /// Create new blockchain database
     /// Get a block by hash
     pub fn get_block_by_hash(&self, hash: &str) -> Result<Option<Block>> {
         let hash_key = format!("hash_{}", hash);
-        if let Some(height_bytes) = self.blocks.get(hash_key.as_bytes())? {            let height: u64 = bincode::decode_from_slice(&height_bytes, bincode::config::standard())?.0;
+        if let Some(height_bytes) = self.read(TreeId::Blocks, hash_key.as_bytes())? {            let height: u64 = bincode::decode_from_slice(&height_bytes, bincode::config::standard())?.0;
             self.get_block_by_height(height)
         } else {
             Ok(None)
         }
     }
 
-    /// Store a transaction
-    pub fn store_transaction(&self, transaction: &Transaction, block_height: u64) -> Result<()> {        let tx_bytes = bincode::encode_to_vec(transaction, bincode::config::standard())?;
+    /// Get the stored (bincode) bytes of a block by hash
+    pub fn get_raw_block_by_hash(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+        let hash_key = format!("hash_{}", hash);
+        let Some(height_bytes) = self.read(TreeId::Blocks, hash_key.as_bytes())? else {
+            return Ok(None);
+        };
+        let height: u64 = bincode::decode_from_slice(&height_bytes, bincode::config::standard())?.0;
+        let block_key = format!("height_{}", height);
+        self.read(TreeId::Blocks, block_key.as_bytes())
+    }
+
+    /// Get the hash and compact filter of the block at a height. Blocks stored before filters
+    /// existed get theirs built now and staged for the next commit.
+    pub fn get_block_filter(&self, height: u64) -> Result<Option<(String, Vec<u8>)>> {
+        let key = height.to_be_bytes();
+        if let Some(filter_bytes) = self.read(TreeId::Filters, &key)? {
+            return Ok(Some(bincode::decode_from_slice(&filter_bytes, bincode::config::standard())?.0));
+        }
+        let Some(block) = self.get_block_by_height(height)? else {
+            return Ok(None);
+        };
+        let filter = (block.hash.clone(), crate::compact_filters::build_filter(&block));
+        let filter_bytes = bincode::encode_to_vec(&filter, bincode::config::standard())?;
+        self.pending.lock().unwrap().insert(TreeId::Filters, &key, filter_bytes);
+        Ok(Some(filter))
+    }
+
+    fn stage_block_filter(&self, pending: &mut WriteBatch, block: &Block) -> Result<()> {
+        let filter = (block.hash.clone(), crate::compact_filters::build_filter(block));
+        let filter_bytes = bincode::encode_to_vec(&filter, bincode::config::standard())?;
+        pending.insert(TreeId::Filters, &block.height.to_be_bytes(), filter_bytes);
+        Ok(())
+    }
+
+    fn stage_block_stats(&self, pending: &mut WriteBatch, block: &Block) -> Result<()> {
+        let stats = self.block_stats_for(pending, block)?;
+        pending.insert(TreeId::BlockStats, &block.height.to_be_bytes(), bincode::encode_to_vec(&stats, bincode::config::standard())?);
+        Ok(())
+    }
+
+    /// Stats of `block`, timing it against its parent
+    fn block_stats_for(&self, pending: &WriteBatch, block: &Block) -> Result<BlockStats> {
+        let parent_timestamp = match block.height.checked_sub(1) {
+            Some(parent) => match self.read_with(pending, TreeId::BlockStats, &parent.to_be_bytes())? {
+                Some(bytes) => Some(bincode::decode_from_slice::<BlockStats, _>(&bytes, bincode::config::standard())?.0.timestamp),
+                None => self.block_by_height_with(pending, parent)?.map(|parent| parent.timestamp),
+            },
+            None => None,
+        };
+        Ok(BlockStats {
+            height: block.height,
+            timestamp: block.timestamp,
+            difficulty: block.difficulty,
+            solve_time: parent_timestamp.map_or(0, |parent| block.timestamp.saturating_sub(parent)),
+            transaction_count: block.transactions.len() as u32,
+        })
+    }
+
+    /// Stats of the blocks from `from` to `to` inclusive, in height order. Blocks stored before
+    /// stats were kept get theirs computed now and staged for the next commit.
+    pub fn get_block_stats(&self, from: u64, to: u64) -> Result<Vec<BlockStats>> {
+        let mut pending = self.pending.lock().unwrap();
+        let mut stats = Vec::new();
+        for height in from..=to.min(self.block_height_with(&pending)?) {
+            let key = height.to_be_bytes();
+            if let Some(bytes) = self.read_with(&pending, TreeId::BlockStats, &key)? {
+                stats.push(bincode::decode_from_slice(&bytes, bincode::config::standard())?.0);
+                continue;
+            }
+            let Some(block) = self.block_by_height_with(&pending, height)? else {
+                continue;
+            };
+            let block_stats = self.block_stats_for(&pending, &block)?;
+            pending.insert(TreeId::BlockStats, &key, bincode::encode_to_vec(&block_stats, bincode::config::standard())?);
+            stats.push(block_stats);
+        }
+        Ok(stats)
+    }
+
+    fn stage_block_index(&self, pending: &mut WriteBatch, block: &Block) -> Result<()> {
+        self.stage_index_entry(pending, block, false)
+    }
+
+    /// Add a block to the block tree and update its tips. A valid block takes its parent's place
+    /// as a tip; an invalid one becomes a tip beside its valid parent, so the branch it failed to
+    /// extend stays visible.
+    fn stage_index_entry(&self, pending: &mut WriteBatch, block: &Block, invalid: bool) -> Result<()> {
+        let parent = self.index_entry_with(pending, &block.previous_hash)?;
+        let parent_invalid = parent.as_ref().is_some_and(|parent| parent.invalid);
+        let invalid = invalid || parent_invalid;
+        let entry = BlockIndexEntry {
+            height: block.height,
+            previous_hash: block.previous_hash.clone(),
+            chain_work: parent
+                .map_or(0, |parent| parent.chain_work)
+                .saturating_add(crate::mining_service::block_work(block.difficulty)),
+            invalid,
+        };
+        pending.insert(TreeId::BlockIndex, block.hash.as_bytes(), bincode::encode_to_vec(&entry, bincode::config::standard())?);
+
+        let mut tips = self.chain_tip_hashes_with(pending)?;
+        if !invalid || parent_invalid {
+            tips.retain(|hash| hash != &block.previous_hash);
+        }
+        if !tips.contains(&block.hash) {
+            tips.push(block.hash.clone());
+        }
+        pending.insert(TreeId::Metadata, CHAIN_TIPS_KEY, bincode::encode_to_vec(&tips, bincode::config::standard())?);
+        Ok(())
+    }
+
+    fn index_entry_with(&self, pending: &WriteBatch, hash: &str) -> Result<Option<BlockIndexEntry>> {
+        match self.read_with(pending, TreeId::BlockIndex, hash.as_bytes())? {
+            Some(bytes) => Ok(Some(bincode::decode_from_slice(&bytes, bincode::config::standard())?.0)),
+            None => Ok(None),
+        }
+    }
+
+    fn chain_tip_hashes_with(&self, pending: &WriteBatch) -> Result<Vec<String>> {
+        match self.read_with(pending, TreeId::Metadata, CHAIN_TIPS_KEY)? {
+            Some(bytes) => Ok(bincode::decode_from_slice(&bytes, bincode::config::standard())?.0),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Record a block that was not connected in the block tree, if it branches off a block the
+    /// tree holds: a block of a competing branch, or one that failed validation. Returns whether
+    /// it was recorded.
+    pub fn record_side_block(&self, block: &Block, invalid: bool) -> Result<bool> {
+        {
+            let mut pending = self.pending.lock().unwrap();
+            if self.index_entry_with(&pending, &block.hash)?.is_some()
+                || self.index_entry_with(&pending, &block.previous_hash)?.is_none()
+            {
+                return Ok(false);
+            }
+            self.stage_index_entry(&mut pending, block, invalid)?;
+        }
+        self.commit_if_ready()?;
+        Ok(true)
+    }
+
+    /// Tips of the block tree, the active one first and then by chain work
+    pub fn chain_tips(&self) -> Result<Vec<ChainTip>> {
+        let pending = self.pending.lock().unwrap();
+        let active_height = self.block_height_with(&pending)?;
+        let active_hash = self.block_by_height_with(&pending, active_height)?.map(|block| block.hash);
+
+        let mut tips = Vec::new();
+        for hash in self.chain_tip_hashes_with(&pending)? {
+            let Some(entry) = self.index_entry_with(&pending, &hash)? else {
+                continue;
+            };
+            let status = if entry.invalid {
+                ChainTipStatus::Invalid
+            } else if active_hash.as_deref() == Some(hash.as_str()) {
+                ChainTipStatus::Active
+            } else {
+                ChainTipStatus::ValidFork
+            };
+
+            // Walk back to the first block the active chain holds at its height
+            let mut branch_length = 0;
+            let mut branch_hash = hash.clone();
+            let mut branch_entry = entry.clone();
+            while self
+                .block_by_height_with(&pending, branch_entry.height)?
+                .is_none_or(|block| block.hash != branch_hash)
+            {
+                branch_length += 1;
+                let Some(parent) = self.index_entry_with(&pending, &branch_entry.previous_hash)? else {
+                    break;
+                };
+                branch_hash = std::mem::replace(&mut branch_entry, parent).previous_hash;
+            }
+
+            tips.push((
+                entry.chain_work,
+                ChainTip {
+                    hash,
+                    height: entry.height,
+                    chain_work: entry.chain_work.to_string(),
+                    branch_length,
+                    status,
+                },
+            ));
+        }
+        tips.sort_by(|(a_work, a), (b_work, b)| {
+            (b.status == ChainTipStatus::Active)
+                .cmp(&(a.status == ChainTipStatus::Active))
+                .then(b_work.cmp(a_work))
+        });
+        Ok(tips.into_iter().map(|(_, tip)| tip).collect())
+    }
+
+    /// Layout version of the stored data: None for a new, empty database
+    fn stored_schema_version(&self) -> Result<Option<u32>> {
+        match self.read(TreeId::Metadata, SCHEMA_VERSION_KEY)? {
+            Some(bytes) => Ok(Some(bincode::decode_from_slice(&bytes, bincode::config::standard())?.0)),
+            None if self.store.is_empty(TreeId::Blocks)? => Ok(None),
+            None => Ok(Some(1)),
+        }
+    }
+
+    /// Bring the stored data up to `SCHEMA_VERSION`, running each missing migration over every
+    /// stored block and reporting progress after each step. A migration interrupted earlier
+    /// resumes from its marker. Returns the version the data had before.
+    pub fn migrate(&self, mut progress: impl FnMut(&MigrationProgress)) -> Result<u32> {
+        let Some(from_version) = self.stored_schema_version()? else {
+            // Nothing to migrate; a new database starts at the current layout
+            let version_bytes = bincode::encode_to_vec(SCHEMA_VERSION, bincode::config::standard())?;
+            self.pending.lock().unwrap().insert(TreeId::Metadata, SCHEMA_VERSION_KEY, version_bytes);
+            self.commit_pending()?;
+            return Ok(SCHEMA_VERSION);
+        };
+        if from_version > SCHEMA_VERSION {
+            return Err(anyhow::anyhow!(
+                "The blockchain database was written by a newer version of B-Rad Coin (schema {}, this version supports up to {})",
+                from_version,
+                SCHEMA_VERSION
+            ));
+        }
+
+        let marker: Option<MigrationMarker> = match self.read(TreeId::Metadata, MIGRATION_MARKER_KEY)? {
+            Some(bytes) => Some(bincode::decode_from_slice(&bytes, bincode::config::standard())?.0),
+            None => None,
+        };
+        for migration in MIGRATIONS.iter().filter(|migration| migration.to_version > from_version) {
+            let resume_at = marker.as_ref().filter(|marker| marker.to_version == migration.to_version).map(|marker| marker.next_height);
+            let mut next = resume_at.unwrap_or(0);
+            let tip = self.get_block_height()?;
+            match resume_at {
+                Some(height) => info!("Resuming migration to schema {} at height {}: {}", migration.to_version, height, migration.description),
+                None => info!("Migrating blockchain database to schema {}: {}", migration.to_version, migration.description),
+            }
+
+            loop {
+                let complete = {
+                    let mut pending = self.pending.lock().unwrap();
+                    let end = (next + MIGRATION_STEP).min(tip + 1);
+                    for height in next..end {
+                        if let Some(block) = self.block_by_height_with(&pending, height)? {
+                            (migration.migrate_block)(self, &mut pending, &block)?;
+                        }
+                    }
+                    next = end;
+
+                    // The marker or the new version is committed with the step's writes
+                    let complete = next > tip;
+                    if complete {
+                        let version_bytes = bincode::encode_to_vec(migration.to_version, bincode::config::standard())?;
+                        pending.insert(TreeId::Metadata, SCHEMA_VERSION_KEY, version_bytes);
+                        pending.remove(TreeId::Metadata, MIGRATION_MARKER_KEY);
+                    } else {
+                        let marker = MigrationMarker { to_version: migration.to_version, next_height: next };
+                        pending.insert(TreeId::Metadata, MIGRATION_MARKER_KEY, bincode::encode_to_vec(&marker, bincode::config::standard())?);
+                    }
+                    complete
+                };
+                self.commit_pending()?;
+
+                progress(&MigrationProgress {
+                    from_version,
+                    to_version: migration.to_version,
+                    description: migration.description,
+                    migrated_height: next,
+                    tip_height: tip,
+                    resumed: resume_at.is_some(),
+                    complete,
+                });
+                if complete {
+                    break;
+                }
+            }
+        }
+
+        if from_version < SCHEMA_VERSION {
+            info!("Blockchain database migrated from schema {} to {}", from_version, SCHEMA_VERSION);
+        }
+        Ok(from_version)
+    }
+
+    /// Turn maintenance of the address index on or off. Blocks connected while it is off are
+    /// indexed by the next backfill.
+    pub fn set_address_index_enabled(&self, enabled: bool) {
+        self.address_index_enabled.store(enabled, Ordering::SeqCst);
+    }
+
+    fn address_index_next_with(&self, pending: &WriteBatch) -> Result<u64> {
+        match self.read_with(pending, TreeId::Metadata, ADDRESS_INDEX_NEXT_KEY)? {
+            Some(bytes) => Ok(bincode::decode_from_slice(&bytes, bincode::config::standard())?.0),
+            None => Ok(0),
+        }
+    }
+
+    fn address_index_status_with(&self, pending: &WriteBatch) -> Result<AddressIndexStatus> {
+        let enabled = self.address_index_enabled.load(Ordering::SeqCst);
+        let indexed_height = self.address_index_next_with(pending)?;
+        let tip_height = self.block_height_with(pending)?;
+        Ok(AddressIndexStatus {
+            enabled,
+            indexed_height,
+            tip_height,
+            complete: enabled && indexed_height > tip_height,
+        })
+    }
+
+    /// How far the address index covers the chain
+    pub fn address_index_status(&self) -> Result<AddressIndexStatus> {
+        let pending = self.pending.lock().unwrap();
+        self.address_index_status_with(&pending)
+    }
+
+    /// Stage address index entries for every address a block pays or spends from
+    fn index_block_addresses(&self, pending: &mut WriteBatch, block: &Block) -> Result<()> {
+        for transaction in &block.transactions {
+            let mut addresses: Vec<String> = transaction.outputs.iter().map(|output| output.address.clone()).collect();
+            for input in &transaction.inputs {
+                // The spent output may be gone from the UTXO set, so take its address from the
+                // transaction that created it
+                let Some(tx_bytes) = self.read_with(pending, TreeId::Transactions, input.previous_txid.as_bytes())? else {
+                    continue;
+                };
+                let previous: Transaction = bincode::decode_from_slice(&tx_bytes, bincode::config::standard())?.0;
+                if let Some(output) = previous.outputs.get(input.previous_output_index as usize) {
+                    addresses.push(output.address.clone());
+                }
+            }
+
+            for address in addresses.iter().filter(|address| !address.is_empty()) {
+                pending.insert(TreeId::AddressTxs, &address_tx_key(address, block.height, &transaction.txid), Vec::new());
+            }
+        }
+        Ok(())
+    }
+
+    /// Index up to `max_blocks` blocks the address index does not cover yet
+    fn backfill_address_index_step(&self, max_blocks: u64) -> Result<AddressIndexStatus> {
+        {
+            let mut pending = self.pending.lock().unwrap();
+            let next = self.address_index_next_with(&pending)?;
+            let tip = self.block_height_with(&pending)?;
+            let mut height = next;
+            while height <= tip && height < next + max_blocks {
+                if let Some(block) = self.block_by_height_with(&pending, height)? {
+                    self.index_block_addresses(&mut pending, &block)?;
+                }
+                height += 1;
+            }
+            let next_bytes = bincode::encode_to_vec(height, bincode::config::standard())?;
+            pending.insert(TreeId::Metadata, ADDRESS_INDEX_NEXT_KEY, next_bytes);
+        }
+        self.commit_pending()?;
+        self.address_index_status()
+    }
+
+    /// Remember that this node mined `block`, for the mining income history. The block itself is
+    /// stored with `store_block`.
+    pub fn record_mined_block(&self, block: &Block) -> Result<()> {
+        let Some(coinbase) = block.transactions.first() else {
+            return Ok(());
+        };
+        let Some(address) = coinbase.outputs.first().map(|output| output.address.clone()) else {
+            return Ok(());
+        };
+        let record = MinedBlock {
+            height: block.height,
+            hash: block.hash.clone(),
+            reward: coinbase.outputs.iter().map(|output| output.value).sum(),
+            address,
+            timestamp: block.timestamp,
+            orphaned: false,
+        };
+        let bytes = bincode::encode_to_vec(&record, bincode::config::standard())?;
+        self.pending.lock().unwrap().insert(TreeId::MinedBlocks, &mined_block_key(&record.address, record.height), bytes);
+        Ok(())
+    }
+
+    /// Mark the record of `block`, if this node mined it, as orphaned or back on the chain
+    fn stage_mined_block_status(&self, pending: &mut WriteBatch, block: &Block, orphaned: bool) -> Result<()> {
+        let Some(address) = block.transactions.first().and_then(|coinbase| coinbase.outputs.first()).map(|output| &output.address) else {
+            return Ok(());
+        };
+        let key = mined_block_key(address, block.height);
+        let Some(bytes) = self.read_with(pending, TreeId::MinedBlocks, &key)? else {
+            return Ok(());
+        };
+        let mut record: MinedBlock = bincode::decode_from_slice(&bytes, bincode::config::standard())?.0;
+        if record.hash == block.hash && record.orphaned != orphaned {
+            if orphaned {
+                warn!("Mined block {} at height {} was replaced by another block", record.hash, record.height);
+            }
+            record.orphaned = orphaned;
+            pending.insert(TreeId::MinedBlocks, &key, bincode::encode_to_vec(&record, bincode::config::standard())?);
+        }
+        Ok(())
+    }
+
+    /// Blocks mined by this node paying any of `addresses` with a timestamp between `from` and
+    /// `to` (inclusive, Unix timestamps), newest first
+    pub fn get_mined_blocks(&self, addresses: &[String], from: Option<i64>, to: Option<i64>) -> Result<Vec<MinedBlock>> {
+        // Records are read with a prefix scan, which only sees committed writes
+        self.commit_pending()?;
+
+        let in_range = |timestamp: i64| from.is_none_or(|from| timestamp >= from) && to.is_none_or(|to| timestamp <= to);
+        let mut mined = Vec::new();
+        for address in addresses {
+            self.store.scan(TreeId::MinedBlocks, &address_tx_prefix(address), false, &mut |_, bytes| {
+                let record: MinedBlock = bincode::decode_from_slice(bytes, bincode::config::standard())?.0;
+                if in_range(record.timestamp as i64) {
+                    mined.push(record);
+                }
+                Ok(true)
+            })?;
+        }
+        mined.sort_by_key(|block| Reverse(block.height));
+        Ok(mined)
+    }
+
+    /// Transactions touching an address from the address index, newest first
+    pub fn get_address_history(&self, address: &str, limit: usize) -> Result<Vec<AddressTx>> {
+        // Index entries are read with a prefix scan, which only sees committed writes
+        self.commit_pending()?;
+
+        let prefix = address_tx_prefix(address);
+        let mut history = Vec::new();
+        if limit == 0 {
+            return Ok(history);
+        }
+        self.store.scan(TreeId::AddressTxs, &prefix, true, &mut |key, _| {
+            let rest = &key[prefix.len()..];
+            if rest.len() >= 8 {
+                let (height_bytes, txid) = rest.split_at(8);
+                history.push(AddressTx {
+                    txid: String::from_utf8_lossy(txid).into_owned(),
+                    block_height: u64::from_be_bytes(height_bytes.try_into()?),
+                });
+            }
+            Ok(history.len() < limit)
+        })?;
+        Ok(history)
+    }
+
+    /// Get the stored (bincode) bytes of a transaction
+    pub fn get_raw_transaction(&self, txid: &str) -> Result<Option<Vec<u8>>> {
+        self.read(TreeId::Transactions, txid.as_bytes())
+    }
+
+    /// Stage a transaction of a block being stored
+    fn store_transaction(&self, pending: &mut WriteBatch, transaction: &Transaction, block_height: u64) -> Result<()> {
+        let tx_bytes = bincode::encode_to_vec(transaction, bincode::config::standard())?;
         
-        self.transactions.insert(transaction.txid.as_bytes(), tx_bytes)?;
+        pending.insert(TreeId::Transactions, transaction.txid.as_bytes(), tx_bytes);
 
         // Update UTXOs
-        self.update_utxos(transaction, block_height)?;
+        self.update_utxos(pending, transaction, block_height)?;
 
         Ok(())
     }
 
     /// Get a transaction by ID
     pub fn get_transaction(&self, txid: &str) -> Result<Option<Transaction>> {
-        if let Some(tx_bytes) = self.transactions.get(txid.as_bytes())? {            let transaction = bincode::decode_from_slice(&tx_bytes, bincode::config::standard())?.0;
+        if let Some(tx_bytes) = self.read(TreeId::Transactions, txid.as_bytes())? {            let transaction = bincode::decode_from_slice(&tx_bytes, bincode::config::standard())?.0;
             Ok(Some(transaction))
         } else {
             Ok(None)
@@ -222,11 +1149,11 @@ impl BlockchainDatabase {    /// Create new blockchain database
     }
 
     /// Update UTXOs based on a transaction
-    fn update_utxos(&self, transaction: &Transaction, block_height: u64) -> Result<()> {
+    fn update_utxos(&self, pending: &mut WriteBatch, transaction: &Transaction, block_height: u64) -> Result<()> {
         // Remove spent UTXOs
         for input in &transaction.inputs {
             let utxo_key = format!("{}:{}", input.previous_txid, input.previous_output_index);
-            self.utxos.remove(utxo_key.as_bytes())?;
+            pending.remove(TreeId::Utxos, utxo_key.as_bytes());
         }
 
         // Add new UTXOs
@@ -242,19 +1169,19 @@ impl BlockchainDatabase {    /// Create new blockchain database
 
             let utxo_key = format!("{}:{}", transaction.txid, index);            let utxo_bytes = bincode::encode_to_vec(&utxo, bincode::config::standard())?;
             
-            self.utxos.insert(utxo_key.as_bytes(), utxo_bytes)?;
+            pending.insert(TreeId::Utxos, utxo_key.as_bytes(), utxo_bytes);
 
             // Index by address
-            self.add_address_utxo(&output.address, &utxo_key)?;
+            self.add_address_utxo(pending, &output.address, &utxo_key)?;
         }
 
         Ok(())
     }
 
     /// Add UTXO to address index
-    fn add_address_utxo(&self, address: &str, utxo_key: &str) -> Result<()> {
+    fn add_address_utxo(&self, pending: &mut WriteBatch, address: &str, utxo_key: &str) -> Result<()> {
         let address_key = format!("addr_{}", address);
-          let mut utxo_list: Vec<String> = if let Some(list_bytes) = self.addresses.get(address_key.as_bytes())? {
+        let mut utxo_list: Vec<String> = if let Some(list_bytes) = self.read_with(pending, TreeId::Addresses, address_key.as_bytes())? {
             bincode::decode_from_slice(&list_bytes, bincode::config::standard())?.0
         } else {
             Vec::new()
@@ -263,7 +1190,7 @@ impl BlockchainDatabase {    /// Create new blockchain database
         if !utxo_list.contains(&utxo_key.to_string()) {
             utxo_list.push(utxo_key.to_string());
             let list_bytes = bincode::encode_to_vec(&utxo_list, bincode::config::standard())?;
-            self.addresses.insert(address_key.as_bytes(), list_bytes)?;
+            pending.insert(TreeId::Addresses, address_key.as_bytes(), list_bytes);
         }
 
         Ok(())
@@ -272,11 +1199,13 @@ impl BlockchainDatabase {    /// Create new blockchain database
     /// Get UTXOs for an address
     pub fn get_address_utxos(&self, address: &str) -> Result<Vec<UTXO>> {
         let address_key = format!("addr_{}", address);
-        let mut utxos = Vec::new();        if let Some(list_bytes) = self.addresses.get(address_key.as_bytes())? {
+        let mut utxos = Vec::new();
+        let pending = self.pending.lock().unwrap();
+        if let Some(list_bytes) = self.read_with(&pending, TreeId::Addresses, address_key.as_bytes())? {
             let utxo_keys: Vec<String> = bincode::decode_from_slice(&list_bytes, bincode::config::standard())?.0;
 
             for utxo_key in utxo_keys {
-                if let Some(utxo_bytes) = self.utxos.get(utxo_key.as_bytes())? {
+                if let Some(utxo_bytes) = self.read_with(&pending, TreeId::Utxos, utxo_key.as_bytes())? {
                     let utxo = bincode::decode_from_slice(&utxo_bytes, bincode::config::standard())?.0;
                     utxos.push(utxo);
                 }
@@ -296,24 +1225,147 @@ impl BlockchainDatabase {    /// Create new blockchain database
     /// Check if a UTXO exists and is unspent
     pub fn is_utxo_unspent(&self, txid: &str, output_index: u32) -> Result<bool> {
         let utxo_key = format!("{}:{}", txid, output_index);
-        Ok(self.utxos.contains_key(utxo_key.as_bytes())?)
+        Ok(self.read(TreeId::Utxos, utxo_key.as_bytes())?.is_some())
+    }
+
+    /// Get an unspent output, or None if it does not exist or is spent
+    pub fn get_utxo(&self, txid: &str, output_index: u32) -> Result<Option<UTXO>> {
+        let utxo_key = format!("{}:{}", txid, output_index);
+        match self.read(TreeId::Utxos, utxo_key.as_bytes())? {
+            Some(utxo_bytes) => Ok(Some(bincode::decode_from_slice(&utxo_bytes, bincode::config::standard())?.0)),
+            None => Ok(None),
+        }
+    }
+
+    /// Number of unspent outputs and the coins they hold, scanning the whole UTXO set
+    pub fn get_utxo_set_totals(&self) -> Result<(u64, u64)> {
+        self.commit_pending()?;
+        let mut count = 0u64;
+        let mut total = 0u64;
+        self.store.scan(TreeId::Utxos, &[], false, &mut |_, utxo_bytes| {
+            let utxo: UTXO = bincode::decode_from_slice(utxo_bytes, bincode::config::standard())?.0;
+            count += 1;
+            total = total.saturating_add(utxo.value);
+            Ok(true)
+        })?;
+        Ok((count, total))
     }
 
     /// Get database statistics
     pub fn get_stats(&self) -> Result<HashMap<String, u64>> {
+        self.commit_pending()?;
         let mut stats = HashMap::new();
         
         stats.insert("block_height".to_string(), self.get_block_height()?);
-        stats.insert("blocks_count".to_string(), self.blocks.len() as u64 / 2); // Divided by 2 because we store by height and hash
-        stats.insert("transactions_count".to_string(), self.transactions.len() as u64);
-        stats.insert("utxos_count".to_string(), self.utxos.len() as u64);
+        stats.insert("blocks_count".to_string(), self.store.len(TreeId::Blocks)? as u64 / 2); // Divided by 2 because we store by height and hash
+        stats.insert("transactions_count".to_string(), self.store.len(TreeId::Transactions)? as u64);
+        stats.insert("utxos_count".to_string(), self.store.len(TreeId::Utxos)? as u64);
         
         Ok(stats)
     }
 
-    /// Flush all pending writes to disk
+    /// Size and cache use of the database
+    pub fn database_stats(&self) -> Result<DatabaseStats> {
+        let mut trees = BTreeMap::new();
+        for tree in TreeId::ALL {
+            trees.insert(tree.name(), self.store.len(tree)?);
+        }
+        let staged_bytes = self.pending.lock().unwrap().bytes;
+        Ok(DatabaseStats {
+            backend: self.store.backend(),
+            trees,
+            size_on_disk: self.store.size_on_disk()?,
+            store_cache_bytes: self.store_cache_bytes,
+            block_cache: self.block_cache.lock().unwrap().stats(),
+            staged_bytes,
+            low_memory: self.low_memory.load(Ordering::SeqCst),
+        })
+    }
+
+    /// Change the budget of the block cache. While memory is short the smaller budget stays in
+    /// effect until it is no longer.
+    pub fn set_block_cache_capacity(&self, bytes: usize) {
+        self.block_cache_bytes.store(bytes, Ordering::SeqCst);
+        if !self.low_memory.load(Ordering::SeqCst) {
+            self.block_cache.lock().unwrap().set_capacity(bytes);
+        }
+    }
+
+    /// Shrink the block cache and the write batch while memory is short, and grow them back
+    /// once it is not. Returns whether the mode changed.
+    pub fn set_low_memory(&self, low_memory: bool) -> Result<bool> {
+        if self.low_memory.swap(low_memory, Ordering::SeqCst) == low_memory {
+            return Ok(false);
+        }
+        let configured = self.block_cache_bytes.load(Ordering::SeqCst);
+        if low_memory {
+            self.block_cache.lock().unwrap().set_capacity(configured.min(LOW_MEMORY_BLOCK_CACHE_BYTES));
+            // Staged writes are memory too
+            self.commit_pending()?;
+            info!("Memory is short; blockchain database caches shrunk");
+        } else {
+            self.block_cache.lock().unwrap().set_capacity(configured);
+            info!("Memory is no longer short; blockchain database caches restored");
+        }
+        Ok(true)
+    }
+
+    /// The storage backend the data lives in
+    pub fn store_backend(&self) -> StoreBackend {
+        self.store.backend()
+    }
+
+    /// Move the stored data to another storage backend. Every record is copied into a new store
+    /// and verified there before the database switches to it. The old store is kept under a
+    /// `.migrated` name, to delete once the new one has proven itself.
+    pub fn migrate_store(&mut self, backend: StoreBackend) -> Result<StoreCopy> {
+        let from = self.store.backend();
+        if backend == from {
+            return Err(anyhow::anyhow!("The blockchain database already uses the {:?} backend", backend));
+        }
+        let source_path = from.path(&self.data_dir);
+        let kept_path = with_suffix(&source_path, ".migrated");
+        let target_path = backend.path(&self.data_dir);
+        for path in [&kept_path, &target_path] {
+            if path.exists() {
+                return Err(anyhow::anyhow!("{:?} is in the way of the migration; move it elsewhere first", path));
+            }
+        }
+        self.commit_pending()?;
+
+        // Copied under another name, so an interrupted copy is never mistaken for a store
+        let partial_path = with_suffix(&target_path, ".partial");
+        remove_store_files(&partial_path)?;
+        info!("Copying the {:?} blockchain store to {:?}", from, partial_path);
+        let copy = backend.open(&partial_path, self.store_cache_bytes).and_then(|target| {
+            let copy = block_store::copy_store(self.store.as_ref(), target.as_ref())?;
+            target.flush()?;
+            Ok(copy)
+        });
+        let copy = match copy {
+            Ok(copy) => copy,
+            Err(e) => {
+                if let Err(remove_error) = remove_store_files(&partial_path) {
+                    warn!("Failed to remove the partial store {:?}: {}", partial_path, remove_error);
+                }
+                return Err(e.context("Failed to copy the blockchain store"));
+            }
+        };
+        std::fs::rename(&partial_path, &target_path).context("Failed to move the copied store into place")?;
+
+        let previous = std::mem::replace(&mut self.store, backend.open(&target_path, self.store_cache_bytes)?);
+        drop(previous);
+        std::fs::rename(&source_path, &kept_path).with_context(|| {
+            format!("Switched to the {:?} store, but could not move the old store {:?} aside; move it manually", backend, source_path)
+        })?;
+        info!("Blockchain database moved from {:?} to {:?}; the old store is kept at {:?}", from, backend, kept_path);
+        Ok(copy)
+    }
+
+    /// Commit staged writes and flush everything to disk
     pub fn flush(&self) -> Result<()> {
-        self.db.flush()?;
+        self.commit_pending()?;
+        self.store.flush()?;
         Ok(())
     }
 
@@ -322,13 +1374,15 @@ impl BlockchainDatabase {    /// Create new blockchain database
     pub fn close(&self) -> Result<()> {
         info!("Releasing blockchain database resources");
         
-        // Flush all pending writes to ensure data integrity
-        self.db.flush()
+        // Commit staged blocks and flush all pending writes to ensure data integrity
+        self.commit_pending()
+            .context("Failed to commit staged writes before closing")?;
+        self.store.flush()
             .context("Failed to flush database before closing")?;
         
         // Force a checkpoint and close the database cleanly
         // This ensures all data is written to disk and file locks are released
-        if let Err(e) = self.db.flush() {
+        if let Err(e) = self.store.flush() {
             error!("Failed to perform final flush: {}", e);
         }
         
@@ -339,18 +1393,111 @@ impl BlockchainDatabase {    /// Create new blockchain database
     // ...existing code...
 }
 
+fn address_tx_prefix(address: &str) -> Vec<u8> {
+    let mut prefix = address.as_bytes().to_vec();
+    prefix.push(0);
+    prefix
+}
+
+/// Address index key; heights are big-endian so an address's entries sort by height
+fn address_tx_key(address: &str, height: u64, txid: &str) -> Vec<u8> {
+    let mut key = address_tx_prefix(address);
+    key.extend_from_slice(&height.to_be_bytes());
+    key.extend_from_slice(txid.as_bytes());
+    key
+}
+
+/// Mined block key; like the address index, an address's blocks sort by height
+fn with_suffix(path: &std::path::Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Delete a store, a sled directory or a redb file, if it exists
+fn remove_store_files(path: &std::path::Path) -> Result<()> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)?;
+    } else if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn mined_block_key(address: &str, height: u64) -> Vec<u8> {
+    let mut key = address_tx_prefix(address);
+    key.extend_from_slice(&height.to_be_bytes());
+    key
+}
+
+impl Drop for BlockchainDatabase {
+    fn drop(&mut self) {
+        // Staged blocks would otherwise be lost when the database is dropped without close()
+        if let Err(e) = self.commit_pending() {
+            error!("Failed to commit staged blockchain writes on drop: {}", e);
+        }
+    }
+}
+
 /// Thread-safe wrapper for BlockchainDatabase
 pub struct AsyncBlockchainDatabase {
     inner: Arc<RwLock<BlockchainDatabase>>,
+    /// Where stored blocks are announced; a private bus until the app's is set
+    event_bus: std::sync::RwLock<EventBus>,
 }
 
 impl AsyncBlockchainDatabase {
-    /// Create new async blockchain database
+    /// Create new async blockchain database for the active network
     pub async fn new(data_dir: PathBuf) -> Result<Self> {
-        let db = BlockchainDatabase::new(data_dir)?;
-        Ok(Self {
-            inner: Arc::new(RwLock::new(db)),
+        Self::open(data_dir, ChainParams::active()).await
+    }
+
+    /// Create new async blockchain database for the active network with caches sized by
+    /// `tuning`, reporting the progress of any schema migration the stored data needs
+    pub async fn new_with_progress(
+        data_dir: PathBuf,
+        tuning: DatabaseTuning,
+        progress: impl FnMut(&MigrationProgress),
+    ) -> Result<Self> {
+        Self::open_with_progress(data_dir, ChainParams::active(), tuning, progress).await
+    }
+
+    /// Open the database for `params`' network, writing its genesis block into an empty store
+    pub async fn open(data_dir: PathBuf, params: &ChainParams) -> Result<Self> {
+        Self::open_with_progress(data_dir, params, DatabaseTuning::default(), |progress| {
+            debug!("Schema migration to {}: {} of {} blocks", progress.to_version, progress.migrated_height, progress.tip_height + 1);
         })
+        .await
+    }
+
+    /// Open the database for `params`' network, migrating older stored data first
+    pub async fn open_with_progress(
+        data_dir: PathBuf,
+        params: &ChainParams,
+        tuning: DatabaseTuning,
+        progress: impl FnMut(&MigrationProgress),
+    ) -> Result<Self> {
+        let db = BlockchainDatabase::open_tuned(data_dir, tuning)?;
+        db.migrate(progress)?;
+        db.ensure_genesis(params)?;
+        let inner = Arc::new(RwLock::new(db));
+        tokio::spawn(Self::commit_staged_writes(Arc::downgrade(&inner)));
+        Ok(Self { inner, event_bus: std::sync::RwLock::new(EventBus::default()) })
+    }
+
+    /// Commit staged writes that have waited `BATCH_MAX_AGE`, until the database is dropped
+    async fn commit_staged_writes(inner: Weak<RwLock<BlockchainDatabase>>) {
+        let mut interval = tokio::time::interval(BATCH_MAX_AGE);
+        loop {
+            interval.tick().await;
+            let Some(inner) = inner.upgrade() else {
+                break;
+            };
+            let db = inner.read().await;
+            if let Err(e) = db.commit_if_ready() {
+                error!("Failed to commit staged blockchain writes: {}", e);
+            }
+        }
     }
 
     /// Get the current block height
@@ -359,10 +1506,31 @@ impl AsyncBlockchainDatabase {
         db.get_block_height()
     }
 
-    /// Store a block
+    /// Announce stored blocks on `event_bus` from now on
+    pub fn set_event_bus(&self, event_bus: EventBus) {
+        *self.event_bus.write().unwrap() = event_bus;
+    }
+
+    pub fn event_bus(&self) -> EventBus {
+        self.event_bus.read().unwrap().clone()
+    }
+
+    /// Store a block and announce it on the event bus. A block replacing another at the same
+    /// height is announced after the replaced one is announced as disconnected.
     pub async fn store_block(&self, block: &Block) -> Result<()> {
         let db = self.inner.write().await;
-        db.store_block(block)
+        let previous = db.get_block_by_height(block.height)?;
+        db.store_block(block)?;
+
+        // Announced under the write lock, so subscribers see blocks in the order they were stored
+        let event_bus = self.event_bus();
+        match previous {
+            Some(previous) if previous.hash == block.hash => return Ok(()),
+            Some(previous) => event_bus.publish(BackendEvent::BlockDisconnected(Arc::new(previous))),
+            None => {}
+        }
+        event_bus.publish(BackendEvent::BlockConnected(Arc::new(block.clone())));
+        Ok(())
     }
 
     /// Get a block by height
@@ -383,6 +1551,169 @@ impl AsyncBlockchainDatabase {
         db.get_transaction(txid)
     }
 
+    /// A copy of the initial block download progress
+    pub async fn sync_progress(&self) -> SyncProgress {
+        let db = self.inner.read().await;
+        db.sync_progress()
+    }
+
+    /// Height of the best header sync has validated
+    pub async fn best_header_height(&self) -> u64 {
+        let db = self.inner.read().await;
+        db.best_header_height()
+    }
+
+    /// Change the initial block download progress
+    pub async fn update_sync_progress<R>(&self, update: impl FnOnce(&mut SyncProgress) -> R) -> Result<R> {
+        let db = self.inner.read().await;
+        db.update_sync_progress(update)
+    }
+
+    /// Keep a requested block that arrived before its parent
+    pub async fn stash_block(&self, block: &Block) -> Result<()> {
+        let db = self.inner.read().await;
+        db.stash_block(block)
+    }
+
+    /// The stashed block at a height
+    pub async fn get_stashed_block(&self, height: u64) -> Result<Option<Block>> {
+        let db = self.inner.read().await;
+        db.get_stashed_block(height)
+    }
+
+    /// Turn maintenance of the address index on or off
+    pub async fn set_address_index_enabled(&self, enabled: bool) {
+        let db = self.inner.read().await;
+        db.set_address_index_enabled(enabled);
+    }
+
+    /// How far the address index covers the chain
+    pub async fn address_index_status(&self) -> Result<AddressIndexStatus> {
+        let db = self.inner.read().await;
+        db.address_index_status()
+    }
+
+    /// Index the blocks the address index does not cover yet, reporting progress after each
+    /// step. Returns at once if a backfill is already running or the index is off.
+    pub async fn backfill_address_index(&self, mut progress: impl FnMut(&AddressIndexStatus)) -> Result<()> {
+        {
+            let db = self.inner.read().await;
+            if db.address_index_backfilling.swap(true, Ordering::SeqCst) {
+                return Ok(());
+            }
+        }
+
+        let mut last_indexed = None;
+        let result = loop {
+            let step = {
+                let db = self.inner.read().await;
+                if !db.address_index_enabled.load(Ordering::SeqCst) {
+                    break Ok(());
+                }
+                db.backfill_address_index_step(ADDRESS_INDEX_BACKFILL_STEP)
+            };
+            match step {
+                Ok(status) => {
+                    progress(&status);
+                    // Stop once done, or when a step finds no block to index
+                    if status.complete || last_indexed == Some(status.indexed_height) {
+                        break Ok(());
+                    }
+                    last_indexed = Some(status.indexed_height);
+                }
+                Err(e) => break Err(e),
+            }
+            tokio::task::yield_now().await;
+        };
+
+        self.inner.read().await.address_index_backfilling.store(false, Ordering::SeqCst);
+        result
+    }
+
+    /// The storage backend the data lives in
+    pub async fn store_backend(&self) -> StoreBackend {
+        self.inner.read().await.store_backend()
+    }
+
+    /// Size and cache use of the database
+    pub async fn database_stats(&self) -> Result<DatabaseStats> {
+        let db = self.inner.read().await;
+        db.database_stats()
+    }
+
+    /// Change the budget of the block cache
+    pub async fn set_block_cache_capacity(&self, bytes: usize) {
+        let db = self.inner.read().await;
+        db.set_block_cache_capacity(bytes);
+    }
+
+    /// Shrink or restore the caches as memory runs short or recovers
+    pub async fn set_low_memory(&self, low_memory: bool) -> Result<bool> {
+        let db = self.inner.read().await;
+        db.set_low_memory(low_memory)
+    }
+
+    /// Move the stored data to another storage backend, holding off every other access until the
+    /// copy is verified and in use
+    pub async fn migrate_store(&self, backend: StoreBackend) -> Result<StoreCopy> {
+        let mut db = self.inner.write().await;
+        db.migrate_store(backend)
+    }
+
+    /// Difficulty and solve time of the blocks from `from` to `to` inclusive
+    pub async fn get_block_stats(&self, from: u64, to: u64) -> Result<Vec<BlockStats>> {
+        let db = self.inner.read().await;
+        db.get_block_stats(from, to)
+    }
+
+    /// Record a block that was not connected in the block tree
+    pub async fn record_side_block(&self, block: &Block, invalid: bool) -> Result<bool> {
+        let db = self.inner.read().await;
+        db.record_side_block(block, invalid)
+    }
+
+    /// Tips of the block tree
+    pub async fn chain_tips(&self) -> Result<Vec<ChainTip>> {
+        let db = self.inner.read().await;
+        db.chain_tips()
+    }
+
+    /// Remember that this node mined a block
+    pub async fn record_mined_block(&self, block: &Block) -> Result<()> {
+        let db = self.inner.write().await;
+        db.record_mined_block(block)
+    }
+
+    /// Blocks mined by this node paying any of `addresses`, newest first
+    pub async fn get_mined_blocks(&self, addresses: &[String], from: Option<i64>, to: Option<i64>) -> Result<Vec<MinedBlock>> {
+        let db = self.inner.read().await;
+        db.get_mined_blocks(addresses, from, to)
+    }
+
+    /// Transactions touching an address, newest first
+    pub async fn get_address_history(&self, address: &str, limit: usize) -> Result<Vec<AddressTx>> {
+        let db = self.inner.read().await;
+        db.get_address_history(address, limit)
+    }
+
+    /// Get the hash and compact filter of the block at a height
+    pub async fn get_block_filter(&self, height: u64) -> Result<Option<(String, Vec<u8>)>> {
+        let db = self.inner.read().await;
+        db.get_block_filter(height)
+    }
+
+    /// Get the stored bytes of a block by hash
+    pub async fn get_raw_block_by_hash(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+        let db = self.inner.read().await;
+        db.get_raw_block_by_hash(hash)
+    }
+
+    /// Get the stored bytes of a transaction
+    pub async fn get_raw_transaction(&self, txid: &str) -> Result<Option<Vec<u8>>> {
+        let db = self.inner.read().await;
+        db.get_raw_transaction(txid)
+    }
+
     /// Get UTXOs for an address
     pub async fn get_address_utxos(&self, address: &str) -> Result<Vec<UTXO>> {
         let db = self.inner.read().await;
@@ -401,6 +1732,18 @@ impl AsyncBlockchainDatabase {
         db.is_utxo_unspent(txid, output_index)
     }
 
+    /// Get an unspent output, or None if it does not exist or is spent
+    pub async fn get_utxo(&self, txid: &str, output_index: u32) -> Result<Option<UTXO>> {
+        let db = self.inner.read().await;
+        db.get_utxo(txid, output_index)
+    }
+
+    /// Number of unspent outputs and the coins they hold
+    pub async fn get_utxo_set_totals(&self) -> Result<(u64, u64)> {
+        let db = self.inner.read().await;
+        db.get_utxo_set_totals()
+    }
+
     /// Get database statistics
     pub async fn get_stats(&self) -> Result<HashMap<String, u64>> {
         let db = self.inner.read().await;
@@ -465,6 +1808,10 @@ impl AsyncBlockchainDatabase {
         }
 
         let db = self.inner.write().await;
+        let genesis_hash = match db.get_block_by_height(0)? {
+            Some(genesis) => genesis.hash,
+            None => ChainParams::active().genesis_block().hash,
+        };
         
         // Create some test blocks with transactions to wallet addresses
         for block_height in 1u64..=10u64 {
@@ -517,7 +1864,7 @@ impl AsyncBlockchainDatabase {
                 height: block_height,
                 hash: format!("block_hash_{}", block_height),
                 previous_hash: if block_height == 1 { 
-                    "genesis".to_string() 
+                    genesis_hash.clone()
                 } else { 
                     format!("block_hash_{}", block_height - 1) 
                 },
@@ -537,3 +1884,271 @@ impl AsyncBlockchainDatabase {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(height: u64, transactions: Vec<Transaction>) -> Block {
+        Block {
+            height,
+            hash: format!("hash{}", height),
+            previous_hash: format!("hash{}", height.saturating_sub(1)),
+            timestamp: height,
+            nonce: 0,
+            difficulty: 1,
+            transactions,
+            merkle_root: String::new(),
+        }
+    }
+
+    fn transaction(txid: &str, spends: Option<(&str, u32)>, address: &str) -> Transaction {
+        Transaction {
+            txid: txid.to_string(),
+            inputs: spends
+                .map(|(previous_txid, previous_output_index)| TransactionInput {
+                    previous_txid: previous_txid.to_string(),
+                    previous_output_index,
+                    script_sig: String::new(),
+                    sequence: 0,
+                })
+                .into_iter()
+                .collect(),
+            outputs: vec![TransactionOutput { value: 50, script_pubkey: String::new(), address: address.to_string() }],
+            timestamp: 0,
+            fee: 0,
+        }
+    }
+
+    #[test]
+    fn test_staged_blocks_are_readable_and_committed_together() {
+        let dir = std::env::temp_dir().join(format!("bradcoin_batch_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        {
+            let db = BlockchainDatabase::new(dir.clone()).unwrap();
+            db.store_block(&block(1, vec![transaction("a", None, "alice")])).unwrap();
+            db.store_block(&block(2, vec![transaction("b", Some(("a", 0)), "bob")])).unwrap();
+
+            // Nothing is on disk yet, but reads see the staged blocks
+            assert!(db.store.is_empty(TreeId::Blocks).unwrap());
+            assert_eq!(db.get_block_height().unwrap(), 2);
+            assert_eq!(db.get_block_by_hash("hash1").unwrap().unwrap().height, 1);
+            assert!(!db.is_utxo_unspent("a", 0).unwrap());
+            assert_eq!(db.get_address_balance("bob").unwrap(), 50);
+
+            db.flush().unwrap();
+            assert_eq!(db.store.len(TreeId::Blocks).unwrap(), 4);
+        }
+
+        let db = BlockchainDatabase::new(dir.clone()).unwrap();
+        assert_eq!(db.get_block_height().unwrap(), 2);
+        assert_eq!(db.get_utxo_set_totals().unwrap(), (1, 50));
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_low_memory_mode_shrinks_the_block_cache_and_commits_staged_writes() {
+        let dir = std::env::temp_dir().join(format!("bradcoin_tuning_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let db = BlockchainDatabase::open_tuned(dir.clone(), DatabaseTuning::from_settings(8, 4, false)).unwrap();
+        db.store_block(&block(1, vec![transaction("a", None, "alice")])).unwrap();
+        assert_eq!(db.get_block_by_height(1).unwrap().unwrap().hash, "hash1");
+        assert!(db.get_block_by_height(2).unwrap().is_none());
+
+        let stats = db.database_stats().unwrap();
+        // Storing looked for a block to replace at height 1 and for its parent first
+        assert_eq!((stats.block_cache.entries, stats.block_cache.hits, stats.block_cache.misses), (1, 1, 3));
+        assert_eq!(stats.block_cache.capacity_bytes, mb_to_bytes(4));
+        assert_eq!(stats.store_cache_bytes, mb_to_bytes(8) as u64);
+        assert!(stats.staged_bytes > 0);
+        assert_eq!(stats.trees["blocks"], 0);
+
+        assert!(db.set_low_memory(true).unwrap());
+        assert!(!db.set_low_memory(true).unwrap());
+        let stats = db.database_stats().unwrap();
+        assert!(stats.low_memory);
+        assert_eq!(stats.block_cache.capacity_bytes, LOW_MEMORY_BLOCK_CACHE_BYTES);
+        assert_eq!(stats.staged_bytes, 0);
+        assert_eq!(stats.trees["blocks"], 2);
+
+        // A new budget waits until memory is no longer short
+        db.set_block_cache_capacity(mb_to_bytes(2));
+        assert_eq!(db.database_stats().unwrap().block_cache.capacity_bytes, LOW_MEMORY_BLOCK_CACHE_BYTES);
+        db.set_low_memory(false).unwrap();
+        assert_eq!(db.database_stats().unwrap().block_cache.capacity_bytes, mb_to_bytes(2));
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_older_schema_is_migrated_and_an_interrupted_migration_resumes() {
+        let dir = std::env::temp_dir().join(format!("bradcoin_migration_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let db = BlockchainDatabase::new(dir.clone()).unwrap();
+        assert_eq!(db.migrate(|_| {}).unwrap(), SCHEMA_VERSION);
+        for height in 0..=2 {
+            db.store_block(&block(height, vec![transaction(&format!("tx{}", height), None, "alice")])).unwrap();
+        }
+        db.flush().unwrap();
+
+        // A version 1 store, interrupted after migrating block 0 to version 2
+        let mut writes = WriteBatch::default();
+        for tree in [TreeId::Filters, TreeId::BlockStats, TreeId::BlockIndex] {
+            db.store.scan(tree, &[], false, &mut |key, _| {
+                writes.remove(tree, key);
+                Ok(true)
+            }).unwrap();
+        }
+        writes.remove(TreeId::Metadata, SCHEMA_VERSION_KEY);
+        writes.remove(TreeId::Metadata, CHAIN_TIPS_KEY);
+        let marker = MigrationMarker { to_version: 2, next_height: 1 };
+        writes.insert(TreeId::Metadata, MIGRATION_MARKER_KEY, bincode::encode_to_vec(&marker, bincode::config::standard()).unwrap());
+        db.store.apply(&writes.writes).unwrap();
+
+        let mut reports = Vec::new();
+        assert_eq!(db.migrate(|progress| reports.push(progress.clone())).unwrap(), 1);
+        assert!(reports.iter().all(|report| report.complete));
+        assert_eq!(reports.iter().map(|report| (report.to_version, report.resumed)).collect::<Vec<_>>(), [(2, true), (3, false), (4, false)]);
+        assert_eq!(db.store.len(TreeId::Filters).unwrap(), 2);
+        assert_eq!(db.store.len(TreeId::BlockStats).unwrap(), 3);
+        assert_eq!(db.store.len(TreeId::BlockIndex).unwrap(), 3);
+        assert_eq!(db.stored_schema_version().unwrap(), Some(SCHEMA_VERSION));
+        assert!(db.store.get(TreeId::Metadata, MIGRATION_MARKER_KEY).unwrap().is_none());
+
+        let mut newer = WriteBatch::default();
+        newer.insert(TreeId::Metadata, SCHEMA_VERSION_KEY, bincode::encode_to_vec(&(SCHEMA_VERSION + 1), bincode::config::standard()).unwrap());
+        db.store.apply(&newer.writes).unwrap();
+        assert!(db.migrate(|_| {}).is_err());
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_store_moves_to_redb_and_is_reopened_there() {
+        let dir = std::env::temp_dir().join(format!("bradcoin_store_migration_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        {
+            let mut db = BlockchainDatabase::new(dir.clone()).unwrap();
+            db.store_block(&block(1, vec![transaction("a", None, "alice")])).unwrap();
+            db.store_block(&block(2, vec![transaction("b", Some(("a", 0)), "bob")])).unwrap();
+            assert_eq!(db.store_backend(), StoreBackend::Sled);
+
+            let copy = db.migrate_store(StoreBackend::Redb).unwrap();
+            assert!(copy.verified);
+            assert_eq!(copy.records["blocks"], 4);
+            assert_eq!(db.store_backend(), StoreBackend::Redb);
+            assert_eq!(db.get_address_balance("bob").unwrap(), 50);
+            assert!(db.migrate_store(StoreBackend::Redb).is_err());
+        }
+
+        let db = BlockchainDatabase::new(dir.clone()).unwrap();
+        assert_eq!(db.store_backend(), StoreBackend::Redb);
+        assert_eq!(db.get_block_height().unwrap(), 2);
+        assert_eq!(db.get_utxo_set_totals().unwrap(), (1, 50));
+        assert!(dir.join("blockchain.db.migrated").is_dir());
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_address_index_backfills_then_follows_new_blocks() {
+        let dir = std::env::temp_dir().join(format!("bradcoin_address_index_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let db = BlockchainDatabase::new(dir.clone()).unwrap();
+        db.store_block(&block(1, vec![transaction("a", None, "alice")])).unwrap();
+        db.store_block(&block(2, vec![transaction("b", Some(("a", 0)), "bob")])).unwrap();
+        assert!(!db.address_index_status().unwrap().complete);
+
+        db.set_address_index_enabled(true);
+        let status = db.backfill_address_index_step(ADDRESS_INDEX_BACKFILL_STEP).unwrap();
+        assert!(status.complete);
+
+        db.store_block(&block(3, vec![transaction("c", None, "alice")])).unwrap();
+        assert_eq!(db.address_index_status().unwrap().indexed_height, 4);
+
+        // Alice is paid by "a" and "c" and spends in "b"
+        let history: Vec<_> = db.get_address_history("alice", 10).unwrap().into_iter().map(|tx| tx.txid).collect();
+        assert_eq!(history, ["c", "b", "a"]);
+        assert_eq!(db.get_address_history("alice", 1).unwrap()[0].block_height, 3);
+        assert!(db.get_address_history("alic", 10).unwrap().is_empty());
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_mined_blocks_are_marked_orphaned_when_replaced() {
+        let dir = std::env::temp_dir().join(format!("bradcoin_mined_blocks_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let db = BlockchainDatabase::new(dir.clone()).unwrap();
+        let ours = block(1, vec![transaction("a", None, "alice")]);
+        db.store_block(&ours).unwrap();
+        db.record_mined_block(&ours).unwrap();
+        let second = block(2, vec![transaction("b", None, "alice")]);
+        db.store_block(&second).unwrap();
+        db.record_mined_block(&second).unwrap();
+
+        let mined = db.get_mined_blocks(&["alice".to_string()], None, None).unwrap();
+        assert_eq!(mined.iter().map(|b| b.height).collect::<Vec<_>>(), [2, 1]);
+        assert_eq!(mined[1].reward, 50);
+        assert!(mined.iter().all(|b| !b.orphaned));
+        assert_eq!(db.get_mined_blocks(&["alice".to_string()], Some(2), None).unwrap().len(), 1);
+
+        let theirs = Block { hash: "other1".to_string(), ..block(1, vec![transaction("c", None, "bob")]) };
+        db.store_block(&theirs).unwrap();
+        let mined = db.get_mined_blocks(&["alice".to_string()], None, Some(1)).unwrap();
+        assert!(mined[0].orphaned);
+        assert!(db.get_mined_blocks(&["bob".to_string()], None, None).unwrap().is_empty());
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_chain_tips_cover_the_active_chain_forks_and_invalid_blocks() {
+        let dir = std::env::temp_dir().join(format!("bradcoin_chain_tips_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let db = BlockchainDatabase::new(dir.clone()).unwrap();
+        for height in 0..=2 {
+            db.store_block(&block(height, vec![transaction(&format!("tx{}", height), None, "alice")])).unwrap();
+        }
+        let side = |hash: &str, previous_hash: &str, height: u64| Block {
+            hash: hash.to_string(),
+            previous_hash: previous_hash.to_string(),
+            ..block(height, Vec::new())
+        };
+        assert!(db.record_side_block(&side("fork2", "hash1", 2), false).unwrap());
+        assert!(db.record_side_block(&side("fork3", "fork2", 3), false).unwrap());
+        assert!(db.record_side_block(&side("bad3", "hash2", 3), true).unwrap());
+        // Already known, or branching off a block the tree does not hold
+        assert!(!db.record_side_block(&side("fork3", "fork2", 3), false).unwrap());
+        assert!(!db.record_side_block(&side("orphan9", "hash8", 9), false).unwrap());
+
+        let work = crate::mining_service::block_work(1);
+        let tips = db.chain_tips().unwrap();
+        let summary: Vec<_> = tips
+            .iter()
+            .map(|tip| (tip.hash.as_str(), tip.status, tip.branch_length, tip.chain_work.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("hash2", ChainTipStatus::Active, 0, (work * 3).to_string()),
+                ("fork3", ChainTipStatus::ValidFork, 2, (work * 4).to_string()),
+                ("bad3", ChainTipStatus::Invalid, 1, (work * 4).to_string()),
+            ]
+        );
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
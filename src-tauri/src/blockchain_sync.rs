@@ -2,12 +2,12 @@
 
 use crate::blockchain_database::AsyncBlockchainDatabase;
 use crate::errors::*;
+use crate::events::{self, EmitEvent};
 use log::{debug, error, info, warn};
-use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tauri::{AppHandle, Emitter, Manager};
+use tauri::AppHandle;
 use tokio::sync::RwLock;
 
 /// Blockchain synchronization service
@@ -20,16 +20,6 @@ pub struct BlockchainSyncService {
     app_handle: Option<AppHandle>,
 }
 
-/// Network status information
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NetworkStatus {
-    pub current_height: i32,
-    pub network_height: i32,
-    pub is_syncing: bool,
-    pub is_connected: bool,
-    pub peer_count: i32,
-}
-
 impl BlockchainSyncService {
     /// Create a new blockchain sync service
     pub fn new(blockchain_db: Arc<AsyncBlockchainDatabase>) -> Self {
@@ -96,7 +86,7 @@ impl BlockchainSyncService {
                     }
                     _ = status_update_interval.tick() => {
                         // Emit status update to frontend
-                        Self::emit_network_status(&app_handle, &current_height, &is_syncing, &is_connected, &peer_count).await;
+                        Self::emit_node_status(&app_handle).await;
                     }
                 }
             }
@@ -128,7 +118,7 @@ impl BlockchainSyncService {
         };
 
         // Get network service stats to check peer status and network height
-        let (network_height, connected_peers) = if let Some(network_service) = app_handle.try_state::<crate::network_service::AsyncNetworkService>() {
+        let (network_height, connected_peers) = if let Some(network_service) = crate::services::get::<crate::network_service::AsyncNetworkService>(app_handle) {
             let stats = network_service.get_stats().await;
             (stats.network_height, stats.connected_peers)
         } else {
@@ -154,7 +144,7 @@ impl BlockchainSyncService {
             let app_handle_clone = app_handle.clone();
             
             tokio::spawn(async move {
-                let sync_result = if let Some(network_service) = app_handle_clone.try_state::<crate::network_service::AsyncNetworkService>() {
+                let sync_result = if let Some(network_service) = crate::services::get::<crate::network_service::AsyncNetworkService>(&app_handle_clone) {
                     info!("Requesting blocks from network service");
                     network_service.sync_blockchain().await
                 } else {
@@ -183,44 +173,13 @@ impl BlockchainSyncService {
                 info!("Blockchain sync process finished");
             });
         }
-    }    /// Emit network status to frontend
-    async fn emit_network_status(
-        app_handle: &AppHandle,
-        current_height: &Arc<AtomicI32>,
-        is_syncing: &Arc<AtomicBool>,
-        is_connected: &Arc<AtomicBool>,
-        peer_count: &Arc<AtomicI32>,
-    ) {
-        // Get network height from network service
-        let network_height = if let Some(network_service) = app_handle.try_state::<crate::network_service::AsyncNetworkService>() {
-            let stats = network_service.get_stats().await;
-            stats.network_height as i32
-        } else {
-            current_height.load(Ordering::Relaxed) // Fallback to current height
-        };
-
-        let status = NetworkStatus {
-            current_height: current_height.load(Ordering::Relaxed),
-            network_height,
-            is_syncing: is_syncing.load(Ordering::Relaxed),
-            is_connected: is_connected.load(Ordering::Relaxed),
-            peer_count: peer_count.load(Ordering::Relaxed),
-        };
-
-        if let Err(e) = app_handle.emit("blockchain-status", &status) {
+    }    /// Emit the node status to the frontend and the tray
+    async fn emit_node_status(app_handle: &AppHandle) {
+        let status = crate::node_status::collect(app_handle).await;
+        if let Err(e) = app_handle.emit_event(events::BLOCKCHAIN_STATUS, &status) {
             debug!("Failed to emit blockchain status: {}", e);
         }
-    }    /// Get current network status
-    pub fn get_network_status(&self) -> NetworkStatus {
-        // Note: This method doesn't have access to app_handle to get network height
-        // Network height will be 0 here, but the async version will have the correct value
-        NetworkStatus {
-            current_height: self.current_height.load(Ordering::Relaxed),
-            network_height: 0, // Will be updated by the async event emission
-            is_syncing: self.is_syncing.load(Ordering::Relaxed),
-            is_connected: self.is_connected.load(Ordering::Relaxed),
-            peer_count: self.peer_count.load(Ordering::Relaxed),
-        }
+        crate::tray::set_node_status(app_handle, &status);
     }
 
     /// Get current block height
@@ -263,12 +222,6 @@ impl AsyncBlockchainSyncService {
         service.initialize(app_handle).await
     }
 
-    /// Get network status
-    pub async fn get_network_status(&self) -> NetworkStatus {
-        let service = self.inner.read().await;
-        service.get_network_status()
-    }
-
     /// Get block height
     pub async fn get_block_height(&self) -> i32 {
         let service = self.inner.read().await;
@@ -299,58 +252,6 @@ impl AsyncBlockchainSyncService {
         Ok(())
     }
 
-    /// Start event emission to frontend
-    pub async fn start_event_emission(&self, app_handle: AppHandle) {
-        let service = self.inner.clone();
-        
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(5));
-            
-            loop {
-                interval.tick().await;
-                
-                let status = {
-                    let service_guard = service.read().await;
-                    service_guard.get_network_status()
-                };
-                
-                if let Err(e) = app_handle.emit("blockchain-status", &status) {
-                    error!("Failed to emit blockchain status: {}", e);
-                }
-            }
-        });
-    }    /// Get network status with network height from network service
-    pub async fn get_network_status_with_network_height(&self, app_handle: &AppHandle) -> NetworkStatus {
-        info!("Getting network status with network height");
-        let service = self.inner.read().await;
-        let local_status = service.get_network_status();
-        
-        info!("Local status: height={}, connected={}, syncing={}", 
-              local_status.current_height, local_status.is_connected, local_status.is_syncing);
-        
-        // Get network height from network service
-        let network_height = if let Some(network_service) = app_handle.try_state::<crate::network_service::AsyncNetworkService>() {
-            info!("Found network service, getting stats");
-            let stats = network_service.get_stats().await;
-            info!("Network service stats: network_height={}", stats.network_height);
-            stats.network_height as i32
-        } else {
-            warn!("Network service not found, using local height as fallback");
-            local_status.current_height // Fallback to current height
-        };
-
-        let result = NetworkStatus {
-            current_height: local_status.current_height,
-            network_height,
-            is_syncing: local_status.is_syncing,
-            is_connected: local_status.is_connected,
-            peer_count: local_status.peer_count,
-        };
-        
-        info!("Final network status: {:?}", result);
-        result
-    }
-
     /// Manually trigger blockchain synchronization (for testing/development)
     pub async fn trigger_sync(&self, app_handle: &AppHandle) -> AppResult<()> {
         let blockchain_db = {
@@ -0,0 +1,152 @@
+//! Chain parameters for each network: message start bytes, default port, address prefix,
+//! difficulty schedule and the canonical genesis block.
+//!
+//! The genesis block is fixed by these parameters rather than mined, so every node on a
+//! network starts from the same block 0. It is written when a database is first opened and is
+//! never validated against the proof-of-work target.
+
+use crate::blockchain_database::Block;
+use crate::mining_service::{calculate_merkle_root, coinbase_transaction, header_hash};
+use serde::{Deserialize, Serialize};
+
+/// Networks with their own chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChainNetwork {
+    Mainnet,
+    Testnet,
+    Regtest,
+}
+
+/// Parameters that define one network's chain
+#[derive(Debug, Clone, Copy)]
+pub struct ChainParams {
+    pub network: ChainNetwork,
+    pub name: &'static str,
+    /// Bytes that start every message, so nodes on different networks cannot talk to each other
+    pub magic: [u8; 4],
+    pub default_port: u16,
+    /// Network the bitcoin library derives and checks addresses and keys for
+    pub address_network: bitcoin::Network,
+    /// Seconds between blocks the difficulty aims for
+    pub target_block_time: u64,
+    /// Blocks between difficulty adjustments
+    pub difficulty_adjustment_interval: u64,
+    /// Easiest target allowed, and the target of the first blocks
+    pub initial_target: u64,
+    pub genesis_timestamp: u64,
+    pub genesis_nonce: u64,
+    /// Address the genesis coinbase pays to; nobody holds its key
+    pub genesis_address: &'static str,
+}
+
+pub const MAINNET: ChainParams = ChainParams {
+    network: ChainNetwork::Mainnet,
+    name: "mainnet",
+    magic: *b"BRAD",
+    default_port: 8333,
+    address_network: bitcoin::Network::Bitcoin,
+    target_block_time: 60,
+    difficulty_adjustment_interval: 144,
+    initial_target: 0x00000000FFFF0000,
+    genesis_timestamp: 1_640_995_200,
+    genesis_nonce: 0,
+    genesis_address: "bradcoin_genesis",
+};
+
+pub const TESTNET: ChainParams = ChainParams {
+    network: ChainNetwork::Testnet,
+    name: "testnet",
+    magic: *b"BRDT",
+    default_port: 18333,
+    address_network: bitcoin::Network::Testnet,
+    target_block_time: 60,
+    difficulty_adjustment_interval: 144,
+    initial_target: 0x000000FFFFFF0000,
+    genesis_timestamp: 1_640_995_200,
+    genesis_nonce: 0,
+    genesis_address: "bradcoin_testnet_genesis",
+};
+
+/// Regtest accepts every hash, so tests mine blocks on the first nonce
+pub const REGTEST: ChainParams = ChainParams {
+    network: ChainNetwork::Regtest,
+    name: "regtest",
+    magic: *b"BRDR",
+    default_port: 18444,
+    address_network: bitcoin::Network::Regtest,
+    target_block_time: 60,
+    difficulty_adjustment_interval: 144,
+    initial_target: u64::MAX,
+    genesis_timestamp: 1_700_000_000,
+    genesis_nonce: 0,
+    genesis_address: "regtest_genesis",
+};
+
+impl ChainParams {
+    pub fn for_network(network: ChainNetwork) -> &'static ChainParams {
+        match network {
+            ChainNetwork::Mainnet => &MAINNET,
+            ChainNetwork::Testnet => &TESTNET,
+            ChainNetwork::Regtest => &REGTEST,
+        }
+    }
+
+    /// The network the node runs on
+    pub fn active() -> &'static ChainParams {
+        &MAINNET
+    }
+
+    /// Human-readable part of this network's bech32 addresses
+    pub fn hrp(&self) -> bitcoin::KnownHrp {
+        bitcoin::KnownHrp::from(self.address_network)
+    }
+
+    /// Block 0 of this network: a lone coinbase paying `genesis_address`, on an all-zero parent
+    pub fn genesis_block(&self) -> Block {
+        let previous_hash = "0".repeat(64);
+        let transactions = vec![coinbase_transaction(0, self.genesis_address, self.genesis_timestamp)];
+        let merkle_root = calculate_merkle_root(&transactions);
+        let hash = header_hash(
+            0,
+            &previous_hash,
+            &merkle_root,
+            self.genesis_timestamp,
+            self.initial_target,
+            self.genesis_nonce,
+        );
+        Block {
+            height: 0,
+            hash,
+            previous_hash,
+            timestamp: self.genesis_timestamp,
+            transactions,
+            nonce: self.genesis_nonce,
+            difficulty: 1,
+            merkle_root,
+        }
+    }
+
+    pub fn is_genesis(&self, block: &Block) -> bool {
+        block.height == 0 && block.hash == self.genesis_block().hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_networks_have_distinct_genesis_blocks() {
+        let main = MAINNET.genesis_block();
+        assert_eq!(main.hash, MAINNET.genesis_block().hash, "genesis is deterministic");
+        assert_eq!(main.previous_hash, "0".repeat(64));
+        assert_eq!(main.transactions.len(), 1);
+        assert!(MAINNET.is_genesis(&main));
+
+        assert_ne!(main.hash, TESTNET.genesis_block().hash);
+        assert_ne!(main.hash, REGTEST.genesis_block().hash);
+        assert_ne!(MAINNET.magic, TESTNET.magic);
+        assert_eq!(ChainParams::for_network(ChainNetwork::Regtest).name, "regtest");
+    }
+}
@@ -0,0 +1,482 @@
+//! Choosing which of a wallet's coins pay for a transaction.
+//!
+//! A coin is spendable once it has the wallet's minimum number of confirmations; younger coins
+//! count towards the pending balance and are never selected. Coins the user froze are never
+//! selected either. Selection spends the oldest coins first, so change from recent payments is
+//! left to mature, unless the user picked the coins to spend themselves.
+
+use crate::errors::AppErrorCode;
+use crate::wallet_data::Utxo;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use thiserror::Error;
+
+/// Confirmations a coin needs before it is spendable when the wallet has not chosen otherwise
+pub const DEFAULT_MIN_CONFIRMATIONS: u32 = 1;
+
+/// Largest minimum a wallet may require
+pub const MAX_MIN_CONFIRMATIONS: u32 = 100;
+
+/// Coins merged by one consolidation when the caller does not say
+pub const DEFAULT_MAX_CONSOLIDATION_INPUTS: usize = 50;
+
+/// Most coins one consolidation transaction may spend
+pub const MAX_CONSOLIDATION_INPUTS: usize = 500;
+
+/// Error type for coin selection
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CoinSelectionError {
+    #[error("Insufficient funds: {available} satoshis spendable, {needed} needed ({pending} pending confirmation)")]
+    InsufficientFunds { available: u64, needed: u64, pending: u64 },
+
+    #[error("Invalid outpoint '{0}', expected txid:vout")]
+    InvalidOutpoint(String),
+
+    #[error("Coin {0} does not belong to the wallet or is already spent")]
+    UnknownUtxo(String),
+
+    #[error("Coin {0} is frozen")]
+    FrozenUtxo(String),
+
+    #[error("Coin {outpoint} has {confirmations} confirmations, {required} required")]
+    ImmatureUtxo { outpoint: String, confirmations: u64, required: u32 },
+
+    #[error("Nothing to consolidate: fewer than two spendable coins are worth more than their fee")]
+    NothingToConsolidate,
+
+    #[error("Nothing to sweep: no spendable coin is worth more than its fee")]
+    NothingToSweep,
+
+    #[error("Amount or fee is too large")]
+    Overflow,
+}
+
+impl CoinSelectionError {
+    /// Code the frontend can match on
+    pub fn code(&self) -> AppErrorCode {
+        match self {
+            CoinSelectionError::InsufficientFunds { .. } => AppErrorCode::InsufficientFunds,
+            CoinSelectionError::InvalidOutpoint(_) => AppErrorCode::InvalidInput,
+            CoinSelectionError::UnknownUtxo(_) => AppErrorCode::NotFound,
+            CoinSelectionError::FrozenUtxo(_) => AppErrorCode::UtxoFrozen,
+            CoinSelectionError::ImmatureUtxo { .. } => AppErrorCode::InsufficientFunds,
+            CoinSelectionError::NothingToConsolidate => AppErrorCode::InvalidOperation,
+            CoinSelectionError::NothingToSweep => AppErrorCode::InvalidOperation,
+            CoinSelectionError::Overflow => AppErrorCode::InvalidInput,
+        }
+    }
+}
+
+/// Which of a wallet's coins may be spent
+#[derive(Debug, Clone, Copy)]
+pub struct CoinControl<'a> {
+    pub min_confirmations: u32,
+    /// Outpoints ("txid:vout") that must not be spent
+    pub frozen: &'a BTreeSet<String>,
+}
+
+/// A wallet balance split by whether coins can be spent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct BalanceBreakdown {
+    pub total: u64,
+    /// Unfrozen coins with at least the wallet's minimum confirmations
+    pub spendable: u64,
+    /// Unconfirmed coins and coins still short of the minimum
+    pub pending: u64,
+    /// Coins the user froze
+    pub frozen: u64,
+}
+
+/// Coins chosen to fund a payment
+#[derive(Debug, Clone, Serialize)]
+pub struct CoinSelection {
+    pub inputs: Vec<Utxo>,
+    pub total_input: u64,
+    pub fee: u64,
+    /// Value returned to the wallet; zero when the inputs match the payment exactly
+    pub change: u64,
+}
+
+/// Coins to merge into a single output paying back to the wallet
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsolidationPlan {
+    pub inputs: Vec<Utxo>,
+    pub total_input: u64,
+    pub fee: u64,
+    /// Value of the one output the inputs become
+    pub output_value: u64,
+}
+
+/// Estimated size of a transaction in bytes, matching the mempool's simplified sizing: base
+/// overhead plus per-input and per-output bytes
+pub fn estimate_transaction_size(input_count: usize, output_count: usize) -> usize {
+    10 + input_count * 150 + output_count * 34
+}
+
+/// Fee at `fee_rate` for a transaction with the given number of inputs and outputs
+fn fee_for(fee_rate: u64, input_count: usize, output_count: usize) -> Result<u64, CoinSelectionError> {
+    fee_rate
+        .checked_mul(estimate_transaction_size(input_count, output_count) as u64)
+        .ok_or(CoinSelectionError::Overflow)
+}
+
+/// Fee at `fee_rate` for one more input
+fn input_fee(fee_rate: u64) -> Result<u64, CoinSelectionError> {
+    Ok(fee_for(fee_rate, 2, 1)? - fee_for(fee_rate, 1, 1)?)
+}
+
+/// Combined value of `utxos`
+fn total_value<'a>(utxos: impl IntoIterator<Item = &'a Utxo>) -> Result<u64, CoinSelectionError> {
+    utxos
+        .into_iter()
+        .try_fold(0u64, |total, utxo| total.checked_add(utxo.value).ok_or(CoinSelectionError::Overflow))
+}
+
+/// `amount` plus `fee`
+fn with_fee(amount: u64, fee: u64) -> Result<u64, CoinSelectionError> {
+    amount.checked_add(fee).ok_or(CoinSelectionError::Overflow)
+}
+
+/// Confirmations of a coin confirmed at `height` when the chain tip is at `tip_height`.
+/// A coin above the known tip (the wallet synced ahead of the local chain) counts as one.
+pub fn confirmations(height: Option<u32>, tip_height: u64) -> u64 {
+    match height {
+        Some(height) => tip_height.saturating_sub(height as u64) + 1,
+        None => 0,
+    }
+}
+
+/// "txid:vout" of a coin
+pub fn outpoint(utxo: &Utxo) -> String {
+    format!("{}:{}", utxo.txid, utxo.vout)
+}
+
+/// Split "txid:vout" into its parts
+pub fn parse_outpoint(outpoint: &str) -> Result<(String, u32), CoinSelectionError> {
+    let invalid = || CoinSelectionError::InvalidOutpoint(outpoint.to_string());
+    let (txid, vout) = outpoint.trim().rsplit_once(':').ok_or_else(invalid)?;
+    if txid.is_empty() {
+        return Err(invalid());
+    }
+    Ok((txid.to_string(), vout.parse().map_err(|_| invalid())?))
+}
+
+pub fn is_mature(utxo: &Utxo, tip_height: u64, min_confirmations: u32) -> bool {
+    confirmations(utxo.height, tip_height) >= min_confirmations as u64
+}
+
+pub fn is_spendable(utxo: &Utxo, tip_height: u64, control: &CoinControl) -> bool {
+    is_mature(utxo, tip_height, control.min_confirmations) && !control.frozen.contains(&outpoint(utxo))
+}
+
+pub fn balance_breakdown(utxos: &[Utxo], tip_height: u64, control: &CoinControl) -> BalanceBreakdown {
+    utxos.iter().fold(BalanceBreakdown::default(), |mut balance, utxo| {
+        balance.total += utxo.value;
+        if control.frozen.contains(&outpoint(utxo)) {
+            balance.frozen += utxo.value;
+        } else if is_mature(utxo, tip_height, control.min_confirmations) {
+            balance.spendable += utxo.value;
+        } else {
+            balance.pending += utxo.value;
+        }
+        balance
+    })
+}
+
+/// Pick spendable coins, oldest first, to pay `amount` to one output at `fee_rate` satoshis per
+/// byte. Change goes to a second output, whose size is included in the fee whenever there is
+/// change. Change below `dust_threshold` would not be relayed, so it is added to the fee instead.
+pub fn select_coins(
+    utxos: &[Utxo],
+    amount: u64,
+    fee_rate: u64,
+    dust_threshold: u64,
+    tip_height: u64,
+    control: &CoinControl,
+) -> Result<CoinSelection, CoinSelectionError> {
+    let mut candidates: Vec<&Utxo> = utxos.iter().filter(|utxo| is_spendable(utxo, tip_height, control)).collect();
+    candidates.sort_by(|a, b| (a.height, &a.txid, a.vout).cmp(&(b.height, &b.txid, b.vout)));
+    fund(candidates, amount, fee_rate, dust_threshold, || balance_breakdown(utxos, tip_height, control))
+}
+
+/// Pay `amount` from exactly the coins the user chose. Every chosen coin is spent, even if
+/// fewer would do, and each must be unfrozen and have the minimum confirmations.
+pub fn select_chosen_coins(
+    utxos: &[Utxo],
+    outpoints: &[String],
+    amount: u64,
+    fee_rate: u64,
+    dust_threshold: u64,
+    tip_height: u64,
+    control: &CoinControl,
+) -> Result<CoinSelection, CoinSelectionError> {
+    let mut chosen: Vec<&Utxo> = Vec::new();
+    for requested in outpoints {
+        let (txid, vout) = parse_outpoint(requested)?;
+        let utxo = utxos
+            .iter()
+            .find(|utxo| utxo.txid == txid && utxo.vout == vout)
+            .ok_or_else(|| CoinSelectionError::UnknownUtxo(requested.clone()))?;
+        if control.frozen.contains(&outpoint(utxo)) {
+            return Err(CoinSelectionError::FrozenUtxo(outpoint(utxo)));
+        }
+        if !is_mature(utxo, tip_height, control.min_confirmations) {
+            return Err(CoinSelectionError::ImmatureUtxo {
+                outpoint: outpoint(utxo),
+                confirmations: confirmations(utxo.height, tip_height),
+                required: control.min_confirmations,
+            });
+        }
+        if !chosen.iter().any(|c| c.txid == utxo.txid && c.vout == utxo.vout) {
+            chosen.push(utxo);
+        }
+    }
+
+    let inputs: Vec<Utxo> = chosen.into_iter().cloned().collect();
+    let total_input = total_value(&inputs)?;
+    let needed = with_fee(amount, fee_for(fee_rate, inputs.len(), 1)?)?;
+    funded(inputs, total_input, amount, fee_rate, dust_threshold)?.ok_or(CoinSelectionError::InsufficientFunds {
+        available: total_input,
+        needed,
+        pending: 0,
+    })
+}
+
+/// Merge up to `max_inputs` of the smallest spendable coins into one output. Coins worth less
+/// than the fee for spending them are left alone, since merging them would lose money.
+pub fn plan_consolidation(
+    utxos: &[Utxo],
+    fee_rate: u64,
+    max_inputs: usize,
+    tip_height: u64,
+    control: &CoinControl,
+) -> Result<ConsolidationPlan, CoinSelectionError> {
+    let input_fee = input_fee(fee_rate)?;
+    let mut candidates: Vec<&Utxo> = utxos
+        .iter()
+        .filter(|utxo| is_spendable(utxo, tip_height, control) && utxo.value > input_fee)
+        .collect();
+    candidates.sort_by(|a, b| (a.value, &a.txid, a.vout).cmp(&(b.value, &b.txid, b.vout)));
+    candidates.truncate(max_inputs);
+
+    let inputs: Vec<Utxo> = candidates.into_iter().cloned().collect();
+    let total_input = total_value(&inputs)?;
+    let fee = fee_for(fee_rate, inputs.len(), 1)?;
+    if inputs.len() < 2 || total_input <= fee {
+        return Err(CoinSelectionError::NothingToConsolidate);
+    }
+    Ok(ConsolidationPlan { inputs, total_input, fee, output_value: total_input - fee })
+}
+
+/// Spend every spendable coin worth more than its fee to a single output, as when a wallet
+/// moves its funds off a seed it no longer trusts
+pub fn plan_sweep(
+    utxos: &[Utxo],
+    fee_rate: u64,
+    tip_height: u64,
+    control: &CoinControl,
+) -> Result<ConsolidationPlan, CoinSelectionError> {
+    let input_fee = input_fee(fee_rate)?;
+    let inputs: Vec<Utxo> = utxos
+        .iter()
+        .filter(|utxo| is_spendable(utxo, tip_height, control) && utxo.value > input_fee)
+        .cloned()
+        .collect();
+    let total_input = total_value(&inputs)?;
+    let fee = fee_for(fee_rate, inputs.len(), 1)?;
+    if inputs.is_empty() || total_input <= fee {
+        return Err(CoinSelectionError::NothingToSweep);
+    }
+    Ok(ConsolidationPlan { inputs, total_input, fee, output_value: total_input - fee })
+}
+
+/// Add `candidates` in order until they pay `amount` plus the fee
+fn fund(
+    candidates: Vec<&Utxo>,
+    amount: u64,
+    fee_rate: u64,
+    dust_threshold: u64,
+    balance: impl FnOnce() -> BalanceBreakdown,
+) -> Result<CoinSelection, CoinSelectionError> {
+    let mut inputs = Vec::new();
+    let mut total_input: u64 = 0;
+    for utxo in candidates {
+        inputs.push(utxo.clone());
+        total_input = total_input.checked_add(utxo.value).ok_or(CoinSelectionError::Overflow)?;
+        if let Some(selection) = funded(inputs.clone(), total_input, amount, fee_rate, dust_threshold)? {
+            return Ok(selection);
+        }
+    }
+
+    let balance = balance();
+    Err(CoinSelectionError::InsufficientFunds {
+        available: balance.spendable,
+        needed: with_fee(amount, fee_for(fee_rate, inputs.len().max(1), 1)?)?,
+        pending: balance.pending,
+    })
+}
+
+/// The selection if `inputs` pay `amount` plus the fee. Change worth at least `dust_threshold`
+/// goes to a change output; anything less is left to the fee.
+fn funded(
+    inputs: Vec<Utxo>,
+    total_input: u64,
+    amount: u64,
+    fee_rate: u64,
+    dust_threshold: u64,
+) -> Result<Option<CoinSelection>, CoinSelectionError> {
+    if total_input < with_fee(amount, fee_for(fee_rate, inputs.len(), 1)?)? {
+        return Ok(None);
+    }
+    let fee = fee_for(fee_rate, inputs.len(), 2)?;
+    match total_input.checked_sub(with_fee(amount, fee)?) {
+        Some(change) if change > 0 && change >= dust_threshold => {
+            Ok(Some(CoinSelection { change, inputs, total_input, fee }))
+        }
+        _ => Ok(Some(CoinSelection { fee: total_input - amount, inputs, total_input, change: 0 })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::relay_policy::DEFAULT_DUST_THRESHOLD;
+
+    fn utxo(txid: &str, value: u64, height: Option<u32>) -> Utxo {
+        Utxo {
+            txid: txid.to_string(),
+            vout: 0,
+            value,
+            script_pubkey: String::new(),
+            address: "addr".to_string(),
+            is_change: false,
+            height,
+        }
+    }
+
+    fn control(min_confirmations: u32, frozen: &BTreeSet<String>) -> CoinControl<'_> {
+        CoinControl { min_confirmations, frozen }
+    }
+
+    #[test]
+    fn test_selection_skips_coins_below_min_confirmations() {
+        let none = BTreeSet::new();
+        let utxos = vec![utxo("new", 50_000, Some(100)), utxo("old", 10_000, Some(90)), utxo("mempool", 80_000, None)];
+
+        let balance = balance_breakdown(&utxos, 100, &control(3, &none));
+        assert_eq!(balance, BalanceBreakdown { total: 140_000, spendable: 10_000, pending: 130_000, frozen: 0 });
+        assert_eq!(balance_breakdown(&utxos, 100, &control(0, &none)).pending, 0);
+
+        let selection = select_coins(&utxos, 5_000, 1, DEFAULT_DUST_THRESHOLD, 100, &control(1, &none)).unwrap();
+        assert_eq!(selection.inputs.len(), 1);
+        assert_eq!(selection.inputs[0].txid, "old");
+        assert_eq!(selection.change, 10_000 - 5_000 - selection.fee);
+
+        let selection = select_coins(&utxos, 20_000, 1, DEFAULT_DUST_THRESHOLD, 100, &control(1, &none)).unwrap();
+        assert_eq!(selection.inputs.len(), 2);
+
+        assert!(matches!(
+            select_coins(&utxos, 20_000, 1, DEFAULT_DUST_THRESHOLD, 100, &control(3, &none)),
+            Err(CoinSelectionError::InsufficientFunds { available: 10_000, pending: 130_000, .. })
+        ));
+    }
+
+    #[test]
+    fn test_frozen_and_chosen_coins() {
+        let frozen = BTreeSet::from(["old:0".to_string()]);
+        let utxos = vec![utxo("new", 50_000, Some(100)), utxo("old", 10_000, Some(90))];
+
+        let selection = select_coins(&utxos, 5_000, 1, DEFAULT_DUST_THRESHOLD, 100, &control(1, &frozen)).unwrap();
+        assert_eq!(selection.inputs[0].txid, "new");
+        assert_eq!(balance_breakdown(&utxos, 100, &control(1, &frozen)).frozen, 10_000);
+
+        let chosen = ["new:0".to_string()];
+        let selection =
+            select_chosen_coins(&utxos, &chosen, 1_000, 1, DEFAULT_DUST_THRESHOLD, 100, &control(1, &frozen)).unwrap();
+        assert_eq!(selection.total_input, 50_000);
+        assert!(matches!(
+            select_chosen_coins(
+                &utxos,
+                &["old:0".to_string()],
+                1_000,
+                1,
+                DEFAULT_DUST_THRESHOLD,
+                100,
+                &control(1, &frozen)
+            ),
+            Err(CoinSelectionError::FrozenUtxo(outpoint)) if outpoint == "old:0"
+        ));
+        assert_eq!(parse_outpoint("abc:x"), Err(CoinSelectionError::InvalidOutpoint("abc:x".to_string())));
+    }
+
+    #[test]
+    fn test_consolidation_merges_smallest_coins_worth_their_fee() {
+        let none = BTreeSet::new();
+        let utxos = vec![
+            utxo("big", 1_000_000, Some(10)),
+            utxo("small", 2_000, Some(10)),
+            utxo("smaller", 1_000, Some(10)),
+            utxo("dust", 100, Some(10)),
+        ];
+
+        let plan = plan_consolidation(&utxos, 1, 2, 100, &control(1, &none)).unwrap();
+        let merged: Vec<&str> = plan.inputs.iter().map(|utxo| utxo.txid.as_str()).collect();
+        assert_eq!(merged, ["smaller", "small"]);
+        assert_eq!(plan.output_value, 3_000 - plan.fee);
+
+        assert!(matches!(
+            plan_consolidation(&utxos[..2], 1, 10, 100, &control(1, &BTreeSet::from(["big:0".to_string()]))),
+            Err(CoinSelectionError::NothingToConsolidate)
+        ));
+    }
+
+    #[test]
+    fn test_sweep_spends_every_coin_worth_its_fee() {
+        let frozen = BTreeSet::from(["frozen:0".to_string()]);
+        let utxos = vec![
+            utxo("big", 1_000_000, Some(10)),
+            utxo("small", 2_000, Some(10)),
+            utxo("dust", 100, Some(10)),
+            utxo("frozen", 5_000, Some(10)),
+            utxo("mempool", 5_000, None),
+        ];
+
+        let plan = plan_sweep(&utxos, 1, 100, &control(1, &frozen)).unwrap();
+        let swept: Vec<&str> = plan.inputs.iter().map(|utxo| utxo.txid.as_str()).collect();
+        assert_eq!(swept, ["big", "small"]);
+        assert_eq!(plan.output_value, 1_002_000 - plan.fee);
+
+        assert_eq!(plan_sweep(&utxos[2..], 1, 100, &control(1, &frozen)).unwrap_err(), CoinSelectionError::NothingToSweep);
+    }
+
+    #[test]
+    fn test_change_below_dust_goes_to_the_fee() {
+        let none = BTreeSet::new();
+        let fee_with_change = estimate_transaction_size(1, 2) as u64;
+        let utxos = vec![utxo("coin", 10_000 + fee_with_change + 100, Some(10))];
+
+        // 100 satoshis of change would be dust, so the whole excess is fee
+        let selection = select_coins(&utxos, 10_000, 1, DEFAULT_DUST_THRESHOLD, 100, &control(1, &none)).unwrap();
+        assert_eq!(selection.change, 0);
+        assert_eq!(selection.fee, fee_with_change + 100);
+
+        // With no dust threshold the change is kept
+        let selection = select_coins(&utxos, 10_000, 1, 0, 100, &control(1, &none)).unwrap();
+        assert_eq!((selection.change, selection.fee), (100, fee_with_change));
+
+        // Too little for a change output at all still pays with the excess as fee
+        let selection = select_coins(&utxos, 10_000 + 120, 1, 0, 100, &control(1, &none)).unwrap();
+        assert_eq!((selection.change, selection.fee), (0, fee_with_change - 20));
+    }
+
+    #[test]
+    fn test_overflowing_amounts_and_fees_are_rejected() {
+        let none = BTreeSet::new();
+        let utxos = vec![utxo("big", u64::MAX, Some(10)), utxo("more", 1_000, Some(10))];
+
+        let select = |amount, fee_rate| select_coins(&utxos, amount, fee_rate, DEFAULT_DUST_THRESHOLD, 100, &control(1, &none));
+        assert_eq!(select(u64::MAX, 1).unwrap_err(), CoinSelectionError::Overflow);
+        assert_eq!(select(1, u64::MAX).unwrap_err(), CoinSelectionError::Overflow);
+        assert_eq!(plan_sweep(&utxos, 1, 100, &control(1, &none)).unwrap_err(), CoinSelectionError::Overflow);
+    }
+}
@@ -0,0 +1,162 @@
+//! Local usage and latency metrics for Tauri commands.
+//!
+//! The async commands in `commands` are marked `#[tracked]`, which runs their body through `track`
+//! to record how often each command is called, how long it takes and how often it fails;
+//! developer tools are not tracked.
+//! Nothing leaves the machine: the figures are kept in memory, returned by `get_command_metrics`
+//! and exported on the metrics endpoint, and start over when the app restarts.
+//!
+//! `track` also notes when the user last did anything, which the idle auto-lock reads. Commands
+//! the UI polls on a timer do not count.
+
+use crate::errors::CommandError;
+use log::warn;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+pub use b_rad_coin_macros::tracked;
+
+/// Upper bounds of the duration histogram buckets in milliseconds; slower calls land in a final
+/// unbounded bucket
+pub const BUCKET_BOUNDS_MS: [u64; 11] = [5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000];
+
+/// Calls slower than this are logged as they finish
+const SLOW_COMMAND: Duration = Duration::from_secs(2);
+
+/// Commands the UI calls on a timer rather than because of the user
+const POLLING_COMMANDS: [&str; 2] = ["get_network_status", "get_wallet_sync_status"];
+
+#[derive(Debug, Default)]
+struct CommandRecord {
+    calls: u64,
+    errors: u64,
+    total: Duration,
+    max: Duration,
+    /// Calls per bucket of `BUCKET_BOUNDS_MS`, plus one for slower calls
+    buckets: [u64; BUCKET_BOUNDS_MS.len() + 1],
+}
+
+/// Metrics of one command since the app started
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandStats {
+    pub command: String,
+    pub calls: u64,
+    pub errors: u64,
+    /// Share of calls that returned an error, from 0 to 1
+    pub error_rate: f64,
+    pub total_ms: f64,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+    /// Calls per histogram bucket; the bucket without an upper bound comes last
+    pub buckets: Vec<HistogramBucket>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistogramBucket {
+    /// Upper bound of the bucket in milliseconds; None for calls slower than every bound
+    pub le_ms: Option<u64>,
+    pub count: u64,
+}
+
+static RECORDS: OnceLock<Mutex<HashMap<&'static str, CommandRecord>>> = OnceLock::new();
+
+static LAST_ACTIVITY: OnceLock<Mutex<Instant>> = OnceLock::new();
+
+fn records() -> &'static Mutex<HashMap<&'static str, CommandRecord>> {
+    RECORDS.get_or_init(Default::default)
+}
+
+fn last_activity() -> &'static Mutex<Instant> {
+    LAST_ACTIVITY.get_or_init(|| Mutex::new(Instant::now()))
+}
+
+/// Time since the last command the user caused, or since this was first asked if there was none
+pub fn idle_for() -> Duration {
+    last_activity().lock().unwrap().elapsed()
+}
+
+/// Run a command body and record its duration and outcome under the command's name
+pub async fn track<T, F>(command: &'static str, body: F) -> Result<T, CommandError>
+where
+    F: Future<Output = Result<T, CommandError>>,
+{
+    let started = Instant::now();
+    if !POLLING_COMMANDS.contains(&command) {
+        *last_activity().lock().unwrap() = started;
+    }
+    let result = body.await;
+    record(command, started.elapsed(), result.is_ok());
+    result
+}
+
+fn record(command: &'static str, elapsed: Duration, ok: bool) {
+    if elapsed >= SLOW_COMMAND {
+        warn!("Command {} took {} ms", command, elapsed.as_millis());
+    }
+
+    let bucket = BUCKET_BOUNDS_MS
+        .iter()
+        .position(|&bound| elapsed <= Duration::from_millis(bound))
+        .unwrap_or(BUCKET_BOUNDS_MS.len());
+
+    let mut records = records().lock().unwrap();
+    let record = records.entry(command).or_default();
+    record.calls += 1;
+    if !ok {
+        record.errors += 1;
+    }
+    record.total += elapsed;
+    record.max = record.max.max(elapsed);
+    record.buckets[bucket] += 1;
+}
+
+/// Metrics of every command called so far, the most time-consuming first
+pub fn snapshot() -> Vec<CommandStats> {
+    let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+
+    let records = records().lock().unwrap();
+    let mut stats: Vec<CommandStats> = records
+        .iter()
+        .map(|(command, record)| CommandStats {
+            command: command.to_string(),
+            calls: record.calls,
+            errors: record.errors,
+            error_rate: record.errors as f64 / record.calls as f64,
+            total_ms: millis(record.total),
+            mean_ms: millis(record.total) / record.calls as f64,
+            max_ms: millis(record.max),
+            buckets: BUCKET_BOUNDS_MS
+                .iter()
+                .map(|&bound| Some(bound))
+                .chain(std::iter::once(None))
+                .zip(record.buckets)
+                .map(|(le_ms, count)| HistogramBucket { le_ms, count })
+                .collect(),
+        })
+        .collect();
+    stats.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_calls_errors_and_buckets() {
+        record("test_metrics_command", Duration::from_millis(3), true);
+        record("test_metrics_command", Duration::from_millis(40), false);
+        record("test_metrics_command", Duration::from_secs(30), true);
+
+        let stats = snapshot().into_iter().find(|s| s.command == "test_metrics_command").unwrap();
+        assert_eq!((stats.calls, stats.errors), (3, 1));
+        assert!((stats.error_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.max_ms, 30_000.0);
+
+        let counts: Vec<_> = stats.buckets.iter().filter(|b| b.count > 0).map(|b| b.le_ms).collect();
+        assert_eq!(counts, [Some(5), Some(50), None]);
+    }
+}
@@ -1,29 +1,63 @@
 use crate::logging;
 use log::{debug, error, info, warn};
 use std::sync::Arc;  // Add this import for Arc
-use tauri::Emitter;
+use std::time::Instant;
+use crate::events::{self, EmitEvent};
 use tauri::{command, Manager, State};
 use serde::{Serialize, Deserialize};
 
 use crate::config::{AppSettings, ConfigManager}; // Ensure WalletInfo is imported if not already
 use crate::security::AsyncSecurityManager;
 use crate::wallet_manager::AsyncWalletManager;
+use crate::event_bus::{self, BackendEvent};
+use crate::services::{BlockchainServices, ServiceContainer};
+use crate::wallet_settings::WalletSettings;
+use crate::wallet_data::{WalletProfile, IMPORTED_KEY_DERIVATION_PATH};
+use crate::password_strength::{self, PasswordStrength};
+use crate::wallet_activity::{self, ActivityEntry, ActivityKind, WalletActivityLog};
+use crate::spending_policy::{self, SPEND_WINDOW_SECS};
+use crate::transaction_drafts::{TransactionDrafts, TransactionPreview, DRAFT_TTL_SECS};
+use crate::wallet_labels::{Contact, ContactBook, LabelExport, LabelImportSummary};
+use crate::ownership_proof::{self, OwnershipProof};
+use crate::coin_selection::{
+    self, balance_breakdown, estimate_transaction_size, CoinControl, CoinSelection, DEFAULT_MIN_CONFIRMATIONS,
+    MAX_MIN_CONFIRMATIONS,
+};
+use crate::paths::PathProvider;
+use crate::settings_profile::{self, SettingChange};
+use crate::atomic_file;
+use crate::tray;
 use bip39::Mnemonic;
 use rand::Rng;
-use crate::blockchain_sync::{AsyncBlockchainSyncService, NetworkStatus};
+use crate::blockchain_sync::AsyncBlockchainSyncService;
+use crate::node_status::NodeStatus;
 use crate::wallet_sync_service::{AsyncWalletSyncService, WalletSyncStatus};
-use crate::mining_service::{AsyncMiningService, MiningStatus};
+use crate::mining_worker::MiningIntensity;
+use crate::mining_service::{AsyncMiningService, BlockTemplate, MiningRewards, MiningStatus, NetworkHashRate};
+use crate::power_monitor::{PowerMonitor, PowerStatus};
 use crate::mempool_service::{AsyncMempoolService, ReplacementReason, ReplacementResult};
+use crate::signature_cache;
+use crate::relay_policy::{RelayPolicy, TransactionSource};
 use crate::network_monitor::{AsyncNetworkMonitor, NetworkDiagnostics};
 use crate::blockchain_database::{Transaction, TransactionInput, TransactionOutput};
 use crate::fee_estimator::{AsyncFeeEstimator, FeeTarget};
+use crate::price_service::{AsyncPriceService, ExchangeRate, FiatAmount, PriceSnapshot};
+use crate::cost_basis::{CostBasisLedger, CostBasisMethod, CostBasisReport};
+use crate::updater::{AsyncUpdateService, UpdateChannel, UpdateInfo};
+use crate::chain_params::ChainParams;
+use crate::command_metrics;
+use crate::errors::{AppErrorCode, CommandError, WalletError};
+use crate::payment_requests::{PaymentRequest, PaymentRequestBook, PaymentRequestStatus};
+use crate::payment_uri::{PaymentUri, PendingPaymentUri};
+use crate::scheduled_payments::{AsyncPaymentScheduler, NewScheduledPayment, ScheduledPayment};
 
 /// Response type for commands with proper error handling
-type CommandResult<T> = Result<T, String>;
+type CommandResult<T> = Result<T, CommandError>;
 
-/// Convert Application errors to string responses for Tauri
-fn format_error<E: std::fmt::Display>(e: E) -> String {
-    format!("{}", e)
+/// Wrap an error that has no more specific code. Typed errors (`AppError`, `WalletError`,
+/// `ConfigError`, `SecurityError`, `ValidationError`) convert with `into()` and keep their code.
+fn format_error<E: std::fmt::Display>(e: E) -> CommandError {
+    CommandError::internal(e.to_string())
 }
 
 /// Wallet details for the frontend
@@ -36,14 +70,14 @@ pub struct WalletDetails {
 
 /// Command to check if a wallet is currently open
 #[command]
+#[command_metrics::tracked]
 pub async fn check_wallet_status(
     wallet_manager: State<'_, AsyncWalletManager>,
 ) -> CommandResult<bool> {
     debug!("Command: check_wallet_status");
-    let manager = wallet_manager.get_manager().await;
 
-    // A wallet is open if get_current_wallet returns Some
-    let result = manager.get_current_wallet().is_some();
+    // A wallet is open if there is an active one
+    let result = wallet_manager.status().active_wallet.is_some();
 
     if result {
         debug!("Wallet status: open");
@@ -56,57 +90,122 @@ pub async fn check_wallet_status(
 
 /// Command to get the number of available CPU cores
 #[command]
+#[command_metrics::tracked]
 pub async fn get_cpu_cores() -> CommandResult<u32> {
     debug!("Command: get_cpu_cores");
-    
+
     let cores = std::thread::available_parallelism()
         .map(|n| n.get() as u32)
         .unwrap_or(1);
-    
+
     debug!("Available CPU cores: {}", cores);
     Ok(cores)
 }
 
-/// Command to close the currently open wallet
+/// Command to close an open wallet (the active one when no name is given).
+/// Sync and mining for that wallet are stopped; other open wallets are left running.
+#[command]
+#[command_metrics::tracked]
+pub async fn close_wallet(
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<bool> {
+    info!("Command: close_wallet {:?}", wallet_name);
+    let closed_wallet = {
+        let mut manager = wallet_manager.get_manager().await;
+        let name = match wallet_name {
+            Some(name) => Some(name),
+            None => manager.get_current_wallet().map(|wallet| wallet.name.clone()),
+        };
+
+        // Close the wallet
+        if let Some(name) = &name {
+            manager.close_wallet_by_name(name);
+        }
+        name
+    };
+
+    if let Some(name) = closed_wallet {
+        if let Some(wallet_sync) = crate::services::get::<AsyncWalletSyncService>(&app_handle) {
+            if let Err(e) = wallet_sync.stop_wallet_sync(&name).await {
+                warn!("Failed to stop wallet sync for {}: {}", name, e);
+            }
+        }
+        if let Some(mining_service) = crate::services::get::<AsyncMiningService>(&app_handle) {
+            if let Err(e) = mining_service.stop_mining(&name).await {
+                warn!("Failed to stop mining for {}: {}", name, e);
+            }
+        }
+        if let Some(power_monitor) = app_handle.try_state::<PowerMonitor>() {
+            power_monitor.forget_miner(&name).await;
+        }
+        // A closed wallet keeps no session, so its cached keys go with it
+        if let Some(security_manager) = app_handle.try_state::<AsyncSecurityManager>() {
+            security_manager.get_manager().await.lock_wallet(&name);
+        }
+    }
+
+    tray::set_wallet_status(&app_handle, wallet_manager.current_wallet_name());
+
+    Ok(true)
+}
+
+/// Command to list the names of all open wallets
+#[command]
+#[command_metrics::tracked]
+pub async fn get_open_wallets(
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<Vec<String>> {
+    debug!("Command: get_open_wallets");
+
+    Ok(wallet_manager.open_wallet_names())
+}
+
+/// Command to switch the active wallet to another open wallet
 #[command]
-pub async fn close_wallet(wallet_manager: State<'_, AsyncWalletManager>) -> CommandResult<bool> {
-    info!("Command: close_wallet");
+#[command_metrics::tracked]
+pub async fn set_active_wallet(
+    wallet_name: String,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<bool> {
+    info!("Command: set_active_wallet to {}", wallet_name);
     let mut manager = wallet_manager.get_manager().await;
 
-    // Close the wallet
-    manager.close_wallet();
+    manager.set_active_wallet(&wallet_name)?;
+    tray::set_wallet_status(&app_handle, Some(wallet_name));
 
     Ok(true)
 }
 
 /// Command to get a list of available wallets
 #[command]
+#[command_metrics::tracked]
 pub async fn get_available_wallets(
     wallet_manager: State<'_, AsyncWalletManager>,
-) -> CommandResult<Vec<String>> {    debug!("Command: get_available_wallets");
-    let mut manager = wallet_manager.get_manager().await;
+) -> CommandResult<Vec<String>> {
+    debug!("Command: get_available_wallets");
 
     // Get wallets and extract names
-    let wallets = manager
-        .list_wallets()
-        .into_iter()
-        .map(|w| w.name.clone())
-        .collect();
+    let wallets = wallet_manager.status().wallets.iter().map(|w| w.name.clone()).collect();
 
     Ok(wallets)
 }
 
 /// Command to get detailed information about all wallets
 #[command]
+#[command_metrics::tracked]
 pub async fn get_wallet_details(
     wallet_manager: State<'_, AsyncWalletManager>,
-) -> CommandResult<Vec<WalletDetails>> {    debug!("Command: get_wallet_details");
-    let mut manager = wallet_manager.get_manager().await;
-    
+) -> CommandResult<Vec<WalletDetails>> {
+    debug!("Command: get_wallet_details");
+
     // Get wallets and convert to WalletDetails
-    let wallets: Vec<WalletDetails> = manager
-        .list_wallets()
-        .into_iter()
+    let wallets: Vec<WalletDetails> = wallet_manager
+        .status()
+        .wallets
+        .iter()
         .map(|w| WalletDetails {
             name: w.name.clone(),
             secured: w.secured,
@@ -122,22 +221,25 @@ pub async fn get_wallet_details(
 
 /// Command to check if the current wallet is secured (password protected)
 #[command]
+#[command_metrics::tracked]
 pub async fn is_current_wallet_secured(
     wallet_manager: State<'_, AsyncWalletManager>,
 ) -> CommandResult<Option<bool>> {
     debug!("Command: is_current_wallet_secured");
-    let manager = wallet_manager.get_manager().await;
+    let status = wallet_manager.status();
 
-    Ok(manager.is_current_wallet_secured())
+    Ok(status.active_wallet.as_deref().and_then(|name| status.wallet(name)).map(|info| info.secured))
 }
 
 /// Command to create a new wallet with optional password protection and a specific seed phrase
 #[command]
+#[command_metrics::tracked]
 pub async fn create_wallet(
     wallet_name: String,
     password: String,
     use_password: bool,
     seed_phrase: Option<String>,
+    passphrase: Option<String>,
     wallet_manager: State<'_, AsyncWalletManager>,
     config_manager_arc: State<'_, Arc<ConfigManager>>,
 ) -> CommandResult<bool> {
@@ -145,6 +247,7 @@ pub async fn create_wallet(
 
     // If password protection is disabled, use empty password
     let effective_password = if use_password {
+        check_password_strength(&config_manager_arc, &wallet_name, &password)?;
         password
     } else {
         String::new()
@@ -159,112 +262,86 @@ pub async fn create_wallet(
     } else {
         // Seed phrase is required
         error!("No seed phrase provided");
-        return Err("Seed phrase is required for wallet creation.".to_string());
+        return Err("Seed phrase is required for wallet creation.".to_string().into());
     };
 
     let mut manager = wallet_manager.get_manager().await;
-    
+
     // Call the synchronous create_wallet_with_seed function
-    match manager.create_wallet_with_seed(&wallet_name, &effective_password, &actual_seed_phrase, use_password) {
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+    match manager.create_wallet_with_seed(&wallet_name, &effective_password, &actual_seed_phrase, passphrase.as_deref(), use_password).await {
         Ok(_) => {
             info!("Wallet created successfully: {}", wallet_name);
             Ok(true)
         }
         Err(e) => {
             error!("Failed to create wallet: {}", e);
-            Err(e.to_string())
+            Err(e.to_string().into())
         }
     }
 }
 
 /// Command to get the name of the currently open wallet
 #[command]
+#[command_metrics::tracked]
 pub async fn get_current_wallet_name(
     wallet_manager: State<'_, AsyncWalletManager>,
 ) -> CommandResult<Option<String>> {
     debug!("Command: get_current_wallet_name");
 
-    let manager = wallet_manager.get_manager().await;
-
-    // Extract name from current wallet if one is open
-    let result = manager
-        .get_current_wallet()
-        .map(|wallet| wallet.name.clone());
-
-    Ok(result)
+    Ok(wallet_manager.current_wallet_name())
 }
 
 /// Command to get the path of the currently open wallet
 #[command]
+#[command_metrics::tracked]
 pub async fn get_current_wallet_path(
     wallet_manager: State<'_, AsyncWalletManager>,
 ) -> CommandResult<Option<String>> {
     debug!("Command: get_current_wallet_path");
-    
-    let manager = wallet_manager.get_manager().await;
-    
+
+    let status = wallet_manager.status();
+
     // Get the current wallet name
-    let current_wallet_name = match manager.get_current_wallet() {
-        Some(wallet) => wallet.name.clone(),
+    let current_wallet_name = match &status.active_wallet {
+        Some(name) => name.clone(),
         None => {
             info!("No wallet is currently open");
             return Ok(None);
         }
     };
-    
+
     // Find the wallet info to get the path
-    let wallet_info = manager.find_wallet_by_name(&current_wallet_name);
-    
+    let wallet_info = status.wallet(&current_wallet_name);
+
     match wallet_info {
         Some(info) => {
             debug!("Found path for wallet '{}': {}", current_wallet_name, info.path);
-            
-            // Get the base wallets directory
-            let wallets_dir = manager.get_wallets_dir();
-            debug!("Base wallets directory: {}", wallets_dir.display());
-            
-            // Check if the path is relative or absolute
-            let path_str = &info.path;
-            let path = std::path::Path::new(path_str);
-            debug!("Wallet path from config: {}", path.display());
-            debug!("Is absolute path: {}", path.is_absolute());
-            
-            let wallet_dir = if path.is_absolute() {
-                // If the path is already absolute, use it directly
-                path.to_path_buf()
-            } else {
-                // Check if the path already starts with "wallets/" to avoid duplication
-                if path_str.starts_with("wallets/") || path_str.starts_with("wallets\\") {
-                    // The path already includes the wallets directory, so use it as relative to the parent of wallets_dir
-                    let parent_dir = wallets_dir.parent().unwrap_or(&wallets_dir);
-                    parent_dir.join(path_str)
-                } else {
-                    // Otherwise, join it with the wallets directory
-                    wallets_dir.join(path_str)
-                }
-            };
-            
+        
+            // Relative wallet paths are resolved against the data directory
+            let wallet_dir = PathProvider::resolve_wallet_path(&info.path);
+        
             debug!("Constructed wallet directory path: {}", wallet_dir.display());
-            
+        
             // Verify the path
             let exists = wallet_dir.exists();
             let is_dir = if exists { wallet_dir.is_dir() } else { false };
-            
+        
             debug!("Wallet path exists: {}, Is directory: {}", exists, is_dir);
-            
+        
             if !exists {
                 warn!("Wallet directory does not exist: {}", wallet_dir.display());
             } else if !is_dir {
                 warn!("Wallet path is not a directory: {}", wallet_dir.display());
             }
-            
+        
             // Try to canonicalize the path
             let canonical_result = wallet_dir.canonicalize();
-            
+        
             match canonical_result {
                 Ok(canonical_path) => {
                     debug!("Canonical wallet path: {}", canonical_path.display());
-                    
+                
                     // Convert to string with platform-specific separators
                     match canonical_path.to_str() {
                         Some(path_str) => {
@@ -300,7 +377,7 @@ pub async fn get_current_wallet_path(
         },
         None => {
             error!("Failed to find path for current wallet: {}", current_wallet_name);
-            Err(format!("Could not find path information for wallet '{}'", current_wallet_name))
+            Err(format!("Could not find path information for wallet '{}'", current_wallet_name).into())
         }
     }
 }
@@ -316,12 +393,48 @@ pub struct UpdateSettingsRequest {
     skip_seed_phrase_dialogs: Option<bool>,
     minimize_to_system_tray: Option<bool>,
     mining_threads: Option<u32>,
+    price_feed_enabled: Option<bool>,
+    price_feed_url: Option<String>,
+    price_feed_interval_secs: Option<u64>,
+    display_currency: Option<String>,
+    auto_update_check: Option<bool>,
+    update_channel: Option<String>,
+    network_trace_enabled: Option<bool>,
+    network_trace_dump: Option<bool>,
+    max_outbound_peers: Option<u32>,
+    max_inbound_peers: Option<u32>,
+    min_peer_protocol_version: Option<u32>,
+    p2p_bind_addresses: Option<Vec<String>>,
+    max_mempool_size_mb: Option<u32>,
+    checkpoints_enabled: Option<bool>,
+    language: Option<String>,
+    push_api_enabled: Option<bool>,
+    push_api_port: Option<u16>,
+    metrics_enabled: Option<bool>,
+    metrics_port: Option<u16>,
+    compact_filter_sync: Option<bool>,
+    address_index_enabled: Option<bool>,
+    min_password_score: Option<u8>,
+    mine_on_battery: Option<bool>,
+    stratum_enabled: Option<bool>,
+    stratum_port: Option<u16>,
+    stratum_payout_address: Option<String>,
+    db_cache_capacity_mb: Option<u32>,
+    db_block_cache_mb: Option<u32>,
+    db_low_memory_mode: Option<bool>,
+    min_relay_fee_rate: Option<u64>,
+    max_standard_tx_size: Option<usize>,
+    dust_threshold: Option<u64>,
+    relay_non_standard_scripts: Option<bool>,
+    blocks_only: Option<bool>,
 }
 
 #[command]
+#[command_metrics::tracked]
 pub async fn update_app_settings(
     request: UpdateSettingsRequest,
     config_manager_arc: State<'_, Arc<ConfigManager>>,
+    app_handle: tauri::AppHandle,
 ) -> CommandResult<bool> {
     info!("Command: update_app_settings - {:?}", request);
 
@@ -341,36 +454,36 @@ pub async fn update_app_settings(
         info!("Updating auto_backup to: {}", auto_backup_val);
         config.app_settings.auto_backup = auto_backup_val;
     }    
-    
+
     if let Some(notifications_val) = request.notifications_enabled {
         info!("Updating notifications_enabled to: {}", notifications_val);
         config.app_settings.notifications_enabled = notifications_val;
     }
-    
+
     if let Some(log_level_val) = request.log_level {
         info!("Updating log_level to: {}", log_level_val);
         config.app_settings.log_level = log_level_val;
         // TODO: Update actual log level at runtime if needed
     }
-    
+
     if let Some(dev_mode) = request.developer_mode {
         info!("Updating developer_mode to: {}", dev_mode);
         config.app_settings.developer_mode = dev_mode;
-        
+    
         // If developer mode is being turned off, also turn off skip_seed_phrase_dialogs
         if !dev_mode && config.app_settings.skip_seed_phrase_dialogs {
             info!("Developer mode disabled, disabling skip_seed_phrase_dialogs");
             config.app_settings.skip_seed_phrase_dialogs = false;
         }
     }
-    
+
     if let Some(skip_dialogs) = request.skip_seed_phrase_dialogs {
         // Only allow skip_seed_phrase_dialogs to be enabled if developer_mode is enabled
         if skip_dialogs && !config.app_settings.developer_mode {
             error!("Cannot enable skip_seed_phrase_dialogs when developer_mode is disabled");
-            return Err("Developer mode must be enabled to skip seed phrase dialogs".to_string());
+            return Err("Developer mode must be enabled to skip seed phrase dialogs".to_string().into());
         }
-        
+    
         info!("Updating skip_seed_phrase_dialogs to: {}", skip_dialogs);
         config.app_settings.skip_seed_phrase_dialogs = skip_dialogs;
     }
@@ -378,12 +491,6 @@ pub async fn update_app_settings(
     if let Some(minimize_to_tray) = request.minimize_to_system_tray {
         info!("Updating minimize_to_system_tray to: {}", minimize_to_tray);
         config.app_settings.minimize_to_system_tray = minimize_to_tray;
-        // Note: System tray changes will take effect on next application restart
-        if minimize_to_tray {
-            info!("System tray will be enabled on next application restart");
-        } else {
-            info!("System tray will be disabled on next application restart");
-        }
     }
 
     if let Some(threads) = request.mining_threads {
@@ -391,21 +498,281 @@ pub async fn update_app_settings(
         let max_cores = std::thread::available_parallelism()
             .map(|n| n.get() as u32)
             .unwrap_or(1);
-        
+    
         if threads == 0 {
             error!("Mining threads cannot be 0");
-            return Err("Mining threads must be at least 1".to_string());
+            return Err("Mining threads must be at least 1".to_string().into());
         }
-        
+    
         if threads > max_cores {
             error!("Mining threads {} exceeds available CPU cores {}", threads, max_cores);
-            return Err(format!("Mining threads cannot exceed {} (available CPU cores)", max_cores));
+            return Err(format!("Mining threads cannot exceed {} (available CPU cores)", max_cores).into());
         }
-        
+    
         info!("Updating mining_threads to: {}", threads);
         config.app_settings.mining_threads = threads;
     }
 
+    if let Some(url) = request.price_feed_url {
+        let url = url.trim().to_string();
+        if url.is_empty() {
+            info!("Clearing price_feed_url");
+            config.app_settings.price_feed_url = None;
+        } else if url.starts_with("https://") || url.starts_with("http://") {
+            info!("Updating price_feed_url to: {}", url);
+            config.app_settings.price_feed_url = Some(url);
+        } else {
+            error!("Invalid price feed URL: {}", url);
+            return Err("Price feed URL must start with http:// or https://".to_string().into());
+        }
+    }
+
+    if let Some(interval) = request.price_feed_interval_secs {
+        if interval < crate::price_service::MIN_REFRESH_INTERVAL_SECS {
+            error!("Price feed interval {} is too short", interval);
+            return Err(format!(
+                "Price feed interval must be at least {} seconds",
+                crate::price_service::MIN_REFRESH_INTERVAL_SECS
+            ).into());
+        }
+        info!("Updating price_feed_interval_secs to: {}", interval);
+        config.app_settings.price_feed_interval_secs = interval;
+    }
+
+    if let Some(currency) = request.display_currency {
+        let currency = currency.trim().to_uppercase();
+        if !crate::price_service::is_valid_currency_code(&currency) {
+            error!("Invalid display currency: {}", currency);
+            return Err(format!("'{}' is not a valid currency code", currency).into());
+        }
+        info!("Updating display_currency to: {}", currency);
+        config.app_settings.display_currency = currency;
+    }
+
+    if let Some(auto_update) = request.auto_update_check {
+        info!("Updating auto_update_check to: {}", auto_update);
+        config.app_settings.auto_update_check = auto_update;
+    }
+
+    if let Some(channel) = request.update_channel {
+        let channel: UpdateChannel = channel.parse()?;
+        info!("Updating update_channel to: {:?}", channel);
+        config.app_settings.update_channel = format!("{:?}", channel).to_lowercase();
+    }
+
+    if let Some(trace_enabled) = request.network_trace_enabled {
+        info!("Updating network_trace_enabled to: {}", trace_enabled);
+        config.app_settings.network_trace_enabled = trace_enabled;
+    }
+
+    if let Some(trace_dump) = request.network_trace_dump {
+        info!("Updating network_trace_dump to: {}", trace_dump);
+        config.app_settings.network_trace_dump = trace_dump;
+    }
+
+    if request.max_outbound_peers.is_some() || request.max_inbound_peers.is_some() {
+        let max_outbound = request.max_outbound_peers.unwrap_or(config.app_settings.max_outbound_peers);
+        let max_inbound = request.max_inbound_peers.unwrap_or(config.app_settings.max_inbound_peers);
+        if let Err(e) = crate::peer_policy::validate_connection_limits(max_outbound, max_inbound) {
+            error!("Invalid connection limits: {}", e);
+            return Err(e.into());
+        }
+        info!("Updating connection limits to: {} outbound, {} inbound", max_outbound, max_inbound);
+        config.app_settings.max_outbound_peers = max_outbound;
+        config.app_settings.max_inbound_peers = max_inbound;
+    }
+
+    if let Some(min_version) = request.min_peer_protocol_version {
+        if let Err(e) = crate::peer_policy::validate_min_protocol_version(min_version) {
+            error!("Invalid minimum peer protocol version: {}", e);
+            return Err(CommandError::new(AppErrorCode::InvalidInput, e));
+        }
+        info!("Updating min_peer_protocol_version to: {}", min_version);
+        config.app_settings.min_peer_protocol_version = min_version;
+    }
+
+    if let Some(bind_addresses) = request.p2p_bind_addresses {
+        if let Err(e) = crate::peer_policy::parse_bind_addresses(&bind_addresses) {
+            error!("Invalid bind addresses: {}", e);
+            return Err(e.into());
+        }
+        info!("Updating p2p_bind_addresses to: {:?}", bind_addresses);
+        config.app_settings.p2p_bind_addresses = bind_addresses;
+    }
+
+    if let Some(max_size_mb) = request.max_mempool_size_mb {
+        if let Err(e) = crate::mempool_service::validate_max_mempool_size_mb(max_size_mb) {
+            error!("Invalid mempool size: {}", e);
+            return Err(e.into());
+        }
+        info!("Updating max_mempool_size_mb to: {}", max_size_mb);
+        config.app_settings.max_mempool_size_mb = max_size_mb;
+    }
+
+    if let Some(cache_mb) = request.db_cache_capacity_mb {
+        if let Err(e) = crate::blockchain_database::validate_cache_mb("Database cache", cache_mb) {
+            error!("Invalid database cache size: {}", e);
+            return Err(CommandError::new(AppErrorCode::InvalidInput, e));
+        }
+        info!("Updating db_cache_capacity_mb to: {} (applied when the database next opens)", cache_mb);
+        config.app_settings.db_cache_capacity_mb = cache_mb;
+    }
+
+    if let Some(cache_mb) = request.db_block_cache_mb {
+        if let Err(e) = crate::blockchain_database::validate_cache_mb("Block cache", cache_mb) {
+            error!("Invalid block cache size: {}", e);
+            return Err(CommandError::new(AppErrorCode::InvalidInput, e));
+        }
+        info!("Updating db_block_cache_mb to: {}", cache_mb);
+        config.app_settings.db_block_cache_mb = cache_mb;
+    }
+
+    if let Some(enabled) = request.db_low_memory_mode {
+        info!("Updating db_low_memory_mode to: {}", enabled);
+        config.app_settings.db_low_memory_mode = enabled;
+    }
+
+    let relay_policy_changed = request.min_relay_fee_rate.is_some()
+        || request.max_standard_tx_size.is_some()
+        || request.dust_threshold.is_some()
+        || request.relay_non_standard_scripts.is_some()
+        || request.blocks_only.is_some();
+    if let Some(fee_rate) = request.min_relay_fee_rate {
+        config.app_settings.min_relay_fee_rate = fee_rate;
+    }
+    if let Some(size) = request.max_standard_tx_size {
+        config.app_settings.max_standard_tx_size = size;
+    }
+    if let Some(threshold) = request.dust_threshold {
+        config.app_settings.dust_threshold = threshold;
+    }
+    if let Some(relay) = request.relay_non_standard_scripts {
+        config.app_settings.relay_non_standard_scripts = relay;
+    }
+    if let Some(blocks_only) = request.blocks_only {
+        config.app_settings.blocks_only = blocks_only;
+    }
+    if relay_policy_changed {
+        if let Err(e) = crate::relay_policy::validate_relay_policy(&config.app_settings) {
+            error!("Invalid relay policy: {}", e);
+            return Err(CommandError::new(AppErrorCode::InvalidInput, e));
+        }
+        info!("Updating relay policy to: {:?}", RelayPolicy::from_settings(&config.app_settings));
+    }
+
+    if let Some(checkpoints) = request.checkpoints_enabled {
+        info!("Updating checkpoints_enabled to: {}", checkpoints);
+        config.app_settings.checkpoints_enabled = checkpoints;
+    }
+
+    let locale = match &request.language {
+        Some(language) => match language.parse::<crate::i18n::Locale>() {
+            Ok(locale) => {
+                info!("Updating language to: {}", locale);
+                config.app_settings.language = locale.code().to_string();
+                Some(locale)
+            }
+            Err(e) => {
+                error!("Invalid language: {}", e);
+                return Err(CommandError::new(AppErrorCode::InvalidInput, e));
+            }
+        },
+        None => None,
+    };
+
+    if let Some(port) = request.push_api_port {
+        if let Err(e) = crate::push_api::validate_port(port) {
+            error!("Invalid push API port: {}", e);
+            return Err(CommandError::new(AppErrorCode::InvalidInput, e));
+        }
+        info!("Updating push_api_port to: {}", port);
+        config.app_settings.push_api_port = port;
+    }
+
+    if let Some(enabled) = request.push_api_enabled {
+        info!("Updating push_api_enabled to: {}", enabled);
+        config.app_settings.push_api_enabled = enabled;
+        if enabled && config.app_settings.push_api_token.is_empty() {
+            config.app_settings.push_api_token = crate::push_api::generate_token();
+        }
+    }
+
+    if let Some(port) = request.metrics_port {
+        if let Err(e) = crate::metrics::validate_port(port) {
+            error!("Invalid metrics port: {}", e);
+            return Err(CommandError::new(AppErrorCode::InvalidInput, e));
+        }
+        info!("Updating metrics_port to: {}", port);
+        config.app_settings.metrics_port = port;
+    }
+
+    if let Some(enabled) = request.metrics_enabled {
+        info!("Updating metrics_enabled to: {}", enabled);
+        config.app_settings.metrics_enabled = enabled;
+    }
+
+    if let Some(enabled) = request.compact_filter_sync {
+        info!("Updating compact_filter_sync to: {}", enabled);
+        config.app_settings.compact_filter_sync = enabled;
+    }
+
+    if let Some(enabled) = request.address_index_enabled {
+        info!("Updating address_index_enabled to: {}", enabled);
+        config.app_settings.address_index_enabled = enabled;
+    }
+
+    if let Some(enabled) = request.price_feed_enabled {
+        if enabled && config.app_settings.price_feed_url.is_none() {
+            error!("Cannot enable price feed without a source URL");
+            return Err("Set a price feed URL before enabling the price feed".to_string().into());
+        }
+        info!("Updating price_feed_enabled to: {}", enabled);
+        config.app_settings.price_feed_enabled = enabled;
+    }
+
+    if let Some(min_score) = request.min_password_score {
+        if let Err(e) = crate::password_strength::validate_min_score(min_score) {
+            error!("Invalid minimum password score: {}", e);
+            return Err(CommandError::new(AppErrorCode::InvalidInput, e));
+        }
+        info!("Updating min_password_score to: {}", min_score);
+        config.app_settings.min_password_score = min_score;
+    }
+
+    if let Some(mine_on_battery) = request.mine_on_battery {
+        info!("Updating mine_on_battery to: {}", mine_on_battery);
+        config.app_settings.mine_on_battery = mine_on_battery;
+    }
+
+    if let Some(port) = request.stratum_port {
+        if let Err(e) = crate::stratum::validate_port(port) {
+            error!("Invalid stratum port: {}", e);
+            return Err(CommandError::new(AppErrorCode::InvalidInput, e));
+        }
+        info!("Updating stratum_port to: {}", port);
+        config.app_settings.stratum_port = port;
+    }
+
+    if let Some(address) = request.stratum_payout_address.as_deref() {
+        if address.trim().is_empty() {
+            info!("Clearing stratum_payout_address");
+            config.app_settings.stratum_payout_address = None;
+        } else {
+            let address = crate::validation::validate_address(address, ChainParams::active().network)?;
+            info!("Updating stratum_payout_address to: {}", address);
+            config.app_settings.stratum_payout_address = Some(address);
+        }
+    }
+
+    if let Some(enabled) = request.stratum_enabled {
+        info!("Updating stratum_enabled to: {}", enabled);
+        config.app_settings.stratum_enabled = enabled;
+    }
+    if config.app_settings.stratum_enabled && config.app_settings.stratum_payout_address.is_none() {
+        error!("Cannot enable the stratum server without a payout address");
+        return Err(CommandError::new(AppErrorCode::InvalidInput, "Set a payout address before enabling the stratum server"));
+    }
+
     // Save the updated config using the inner ConfigManager
     match config_manager
         .update_app_settings(config.app_settings.clone())
@@ -413,20 +780,142 @@ pub async fn update_app_settings(
     {
         Ok(_) => {
             info!("Settings updated successfully - final developer_mode: {}", config.app_settings.developer_mode);
+            if let Some(max_size_mb) = request.max_mempool_size_mb {
+                apply_mempool_size_cap(&app_handle, max_size_mb).await;
+            }
+            if relay_policy_changed {
+                apply_relay_policy(&app_handle, RelayPolicy::from_settings(&config.app_settings)).await;
+            }
+            if let Some(locale) = locale {
+                crate::i18n::set_locale(locale);
+                crate::tray::refresh_language(&app_handle);
+            }
+            if request.minimize_to_system_tray.is_some() {
+                tray::apply_setting(&app_handle, config.app_settings.minimize_to_system_tray);
+            }
+            if request.push_api_enabled.is_some() || request.push_api_port.is_some() {
+                logging::log_audit_event(
+                    "push_api_settings_changed",
+                    &format!("enabled={} port={}", config.app_settings.push_api_enabled, config.app_settings.push_api_port),
+                );
+                apply_push_api_settings(&app_handle, &config.app_settings);
+            }
+            if request.metrics_enabled.is_some() || request.metrics_port.is_some() {
+                if let Some(metrics) = app_handle.try_state::<crate::metrics::MetricsServer>() {
+                    metrics.apply_settings(&app_handle, &config.app_settings);
+                }
+            }
+            if request.stratum_enabled.is_some() || request.stratum_port.is_some() || request.stratum_payout_address.is_some() {
+                logging::log_audit_event(
+                    "stratum_settings_changed",
+                    &format!("enabled={} port={}", config.app_settings.stratum_enabled, config.app_settings.stratum_port),
+                );
+                if let Some(stratum) = app_handle.try_state::<crate::stratum::StratumServer>() {
+                    stratum.apply_settings(&app_handle, &config.app_settings);
+                }
+            }
+            if let Some(cache_mb) = request.db_block_cache_mb {
+                if let Some(blockchain_db) =
+                    crate::services::get::<crate::blockchain_database::AsyncBlockchainDatabase>(&app_handle)
+                {
+                    blockchain_db.set_block_cache_capacity(crate::blockchain_database::mb_to_bytes(cache_mb)).await;
+                }
+            }
+            if let Some(enabled) = request.address_index_enabled {
+                if let Some(blockchain_db) =
+                    crate::services::get::<crate::blockchain_database::AsyncBlockchainDatabase>(&app_handle)
+                {
+                    apply_address_index_setting(&app_handle, &blockchain_db, enabled).await;
+                }
+            }
+            event_bus::publish(&app_handle, BackendEvent::SettingsChanged(Arc::new(config.app_settings)));
             Ok(true)
         }
         Err(e) => {
             error!("Failed to update settings: {}", e);
-            Err(format_error(e))
+            Err(e.into())
         }
     }
 }
 
+/// Restart the push API with new settings
+fn apply_push_api_settings(app_handle: &tauri::AppHandle, settings: &AppSettings) {
+    if let Some(push_api) = app_handle.try_state::<crate::push_api::PushApiServer>() {
+        push_api.apply_settings(settings);
+    }
+}
+
+/// Command to replace the push API token, disconnecting clients that used the old one
+#[command]
+#[command_metrics::tracked]
+pub async fn regenerate_push_api_token(
+    config_manager: State<'_, Arc<ConfigManager>>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<String> {
+    info!("Command: regenerate_push_api_token");
+
+    let mut settings = config_manager.get_config().app_settings;
+    settings.push_api_token = crate::push_api::generate_token();
+    config_manager.update_app_settings(settings.clone()).await?;
+    logging::log_audit_event("push_api_token_regenerated", "");
+    apply_push_api_settings(&app_handle, &settings);
+    Ok(settings.push_api_token)
+}
+
+/// Command to estimate the strength of a password, e.g. while a wallet is being created
+#[command]
+#[command_metrics::tracked]
+pub async fn evaluate_password_strength(
+    password: String,
+    wallet_name: Option<String>,
+    config_manager: State<'_, Arc<ConfigManager>>,
+) -> CommandResult<PasswordStrength> {
+    let min_score = config_manager.get_config().app_settings.min_password_score;
+    let user_inputs: Vec<&str> = wallet_name.as_deref().into_iter().collect();
+    Ok(password_strength::evaluate(&password, &user_inputs, min_score))
+}
+
+/// Reject a wallet password below the configured minimum strength
+fn check_password_strength(config_manager: &ConfigManager, wallet_name: &str, password: &str) -> CommandResult<()> {
+    let min_score = config_manager.get_config().app_settings.min_password_score;
+    password_strength::require_minimum(password, &[wallet_name], min_score).map_err(|e| {
+        warn!("Rejected weak password for wallet {}", wallet_name);
+        CommandError::new(AppErrorCode::WeakPassword, e)
+    })
+}
+
+/// Whether opening a wallet file failed because the password does not decrypt it
+fn is_wrong_password(e: &WalletError) -> bool {
+    matches!(e, WalletError::InvalidPassword | WalletError::AccessDenied(_))
+}
+
+/// Apply a new mempool size cap to the running mempools
+async fn apply_mempool_size_cap(app_handle: &tauri::AppHandle, max_size_mb: u32) {
+    if let Some(state) = app_handle.try_state::<crate::AppState>() {
+        state.mempool_service.set_max_size_mb(max_size_mb).await;
+    }
+    if let Some(mempool_service) = crate::services::get::<AsyncMempoolService>(app_handle) {
+        mempool_service.set_max_size_mb(max_size_mb).await;
+    }
+}
+
+/// Apply a new relay policy to the mempools of the app state and the running services
+async fn apply_relay_policy(app_handle: &tauri::AppHandle, policy: RelayPolicy) {
+    if let Some(state) = app_handle.try_state::<crate::AppState>() {
+        state.mempool_service.set_policy(policy.clone()).await;
+    }
+    if let Some(mempool_service) = crate::services::get::<AsyncMempoolService>(app_handle) {
+        mempool_service.set_policy(policy).await;
+    }
+}
+
 /// Command to get current application settings
 #[command]
+#[command_metrics::tracked]
 pub async fn get_app_settings(
     config_manager_arc: State<'_, Arc<ConfigManager>>, // Change type to State<'_, Arc<ConfigManager>>
-) -> CommandResult<AppSettings> {    debug!("Command: get_app_settings");
+) -> CommandResult<AppSettings> {
+    debug!("Command: get_app_settings");
 
     // Access the inner ConfigManager through the Arc
     let config = config_manager_arc.inner().get_config();
@@ -434,33 +923,113 @@ pub async fn get_app_settings(
     Ok(config.app_settings.clone())
 }
 
-/// Command to open a wallet
+/// Command to export application settings to a file so they can be moved to another machine.
+/// Machine-specific paths are left out unless `include_machine_paths` is set.
 #[command]
-pub async fn open_wallet(
-    wallet_name: String,
-    password: Option<String>,
-    wallet_manager: State<'_, AsyncWalletManager>,
-    security_manager: State<'_, AsyncSecurityManager>,
-    wallet_sync: State<'_, AsyncWalletSyncService>,
+#[command_metrics::tracked]
+pub async fn export_settings(
+    path: String,
+    include_machine_paths: Option<bool>,
+    config_manager_arc: State<'_, Arc<ConfigManager>>,
 ) -> CommandResult<bool> {
-    info!("Command: open_wallet for wallet: {}", wallet_name);
+    info!("Command: export_settings to {}", path);
 
-    // First, determine if the wallet exists and if it's secured
-    let is_wallet_secured = {
-        let manager = wallet_manager.get_manager().await;
-        match manager.find_wallet_by_name(&wallet_name) {
-            Some(info) => {
-                debug!(
-                    "Found wallet info for '{}', secured: {}",
-                    wallet_name, info.secured
-                );
-                info.secured
-            }
-            None => {
-                error!("Wallet '{}' not found", wallet_name);
-                return Err(format!("Wallet '{}' not found", wallet_name));
-            }
-        }
+    let settings = config_manager_arc.inner().get_config().app_settings;
+    let profile = settings_profile::export_profile(&settings, include_machine_paths.unwrap_or(false))
+        .map_err(format_error)?;
+    let contents = serde_json::to_string_pretty(&profile).map_err(format_error)?;
+
+    atomic_file::write_atomic(std::path::Path::new(&path), contents.as_bytes()).map_err(|e| {
+        error!("Failed to write settings export {}: {}", path, e);
+        format!("Failed to write settings file: {}", e)
+    })?;
+
+    info!("Settings exported to {}", path);
+    Ok(true)
+}
+
+/// Result of a settings import
+#[derive(Debug, Serialize)]
+pub struct SettingsImportResult {
+    /// Whether the changes were saved; false for a dry run
+    pub applied: bool,
+    /// Settings that differ from the current ones
+    pub changes: Vec<SettingChange>,
+}
+
+/// Command to import application settings from a file written by export_settings.
+/// With `dry_run` the file is only validated and the would-be changes are returned.
+#[command]
+#[command_metrics::tracked]
+pub async fn import_settings(
+    path: String,
+    dry_run: Option<bool>,
+    include_machine_paths: Option<bool>,
+    config_manager_arc: State<'_, Arc<ConfigManager>>,
+) -> CommandResult<SettingsImportResult> {
+    let dry_run = dry_run.unwrap_or(false);
+    info!("Command: import_settings from {} (dry run: {})", path, dry_run);
+
+    let contents = tokio::fs::read_to_string(&path).await.map_err(|e| {
+        error!("Failed to read settings file {}: {}", path, e);
+        format!("Failed to read settings file: {}", e)
+    })?;
+
+    let config_manager = config_manager_arc.inner();
+    let current = config_manager.get_config().app_settings;
+    let (incoming, changes) =
+        settings_profile::apply_profile(&current, &contents, include_machine_paths.unwrap_or(false))
+            .map_err(|e| {
+                error!("Rejected settings file {}: {}", path, e);
+                format_error(e)
+            })?;
+
+    if dry_run || changes.is_empty() {
+        info!("Settings import would change {} setting(s)", changes.len());
+        return Ok(SettingsImportResult { applied: false, changes });
+    }
+
+    config_manager
+        .update_app_settings(incoming)
+        .await
+        .map_err(|e| {
+            error!("Failed to save imported settings: {}", e);
+            format_error(e)
+        })?;
+
+    info!("Imported {} setting(s) from {}", changes.len(), path);
+    Ok(SettingsImportResult { applied: true, changes })
+}
+
+/// Command to open a wallet
+#[command]
+#[command_metrics::tracked]
+pub async fn open_wallet(
+    wallet_name: String,
+    password: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    security_manager: State<'_, AsyncSecurityManager>,
+    services: State<'_, ServiceContainer>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<bool> {
+    info!("Command: open_wallet for wallet: {}", wallet_name);
+
+    // First, determine if the wallet exists and if it's secured
+    let is_wallet_secured = {
+        let manager = wallet_manager.read_manager().await;
+        match manager.find_wallet_by_name(&wallet_name) {
+            Some(info) => {
+                debug!(
+                    "Found wallet info for '{}', secured: {}",
+                    wallet_name, info.secured
+                );
+                info.secured
+            }
+            None => {
+                error!("Wallet '{}' not found", wallet_name);
+                return Err(WalletError::NotFound(wallet_name.clone()).into());
+            }
+        }
     }; // Release the mutex lock here
 
     // Handle secured vs unsecured wallets separately
@@ -470,7 +1039,7 @@ pub async fn open_wallet(
             Some(pwd) if !pwd.is_empty() => pwd,
             _ => {
                 error!("Password is required for secured wallet '{}'", wallet_name);
-                return Err("Password is required for this secured wallet".to_string());
+                return Err(CommandError::new(AppErrorCode::PasswordRequired, "Password is required for this secured wallet"));
             }
         };
 
@@ -482,68 +1051,109 @@ pub async fn open_wallet(
                     "Authentication succeeded for secured wallet: {}",
                     wallet_name
                 );
+                logging::log_audit_event("wallet_unlocked", &format!("wallet={}", wallet_name));
                 drop(sec_manager); // Explicitly release security manager lock                // Now open the wallet with the validated password
                 let mut manager = wallet_manager.get_manager().await;
-                match manager.open_wallet(&wallet_name, Some(&password)) {
+                match manager.open_wallet(&wallet_name, Some(&password)).await {
                     Ok(_) => {
                         info!("Successfully opened secured wallet: {}", wallet_name);
-                        
-                        // Automatically start wallet synchronization
-                        if let Some(wallet) = manager.get_current_wallet() {
+                        security_manager.get_manager().await.clear_failed_attempts(&wallet_name);
+                    
+                        // Automatically start wallet synchronization unless the wallet opted out
+                        let auto_sync = manager.get_current_wallet()
+                            .map(|wallet| WalletSettings::load(&wallet.path).map(|s| s.auto_sync_on_open).unwrap_or(true))
+                            .unwrap_or(false);
+                        if !auto_sync {
+                            info!("Automatic sync disabled for wallet: {}", wallet_name);
+                        } else if let Some(wallet) = manager.get_current_wallet() {
                             let addresses: Vec<String> = wallet.data.addresses.iter()
                                 .map(|addr| addr.address.clone())
                                 .collect();
-                            
+                        
                             if !addresses.is_empty() {
                                 info!("Starting automatic sync for wallet: {} with {} addresses", wallet_name, addresses.len());
-                                if let Err(e) = wallet_sync.start_wallet_sync(wallet_name.clone(), addresses).await {
-                                    warn!("Failed to start automatic wallet sync: {}", e);
+                                match services.require::<AsyncWalletSyncService>() {
+                                    Ok(wallet_sync) => {
+                                        if let Err(e) = wallet_sync.start_wallet_sync(wallet_name.clone(), addresses).await {
+                                            warn!("Failed to start automatic wallet sync: {}", e);
+                                        }
+                                    }
+                                    Err(e) => warn!("Failed to start automatic wallet sync: {}", e.message),
                                 }
                             } else {
                                 info!("No addresses found in wallet: {}, skipping sync", wallet_name);
                             }
                         }
-                        
+
+                        // The shared activity log belongs to the main profile
+                        if let Some(wallet) = manager.get_current_wallet().filter(|wallet| wallet.data.profile == WalletProfile::Main) {
+                            wallet_activity::record(&wallet.path, ActivityEntry::now(ActivityKind::WalletOpened));
+                        }
+                        tray::set_wallet_status(&app_handle, Some(wallet_name.clone()));
+                        event_bus::publish(&app_handle, BackendEvent::WalletOpened { wallet_name: wallet_name.clone() });
                         Ok(true)
                     }
                     Err(e) => {
                         error!("Failed to open secured wallet: {}", e);
-                        Err(format_error(e))
+                        drop(manager);
+                        // A password the wallet file rejects counts towards the unlock backoff
+                        if is_wrong_password(&e) {
+                            let mut sec_manager = security_manager.get_manager().await;
+                            sec_manager.record_failed_attempt(&wallet_name);
+                            sec_manager.lock_wallet(&wallet_name);
+                        }
+                        Err(e.into())
                     }
                 }
             }
             Err(e) => {
                 error!("Authentication failed: {}", e);
-                Err(format_error(e))
+                logging::log_audit_event("wallet_unlock_failed", &format!("wallet={} reason={}", wallet_name, e));
+                Err(e.into())
             }
         }
     } else {        // For unsecured wallets, just open directly
         let mut manager = wallet_manager.get_manager().await;
-        match manager.open_wallet(&wallet_name, None) {
+        match manager.open_wallet(&wallet_name, None).await {
             Ok(_) => {
                 info!("Successfully opened unsecured wallet: {}", wallet_name);
-                
-                // Automatically start wallet synchronization
-                if let Some(wallet) = manager.get_current_wallet() {
+            
+                // Automatically start wallet synchronization unless the wallet opted out
+                let auto_sync = manager.get_current_wallet()
+                    .map(|wallet| WalletSettings::load(&wallet.path).map(|s| s.auto_sync_on_open).unwrap_or(true))
+                    .unwrap_or(false);
+                if !auto_sync {
+                    info!("Automatic sync disabled for wallet: {}", wallet_name);
+                } else if let Some(wallet) = manager.get_current_wallet() {
                     let addresses: Vec<String> = wallet.data.addresses.iter()
                         .map(|addr| addr.address.clone())
                         .collect();
-                    
+                
                     if !addresses.is_empty() {
                         info!("Starting automatic sync for wallet: {} with {} addresses", wallet_name, addresses.len());
-                        if let Err(e) = wallet_sync.start_wallet_sync(wallet_name.clone(), addresses).await {
-                            warn!("Failed to start automatic wallet sync: {}", e);
+                        match services.require::<AsyncWalletSyncService>() {
+                            Ok(wallet_sync) => {
+                                if let Err(e) = wallet_sync.start_wallet_sync(wallet_name.clone(), addresses).await {
+                                    warn!("Failed to start automatic wallet sync: {}", e);
+                                }
+                            }
+                            Err(e) => warn!("Failed to start automatic wallet sync: {}", e.message),
                         }
                     } else {
                         info!("No addresses found in wallet: {}, skipping sync", wallet_name);
                     }
                 }
-                
+
+                if let Some(wallet) = manager.get_current_wallet() {
+                    wallet_activity::record(&wallet.path, ActivityEntry::now(ActivityKind::WalletOpened));
+                }
+                tray::set_wallet_status(&app_handle, Some(wallet_name.clone()));
+                event_bus::publish(&app_handle, BackendEvent::WalletOpened { wallet_name: wallet_name.clone() });
                 Ok(true)
             }
             Err(e) => {
                 error!("Failed to open unsecured wallet: {}", e);
-                Err(format_error(e))
+                Err(e.into())
             }
         }
     }
@@ -551,6 +1161,7 @@ pub async fn open_wallet(
 
 /// Command to initiate application shutdown
 #[command]
+#[command_metrics::tracked]
 pub async fn shutdown_application(app: tauri::AppHandle) -> CommandResult<bool> {
     info!("Command: shutdown_application received");
 
@@ -578,7 +1189,7 @@ pub async fn shutdown_application(app: tauri::AppHandle) -> CommandResult<bool>
 
         // Send shutdown complete event to frontend
         if let Some(main_window) = app_handle.get_webview_window("main") {
-            let _ = main_window.emit("app-shutdown-complete", ());
+            let _ = main_window.emit_event(events::APP_SHUTDOWN_COMPLETE, &());
 
             // Give the frontend a moment to react
             tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
@@ -594,14 +1205,18 @@ pub async fn shutdown_application(app: tauri::AppHandle) -> CommandResult<bool>
 
 /// Command to secure an existing wallet with a password
 #[command]
+#[command_metrics::tracked]
 pub async fn secure_wallet(
     wallet_name: String,
     password: String,
     wallet_manager: State<'_, AsyncWalletManager>,
     security_manager: State<'_, AsyncSecurityManager>,
+    config_manager: State<'_, Arc<ConfigManager>>,
 ) -> CommandResult<bool> {
     info!("Command: secure_wallet for wallet: {}", wallet_name);
 
+    check_password_strength(&config_manager, &wallet_name, &password)?;
+
     // Store the password in the security manager first
     {
         let mut sec_manager = security_manager.get_manager().await;
@@ -610,45 +1225,233 @@ pub async fn secure_wallet(
 
     // Then secure the wallet
     let mut manager = wallet_manager.get_manager().await;
-    match manager.secure_wallet(&wallet_name, &password) {
+    match manager.secure_wallet(&wallet_name, &password).await {
         Ok(_) => {
             info!("Successfully secured wallet: {}", wallet_name);
+            logging::log_audit_event("wallet_password_set", &format!("wallet={}", wallet_name));
+            if let Some(wallet_dir) = manager.get_wallet_dir(&wallet_name) {
+                wallet_activity::record(&wallet_dir, ActivityEntry::now(ActivityKind::PasswordChanged));
+            }
             Ok(true)
         }
         Err(e) => {
             error!("Failed to secure wallet: {}", e);
-            Err(format_error(e))
+            Err(e.into())
+        }
+    }
+}
+
+/// Command to give a secured wallet a duress password. The duress password opens a decoy
+/// profile, a separate wallet from `decoy_seed_phrase` meant to hold limited funds. Not written
+/// to the audit log, which would give the decoy away.
+// Not tracked by command_metrics either: a call count for this command would show a decoy exists
+#[command]
+pub async fn set_duress_password(
+    wallet_name: String,
+    password: String,
+    duress_password: String,
+    decoy_seed_phrase: String,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    security_manager: State<'_, AsyncSecurityManager>,
+) -> CommandResult<bool> {
+    let decoy_seed_phrase = zeroize::Zeroizing::new(decoy_seed_phrase.trim().to_lowercase());
+    {
+        let mut manager = wallet_manager.get_manager().await;
+        manager.set_duress_profile(&wallet_name, &password, &duress_password, &decoy_seed_phrase)?;
+    }
+
+    let mut sec_manager = security_manager.get_manager().await;
+    sec_manager.store_duress_password(&wallet_name, Some(&duress_password));
+    Ok(true)
+}
+
+/// Command to remove the duress password and decoy profile of a wallet
+// Not tracked by command_metrics, which would show a decoy existed
+#[command]
+pub async fn remove_duress_password(
+    wallet_name: String,
+    password: String,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    security_manager: State<'_, AsyncSecurityManager>,
+) -> CommandResult<bool> {
+    {
+        let mut manager = wallet_manager.get_manager().await;
+        manager.remove_duress_profile(&wallet_name, &password)?;
+    }
+
+    let mut sec_manager = security_manager.get_manager().await;
+    sec_manager.store_duress_password(&wallet_name, None);
+    Ok(true)
+}
+
+/// Command to lock a wallet, ending its unlock session.
+/// Defaults to the currently open wallet when no name is given.
+#[command]
+#[command_metrics::tracked]
+pub async fn lock_wallet(
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    security_manager: State<'_, AsyncSecurityManager>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<bool> {
+    let wallet_name = match wallet_name {
+        Some(name) => name,
+        None => {
+            let manager = wallet_manager.read_manager().await;
+            match manager.get_current_wallet() {
+                Some(wallet) => wallet.name.clone(),
+                None => return Err(WalletError::NoWalletOpen.into()),
+            }
+        }
+    };
+
+    info!("Command: lock_wallet for wallet: {}", wallet_name);
+
+    let was_unlocked = {
+        let mut sec_manager = security_manager.get_manager().await;
+        sec_manager.lock_wallet(&wallet_name)
+    };
+
+    if was_unlocked {
+        let payload = crate::security::WalletSessionEvent {
+            wallet_name: wallet_name.clone(),
+            reason: "locked".to_string(),
+        };
+        if let Err(e) = app_handle.emit_event(events::WALLET_SESSION_EXPIRED, &payload) {
+            warn!("Failed to emit wallet-session-expired event: {}", e);
         }
     }
+
+    Ok(was_unlocked)
+}
+
+/// Command to set the auto-lock timeout for a wallet, or the default when no wallet is given
+#[command]
+#[command_metrics::tracked]
+pub async fn set_auto_lock_timeout(
+    wallet_name: Option<String>,
+    timeout_seconds: u64,
+    security_manager: State<'_, AsyncSecurityManager>,
+) -> CommandResult<()> {
+    info!("Command: set_auto_lock_timeout for {:?} to {} seconds", wallet_name, timeout_seconds);
+
+    if timeout_seconds == 0 {
+        return Err("Auto-lock timeout must be greater than zero".to_string().into());
+    }
+
+    let mut sec_manager = security_manager.get_manager().await;
+    sec_manager.set_session_timeout(wallet_name.as_deref(), timeout_seconds);
+    logging::log_audit_event(
+        "auto_lock_timeout_changed",
+        &format!("wallet={} seconds={}", wallet_name.as_deref().unwrap_or("default"), timeout_seconds),
+    );
+    Ok(())
+}
+
+/// Command to set the auth timeout: how long wallets stay unlocked without use, and how long the
+/// app may go without a command before every wallet locks. Applies to wallets already unlocked
+/// and is saved to the settings.
+#[command]
+#[command_metrics::tracked]
+pub async fn set_auth_timeout(
+    timeout_minutes: u64,
+    security_manager: State<'_, AsyncSecurityManager>,
+    config_manager_arc: State<'_, Arc<ConfigManager>>,
+) -> CommandResult<()> {
+    info!("Command: set_auth_timeout to {} minutes", timeout_minutes);
+
+    if !(1..=crate::security::MAX_AUTH_TIMEOUT_MINUTES).contains(&timeout_minutes) {
+        return Err(CommandError::new(
+            AppErrorCode::InvalidInput,
+            format!("Auth timeout must be between 1 and {} minutes", crate::security::MAX_AUTH_TIMEOUT_MINUTES),
+        ));
+    }
+
+    let config_manager = config_manager_arc.inner();
+    let mut settings = config_manager.get_config().app_settings;
+    settings.auth_timeout_minutes = timeout_minutes;
+    config_manager.update_app_settings(settings).await?;
+
+    security_manager.get_manager().await.set_session_timeout(None, timeout_minutes * 60);
+    logging::log_audit_event("auth_timeout_changed", &format!("minutes={}", timeout_minutes));
+    Ok(())
+}
+
+/// Command to get the unlock session status of a wallet
+#[command]
+#[command_metrics::tracked]
+pub async fn get_wallet_session(
+    wallet_name: String,
+    security_manager: State<'_, AsyncSecurityManager>,
+) -> CommandResult<crate::security::WalletSessionInfo> {
+    debug!("Command: get_wallet_session for wallet: {}", wallet_name);
+
+    let sec_manager = security_manager.get_manager().await;
+    Ok(sec_manager.get_session_info(&wallet_name))
+}
+
+/// Audit log records, newest first, with the outcome of checking the hash chain
+#[derive(Debug, Serialize)]
+pub struct AuditLogView {
+    pub records: Vec<crate::audit_log::AuditRecord>,
+    pub verification: crate::audit_log::AuditVerification,
+}
+
+/// Command to read the security audit log and verify that it has not been tampered with
+#[command]
+#[command_metrics::tracked]
+pub async fn get_audit_log(limit: Option<usize>) -> CommandResult<AuditLogView> {
+    info!("Command: get_audit_log");
+
+    let path = PathProvider::data_dir()
+        .map(|dir| dir.join(crate::audit_log::AUDIT_LOG_FILE))
+        .ok_or_else(|| "Failed to determine app data directory".to_string())?;
+    let (records, verification) = crate::audit_log::read(&path)?;
+    if !verification.valid {
+        warn!("Audit log failed verification: {:?}", verification.error);
+    }
+
+    Ok(AuditLogView {
+        records: records.into_iter().rev().take(limit.unwrap_or(usize::MAX)).collect(),
+        verification,
+    })
 }
 
-/// Command to recover a wallet using a seed phrase
+/// Command to recover a wallet using a seed phrase and optional BIP39 passphrase
 #[command]
+#[command_metrics::tracked]
 pub async fn recover_wallet(
     wallet_name: String,
-    _seed_phrase: String,
+    seed_phrase: String,
     password: String,
     use_password: bool,
+    passphrase: Option<String>,
     wallet_manager: State<'_, AsyncWalletManager>,
+    config_manager: State<'_, Arc<ConfigManager>>,
 ) -> CommandResult<bool> {
     info!("Command: recover_wallet with name: {}", wallet_name);
     debug!("Recovering wallet using seed phrase");
-
-    // TODO: In the future, implement proper recovery from seed phrase
-    // For now, we'll reuse the create_wallet logic as a placeholder
+    use std::str::FromStr;
 
     // If password protection is disabled, use empty password
     let effective_password = if use_password {
+        check_password_strength(&config_manager, &wallet_name, &password)?;
         password
     } else {
         String::new()
     };
 
+    let seed_phrase = zeroize::Zeroizing::new(seed_phrase.trim().to_lowercase());
+    Mnemonic::from_str(&seed_phrase)
+        .map_err(|e| format!("Invalid seed phrase: {}", e))?;
+
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+
     let mut manager = wallet_manager.get_manager().await;
-    match manager.create_wallet(&wallet_name, &effective_password) {
+    match manager.create_wallet_with_seed(&wallet_name, &effective_password, &seed_phrase, passphrase.as_deref(), use_password).await {
         Ok(_) => {
             info!("Successfully recovered wallet: {}", wallet_name);
-            // Now open the newly created wallet
+            // Make sure the recovered wallet is the open one
             match manager.open_wallet(
                 &wallet_name,
                 if use_password {
@@ -656,143 +1459,392 @@ pub async fn recover_wallet(
                 } else {
                     None
                 },
-            ) {
+            ).await {
                 Ok(_) => {
                     info!("Successfully opened recovered wallet: {}", wallet_name);
                     Ok(true)
                 }
                 Err(e) => {
                     error!("Recovered wallet but failed to open it: {}", e);
-                    Err(format_error(e))
+                    Err(e.into())
                 }
             }
         }
         Err(e) => {
             error!("Failed to recover wallet: {}", e);
-            Err(format_error(e))
+            Err(e.into())
         }
     }
 }
 
-/// Command to get the application version
+/// Command to start a seed backup check by picking word positions (1-based) the user must re-enter
 #[command]
-pub fn get_app_version() -> CommandResult<String> {
-    debug!("Command: get_app_version");
-    // Get version from the Cargo.toml via environment variable
-    let version = crate::APP_VERSION;
-    debug!("App version: {}", version);
-    Ok(version.to_string())
-}
+#[command_metrics::tracked]
+pub async fn get_seed_verification_challenge(
+    wallet_name: String,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<Vec<usize>> {
+    info!("Command: get_seed_verification_challenge for wallet: {}", wallet_name);
 
-/// Command to generate a new 12-word BIP-39 seed phrase using cryptographically secure methods
-#[command]
-pub async fn generate_seed_phrase() -> CommandResult<String> {
-    debug!("Command: generate_seed_phrase using BIP39 standard");
-      // Generate entropy for 128-bit security (12 words)
-    let mut entropy = [0u8; 16];
-    rand::rng().fill(&mut entropy);
-    
-    // Create mnemonic from entropy using BIP39 standard
-    let mnemonic = Mnemonic::from_entropy(&entropy)
-        .map_err(|e| format!("Failed to generate BIP39 mnemonic: {}", e))?;
-    
-    let phrase = mnemonic.to_string();
-    
-    // Get first and last words for safe logging (never log the full phrase)
-    let words: Vec<&str> = phrase.split_whitespace().collect();
-    let first_word = words.first().unwrap_or(&"");
-    let last_word = words.last().unwrap_or(&"");
-    
-    debug!("Generated BIP39 seed phrase (first word: {}, last word: {})", first_word, last_word);
-    info!("Successfully generated secure BIP39 mnemonic with {} words", words.len());
-    
-    Ok(phrase)
+    let mut manager = wallet_manager.get_manager().await;
+    manager.create_seed_challenge(&wallet_name).map_err(CommandError::from)
 }
 
-/// Command to open a folder in the system's file explorer
+/// Command to check the words entered for the pending seed challenge.
+/// Answers are keyed by word position; secured wallets need their password to persist the result.
 #[command]
-pub async fn open_folder_in_explorer(path: String) -> CommandResult<bool> {
-    info!("Command: open_folder_in_explorer with path: {}", path);
-    
-    // Create a PathBuf from the path string
-    let path_buf = std::path::PathBuf::from(&path);
-    info!("Converted path to PathBuf: {}", path_buf.display());
-    
-    // Check if the path exists
-    let exists = path_buf.exists();
-    info!("Path exists check: {}", exists);
-    
-    if !exists {
-        error!("Path does not exist: {}", path);
-        return Err(format!("The path '{}' does not exist.", path));
-    }
-    
-    // Log file or directory status
-    let is_file = path_buf.is_file();
-    let is_dir = path_buf.is_dir();
-    info!("Path is file: {}, Path is directory: {}", is_file, is_dir);
-    
-    // Determine if this is a file or directory
-    let target_path = if is_file {
-        // If it's a file, we want to open its parent directory
-        match path_buf.parent() {
-            Some(parent) => {
-                info!("Path is a file, opening parent directory: {}", parent.display());
-                parent.to_path_buf()
-            },
-            None => {
-                error!("Could not determine parent directory for: {}", path);
-                return Err("Could not determine the directory to open.".to_string());
-            }
+#[command_metrics::tracked]
+pub async fn verify_seed_challenge(
+    answers: std::collections::HashMap<usize, String>,
+    password: Option<String>,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<bool> {
+    info!("Command: verify_seed_challenge");
+
+    let mut manager = wallet_manager.get_manager().await;
+    let verified = manager
+        .verify_seed_challenge(wallet_name.as_deref(), &answers, password.as_deref())
+        .map_err(CommandError::from)?;
+    if verified {
+        if let Some(wallet) = manager.get_wallet(wallet_name.as_deref()) {
+            wallet_activity::record(&wallet.path, ActivityEntry::now(ActivityKind::BackupVerified));
         }
-    } else {
-        // It's a directory, use it directly
-        info!("Path is a directory, using directly");
-        path_buf
-    };
-    
-    // Try to get canonical path
-    info!("About to open path: {}", target_path.display());
-    let canonical_result = target_path.canonicalize();
-    
-    if let Ok(canonical_path) = &canonical_result {
-        info!("Canonical path: {}", canonical_path.display());
-    } else if let Err(e) = &canonical_result {
-        warn!("Failed to canonicalize path: {}", e);
     }
-    
-    // Use the canonical path if available, otherwise use the target path
-    let final_path = canonical_result.unwrap_or_else(|_| target_path);
-    
-    // Open the directory with the system file explorer
-    info!("Using opener to open path: {}", final_path.display());
-    match opener::open(&final_path) {
-        Ok(_) => {
-            info!("Successfully opened directory: {}", final_path.display());
-            Ok(true)
-        },
-        Err(e) => {
-            error!("Failed to open directory: {}", e);
-            Err(format!("Failed to open directory: {}", e))
-        }
+    Ok(verified)
+}
+
+/// Command to check whether the current wallet's seed backup has been verified
+#[command]
+#[command_metrics::tracked]
+pub async fn is_seed_backup_verified(
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<bool> {
+    debug!("Command: is_seed_backup_verified");
+
+    let manager = wallet_manager.read_manager().await;
+    match manager.get_wallet(wallet_name.as_deref()) {
+        Some(wallet) => Ok(wallet.data.backup_verified),
+        None => Err(WalletError::NoWalletOpen.into()),
     }
 }
 
-/// Command to open a folder using platform-specific shell commands
-/// This is a fallback method if the opener crate fails
+/// Command to check whether a BIP39 passphrase matches the one used for the current wallet
 #[command]
+#[command_metrics::tracked]
+pub async fn check_wallet_passphrase(
+    passphrase: String,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<bool> {
+    info!("Command: check_wallet_passphrase");
+
+    let manager = wallet_manager.read_manager().await;
+    let current_wallet = match manager.get_wallet(wallet_name.as_deref()) {
+        Some(wallet) => wallet,
+        None => {
+            error!("No wallet is currently open");
+            return Err(WalletError::NoWalletOpen.into());
+        }
+    };
+
+    let expected = match &current_wallet.data.master_fingerprint {
+        Some(fingerprint) => fingerprint.clone(),
+        None => return Err("This wallet has no stored fingerprint to check against".to_string().into()),
+    };
+
+    let seed_phrase = match &current_wallet.data.seed_phrase {
+        Some(seed_phrase) => seed_phrase.clone(),
+        None => return Err("This wallet has no seed phrase".to_string().into()),
+    };
+    drop(manager);
+
+    let fingerprint = crate::wallet_manager::WalletManager::master_fingerprint(seed_phrase.expose_secret(), &passphrase)
+        .map_err(format_error)?;
+
+    Ok(fingerprint == expected)
+}
+
+/// Data directory information for the settings page
+#[derive(Debug, Serialize)]
+pub struct DataDirectoryInfo {
+    pub current: Option<String>,
+    pub default: Option<String>,
+    pub is_overridden: bool,
+}
+
+/// Command to get the data directory root in use
+#[command]
+#[command_metrics::tracked]
+pub async fn get_data_directory() -> CommandResult<DataDirectoryInfo> {
+    debug!("Command: get_data_directory");
+
+    Ok(DataDirectoryInfo {
+        current: PathProvider::data_dir().map(|dir| dir.to_string_lossy().to_string()),
+        default: PathProvider::default_data_dir().map(|dir| dir.to_string_lossy().to_string()),
+        is_overridden: PathProvider::is_overridden(),
+    })
+}
+
+/// Command to choose the data directory root used from the next start (None restores the default).
+/// Existing data is not moved.
+#[command]
+#[command_metrics::tracked]
+pub async fn set_data_directory(path: Option<String>) -> CommandResult<bool> {
+    info!("Command: set_data_directory to {:?}", path);
+
+    let path = path.map(std::path::PathBuf::from);
+    if let Some(dir) = &path {
+        if !dir.is_absolute() {
+            return Err("Data directory must be an absolute path".to_string().into());
+        }
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            error!("Failed to create data directory {}: {}", dir.display(), e);
+            return Err(format!("Failed to create data directory: {}", e).into());
+        }
+    }
+
+    PathProvider::set_persisted_data_dir(path.as_deref()).map_err(|e| {
+        error!("Failed to save data directory setting: {}", e);
+        format!("Failed to save data directory setting: {}", e)
+    })?;
+
+    Ok(true)
+}
+
+/// Command to get the settings of a wallet (the active one when no name is given)
+#[command]
+#[command_metrics::tracked]
+pub async fn get_wallet_settings(
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<WalletSettings> {
+    debug!("Command: get_wallet_settings {:?}", wallet_name);
+
+    let manager = wallet_manager.read_manager().await;
+    let wallet_name = match wallet_name {
+        Some(name) => name,
+        None => match manager.get_current_wallet() {
+            Some(wallet) => wallet.name.clone(),
+            None => return Err(WalletError::NoWalletOpen.into()),
+        },
+    };
+
+    let wallet_dir = manager
+        .get_wallet_dir(&wallet_name)
+        .ok_or_else(|| WalletError::NotFound(wallet_name.clone()))?;
+
+    WalletSettings::load(&wallet_dir).map_err(|e| {
+        error!("Failed to load settings for wallet {}: {}", wallet_name, e);
+        CommandError::new(AppErrorCode::ConfigLoadFailed, "Failed to load wallet settings").with_details(e)
+    })
+}
+
+/// Command to update the settings of a wallet (the active one when no name is given).
+/// Lifting or raising a spending limit of a secured wallet needs its `password`.
+#[command]
+#[command_metrics::tracked]
+pub async fn update_wallet_settings(
+    settings: WalletSettings,
+    wallet_name: Option<String>,
+    password: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<bool> {
+    info!("Command: update_wallet_settings {:?}", wallet_name);
+
+    if settings.min_confirmations > MAX_MIN_CONFIRMATIONS {
+        return Err(CommandError::new(
+            AppErrorCode::InvalidInput,
+            format!("Minimum confirmations cannot exceed {}", MAX_MIN_CONFIRMATIONS),
+        ));
+    }
+
+    let manager = wallet_manager.read_manager().await;
+    let wallet_name = match wallet_name {
+        Some(name) => name,
+        None => match manager.get_current_wallet() {
+            Some(wallet) => wallet.name.clone(),
+            None => return Err(WalletError::NoWalletOpen.into()),
+        },
+    };
+
+    // When the wallet is open we can make sure the mining address belongs to it
+    if let (Some(address), Some(wallet)) = (&settings.mining_address, manager.get_wallet(Some(&wallet_name))) {
+        if !wallet.data.receiving_addresses().any(|a| &a.address == address) {
+            return Err(format!("Address '{}' does not belong to wallet '{}'", address, wallet_name).into());
+        }
+    }
+
+    let wallet_dir = manager
+        .get_wallet_dir(&wallet_name)
+        .ok_or_else(|| WalletError::NotFound(wallet_name.clone()))?;
+
+    let secured = manager.find_wallet_by_name(&wallet_name).is_some_and(|info| info.secured);
+    if settings.spending_policy.confirm_password_above.is_some() && !secured {
+        return Err(CommandError::new(
+            AppErrorCode::InvalidOperation,
+            "Secure the wallet with a password before requiring it for large sends",
+        ));
+    }
+    let current = WalletSettings::load(&wallet_dir).unwrap_or_default();
+    if secured && current.spending_policy.loosened_by(&settings.spending_policy) {
+        let password = password.ok_or_else(|| {
+            CommandError::new(AppErrorCode::PasswordRequired, "Relaxing the spending limits requires the wallet password")
+        })?;
+        manager.verify_password(&wallet_name, &password)?;
+    }
+
+    match settings.save(&wallet_dir) {
+        Ok(_) => {
+            info!("Updated settings for wallet: {}", wallet_name);
+            Ok(true)
+        }
+        Err(e) => {
+            error!("Failed to save settings for wallet {}: {}", wallet_name, e);
+            Err(format!("Failed to save wallet settings: {}", e).into())
+        }
+    }
+}
+
+/// Command to get the application version
+#[command]
+pub fn get_app_version() -> CommandResult<String> {
+    debug!("Command: get_app_version");
+    // Get version from the Cargo.toml via environment variable
+    let version = crate::APP_VERSION;
+    debug!("App version: {}", version);
+    Ok(version.to_string())
+}
+
+/// Command to get call counts, latencies and error rates of backend commands since startup
+#[command]
+#[command_metrics::tracked]
+pub async fn get_command_metrics() -> CommandResult<Vec<command_metrics::CommandStats>> {
+    debug!("Command: get_command_metrics");
+    Ok(command_metrics::snapshot())
+}
+
+/// Command to generate a new BIP-39 seed phrase using cryptographically secure methods.
+/// Supports 12, 15, 18, 21 or 24 words (defaults to 12).
+#[command]
+#[command_metrics::tracked]
+pub async fn generate_seed_phrase(word_count: Option<u32>) -> CommandResult<String> {
+    let word_count = word_count.unwrap_or(12);
+    debug!("Command: generate_seed_phrase using BIP39 standard ({} words)", word_count);
+
+    // Every 3 words encode 32 bits of entropy (plus checksum)
+    let entropy_len = match word_count {
+        12 | 15 | 18 | 21 | 24 => (word_count as usize / 3) * 4,
+        _ => {
+            return Err(format!(
+                "Unsupported word count {}. Use 12, 15, 18, 21 or 24",
+                word_count
+            ).into());
+        }
+    };
+
+    let mut entropy = zeroize::Zeroizing::new(vec![0u8; entropy_len]);
+    rand::rng().fill(entropy.as_mut_slice());
+
+    // Create mnemonic from entropy using BIP39 standard
+    let mnemonic = Mnemonic::from_entropy(&entropy)
+        .map_err(|e| format!("Failed to generate BIP39 mnemonic: {}", e))?;
+
+    let phrase = mnemonic.to_string();
+    let words = phrase.split_whitespace().count();
+
+    info!("Successfully generated secure BIP39 mnemonic with {} words", words);
+
+    Ok(phrase)
+}
+
+/// Command to open a folder in the system's file explorer
+#[command]
+#[command_metrics::tracked]
+pub async fn open_folder_in_explorer(path: String) -> CommandResult<bool> {
+    info!("Command: open_folder_in_explorer with path: {}", path);
+
+    // Create a PathBuf from the path string
+    let path_buf = std::path::PathBuf::from(&path);
+    info!("Converted path to PathBuf: {}", path_buf.display());
+
+    // Check if the path exists
+    let exists = path_buf.exists();
+    info!("Path exists check: {}", exists);
+
+    if !exists {
+        error!("Path does not exist: {}", path);
+        return Err(format!("The path '{}' does not exist.", path).into());
+    }
+
+    // Log file or directory status
+    let is_file = path_buf.is_file();
+    let is_dir = path_buf.is_dir();
+    info!("Path is file: {}, Path is directory: {}", is_file, is_dir);
+
+    // Determine if this is a file or directory
+    let target_path = if is_file {
+        // If it's a file, we want to open its parent directory
+        match path_buf.parent() {
+            Some(parent) => {
+                info!("Path is a file, opening parent directory: {}", parent.display());
+                parent.to_path_buf()
+            },
+            None => {
+                error!("Could not determine parent directory for: {}", path);
+                return Err("Could not determine the directory to open.".to_string().into());
+            }
+        }
+    } else {
+        // It's a directory, use it directly
+        info!("Path is a directory, using directly");
+        path_buf
+    };
+
+    // Try to get canonical path
+    info!("About to open path: {}", target_path.display());
+    let canonical_result = target_path.canonicalize();
+
+    if let Ok(canonical_path) = &canonical_result {
+        info!("Canonical path: {}", canonical_path.display());
+    } else if let Err(e) = &canonical_result {
+        warn!("Failed to canonicalize path: {}", e);
+    }
+
+    // Use the canonical path if available, otherwise use the target path
+    let final_path = canonical_result.unwrap_or_else(|_| target_path);
+
+    // Open the directory with the system file explorer
+    info!("Using opener to open path: {}", final_path.display());
+    match opener::open(&final_path) {
+        Ok(_) => {
+            info!("Successfully opened directory: {}", final_path.display());
+            Ok(true)
+        },
+        Err(e) => {
+            error!("Failed to open directory: {}", e);
+            Err(format!("Failed to open directory: {}", e).into())
+        }
+    }
+}
+
+/// Command to open a folder using platform-specific shell commands
+/// This is a fallback method if the opener crate fails
+#[command]
+#[command_metrics::tracked]
 pub async fn open_folder_with_shell_command(path: String) -> CommandResult<bool> {
     info!("Command: open_folder_with_shell_command with path: {}", path);
-    
+
     // Create a PathBuf from the path string
     let path_buf = std::path::PathBuf::from(&path);
-    
+
     // Check if the path exists
     if !path_buf.exists() {
         error!("Path does not exist: {}", path);
-        return Err(format!("The path '{}' does not exist.", path));
+        return Err(format!("The path '{}' does not exist.", path).into());
     }
-    
+
     // Determine if this is a file or directory
     let target_path = if path_buf.is_file() {
         // If it's a file, we want to open its parent directory
@@ -803,17 +1855,17 @@ pub async fn open_folder_with_shell_command(path: String) -> CommandResult<bool>
             },
             None => {
                 error!("Could not determine parent directory for: {}", path);
-                return Err("Could not determine the directory to open.".to_string());
+                return Err("Could not determine the directory to open.".to_string().into());
             }
         }
     } else {
         // It's a directory, use it directly
         path_buf
     };
-    
+
     // Log the final path we're trying to open
     info!("Attempting to open directory with shell command: {}", target_path.display());
-    
+
     // Use platform-specific commands to open the folder
     let result = if cfg!(target_os = "windows") {
         // On Windows, use explorer.exe
@@ -821,10 +1873,10 @@ pub async fn open_folder_with_shell_command(path: String) -> CommandResult<bool>
             Some(s) => s.to_string(),
             None => {
                 error!("Failed to convert path to string");
-                return Err("Failed to convert path to string".to_string());
+                return Err("Failed to convert path to string".to_string().into());
             }
         };
-        
+    
         // Use explorer.exe to open the folder
         match std::process::Command::new("explorer")
             .arg(&path_str)
@@ -870,16 +1922,17 @@ pub async fn open_folder_with_shell_command(path: String) -> CommandResult<bool>
         error!("Unsupported operating system for shell command folder opening");
         false
     };
-    
+
     if result {
         Ok(true)
     } else {
-        Err("Failed to open folder with shell command".to_string())
+        Err("Failed to open folder with shell command".to_string().into())
     }
 }
 
 /// Command to delete a wallet by name
 #[command]
+#[command_metrics::tracked]
 pub async fn delete_wallet(
     wallet_name: String,
     wallet_manager_state: State<'_, AsyncWalletManager>, // Changed param name for clarity in thought process, will use original if needed
@@ -890,9 +1943,9 @@ pub async fn delete_wallet(
     // --- Step 1: Close the wallet if it's the one being deleted and is open ---
     { // Scope for first WalletManager lock
         let mut manager = wallet_manager_state.get_manager().await;
-        if manager.get_current_wallet().map_or(false, |w| w.name == wallet_name) {
+        if manager.is_wallet_open(&wallet_name) {
             info!("Wallet '{}' is currently open. Closing it before deletion.", wallet_name);
-            manager.close_wallet(); // Assumes WalletManager::close_wallet() returns ()
+            manager.close_wallet_by_name(&wallet_name);
             info!("Successfully closed wallet '{}'.", wallet_name);
         }
         // WalletManager lock (manager) is released here
@@ -906,25 +1959,20 @@ pub async fn delete_wallet(
             Some(info) => info.path.clone(), // This is String, assumed relative path
             None => {
                 error!("Wallet '{}' not found in configuration.", wallet_name);
-                return Err(format!("Wallet '{}' not found in configuration", wallet_name));
+                return Err(format!("Wallet '{}' not found in configuration", wallet_name).into());
             }
         }
     };
 
-    // --- Step 3: Get WalletManager's base directory for wallets to construct full path ---
-    let full_wallet_path_to_delete = { // Scope for another WalletManager lock (read-only part)
-        let manager = wallet_manager_state.get_manager().await;
-        let wallets_base_dir = manager.get_wallets_dir(); // Returns PathBuf
-        wallets_base_dir.join(&relative_wallet_path) // Join to get full PathBuf
-        // WalletManager lock (manager) is released here
-    };      // --- Step 4: Remove wallet entry from configuration using WalletManager's method ---
+    // --- Step 3: Resolve the wallet directory under the data directory ---
+    let full_wallet_path_to_delete = PathProvider::resolve_wallet_path(&relative_wallet_path);      // --- Step 4: Remove wallet entry from configuration using WalletManager's method ---
     // This was the original location of this logic in the old delete_wallet.
     { // Scope for WalletManager lock (modifying config part)
         let mut manager = wallet_manager_state.get_manager().await;
         if let Err(e) = manager.remove_wallet_from_config(&wallet_name).await {
             error!("Failed to remove wallet '{}' from config: {}", wallet_name, e);
             // If this fails, we haven't deleted files yet, which is safer.
-            return Err(format!("Failed to remove wallet from config: {}", e));
+            return Err(format!("Failed to remove wallet from config: {}", e).into());
         }
         // WalletManager lock (manager) is released here
     }
@@ -939,40 +1987,125 @@ pub async fn delete_wallet(
                 error!("Failed to delete wallet directory {}: {}", full_wallet_path_to_delete.display(), e);
                 // CRITICAL: Wallet is removed from config, but files still exist.
                 // This is an inconsistent state. This error should be handled carefully by the user.
-                return Err(format!("Wallet config removed, but failed to delete wallet files: {}. Manual cleanup may be required at {}", e, full_wallet_path_to_delete.display()));
+                return Err(format!("Wallet config removed, but failed to delete wallet files: {}. Manual cleanup may be required at {}", e, full_wallet_path_to_delete.display()).into());
             }
         }
     } else {
         // If directory doesn't exist, but config removal was successful, log as warning.
         warn!("Wallet directory {} does not exist, skipping deletion. Wallet was already removed from config.", full_wallet_path_to_delete.display());
     }
-    
+
     info!("Successfully deleted wallet '{}'", wallet_name);
     Ok(true)
 }
 
+/// Event payload emitted after a wallet has been renamed
+#[derive(Debug, Clone, Serialize)]
+pub struct WalletRenamedEvent {
+    pub old_name: String,
+    pub new_name: String,
+}
+
+/// Command to rename a wallet, keeping config, files, sessions, sync and mining in step
+#[command]
+#[command_metrics::tracked]
+pub async fn rename_wallet(
+    old_name: String,
+    new_name: String,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    security_manager: State<'_, AsyncSecurityManager>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<bool> {
+    info!("Command: rename_wallet from '{}' to '{}'", old_name, new_name);
+    let new_name = new_name.trim().to_string();
+
+    // Stop background work registered under the old name before the files move
+    let previous_mining = match crate::services::get::<AsyncMiningService>(&app_handle) {
+        Some(mining_service) => mining_service.remove_wallet(&old_name).await,
+        None => None,
+    };
+    if let Some(wallet_sync) = crate::services::get::<AsyncWalletSyncService>(&app_handle) {
+        wallet_sync.remove_wallet(&old_name).await;
+    }
+
+    let (rename_result, sync_addresses, auto_sync) = {
+        let mut manager = wallet_manager.get_manager().await;
+        let rename_result = manager.rename_wallet(&old_name, &new_name).await;
+        let wallet_name = if rename_result.is_ok() { &new_name } else { &old_name };
+
+        let (sync_addresses, auto_sync) = match manager.get_wallet(Some(wallet_name)) {
+            Some(wallet) => (
+                wallet.data.addresses.iter().map(|a| a.address.clone()).collect::<Vec<_>>(),
+                WalletSettings::load(&wallet.path).map(|s| s.auto_sync_on_open).unwrap_or(true),
+            ),
+            None => (Vec::new(), false),
+        };
+        (rename_result, sync_addresses, auto_sync)
+    };
+
+    if rename_result.is_ok() {
+        let mut sec_manager = security_manager.get_manager().await;
+        sec_manager.rename_wallet(&old_name, &new_name);
+        drop(sec_manager);
+        if let Some(scheduler) = app_handle.try_state::<AsyncPaymentScheduler>() {
+            scheduler.rename_wallet(&old_name, &new_name).await;
+        }
+    }
+
+    // Resume background work under whichever name the wallet now has
+    let wallet_name = if rename_result.is_ok() { new_name.clone() } else { old_name.clone() };
+    if let Some(status) = previous_mining.filter(|status| status.is_mining) {
+        if let Some(mining_service) = crate::services::get::<AsyncMiningService>(&app_handle) {
+            if let Err(e) = mining_service.start_mining(wallet_name.clone(), status.mining_address).await {
+                warn!("Failed to restart mining for wallet {}: {}", wallet_name, e);
+            }
+        }
+    }
+    if auto_sync && !sync_addresses.is_empty() {
+        if let Some(wallet_sync) = crate::services::get::<AsyncWalletSyncService>(&app_handle) {
+            if let Err(e) = wallet_sync.start_wallet_sync(wallet_name.clone(), sync_addresses).await {
+                warn!("Failed to restart sync for wallet {}: {}", wallet_name, e);
+            }
+        }
+    }
+
+    if let Err(e) = rename_result {
+        error!("Failed to rename wallet: {}", e);
+        return Err(e.into());
+    }
+
+    if let Err(e) = app_handle.emit_event(events::WALLET_RENAMED, &WalletRenamedEvent { old_name, new_name }) {
+        warn!("Failed to emit wallet-renamed event: {}", e);
+    }
+
+    tray::set_wallet_status(&app_handle, wallet_manager.current_wallet_name());
+
+    Ok(true)
+}
+
 /// Command to get a fully qualified wallet path
 #[command]
+#[command_metrics::tracked]
 pub async fn get_fully_qualified_wallet_path(
     relative_path: String,
     wallet_manager: State<'_, AsyncWalletManager>,
 ) -> CommandResult<String> {
     debug!("Command: get_fully_qualified_wallet_path for path '{}'", relative_path);
-    
-    let manager = wallet_manager.get_manager().await;
-    
+
+    let manager = wallet_manager.read_manager().await;
+
     // Get the base wallets directory
     let wallets_dir = manager.get_wallets_dir();
     debug!("Base wallets directory: {}", wallets_dir.display());
-    
+
     // Join the relative path with the base directory
     let full_path = wallets_dir.join(relative_path);
     debug!("Fully qualified path: {}", full_path.display());
-    
+
     // Convert to string for return
     match full_path.to_str() {
         Some(path_str) => Ok(path_str.to_string()),
-        None => Err("Failed to convert path to string".to_string())
+        None => Err("Failed to convert path to string".to_string().into())
     }
 }
 
@@ -986,24 +2119,25 @@ pub fn greet(name: String) -> String {
 /// Command to clean up orphaned wallet directories
 /// Deletes all wallet files/folders in the wallets directory that are not present in the app configuration
 #[command]
+#[command_metrics::tracked]
 pub async fn cleanup_orphaned_wallets(
     wallet_manager: State<'_, AsyncWalletManager>,
     config_manager: State<'_, Arc<ConfigManager>>,
 ) -> CommandResult<Vec<String>> {
     info!("Command: cleanup_orphaned_wallets - Starting cleanup process");
-    
-    let manager = wallet_manager.get_manager().await;
+
+    let manager = wallet_manager.read_manager().await;
     let config = config_manager.get_config();
       // Get the base wallets directory
     let wallets_dir = manager.get_wallets_dir();
     info!("Scanning wallets directory: {}", wallets_dir.display());
-    
+
     // Ensure the wallets directory exists
     if !wallets_dir.exists() {
         info!("Wallets directory does not exist, nothing to clean up");
         return Ok(vec![]);
     }
-    
+
     // Get list of wallet names from config
     let configured_wallets: std::collections::HashSet<String> = config
         .wallets
@@ -1011,9 +2145,9 @@ pub async fn cleanup_orphaned_wallets(
         .map(|w| w.name.clone())
         .collect();
       debug!("Configured wallets: {:?}", configured_wallets);
-    
+
     let mut deleted_items = Vec::new();
-    
+
     // Read the wallets directory
     match std::fs::read_dir(&wallets_dir) {
         Ok(entries) => {
@@ -1025,15 +2159,15 @@ pub async fn cleanup_orphaned_wallets(
                             Some(name) => name.to_string_lossy().to_string(),
                             None => continue,
                         };
-                        
+                    
                         // Skip if this is a configured wallet
                         if configured_wallets.contains(&file_name) {
                             continue;
                         }
-                        
+                    
                         // This is an orphaned wallet directory/file
                         info!("Found orphaned wallet item: {}", file_name);
-                        
+                    
                         // Attempt to delete it
                         if path.is_dir() {
                             match std::fs::remove_dir_all(&path) {
@@ -1043,7 +2177,7 @@ pub async fn cleanup_orphaned_wallets(
                                 }
                                 Err(e) => {
                                     error!("Failed to delete orphaned wallet directory {}: {}", file_name, e);
-                                    return Err(format!("Failed to delete directory {}: {}", file_name, e));
+                                    return Err(format!("Failed to delete directory {}: {}", file_name, e).into());
                                 }
                             }
                         } else {
@@ -1054,72 +2188,69 @@ pub async fn cleanup_orphaned_wallets(
                                 }
                                 Err(e) => {
                                     error!("Failed to delete orphaned wallet file {}: {}", file_name, e);
-                                    return Err(format!("Failed to delete file {}: {}", file_name, e));
+                                    return Err(format!("Failed to delete file {}: {}", file_name, e).into());
                                 }
                             }
                         }
                     }
                     Err(e) => {
                         error!("Error reading directory entry: {}", e);
-                        return Err(format!("Error reading directory entry: {}", e));
+                        return Err(format!("Error reading directory entry: {}", e).into());
                     }
                 }
             }
         }
         Err(e) => {
             error!("Failed to read wallets directory: {}", e);
-            return Err(format!("Failed to read wallets directory: {}", e));
+            return Err(format!("Failed to read wallets directory: {}", e).into());
         }
     }
-    
+
     if deleted_items.is_empty() {
         info!("No orphaned wallet items found to clean up");
     } else {
         info!("Cleaned up {} orphaned wallet items", deleted_items.len());
     }
-    
+
     Ok(deleted_items)
 }
 
 /// Command to delete all wallets from both config and disk
 /// Deletes all wallets listed in the config file and removes all wallet directories from the wallets folder
 #[command]
+#[command_metrics::tracked]
 pub async fn delete_all_wallets(
     wallet_manager: State<'_, AsyncWalletManager>,
     config_manager: State<'_, Arc<ConfigManager>>,
     app: tauri::AppHandle,
-) -> CommandResult<Vec<String>> {    info!("Command: delete_all_wallets - Starting deletion process");
+) -> CommandResult<Vec<String>> {
+    info!("Command: delete_all_wallets - Starting deletion process");
     debug!("Command: delete_all_wallets");
       // Close any currently open wallet first - do this separately to avoid deadlock
     {
-        let manager = wallet_manager.get_manager().await;
-        if manager.get_current_wallet().is_some() {
-            info!("Closing currently open wallet before deletion");
+        let manager = wallet_manager.read_manager().await;
+        if !manager.open_wallet_names().is_empty() {
+            info!("Closing all open wallets before deletion");
             drop(manager); // Release the lock explicitly
             let mut manager_mut = wallet_manager.get_manager().await;
-            manager_mut.close_wallet();
+            manager_mut.close_all_wallets();
         }
     } // Ensure the manager lock is dropped here
-    
+
     // Now get a fresh lock for the deletion operations
-    let manager = wallet_manager.get_manager().await;
+    let manager = wallet_manager.read_manager().await;
     let config = config_manager.get_config();
-    
+
     let mut deleted_items = Vec::new();
       // Step 1: Delete wallets from their configured paths
     for wallet_info in &config.wallets {
         info!("Processing wallet from config: {}", wallet_info.name);
-        
+    
         // Get the full path to the wallet
-        let wallet_path = if std::path::Path::new(&wallet_info.path).is_absolute() {
-            std::path::PathBuf::from(&wallet_info.path)
-        } else {
-            // If relative path, join with the wallets directory
-            manager.get_wallets_dir().join(&wallet_info.path)
-        };
-        
+        let wallet_path = PathProvider::resolve_wallet_path(&wallet_info.path);
+    
         debug!("Attempting to delete wallet at path: {}", wallet_path.display());
-        
+    
         if wallet_path.exists() {
             if wallet_path.is_dir() {
                 match std::fs::remove_dir_all(&wallet_path) {
@@ -1129,7 +2260,7 @@ pub async fn delete_all_wallets(
                     }
                     Err(e) => {
                         error!("Failed to delete wallet directory {}: {}", wallet_info.name, e);
-                        return Err(format!("Failed to delete wallet directory {}: {}", wallet_info.name, e));
+                        return Err(format!("Failed to delete wallet directory {}: {}", wallet_info.name, e).into());
                     }
                 }
             } else if wallet_path.is_file() {
@@ -1140,7 +2271,7 @@ pub async fn delete_all_wallets(
                     }
                     Err(e) => {
                         error!("Failed to delete wallet file {}: {}", wallet_info.name, e);
-                        return Err(format!("Failed to delete wallet file {}: {}", wallet_info.name, e));
+                        return Err(format!("Failed to delete wallet file {}: {}", wallet_info.name, e).into());
                     }
                 }
             }        } else {
@@ -1151,7 +2282,7 @@ pub async fn delete_all_wallets(
       // Step 2: Delete any remaining items in the wallets directory
     let wallets_dir = manager.get_wallets_dir();
     info!("Cleaning up remaining items in wallets directory: {}", wallets_dir.display());
-    
+
     if wallets_dir.exists() {
         match std::fs::read_dir(&wallets_dir) {
             Ok(entries) => {
@@ -1163,9 +2294,9 @@ pub async fn delete_all_wallets(
                                 Some(name) => name.to_string_lossy().to_string(),
                                 None => continue,
                             };
-                            
+                        
                             debug!("Found remaining item in wallets directory: {}", file_name);
-                            
+                        
                             if path.is_dir() {
                                 match std::fs::remove_dir_all(&path) {
                                     Ok(()) => {
@@ -1174,7 +2305,7 @@ pub async fn delete_all_wallets(
                                     }
                                     Err(e) => {
                                         error!("Failed to delete remaining directory {}: {}", file_name, e);
-                                        return Err(format!("Failed to delete remaining directory {}: {}", file_name, e));
+                                        return Err(format!("Failed to delete remaining directory {}: {}", file_name, e).into());
                                     }
                                 }
                             } else {
@@ -1185,21 +2316,21 @@ pub async fn delete_all_wallets(
                                     }
                                     Err(e) => {
                                         error!("Failed to delete remaining file {}: {}", file_name, e);
-                                        return Err(format!("Failed to delete remaining file {}: {}", file_name, e));
+                                        return Err(format!("Failed to delete remaining file {}: {}", file_name, e).into());
                                     }
                                 }
                             }
                         }
                         Err(e) => {
                             error!("Error reading directory entry: {}", e);
-                            return Err(format!("Error reading directory entry: {}", e));
+                            return Err(format!("Error reading directory entry: {}", e).into());
                         }
                     }
                 }
             }
             Err(e) => {
                 error!("Failed to read wallets directory: {}", e);
-                return Err(format!("Failed to read wallets directory: {}", e));
+                return Err(format!("Failed to read wallets directory: {}", e).into());
             }
         }
     }
@@ -1207,28 +2338,30 @@ pub async fn delete_all_wallets(
     info!("Clearing wallets from config file");
     let mut new_config = config.clone();
     new_config.wallets.clear();
-    
+
     match config_manager.update_config(new_config).await {
         Ok(()) => {
             info!("Successfully cleared wallets from config file");
             deleted_items.push("Config file: Cleared all wallet entries".to_string());
+            drop(manager);
+            wallet_manager.get_manager().await.reload_config();
         }
         Err(e) => {
             error!("Failed to clear wallets from config: {}", e);
-            return Err(format!("Failed to clear wallets from config: {}", e));
+            return Err(format!("Failed to clear wallets from config: {}", e).into());
         }
     }
       if deleted_items.is_empty() {
         info!("No wallets found to delete");
     } else {
         info!("Successfully deleted {} wallet items", deleted_items.len());
-        
+    
         // Emit an event to notify frontend that all wallets have been deleted
         if let Some(main_window) = app.get_webview_window("main") {
-            let _ = main_window.emit("wallets-deleted", ());
+            let _ = main_window.emit_event(events::WALLETS_DELETED, &());
         }
     }
-    
+
     Ok(deleted_items)
 }
 
@@ -1236,10 +2369,21 @@ pub async fn delete_all_wallets(
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CurrentWalletInfo {
     pub name: String,
-    pub addresses: Vec<AddressDetails>,
+    /// Addresses in the wallet; `get_wallet_addresses` lists them a page at a time
+    pub address_count: usize,
     pub master_public_key: String,
     pub balance: u64,
+    /// Part of the balance with the wallet's minimum confirmations
+    pub spendable_balance: u64,
+    /// Part of the balance still waiting for confirmations
+    pub pending_balance: u64,
+    /// Part of the balance in coins the user froze
+    pub frozen_balance: u64,
+    /// Fiat equivalent of the balance when the price feed is enabled
+    pub balance_fiat: Option<FiatAmount>,
     pub is_secured: bool,
+    /// Set when the wallet was opened read-only; changes are not saved
+    pub read_only: Option<crate::storage_media::ReadOnlyReason>,
 }
 
 /// Detailed address information
@@ -1250,19 +2394,81 @@ pub struct AddressDetails {
     pub derivation_path: String,
     pub address_type: String,
     pub label: Option<String>,
+    /// Left behind when the wallet was re-keyed; shown but not for receiving
+    pub retired: bool,
+}
+
+/// Addresses returned per page by get_wallet_addresses
+const ADDRESS_PAGE_SIZE: usize = 100;
+
+/// One page of a wallet's addresses
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddressPage {
+    pub addresses: Vec<AddressDetails>,
+    /// Zero-based
+    pub page: usize,
+    pub page_size: usize,
+    /// Addresses across all pages
+    pub total: usize,
+}
+
+impl AddressPage {
+    /// Page `page` of the wallet's addresses: receiving addresses before retired ones, Native
+    /// SegWit before other types, then by derivation path
+    fn of(data: &crate::wallet_data::WalletData, page: usize) -> Self {
+        let native_segwit = |info: &crate::wallet_data::AddressInfo| info.key_type == crate::wallet_data::KeyType::NativeSegWit;
+        let mut ordered: Vec<&crate::wallet_data::AddressInfo> = data.addresses.iter().collect();
+        ordered.sort_by(|a, b| {
+            (a.retired, !native_segwit(a), &a.derivation_path).cmp(&(b.retired, !native_segwit(b), &b.derivation_path))
+        });
+
+        let addresses = ordered
+            .into_iter()
+            .skip(page.saturating_mul(ADDRESS_PAGE_SIZE))
+            .take(ADDRESS_PAGE_SIZE)
+            .map(|info| AddressDetails {
+                address: info.address.clone(),
+                // Addresses listed before the public key was kept beside them fall back to the key pair
+                public_key: if info.public_key.is_empty() {
+                    data.keys.get(&info.address).map_or_else(|| "Unknown".to_string(), |key_pair| key_pair.public_key.clone())
+                } else {
+                    info.public_key.clone()
+                },
+                derivation_path: info.derivation_path.clone(),
+                address_type: match info.key_type {
+                    crate::wallet_data::KeyType::Legacy => "Legacy (P2PKH)".to_string(),
+                    crate::wallet_data::KeyType::SegWit => "SegWit (P2SH-P2WPKH)".to_string(),
+                    crate::wallet_data::KeyType::NativeSegWit => "Native SegWit (P2WPKH)".to_string(),
+                    crate::wallet_data::KeyType::Taproot => "Taproot (P2TR)".to_string(),
+                },
+                label: info.label.clone(),
+                retired: info.retired,
+            })
+            .collect();
+
+        AddressPage {
+            addresses,
+            page,
+            page_size: ADDRESS_PAGE_SIZE,
+            total: data.addresses.len(),
+        }
+    }
 }
 
 /// Command to get current wallet information for the Account page
 #[command]
+#[command_metrics::tracked]
 pub async fn get_current_wallet_info(
+    wallet_name: Option<String>,
     wallet_manager: State<'_, AsyncWalletManager>,
+    app_handle: tauri::AppHandle,
 ) -> CommandResult<Option<CurrentWalletInfo>> {
     info!("Command: get_current_wallet_info");
 
-    let manager = wallet_manager.get_manager().await;
+    let manager = wallet_manager.read_manager().await;
 
     // Check if a wallet is currently open
-    let current_wallet = match manager.get_current_wallet() {
+    let current_wallet = match manager.get_wallet(wallet_name.as_deref()) {
         Some(wallet) => wallet,
         None => {
             debug!("No wallet is currently open");
@@ -1273,96 +2479,210 @@ pub async fn get_current_wallet_info(
     let wallet_name = current_wallet.name.clone();
     debug!("Getting wallet info for: {}", wallet_name);
 
-    // Get addresses with detailed information from the addresses list
-    // Use addresses list as primary source to maintain proper ordering
-    let mut addresses = Vec::new();
-    for addr_info in &current_wallet.data.addresses {
-        // Look up corresponding key pair details
-        let key_pair = current_wallet.data.keys.get(&addr_info.address);
-        
-        let (public_key, address_type) = if let Some(kp) = key_pair {
-            let addr_type = match kp.key_type {
-                crate::wallet_data::KeyType::Legacy => "Legacy (P2PKH)".to_string(),
-                crate::wallet_data::KeyType::SegWit => "SegWit (P2SH-P2WPKH)".to_string(),
-                crate::wallet_data::KeyType::NativeSegWit => "Native SegWit (P2WPKH)".to_string(),
-                crate::wallet_data::KeyType::Taproot => "Taproot (P2TR)".to_string(),
-            };
-            (kp.public_key.clone(), addr_type)
-        } else {
-            // Fallback if key pair not found
-            ("Unknown".to_string(), "Unknown".to_string())
-        };
-            
-        addresses.push(AddressDetails {
-            address: addr_info.address.clone(),
-            public_key,
-            derivation_path: addr_info.derivation_path.clone(),
-            address_type,
-            label: addr_info.label.clone(),
-        });
-    }
-
-    // Sort addresses so Native SegWit addresses appear first, then by derivation path
-    addresses.sort_by(|a, b| {
-        let a_is_native_segwit = a.address_type == "Native SegWit (P2WPKH)";
-        let b_is_native_segwit = b.address_type == "Native SegWit (P2WPKH)";
-        
-        match (a_is_native_segwit, b_is_native_segwit) {
-            (true, false) => std::cmp::Ordering::Less,    // Native SegWit comes first
-            (false, true) => std::cmp::Ordering::Greater, // Native SegWit comes first
-            _ => a.derivation_path.cmp(&b.derivation_path), // Same type, sort by derivation path
-        }
-    });
+    let control = CoinControl {
+        min_confirmations: wallet_min_confirmations(&current_wallet.path),
+        frozen: &current_wallet.data.frozen_utxos,
+    };
+    let balance = balance_breakdown(&current_wallet.data.utxos, chain_tip_height(&app_handle).await, &control);
 
     let wallet_info = CurrentWalletInfo {
         name: wallet_name.clone(),
-        addresses,
+        address_count: current_wallet.data.addresses.len(),
         master_public_key: current_wallet.data.master_public_key.clone(),
         balance: current_wallet.data.balance,
-        is_secured: manager.is_current_wallet_secured().unwrap_or(false),
+        spendable_balance: balance.spendable,
+        pending_balance: balance.pending,
+        frozen_balance: balance.frozen,
+        balance_fiat: fiat_equivalent(&app_handle, current_wallet.data.balance).await,
+        is_secured: manager.find_wallet_by_name(&wallet_name).map_or(false, |info| info.secured),
+        read_only: current_wallet.data.read_only,
     };
 
     info!("Successfully retrieved wallet info for: {}", wallet_name);
     Ok(Some(wallet_info))
 }
 
-/// Command to get the private key of the currently open wallet
+/// Command to get one page of an open wallet's addresses (the active one when no name is given).
+/// Only the address list is read, so the wallet's key pairs stay unparsed.
+#[command]
+#[command_metrics::tracked]
+pub async fn get_wallet_addresses(
+    wallet_name: Option<String>,
+    page: usize,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<AddressPage> {
+    info!("Command: get_wallet_addresses page {}", page);
+
+    let manager = wallet_manager.read_manager().await;
+    let wallet = manager.get_wallet(wallet_name.as_deref()).ok_or(WalletError::NoWalletOpen)?;
+    Ok(AddressPage::of(&wallet.data, page))
+}
+
+/// Payload emitted when a private key has been revealed to the user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivateKeyAccessEvent {
+    pub wallet_name: String,
+    pub timestamp: i64,
+}
+
+/// Command to get the private key of the currently open wallet.
+/// Secured wallets require the wallet password to be re-entered; watch-only wallets never expose keys.
 #[command]
+#[command_metrics::tracked]
 pub async fn get_wallet_private_key(
+    password: Option<String>,
+    wallet_name: Option<String>,
     wallet_manager: State<'_, AsyncWalletManager>,
+    security_manager: State<'_, AsyncSecurityManager>,
+    app_handle: tauri::AppHandle,
 ) -> CommandResult<String> {
     info!("Command: get_wallet_private_key");
 
-    let manager = wallet_manager.get_manager().await;
+    let (wallet_name, is_secured, is_watch_only) = {
+        let manager = wallet_manager.read_manager().await;
+        let current_wallet = match manager.get_wallet(wallet_name.as_deref()) {
+            Some(wallet) => wallet,
+            None => {
+                error!("No wallet is currently open");
+                return Err(WalletError::NoWalletOpen.into());
+            }
+        };
+
+        (
+            current_wallet.name.clone(),
+            manager.find_wallet_by_name(&current_wallet.name).map_or(false, |info| info.secured),
+            current_wallet.data.is_watch_only(),
+        )
+    };
 
-    // Check if a wallet is currently open
-    let current_wallet = match manager.get_current_wallet() {
-        Some(wallet) => wallet,
-        None => {
-            error!("No wallet is currently open");
-            return Err("No wallet is currently open".to_string());
+    if is_watch_only {
+        logging::log_audit_event("private_key_export_denied", &format!("wallet={} reason=watch_only", wallet_name));
+        return Err("This wallet does not hold private keys".to_string().into());
+    }
+
+    if is_secured {
+        let password = match password {
+            Some(pwd) if !pwd.is_empty() => pwd,
+            _ => {
+                logging::log_audit_event("private_key_export_denied", &format!("wallet={} reason=missing_password", wallet_name));
+                return Err(CommandError::new(AppErrorCode::PasswordRequired, "Password is required to reveal the private key"));
+            }
+        };
+
+        // Rate-limited check against the security manager first
+        {
+            let mut sec_manager = security_manager.get_manager().await;
+            if let Err(e) = sec_manager.authenticate_wallet(&wallet_name, &password) {
+                logging::log_audit_event("private_key_export_denied", &format!("wallet={} reason=authentication_failed", wallet_name));
+                return Err(e.into());
+            }
+        }
+
+        // Then prove the password opens the profile in use; the duress password also decrypts
+        // the wallet file, but must not reveal the main profile's key
+        let verified = wallet_manager.read_manager().await.verify_password(&wallet_name, &password);
+        let mut sec_manager = security_manager.get_manager().await;
+        if let Err(e) = verified {
+            warn!("Password verification failed for wallet {}: {}", wallet_name, e);
+            if is_wrong_password(&e) {
+                sec_manager.record_failed_attempt(&wallet_name);
+            }
+            logging::log_audit_event("private_key_export_denied", &format!("wallet={} reason=invalid_password", wallet_name));
+            return Err(WalletError::InvalidPassword.into());
         }
-    };    let wallet_name = current_wallet.name.clone();
-    debug!("Getting private key for wallet: {}", wallet_name);
+        sec_manager.clear_failed_attempts(&wallet_name);
+    }
 
-    // Get the private key from the wallet data that's already loaded in memory
-    match &current_wallet.data.master_private_key {
-        Some(private_key) => {
-            info!("Successfully retrieved private key for wallet: {}", wallet_name);
-            Ok(private_key.clone())
+    let manager = wallet_manager.read_manager().await;
+    let current_wallet = match manager.get_wallet(Some(&wallet_name)) {
+        Some(wallet) => wallet,
+        _ => {
+            error!("Wallet {} was closed while revealing the private key", wallet_name);
+            return Err(WalletError::NoWalletOpen.into());
         }
+    };
+
+    let private_key = match &current_wallet.data.master_private_key {
+        Some(private_key) => private_key.expose_secret().to_string(),
         None => {
             error!("No private key found in wallet data for: {}", wallet_name);
-            Err("No private key found in wallet data".to_string())
+            return Err("No private key found in wallet data".to_string().into());
         }
+    };
+    drop(manager);
+
+    logging::log_audit_event("private_key_exported", &format!("wallet={}", wallet_name));
+
+    let payload = PrivateKeyAccessEvent {
+        wallet_name: wallet_name.clone(),
+        timestamp: chrono::Utc::now().timestamp(),
+    };
+    if let Err(e) = app_handle.emit_event(events::PRIVATE_KEY_REVEALED, &payload) {
+        warn!("Failed to emit private-key-revealed event: {}", e);
     }
+
+    info!("Revealed private key for wallet: {}", wallet_name);
+    Ok(private_key)
+}
+
+/// Default number of seconds before sensitive clipboard contents are cleared
+const CLIPBOARD_CLEAR_DEFAULT_SECS: u64 = 30;
+
+/// Command to copy a sensitive value (seed phrase, private key, address) to the clipboard.
+/// The clipboard is cleared after the timeout, but only if it still holds the copied value.
+#[command]
+#[command_metrics::tracked]
+pub async fn copy_sensitive_to_clipboard(
+    value: String,
+    timeout_secs: Option<u64>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<()> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+    use zeroize::Zeroizing;
+
+    let timeout_secs = timeout_secs.unwrap_or(CLIPBOARD_CLEAR_DEFAULT_SECS);
+    info!("Command: copy_sensitive_to_clipboard (clears in {} seconds)", timeout_secs);
+
+    let value = Zeroizing::new(value);
+    app_handle.clipboard().write_text(value.as_str().to_string())
+        .map_err(|e| format!("Failed to copy to clipboard: {}", e))?;
+
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(timeout_secs)).await;
+
+        let current = match app_handle.clipboard().read_text() {
+            Ok(text) => Zeroizing::new(text),
+            Err(e) => {
+                debug!("Could not read clipboard before clearing: {}", e);
+                return;
+            }
+        };
+
+        if current.as_str() != value.as_str() {
+            debug!("Clipboard contents changed since copy, leaving it untouched");
+            return;
+        }
+
+        match app_handle.clipboard().clear() {
+            Ok(_) => {
+                info!("Cleared sensitive data from clipboard");
+                if let Err(e) = app_handle.emit_event(events::CLIPBOARD_CLEARED, &()) {
+                    warn!("Failed to emit clipboard-cleared event: {}", e);
+                }
+            }
+            Err(e) => warn!("Failed to clear clipboard: {}", e),
+        }
+    });
+
+    Ok(())
 }
 
 /// Command to show the main window (used by tray)
 #[command]
+#[command_metrics::tracked]
 pub async fn show_main_window(app_handle: tauri::AppHandle) -> CommandResult<()> {
     debug!("Command: show_main_window");
-    
+
     if let Some(window) = app_handle.get_webview_window("main") {
         window.show().map_err(format_error)?;
         window.set_focus().map_err(format_error)?;
@@ -1370,95 +2690,76 @@ pub async fn show_main_window(app_handle: tauri::AppHandle) -> CommandResult<()>
         Ok(())
     } else {
         error!("Main window not found");
-        Err("Main window not found".to_string())
+        Err("Main window not found".to_string().into())
     }
 }
 
 /// Command to hide the main window (minimize to tray)
 #[command]
+#[command_metrics::tracked]
 pub async fn hide_to_tray(app_handle: tauri::AppHandle) -> CommandResult<()> {
     debug!("Command: hide_to_tray");
-    
+
     if let Some(window) = app_handle.get_webview_window("main") {
         window.hide().map_err(format_error)?;
         info!("Main window hidden to tray");
         Ok(())
     } else {
         error!("Main window not found");
-        Err("Main window not found".to_string())
+        Err("Main window not found".to_string().into())
     }
 }
 
 /// Command to update the system tray menu with current wallet status
 #[command]
+#[command_metrics::tracked]
 pub async fn update_tray_wallet_status(
-    _app_handle: tauri::AppHandle,
+    app_handle: tauri::AppHandle,
     wallet_name: Option<String>,
 ) -> CommandResult<()> {
     debug!("Command: update_tray_wallet_status for wallet: {:?}", wallet_name);
-    
-    // For now, just log the update - actual menu item updating in Tauri 2 is complex
-    let wallet_text = match wallet_name {
-        Some(name) => format!("Wallet: {}", name),
-        None => "No wallet open".to_string(),
-    };
-    
-    info!("Tray wallet status updated to: {}", wallet_text);
-    
-    // TODO: Implement actual menu item text update when Tauri 2 API supports it
-    // Currently, Tauri 2 doesn't have a straightforward way to update menu item text
-    // This is a placeholder for future implementation
-    
+
+    tray::set_wallet_status(&app_handle, wallet_name);
+
     Ok(())
 }
 
 /// Command to update the system tray menu with network status
 #[command]
+#[command_metrics::tracked]
 pub async fn update_tray_network_status(
-    _app_handle: tauri::AppHandle,
+    app_handle: tauri::AppHandle,
     is_connected: bool,
     peer_count: Option<u32>,
 ) -> CommandResult<()> {
     debug!("Command: update_tray_network_status - connected: {}, peers: {:?}", is_connected, peer_count);
-    
-    let status_text = if is_connected {
-        match peer_count {
-            Some(count) => format!("Network: Connected ({} peers)", count),
-            None => "Network: Connected".to_string(),
-        }
-    } else {
-        "Network: Disconnected".to_string()
-    };
-    
-    info!("Tray network status updated to: {}", status_text);
-    
-    // TODO: Implement actual menu item text update when Tauri 2 API supports it
-    // Currently, Tauri 2 doesn't have a straightforward way to update menu item text
-    // This is a placeholder for future implementation
-    
+
+    tray::set_connection_status(&app_handle, is_connected, peer_count);
+
     Ok(())
 }
 
 /// Command to check the synchronization status of the blockchain
 #[command]
-pub async fn check_sync_status(
-    blockchain_sync_service: State<'_, AsyncBlockchainSyncService>,
-) -> CommandResult<NetworkStatus> {    info!("Command: check_sync_status");
+#[command_metrics::tracked]
+pub async fn check_sync_status(app: tauri::AppHandle) -> CommandResult<NodeStatus> {
+    info!("Command: check_sync_status");
 
-    // Get the current network status from the blockchain sync service
-    let status = blockchain_sync_service.get_network_status().await;
+    let status = crate::node_status::collect(&app).await;
 
-    info!("Current network status: {:?}", status);
+    info!("Current node status: {:?}", status);
 
     Ok(status)
 }
 
 /// Command to force synchronization with the blockchain
 #[command]
+#[command_metrics::tracked]
 pub async fn force_sync(
     app: tauri::AppHandle,
-    blockchain_sync_service: State<'_, AsyncBlockchainSyncService>,
+    services: State<'_, ServiceContainer>,
 ) -> CommandResult<bool> {
+    let blockchain_sync_service = services.require::<AsyncBlockchainSyncService>()?;
     info!("Command: force_sync");
 
     // Trigger a manual sync with the blockchain
@@ -1471,22 +2772,22 @@ pub async fn force_sync(
 
 /// Command to get current blockchain network status
 #[command]
-pub async fn get_network_status(
-    app: tauri::AppHandle,
-    blockchain_sync: State<'_, AsyncBlockchainSyncService>,
-) -> CommandResult<NetworkStatus> {
+#[command_metrics::tracked]
+pub async fn get_network_status(app: tauri::AppHandle) -> CommandResult<NodeStatus> {
     info!("Command: get_network_status");
-    let status = blockchain_sync.get_network_status_with_network_height(&app).await;
-    info!("Network status: connected={}, local_height={}, network_height={}, syncing={}, peers={}", 
-           status.is_connected, status.current_height, status.network_height, status.is_syncing, status.peer_count);
+    let status = crate::node_status::collect(&app).await;
+    info!("Node status: peers={}, chain_height={}, header_height={}, network_height={}, sync={:?}", 
+           status.peer_count, status.chain_height, status.header_height, status.network_height, status.sync_state);
     Ok(status)
 }
 
 /// Command to get current block height
 #[command]
+#[command_metrics::tracked]
 pub async fn get_block_height(
-    blockchain_sync: State<'_, AsyncBlockchainSyncService>,
+    services: State<'_, ServiceContainer>,
 ) -> CommandResult<i32> {
+    let blockchain_sync = services.require::<AsyncBlockchainSyncService>()?;
     debug!("Command: get_block_height");
     let height = blockchain_sync.get_block_height().await;
     debug!("Current block height: {}", height);
@@ -1495,9 +2796,11 @@ pub async fn get_block_height(
 
 /// Command to check if blockchain is currently syncing
 #[command]
+#[command_metrics::tracked]
 pub async fn is_blockchain_syncing(
-    blockchain_sync: State<'_, AsyncBlockchainSyncService>,
+    services: State<'_, ServiceContainer>,
 ) -> CommandResult<bool> {
+    let blockchain_sync = services.require::<AsyncBlockchainSyncService>()?;
     debug!("Command: is_blockchain_syncing");
     let syncing = blockchain_sync.is_syncing().await;
     debug!("Blockchain syncing: {}", syncing);
@@ -1506,9 +2809,11 @@ pub async fn is_blockchain_syncing(
 
 /// Command to check network connection status
 #[command]
+#[command_metrics::tracked]
 pub async fn is_network_connected(
-    blockchain_sync: State<'_, AsyncBlockchainSyncService>,
+    services: State<'_, ServiceContainer>,
 ) -> CommandResult<bool> {
+    let blockchain_sync = services.require::<AsyncBlockchainSyncService>()?;
     debug!("Command: is_network_connected");
     let connected = blockchain_sync.is_connected().await;
     debug!("Network connected: {}", connected);
@@ -1517,58 +2822,154 @@ pub async fn is_network_connected(
 
 /// Command to get peer count
 #[command]
+#[command_metrics::tracked]
 pub async fn get_peer_count(
-    blockchain_sync: State<'_, AsyncBlockchainSyncService>,
+    services: State<'_, ServiceContainer>,
 ) -> CommandResult<i32> {
+    let blockchain_sync = services.require::<AsyncBlockchainSyncService>()?;
     debug!("Command: get_peer_count");
     let count = blockchain_sync.get_peer_count().await;
     debug!("Peer count: {}", count);
     Ok(count)
 }
 
+/// Command to get the score breakdown of connected peers, best first
+#[command]
+#[command_metrics::tracked]
+pub async fn get_peer_details(
+    app_handle: tauri::AppHandle,
+) -> CommandResult<Vec<crate::network_service::PeerDetails>> {
+    debug!("Command: get_peer_details");
+    let network_service = crate::services::get::<crate::network_service::AsyncNetworkService>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Network service is not running"))?;
+    Ok(network_service.get_peer_details().await)
+}
+
+/// Command to get the address peers see this node at and whether it has accepted inbound connections
+#[command]
+#[command_metrics::tracked]
+pub async fn get_external_address(
+    app_handle: tauri::AppHandle,
+) -> CommandResult<crate::reachability::ReachabilityStatus> {
+    debug!("Command: get_external_address");
+    let network_service = crate::services::get::<crate::network_service::AsyncNetworkService>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Network service is not running"))?;
+    Ok(network_service.get_reachability().await)
+}
+
+/// Command to compare the local clock with the clocks of peers
+#[command]
+#[command_metrics::tracked]
+pub async fn get_clock_status() -> CommandResult<crate::network_time::ClockSkewStatus> {
+    debug!("Command: get_clock_status");
+    Ok(crate::network_time::status())
+}
+
+/// Coin supply according to the UTXO set, compared with the subsidy schedule
+#[derive(Debug, Serialize)]
+pub struct SupplyInfo {
+    pub height: u64,
+    /// Subsidy of the next block
+    pub block_subsidy: u64,
+    pub halvings: u64,
+    pub next_halving_height: Option<u64>,
+    /// Total subsidy issued up to `height`
+    pub expected_supply: u64,
+    /// Coins held by unspent outputs
+    pub utxo_supply: u64,
+    pub utxo_count: u64,
+    pub max_supply: u64,
+    /// Issued but not in the UTXO set, e.g. fees no coinbase claimed
+    pub unclaimed: u64,
+    /// False if the UTXO set holds more coins than the schedule has issued
+    pub within_schedule: bool,
+}
+
+/// Command to audit the coin supply in the UTXO set against the subsidy schedule
+#[command]
+#[command_metrics::tracked]
+pub async fn get_supply_info(
+    app_handle: tauri::AppHandle,
+) -> CommandResult<SupplyInfo> {
+    info!("Command: get_supply_info");
+    let blockchain_db = crate::services::get::<crate::blockchain_database::AsyncBlockchainDatabase>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Blockchain services are not running"))?;
+
+    let height = blockchain_db.get_block_height().await.map_err(format_error)?;
+    let (utxo_count, utxo_supply) = blockchain_db.get_utxo_set_totals().await.map_err(format_error)?;
+    let expected_supply = crate::consensus::expected_supply(height);
+
+    let within_schedule = utxo_supply <= expected_supply;
+    if !within_schedule {
+        error!(
+            "Supply audit failed: UTXO set holds {} satoshis, schedule allows {} at height {}",
+            utxo_supply, expected_supply, height
+        );
+    }
+
+    Ok(SupplyInfo {
+        height,
+        block_subsidy: crate::consensus::block_subsidy(height + 1),
+        halvings: crate::consensus::halvings_at(height),
+        next_halving_height: crate::consensus::next_halving_height(height),
+        expected_supply,
+        utxo_supply,
+        utxo_count,
+        max_supply: crate::consensus::MAX_SUPPLY,
+        unclaimed: expected_supply.saturating_sub(utxo_supply),
+        within_schedule,
+    })
+}
+
 // ============================================================================
 // Wallet Sync Commands
 // ============================================================================
 
 /// Command to start syncing a wallet
 #[command]
+#[command_metrics::tracked]
 pub async fn start_wallet_sync(
-    wallet_sync: State<'_, AsyncWalletSyncService>,
+    services: State<'_, ServiceContainer>,
     wallet_id: String,
     addresses: Vec<String>,
 ) -> CommandResult<()> {
+    let wallet_sync = services.require::<AsyncWalletSyncService>()?;
     debug!("Command: start_wallet_sync for wallet: {}", wallet_id);
-    
+
     wallet_sync.start_wallet_sync(wallet_id.clone(), addresses).await
         .map_err(format_error)?;
-    
+
     info!("Started wallet sync for: {}", wallet_id);
     Ok(())
 }
 
 /// Command to stop syncing a wallet
 #[command]
+#[command_metrics::tracked]
 pub async fn stop_wallet_sync(
-    wallet_sync: State<'_, AsyncWalletSyncService>,
+    services: State<'_, ServiceContainer>,
     wallet_id: String,
 ) -> CommandResult<()> {
+    let wallet_sync = services.require::<AsyncWalletSyncService>()?;
     debug!("Command: stop_wallet_sync for wallet: {}", wallet_id);
-    
+
     wallet_sync.stop_wallet_sync(&wallet_id).await
         .map_err(format_error)?;
-    
+
     info!("Stopped wallet sync for: {}", wallet_id);
     Ok(())
 }
 
 /// Command to get wallet sync status
 #[command]
+#[command_metrics::tracked]
 pub async fn get_wallet_sync_status(
-    wallet_sync: State<'_, AsyncWalletSyncService>,
+    services: State<'_, ServiceContainer>,
     wallet_id: String,
 ) -> CommandResult<Option<WalletSyncStatus>> {
+    let wallet_sync = services.require::<AsyncWalletSyncService>()?;
     debug!("Command: get_wallet_sync_status for wallet: {}", wallet_id);
-    
+
     let status = wallet_sync.get_wallet_sync_status(&wallet_id).await;
     debug!("Wallet sync status for {}: {:?}", wallet_id, status.is_some());
     Ok(status)
@@ -1576,11 +2977,13 @@ pub async fn get_wallet_sync_status(
 
 /// Command to get all wallet sync statuses
 #[command]
+#[command_metrics::tracked]
 pub async fn get_all_wallet_sync_statuses(
-    wallet_sync: State<'_, AsyncWalletSyncService>,
+    services: State<'_, ServiceContainer>,
 ) -> CommandResult<std::collections::HashMap<String, WalletSyncStatus>> {
+    let wallet_sync = services.require::<AsyncWalletSyncService>()?;
     debug!("Command: get_all_wallet_sync_statuses");
-    
+
     let statuses = wallet_sync.get_all_sync_statuses().await;
     debug!("Retrieved {} wallet sync statuses", statuses.len());
     Ok(statuses)
@@ -1592,43 +2995,95 @@ pub async fn get_all_wallet_sync_statuses(
 
 /// Command to start mining for a wallet
 #[command]
+#[command_metrics::tracked]
 pub async fn start_mining(
-    mining_service: State<'_, AsyncMiningService>,
+    services: State<'_, ServiceContainer>,
+    power_monitor: State<'_, PowerMonitor>,
     wallet_id: String,
     mining_address: String,
+    app_handle: tauri::AppHandle,
 ) -> CommandResult<()> {
+    let mining_service = services.require::<AsyncMiningService>()?;
     debug!("Command: start_mining for wallet: {} at address: {}", wallet_id, mining_address);
-    
+
+    if let Some(reason) = power_monitor.mining_blocked(&app_handle).await {
+        return Err(CommandError::new(AppErrorCode::InvalidOperation, reason.describe()));
+    }
+
     mining_service.start_mining(wallet_id.clone(), mining_address).await
         .map_err(format_error)?;
-    
+
     info!("Started mining for wallet: {}", wallet_id);
     Ok(())
 }
 
 /// Command to stop mining for a wallet
 #[command]
+#[command_metrics::tracked]
 pub async fn stop_mining(
-    mining_service: State<'_, AsyncMiningService>,
+    services: State<'_, ServiceContainer>,
+    power_monitor: State<'_, PowerMonitor>,
     wallet_id: String,
 ) -> CommandResult<()> {
+    let mining_service = services.require::<AsyncMiningService>()?;
     debug!("Command: stop_mining for wallet: {}", wallet_id);
-    
+
+    // Stopped by hand, so it should not resume after a sleep or battery pause either
+    power_monitor.forget_miner(&wallet_id).await;
     mining_service.stop_mining(&wallet_id).await
         .map_err(format_error)?;
-    
+
     info!("Stopped mining for wallet: {}", wallet_id);
     Ok(())
 }
 
+/// Command to set the mining duty cycle and thread priority. Takes effect from the next work
+/// slice of wallets already mining and is saved to the settings.
+#[command]
+#[command_metrics::tracked]
+pub async fn set_mining_intensity(
+    services: State<'_, ServiceContainer>,
+    config_manager_arc: State<'_, Arc<ConfigManager>>,
+    duty_cycle: u8,
+    low_priority: bool,
+) -> CommandResult<MiningIntensity> {
+    let mining_service = services.require::<AsyncMiningService>()?;
+    info!("Command: set_mining_intensity to {}%, low priority: {}", duty_cycle, low_priority);
+
+    let intensity = MiningIntensity { duty_cycle, low_priority };
+    intensity.validate().map_err(|e| CommandError::new(AppErrorCode::InvalidInput, e))?;
+
+    let config_manager = config_manager_arc.inner();
+    let mut settings = config_manager.get_config().app_settings;
+    settings.mining_duty_cycle = duty_cycle;
+    settings.mining_low_priority = low_priority;
+    config_manager.update_app_settings(settings).await?;
+
+    mining_service.set_intensity(intensity).await;
+    Ok(intensity)
+}
+
+/// Command to get the power source and whether mining is paused for sleep or battery power
+#[command]
+#[command_metrics::tracked]
+pub async fn get_power_status(
+    power_monitor: State<'_, PowerMonitor>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<PowerStatus> {
+    debug!("Command: get_power_status");
+    Ok(power_monitor.status(&app_handle).await)
+}
+
 /// Command to get mining status for a wallet
 #[command]
+#[command_metrics::tracked]
 pub async fn get_mining_status(
-    mining_service: State<'_, AsyncMiningService>,
+    services: State<'_, ServiceContainer>,
     wallet_id: String,
 ) -> CommandResult<Option<MiningStatus>> {
+    let mining_service = services.require::<AsyncMiningService>()?;
     debug!("Command: get_mining_status for wallet: {}", wallet_id);
-    
+
     let status = mining_service.get_mining_status(&wallet_id).await;
     debug!("Mining status for {}: {:?}", wallet_id, status.is_some());
     Ok(status)
@@ -1636,152 +3091,273 @@ pub async fn get_mining_status(
 
 /// Command to get all mining statuses
 #[command]
+#[command_metrics::tracked]
 pub async fn get_all_mining_statuses(
-    mining_service: State<'_, AsyncMiningService>,
+    services: State<'_, ServiceContainer>,
 ) -> CommandResult<std::collections::HashMap<String, MiningStatus>> {
+    let mining_service = services.require::<AsyncMiningService>()?;
     debug!("Command: get_all_mining_statuses");
-    
+
     let statuses = mining_service.get_all_mining_statuses().await;
     debug!("Retrieved {} mining statuses", statuses.len());
     Ok(statuses)
 }
 
+/// Command to get the blocks a wallet mined between `from` and `to` (inclusive, Unix timestamps),
+/// with their rewards and whether they were orphaned
+#[command]
+#[command_metrics::tracked]
+pub async fn get_mining_rewards(
+    wallet_manager: State<'_, AsyncWalletManager>,
+    wallet_id: String,
+    from: Option<i64>,
+    to: Option<i64>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<MiningRewards> {
+    debug!("Command: get_mining_rewards for wallet: {} ({:?} to {:?})", wallet_id, from, to);
+    let blockchain_db = crate::services::get::<crate::blockchain_database::AsyncBlockchainDatabase>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Blockchain services are not running"))?;
+
+    let addresses: Vec<String> = {
+        let manager = wallet_manager.read_manager().await;
+        let wallet = manager.get_wallet(Some(&wallet_id)).ok_or(WalletError::NoWalletOpen)?;
+        wallet.data.addresses.iter().map(|a| a.address.clone()).collect()
+    };
+    let blocks = blockchain_db.get_mined_blocks(&addresses, from, to).await.map_err(format_error)?;
+    Ok(MiningRewards::from_blocks(blocks))
+}
+
+/// Command to get the difficulty and solve time of blocks from `from_height` to `to_height`
+/// inclusive, for charts. Defaults to the last 1000 blocks.
+#[command]
+#[command_metrics::tracked]
+pub async fn get_difficulty_history(
+    from_height: Option<u64>,
+    to_height: Option<u64>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<Vec<crate::blockchain_database::BlockStats>> {
+    debug!("Command: get_difficulty_history from {:?} to {:?}", from_height, to_height);
+    let blockchain_db = crate::services::get::<crate::blockchain_database::AsyncBlockchainDatabase>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Blockchain services are not running"))?;
+
+    let to_height = match to_height {
+        Some(height) => height,
+        None => blockchain_db.get_block_height().await.map_err(format_error)?,
+    };
+    let from_height = from_height.unwrap_or_else(|| to_height.saturating_sub(999));
+    if from_height > to_height {
+        return Err(CommandError::new(AppErrorCode::InvalidInput, "The range starts after it ends"));
+    }
+    if to_height - from_height >= crate::mining_service::MAX_CHART_BLOCKS {
+        return Err(CommandError::new(
+            AppErrorCode::InvalidInput,
+            format!("At most {} blocks can be charted at once", crate::mining_service::MAX_CHART_BLOCKS),
+        ));
+    }
+    blockchain_db.get_block_stats(from_height, to_height).await.map_err(format_error)
+}
+
+/// Command to estimate the network hash rate over the last `window` blocks
+#[command]
+#[command_metrics::tracked]
+pub async fn get_network_hashrate_estimate(
+    services: State<'_, ServiceContainer>,
+    window: Option<u64>,
+) -> CommandResult<NetworkHashRate> {
+    let mining_service = services.require::<AsyncMiningService>()?;
+    debug!("Command: get_network_hashrate_estimate over {:?} blocks", window);
+    let window = window
+        .unwrap_or(crate::mining_service::DEFAULT_HASH_RATE_WINDOW)
+        .clamp(1, crate::mining_service::MAX_CHART_BLOCKS);
+    mining_service.network_hash_rate(window).await.map_err(format_error)
+}
+
+/// Command to get a candidate block for an external miner, paying the reward to `mining_address`
+#[command]
+#[command_metrics::tracked]
+pub async fn get_block_template(
+    services: State<'_, ServiceContainer>,
+    mining_address: String,
+) -> CommandResult<BlockTemplate> {
+    let mining_service = services.require::<AsyncMiningService>()?;
+    debug!("Command: get_block_template for address: {}", mining_address);
+
+    let mining_address = crate::validation::validate_address(&mining_address, ChainParams::active().network)?;
+    mining_service.block_template(&mining_address).await.map_err(format_error)
+}
+
+/// Command to accept a block mined outside the node, given as hex of its bincode encoding.
+/// Returns the block hash once it is stored and broadcast.
+#[command]
+#[command_metrics::tracked]
+pub async fn submit_block(
+    services: State<'_, ServiceContainer>,
+    block_hex: String,
+) -> CommandResult<String> {
+    let mining_service = services.require::<AsyncMiningService>()?;
+    debug!("Command: submit_block ({} hex chars)", block_hex.len());
+
+    let bytes = hex::decode(block_hex.trim())
+        .map_err(|e| CommandError::new(AppErrorCode::InvalidInput, format!("Block is not valid hex: {}", e)))?;
+    let (block, _) = bincode::decode_from_slice::<crate::blockchain_database::Block, _>(&bytes, bincode::config::standard())
+        .map_err(|e| CommandError::new(AppErrorCode::InvalidInput, format!("Block cannot be decoded: {}", e)))?;
+
+    mining_service.submit_block(block).await.map_err(|e| {
+        warn!("Rejected submitted block: {}", e);
+        CommandError::new(AppErrorCode::InvalidInput, format!("Block rejected: {}", e))
+    })
+}
+
+/// Command to get share statistics of the miners connected through the stratum server
+#[command]
+#[command_metrics::tracked]
+pub async fn get_stratum_workers(
+    stratum: State<'_, crate::stratum::StratumServer>,
+) -> CommandResult<Vec<crate::stratum::WorkerStats>> {
+    debug!("Command: get_stratum_workers");
+    Ok(stratum.workers())
+}
+
 /// Check if blockchain database exists at configured or default location
 #[command]
+#[command_metrics::tracked]
 pub async fn check_blockchain_database_exists(
     config_manager: State<'_, Arc<ConfigManager>>,
 ) -> CommandResult<bool> {
     info!("Command: check_blockchain_database_exists");
-    
+
     let config = config_manager.get_config();
-    
+
     // Get the default location for fallback
-    let default_blockchain_data_dir = match dirs::data_dir() {
-        Some(dir) => dir.join("com.b-rad-coin.app").join("blockchain"),
+    let default_blockchain_data_dir = match PathProvider::blockchain_dir() {
+        Some(dir) => dir,
         None => {
             error!("Failed to determine default blockchain data directory");
             return Ok(false);
         }
     };
-    
+
     // Check if there's a custom location configured
     if let Some(custom_location) = &config.app_settings.local_blockchain_file_location {
         let custom_path = std::path::Path::new(custom_location);
         let custom_exists = custom_path.exists() && custom_path.is_dir();
         info!("Checking custom blockchain location: {:?}, exists: {}", custom_path, custom_exists);
-        
+    
         if custom_exists {
             return Ok(true);
         }
-        
+    
         // Custom location doesn't exist, check default location
         info!("Custom location not found, checking default location");
         let default_exists = default_blockchain_data_dir.exists() && default_blockchain_data_dir.is_dir();
         info!("Checking default blockchain location: {:?}, exists: {}", default_blockchain_data_dir, default_exists);
-        
+    
         if default_exists {
             // Database found at default location, update config to point there
             info!("Database found at default location, updating config");
             let mut new_config = config.clone();
             new_config.app_settings.local_blockchain_file_location = Some(default_blockchain_data_dir.to_string_lossy().to_string());
-            
+        
             if let Err(e) = config_manager.update_config(new_config).await {
                 error!("Failed to update configuration to default location: {}", e);
                 // Still return true since database exists, even if config update failed
                 return Ok(true);
             }
-            
+        
             info!("Configuration updated to point to default blockchain location");
             return Ok(true);
         }
-        
+    
         // Database not found in either location, update config to default for future use
         info!("Database not found in custom or default location, updating config to default location");
         let mut new_config = config.clone();
         new_config.app_settings.local_blockchain_file_location = Some(default_blockchain_data_dir.to_string_lossy().to_string());
-        
+    
         if let Err(e) = config_manager.update_config(new_config).await {
             error!("Failed to update configuration to default location: {}", e);
         } else {
             info!("Configuration updated to default blockchain location for future use");
         }
-        
+    
         return Ok(false);
     }
-    
+
     // No custom location configured, check default location
     let default_exists = default_blockchain_data_dir.exists() && default_blockchain_data_dir.is_dir();
     info!("Checking default blockchain location: {:?}, exists: {}", default_blockchain_data_dir, default_exists);
-    
+
     if !default_exists {
         // Database not found at default location, update config to explicitly point to default
         info!("Database not found at default location, updating config to default location");
         let mut new_config = config.clone();
         new_config.app_settings.local_blockchain_file_location = Some(default_blockchain_data_dir.to_string_lossy().to_string());
-        
+    
         if let Err(e) = config_manager.update_config(new_config).await {
             error!("Failed to update configuration to default location: {}", e);
         } else {
             info!("Configuration updated to default blockchain location for future use");
         }
     }
-    
+
     Ok(default_exists)
 }
 
 /// Get the blockchain database path (custom or default)
 #[command]
+#[command_metrics::tracked]
 pub async fn get_blockchain_database_path(
     config_manager: State<'_, Arc<ConfigManager>>,
 ) -> CommandResult<String> {
     info!("Command: get_blockchain_database_path");
-    
+
     let config = config_manager.get_config();
-    
+
     // Return custom location if configured
     if let Some(custom_location) = &config.app_settings.local_blockchain_file_location {
         return Ok(custom_location.clone());
     }
-    
+
     // Return default location
-    let blockchain_data_dir = match dirs::data_dir() {
-        Some(dir) => dir.join("com.b-rad-coin.app").join("blockchain"),
+    let blockchain_data_dir = match PathProvider::blockchain_dir() {
+        Some(dir) => dir,
         None => {
-            return Err("Failed to determine blockchain data directory".to_string());
+            return Err("Failed to determine blockchain data directory".to_string().into());
         }
     };
-    
+
     Ok(blockchain_data_dir.to_string_lossy().to_string())
 }
 
 /// Get the default blockchain database path (ignoring any custom configuration)
 #[command]
+#[command_metrics::tracked]
 pub async fn get_default_blockchain_database_path() -> CommandResult<String> {
     info!("Command: get_default_blockchain_database_path");
-    
+
     // Always return the default system location, ignoring config
-    let blockchain_data_dir = match dirs::data_dir() {
-        Some(dir) => dir.join("com.b-rad-coin.app").join("blockchain"),
+    let blockchain_data_dir = match PathProvider::blockchain_dir() {
+        Some(dir) => dir,
         None => {
-            return Err("Failed to determine default blockchain data directory".to_string());
+            return Err("Failed to determine default blockchain data directory".to_string().into());
         }
     };
-    
+
     Ok(blockchain_data_dir.to_string_lossy().to_string())
 }
 
 /// Open folder picker dialog
 #[command]
+#[command_metrics::tracked]
 pub async fn open_folder_picker(title: String) -> CommandResult<Option<String>> {
     info!("Command: open_folder_picker with title: {}", title);
-    
+
     use rfd::AsyncFileDialog;
-    
+
     let folder = AsyncFileDialog::new()
         .set_title(&title)
         .pick_folder()
         .await;
-    
+
     match folder {
         Some(path) => {
             let path_str = path.path().to_string_lossy().to_string();
@@ -1797,66 +3373,68 @@ pub async fn open_folder_picker(title: String) -> CommandResult<Option<String>>
 
 /// Create blockchain database at specified location and update config
 #[command]
+#[command_metrics::tracked]
 pub async fn create_blockchain_database_at_location(
     location: String,
     config_manager: State<'_, Arc<ConfigManager>>,
     app_handle: tauri::AppHandle,
 ) -> CommandResult<bool> {
     info!("Command: create_blockchain_database_at_location at: {}", location);
-    
+
     // First, stop all existing blockchain services to release database locks
     info!("Stopping existing blockchain services before creating new database");
     if let Err(e) = stop_blockchain_services_internal(&app_handle).await {
         error!("Failed to stop blockchain services: {}", e);
-        return Err(format!("Failed to stop existing services: {}", e));
+        return Err(format!("Failed to stop existing services: {}", e).into());
     }
-    
+
     // Wait longer for network resources to be fully released
     info!("Waiting for network resources to be released...");
     tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
-    
+
     let blockchain_path = std::path::Path::new(&location).join("blockchain.db");
-    
+
     // Create the blockchain database
     match crate::blockchain_database::AsyncBlockchainDatabase::new(blockchain_path.clone()).await {
         Ok(_) => {
             info!("Blockchain database created successfully at: {:?}", blockchain_path);
-            
+        
             // Update configuration with the new location
             let mut config = config_manager.get_config().clone();
             config.app_settings.local_blockchain_file_location = Some(location);
-            
+        
             if let Err(e) = config_manager.update_config(config).await {
                 error!("Failed to update configuration: {}", e);
-                return Err(format!("Created database but failed to update config: {}", e));
+                return Err(format!("Created database but failed to update config: {}", e).into());
             }
-            
+        
             info!("Configuration updated with new blockchain location");
             Ok(true)
         }
         Err(e) => {
             error!("Failed to create blockchain database: {}", e);
-            Err(format!("Failed to create blockchain database: {}", e))
+            Err(format!("Failed to create blockchain database: {}", e).into())
         }
     }
 }
 
 /// Set existing blockchain database location and update config
 #[command]
+#[command_metrics::tracked]
 pub async fn set_blockchain_database_location(
     location: String,
     config_manager: State<'_, Arc<ConfigManager>>,
     app_handle: tauri::AppHandle,
 ) -> CommandResult<bool> {
     info!("Command: set_blockchain_database_location to: {}", location);
-    
+
     let blockchain_path = std::path::Path::new(&location);
-    
+
     // Verify the location exists and contains a valid blockchain database
     if !blockchain_path.exists() || !blockchain_path.is_dir() {
-        return Err("Selected location does not exist or is not a directory".to_string());
+        return Err("Selected location does not exist or is not a directory".to_string().into());
     }
-    
+
     // Check if it looks like a blockchain database directory
     // Look for any files that suggest this is a Sled database directory
     let mut has_db_files = false;
@@ -1864,7 +3442,7 @@ pub async fn set_blockchain_database_location(
         for entry in entries.flatten() {
             let file_name = entry.file_name();
             let file_name_str = file_name.to_string_lossy();
-            
+        
             // Look for common Sled database files/directories
             if file_name_str == "conf" || 
                file_name_str == "db" || 
@@ -1881,130 +3459,166 @@ pub async fn set_blockchain_database_location(
             }
         }
     }
-    
+
     if !has_db_files {
-        return Err("Selected location does not appear to contain a valid blockchain database".to_string());
+        return Err("Selected location does not appear to contain a valid blockchain database".to_string().into());
     }
-    
+
     // First, stop all existing blockchain services to release database locks
     info!("Stopping existing blockchain services before switching database location");
     if let Err(e) = stop_blockchain_services_internal(&app_handle).await {
         error!("Failed to stop blockchain services: {}", e);
-        return Err(format!("Failed to stop existing services: {}", e));
+        return Err(format!("Failed to stop existing services: {}", e).into());
     }
-    
+
     // Wait longer for all resources to be fully released, including file locks
     info!("Waiting for all resources and file locks to be released...");
     tokio::time::sleep(tokio::time::Duration::from_millis(3000)).await;
-    
+
     // Force garbage collection to help release any remaining references
     std::hint::black_box(());
     tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    
+
     // Update configuration with the location
     let mut config = config_manager.get_config().clone();
     config.app_settings.local_blockchain_file_location = Some(location);
-    
+
     if let Err(e) = config_manager.update_config(config).await {
         error!("Failed to update configuration: {}", e);
-        return Err(format!("Failed to update configuration: {}", e));
+        return Err(format!("Failed to update configuration: {}", e).into());
     }
-    
+
     info!("Configuration updated with blockchain location");
     Ok(true)
 }
 
 /// Start blockchain services after database setup is complete
 #[command]
+#[command_metrics::tracked]
 pub async fn start_blockchain_services(
     app_handle: tauri::AppHandle,
 ) -> CommandResult<bool> {
     info!("Command: start_blockchain_services");
-    
+
     // First, ensure any existing services are properly stopped
     // This is important when switching database locations
-    if let Err(e) = stop_blockchain_services_internal(&app_handle).await {
-        warn!("Failed to stop existing services (this might be normal): {}", e);
+    let were_running = match stop_blockchain_services_internal(&app_handle).await {
+        Ok(were_running) => were_running,
+        Err(e) => {
+            warn!("Failed to stop existing services (this might be normal): {}", e);
+            true
+        }
+    };
+
+    // Wait a moment for resources to be fully released; on a cold start nothing holds them
+    if were_running {
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
     }
-    
-    // Wait a moment for resources to be fully released
-    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-    
+
     // Initialize blockchain database with the configured location
+    let database_started = Instant::now();
     let config_manager = app_handle.state::<Arc<ConfigManager>>();
-    
+
     // Force reload configuration to ensure we have the latest blockchain location
     (**config_manager).reload_config().await.map_err(|e| {
         error!("Failed to reload configuration: {}", e);
         format!("Failed to reload configuration: {}", e)
     })?;
-    
+
     let config = config_manager.get_config();
-    
+
     let blockchain_data_dir = if let Some(custom_location) = &config.app_settings.local_blockchain_file_location {
         std::path::PathBuf::from(custom_location)
     } else {
-        match dirs::data_dir() {
-            Some(dir) => dir.join("com.b-rad-coin.app").join("blockchain"),
+        match PathProvider::blockchain_dir() {
+            Some(dir) => dir,
             None => {
-                return Err("Failed to determine blockchain data directory".to_string());
+                return Err("Failed to determine blockchain data directory".to_string().into());
             }
         }
     };
-    
-    // Initialize blockchain database
-    let blockchain_db = match crate::blockchain_database::AsyncBlockchainDatabase::new(blockchain_data_dir).await {
+
+    // Initialize blockchain database, upgrading data written by an older version
+    let migration_app_handle = app_handle.clone();
+    let report_migration = move |progress: &crate::blockchain_database::MigrationProgress| {
+        if let Err(e) = migration_app_handle.emit_event(events::DATABASE_MIGRATION_PROGRESS, progress) {
+            warn!("Failed to emit database migration progress: {}", e);
+        }
+    };
+    // Caches start small if memory is already short
+    let settings = &config.app_settings;
+    let tuning = crate::blockchain_database::DatabaseTuning::from_settings(
+        settings.db_cache_capacity_mb,
+        settings.db_block_cache_mb,
+        settings.db_low_memory_mode && crate::memory_monitor::memory_is_short(),
+    );
+    let blockchain_db = match crate::blockchain_database::AsyncBlockchainDatabase::new_with_progress(blockchain_data_dir, tuning, report_migration).await {
         Ok(db) => Arc::new(db),
         Err(e) => {
             error!("Failed to initialize blockchain database: {}", e);
-            return Err(format!("Failed to initialize blockchain database: {}", e));
+            return Err(format!("Failed to initialize blockchain database: {}", e).into());
         }
     };
-    
-    // Store blockchain database in app state
-    app_handle.manage(blockchain_db.clone());
-    
-    // Initialize and store blockchain sync service
-    let blockchain_sync = crate::blockchain_sync::AsyncBlockchainSyncService::new(blockchain_db.clone());
-    app_handle.manage(blockchain_sync);
-    
-    // Initialize and store wallet sync service
-    let wallet_sync = crate::wallet_sync_service::AsyncWalletSyncService::new(blockchain_db.clone());
-    app_handle.manage(wallet_sync);
-    
-    // Initialize and store mining service
-    let mining_service = crate::mining_service::AsyncMiningService::new(blockchain_db.clone());
-    app_handle.manage(mining_service);
-    
-    // Initialize and store mempool service
+    crate::startup_timings::record(&app_handle, "blockchain_database", database_started);
+    let services_started = Instant::now();
+
+    // Announce the database's blocks on the app's event bus
+    if let Some(event_bus) = app_handle.try_state::<crate::event_bus::EventBus>() {
+        blockchain_db.set_event_bus(event_bus.inner().clone());
+    }
+    apply_address_index_setting(&app_handle, &blockchain_db, config.app_settings.address_index_enabled).await;
+
+    let blockchain_sync = Arc::new(crate::blockchain_sync::AsyncBlockchainSyncService::new(blockchain_db.clone()));
+
+    // Wallet sync keeps open wallets up to date as blocks arrive
+    let wallet_sync = Arc::new(AsyncWalletSyncService::new(blockchain_db.clone()));
+    if let Err(e) = wallet_sync.initialize(app_handle.clone()).await {
+        warn!("Failed to initialize wallet sync service: {}", e);
+    }
+    if let Some(wallet_manager) = app_handle.try_state::<AsyncWalletManager>() {
+        wallet_sync.set_wallet_manager(wallet_manager.inner().clone()).await;
+    }
+    wallet_sync.set_config_manager(config_manager.inner().clone()).await;
+    wallet_sync.follow_chain(&blockchain_db.event_bus()).await;
+
+    let mining_service = Arc::new(AsyncMiningService::new(blockchain_db.clone()));
+    mining_service
+        .set_intensity(crate::mining_worker::MiningIntensity::from_settings(&config.app_settings))
+        .await;
+
     let mempool_service = crate::mempool_service::AsyncMempoolService::new(blockchain_db.clone());
-    app_handle.manage(mempool_service.clone());
-    
-    // Initialize and store fee estimator
+    mempool_service.set_max_size_mb(config.app_settings.max_mempool_size_mb).await;
+    mempool_service.set_policy(RelayPolicy::from_settings(&config.app_settings)).await;
+
     let fee_estimator = crate::fee_estimator::AsyncFeeEstimator::new(blockchain_db.clone());
     fee_estimator.set_mempool(mempool_service.clone()).await;
-    app_handle.manage(fee_estimator);
-    
-    // Initialize and store network monitor
+
+    // The network service relays mempool transactions and is watched by the network monitor
     let network_monitor = crate::network_monitor::AsyncNetworkMonitor::new();
-    app_handle.manage(network_monitor.clone());
-    
-    // Initialize and store network service
     let mut network_service = crate::network_service::AsyncNetworkService::new(blockchain_db.clone(), None);
-    
-    // Connect mempool to network service for transaction propagation
-    network_service.set_mempool(mempool_service);
-    
-    // Connect network monitor to network service
+    network_service.set_mempool(mempool_service.clone());
     network_monitor.set_network_service(network_service.clone()).await;
-    
-    app_handle.manage(network_service);
-    
+    let network_service = Arc::new(network_service);
+    let network_monitor = Arc::new(network_monitor);
+
+    // Make this set current; commands see it from here on
+    app_handle.state::<ServiceContainer>().install(BlockchainServices {
+        blockchain_db: blockchain_db.clone(),
+        blockchain_sync: blockchain_sync.clone(),
+        wallet_sync,
+        mining: mining_service,
+        mempool: Arc::new(mempool_service),
+        fee_estimator: Arc::new(fee_estimator),
+        network_monitor: network_monitor.clone(),
+        network: network_service.clone(),
+    });
+    crate::startup_timings::record(&app_handle, "blockchain_services", services_started);
+
     // Start network service with retry logic for port binding
-    let network_service = app_handle.state::<crate::network_service::AsyncNetworkService>();
+    let network_started = Instant::now();
     let mut retries = 3;
     let mut last_error_msg = None;
-    
+
     while retries > 0 {
         match network_service.start().await {
             Ok(()) => {
@@ -2023,153 +3637,246 @@ pub async fn start_blockchain_services(
                 } else {
                     // For other errors, don't retry
                     error!("Failed to start network service: {}", e);
-                    return Err(format!("Failed to start network service: {}", e));
+                    return Err(format!("Failed to start network service: {}", e).into());
                 }
             }
         }
     }
-    
+
     if retries == 0 {
         if let Some(err_msg) = last_error_msg {
             error!("Failed to start network service after retries: {}", err_msg);
-            return Err(format!("Failed to start network service after retries: {}. The network port (8333) may still be in use by another process or a previous instance. Please wait a few moments and try again.", err_msg));
+            return Err(format!("Failed to start network service after retries: {}. The network port (8333) may still be in use by another process or a previous instance. Please wait a few moments and try again.", err_msg).into());
         }
     }
-    
+    crate::startup_timings::record(&app_handle, "network", network_started);
+
     // Start blockchain sync service
-    let blockchain_sync = app_handle.state::<crate::blockchain_sync::AsyncBlockchainSyncService>();
+    let sync_started = Instant::now();
     if let Err(e) = blockchain_sync.initialize(app_handle.clone()).await {
         error!("Failed to initialize blockchain sync service: {}", e);
-        return Err(format!("Failed to initialize blockchain sync service: {}", e));
+        return Err(format!("Failed to initialize blockchain sync service: {}", e).into());
     }
-    
+
     if let Err(e) = blockchain_sync.start_sync().await {
         error!("Failed to start blockchain sync: {}", e);
-        return Err(format!("Failed to start blockchain sync: {}", e));
+        return Err(format!("Failed to start blockchain sync: {}", e).into());
     }
-    
+    crate::startup_timings::record(&app_handle, "sync", sync_started);
+
     // Start network monitoring
-    let network_monitor = app_handle.state::<crate::network_monitor::AsyncNetworkMonitor>();
     tokio::spawn({
-        let monitor = network_monitor.inner().clone();
+        let monitor = network_monitor.clone();
         async move {
             if let Err(e) = monitor.start_monitoring().await {
                 error!("Network monitoring stopped: {}", e);
             }
         }
     });
-    
+
     info!("All blockchain services started successfully");
-    
+
     // Notify frontend that blockchain services are ready
     if let Some(window) = app_handle.get_webview_window("main") {
-        let _ = window.emit("blockchain-services-ready", ());
+        let _ = window.emit_event(events::BLOCKCHAIN_SERVICES_READY, &());
     }
-    
+
     Ok(true)
 }
 
-/// Internal function to stop blockchain services (used by other functions)
-async fn stop_blockchain_services_internal(app_handle: &tauri::AppHandle) -> Result<(), String> {
+/// Internal function to stop blockchain services (used by other functions); returns whether
+/// they were running
+async fn stop_blockchain_services_internal(app_handle: &tauri::AppHandle) -> Result<bool, String> {
     info!("Stopping blockchain services internally");
     
-    // Stop network service if it exists
-    if let Some(network_service) = app_handle.try_state::<crate::network_service::AsyncNetworkService>() {
-        info!("Stopping network service");
-        if let Err(e) = network_service.stop().await {
-            error!("Failed to stop network service: {}", e);
-        } else {
-            info!("Network service stopped successfully");
-        }
+    // Commands stop seeing the services from here on
+    let Some(services) = app_handle.state::<ServiceContainer>().take() else {
+        info!("Blockchain services are not running");
+        return Ok(false);
+    };
+    
+    info!("Stopping network service");
+    if let Err(e) = services.network.stop().await {
+        error!("Failed to stop network service: {}", e);
+    } else {
+        info!("Network service stopped successfully");
     }
     
     // Wait for network service to fully stop and release the port
     info!("Waiting for network service to fully release resources...");
     tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
     
-    // Stop blockchain sync service if it exists
-    if let Some(blockchain_sync) = app_handle.try_state::<crate::blockchain_sync::AsyncBlockchainSyncService>() {
-        info!("Stopping blockchain sync service");
-        // Note: Add explicit stop method to blockchain sync service if available
-    }
+    info!("Stopping wallet sync service");
+    services.wallet_sync.stop_following().await;
     
-    // Stop wallet sync service if it exists
-    if let Some(wallet_sync) = app_handle.try_state::<crate::wallet_sync_service::AsyncWalletSyncService>() {
-        info!("Stopping wallet sync service");
-        // Note: Add explicit stop method to wallet sync service if available
+    info!("Closing blockchain database");
+    if let Err(e) = services.blockchain_db.close().await {
+        error!("Failed to close blockchain database: {}", e);
+        return Err(format!("Failed to close blockchain database: {}", e));
+    } else {
+        info!("Blockchain database closed successfully");
     }
     
-    // Close blockchain database if it exists
-    if let Some(blockchain_db) = app_handle.try_state::<Arc<crate::blockchain_database::AsyncBlockchainDatabase>>() {
-        info!("Closing blockchain database");
-        if let Err(e) = blockchain_db.close().await {
-            error!("Failed to close blockchain database: {}", e);
-            return Err(format!("Failed to close blockchain database: {}", e));
-        } else {
-            info!("Blockchain database closed successfully");
-        }
-        
-        // Wait additional time for database locks to be fully released
-        info!("Waiting for database locks to be fully released...");
-        tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
-    }
+    // Wait additional time for database locks to be fully released
+    info!("Waiting for database locks to be fully released...");
+    tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
     
     info!("Blockchain services stopped successfully");
-    Ok(())
+    Ok(true)
 }
 
 /// Stop blockchain services to allow database operations
 #[command]
+#[command_metrics::tracked]
 pub async fn stop_blockchain_services(
     app_handle: tauri::AppHandle,
 ) -> CommandResult<bool> {
     info!("Command: stop_blockchain_services");
-    
+
     match stop_blockchain_services_internal(&app_handle).await {
-        Ok(()) => Ok(true),
-        Err(e) => Err(e),
+        Ok(_) => Ok(true),
+        Err(e) => Err(e.into()),
     }
 }
 
 /// Check if blockchain services are ready
 #[command]
+#[command_metrics::tracked]
 pub async fn is_blockchain_ready(
     app_handle: tauri::AppHandle,
 ) -> CommandResult<bool> {
     info!("Command: is_blockchain_ready");
-    
+
     // Check if blockchain database service exists and is initialized
-    let blockchain_db_exists = app_handle.try_state::<Arc<crate::blockchain_database::AsyncBlockchainDatabase>>().is_some();
-    
+    let blockchain_db_exists = crate::services::get::<crate::blockchain_database::AsyncBlockchainDatabase>(&app_handle).is_some();
+
     // Check if blockchain sync service exists and is initialized
-    let blockchain_sync_exists = app_handle.try_state::<crate::blockchain_sync::AsyncBlockchainSyncService>().is_some();
-    
+    let blockchain_sync_exists = crate::services::get::<AsyncBlockchainSyncService>(&app_handle).is_some();
+
     // Check if network service exists and is running
-    let network_service_exists = app_handle.try_state::<crate::network_service::AsyncNetworkService>().is_some();
-    
+    let network_service_exists = crate::services::get::<crate::network_service::AsyncNetworkService>(&app_handle).is_some();
+
     let is_ready = blockchain_db_exists && blockchain_sync_exists && network_service_exists;
-    
+
     info!("Blockchain ready check: db={}, sync={}, network={}, ready={}", 
           blockchain_db_exists, blockchain_sync_exists, network_service_exists, is_ready);
-    
+
     Ok(is_ready)
 }
 
 /// Get the estimated size of blockchain database files
 #[command]
+#[command_metrics::tracked]
 pub async fn get_blockchain_database_size(
     config_manager: State<'_, Arc<ConfigManager>>,
 ) -> CommandResult<u64> {
     info!("Command: get_blockchain_database_size");
-    
+
     let config = config_manager.get_config();
-    
+    let total_size = blockchain_database_size(&config.app_settings)?;
+
+    info!("Blockchain database size: {} bytes", total_size);
+    Ok(total_size)
+}
+
+/// Turn the address index on or off, and index any blocks it does not cover yet in the background
+async fn apply_address_index_setting(
+    app_handle: &tauri::AppHandle,
+    blockchain_db: &Arc<crate::blockchain_database::AsyncBlockchainDatabase>,
+    enabled: bool,
+) {
+    blockchain_db.set_address_index_enabled(enabled).await;
+    if !enabled {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    let blockchain_db = blockchain_db.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = blockchain_db
+            .backfill_address_index(|status| {
+                if let Err(e) = app_handle.emit_event(events::ADDRESS_INDEX_PROGRESS, status) {
+                    warn!("Failed to emit address index progress: {}", e);
+                }
+            })
+            .await;
+        match result {
+            Ok(()) => info!("Address index is up to date"),
+            Err(e) => error!("Failed to build the address index: {}", e),
+        }
+    });
+}
+
+/// Command to get the transactions touching an address, newest first. Needs the address index.
+#[command]
+#[command_metrics::tracked]
+pub async fn get_address_history(
+    address: String,
+    limit: Option<usize>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<Vec<crate::blockchain_database::AddressTx>> {
+    debug!("Command: get_address_history for {}", address);
+
+    let blockchain_db = crate::services::get::<crate::blockchain_database::AsyncBlockchainDatabase>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Blockchain database is not available"))?;
+
+    let status = blockchain_db.address_index_status().await.map_err(|e| e.to_string())?;
+    if !status.enabled {
+        return Err(CommandError::new(
+            AppErrorCode::InvalidOperation,
+            "Enable the address index in settings to look up address history",
+        ));
+    }
+    if !status.complete {
+        return Err(CommandError::new(
+            AppErrorCode::InvalidOperation,
+            format!(
+                "The address index is still being built ({} of {} blocks)",
+                status.indexed_height, status.tip_height + 1
+            ),
+        ));
+    }
+
+    blockchain_db
+        .get_address_history(&address, limit.unwrap_or(100))
+        .await
+        .map_err(|e| e.to_string().into())
+}
+
+/// Command to get how far the address index covers the chain
+#[command]
+#[command_metrics::tracked]
+pub async fn get_address_index_status(
+    app_handle: tauri::AppHandle,
+) -> CommandResult<crate::blockchain_database::AddressIndexStatus> {
+    debug!("Command: get_address_index_status");
+
+    let blockchain_db = crate::services::get::<crate::blockchain_database::AsyncBlockchainDatabase>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Blockchain database is not available"))?;
+    blockchain_db.address_index_status().await.map_err(|e| e.to_string().into())
+}
+
+/// Command to get the size and cache use of the blockchain database
+#[command]
+#[command_metrics::tracked]
+pub async fn get_database_stats(
+    app_handle: tauri::AppHandle,
+) -> CommandResult<crate::blockchain_database::DatabaseStats> {
+    debug!("Command: get_database_stats");
+
+    let blockchain_db = crate::services::get::<crate::blockchain_database::AsyncBlockchainDatabase>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Blockchain database is not available"))?;
+    blockchain_db.database_stats().await.map_err(|e| e.to_string().into())
+}
+
+/// Total size of the blockchain database files at the configured location, 0 if there are none yet
+pub(crate) fn blockchain_database_size(settings: &AppSettings) -> Result<u64, String> {
     // Get current blockchain location
-    let current_location = if let Some(custom_location) = &config.app_settings.local_blockchain_file_location {
+    let current_location = if let Some(custom_location) = &settings.local_blockchain_file_location {
         std::path::PathBuf::from(custom_location)
     } else {
-        match dirs::data_dir() {
-            Some(dir) => dir.join("com.b-rad-coin.app").join("blockchain"),
+        match PathProvider::blockchain_dir() {
+            Some(dir) => dir,
             None => {
                 return Err("Failed to determine blockchain data directory".to_string());
             }
@@ -2181,7 +3888,7 @@ pub async fn get_blockchain_database_size(
     }
     
     // Calculate total size of blockchain database files
-    let total_size = calculate_directory_size(&current_location, &[
+    Ok(calculate_directory_size(&current_location, &[
         "blocks",        // Sled tree for blocks
         "transactions",  // Sled tree for transactions
         "utxos",        // Sled tree for UTXOs
@@ -2190,10 +3897,7 @@ pub async fn get_blockchain_database_size(
         "conf",         // Sled configuration file
         "db",           // Sled database file
         "snap",         // Sled snapshot files
-    ]);
-    
-    info!("Blockchain database size: {} bytes", total_size);
-    Ok(total_size)
+    ]))
 }
 
 /// Helper function to calculate the size of database files in a directory
@@ -2254,18 +3958,19 @@ pub struct WalletAddress {
 
 /// Command to get all addresses from all available wallets
 #[command]
+#[command_metrics::tracked]
 pub async fn get_all_wallet_addresses(
     wallet_manager: State<'_, AsyncWalletManager>,
 ) -> CommandResult<Vec<WalletAddress>> {
     debug!("Command: get_all_wallet_addresses");
-    
+
     let mut manager = wallet_manager.get_manager().await;
     let mut all_addresses = Vec::new();
-    
+
     // Get all available wallets
     let wallet_list = manager.list_wallets();
     let wallet_count = wallet_list.len();
-    
+
     for wallet_info in &wallet_list {
         // Use addresses from wallet info (stored in config)
         for (index, address) in wallet_info.addresses.iter().enumerate() {
@@ -2277,28 +3982,31 @@ pub async fn get_all_wallet_addresses(
             });
         }
     }
-    
+
     info!("Found {} addresses across {} wallets", all_addresses.len(), wallet_count);
     Ok(all_addresses)
 }
 
 /// Command to get the current mining configuration including status and reward address
 #[command]
+#[command_metrics::tracked]
 pub async fn get_mining_configuration(
+    wallet_name: Option<String>,
     wallet_manager: State<'_, AsyncWalletManager>,
-    mining_service: State<'_, AsyncMiningService>,
+    services: State<'_, ServiceContainer>,
 ) -> CommandResult<Option<MiningConfiguration>> {
+    let mining_service = services.require::<AsyncMiningService>()?;
     debug!("Command: get_mining_configuration");
-    
-    let manager = wallet_manager.get_manager().await;
-    
+
+    let manager = wallet_manager.read_manager().await;
+
     // Check if there's a current wallet
-    if let Some(current_wallet) = manager.get_current_wallet() {
+    if let Some(current_wallet) = manager.get_wallet(wallet_name.as_deref()) {
         let wallet_id = current_wallet.name.clone();
-        
+    
         // Get mining status
         let mining_status = mining_service.get_mining_status(&wallet_id).await;
-        
+    
         match mining_status {
             Some(status) => {
                 Ok(Some(MiningConfiguration {
@@ -2312,12 +4020,18 @@ pub async fn get_mining_configuration(
             }
             None => {
                 // No mining status found, but wallet is open
-                // Return default configuration with first address if available
-                if let Some(first_address) = current_wallet.data.addresses.first() {
+                // Return default configuration with the configured mining address or the first address
+                let configured_address = WalletSettings::load(&current_wallet.path)
+                    .ok()
+                    .and_then(|settings| settings.mining_address);
+                let mining_address = configured_address
+                    .or_else(|| current_wallet.data.receiving_addresses().next().map(|a| a.address.clone()));
+
+                if let Some(mining_address) = mining_address {
                     Ok(Some(MiningConfiguration {
                         wallet_id: wallet_id.clone(),
                         is_mining: false,
-                        mining_address: first_address.address.clone(),
+                        mining_address,
                         hash_rate: 0.0,
                         blocks_mined: 0,
                         current_difficulty: 0,
@@ -2345,46 +4059,37 @@ pub struct MiningConfiguration {
 
 /// Command to update the label of an address in the current wallet
 #[command]
+#[command_metrics::tracked]
 pub async fn update_address_label(
     address: String,
     label: Option<String>,
+    wallet_name: Option<String>,
     wallet_manager: State<'_, AsyncWalletManager>,
 ) -> CommandResult<bool> {
     info!("Command: update_address_label for address: {}", address);
 
     let mut manager = wallet_manager.get_manager().await;
 
-    // First get the wallet name and secured status
-    let (_wallet_name, is_secured, wallet_path) = {
-        let current_wallet = match manager.get_current_wallet() {
-            Some(wallet) => wallet,
-            None => {
-                error!("No wallet is currently open");
-                return Err("No wallet is currently open".to_string());
-            }
-        };
-
-        let wallet_name = current_wallet.name.clone();
-        let wallet_path = current_wallet.path.clone();
-        
-        let is_secured = if let Some(wallet_info) = manager.find_wallet_by_name(&wallet_name) {
-            wallet_info.secured
-        } else {
-            false
-        };
-
-        (wallet_name, is_secured, wallet_path)
+    // First get the wallet name
+    let wallet_name = match manager.get_wallet(wallet_name.as_deref()) {
+        Some(wallet) => wallet.name.clone(),
+        None => {
+            error!("No wallet is currently open");
+            return Err(WalletError::NoWalletOpen.into());
+        }
     };
 
     // Now get mutable access to update the wallet
-    let current_wallet = match manager.get_current_wallet_mut() {
+    let current_wallet = match manager.get_wallet_mut(Some(&wallet_name)) {
         Some(wallet) => wallet,
         None => {
             error!("No wallet is currently open");
-            return Err("No wallet is currently open".to_string());
+            return Err(WalletError::NoWalletOpen.into());
         }
     };
 
+    ensure_writable(&current_wallet.data)?;
+
     // Update the label in the addresses list
     let mut address_found = false;
     for addr_info in &mut current_wallet.data.addresses {
@@ -2397,153 +4102,1688 @@ pub async fn update_address_label(
 
     if !address_found {
         error!("Address not found in current wallet: {}", address);
-        return Err(format!("Address '{}' not found in current wallet", address));
+        return Err(format!("Address '{}' not found in current wallet", address).into());
     }
 
     // Update the modified timestamp
     current_wallet.data.modified_at = chrono::Utc::now().timestamp();
 
-    // Get the wallet data file path
-    let wallet_data_path = wallet_path.join("wallet.dat");
-
-    // Save the wallet data to disk
-    // Note: Since this is an open wallet, if it's secured, it would have been unlocked already
-    match current_wallet.data.save(&wallet_data_path, if is_secured { Some("") } else { None }) {
-        Ok(_) => {
-            info!("Successfully updated label for address: {}", address);
-            Ok(true)
-        }
-        Err(e) => {
-            error!("Failed to save wallet data: {}", e);
-            Err(format!("Failed to save wallet data: {}", e))
-        }
-    }
+    // Saved in the background by the wallet persister
+    manager.mark_dirty(&wallet_name);
+    info!("Successfully updated label for address: {}", address);
+    Ok(true)
 }
 
 /// Command to derive a new address for the current wallet
 #[command]
+#[command_metrics::tracked]
 pub async fn derive_new_address(
     label: Option<String>,
+    wallet_name: Option<String>,
     wallet_manager: State<'_, AsyncWalletManager>,
 ) -> CommandResult<String> {
     info!("Command: derive_new_address with label: {:?}", label);
+    let (_, address) = derive_next_address(&wallet_manager, wallet_name.as_deref(), label).await?;
+    Ok(address)
+}
 
+/// Derive the next HD address of an open wallet (the active one when no name is given) and
+/// save it, returning the wallet's name and the new address
+async fn derive_next_address(
+    wallet_manager: &AsyncWalletManager,
+    wallet_name: Option<&str>,
+    label: Option<String>,
+) -> CommandResult<(String, String)> {
     let mut manager = wallet_manager.get_manager().await;
 
-    // First get the wallet name and secured status
-    let (_wallet_name, is_secured, wallet_path, master_private_key, next_index) = {
-        let current_wallet = match manager.get_current_wallet() {
+    // First get the wallet name and master key
+    let (wallet_name, wallet_path, master_private_key, next_index) = {
+        let current_wallet = match manager.get_wallet(wallet_name) {
             Some(wallet) => wallet,
             None => {
                 error!("No wallet is currently open");
-                return Err("No wallet is currently open".to_string());
+                return Err(WalletError::NoWalletOpen.into());
+            }
+        };
+
+        let wallet_name = current_wallet.name.clone();
+        let wallet_path = current_wallet.path.clone();
+        
+        let master_private_key = match &current_wallet.data.master_private_key {
+            Some(key) => key.clone(),
+            None => {
+                error!("No master private key available for key derivation");
+                return Err("Master private key not available for key derivation".to_string().into());
             }
         };
 
-        let wallet_name = current_wallet.name.clone();
-        let wallet_path = current_wallet.path.clone();
-        
-        let master_private_key = match &current_wallet.data.master_private_key {
-            Some(key) => key.clone(),
-            None => {
-                error!("No master private key available for key derivation");
-                return Err("Master private key not available for key derivation".to_string());
-            }
-        };
+        // Imported keys have no derivation path, so only HD addresses of the current seed advance the index
+        let next_index = current_wallet.data.receiving_addresses()
+            .filter(|addr_info| !addr_info.is_imported())
+            .count() as u32;
+
+        (wallet_name, wallet_path, master_private_key, next_index)
+    };
+
+    // Determine the derivation path
+    let derivation_path = format!("m/44'/0'/0'/0/{}", next_index);
+
+    info!("Deriving new address at path: {}", derivation_path);
+
+    // Parse the master private key
+    use bitcoin::bip32::{Xpriv, DerivationPath};
+    use bitcoin::secp256k1::Secp256k1;
+    use std::str::FromStr;
+
+    let secp = Secp256k1::new();
+    
+    let master_xpriv = Xpriv::from_str(master_private_key.expose_secret())
+        .map_err(|e| format!("Failed to parse master private key: {}", e))?;
+    let master_fingerprint = Some(master_xpriv.fingerprint(&secp).to_string());
+
+    let derivation_path_parsed = DerivationPath::from_str(&derivation_path)
+        .map_err(|e| format!("Failed to parse derivation path: {}", e))?;
+
+    // Derive the new key pair
+    let derived_xpriv = master_xpriv.derive_priv(&secp, &derivation_path_parsed)
+        .map_err(|e| format!("Failed to derive private key: {}", e))?;
+
+    let private_key = derived_xpriv.private_key;
+    let public_key = private_key.public_key(&secp);
+
+    // Create address (using P2WPKH - native segwit)
+    use bitcoin::{Address, PublicKey, PrivateKey, CompressedPublicKey};
+    let bitcoin_private_key = PrivateKey::new(private_key, ChainParams::active().address_network);
+    let compressed_pubkey = CompressedPublicKey::from_private_key(&secp, &bitcoin_private_key)
+        .map_err(|e| format!("Failed to create compressed public key: {}", e))?;
+    let address = Address::p2wpkh(&compressed_pubkey, ChainParams::active().hrp());
+    let address_string = address.to_string();
+
+    // Create the new key pair
+    let bitcoin_public_key = PublicKey::new(public_key);
+    let key_pair = crate::wallet_data::KeyPair {
+        private_key: bitcoin_private_key.to_wif().into(),
+        public_key: bitcoin_public_key.to_string(),
+        address: address_string.clone(),
+        key_type: crate::wallet_data::KeyType::NativeSegWit,
+        derivation_path: derivation_path.clone(),
+    };
+
+    // Create the new address info
+    let address_info = crate::wallet_data::AddressInfo {
+        address: address_string.clone(),
+        key_type: crate::wallet_data::KeyType::NativeSegWit,
+        derivation_path: derivation_path.clone(),
+        master_fingerprint,
+        public_key: key_pair.public_key.clone(),
+        label: label.clone(),
+        retired: false,
+    };
+
+    // Now get mutable access to update the wallet
+    let current_wallet = match manager.get_wallet_mut(Some(&wallet_name)) {
+        Some(wallet) => wallet,
+        None => {
+            error!("No wallet is currently open");
+            return Err(WalletError::NoWalletOpen.into());
+        }
+    };
+
+    // Add to wallet data
+    current_wallet.data.keys.insert(address_string.clone(), key_pair).map_err(WalletError::from)?;
+    current_wallet.data.addresses.push(address_info);
+
+    // Update the modified timestamp
+    current_wallet.data.modified_at = chrono::Utc::now().timestamp();
+
+    // A wallet opened read-only keeps the address for this session only
+    if let Some(reason) = current_wallet.data.read_only {
+        warn!("Derived address {} is not saved: {}", address_string, reason.describe());
+        return Ok((wallet_name, address_string));
+    }
+
+    // Save the wallet data to disk once the manager is released
+    drop(manager);
+    match wallet_manager.save_wallet(&wallet_name).await {
+        Ok(_) => {
+            info!("Successfully derived new address: {}", address_string);
+            let entry = ActivityEntry { address: Some(address_string.clone()), ..ActivityEntry::now(ActivityKind::AddressCreated) };
+            wallet_activity::record(&wallet_path, entry);
+            Ok((wallet_name, address_string))
+        }
+        Err(e) => {
+            error!("Failed to save wallet data: {}", e);
+            Err(format!("Failed to save wallet data: {}", e).into())
+        }
+    }
+}
+
+/// Command to import an individual private key (WIF) into the current wallet
+#[command]
+#[command_metrics::tracked]
+pub async fn import_private_key(
+    wif: String,
+    label: Option<String>,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    config_manager: State<'_, Arc<ConfigManager>>,
+    services: State<'_, ServiceContainer>,
+) -> CommandResult<String> {
+    info!("Command: import_private_key with label: {:?}", label);
+
+    use bitcoin::secp256k1::Secp256k1;
+    use bitcoin::{Address, CompressedPublicKey, PrivateKey};
+
+    // Validate the WIF before touching the wallet
+    let private_key = PrivateKey::from_wif(wif.trim())
+        .map_err(|e| format!("Invalid WIF private key: {}", e))?;
+
+    if !private_key.compressed {
+        return Err("Uncompressed private keys are not supported for native segwit addresses".to_string().into());
+    }
+
+    let secp = Secp256k1::new();
+    let compressed_pubkey = CompressedPublicKey::from_private_key(&secp, &private_key)
+        .map_err(|e| format!("Failed to create compressed public key: {}", e))?;
+    let address_string = Address::p2wpkh(&compressed_pubkey, ChainParams::active().hrp()).to_string();
+
+    let mut manager = wallet_manager.get_manager().await;
+
+    let wallet_name = {
+        let current_wallet = match manager.get_wallet(wallet_name.as_deref()) {
+            Some(wallet) => wallet,
+            None => {
+                error!("No wallet is currently open");
+                return Err(WalletError::NoWalletOpen.into());
+            }
+        };
+
+        if current_wallet.data.keys.contains_key(&address_string) {
+            warn!("Address {} already exists in wallet {}", address_string, current_wallet.name);
+            return Err(format!("Address '{}' already exists in current wallet", address_string).into());
+        }
+
+        current_wallet.name.clone()
+    };
+
+    let key_pair = crate::wallet_data::KeyPair {
+        private_key: private_key.to_wif().into(),
+        public_key: private_key.public_key(&secp).to_string(),
+        address: address_string.clone(),
+        key_type: crate::wallet_data::KeyType::NativeSegWit,
+        derivation_path: IMPORTED_KEY_DERIVATION_PATH.to_string(),
+    };
+
+    let address_info = crate::wallet_data::AddressInfo {
+        address: address_string.clone(),
+        key_type: crate::wallet_data::KeyType::NativeSegWit,
+        derivation_path: IMPORTED_KEY_DERIVATION_PATH.to_string(),
+        master_fingerprint: None,
+        public_key: key_pair.public_key.clone(),
+        label,
+        retired: false,
+    };
+
+    let current_wallet = match manager.get_wallet_mut(Some(&wallet_name)) {
+        Some(wallet) => wallet,
+        None => {
+            error!("No wallet is currently open");
+            return Err(WalletError::NoWalletOpen.into());
+        }
+    };
+
+    ensure_writable(&current_wallet.data)?;
+    current_wallet.data.keys.insert(address_string.clone(), key_pair).map_err(WalletError::from)?;
+    current_wallet.data.addresses.push(address_info);
+    current_wallet.data.modified_at = chrono::Utc::now().timestamp();
+
+    let wallet_addresses: Vec<String> = current_wallet.data.addresses.iter()
+        .map(|addr_info| addr_info.address.clone())
+        .collect();
+
+    // Release the wallet manager before saving and before the sync service needs it
+    drop(manager);
+
+    if let Err(e) = wallet_manager.save_wallet(&wallet_name).await {
+        error!("Failed to save wallet data: {}", e);
+        return Err(format!("Failed to save wallet data: {}", e).into());
+    }
+
+    if let Err(e) = config_manager.update_wallet_addresses(&wallet_name, wallet_addresses.clone()).await {
+        warn!("Failed to update wallet addresses in config: {}", e);
+    }
+
+    // Rescan so funds already sitting on the imported address show up in the balance
+    match services.require::<AsyncWalletSyncService>() {
+        Ok(wallet_sync) => {
+            if let Err(e) = wallet_sync.start_wallet_sync(wallet_name.clone(), wallet_addresses).await {
+                warn!("Failed to start rescan after key import for {}: {}", wallet_name, e);
+            }
+        }
+        Err(e) => warn!("Failed to start rescan after key import for {}: {}", wallet_name, e.message),
+    }
+
+    info!("Successfully imported private key for address: {}", address_string);
+    Ok(address_string)
+}
+
+/// Result of sweeping an external private key
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SweepResult {
+    pub txid: String,
+    pub source_address: String,
+    pub destination_address: String,
+    pub inputs_swept: usize,
+    pub total_input: u64,
+    pub fee: u64,
+    pub amount_sent: u64,
+    /// Fiat equivalent of the amount sent when the price feed is enabled
+    pub amount_sent_fiat: Option<FiatAmount>,
+}
+
+/// Message every input of a transaction signs
+fn transaction_signing_message(transaction: &Transaction) -> Result<bitcoin::secp256k1::Message, String> {
+    signature_cache::signing_message(transaction).map_err(|e| e.to_string())
+}
+
+/// Sign every input of a transaction that spends from a single key
+fn sign_transaction_inputs(
+    transaction: &mut Transaction,
+    private_key: &bitcoin::PrivateKey,
+) -> Result<(), String> {
+    let script_sig = signature_cache::sign(&transaction_signing_message(transaction)?, private_key);
+
+    for input in &mut transaction.inputs {
+        input.script_sig = script_sig.clone();
+    }
+
+    Ok(())
+}
+
+/// Sign each input of a transaction with the key of its own, `keys[i]` signing input `i`
+fn sign_transaction_inputs_with(
+    transaction: &mut Transaction,
+    keys: &[bitcoin::PrivateKey],
+) -> Result<(), String> {
+    let message = transaction_signing_message(transaction)?;
+
+    for (input, private_key) in transaction.inputs.iter_mut().zip(keys) {
+        input.script_sig = signature_cache::sign(&message, private_key);
+    }
+
+    Ok(())
+}
+
+/// Key pair of each coin a wallet is about to spend, in input order
+fn wallet_key_pairs(
+    wallet_data: &crate::wallet_data::WalletData,
+    inputs: &[crate::wallet_data::Utxo],
+) -> Result<Vec<crate::wallet_data::KeyPair>, WalletError> {
+    inputs
+        .iter()
+        .map(|utxo| {
+            wallet_data.keys.get(&utxo.address).cloned().ok_or_else(|| {
+                WalletError::KeyDerivationError(format!("No private key for address {}", utxo.address))
+            })
+        })
+        .collect()
+}
+
+/// Private keys of `key_pairs` of an open wallet, in order. They come from the security manager's
+/// key cache, so a secured wallet only signs while it is unlocked. Call this without holding the
+/// wallet manager.
+async fn signing_keys(
+    app_handle: &tauri::AppHandle,
+    wallet_name: &str,
+    key_pairs: &[crate::wallet_data::KeyPair],
+) -> CommandResult<Vec<bitcoin::PrivateKey>> {
+    let secured = app_handle
+        .try_state::<AsyncWalletManager>()
+        .is_some_and(|wallet_manager| wallet_manager.status().wallet(wallet_name).is_some_and(|info| info.secured));
+    let security_manager = app_handle
+        .try_state::<AsyncSecurityManager>()
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Security manager is not available"))?;
+    let keys = security_manager.get_manager().await.signing_keys(wallet_name, secured, key_pairs)?;
+    Ok(keys)
+}
+
+/// Add a signed transaction to the local mempool and relay it to peers, returning its txid.
+/// A relay failure is only logged since the mempool rebroadcasts what it holds.
+async fn submit_and_broadcast(
+    app_handle: &tauri::AppHandle,
+    mut transaction: Transaction,
+    kind: &str,
+) -> CommandResult<String> {
+    let mempool_service = crate::services::get::<AsyncMempoolService>(app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Mempool service is not running"))?;
+
+    let txid = mempool_service.add_transaction(transaction.clone(), TransactionSource::Local).await
+        .map_err(|e| format!("Failed to submit {} transaction: {}", kind, e))?;
+    transaction.txid = txid.clone();
+
+    if let Some(network_service) = crate::services::get::<crate::network_service::AsyncNetworkService>(app_handle) {
+        if let Err(e) = network_service.broadcast_transaction(transaction).await {
+            warn!("Failed to broadcast {} transaction {}: {}", kind, txid, e);
+        }
+    } else {
+        warn!("Network service not available, {} transaction {} only added to local mempool", kind, txid);
+    }
+
+    Ok(txid)
+}
+
+/// Command to choose which coins of a wallet (the active one when no name is given) would pay
+/// `amount` satoshis at `fee_rate` sat/byte. Only unfrozen coins with the wallet's minimum
+/// confirmations are considered. With `outpoints` ("txid:vout") exactly those coins are spent.
+#[command]
+#[command_metrics::tracked]
+pub async fn select_coins_for_payment(
+    amount: u64,
+    fee_rate: u64,
+    outpoints: Option<Vec<String>>,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<CoinSelection> {
+    info!("Command: select_coins_for_payment {} satoshis at {} sat/byte", amount, fee_rate);
+
+    crate::validation::validate_amount(amount)?;
+    if fee_rate == 0 {
+        return Err(CommandError::new(AppErrorCode::InvalidInput, "Fee rate must be greater than zero"));
+    }
+
+    let manager = wallet_manager.read_manager().await;
+    let wallet = match &wallet_name {
+        Some(name) => manager.get_wallet(Some(name.as_str())).ok_or_else(|| WalletError::NotFound(name.clone()))?,
+        None => manager.get_current_wallet().ok_or(WalletError::NoWalletOpen)?,
+    };
+    let control = CoinControl {
+        min_confirmations: wallet_min_confirmations(&wallet.path),
+        frozen: &wallet.data.frozen_utxos,
+    };
+
+    let tip_height = chain_tip_height(&app_handle).await;
+    let dust_threshold = dust_threshold(&app_handle).await;
+    let selection = match outpoints.filter(|outpoints| !outpoints.is_empty()) {
+        Some(outpoints) => coin_selection::select_chosen_coins(
+            &wallet.data.utxos,
+            &outpoints,
+            amount,
+            fee_rate,
+            dust_threshold,
+            tip_height,
+            &control,
+        )?,
+        None => {
+            coin_selection::select_coins(&wallet.data.utxos, amount, fee_rate, dust_threshold, tip_height, &control)?
+        }
+    };
+    Ok(selection)
+}
+
+/// A wallet coin as shown in coin control
+#[derive(Debug, Serialize)]
+pub struct WalletUtxoInfo {
+    /// "txid:vout", the identifier freeze_utxo and select_coins_for_payment take
+    pub outpoint: String,
+    pub txid: String,
+    pub vout: u32,
+    pub address: String,
+    pub amount: u64,
+    pub confirmations: u64,
+    /// Label of the receiving address
+    pub label: Option<String>,
+    pub is_change: bool,
+    pub frozen: bool,
+    /// Unfrozen and with the wallet's minimum confirmations
+    pub spendable: bool,
+}
+
+/// Command to list the coins of a wallet (the active one when no name is given), oldest first
+#[command]
+#[command_metrics::tracked]
+pub async fn list_utxos(
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<Vec<WalletUtxoInfo>> {
+    debug!("Command: list_utxos {:?}", wallet_name);
+
+    let manager = wallet_manager.read_manager().await;
+    let wallet = match &wallet_name {
+        Some(name) => manager.get_wallet(Some(name.as_str())).ok_or_else(|| WalletError::NotFound(name.clone()))?,
+        None => manager.get_current_wallet().ok_or(WalletError::NoWalletOpen)?,
+    };
+    let control = CoinControl {
+        min_confirmations: wallet_min_confirmations(&wallet.path),
+        frozen: &wallet.data.frozen_utxos,
+    };
+    let tip_height = chain_tip_height(&app_handle).await;
+
+    let mut utxos: Vec<&crate::wallet_data::Utxo> = wallet.data.utxos.iter().collect();
+    utxos.sort_by(|a, b| (a.height.is_none(), a.height, &a.txid, a.vout).cmp(&(b.height.is_none(), b.height, &b.txid, b.vout)));
+    Ok(utxos
+        .into_iter()
+        .map(|utxo| WalletUtxoInfo {
+            outpoint: coin_selection::outpoint(utxo),
+            txid: utxo.txid.clone(),
+            vout: utxo.vout,
+            address: utxo.address.clone(),
+            amount: utxo.value,
+            confirmations: coin_selection::confirmations(utxo.height, tip_height),
+            label: wallet
+                .data
+                .addresses
+                .iter()
+                .find(|a| a.address == utxo.address)
+                .and_then(|a| a.label.clone()),
+            is_change: utxo.is_change,
+            frozen: wallet.data.is_utxo_frozen(utxo),
+            spendable: coin_selection::is_spendable(utxo, tip_height, &control),
+        })
+        .collect())
+}
+
+/// Command to stop a coin from being spent until it is unfrozen
+#[command]
+#[command_metrics::tracked]
+pub async fn freeze_utxo(
+    outpoint: String,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<bool> {
+    info!("Command: freeze_utxo {}", outpoint);
+    set_utxo_frozen(&outpoint, true, wallet_name, &wallet_manager).await
+}
+
+/// Command to let a frozen coin be spent again
+#[command]
+#[command_metrics::tracked]
+pub async fn unfreeze_utxo(
+    outpoint: String,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<bool> {
+    info!("Command: unfreeze_utxo {}", outpoint);
+    set_utxo_frozen(&outpoint, false, wallet_name, &wallet_manager).await
+}
+
+/// Refuse a change that would have to be saved to a wallet opened read-only
+fn ensure_writable(wallet_data: &crate::wallet_data::WalletData) -> CommandResult<()> {
+    match wallet_data.read_only {
+        Some(reason) => Err(WalletError::from(crate::wallet_data::WalletDataError::ReadOnly(reason)).into()),
+        None => Ok(()),
+    }
+}
+
+/// Freeze or unfreeze a coin and save the wallet
+async fn set_utxo_frozen(
+    outpoint: &str,
+    frozen: bool,
+    wallet_name: Option<String>,
+    wallet_manager: &AsyncWalletManager,
+) -> CommandResult<bool> {
+    let (txid, vout) = coin_selection::parse_outpoint(outpoint)?;
+
+    let mut manager = wallet_manager.get_manager().await;
+    let wallet_name = match wallet_name {
+        Some(name) => name,
+        None => manager.get_current_wallet().ok_or(WalletError::NoWalletOpen)?.name.clone(),
+    };
+    let wallet = manager
+        .get_wallet_mut(Some(&wallet_name))
+        .ok_or_else(|| WalletError::NotFound(wallet_name.clone()))?;
+    ensure_writable(&wallet.data)?;
+
+    if !wallet.data.set_utxo_frozen(&txid, vout, frozen) {
+        return Err(coin_selection::CoinSelectionError::UnknownUtxo(outpoint.to_string()).into());
+    }
+
+    // Saved in the background by the wallet persister
+    manager.mark_dirty(&wallet_name);
+    info!("{} coin {} in wallet {}", if frozen { "Froze" } else { "Unfroze" }, outpoint, wallet_name);
+    Ok(true)
+}
+
+/// Highest fee rate, in sat/byte, a sweep accepts; anything above is taken to be a typo
+const MAX_FEE_RATE: u64 = 10_000;
+
+/// Command to sweep all funds held by an external private key (WIF) to a destination address.
+/// The foreign key is only used to sign and is never written to the wallet.
+#[command]
+#[command_metrics::tracked]
+pub async fn sweep_private_key(
+    wif: String,
+    destination_address: String,
+    fee_rate: u64,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<SweepResult> {
+    info!("Command: sweep_private_key to {} at {} sat/byte", destination_address, fee_rate);
+
+    if fee_rate > MAX_FEE_RATE {
+        return Err(CommandError::new(
+            AppErrorCode::InvalidInput,
+            format!("Fee rate must be at most {} sat/byte", MAX_FEE_RATE),
+        ));
+    }
+
+    use bitcoin::secp256k1::Secp256k1;
+    use bitcoin::{Address, CompressedPublicKey, PrivateKey};
+
+    let private_key = PrivateKey::from_wif(wif.trim())
+        .map_err(|e| format!("Invalid WIF private key: {}", e))?;
+
+    if !private_key.compressed {
+        return Err("Uncompressed private keys are not supported for native segwit addresses".to_string().into());
+    }
+
+    let destination_address = crate::validation::validate_address(&destination_address, ChainParams::active().network)
+        ?;
+
+    let secp = Secp256k1::new();
+    let compressed_pubkey = CompressedPublicKey::from_private_key(&secp, &private_key)
+        .map_err(|e| format!("Failed to create compressed public key: {}", e))?;
+    let source_address = Address::p2wpkh(&compressed_pubkey, ChainParams::active().hrp()).to_string();
+
+    let blockchain_db = crate::services::get::<crate::blockchain_database::AsyncBlockchainDatabase>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Blockchain services are not running"))?;
+
+    let utxos = blockchain_db.get_address_utxos(&source_address).await
+        .map_err(|e| format!("Failed to scan UTXOs for {}: {}", source_address, e))?;
+
+    if utxos.is_empty() {
+        return Err(format!("No funds found for address '{}'", source_address).into());
+    }
+
+    let total_input: u64 = utxos.iter().map(|utxo| utxo.value).sum();
+    let fee = fee_rate
+        .checked_mul(estimate_transaction_size(utxos.len(), 1) as u64)
+        .ok_or_else(|| CommandError::new(AppErrorCode::InvalidInput, "Fee for this sweep is too large"))?;
+
+    if fee >= total_input {
+        return Err(format!(
+            "Balance of {} satoshis is too small to cover a fee of {} satoshis",
+            total_input, fee
+        ).into());
+    }
+
+    let amount_sent = total_input - fee;
+
+    let mut transaction = Transaction {
+        txid: String::new(), // Will be calculated by the mempool
+        inputs: utxos.iter().map(|utxo| TransactionInput {
+            previous_txid: utxo.txid.clone(),
+            previous_output_index: utxo.output_index,
+            script_sig: String::new(),
+            sequence: 0xffffffff,
+        }).collect(),
+        outputs: vec![TransactionOutput {
+            value: amount_sent,
+            script_pubkey: String::new(),
+            address: destination_address.clone(),
+        }],
+        timestamp: chrono::Utc::now().timestamp() as u64,
+        fee,
+    };
+
+    sign_transaction_inputs(&mut transaction, &private_key)?;
+    let txid = submit_and_broadcast(&app_handle, transaction, "sweep").await?;
+
+    info!("Swept {} satoshis from {} to {} in transaction {}", amount_sent, source_address, destination_address, txid);
+
+    Ok(SweepResult {
+        txid,
+        source_address,
+        destination_address: destination_address.trim().to_string(),
+        inputs_swept: utxos.len(),
+        total_input,
+        fee,
+        amount_sent,
+        amount_sent_fiat: fiat_equivalent(&app_handle, amount_sent).await,
+    })
+}
+
+/// Result of merging small coins of a wallet into one
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConsolidationResult {
+    /// None for a dry run, which only previews the consolidation
+    pub txid: Option<String>,
+    pub destination_address: String,
+    pub inputs_consolidated: usize,
+    pub total_input: u64,
+    pub fee: u64,
+    pub output_value: u64,
+    /// Coins the wallet holds before and after the consolidation confirms
+    pub utxo_count_before: usize,
+    pub utxo_count_after: usize,
+}
+
+/// Command to merge up to `max_inputs` of the smallest spendable coins of a wallet (the active
+/// one when no name is given) into one output to the wallet's own first address. Best run
+/// while fees are low. With `dry_run` nothing is signed or sent; the result previews the fee
+/// and how many coins the wallet would be left with.
+#[command]
+#[command_metrics::tracked]
+pub async fn consolidate_utxos(
+    fee_rate: u64,
+    max_inputs: Option<usize>,
+    dry_run: Option<bool>,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<ConsolidationResult> {
+    let dry_run = dry_run.unwrap_or(false);
+    info!("Command: consolidate_utxos at {} sat/byte (dry run: {})", fee_rate, dry_run);
+
+    if fee_rate == 0 {
+        return Err(CommandError::new(AppErrorCode::InvalidInput, "Fee rate must be greater than zero"));
+    }
+    let max_inputs = max_inputs.unwrap_or(coin_selection::DEFAULT_MAX_CONSOLIDATION_INPUTS);
+    if !(2..=coin_selection::MAX_CONSOLIDATION_INPUTS).contains(&max_inputs) {
+        return Err(CommandError::new(
+            AppErrorCode::InvalidInput,
+            format!("Maximum inputs must be between 2 and {}", coin_selection::MAX_CONSOLIDATION_INPUTS),
+        ));
+    }
+
+    let tip_height = chain_tip_height(&app_handle).await;
+    let manager = wallet_manager.read_manager().await;
+    let wallet = manager.get_wallet(wallet_name.as_deref()).ok_or(WalletError::NoWalletOpen)?;
+    if wallet.data.is_watch_only() {
+        return Err(WalletError::InvalidOperation("Watch-only wallets cannot sign a consolidation".to_string()).into());
+    }
+
+    let control = coin_selection::CoinControl {
+        min_confirmations: wallet_min_confirmations(&wallet.path),
+        frozen: &wallet.data.frozen_utxos,
+    };
+    let plan = coin_selection::plan_consolidation(&wallet.data.utxos, fee_rate, max_inputs, tip_height, &control)?;
+    let destination_address = wallet
+        .data
+        .receiving_addresses()
+        .next()
+        .map(|a| a.address.clone())
+        .ok_or_else(|| WalletError::InvalidOperation("Wallet has no address to consolidate to".to_string()))?;
+
+    let utxo_count_before = wallet.data.utxos.len();
+    let mut result = ConsolidationResult {
+        txid: None,
+        destination_address,
+        inputs_consolidated: plan.inputs.len(),
+        total_input: plan.total_input,
+        fee: plan.fee,
+        output_value: plan.output_value,
+        utxo_count_before,
+        utxo_count_after: utxo_count_before - plan.inputs.len() + 1,
+    };
+    if dry_run {
+        return Ok(result);
+    }
+
+    let key_pairs = wallet_key_pairs(&wallet.data, &plan.inputs)?;
+    let wallet_name = wallet.name.clone();
+    drop(manager);
+
+    let keys = signing_keys(&app_handle, &wallet_name, &key_pairs).await?;
+    let mut transaction = Transaction {
+        txid: String::new(), // Will be calculated by the mempool
+        inputs: plan.inputs.iter().map(|utxo| TransactionInput {
+            previous_txid: utxo.txid.clone(),
+            previous_output_index: utxo.vout,
+            script_sig: String::new(),
+            sequence: 0xffffffff,
+        }).collect(),
+        outputs: vec![TransactionOutput {
+            value: plan.output_value,
+            script_pubkey: String::new(),
+            address: result.destination_address.clone(),
+        }],
+        timestamp: chrono::Utc::now().timestamp() as u64,
+        fee: plan.fee,
+    };
+
+    sign_transaction_inputs_with(&mut transaction, &keys)?;
+    let txid = submit_and_broadcast(&app_handle, transaction, "consolidation").await?;
+
+    info!(
+        "Consolidated {} coins of wallet {} into {} satoshis in transaction {}",
+        result.inputs_consolidated, wallet_name, result.output_value, txid
+    );
+    result.txid = Some(txid);
+    Ok(result)
+}
+
+/// Result of moving a wallet onto a new seed, or of sweeping its retired addresses again
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RekeyResult {
+    /// None for a dry run, or when no coin on the retired addresses was worth sweeping
+    pub txid: Option<String>,
+    /// First address of the new seed, which receives the swept funds
+    pub destination_address: String,
+    pub addresses_retired: usize,
+    pub inputs_swept: usize,
+    pub total_input: u64,
+    pub fee: u64,
+    pub amount_sent: u64,
+    /// Coins that stay on retired addresses: frozen, short of confirmations or worth less than
+    /// their fee. Sweep them once they can be spent.
+    pub coins_left_behind: usize,
+}
+
+impl RekeyResult {
+    /// What sweeping the retired addresses of `wallet_data` at `fee_rate` would do, and the plan
+    /// to do it when any coin there is worth its fee
+    fn plan(
+        wallet_data: &crate::wallet_data::WalletData,
+        wallet_dir: &std::path::Path,
+        fee_rate: u64,
+        tip_height: u64,
+    ) -> CommandResult<(Self, Option<coin_selection::ConsolidationPlan>)> {
+        let destination_address = wallet_data
+            .receiving_addresses()
+            .next()
+            .map(|a| a.address.clone())
+            .ok_or_else(|| WalletError::InvalidOperation("Wallet has no address to sweep to".to_string()))?;
+        let retired_coins: Vec<crate::wallet_data::Utxo> = wallet_data.utxos.iter()
+            .filter(|utxo| wallet_data.is_retired(&utxo.address))
+            .cloned()
+            .collect();
+
+        let control = CoinControl {
+            min_confirmations: wallet_min_confirmations(wallet_dir),
+            frozen: &wallet_data.frozen_utxos,
+        };
+        let plan = match coin_selection::plan_sweep(&retired_coins, fee_rate, tip_height, &control) {
+            Ok(plan) => Some(plan),
+            Err(coin_selection::CoinSelectionError::NothingToSweep) => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let result = Self {
+            txid: None,
+            destination_address,
+            addresses_retired: wallet_data.addresses.iter().filter(|a| a.retired).count(),
+            inputs_swept: plan.as_ref().map_or(0, |plan| plan.inputs.len()),
+            total_input: plan.as_ref().map_or(0, |plan| plan.total_input),
+            fee: plan.as_ref().map_or(0, |plan| plan.fee),
+            amount_sent: plan.as_ref().map_or(0, |plan| plan.output_value),
+            coins_left_behind: retired_coins.len() - plan.as_ref().map_or(0, |plan| plan.inputs.len()),
+        };
+        Ok((result, plan))
+    }
+}
+
+/// Sign and send a sweep of retired coins to `destination_address`, returning its txid
+async fn broadcast_retired_sweep(
+    app_handle: &tauri::AppHandle,
+    plan: &coin_selection::ConsolidationPlan,
+    keys: &[bitcoin::PrivateKey],
+    destination_address: &str,
+) -> CommandResult<String> {
+    let mut transaction = Transaction {
+        txid: String::new(), // Will be calculated by the mempool
+        inputs: plan.inputs.iter().map(|utxo| TransactionInput {
+            previous_txid: utxo.txid.clone(),
+            previous_output_index: utxo.vout,
+            script_sig: String::new(),
+            sequence: 0xffffffff,
+        }).collect(),
+        outputs: vec![TransactionOutput {
+            value: plan.output_value,
+            script_pubkey: String::new(),
+            address: destination_address.to_string(),
+        }],
+        timestamp: chrono::Utc::now().timestamp() as u64,
+        fee: plan.fee,
+    };
+
+    sign_transaction_inputs_with(&mut transaction, keys)?;
+    submit_and_broadcast(app_handle, transaction, "re-key sweep").await
+}
+
+/// Command to move a wallet (the active one when no name is given) onto a new seed, for when
+/// the old seed may be compromised. The frontend generates `new_seed_phrase` with
+/// `generate_seed_phrase` and has the user write it down first. Every address of the old seed,
+/// imported ones included, is retired: it stays synced and keeps its key so coins on it can be
+/// spent, but is never offered to receive again. The wallet is saved with the new seed before
+/// anything is sent, then all spendable coins on the retired addresses are swept to the first
+/// address of the new seed. Secured wallets must confirm their password. With `dry_run`
+/// nothing changes; the result previews the sweep.
+#[command]
+#[command_metrics::tracked]
+pub async fn rekey_wallet(
+    new_seed_phrase: String,
+    passphrase: Option<String>,
+    fee_rate: u64,
+    password: Option<String>,
+    dry_run: Option<bool>,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    config_manager: State<'_, Arc<ConfigManager>>,
+    services: State<'_, ServiceContainer>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<RekeyResult> {
+    let dry_run = dry_run.unwrap_or(false);
+    info!("Command: rekey_wallet at {} sat/byte (dry run: {})", fee_rate, dry_run);
+
+    if fee_rate == 0 {
+        return Err(CommandError::new(AppErrorCode::InvalidInput, "Fee rate must be greater than zero"));
+    }
+
+    let (wallet_name, is_secured) = {
+        let manager = wallet_manager.read_manager().await;
+        let wallet = manager.get_wallet(wallet_name.as_deref()).ok_or(WalletError::NoWalletOpen)?;
+        if wallet.data.is_watch_only() {
+            return Err(WalletError::InvalidOperation("Watch-only wallets have no keys to re-key".to_string()).into());
+        }
+        ensure_writable(&wallet.data)?;
+        let is_secured = manager.find_wallet_by_name(&wallet.name).is_some_and(|info| info.secured);
+        if is_secured {
+            let password = password.as_deref().ok_or_else(|| {
+                CommandError::new(AppErrorCode::PasswordRequired, "Re-keying a wallet requires its password")
+            })?;
+            manager.verify_password(&wallet.name, password)?;
+        }
+        (wallet.name.clone(), is_secured)
+    };
+
+    // Derive the new seed before taking the manager for writing; key stretching is slow
+    let fresh = crate::wallet_manager::WalletManager::wallet_data_from_seed(
+        &wallet_name,
+        new_seed_phrase.trim(),
+        passphrase.as_deref().unwrap_or(""),
+        is_secured,
+    )?;
+
+    let tip_height = chain_tip_height(&app_handle).await;
+    let (mut result, plan, key_pairs) = {
+        let manager = wallet_manager.read_manager().await;
+        let wallet = manager.get_wallet(Some(&wallet_name)).ok_or(WalletError::NoWalletOpen)?;
+        if fresh.master_fingerprint == wallet.data.master_fingerprint {
+            return Err(CommandError::new(AppErrorCode::InvalidInput, "The new seed is the one the wallet already uses"));
+        }
+
+        let mut rekeyed = wallet.data.clone();
+        rekeyed.rekey(fresh.clone()).map_err(WalletError::from)?;
+        let (result, plan) = RekeyResult::plan(&rekeyed, &wallet.path, fee_rate, tip_height)?;
+        let key_pairs = match &plan {
+            Some(plan) => wallet_key_pairs(&rekeyed, &plan.inputs)?,
+            None => Vec::new(),
+        };
+        (result, plan, key_pairs)
+    };
+    if dry_run {
+        return Ok(result);
+    }
+    // The old keys are needed for the sweep, so a locked wallet is refused before it changes
+    let keys = signing_keys(&app_handle, &wallet_name, &key_pairs).await?;
+
+    let mut manager = wallet_manager.get_manager().await;
+    let wallet = manager.get_wallet_mut(Some(&wallet_name)).ok_or(WalletError::NoWalletOpen)?;
+    let wallet_dir = wallet.path.clone();
+    let previous = wallet.data.clone();
+    wallet.data.rekey(fresh).map_err(WalletError::from)?;
+    let wallet_addresses = wallet.data.get_addresses();
+    manager.cancel_seed_challenge(&wallet_name);
+    drop(manager);
+
+    // Nothing is sent to the new seed until it is on disk. It is encrypted with the password the
+    // wallet was unlocked with, which verify_password checked the entered one against.
+    if let Err(e) = wallet_manager.save_wallet(&wallet_name).await {
+        error!("Failed to save re-keyed wallet {}: {}", wallet_name, e);
+        if let Some(wallet) = wallet_manager.get_manager().await.get_wallet_mut(Some(&wallet_name)) {
+            wallet.data = previous;
+        }
+        return Err(format!("Failed to save wallet data: {}", e).into());
+    }
+    logging::log_audit_event("wallet_rekeyed", &format!("wallet={}", wallet_name));
+
+    if let Err(e) = config_manager.update_wallet_addresses(&wallet_name, wallet_addresses.clone()).await {
+        warn!("Failed to update wallet addresses in config: {}", e);
+    }
+    let mut settings = WalletSettings::load(&wallet_dir).unwrap_or_default();
+    if settings.mining_address.as_ref().is_some_and(|address| previous.get_addresses().contains(address)) {
+        // Mining rewards must not keep arriving on the old seed
+        settings.mining_address = None;
+        if let Err(e) = settings.save(&wallet_dir) {
+            warn!("Failed to reset the mining address of wallet {}: {}", wallet_name, e);
+        }
+    }
+    match services.require::<AsyncWalletSyncService>() {
+        Ok(wallet_sync) => {
+            if let Err(e) = wallet_sync.start_wallet_sync(wallet_name.clone(), wallet_addresses).await {
+                warn!("Failed to start sync after re-keying {}: {}", wallet_name, e);
+            }
+        }
+        Err(e) => warn!("Failed to start sync after re-keying {}: {}", wallet_name, e.message),
+    }
+
+    if let Some(plan) = plan {
+        let txid = broadcast_retired_sweep(&app_handle, &plan, &keys, &result.destination_address)
+            .await
+            .map_err(|e| {
+                CommandError::new(e.code, format!("The wallet now uses the new seed, but sweeping the old addresses failed: {}", e.message))
+            })?;
+        result.txid = Some(txid);
+    }
+
+    info!(
+        "Re-keyed wallet {}: retired {} addresses, swept {} satoshis to {}",
+        wallet_name, result.addresses_retired, result.amount_sent, result.destination_address
+    );
+    Ok(result)
+}
+
+/// Command to sweep coins that reached the retired addresses of a re-keyed wallet (the active
+/// one when no name is given) to the first address of its current seed. With `dry_run` the
+/// result only previews the sweep.
+#[command]
+#[command_metrics::tracked]
+pub async fn sweep_retired_addresses(
+    fee_rate: u64,
+    dry_run: Option<bool>,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<RekeyResult> {
+    let dry_run = dry_run.unwrap_or(false);
+    info!("Command: sweep_retired_addresses at {} sat/byte (dry run: {})", fee_rate, dry_run);
+
+    if fee_rate == 0 {
+        return Err(CommandError::new(AppErrorCode::InvalidInput, "Fee rate must be greater than zero"));
+    }
+
+    let tip_height = chain_tip_height(&app_handle).await;
+    let manager = wallet_manager.read_manager().await;
+    let wallet = manager.get_wallet(wallet_name.as_deref()).ok_or(WalletError::NoWalletOpen)?;
+    let (mut result, plan) = RekeyResult::plan(&wallet.data, &wallet.path, fee_rate, tip_height)?;
+    let plan = match plan {
+        Some(plan) if !dry_run => plan,
+        Some(_) => return Ok(result),
+        None if dry_run => return Ok(result),
+        None => return Err(coin_selection::CoinSelectionError::NothingToSweep.into()),
+    };
+    let key_pairs = wallet_key_pairs(&wallet.data, &plan.inputs)?;
+    let wallet_name = wallet.name.clone();
+    drop(manager);
+
+    let keys = signing_keys(&app_handle, &wallet_name, &key_pairs).await?;
+    let txid = broadcast_retired_sweep(&app_handle, &plan, &keys, &result.destination_address).await?;
+    info!("Swept {} satoshis from the retired addresses of wallet {} in transaction {}", result.amount_sent, wallet_name, txid);
+    result.txid = Some(txid);
+    Ok(result)
+}
+
+/// Pay `amount` satoshis from the spendable coins of an open wallet and broadcast the
+/// transaction, returning its txid. Change goes back to the wallet's first address.
+pub(crate) async fn pay_from_wallet(
+    app_handle: &tauri::AppHandle,
+    wallet_name: &str,
+    recipient: &str,
+    amount: u64,
+    fee_rate: u64,
+) -> CommandResult<String> {
+    let preview = preview_payment(app_handle, wallet_name, recipient, amount, fee_rate, None).await?;
+    // Unattended payments cannot confirm a password, so sends above that threshold are refused
+    send_preview(app_handle, &preview, None).await
+}
+
+/// Choose the coins of an open wallet that pay `amount` satoshis to `recipient` at `fee_rate`
+/// sat/byte, exactly `outpoints` when given, without signing anything
+async fn preview_payment(
+    app_handle: &tauri::AppHandle,
+    wallet_name: &str,
+    recipient: &str,
+    amount: u64,
+    fee_rate: u64,
+    outpoints: Option<&[String]>,
+) -> CommandResult<TransactionPreview> {
+    let recipient = crate::validation::validate_address(recipient, ChainParams::active().network)?;
+    crate::validation::validate_amount(amount)?;
+
+    let tip_height = chain_tip_height(app_handle).await;
+    let dust_threshold = dust_threshold(app_handle).await;
+    let wallet_manager = app_handle
+        .try_state::<AsyncWalletManager>()
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Wallet manager is not available"))?;
+    let manager = wallet_manager.read_manager().await;
+    let wallet = manager
+        .get_wallet(Some(wallet_name))
+        .ok_or_else(|| WalletError::NotFound(wallet_name.to_string()))?;
+    if wallet.data.is_watch_only() {
+        return Err(WalletError::InvalidOperation("Watch-only wallets cannot sign payments".to_string()).into());
+    }
+
+    let control = CoinControl {
+        min_confirmations: wallet_min_confirmations(&wallet.path),
+        frozen: &wallet.data.frozen_utxos,
+    };
+    let selection = match outpoints.filter(|outpoints| !outpoints.is_empty()) {
+        Some(outpoints) => coin_selection::select_chosen_coins(
+            &wallet.data.utxos,
+            outpoints,
+            amount,
+            fee_rate,
+            dust_threshold,
+            tip_height,
+            &control,
+        )?,
+        None => {
+            coin_selection::select_coins(&wallet.data.utxos, amount, fee_rate, dust_threshold, tip_height, &control)?
+        }
+    };
+    let change_address = wallet
+        .data
+        .receiving_addresses()
+        .next()
+        .map(|a| a.address.clone())
+        .ok_or_else(|| WalletError::InvalidOperation("Wallet has no address for change".to_string()))?;
+
+    let mut outputs = vec![TransactionOutput {
+        value: amount,
+        script_pubkey: String::new(),
+        address: recipient,
+    }];
+    if selection.change > 0 {
+        outputs.push(TransactionOutput {
+            value: selection.change,
+            script_pubkey: String::new(),
+            address: change_address,
+        });
+    }
+
+    let size = coin_selection::estimate_transaction_size(selection.inputs.len(), outputs.len());
+    let created_at = chrono::Utc::now().timestamp();
+    Ok(TransactionPreview {
+        id: TransactionPreview::new_id(),
+        wallet_name: wallet.name.clone(),
+        amount,
+        total_input: selection.total_input,
+        change: selection.change,
+        fee: selection.fee,
+        size,
+        effective_fee_rate: selection.fee as f64 / size as f64,
+        inputs: selection.inputs,
+        outputs,
+        created_at,
+        expires_at: created_at + DRAFT_TTL_SECS,
+    })
+}
+
+/// Sign and broadcast a previewed payment, returning its txid. The wallet's spending policy is
+/// checked here, and the coins must still be unspent and unfrozen.
+async fn send_preview(
+    app_handle: &tauri::AppHandle,
+    preview: &TransactionPreview,
+    confirm_password: Option<&str>,
+) -> CommandResult<String> {
+    let wallet_manager = app_handle
+        .try_state::<AsyncWalletManager>()
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Wallet manager is not available"))?;
+    let manager = wallet_manager.read_manager().await;
+    let wallet = manager
+        .get_wallet(Some(&preview.wallet_name))
+        .ok_or_else(|| WalletError::NotFound(preview.wallet_name.clone()))?;
+    if wallet.data.is_watch_only() {
+        return Err(WalletError::InvalidOperation("Watch-only wallets cannot sign payments".to_string()).into());
+    }
+    let unchanged = preview.inputs.iter().all(|input| {
+        !wallet.data.frozen_utxos.contains(&coin_selection::outpoint(input))
+            && wallet.data.utxos.iter().any(|utxo| utxo.txid == input.txid && utxo.vout == input.vout)
+    });
+    if !unchanged {
+        return Err(CommandError::new(
+            AppErrorCode::InvalidOperation,
+            "The wallet's coins changed since the preview; preview the payment again",
+        ));
+    }
+
+    let payments: Vec<(&str, u64)> = preview
+        .outputs
+        .iter()
+        .filter(|output| !wallet.data.addresses.iter().any(|a| a.address == output.address))
+        .map(|output| (output.address.as_str(), output.value))
+        .collect();
+    let cooling_off_key = check_spending_policy(&manager, &wallet.name, &wallet.path, &payments, confirm_password)?;
+    let entry = ActivityEntry {
+        address: payments.first().map(|(address, _)| address.to_string()),
+        amount: Some(payments.iter().map(|(_, value)| value).sum()),
+        ..ActivityEntry::now(ActivityKind::TransactionSent)
+    };
+    let key_pairs = wallet_key_pairs(&wallet.data, &preview.inputs)?;
+    let wallet_dir = wallet.path.clone();
+    drop(manager);
+
+    let keys = signing_keys(app_handle, &preview.wallet_name, &key_pairs).await?;
+
+    let mut transaction = Transaction {
+        txid: String::new(), // Will be calculated by the mempool
+        inputs: preview.inputs.iter().map(|utxo| TransactionInput {
+            previous_txid: utxo.txid.clone(),
+            previous_output_index: utxo.vout,
+            script_sig: String::new(),
+            sequence: 0xffffffff,
+        }).collect(),
+        outputs: preview.outputs.clone(),
+        timestamp: chrono::Utc::now().timestamp() as u64,
+        fee: preview.fee,
+    };
+
+    sign_transaction_inputs_with(&mut transaction, &keys)?;
+    let txid = submit_and_broadcast(app_handle, transaction, "payment").await?;
+
+    wallet_activity::record(&wallet_dir, ActivityEntry { txid: Some(txid.clone()), ..entry });
+    if let Some(key) = &cooling_off_key {
+        spending_policy::release(&wallet_dir, key);
+    }
+    Ok(txid)
+}
+
+/// Command to prepare a payment from a wallet (the active one when no name is given) for
+/// confirmation. Coins are chosen as for sending, exactly `outpoints` when given, but nothing is
+/// signed; the preview can be committed with `commit_transaction` until it expires.
+#[command]
+#[command_metrics::tracked]
+pub async fn preview_transaction(
+    recipient: String,
+    amount: u64,
+    fee_rate: u64,
+    outpoints: Option<Vec<String>>,
+    wallet_name: Option<String>,
+    drafts: State<'_, TransactionDrafts>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<TransactionPreview> {
+    info!("Command: preview_transaction {} satoshis at {} sat/byte", amount, fee_rate);
+
+    if fee_rate == 0 {
+        return Err(CommandError::new(AppErrorCode::InvalidInput, "Fee rate must be greater than zero"));
+    }
+    let wallet_name = match wallet_name {
+        Some(name) => name,
+        None => {
+            let manager = wallet_manager.read_manager().await;
+            manager.get_current_wallet().ok_or(WalletError::NoWalletOpen)?.name.clone()
+        }
+    };
+
+    let preview = preview_payment(&app_handle, &wallet_name, &recipient, amount, fee_rate, outpoints.as_deref()).await?;
+    drafts.insert(preview.clone());
+    Ok(preview)
+}
+
+/// Command to sign and broadcast a payment prepared by `preview_transaction`, returning its txid.
+/// `confirm_password` is the wallet password when its spending policy asks for it. A preview
+/// that fails to send can be committed again until it expires.
+#[command]
+#[command_metrics::tracked]
+pub async fn commit_transaction(
+    preview_id: String,
+    confirm_password: Option<String>,
+    drafts: State<'_, TransactionDrafts>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<String> {
+    info!("Command: commit_transaction {}", preview_id);
+
+    let preview = drafts.take(&preview_id, chrono::Utc::now().timestamp()).ok_or_else(|| {
+        CommandError::new(AppErrorCode::InvalidInput, "The preview expired or was already sent; preview the payment again")
+    })?;
+    let result = send_preview(&app_handle, &preview, confirm_password.as_deref()).await;
+    match &result {
+        Ok(txid) => info!("Sent previewed payment {} in transaction {}", preview.id, txid),
+        Err(_) => drafts.insert(preview),
+    }
+    result
+}
+
+/// Refuse a send from a wallet that its spending policy blocks. `payments` are the recipients
+/// outside the wallet and what they get. Returns the key of a large send that waited out its
+/// cooling-off period, to release once it went out.
+fn check_spending_policy(
+    manager: &crate::wallet_manager::WalletManager,
+    wallet_name: &str,
+    wallet_dir: &std::path::Path,
+    payments: &[(&str, u64)],
+    confirm_password: Option<&str>,
+) -> CommandResult<Option<String>> {
+    let policy = WalletSettings::load(wallet_dir).unwrap_or_default().spending_policy;
+    let amount: u64 = payments.iter().map(|(_, value)| value).sum();
+    let now = chrono::Utc::now().timestamp();
+
+    let spent_today = match policy.daily_limit {
+        Some(_) => WalletActivityLog::load(wallet_dir).map_err(format_error)?.sent_since(now - SPEND_WINDOW_SECS),
+        None => 0,
+    };
+    let password_confirmed = match confirm_password {
+        Some(password) if policy.confirm_password_above.is_some_and(|threshold| amount > threshold) => {
+            manager.verify_password(wallet_name, password)?;
+            true
+        }
+        _ => false,
+    };
+    // The cooling-off period only starts once the send passes the other limits
+    let result = policy.check_limits(amount, spent_today, password_confirmed);
+    let cooling_off_key = policy.needs_cooling_off(amount).then(|| spending_policy::send_key(payments));
+    let result = match (&cooling_off_key, result) {
+        (Some(key), Ok(())) => {
+            let requested_at = spending_policy::pending_since(wallet_dir, key, &policy, now).map_err(format_error)?;
+            policy.check_cooling_off(requested_at, now)
+        }
+        (_, result) => result,
+    };
+
+    if let Err(block) = result {
+        info!("Send of {} satoshis from wallet {} blocked: {}", amount, wallet_name, block);
+        return Err(block.into());
+    }
+    Ok(cooling_off_key)
+}
+
+// Scheduled payment commands
+
+/// Command to list recurring payments, soonest first
+#[command]
+#[command_metrics::tracked]
+pub async fn list_scheduled_payments(
+    scheduler: State<'_, AsyncPaymentScheduler>,
+) -> CommandResult<Vec<ScheduledPayment>> {
+    info!("Command: list_scheduled_payments");
+    Ok(scheduler.list().await)
+}
+
+/// Command to schedule a recurring payment from a wallet
+#[command]
+#[command_metrics::tracked]
+pub async fn create_scheduled_payment(
+    payment: NewScheduledPayment,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    scheduler: State<'_, AsyncPaymentScheduler>,
+) -> CommandResult<ScheduledPayment> {
+    info!("Command: create_scheduled_payment from wallet {}", payment.wallet_name);
+
+    let mut payment = payment;
+    payment.recipient = crate::validation::validate_address(&payment.recipient, ChainParams::active().network)?;
+    crate::validation::validate_amount(payment.amount)?;
+    {
+        let manager = wallet_manager.read_manager().await;
+        if manager.find_wallet_by_name(&payment.wallet_name).is_none() {
+            return Err(WalletError::NotFound(payment.wallet_name.clone()).into());
+        }
+    }
+
+    scheduler.add(payment).await
+}
+
+/// Command to delete a scheduled payment
+#[command]
+#[command_metrics::tracked]
+pub async fn delete_scheduled_payment(
+    id: String,
+    scheduler: State<'_, AsyncPaymentScheduler>,
+) -> CommandResult<bool> {
+    info!("Command: delete_scheduled_payment {}", id);
+    Ok(scheduler.remove(&id).await)
+}
+
+/// Command to pause or resume a scheduled payment
+#[command]
+#[command_metrics::tracked]
+pub async fn set_scheduled_payment_enabled(
+    id: String,
+    enabled: bool,
+    scheduler: State<'_, AsyncPaymentScheduler>,
+) -> CommandResult<ScheduledPayment> {
+    info!("Command: set_scheduled_payment_enabled {} {}", id, enabled);
+    scheduler.set_enabled(&id, enabled).await
+}
+
+/// Command to send a due payment that waits for confirmation, returning its txid
+#[command]
+#[command_metrics::tracked]
+pub async fn confirm_scheduled_payment(
+    id: String,
+    scheduler: State<'_, AsyncPaymentScheduler>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<String> {
+    info!("Command: confirm_scheduled_payment {}", id);
+    scheduler.confirm(&app_handle, &id).await
+}
+
+/// Command to skip the current occurrence of a scheduled payment
+#[command]
+#[command_metrics::tracked]
+pub async fn skip_scheduled_payment(
+    id: String,
+    scheduler: State<'_, AsyncPaymentScheduler>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<ScheduledPayment> {
+    info!("Command: skip_scheduled_payment {}", id);
+    scheduler.skip(&app_handle, &id).await
+}
+
+// Payment request commands
+
+/// Command to request a payment to a fresh address of a wallet (the active one when no name is
+/// given). The returned request carries a brad:// URI to share; wallet sync tracks what arrives.
+#[command]
+#[command_metrics::tracked]
+pub async fn create_payment_request(
+    amount: u64,
+    label: Option<String>,
+    message: Option<String>,
+    expiry_secs: Option<u64>,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<PaymentRequest> {
+    info!("Command: create_payment_request for {} satoshis", amount);
+
+    crate::validation::validate_amount(amount)?;
+    let label = label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+    let message = message.map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+    if expiry_secs == Some(0) {
+        return Err(CommandError::new(AppErrorCode::InvalidInput, "Expiry must be greater than zero"));
+    }
+
+    let (wallet_name, address) = derive_next_address(&wallet_manager, wallet_name.as_deref(), label.clone()).await?;
+    let wallet_path = {
+        let manager = wallet_manager.read_manager().await;
+        manager.get_wallet(Some(&wallet_name)).ok_or(WalletError::NoWalletOpen)?.path.clone()
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let uri = crate::payment_uri::format_payment_uri(&PaymentUri {
+        address: address.clone(),
+        amount: Some(amount),
+        label: label.clone(),
+        message: message.clone(),
+    });
+    let request = PaymentRequest {
+        id: hex::encode(rand::rng().random::<[u8; 8]>()),
+        address,
+        amount,
+        label,
+        message,
+        uri,
+        created_at: now,
+        expires_at: expiry_secs.map(|secs| now.saturating_add(secs.min(i64::MAX as u64) as i64)),
+        status: PaymentRequestStatus::Pending,
+        received: 0,
+        paid_at: None,
+        outputs: Default::default(),
+    };
+
+    let mut book = PaymentRequestBook::load(&wallet_path).map_err(format_error)?;
+    book.add(request.clone());
+    book.save(&wallet_path).map_err(format_error)?;
+    info!("Created payment request {} on {} for wallet {}", request.id, request.address, wallet_name);
+    Ok(request)
+}
+
+/// Command to list the payment requests of a wallet, newest first, with expiry brought up to date
+#[command]
+#[command_metrics::tracked]
+pub async fn list_payment_requests(
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<Vec<PaymentRequest>> {
+    info!("Command: list_payment_requests");
+
+    let manager = wallet_manager.read_manager().await;
+    let wallet = manager.get_wallet(wallet_name.as_deref()).ok_or(WalletError::NoWalletOpen)?;
+    let mut book = PaymentRequestBook::load(&wallet.path).map_err(format_error)?;
+    if !book.record_sync(&wallet.data.utxos, chrono::Utc::now().timestamp()).is_empty() {
+        if let Err(e) = book.save(&wallet.path) {
+            warn!("Failed to save payment requests for {}: {}", wallet.name, e);
+        }
+    }
+    Ok(book.requests())
+}
+
+/// Command to delete a payment request. The address stays in the wallet.
+#[command]
+#[command_metrics::tracked]
+pub async fn delete_payment_request(
+    id: String,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<bool> {
+    info!("Command: delete_payment_request {}", id);
+
+    let manager = wallet_manager.read_manager().await;
+    let wallet = manager.get_wallet(wallet_name.as_deref()).ok_or(WalletError::NoWalletOpen)?;
+    let mut book = PaymentRequestBook::load(&wallet.path).map_err(format_error)?;
+    if !book.remove(&id) {
+        return Ok(false);
+    }
+    book.save(&wallet.path).map_err(format_error)?;
+    Ok(true)
+}
+
+/// Command to read a wallet's activity feed, newest first. The wallet does not need to be open.
+#[command]
+#[command_metrics::tracked]
+pub async fn get_wallet_activity(
+    wallet_id: String,
+    limit: Option<usize>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<Vec<ActivityEntry>> {
+    debug!("Command: get_wallet_activity for {}", wallet_id);
+
+    let wallet_dir = {
+        let manager = wallet_manager.read_manager().await;
+        // The activity log is the main profile's; a decoy shows none of it
+        if manager.get_wallet(Some(&wallet_id)).is_some_and(|wallet| wallet.data.profile == WalletProfile::Decoy) {
+            return Ok(Vec::new());
+        }
+        manager.get_wallet_dir(&wallet_id).ok_or_else(|| WalletError::NotFound(wallet_id.clone()))?
+    };
+    let log = WalletActivityLog::load(&wallet_dir).map_err(format_error)?;
+    Ok(log.recent(limit.unwrap_or(100)))
+}
+
+// Label and contact commands
+
+/// Command to write the address labels, transaction notes and contacts of an open wallet (the
+/// active one when no name is given) to a file, signed by the wallet, without any keys
+#[command]
+#[command_metrics::tracked]
+pub async fn export_wallet_labels(
+    path: String,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<bool> {
+    info!("Command: export_wallet_labels to {}", path);
+
+    let manager = wallet_manager.read_manager().await;
+    let wallet = manager.get_wallet(wallet_name.as_deref()).ok_or(WalletError::NoWalletOpen)?;
+    let signing_address = wallet
+        .data
+        .addresses
+        .first()
+        .ok_or_else(|| WalletError::InvalidOperation("Wallet has no address to sign with".to_string()))?;
+    if wallet.data.is_watch_only() {
+        return Err(WalletError::InvalidOperation("Watch-only wallets cannot sign a label export".to_string()).into());
+    }
+    let key_pair = wallet.data.keys.get(&signing_address.address).cloned().ok_or_else(|| {
+        WalletError::KeyDerivationError(format!("No private key for address {}", signing_address.address))
+    })?;
+    let contacts = ContactBook::load(&wallet.path).map_err(format_error)?;
+    let mut export = LabelExport::collect(&wallet.data, &contacts, chrono::Utc::now().timestamp());
+    let wallet_name = wallet.name.clone();
+    drop(manager);
+
+    let key = signing_keys(&app_handle, &wallet_name, std::slice::from_ref(&key_pair)).await?.remove(0);
+    export.sign(&key);
+    let contents = serde_json::to_string_pretty(&export).map_err(format_error)?;
+    atomic_file::write_atomic(std::path::Path::new(&path), contents.as_bytes()).map_err(|e| {
+        error!("Failed to write label export {}: {}", path, e);
+        format!("Failed to write label file: {}", e)
+    })?;
 
-        let next_index = current_wallet.data.addresses.len() as u32;
-        
-        let is_secured = if let Some(wallet_info) = manager.find_wallet_by_name(&wallet_name) {
-            wallet_info.secured
-        } else {
-            false
-        };
+    info!(
+        "Exported {} label(s), {} note(s) and {} contact(s) of wallet {} to {}",
+        export.address_labels.len(), export.transaction_notes.len(), export.contacts.len(), wallet_name, path
+    );
+    Ok(true)
+}
 
-        (wallet_name, is_secured, wallet_path, master_private_key, next_index)
-    };
+/// Command to merge a label file written by export_wallet_labels into an open wallet (the active
+/// one when no name is given). The file must be signed by a copy of the same wallet.
+#[command]
+#[command_metrics::tracked]
+pub async fn import_wallet_labels(
+    path: String,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<LabelImportSummary> {
+    info!("Command: import_wallet_labels from {}", path);
 
-    // Determine the derivation path
-    let derivation_path = format!("m/44'/0'/0'/0/{}", next_index);
+    let contents = tokio::fs::read_to_string(&path).await.map_err(|e| {
+        error!("Failed to read label file {}: {}", path, e);
+        format!("Failed to read label file: {}", e)
+    })?;
 
-    info!("Deriving new address at path: {}", derivation_path);
+    let mut manager = wallet_manager.get_manager().await;
+    let wallet_name = match wallet_name {
+        Some(name) => name,
+        None => manager.get_current_wallet().ok_or(WalletError::NoWalletOpen)?.name.clone(),
+    };
+    let wallet = manager
+        .get_wallet_mut(Some(&wallet_name))
+        .ok_or_else(|| WalletError::NotFound(wallet_name.clone()))?;
+    ensure_writable(&wallet.data)?;
+
+    let export = LabelExport::parse_verified(&contents, wallet.data.addresses.iter().map(|a| a.address.as_str()))
+        .map_err(|e| {
+            warn!("Rejected label file {}: {}", path, e);
+            CommandError::new(AppErrorCode::InvalidInput, e.to_string())
+        })?;
+    let mut contacts = ContactBook::load(&wallet.path).map_err(format_error)?;
+    let summary = export.apply(&mut wallet.data, &mut contacts);
+
+    let wallet_data_changed = summary.labels_updated + summary.notes_updated > 0;
+    let wallet_dir = wallet.path.clone();
+    if wallet_data_changed {
+        wallet.data.modified_at = chrono::Utc::now().timestamp();
+        // Saved in the background by the wallet persister
+        manager.mark_dirty(&wallet_name);
+    }
+    drop(manager);
 
-    // Parse the master private key
-    use bitcoin::bip32::{Xpriv, DerivationPath};
-    use bitcoin::secp256k1::Secp256k1;
-    use std::str::FromStr;
+    if summary.contacts_updated > 0 {
+        contacts.save(&wallet_dir).map_err(format_error)?;
+    }
 
-    let secp = Secp256k1::new();
-    
-    let master_xpriv = Xpriv::from_str(&master_private_key)
-        .map_err(|e| format!("Failed to parse master private key: {}", e))?;
+    info!("Imported labels into wallet {}: {:?}", wallet_name, summary);
+    Ok(summary)
+}
 
-    let derivation_path_parsed = DerivationPath::from_str(&derivation_path)
-        .map_err(|e| format!("Failed to parse derivation path: {}", e))?;
+// Ownership proof commands
 
-    // Derive the new key pair
-    let derived_xpriv = master_xpriv.derive_priv(&secp, &derivation_path_parsed)
-        .map_err(|e| format!("Failed to derive private key: {}", e))?;
+/// Command to prove that an open wallet (the active one when no name is given) holds the key of
+/// `address`, by signing `message` with it as BIP322 describes
+#[command]
+#[command_metrics::tracked]
+pub async fn create_ownership_proof(
+    address: String,
+    message: String,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<OwnershipProof> {
+    info!("Command: create_ownership_proof for {}", address);
+
+    let address = address.trim();
+    let manager = wallet_manager.read_manager().await;
+    let wallet = manager.get_wallet(wallet_name.as_deref()).ok_or(WalletError::NoWalletOpen)?;
+    let key_pair = wallet.data.keys.get(address).cloned().ok_or_else(|| {
+        CommandError::new(AppErrorCode::NotFound, format!("Address '{}' does not belong to wallet '{}'", address, wallet.name))
+    })?;
+    if key_pair.private_key.is_empty() {
+        return Err(WalletError::InvalidOperation("Watch-only wallets cannot sign an ownership proof".to_string()).into());
+    }
+    let wallet_name = wallet.name.clone();
+    drop(manager);
 
-    let private_key = derived_xpriv.private_key;
-    let public_key = private_key.public_key(&secp);
+    let key = signing_keys(&app_handle, &wallet_name, std::slice::from_ref(&key_pair)).await?.remove(0);
+    let proof = ownership_proof::sign(address, &message, &key, &key_pair.key_type)?;
 
-    // Create address (using P2WPKH - native segwit)
-    use bitcoin::{Address, Network, PublicKey, PrivateKey, CompressedPublicKey, KnownHrp};
-    let bitcoin_private_key = PrivateKey::new(private_key, Network::Bitcoin);
-    let compressed_pubkey = CompressedPublicKey::from_private_key(&secp, &bitcoin_private_key)
-        .map_err(|e| format!("Failed to create compressed public key: {}", e))?;
-    let address = Address::p2wpkh(&compressed_pubkey, KnownHrp::Mainnet);
-    let address_string = address.to_string();
+    logging::log_audit_event("ownership_proof_created", &format!("wallet={} address={}", wallet_name, address));
+    Ok(proof)
+}
 
-    // Create the new key pair
-    let bitcoin_public_key = PublicKey::new(public_key);
-    let key_pair = crate::wallet_data::KeyPair {
-        private_key: bitcoin_private_key.to_wif(),
-        public_key: bitcoin_public_key.to_string(),
-        address: address_string.clone(),
-        key_type: crate::wallet_data::KeyType::NativeSegWit,
-        derivation_path: derivation_path.clone(),
-    };
+/// Command to check an ownership proof made by this or any other BIP322 wallet
+#[command]
+#[command_metrics::tracked]
+pub async fn verify_ownership_proof(proof: OwnershipProof) -> CommandResult<bool> {
+    info!("Command: verify_ownership_proof for {}", proof.address);
+    Ok(ownership_proof::verify(&proof)?)
+}
 
-    // Create the new address info
-    let address_info = crate::wallet_data::AddressInfo {
-        address: address_string.clone(),
-        key_type: crate::wallet_data::KeyType::NativeSegWit,
-        derivation_path: derivation_path.clone(),
-        label: label.clone(),
-    };
+/// Command to list the contacts of a wallet (the active one when no name is given)
+#[command]
+#[command_metrics::tracked]
+pub async fn list_contacts(
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<Vec<Contact>> {
+    debug!("Command: list_contacts {:?}", wallet_name);
+    let wallet_dir = contacts_dir(wallet_name, &wallet_manager).await?;
+    Ok(ContactBook::load(&wallet_dir).map_err(format_error)?.contacts)
+}
 
-    // Now get mutable access to update the wallet
-    let current_wallet = match manager.get_current_wallet_mut() {
-        Some(wallet) => wallet,
-        None => {
-            error!("No wallet is currently open");
-            return Err("No wallet is currently open".to_string());
-        }
-    };
+/// Command to add a contact to a wallet (the active one when no name is given), replacing any
+/// contact with the same address
+#[command]
+#[command_metrics::tracked]
+pub async fn save_contact(
+    contact: Contact,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<bool> {
+    info!("Command: save_contact {}", contact.address);
 
-    // Add to wallet data
-    current_wallet.data.keys.insert(address_string.clone(), key_pair);
-    current_wallet.data.addresses.push(address_info);
+    if contact.name.trim().is_empty() {
+        return Err(CommandError::new(AppErrorCode::InvalidInput, "Contact name cannot be empty"));
+    }
+    crate::validation::validate_address(&contact.address, ChainParams::active().network)?;
 
-    // Update the modified timestamp
-    current_wallet.data.modified_at = chrono::Utc::now().timestamp();
+    let wallet_dir = contacts_dir(wallet_name, &wallet_manager).await?;
+    let mut book = ContactBook::load(&wallet_dir).map_err(format_error)?;
+    if book.upsert(contact) {
+        book.save(&wallet_dir).map_err(format_error)?;
+    }
+    Ok(true)
+}
 
-    // Get the wallet data file path
-    let wallet_data_path = wallet_path.join("wallet.dat");
+/// Command to remove the contact with `address` from a wallet (the active one when no name is given)
+#[command]
+#[command_metrics::tracked]
+pub async fn remove_contact(
+    address: String,
+    wallet_name: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> CommandResult<bool> {
+    info!("Command: remove_contact {}", address);
 
-    // Save the wallet data to disk
-    // Note: Since this is an open wallet, if it's secured, it would have been unlocked already
-    match current_wallet.data.save(&wallet_data_path, if is_secured { Some("") } else { None }) {
-        Ok(_) => {
-            info!("Successfully derived new address: {}", address_string);
-            Ok(address_string)
-        }
-        Err(e) => {
-            error!("Failed to save wallet data: {}", e);
-            Err(format!("Failed to save wallet data: {}", e))
-        }
+    let wallet_dir = contacts_dir(wallet_name, &wallet_manager).await?;
+    let mut book = ContactBook::load(&wallet_dir).map_err(format_error)?;
+    if !book.remove(&address) {
+        return Ok(false);
     }
+    book.save(&wallet_dir).map_err(format_error)?;
+    Ok(true)
+}
+
+/// Directory holding the contacts of a wallet (the active one when no name is given)
+async fn contacts_dir(wallet_name: Option<String>, wallet_manager: &AsyncWalletManager) -> CommandResult<std::path::PathBuf> {
+    let manager = wallet_manager.read_manager().await;
+    let wallet_name = match wallet_name {
+        Some(name) => name,
+        None => manager.get_current_wallet().ok_or(WalletError::NoWalletOpen)?.name.clone(),
+    };
+    manager.get_wallet_dir(&wallet_name).ok_or_else(|| WalletError::NotFound(wallet_name).into())
 }
 
 // Transaction submission and mempool commands
@@ -2556,15 +5796,43 @@ pub struct TransactionSubmission {
     pub fee: u64,
 }
 
-/// Submit a transaction to the mempool
+/// Submit a transaction to the mempool. Sends from a wallet are held to its spending policy;
+/// `confirm_password` is the wallet password when the policy asks for it.
 #[command]
+#[command_metrics::tracked]
 pub async fn submit_transaction(
     state: State<'_, crate::AppState>,
     transaction_data: TransactionSubmission,
+    wallet_name: Option<String>,
+    confirm_password: Option<String>,
+    wallet_manager: State<'_, AsyncWalletManager>,
 ) -> CommandResult<String> {
     info!("Submitting transaction to mempool");
-    
+
+    let network = ChainParams::active().network;
+    for output in &transaction_data.outputs {
+        crate::validation::validate_address(&output.address, network)?;
+        crate::validation::validate_amount(output.value)?;
+    }
+
+    let cooling_off_key = {
+        let manager = wallet_manager.read_manager().await;
+        match manager.get_wallet(wallet_name.as_deref()) {
+            Some(wallet) => {
+                let payments: Vec<(&str, u64)> = transaction_data
+                    .outputs
+                    .iter()
+                    .filter(|output| !wallet.data.addresses.iter().any(|a| a.address == output.address))
+                    .map(|output| (output.address.as_str(), output.value))
+                    .collect();
+                check_spending_policy(&manager, &wallet.name, &wallet.path, &payments, confirm_password.as_deref())?
+            }
+            None => None,
+        }
+    };
+
     // Create transaction from submission data
+    let outputs = transaction_data.outputs.clone();
     let transaction = Transaction {
         txid: String::new(), // Will be calculated during validation
         inputs: transaction_data.inputs,
@@ -2572,27 +5840,90 @@ pub async fn submit_transaction(
         timestamp: chrono::Utc::now().timestamp() as u64,
         fee: transaction_data.fee,
     };
-    
+
     // Submit to mempool
-    match state.mempool_service.add_transaction(transaction).await {
+    match state.mempool_service.add_transaction(transaction, TransactionSource::Local).await {
         Ok(tx_hash) => {
             info!("Transaction submitted successfully: {}", tx_hash);
+            // Record the payment in the sending wallet's activity, counting only what left it
+            let manager = wallet_manager.read_manager().await;
+            if let Some(wallet) = manager.get_wallet(wallet_name.as_deref()) {
+                let external: Vec<&TransactionOutput> = outputs
+                    .iter()
+                    .filter(|output| !wallet.data.addresses.iter().any(|a| a.address == output.address))
+                    .collect();
+                let entry = ActivityEntry {
+                    address: external.first().map(|output| output.address.clone()),
+                    txid: Some(tx_hash.clone()),
+                    amount: Some(external.iter().map(|output| output.value).sum()),
+                    ..ActivityEntry::now(ActivityKind::TransactionSent)
+                };
+                wallet_activity::record(&wallet.path, entry);
+                if let Some(key) = &cooling_off_key {
+                    spending_policy::release(&wallet.path, key);
+                }
+            }
             Ok(tx_hash)
         }
         Err(e) => {
             error!("Failed to submit transaction: {}", e);
-            Err(format!("Failed to submit transaction: {}", e))
+            Err(format!("Failed to submit transaction: {}", e).into())
+        }
+    }
+}
+
+/// Whether the blockchain services run, and the policy the mempool accepts transactions under
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceHealth {
+    pub blockchain_services_running: bool,
+    /// 0 while the services are not running
+    pub mempool_transactions: usize,
+    /// Fee rate a new transaction needs now, in sat/byte; the policy minimum or, while the
+    /// mempool is full, higher
+    pub mempool_min_fee_rate: u64,
+    pub relay_policy: RelayPolicy,
+}
+
+/// Get the health of the blockchain services and the relay policy in force
+#[command]
+#[command_metrics::tracked]
+pub async fn get_service_health(
+    services: State<'_, ServiceContainer>,
+    config_manager: State<'_, Arc<ConfigManager>>,
+) -> CommandResult<ServiceHealth> {
+    debug!("Command: get_service_health");
+
+    let blockchain_services_running = services.blockchain().is_some();
+    match services.get::<AsyncMempoolService>() {
+        Some(mempool_service) => {
+            let stats = mempool_service.get_stats().await;
+            Ok(ServiceHealth {
+                blockchain_services_running,
+                mempool_transactions: stats.transaction_count,
+                mempool_min_fee_rate: stats.mempool_min_fee_rate,
+                relay_policy: mempool_service.policy().await,
+            })
+        }
+        None => {
+            let relay_policy = RelayPolicy::from_settings(&config_manager.get_config().app_settings);
+            Ok(ServiceHealth {
+                blockchain_services_running,
+                mempool_transactions: 0,
+                mempool_min_fee_rate: relay_policy.min_relay_fee_rate,
+                relay_policy,
+            })
         }
     }
 }
 
 /// Get mempool status and transaction count
 #[command]
+#[command_metrics::tracked]
 pub async fn get_mempool_status(
     state: State<'_, crate::AppState>,
 ) -> CommandResult<serde_json::Value> {
     debug!("Getting mempool status");
-    
+
     match state.mempool_service.get_mempool_info().await {
         Ok(info) => {
             let status = serde_json::json!({
@@ -2606,21 +5937,33 @@ pub async fn get_mempool_status(
         }
         Err(e) => {
             error!("Failed to get mempool status: {}", e);
-            Err(format!("Failed to get mempool status: {}", e))
+            Err(format!("Failed to get mempool status: {}", e).into())
         }
     }
 }
 
+/// Get the lowest fee rate (sat/byte) the mempool currently accepts. Rises while the
+/// mempool is full and evicting, so the send flow should not offer anything lower.
+#[command]
+#[command_metrics::tracked]
+pub async fn get_mempool_min_fee(
+    state: State<'_, crate::AppState>,
+) -> CommandResult<u64> {
+    debug!("Getting mempool minimum fee");
+    Ok(state.mempool_service.get_min_fee_rate().await)
+}
+
 /// Get pending transactions from mempool
 #[command]
+#[command_metrics::tracked]
 pub async fn get_pending_transactions(
     state: State<'_, crate::AppState>,
     limit: Option<usize>,
 ) -> CommandResult<Vec<Transaction>> {
     debug!("Getting pending transactions from mempool");
-    
+
     let limit = limit.unwrap_or(100);
-    
+
     let transactions = state.mempool_service.get_transactions_for_mining(limit, 1000000).await;
     debug!("Retrieved {} pending transactions", transactions.len());
     Ok(transactions)
@@ -2628,11 +5971,12 @@ pub async fn get_pending_transactions(
 
 /// Get fee estimates for different confirmation targets
 #[command]
+#[command_metrics::tracked]
 pub async fn get_fee_estimates(
     state: State<'_, crate::AppState>,
 ) -> CommandResult<serde_json::Value> {
     debug!("Getting fee estimates");
-    
+
     match state.fee_estimator.estimate_fees().await {
         Ok(estimates) => {
             let estimates_json = serde_json::to_value(estimates)
@@ -2641,20 +5985,21 @@ pub async fn get_fee_estimates(
         }
         Err(e) => {
             error!("Failed to get fee estimates: {}", e);
-            Err(format!("Failed to get fee estimates: {}", e))
+            Err(format!("Failed to get fee estimates: {}", e).into())
         }
     }
 }
 
 /// Calculate recommended fee for transaction
 #[command]
+#[command_metrics::tracked]
 pub async fn calculate_transaction_fee(
     state: State<'_, crate::AppState>,
     tx_size_bytes: usize,
     priority: String, // "slow", "normal", "fast", "urgent"
 ) -> CommandResult<u64> {
     debug!("Calculating fee for {} byte transaction with {} priority", tx_size_bytes, priority);
-    
+
     let target = match priority.to_lowercase().as_str() {
         "urgent" => FeeTarget::NextBlock,
         "fast" => FeeTarget::Fast,
@@ -2662,7 +6007,7 @@ pub async fn calculate_transaction_fee(
         "slow" => FeeTarget::Slow,
         _ => FeeTarget::Normal,
     };
-    
+
     match state.fee_estimator.get_recommended_fee(tx_size_bytes, target).await {
         Ok(fee) => {
             debug!("Recommended fee: {} satoshis for {} priority", fee, priority);
@@ -2670,18 +6015,19 @@ pub async fn calculate_transaction_fee(
         }
         Err(e) => {
             error!("Failed to calculate transaction fee: {}", e);
-            Err(format!("Failed to calculate transaction fee: {}", e))
+            Err(format!("Failed to calculate transaction fee: {}", e).into())
         }
     }
 }
 
 /// Get comprehensive network diagnostics
 #[command]
+#[command_metrics::tracked]
 pub async fn get_network_diagnostics(
     state: State<'_, crate::AppState>,
 ) -> CommandResult<NetworkDiagnostics> {
     debug!("Getting network diagnostics");
-    
+
     match state.network_monitor.collect_diagnostics().await {
         Ok(diagnostics) => {
             debug!("Successfully collected network diagnostics");
@@ -2689,18 +6035,19 @@ pub async fn get_network_diagnostics(
         }
         Err(e) => {
             error!("Failed to collect network diagnostics: {}", e);
-            Err(format!("Failed to collect network diagnostics: {}", e))
+            Err(format!("Failed to collect network diagnostics: {}", e).into())
         }
     }
 }
 
 /// Get network diagnostic history
 #[command]
+#[command_metrics::tracked]
 pub async fn get_network_diagnostic_history(
     state: State<'_, crate::AppState>,
 ) -> CommandResult<Vec<NetworkDiagnostics>> {
     debug!("Getting network diagnostic history");
-    
+
     let history = state.network_monitor.get_diagnostic_history().await;
     debug!("Retrieved {} diagnostic snapshots", history.len());
     Ok(history)
@@ -2708,19 +6055,21 @@ pub async fn get_network_diagnostic_history(
 
 /// Record bandwidth usage for monitoring
 #[command]
+#[command_metrics::tracked]
 pub async fn record_bandwidth_usage(
     state: State<'_, crate::AppState>,
     bytes_sent: u64,
     bytes_received: u64,
 ) -> CommandResult<()> {
     debug!("Recording bandwidth usage: {} sent, {} received", bytes_sent, bytes_received);
-    
+
     state.network_monitor.record_bandwidth(bytes_sent, bytes_received).await;
     Ok(())
 }
 
 /// Replace a transaction with higher fee (RBF)
 #[command]
+#[command_metrics::tracked]
 pub async fn replace_transaction_rbf(
     state: State<'_, crate::AppState>,
     old_tx_hash: String,
@@ -2728,14 +6077,14 @@ pub async fn replace_transaction_rbf(
     reason: String, // "rbf", "higher_fee", "user_request"
 ) -> CommandResult<ReplacementResult> {
     debug!("Replacing transaction {} with RBF", old_tx_hash);
-    
+
     let replacement_reason = match reason.to_lowercase().as_str() {
         "rbf" => ReplacementReason::RbfFlag,
         "higher_fee" => ReplacementReason::HigherFee,
         "user_request" => ReplacementReason::UserRequest,
         _ => ReplacementReason::UserRequest,
     };
-    
+
     match state.mempool_service.replace_transaction(&old_tx_hash, new_transaction, replacement_reason).await {
         Ok(result) => {
             info!("Transaction replacement successful: {} -> {}", old_tx_hash, result.new_tx_hash);
@@ -2743,19 +6092,279 @@ pub async fn replace_transaction_rbf(
         }
         Err(e) => {
             error!("Failed to replace transaction: {}", e);
-            Err(format!("Failed to replace transaction: {}", e))
+            Err(format!("Failed to replace transaction: {}", e).into())
         }
     }
 }
 
 /// Get transactions that can be replaced
 #[command]
+#[command_metrics::tracked]
 pub async fn get_replaceable_transactions(
     state: State<'_, crate::AppState>,
 ) -> CommandResult<Vec<(String, Transaction, bool)>> {
     debug!("Getting replaceable transactions");
-    
+
     let replaceable = state.mempool_service.get_replaceable_transactions().await;
     debug!("Found {} replaceable transactions", replaceable.len());
     Ok(replaceable)
 }
+
+// ============================================================================
+// Price Feed Commands
+// ============================================================================
+
+/// Minimum confirmations the wallet in `wallet_dir` requires before spending a coin
+fn wallet_min_confirmations(wallet_dir: &std::path::Path) -> u32 {
+    WalletSettings::load(wallet_dir)
+        .map(|settings| settings.min_confirmations)
+        .unwrap_or(DEFAULT_MIN_CONFIRMATIONS)
+}
+
+/// Height of the local chain tip, or 0 while blockchain services are not running
+async fn chain_tip_height(app_handle: &tauri::AppHandle) -> u64 {
+    match crate::services::get::<crate::blockchain_database::AsyncBlockchainDatabase>(app_handle) {
+        Some(blockchain_db) => blockchain_db.get_block_height().await.unwrap_or(0),
+        None => 0,
+    }
+}
+
+/// Smallest output the running mempool relays
+async fn dust_threshold(app_handle: &tauri::AppHandle) -> u64 {
+    match crate::services::get::<AsyncMempoolService>(app_handle) {
+        Some(mempool_service) => mempool_service.policy().await.dust_threshold,
+        None => crate::relay_policy::DEFAULT_DUST_THRESHOLD,
+    }
+}
+
+/// Fiat equivalent of an amount in the display currency, if the price feed is enabled.
+/// Only reads cached rates, so it never waits on the network; when the last refresh
+/// is older than two intervals the result is marked stale and shows its "as of" time.
+async fn fiat_equivalent(app_handle: &tauri::AppHandle, satoshis: u64) -> Option<FiatAmount> {
+    let config_manager = app_handle.try_state::<Arc<ConfigManager>>()?;
+    let settings = config_manager.get_config().app_settings;
+    if !settings.price_feed_enabled {
+        return None;
+    }
+    let price_service = app_handle.try_state::<AsyncPriceService>()?;
+    let max_age = settings.price_feed_interval_secs.saturating_mul(2);
+    price_service
+        .to_fiat(satoshis, &settings.display_currency, max_age)
+        .await
+}
+
+/// Command to convert amounts in satoshis to the display currency, e.g. for transaction lists.
+/// Entries are None when the price feed is disabled or has no rate for the currency.
+#[command]
+#[command_metrics::tracked]
+pub async fn convert_to_fiat(
+    amounts: Vec<u64>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<Vec<Option<FiatAmount>>> {
+    debug!("Command: convert_to_fiat for {} amounts", amounts.len());
+
+    let mut converted = Vec::with_capacity(amounts.len());
+    for amount in amounts {
+        converted.push(fiat_equivalent(&app_handle, amount).await);
+    }
+    Ok(converted)
+}
+
+/// Command to get the cached exchange rate of BRAD into a currency
+#[command]
+#[command_metrics::tracked]
+pub async fn get_exchange_rate(
+    currency: String,
+    price_service: State<'_, AsyncPriceService>,
+) -> CommandResult<Option<ExchangeRate>> {
+    debug!("Command: get_exchange_rate for {}", currency);
+
+    Ok(price_service.get_exchange_rate(&currency).await)
+}
+
+/// Command to fetch exchange rates now instead of waiting for the next refresh
+#[command]
+#[command_metrics::tracked]
+pub async fn refresh_exchange_rates(
+    price_service: State<'_, AsyncPriceService>,
+    config_manager_arc: State<'_, Arc<ConfigManager>>,
+) -> CommandResult<PriceSnapshot> {
+    info!("Command: refresh_exchange_rates");
+
+    let settings = config_manager_arc.inner().get_config().app_settings;
+    let url = settings
+        .price_feed_url
+        .filter(|_| settings.price_feed_enabled)
+        .ok_or_else(|| "Price feed is not enabled".to_string())?;
+
+    price_service.refresh(&url).await.map_err(|e| {
+        error!("Failed to refresh exchange rates: {}", e);
+        format_error(e)
+    })
+}
+
+/// Options for a cost basis report
+#[derive(Debug, serde::Deserialize)]
+pub struct CostBasisReportRequest {
+    /// "fifo" or "average_cost"
+    method: String,
+    /// Report currency; defaults to the display currency
+    currency: Option<String>,
+    /// Only include disposals at or after this Unix timestamp
+    from: Option<i64>,
+    /// Only include disposals at or before this Unix timestamp
+    to: Option<i64>,
+    /// Also write the per-lot detail and totals to this path as CSV
+    export_path: Option<String>,
+    wallet_name: Option<String>,
+}
+
+/// Command to compute realized capital gains for a wallet using FIFO or average cost
+#[command]
+#[command_metrics::tracked]
+pub async fn generate_cost_basis_report(
+    request: CostBasisReportRequest,
+    wallet_manager: State<'_, AsyncWalletManager>,
+    config_manager_arc: State<'_, Arc<ConfigManager>>,
+) -> CommandResult<CostBasisReport> {
+    info!("Command: generate_cost_basis_report - {:?}", request);
+
+    let method: CostBasisMethod = request.method.parse()?;
+    let currency = request.currency.unwrap_or_else(|| {
+        config_manager_arc.inner().get_config().app_settings.display_currency
+    });
+
+    let ledger = {
+        let manager = wallet_manager.read_manager().await;
+        let wallet = manager
+            .get_wallet(request.wallet_name.as_deref())
+            .ok_or(WalletError::NoWalletOpen)?;
+        CostBasisLedger::load(&wallet.path).map_err(format_error)?
+    };
+
+    let report = ledger.generate_report(method, &currency, request.from, request.to);
+
+    if let Some(path) = request.export_path {
+        atomic_file::write_atomic(std::path::Path::new(&path), report.to_csv().as_bytes()).map_err(|e| {
+            error!("Failed to write cost basis report {}: {}", path, e);
+            format!("Failed to write report: {}", e)
+        })?;
+        info!("Cost basis report exported to {}", path);
+    }
+
+    Ok(report)
+}
+
+// ============================================================================
+// Payment Link Commands
+// ============================================================================
+
+/// Command to parse a pasted brad:// payment link
+#[command]
+#[command_metrics::tracked]
+pub async fn parse_payment_uri(uri: String) -> CommandResult<PaymentUri> {
+    debug!("Command: parse_payment_uri");
+
+    crate::payment_uri::parse_payment_uri(&uri).map_err(|e| match e {
+        crate::payment_uri::PaymentUriError::Invalid(e) => e.into(),
+        other => format_error(other),
+    })
+}
+
+/// Recipient and amount checked the same way the send commands and payment links check them
+#[derive(Debug, Serialize)]
+pub struct PaymentDetails {
+    pub address: String,
+    /// Amount in satoshis
+    pub amount: u64,
+}
+
+/// Command to validate what the user typed on the send screen before building a transaction.
+/// Errors start with a code such as "WRONG_NETWORK:" or "INVALID_AMOUNT:".
+#[command]
+#[command_metrics::tracked]
+pub async fn validate_payment_details(address: String, amount: String) -> CommandResult<PaymentDetails> {
+    debug!("Command: validate_payment_details");
+
+    let address = crate::validation::validate_address(&address, ChainParams::active().network)
+        ?;
+    let amount = crate::validation::parse_amount(amount.trim())?;
+    Ok(PaymentDetails { address, amount })
+}
+
+/// Command to take the payment link opened from outside the app, if the send screen has not
+/// handled it yet. Covers links that arrive before the frontend listens for events.
+#[command]
+#[command_metrics::tracked]
+pub async fn take_pending_payment_uri(
+    pending: State<'_, PendingPaymentUri>,
+) -> CommandResult<Option<PaymentUri>> {
+    debug!("Command: take_pending_payment_uri");
+
+    Ok(pending.take())
+}
+
+// ============================================================================
+// Update Commands
+// ============================================================================
+
+/// Command to check for an application update. Uses the configured channel unless one is given.
+#[command]
+#[command_metrics::tracked]
+pub async fn check_for_updates(
+    channel: Option<String>,
+    update_service: State<'_, AsyncUpdateService>,
+    config_manager_arc: State<'_, Arc<ConfigManager>>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<Option<UpdateInfo>> {
+    let channel = channel.unwrap_or_else(|| config_manager_arc.inner().get_config().app_settings.update_channel);
+    let channel: UpdateChannel = channel.parse()?;
+    info!("Command: check_for_updates on {:?} channel", channel);
+
+    update_service.check(&app_handle, channel).await.map_err(|e| {
+        error!("Update check failed: {}", e);
+        format_error(e)
+    })
+}
+
+/// Command to get the update waiting for confirmation, if any
+#[command]
+#[command_metrics::tracked]
+pub async fn get_pending_update(
+    update_service: State<'_, AsyncUpdateService>,
+) -> CommandResult<Option<UpdateInfo>> {
+    debug!("Command: get_pending_update");
+
+    Ok(update_service.pending_update().await)
+}
+
+/// Command to install the pending update after the user confirmed, then restart the app.
+/// Downloads the package first if the background download has not finished.
+#[command]
+#[command_metrics::tracked]
+pub async fn install_pending_update(
+    update_service: State<'_, AsyncUpdateService>,
+    app_handle: tauri::AppHandle,
+) -> CommandResult<bool> {
+    info!("Command: install_pending_update");
+
+    update_service.install(&app_handle).await.map_err(|e| {
+        error!("Failed to install update: {}", e);
+        format_error(e)
+    })?;
+
+    // Release wallets and the blockchain database before the new version starts
+    crate::SHUTDOWN_IN_PROGRESS.store(true, std::sync::atomic::Ordering::SeqCst);
+    if let Some(wallet_manager) = app_handle.try_state::<AsyncWalletManager>() {
+        if let Err(e) = wallet_manager.shutdown().await {
+            error!("Wallet manager shutdown error before restart: {}", e);
+        }
+    }
+    if let Err(e) = stop_blockchain_services_internal(&app_handle).await {
+        warn!("Failed to stop blockchain services before restart: {}", e);
+    }
+    logging::log_app_shutdown();
+
+    info!("Update installed, restarting");
+    app_handle.restart();
+}
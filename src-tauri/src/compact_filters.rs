@@ -0,0 +1,234 @@
+//! BIP158-style compact block filters.
+//!
+//! Each block gets a Golomb-coded set of the addresses it pays and the outpoints ("txid:vout") it
+//! spends. A wallet tests its addresses and coins against the filter and reads only the blocks
+//! that match, instead of every block. Filters can report false positives (about one in
+//! `FILTER_M` per queried item) but never miss a block that touches the wallet.
+//!
+//! Unlike BIP158, items are hashed with SHA-256 keyed by the block hash rather than SipHash, and
+//! addresses stand in for output scripts, since that is what this chain's outputs carry.
+
+use crate::blockchain_database::Block;
+use sha2::{Digest, Sha256};
+
+/// Golomb-Rice parameter: remainder bits per item
+pub const FILTER_P: u8 = 19;
+
+/// Inverse false positive rate per queried item
+pub const FILTER_M: u64 = 784_931;
+
+/// Most filters sent for one `GetCFilters` request
+pub const MAX_FILTERS_PER_REQUEST: u64 = 1_000;
+
+/// Items a block's filter commits to: the addresses it pays and the outpoints it spends
+pub fn filter_items(block: &Block) -> Vec<Vec<u8>> {
+    let mut items: Vec<Vec<u8>> = Vec::new();
+    for transaction in &block.transactions {
+        for input in &transaction.inputs {
+            items.push(outpoint_item(&input.previous_txid, input.previous_output_index));
+        }
+        for output in transaction.outputs.iter().filter(|output| !output.address.is_empty()) {
+            items.push(output.address.as_bytes().to_vec());
+        }
+    }
+    items.sort();
+    items.dedup();
+    items
+}
+
+/// Filter item of a spent outpoint
+pub fn outpoint_item(txid: &str, vout: u32) -> Vec<u8> {
+    format!("{}:{}", txid, vout).into_bytes()
+}
+
+/// Build the filter of a block
+pub fn build_filter(block: &Block) -> Vec<u8> {
+    encode(&block.hash, &filter_items(block))
+}
+
+/// Encode items as a Golomb-coded set keyed by `block_hash`
+pub fn encode(block_hash: &str, items: &[Vec<u8>]) -> Vec<u8> {
+    let key = filter_key(block_hash);
+    let range = items.len() as u64 * FILTER_M;
+    let mut values: Vec<u64> = items.iter().map(|item| hash_to_range(&key, item, range)).collect();
+    values.sort_unstable();
+
+    let mut out = Vec::new();
+    write_compact_size(&mut out, items.len() as u64);
+    let mut writer = BitWriter::new(out);
+    let mut last = 0;
+    for value in values {
+        let delta = value - last;
+        last = value;
+        // Quotient in unary, then the remainder in FILTER_P bits
+        for _ in 0..(delta >> FILTER_P) {
+            writer.write_bit(true);
+        }
+        writer.write_bit(false);
+        writer.write_bits(delta, FILTER_P);
+    }
+    writer.finish()
+}
+
+/// Whether the filter may contain any of `query`. False if the filter is malformed.
+pub fn matches_any(filter: &[u8], block_hash: &str, query: &[Vec<u8>]) -> bool {
+    let Some((count, mut reader)) = read_header(filter) else {
+        return false;
+    };
+    if count == 0 || query.is_empty() {
+        return false;
+    }
+
+    let key = filter_key(block_hash);
+    let range = count * FILTER_M;
+    let mut wanted: Vec<u64> = query.iter().map(|item| hash_to_range(&key, item, range)).collect();
+    wanted.sort_unstable();
+
+    // Walk both sorted lists together
+    let mut wanted = wanted.into_iter().peekable();
+    let mut value = 0u64;
+    for _ in 0..count {
+        let Some(delta) = reader.read_golomb() else {
+            return false;
+        };
+        value += delta;
+        while let Some(&next) = wanted.peek() {
+            if next == value {
+                return true;
+            }
+            if next > value {
+                break;
+            }
+            wanted.next();
+        }
+        if wanted.peek().is_none() {
+            return false;
+        }
+    }
+    false
+}
+
+fn filter_key(block_hash: &str) -> [u8; 16] {
+    let digest = Sha256::digest(block_hash.as_bytes());
+    let mut key = [0u8; 16];
+    key.copy_from_slice(&digest[..16]);
+    key
+}
+
+/// Hash an item uniformly into [0, range)
+fn hash_to_range(key: &[u8; 16], item: &[u8], range: u64) -> u64 {
+    let digest = Sha256::new().chain_update(key).chain_update(item).finalize();
+    let hash = u64::from_le_bytes(digest[..8].try_into().expect("digest has 8 bytes"));
+    ((hash as u128 * range as u128) >> 64) as u64
+}
+
+fn write_compact_size(out: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=0xfc => out.push(n as u8),
+        0xfd..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xfe);
+            out.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
+
+fn read_header(filter: &[u8]) -> Option<(u64, BitReader<'_>)> {
+    let (&prefix, rest) = filter.split_first()?;
+    let (count, len) = match prefix {
+        0xfd => (u16::from_le_bytes(rest.get(..2)?.try_into().ok()?) as u64, 2),
+        0xfe => (u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as u64, 4),
+        0xff => (u64::from_le_bytes(rest.get(..8)?.try_into().ok()?), 8),
+        n => (n as u64, 0),
+    };
+    Some((count, BitReader::new(&rest[len..])))
+}
+
+struct BitWriter {
+    out: Vec<u8>,
+    byte: u8,
+    used: u8,
+}
+
+impl BitWriter {
+    fn new(out: Vec<u8>) -> Self {
+        Self { out, byte: 0, used: 0 }
+    }
+
+    fn write_bit(&mut self, bit: bool) {
+        self.byte = (self.byte << 1) | bit as u8;
+        self.used += 1;
+        if self.used == 8 {
+            self.out.push(self.byte);
+            self.byte = 0;
+            self.used = 0;
+        }
+    }
+
+    fn write_bits(&mut self, value: u64, bits: u8) {
+        for i in (0..bits).rev() {
+            self.write_bit((value >> i) & 1 == 1);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.used > 0 {
+            self.out.push(self.byte << (8 - self.used));
+        }
+        self.out
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn read_bit(&mut self) -> Option<bool> {
+        let byte = self.data.get(self.position / 8)?;
+        let bit = (byte >> (7 - self.position % 8)) & 1 == 1;
+        self.position += 1;
+        Some(bit)
+    }
+
+    fn read_golomb(&mut self) -> Option<u64> {
+        let mut quotient = 0u64;
+        while self.read_bit()? {
+            quotient += 1;
+        }
+        let mut remainder = 0u64;
+        for _ in 0..FILTER_P {
+            remainder = (remainder << 1) | self.read_bit()? as u64;
+        }
+        Some((quotient << FILTER_P) | remainder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_matches_its_items_only() {
+        let items: Vec<Vec<u8>> = (0..200).map(|i| format!("address{}", i).into_bytes()).collect();
+        let filter = encode("blockhash", &items);
+
+        assert!(matches_any(&filter, "blockhash", &[b"address150".to_vec()]));
+        assert!(matches_any(&filter, "blockhash", &[b"nope".to_vec(), b"address0".to_vec()]));
+        assert!(!matches_any(&filter, "blockhash", &[b"somebody else".to_vec()]));
+        assert!(!matches_any(&encode("blockhash", &[]), "blockhash", &[b"address0".to_vec()]));
+        assert!(!matches_any(&[], "blockhash", &[b"address0".to_vec()]));
+    }
+}
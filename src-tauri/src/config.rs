@@ -1,14 +1,20 @@
+use crate::atomic_file;
+use crate::config_migrations::{self, CURRENT_CONFIG_VERSION};
 use crate::errors::ConfigError;
-use log::{debug, error, info};
+use crate::paths::PathProvider;
+use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::fs;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::AsyncReadExt;
 
 
 /// Configuration structure for the application
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
+    /// Schema version of this file, see config_migrations
+    #[serde(default)]
+    pub config_version: u32,
     /// List of available wallets
     pub wallets: Vec<WalletInfo>,
     /// Application settings
@@ -60,6 +66,123 @@ pub struct AppSettings {
     /// Custom location for the blockchain database file
     #[serde(default)]
     pub local_blockchain_file_location: Option<String>,
+    /// Whether to fetch exchange rates and show fiat equivalents
+    #[serde(default)]
+    pub price_feed_enabled: bool,
+    /// HTTP source for exchange rates
+    #[serde(default)]
+    pub price_feed_url: Option<String>,
+    /// Seconds between exchange rate refreshes
+    #[serde(default = "default_price_feed_interval_secs")]
+    pub price_feed_interval_secs: u64,
+    /// Currency code used for fiat equivalents, e.g. "USD"
+    #[serde(default = "default_display_currency")]
+    pub display_currency: String,
+    /// Whether to check for application updates periodically
+    #[serde(default = "default_auto_update_check")]
+    pub auto_update_check: bool,
+    /// Release channel to update from: "stable" or "beta"
+    #[serde(default = "default_update_channel")]
+    pub update_channel: String,
+    /// Record recent P2P messages per peer for debugging
+    #[serde(default)]
+    pub network_trace_enabled: bool,
+    /// Periodically write the network trace to the logs directory
+    #[serde(default)]
+    pub network_trace_dump: bool,
+    /// Outbound peers the node keeps connected
+    #[serde(default = "default_max_outbound_peers")]
+    pub max_outbound_peers: u32,
+    /// Inbound peers accepted before the lowest-scoring one is evicted
+    #[serde(default = "default_max_inbound_peers")]
+    pub max_inbound_peers: u32,
+    /// Peers announcing an older protocol version are disconnected during the handshake
+    #[serde(default = "default_min_peer_protocol_version")]
+    pub min_peer_protocol_version: u32,
+    /// IP addresses of the interfaces to accept peers on; empty listens on all IPv4 and IPv6
+    /// interfaces. Applies when the network service next starts.
+    #[serde(default)]
+    pub p2p_bind_addresses: Vec<String>,
+    /// Memory the mempool may use before the lowest fee-rate transactions are evicted, in MB
+    #[serde(default = "default_max_mempool_size_mb")]
+    pub max_mempool_size_mb: u32,
+    /// Trust built-in checkpoints during sync; off means every block is fully validated
+    #[serde(default = "default_checkpoints_enabled")]
+    pub checkpoints_enabled: bool,
+    /// Language of tray labels and error messages the backend produces, e.g. "en"
+    #[serde(default = "default_language")]
+    pub language: String,
+    /// Stream node events to external apps over a local WebSocket
+    #[serde(default)]
+    pub push_api_enabled: bool,
+    /// Loopback port of the push API
+    #[serde(default = "default_push_api_port")]
+    pub push_api_port: u16,
+    /// Token external apps must present to the push API; generated when the API is enabled
+    #[serde(default)]
+    pub push_api_token: String,
+    /// Serve Prometheus-style metrics on a local HTTP endpoint
+    #[serde(default)]
+    pub metrics_enabled: bool,
+    /// Loopback port of the metrics endpoint
+    #[serde(default = "default_metrics_port")]
+    pub metrics_port: u16,
+    /// Find wallet coins by matching compact block filters instead of the address index
+    #[serde(default)]
+    pub compact_filter_sync: bool,
+    /// Keep an address to transaction index for address history lookups, at some disk cost
+    #[serde(default)]
+    pub address_index_enabled: bool,
+    /// Lowest password strength score, 0 to 4, accepted when securing a wallet
+    #[serde(default = "default_min_password_score")]
+    pub min_password_score: u8,
+    /// Keep mining while the machine runs on battery; off pauses mining until it is plugged in
+    #[serde(default = "default_mine_on_battery")]
+    pub mine_on_battery: bool,
+    /// Share of the time mining threads hash, in percent
+    #[serde(default = "default_mining_duty_cycle")]
+    pub mining_duty_cycle: u8,
+    /// Run mining threads at the lowest OS priority
+    #[serde(default = "default_mining_low_priority")]
+    pub mining_low_priority: bool,
+    /// Serve mining work to miner processes on the local network
+    #[serde(default)]
+    pub stratum_enabled: bool,
+    /// Port of the stratum server, on every interface
+    #[serde(default = "default_stratum_port")]
+    pub stratum_port: u16,
+    /// Address blocks found through the stratum server pay to
+    #[serde(default)]
+    pub stratum_payout_address: Option<String>,
+    /// Minutes an unlocked wallet stays unlocked without use, and minutes without any command
+    /// after which every wallet is locked
+    #[serde(default = "default_auth_timeout_minutes")]
+    pub auth_timeout_minutes: u64,
+    /// Cache of the blockchain storage engine, in MiB; takes effect when the database next opens
+    #[serde(default = "default_db_cache_capacity_mb")]
+    pub db_cache_capacity_mb: u32,
+    /// Decoded blocks kept in memory, in MiB
+    #[serde(default = "default_db_block_cache_mb")]
+    pub db_block_cache_mb: u32,
+    /// Shrink the blockchain database caches while the system is short of memory
+    #[serde(default = "default_db_low_memory_mode")]
+    pub db_low_memory_mode: bool,
+    /// Lowest fee rate of a transaction the mempool accepts and relays, in sat/byte
+    #[serde(default = "default_min_relay_fee_rate")]
+    pub min_relay_fee_rate: u64,
+    /// Largest transaction the mempool accepts and relays, in bytes
+    #[serde(default = "default_max_standard_tx_size")]
+    pub max_standard_tx_size: usize,
+    /// Outputs worth less than this many satoshis are dust and not relayed
+    #[serde(default = "default_dust_threshold")]
+    pub dust_threshold: u64,
+    /// Relay transactions with outputs whose scripts are not of a standard form
+    #[serde(default)]
+    pub relay_non_standard_scripts: bool,
+    /// Skip transaction relay to save bandwidth: no transactions are taken from peers, fewer
+    /// peers are kept and fees are estimated from blocks alone. The node's own are still sent.
+    #[serde(default)]
+    pub blocks_only: bool,
 }
 
 /// Default implementation for Config
@@ -67,6 +190,7 @@ impl Default for Config {
     fn default() -> Self {
         debug!("Creating default configuration");
         Self {
+            config_version: CURRENT_CONFIG_VERSION,
             wallets: vec![],
             app_settings: AppSettings::default(),
         }
@@ -83,6 +207,126 @@ fn default_minimize_to_system_tray() -> bool {
     false
 }
 
+/// Default value for price_feed_interval_secs
+fn default_price_feed_interval_secs() -> u64 {
+    300
+}
+
+/// Default value for display_currency
+fn default_display_currency() -> String {
+    crate::price_service::DEFAULT_FIAT_CURRENCY.to_string()
+}
+
+/// Default value for auto_update_check
+fn default_auto_update_check() -> bool {
+    true
+}
+
+/// Default value for update_channel
+fn default_update_channel() -> String {
+    "stable".to_string()
+}
+
+/// Default value for max_outbound_peers
+fn default_max_outbound_peers() -> u32 {
+    crate::network_constants::MAX_OUTBOUND_PEERS as u32
+}
+
+/// Default value for min_peer_protocol_version
+fn default_min_peer_protocol_version() -> u32 {
+    crate::network_constants::MIN_PROTOCOL_VERSION
+}
+
+/// Default value for max_inbound_peers
+fn default_max_inbound_peers() -> u32 {
+    crate::network_constants::MAX_INBOUND_PEERS as u32
+}
+
+/// Default value for max_mempool_size_mb
+fn default_max_mempool_size_mb() -> u32 {
+    crate::mempool_service::DEFAULT_MAX_MEMPOOL_SIZE_MB
+}
+
+/// Default value for checkpoints_enabled
+fn default_checkpoints_enabled() -> bool {
+    true
+}
+
+/// Default value for language
+fn default_language() -> String {
+    crate::i18n::Locale::default().code().to_string()
+}
+
+/// Default value for push_api_port
+fn default_push_api_port() -> u16 {
+    crate::push_api::DEFAULT_PUSH_API_PORT
+}
+
+/// Default value for metrics_port
+fn default_metrics_port() -> u16 {
+    crate::metrics::DEFAULT_METRICS_PORT
+}
+
+/// Default value for min_password_score
+fn default_min_password_score() -> u8 {
+    crate::password_strength::DEFAULT_MIN_PASSWORD_SCORE
+}
+
+/// Default value for mining_duty_cycle
+fn default_mining_duty_cycle() -> u8 {
+    100
+}
+
+/// Default value for mining_low_priority
+fn default_mining_low_priority() -> bool {
+    true
+}
+
+/// Default value for auth_timeout_minutes
+fn default_auth_timeout_minutes() -> u64 {
+    30
+}
+
+/// Default value for db_cache_capacity_mb
+fn default_db_cache_capacity_mb() -> u32 {
+    crate::blockchain_database::DEFAULT_STORE_CACHE_MB
+}
+
+/// Default value for db_block_cache_mb
+fn default_db_block_cache_mb() -> u32 {
+    crate::blockchain_database::DEFAULT_BLOCK_CACHE_MB
+}
+
+/// Default value for db_low_memory_mode
+fn default_db_low_memory_mode() -> bool {
+    true
+}
+
+/// Default value for min_relay_fee_rate
+fn default_min_relay_fee_rate() -> u64 {
+    crate::relay_policy::DEFAULT_MIN_RELAY_FEE_RATE
+}
+
+/// Default value for max_standard_tx_size
+fn default_max_standard_tx_size() -> usize {
+    crate::relay_policy::DEFAULT_MAX_STANDARD_TX_SIZE
+}
+
+/// Default value for dust_threshold
+fn default_dust_threshold() -> u64 {
+    crate::relay_policy::DEFAULT_DUST_THRESHOLD
+}
+
+/// Default value for stratum_port
+fn default_stratum_port() -> u16 {
+    crate::stratum::DEFAULT_STRATUM_PORT
+}
+
+/// Default value for mine_on_battery
+fn default_mine_on_battery() -> bool {
+    true
+}
+
 /// Default value for mining_threads
 fn default_mining_threads() -> u32 {
     // Default to number of CPU cores, but at least 1
@@ -103,6 +347,44 @@ impl Default for AppSettings {    fn default() -> Self {
             minimize_to_system_tray: false,
             mining_threads: default_mining_threads(),
             local_blockchain_file_location: None,
+            price_feed_enabled: false,
+            price_feed_url: None,
+            price_feed_interval_secs: default_price_feed_interval_secs(),
+            display_currency: default_display_currency(),
+            auto_update_check: default_auto_update_check(),
+            update_channel: default_update_channel(),
+            network_trace_enabled: false,
+            network_trace_dump: false,
+            max_outbound_peers: default_max_outbound_peers(),
+            max_inbound_peers: default_max_inbound_peers(),
+            min_peer_protocol_version: default_min_peer_protocol_version(),
+            p2p_bind_addresses: Vec::new(),
+            max_mempool_size_mb: default_max_mempool_size_mb(),
+            checkpoints_enabled: default_checkpoints_enabled(),
+            language: default_language(),
+            push_api_enabled: false,
+            push_api_port: default_push_api_port(),
+            push_api_token: String::new(),
+            metrics_enabled: false,
+            metrics_port: default_metrics_port(),
+            compact_filter_sync: false,
+            address_index_enabled: false,
+            min_password_score: default_min_password_score(),
+            mine_on_battery: default_mine_on_battery(),
+            mining_duty_cycle: default_mining_duty_cycle(),
+            mining_low_priority: default_mining_low_priority(),
+            stratum_enabled: false,
+            stratum_port: default_stratum_port(),
+            stratum_payout_address: None,
+            auth_timeout_minutes: default_auth_timeout_minutes(),
+            db_cache_capacity_mb: default_db_cache_capacity_mb(),
+            db_block_cache_mb: default_db_block_cache_mb(),
+            db_low_memory_mode: default_db_low_memory_mode(),
+            min_relay_fee_rate: default_min_relay_fee_rate(),
+            max_standard_tx_size: default_max_standard_tx_size(),
+            dust_threshold: default_dust_threshold(),
+            relay_non_standard_scripts: false,
+            blocks_only: false,
         }
     }
 }
@@ -111,20 +393,27 @@ impl Default for AppSettings {    fn default() -> Self {
 pub struct ConfigManager {
     config: std::sync::Mutex<Config>,
     config_path: PathBuf,
+    recovered_from_backup: bool,
 }
 
 impl ConfigManager {
     /// Create a new ConfigManager instance
     pub async fn new() -> Result<Self, ConfigError> {
         debug!("Initializing configuration manager");
-        let (config, config_path) = Self::load_config().await?;
+        let (config, config_path, recovered_from_backup) = Self::load_config().await?;
 
         Ok(ConfigManager {
             config: std::sync::Mutex::new(config),
             config_path,
+            recovered_from_backup,
         })
     }
 
+    /// Whether the config file was corrupt at startup and the backup was used instead
+    pub fn was_recovered_from_backup(&self) -> bool {
+        self.recovered_from_backup
+    }
+
     /// Get a reference to the current configuration
     pub fn get_config(&self) -> Config {
         self.config.lock().unwrap().clone()
@@ -164,70 +453,31 @@ impl ConfigManager {
         config: &Config,
         path: &PathBuf,
     ) -> Result<(), ConfigError> {
-        debug!("Serializing configuration to JSON");
-        let config_json = match serde_json::to_string_pretty(config) {
-            Ok(json) => json,
-            Err(e) => {
-                error!("Failed to serialize config to JSON: {}", e);
-                return Err(ConfigError::SaveError(format!(
-                    "Failed to serialize config to JSON: {}",
-                    e
-                )));
-            }
-        };
-
-        debug!("Writing configuration to file: {}", path.display());
-        let mut file = match fs::File::create(path).await {
-            Ok(file) => file,
-            Err(e) => {
-                error!("Failed to create config file: {}", e);
-                return Err(ConfigError::SaveError(format!(
-                    "Failed to create config file: {}",
-                    e
-                )));
-            }
-        };
-
-        // Write the config data to the file
-        if let Err(e) = file.write_all(config_json.as_bytes()).await {
-            error!("Failed to write configuration to file: {}", e);
-            return Err(ConfigError::SaveError(format!(
-                "Failed to write configuration to file: {}",
-                e
-            )));
-        }
-        
-        // Flush to ensure data is written to disk
-        if let Err(e) = file.flush().await {
-            error!("Failed to flush configuration file: {}", e);
-            return Err(ConfigError::SaveError(format!(
-                "Failed to flush configuration file: {}",
-                e
-            )));
-        }
-        
-        debug!("Config file saved successfully");
-        Ok(())
+        Self::save_config_to_path_static(config, path).await
     }
 
     /// Add a new wallet to configuration
     pub async fn add_wallet(&self, wallet_info: WalletInfo) -> Result<(), ConfigError> {
-        // Check if wallet already exists
-        let mut config = self.config.lock().unwrap();
-        if config.wallets.iter().any(|w| w.name == wallet_info.name) {
-            error!(
-                "Wallet '{}' already exists in configuration",
-                wallet_info.name
-            );
-            return Err(ConfigError::Generic(format!(
-                "Wallet '{}' already exists",
-                wallet_info.name
-            )));
-        }
+        // Clone the config to avoid holding the mutex guard across an await point
+        let config_clone = {
+            let mut config = self.config.lock().unwrap();
+            // Check if wallet already exists
+            if config.wallets.iter().any(|w| w.name == wallet_info.name) {
+                error!(
+                    "Wallet '{}' already exists in configuration",
+                    wallet_info.name
+                );
+                return Err(ConfigError::Generic(format!(
+                    "Wallet '{}' already exists",
+                    wallet_info.name
+                )));
+            }
 
-        info!("Adding new wallet '{}' to configuration", wallet_info.name);
-        config.wallets.push(wallet_info);
-        self.save_config_to_path(&config, &self.config_path).await
+            info!("Adding new wallet '{}' to configuration", wallet_info.name);
+            config.wallets.push(wallet_info);
+            config.clone()
+        };
+        self.save_config_to_path(&config_clone, &self.config_path).await
     }
 
     /// Remove all wallets from the configuration
@@ -255,10 +505,16 @@ impl ConfigManager {
         Ok(())
     }
 
-    /// Load the configuration from file
-    async fn load_config() -> Result<(Config, PathBuf), ConfigError> {
+    /// Load the configuration from file, falling back to the last good backup if it is corrupt.
+    /// The returned flag tells whether the backup was used.
+    async fn load_config() -> Result<(Config, PathBuf, bool), ConfigError> {
         let config_path = Self::get_config_path().await?;
+        let (config, recovered) = Self::load_config_from(&config_path).await?;
+        Ok((config, config_path, recovered))
+    }
 
+    /// Load the configuration at `config_path`, creating a default one if there is none
+    async fn load_config_from(config_path: &PathBuf) -> Result<(Config, bool), ConfigError> {
         // Check if the config file exists
         match fs::try_exists(&config_path).await {
             Ok(exists) => {
@@ -267,46 +523,41 @@ impl ConfigManager {
                         "Loading existing configuration from {}",
                         config_path.display()
                     );
-                    // Read and parse the config file
-                    let mut file = match fs::File::open(&config_path).await {
-                        Ok(file) => file,
+
+                    let (config, migrated, recovered) = match Self::read_config_file(config_path).await {
+                        Ok((config, migrated)) => (config, migrated, false),
+                        // A config from a newer app is not corrupt, so never paper over it with the backup
+                        Err(e @ ConfigError::UnsupportedVersion { .. }) => return Err(e),
                         Err(e) => {
-                            error!("Failed to open config file: {}", e);
-                            return Err(ConfigError::LoadError(format!(
-                                "Failed to open config file: {}",
-                                e
-                            )));
+                            let backup = atomic_file::backup_path(config_path);
+                            warn!("Configuration is unreadable ({}), trying backup {}", e, backup.display());
+                            match Self::read_config_file(&backup).await {
+                                Ok((config, _)) => (config, true, true),
+                                Err(backup_err) => {
+                                    error!("Backup configuration is unusable too: {}", backup_err);
+                                    return Err(e);
+                                }
+                            }
                         }
                     };
 
-                    let mut config_content = String::new();
-                    if let Err(e) = file.read_to_string(&mut config_content).await {
-                        error!("Failed to read config file: {}", e);
-                        return Err(ConfigError::LoadError(format!(
-                            "Failed to read config file: {}",
-                            e
-                        )));
+                    if recovered {
+                        // Saving over the corrupt file would back it up over the good backup
+                        Self::set_aside_corrupt_config(config_path).await;
+                        Self::write_config_file(&config, config_path, false).await?;
+                        warn!("Configuration recovered from backup");
+                    } else if migrated {
+                        Self::save_config_to_path_static(&config, config_path).await?;
                     }
 
-                    let config: Config = match serde_json::from_str(&config_content) {
-                        Ok(config) => config,
-                        Err(e) => {
-                            error!("Failed to parse config file: {}", e);
-                            return Err(ConfigError::ParseError(format!(
-                                "Failed to parse config file: {}",
-                                e
-                            )));
-                        }
-                    };
-
                     info!("Configuration loaded successfully");
-                    Ok((config, config_path))
+                    Ok((config, recovered))
                 } else {
                     // Create a default config if it doesn't exist
                     info!("No configuration found. Creating default configuration");
                     let default_config = Config::default();
-                    Self::save_config_to_path_static(&default_config, &config_path).await?;
-                    Ok((default_config, config_path))
+                    Self::save_config_to_path_static(&default_config, config_path).await?;
+                    Ok((default_config, false))
                 }
             }
             Err(e) => {
@@ -319,10 +570,110 @@ impl ConfigManager {
         }
     }
 
-    /// Save configuration to path (static version)
+    /// Read, migrate and parse a config file. The flag tells whether it needs to be written back.
+    async fn read_config_file(path: &PathBuf) -> Result<(Config, bool), ConfigError> {
+        // Read and parse the config file
+        let mut file = match fs::File::open(path).await {
+            Ok(file) => file,
+            Err(e) => {
+                error!("Failed to open config file: {}", e);
+                return Err(ConfigError::LoadError(format!(
+                    "Failed to open config file: {}",
+                    e
+                )));
+            }
+        };
+
+        let mut config_content = String::new();
+        if let Err(e) = file.read_to_string(&mut config_content).await {
+            error!("Failed to read config file: {}", e);
+            return Err(ConfigError::LoadError(format!(
+                "Failed to read config file: {}",
+                e
+            )));
+        }
+
+        let raw_config: serde_json::Value = match serde_json::from_str(&config_content) {
+            Ok(value) => value,
+            Err(e) => {
+                error!("Failed to parse config file: {}", e);
+                return Err(ConfigError::ParseError(format!(
+                    "Failed to parse config file: {}",
+                    e
+                )));
+            }
+        };
+
+        // Upgrade older files before deserializing, keeping a copy of the original
+        let version = config_migrations::config_version(&raw_config);
+        let needs_migration = version < CURRENT_CONFIG_VERSION;
+        if needs_migration {
+            Self::backup_before_migration(path, version).await?;
+        }
+        let raw_config = config_migrations::migrate(raw_config).map_err(|e| {
+            error!("Failed to migrate config file: {}", e);
+            e
+        })?;
+
+        let config: Config = match serde_json::from_value(raw_config) {
+            Ok(config) => config,
+            Err(e) => {
+                error!("Failed to parse config file: {}", e);
+                return Err(ConfigError::ParseError(format!(
+                    "Failed to parse config file: {}",
+                    e
+                )));
+            }
+        };
+
+        if needs_migration {
+            info!("Configuration migrated from version {} to {}", version, CURRENT_CONFIG_VERSION);
+        }
+        Ok((config, needs_migration))
+    }
+
+    /// Copy the config file aside before migrating it so the user can roll back
+    async fn backup_before_migration(config_path: &PathBuf, version: u32) -> Result<(), ConfigError> {
+        let backup_path = config_path.with_extension(format!("v{}.bak.json", version));
+        match fs::copy(config_path, &backup_path).await {
+            Ok(_) => {
+                info!("Backed up configuration to {}", backup_path.display());
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to back up configuration before migration: {}", e);
+                Err(ConfigError::SaveError(format!(
+                    "Failed to back up configuration before migration: {}",
+                    e
+                )))
+            }
+        }
+    }
+
+    /// Move an unreadable config file to app_config.json.corrupt so it can still be inspected
+    async fn set_aside_corrupt_config(config_path: &PathBuf) {
+        let corrupt_path = config_path.with_extension("json.corrupt");
+        match fs::rename(config_path, &corrupt_path).await {
+            Ok(()) => warn!("Moved unreadable configuration to {}", corrupt_path.display()),
+            Err(e) => error!("Failed to move unreadable configuration aside: {}", e),
+        }
+    }
+
+    /// Save configuration to path (static version).
+    /// Writes atomically and keeps the previous file as app_config.json.bak.
     async fn save_config_to_path_static(
         config: &Config,
         path: &PathBuf,
+    ) -> Result<(), ConfigError> {
+        Self::write_config_file(config, path, true).await
+    }
+
+    /// Write the configuration atomically, first keeping the previous file as a backup if
+    /// `keep_backup` is set
+    async fn write_config_file(
+        config: &Config,
+        path: &Path,
+        keep_backup: bool,
     ) -> Result<(), ConfigError> {
         debug!("Serializing configuration to JSON");
         let config_json = match serde_json::to_string_pretty(config) {
@@ -337,27 +688,36 @@ impl ConfigManager {
         };
 
         debug!("Writing configuration to file: {}", path.display());
-        let mut file = match fs::File::create(path).await {
-            Ok(file) => file,
+        let target = path.to_path_buf();
+        let write_result = tokio::task::spawn_blocking(move || {
+            if keep_backup {
+                atomic_file::write_atomic_with_backup(&target, config_json.as_bytes())
+            } else {
+                atomic_file::write_atomic(&target, config_json.as_bytes())
+            }
+        })
+        .await;
+
+        match write_result {
+            Ok(Ok(())) => {
+                info!("Configuration saved successfully");
+                Ok(())
+            }
+            Ok(Err(e)) => {
+                error!("Failed to write configuration to file: {}", e);
+                Err(ConfigError::SaveError(format!(
+                    "Failed to write configuration to file: {}",
+                    e
+                )))
+            }
             Err(e) => {
-                error!("Failed to create config file: {}", e);
-                return Err(ConfigError::SaveError(format!(
-                    "Failed to create config file: {}",
+                error!("Configuration write task failed: {}", e);
+                Err(ConfigError::SaveError(format!(
+                    "Configuration write task failed: {}",
                     e
-                )));
+                )))
             }
-        };
-
-        if let Err(e) = file.write_all(config_json.as_bytes()).await {
-            error!("Failed to write configuration to file: {}", e);
-            return Err(ConfigError::SaveError(format!(
-                "Failed to write configuration to file: {}",
-                e
-            )));
         }
-
-        info!("Configuration saved successfully");
-        Ok(())
     }
 
     /// Get the configuration directory path
@@ -365,9 +725,9 @@ impl ConfigManager {
         // In Tauri 2.0, we need to fall back to standard platform-specific paths
         // since we can't access the Tauri API directly during initialization
 
-        // Get the app-specific data directory based on the platform
-        let app_data_dir = match dirs::data_dir() {
-            Some(dir) => dir.join("com.b-rad-coin.app"), // Match the identifier in tauri.conf.json
+        // Resolve through the PathProvider so a data directory override is honoured
+        let config_dir = match PathProvider::config_dir() {
+            Some(dir) => dir,
             None => {
                 error!("Failed to get app data directory");
                 return Err(ConfigError::PathError(
@@ -375,9 +735,6 @@ impl ConfigManager {
                 ));
             }
         };
-
-        // Join with our config directory name
-        let config_dir = app_data_dir.join("config");
         debug!("Configuration directory: {}", config_dir.display());
 
         // Create directory if it doesn't exist
@@ -629,7 +986,7 @@ impl ConfigManager {
     /// Reload configuration from disk
     pub async fn reload_config(&self) -> Result<(), ConfigError> {
         info!("Reloading configuration from disk");
-        let (new_config, _) = Self::load_config().await?;
+        let (new_config, _, _) = Self::load_config().await?;
         
         {
             let mut config = self.config.lock().unwrap();
@@ -639,4 +996,28 @@ impl ConfigManager {
         info!("Configuration reloaded successfully");
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_recovery_keeps_the_good_backup() {
+        let dir = std::env::temp_dir().join(format!("bradcoin_config_recovery_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config_path = dir.join("app_config.json");
+
+        // Saving twice leaves a good config and a good backup
+        ConfigManager::save_config_to_path_static(&Config::default(), &config_path).await.unwrap();
+        ConfigManager::save_config_to_path_static(&Config::default(), &config_path).await.unwrap();
+        std::fs::write(&config_path, b"{ not json").unwrap();
+
+        let (_, recovered) = ConfigManager::load_config_from(&config_path).await.unwrap();
+        assert!(recovered);
+        assert!(ConfigManager::read_config_file(&atomic_file::backup_path(&config_path)).await.is_ok());
+        assert!(ConfigManager::read_config_file(&config_path).await.is_ok());
+        assert_eq!(std::fs::read(config_path.with_extension("json.corrupt")).unwrap(), b"{ not json");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::config::AppSettings;
+use crate::errors::ConfigError;
+use log::{info, warn};
+use serde_json::{Map, Value};
+
+/// Schema version written by this build. Bump it and add a step to `MIGRATIONS` when the config shape changes.
+pub const CURRENT_CONFIG_VERSION: u32 = 1;
+
+/// A single upgrade step from version N to N + 1
+type Migration = fn(Value) -> Result<Value, ConfigError>;
+
+/// Upgrade steps, indexed by the version they upgrade from
+const MIGRATIONS: [Migration; CURRENT_CONFIG_VERSION as usize] = [migrate_v0_to_v1];
+
+/// Read the schema version of a raw config. Files written before versioning count as version 0.
+pub fn config_version(value: &Value) -> u32 {
+    value
+        .get("config_version")
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .unwrap_or(0)
+}
+
+/// Upgrade a raw config step by step until it reaches the current version
+pub fn migrate(mut value: Value) -> Result<Value, ConfigError> {
+    let mut version = config_version(&value);
+    if version > CURRENT_CONFIG_VERSION {
+        return Err(ConfigError::UnsupportedVersion {
+            found: version,
+            supported: CURRENT_CONFIG_VERSION,
+        });
+    }
+
+    while version < CURRENT_CONFIG_VERSION {
+        info!("Migrating configuration from version {} to {}", version, version + 1);
+        value = MIGRATIONS[version as usize](value)?;
+        version += 1;
+        value["config_version"] = Value::from(version);
+    }
+
+    Ok(value)
+}
+
+/// Version 0 covers the early flat layout (`theme`/`auto_backup` at the top level)
+/// and unversioned files that predate newer app settings.
+fn migrate_v0_to_v1(value: Value) -> Result<Value, ConfigError> {
+    let mut root = match value {
+        Value::Object(map) => map,
+        _ => {
+            return Err(ConfigError::ParseError(
+                "Configuration root is not an object".to_string(),
+            ))
+        }
+    };
+
+    let defaults = match serde_json::to_value(AppSettings::default()) {
+        Ok(Value::Object(map)) => map,
+        _ => {
+            return Err(ConfigError::Generic(
+                "Failed to build default app settings".to_string(),
+            ))
+        }
+    };
+
+    let mut app_settings = match root.remove("app_settings") {
+        Some(Value::Object(map)) => map,
+        Some(_) => {
+            warn!("Ignoring malformed app_settings in legacy configuration");
+            Map::new()
+        }
+        None => Map::new(),
+    };
+
+    // Move settings that used to live at the top level into app_settings
+    for key in defaults.keys() {
+        if let Some(old_value) = root.remove(key) {
+            app_settings.entry(key.clone()).or_insert(old_value);
+        }
+    }
+
+    // Fill in settings that did not exist yet
+    for (key, default_value) in defaults {
+        app_settings.entry(key).or_insert(default_value);
+    }
+
+    root.insert("app_settings".to_string(), Value::Object(app_settings));
+    root.entry("wallets".to_string())
+        .or_insert_with(|| Value::Array(Vec::new()));
+
+    Ok(Value::Object(root))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use serde_json::json;
+
+    #[test]
+    fn test_flat_legacy_config_is_migrated() {
+        let legacy = json!({ "theme": "dark", "auto_backup": false });
+
+        let migrated = migrate(legacy).unwrap();
+        assert_eq!(config_version(&migrated), CURRENT_CONFIG_VERSION);
+
+        let config: Config = serde_json::from_value(migrated).unwrap();
+        assert_eq!(config.app_settings.theme, "dark");
+        assert!(!config.app_settings.auto_backup);
+        assert!(config.wallets.is_empty());
+    }
+
+    #[test]
+    fn test_unversioned_config_keeps_existing_settings() {
+        let unversioned = json!({
+            "wallets": [],
+            "app_settings": { "theme": "light", "auto_backup": true, "developer_mode": true }
+        });
+
+        let config: Config = serde_json::from_value(migrate(unversioned).unwrap()).unwrap();
+        assert_eq!(config.app_settings.theme, "light");
+        assert!(config.app_settings.developer_mode);
+        assert_eq!(config.app_settings.log_level, AppSettings::default().log_level);
+    }
+
+    #[test]
+    fn test_newer_config_is_rejected() {
+        let newer = json!({ "config_version": CURRENT_CONFIG_VERSION + 1 });
+
+        match migrate(newer) {
+            Err(ConfigError::UnsupportedVersion { found, supported }) => {
+                assert_eq!(found, CURRENT_CONFIG_VERSION + 1);
+                assert_eq!(supported, CURRENT_CONFIG_VERSION);
+            }
+            other => panic!("expected UnsupportedVersion, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,277 @@
+//! Monetary policy: the block subsidy schedule and the coinbase rules blocks must follow.
+//!
+//! The subsidy starts at 50 coins and halves every `HALVING_INTERVAL` blocks until it reaches
+//! zero, which caps the supply at `MAX_SUPPLY`. A coinbase may claim at most the subsidy for
+//! its height plus the fees of the block's other transactions. A transaction's fee is what its
+//! inputs spend minus what its outputs pay, looked up from the outputs it spends; the `fee` a
+//! transaction declares is never trusted.
+//!
+//! Checkpoints pin known main-chain blocks by hash. A chain that disagrees with one is rejected
+//! as soon as the header arrives, and blocks up to the last checkpoint are assumed valid, so
+//! their inputs are not re-checked during initial sync.
+//!
+//! A block may not be timestamped more than `MAX_FUTURE_BLOCK_TIME_SECS` after network-adjusted
+//! time (see `network_time`).
+
+use crate::blockchain_database::{Block, Transaction};
+use thiserror::Error;
+
+/// Satoshis in one coin
+pub const COIN: u64 = 100_000_000;
+
+/// Subsidy of the first blocks, before any halving
+pub const INITIAL_SUBSIDY: u64 = 50 * COIN;
+
+/// Blocks between subsidy halvings
+pub const HALVING_INTERVAL: u64 = 210_000;
+
+/// Coins that will ever exist, in satoshis: the sum of every subsidy in the schedule
+pub const MAX_SUPPLY: u64 = 2_099_999_997_690_000;
+
+/// Known main-chain blocks as (height, hash), in height order. Added at release time once the
+/// blocks are buried deep enough that they can no longer be reorganized.
+pub const CHECKPOINTS: &[(u64, &str)] = &[];
+
+/// How far ahead of network-adjusted time a block timestamp may be
+pub const MAX_FUTURE_BLOCK_TIME_SECS: u64 = 2 * 60 * 60;
+
+/// Error type for consensus rule violations
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ConsensusError {
+    #[error("Block {actual} at height {height} does not match checkpoint {expected}")]
+    CheckpointMismatch { height: u64, expected: String, actual: String },
+
+    #[error("Block has no coinbase transaction")]
+    MissingCoinbase,
+
+    #[error("Transaction {0} is a coinbase but is not the first in the block")]
+    MisplacedCoinbase(String),
+
+    #[error("Transaction {txid} pays {outputs} satoshis but only spends {inputs}")]
+    OutputsExceedInputs { txid: String, inputs: u64, outputs: u64 },
+
+    #[error("Coinbase pays {actual} satoshis, more than the allowed {allowed} (subsidy plus fees)")]
+    CoinbaseTooLarge { actual: u64, allowed: u64 },
+
+    #[error("Block time {timestamp} is too far ahead of network time {network_time}")]
+    TimeTooNew { timestamp: u64, network_time: u64 },
+}
+
+/// Subsidy for the block at `height`
+pub fn block_subsidy(height: u64) -> u64 {
+    let halvings = height / HALVING_INTERVAL;
+    if halvings >= 64 {
+        0
+    } else {
+        INITIAL_SUBSIDY >> halvings
+    }
+}
+
+/// Number of halvings that have happened by `height`
+pub fn halvings_at(height: u64) -> u64 {
+    height / HALVING_INTERVAL
+}
+
+/// Height of the next block with a lower subsidy, if the subsidy has not reached zero yet
+pub fn next_halving_height(height: u64) -> Option<u64> {
+    if block_subsidy(height) == 0 {
+        return None;
+    }
+    Some((halvings_at(height) + 1) * HALVING_INTERVAL)
+}
+
+/// Total subsidy of all blocks from genesis up to and including `height`
+pub fn expected_supply(height: u64) -> u64 {
+    let mut supply: u64 = 0;
+    let mut era_start = 0;
+    while era_start <= height {
+        let subsidy = block_subsidy(era_start);
+        if subsidy == 0 {
+            break;
+        }
+        let era_end = (era_start + HALVING_INTERVAL - 1).min(height);
+        supply += subsidy * (era_end - era_start + 1);
+        era_start += HALVING_INTERVAL;
+    }
+    supply
+}
+
+/// Checkpoints in force: the built-in ones, or none when the user asked for full validation
+pub fn active_checkpoints(enabled: bool) -> &'static [(u64, &'static str)] {
+    if enabled {
+        CHECKPOINTS
+    } else {
+        &[]
+    }
+}
+
+/// Reject a block whose height has a checkpoint with a different hash
+pub fn check_checkpoint(checkpoints: &[(u64, &str)], height: u64, hash: &str) -> Result<(), ConsensusError> {
+    match checkpoints.iter().find(|(checkpoint_height, _)| *checkpoint_height == height) {
+        Some((_, expected)) if !expected.eq_ignore_ascii_case(hash) => Err(ConsensusError::CheckpointMismatch {
+            height,
+            expected: expected.to_string(),
+            actual: hash.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Whether a block at `height` is at or below the last checkpoint, so its transactions
+/// need not be fully validated
+pub fn is_assumed_valid(checkpoints: &[(u64, &str)], height: u64) -> bool {
+    checkpoints.iter().map(|(checkpoint_height, _)| *checkpoint_height).max().is_some_and(|last| height <= last)
+}
+
+/// Reject a block timestamped too far ahead of `network_time`
+pub fn check_block_time(block: &Block, network_time: u64) -> Result<(), ConsensusError> {
+    if block.timestamp > network_time.saturating_add(MAX_FUTURE_BLOCK_TIME_SECS) {
+        return Err(ConsensusError::TimeTooNew { timestamp: block.timestamp, network_time });
+    }
+    Ok(())
+}
+
+/// Fee of `tx`, which spends outputs worth `input_value` in total. Rejects a transaction that
+/// pays out more than it spends.
+pub fn transaction_fee(tx: &Transaction, input_value: u64) -> Result<u64, ConsensusError> {
+    let outputs = tx.outputs.iter().fold(0u64, |total, output| total.saturating_add(output.value));
+    input_value.checked_sub(outputs).ok_or_else(|| ConsensusError::OutputsExceedInputs {
+        txid: tx.txid.clone(),
+        inputs: input_value,
+        outputs,
+    })
+}
+
+/// Check that the first transaction, and only that one, is a coinbase (has no inputs)
+pub fn check_coinbase_placement(block: &Block) -> Result<&Transaction, ConsensusError> {
+    let Some(coinbase) = block.transactions.first().filter(|tx| tx.inputs.is_empty()) else {
+        return Err(ConsensusError::MissingCoinbase);
+    };
+    if let Some(tx) = block.transactions.iter().skip(1).find(|tx| tx.inputs.is_empty()) {
+        return Err(ConsensusError::MisplacedCoinbase(tx.txid.clone()));
+    }
+    Ok(coinbase)
+}
+
+/// Check the coinbase rules: the coinbase is placed correctly and its outputs claim no more than
+/// the subsidy plus `fees`, the total fee of the other transactions as `transaction_fee` computes it
+pub fn validate_coinbase(block: &Block, fees: u64) -> Result<(), ConsensusError> {
+    let coinbase = check_coinbase_placement(block)?;
+    let allowed = block_subsidy(block.height).saturating_add(fees);
+    let actual = coinbase
+        .outputs
+        .iter()
+        .fold(0u64, |total, output| total.saturating_add(output.value));
+    if actual > allowed {
+        return Err(ConsensusError::CoinbaseTooLarge { actual, allowed });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mining_service::coinbase_transaction;
+
+    #[test]
+    fn test_subsidy_schedule_and_supply() {
+        assert_eq!(block_subsidy(0), 50 * COIN);
+        assert_eq!(block_subsidy(HALVING_INTERVAL - 1), 50 * COIN);
+        assert_eq!(block_subsidy(HALVING_INTERVAL), 25 * COIN);
+        assert_eq!(block_subsidy(64 * HALVING_INTERVAL), 0);
+        assert_eq!(next_halving_height(5), Some(HALVING_INTERVAL));
+        assert_eq!(next_halving_height(64 * HALVING_INTERVAL), None);
+
+        assert_eq!(expected_supply(0), 50 * COIN);
+        assert_eq!(expected_supply(HALVING_INTERVAL), HALVING_INTERVAL * 50 * COIN + 25 * COIN);
+        assert_eq!(expected_supply(u64::MAX), MAX_SUPPLY);
+    }
+
+    #[test]
+    fn test_checkpoints() {
+        let checkpoints: &[(u64, &str)] = &[(10, "aa"), (20, "bb")];
+        assert_eq!(check_checkpoint(checkpoints, 10, "AA"), Ok(()));
+        assert_eq!(check_checkpoint(checkpoints, 15, "anything"), Ok(()));
+        assert!(matches!(
+            check_checkpoint(checkpoints, 20, "cc"),
+            Err(ConsensusError::CheckpointMismatch { height: 20, .. })
+        ));
+
+        assert!(is_assumed_valid(checkpoints, 20));
+        assert!(!is_assumed_valid(checkpoints, 21));
+        assert!(!is_assumed_valid(active_checkpoints(false), 0));
+    }
+
+    fn spend(fee_field: u64) -> Transaction {
+        let mut spend = coinbase_transaction(1, "payer", 0);
+        spend.txid = "spend".to_string();
+        spend.inputs.push(crate::blockchain_database::TransactionInput {
+            previous_txid: "coinbase_0".to_string(),
+            previous_output_index: 0,
+            script_sig: String::new(),
+            sequence: u32::MAX,
+        });
+        spend.fee = fee_field;
+        spend
+    }
+
+    #[test]
+    fn test_coinbase_may_claim_subsidy_plus_fees() {
+        let spend = spend(1_000);
+        let fee = transaction_fee(&spend, spend.outputs[0].value + 1_000).unwrap();
+        assert_eq!(fee, 1_000);
+
+        let mut coinbase = coinbase_transaction(1, "miner", 0);
+        coinbase.outputs[0].value += 1_000;
+        let mut block = Block {
+            height: 1,
+            hash: String::new(),
+            previous_hash: String::new(),
+            timestamp: 0,
+            nonce: 0,
+            difficulty: 1,
+            transactions: vec![coinbase, spend],
+            merkle_root: String::new(),
+        };
+        assert_eq!(validate_coinbase(&block, fee), Ok(()));
+
+        block.transactions[0].outputs[0].value += 1;
+        assert!(matches!(validate_coinbase(&block, fee), Err(ConsensusError::CoinbaseTooLarge { .. })));
+
+        block.transactions.swap(0, 1);
+        assert_eq!(validate_coinbase(&block, fee), Err(ConsensusError::MissingCoinbase));
+    }
+
+    #[test]
+    fn test_declared_fees_cannot_mint_coins() {
+        // Spends exactly what it pays, yet claims a large fee
+        let inflated = spend(1_000 * COIN);
+        let input_value = inflated.outputs[0].value;
+        let fee = transaction_fee(&inflated, input_value).unwrap();
+        assert_eq!(fee, 0);
+
+        let mut coinbase = coinbase_transaction(1, "miner", 0);
+        coinbase.outputs[0].value += inflated.fee;
+        let block = Block {
+            height: 1,
+            hash: String::new(),
+            previous_hash: String::new(),
+            timestamp: 0,
+            nonce: 0,
+            difficulty: 1,
+            transactions: vec![coinbase, inflated],
+            merkle_root: String::new(),
+        };
+        assert!(matches!(validate_coinbase(&block, fee), Err(ConsensusError::CoinbaseTooLarge { .. })));
+
+        // Paying out more than it spends creates coins as well
+        assert_eq!(
+            transaction_fee(&block.transactions[1], input_value - 1),
+            Err(ConsensusError::OutputsExceedInputs {
+                txid: "spend".to_string(),
+                inputs: input_value - 1,
+                outputs: input_value,
+            })
+        );
+    }
+}
@@ -0,0 +1,387 @@
+use crate::atomic_file;
+use crate::price_service::{ExchangeRate, SATOSHIS_PER_COIN};
+use crate::wallet_data::{Utxo, WalletDataError};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+
+/// File name of the acquisition/disposal ledger, stored next to wallet.dat.
+/// Kept outside wallet.dat so sync can update it without the wallet password.
+pub const COST_BASIS_FILE: &str = "cost_basis.json";
+
+/// How disposals are matched against acquisitions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CostBasisMethod {
+    /// Oldest coins are disposed of first
+    Fifo,
+    /// Every disposal uses the running average cost of all coins held
+    AverageCost,
+}
+
+impl std::str::FromStr for CostBasisMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "fifo" => Ok(CostBasisMethod::Fifo),
+            "average" | "average_cost" | "averagecost" => Ok(CostBasisMethod::AverageCost),
+            _ => Err(format!("Unknown cost basis method '{}', expected 'fifo' or 'average_cost'", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LedgerEventKind {
+    Acquisition,
+    Disposal,
+}
+
+/// Coins entering or leaving the wallet, with the price at that moment if the price feed had one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEvent {
+    pub kind: LedgerEventKind,
+    /// Amount in satoshis
+    pub amount: u64,
+    /// Unix timestamp the change was observed
+    pub timestamp: i64,
+    /// Price of one BRAD at that time
+    pub price: Option<f64>,
+    /// Currency of `price`
+    pub currency: Option<String>,
+}
+
+/// Acquisition and disposal history of one wallet
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CostBasisLedger {
+    /// Outputs seen at the last sync, keyed by "txid:vout", with their value
+    #[serde(default)]
+    known_outputs: HashMap<String, u64>,
+    #[serde(default)]
+    pub events: Vec<LedgerEvent>,
+}
+
+/// One matched piece of a disposal
+#[derive(Debug, Clone, Serialize)]
+pub struct LotDetail {
+    /// When the coins were acquired; None for average cost, which does not track lots
+    pub acquired_at: Option<i64>,
+    pub disposed_at: i64,
+    /// Amount in satoshis
+    pub amount: u64,
+    pub cost_basis: f64,
+    pub proceeds: f64,
+    pub gain: f64,
+    /// Whether an acquisition or disposal price was unknown and counted as zero
+    pub price_missing: bool,
+}
+
+/// Capital gains summary plus per-lot detail
+#[derive(Debug, Clone, Serialize)]
+pub struct CostBasisReport {
+    pub method: CostBasisMethod,
+    pub currency: String,
+    pub total_proceeds: f64,
+    pub total_cost_basis: f64,
+    pub total_gain: f64,
+    /// Satoshis still held after all disposals
+    pub remaining_amount: u64,
+    /// Number of lots that used a missing price
+    pub lots_missing_price: usize,
+    pub lots: Vec<LotDetail>,
+}
+
+impl CostBasisReport {
+    /// Render the report as CSV: one row per lot followed by a totals row
+    pub fn to_csv(&self) -> String {
+        let timestamp = |ts: i64| {
+            chrono::DateTime::from_timestamp(ts, 0)
+                .map(|dt| dt.to_rfc3339())
+                .unwrap_or_default()
+        };
+
+        let mut csv = format!(
+            "acquired_at,disposed_at,amount_brad,cost_basis_{c},proceeds_{c},gain_{c},price_missing\n",
+            c = self.currency.to_lowercase()
+        );
+        for lot in &self.lots {
+            csv.push_str(&format!(
+                "{},{},{:.8},{:.2},{:.2},{:.2},{}\n",
+                lot.acquired_at.map(timestamp).unwrap_or_default(),
+                timestamp(lot.disposed_at),
+                lot.amount as f64 / SATOSHIS_PER_COIN,
+                lot.cost_basis,
+                lot.proceeds,
+                lot.gain,
+                lot.price_missing
+            ));
+        }
+        csv.push_str(&format!(
+            "total,,{:.8},{:.2},{:.2},{:.2},{}\n",
+            self.lots.iter().map(|lot| lot.amount).sum::<u64>() as f64 / SATOSHIS_PER_COIN,
+            self.total_cost_basis,
+            self.total_proceeds,
+            self.total_gain,
+            self.lots_missing_price
+        ));
+        csv
+    }
+}
+
+impl CostBasisLedger {
+    /// Load the ledger from a wallet directory, or start an empty one
+    pub fn load(wallet_dir: &Path) -> Result<Self, WalletDataError> {
+        let path = wallet_dir.join(COST_BASIS_FILE);
+        if !path.exists() {
+            debug!("No cost basis ledger at {}, starting empty", path.display());
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Save the ledger into a wallet directory
+    pub fn save(&self, wallet_dir: &Path) -> Result<(), WalletDataError> {
+        let path = wallet_dir.join(COST_BASIS_FILE);
+        atomic_file::write_atomic(&path, serde_json::to_string_pretty(self)?.as_bytes())?;
+        debug!("Cost basis ledger saved to {}", path.display());
+        Ok(())
+    }
+
+    /// Compare the wallet's outputs with the previous sync and record what came in or went out.
+    /// Coins spent and received in the same sync are netted, so change does not count as a new acquisition.
+    /// Returns whether anything was recorded.
+    pub fn record_sync(&mut self, utxos: &[Utxo], timestamp: i64, rate: Option<&ExchangeRate>) -> bool {
+        let current: HashMap<String, u64> = utxos
+            .iter()
+            .map(|utxo| (format!("{}:{}", utxo.txid, utxo.vout), utxo.value))
+            .collect();
+
+        let received: u64 = current
+            .iter()
+            .filter(|(key, _)| !self.known_outputs.contains_key(*key))
+            .map(|(_, value)| value)
+            .sum();
+        let spent: u64 = self
+            .known_outputs
+            .iter()
+            .filter(|(key, _)| !current.contains_key(*key))
+            .map(|(_, value)| value)
+            .sum();
+        self.known_outputs = current;
+
+        let (kind, amount) = if received > spent {
+            (LedgerEventKind::Acquisition, received - spent)
+        } else if spent > received {
+            (LedgerEventKind::Disposal, spent - received)
+        } else {
+            return false;
+        };
+
+        info!("Recording {:?} of {} satoshis for cost basis", kind, amount);
+        self.events.push(LedgerEvent {
+            kind,
+            amount,
+            timestamp,
+            price: rate.map(|r| r.rate),
+            currency: rate.map(|r| r.currency.clone()),
+        });
+        true
+    }
+
+    /// Compute realized gains for disposals between `from` and `to` (inclusive, Unix timestamps)
+    pub fn generate_report(
+        &self,
+        method: CostBasisMethod,
+        currency: &str,
+        from: Option<i64>,
+        to: Option<i64>,
+    ) -> CostBasisReport {
+        let mut events: Vec<&LedgerEvent> = self.events.iter().collect();
+        events.sort_by_key(|event| event.timestamp);
+
+        // Prices recorded in another currency cannot be used for this report
+        let price_in = |event: &LedgerEvent| -> Option<f64> {
+            match (&event.currency, event.price) {
+                (Some(c), Some(price)) if c.eq_ignore_ascii_case(currency) => Some(price),
+                _ => None,
+            }
+        };
+        let in_range = |timestamp: i64| {
+            from.is_none_or(|from| timestamp >= from) && to.is_none_or(|to| timestamp <= to)
+        };
+
+        // Open lots: (acquired_at, remaining satoshis, price)
+        let mut open_lots: VecDeque<(i64, u64, Option<f64>)> = VecDeque::new();
+        let mut lots = Vec::new();
+
+        for event in events {
+            match event.kind {
+                LedgerEventKind::Acquisition => {
+                    open_lots.push_back((event.timestamp, event.amount, price_in(event)));
+                }
+                LedgerEventKind::Disposal => {
+                    let sale_price = price_in(event);
+                    let matched = match method {
+                        CostBasisMethod::Fifo => take_fifo(&mut open_lots, event.amount),
+                        CostBasisMethod::AverageCost => take_average(&mut open_lots, event.amount),
+                    };
+
+                    for (acquired_at, amount, cost_price) in matched {
+                        if !in_range(event.timestamp) {
+                            continue;
+                        }
+                        let coins = amount as f64 / SATOSHIS_PER_COIN;
+                        let cost_basis = coins * cost_price.unwrap_or(0.0);
+                        let proceeds = coins * sale_price.unwrap_or(0.0);
+                        lots.push(LotDetail {
+                            acquired_at,
+                            disposed_at: event.timestamp,
+                            amount,
+                            cost_basis,
+                            proceeds,
+                            gain: proceeds - cost_basis,
+                            price_missing: cost_price.is_none() || sale_price.is_none(),
+                        });
+                    }
+                }
+            }
+        }
+
+        let total_proceeds = lots.iter().map(|lot| lot.proceeds).sum();
+        let total_cost_basis = lots.iter().map(|lot| lot.cost_basis).sum();
+        CostBasisReport {
+            method,
+            currency: currency.to_uppercase(),
+            total_proceeds,
+            total_cost_basis,
+            total_gain: total_proceeds - total_cost_basis,
+            remaining_amount: open_lots.iter().map(|(_, amount, _)| amount).sum(),
+            lots_missing_price: lots.iter().filter(|lot| lot.price_missing).count(),
+            lots,
+        }
+    }
+}
+
+/// Consume the oldest lots first. Amounts beyond the recorded acquisitions have no known cost.
+fn take_fifo(
+    open_lots: &mut VecDeque<(i64, u64, Option<f64>)>,
+    mut amount: u64,
+) -> Vec<(Option<i64>, u64, Option<f64>)> {
+    let mut matched = Vec::new();
+    while amount > 0 {
+        let Some(lot) = open_lots.front_mut() else {
+            matched.push((None, amount, None));
+            break;
+        };
+        let taken = lot.1.min(amount);
+        matched.push((Some(lot.0), taken, lot.2));
+        lot.1 -= taken;
+        amount -= taken;
+        if lot.1 == 0 {
+            open_lots.pop_front();
+        }
+    }
+    matched
+}
+
+/// Use the average price of everything held, shrinking every lot proportionally
+fn take_average(
+    open_lots: &mut VecDeque<(i64, u64, Option<f64>)>,
+    amount: u64,
+) -> Vec<(Option<i64>, u64, Option<f64>)> {
+    let held: u64 = open_lots.iter().map(|(_, amount, _)| amount).sum();
+    if held == 0 {
+        return vec![(None, amount, None)];
+    }
+
+    let any_missing = open_lots.iter().any(|(_, _, price)| price.is_none());
+    let total_cost: f64 = open_lots
+        .iter()
+        .map(|(_, amount, price)| *amount as f64 * price.unwrap_or(0.0))
+        .sum();
+    let average = (!any_missing).then(|| total_cost / held as f64);
+
+    let taken = amount.min(held);
+    let remaining_fraction = (held - taken) as f64 / held as f64;
+    for lot in open_lots.iter_mut() {
+        lot.1 = (lot.1 as f64 * remaining_fraction).round() as u64;
+    }
+    open_lots.retain(|(_, amount, _)| *amount > 0);
+
+    let mut matched = vec![(None, taken, average)];
+    if amount > taken {
+        matched.push((None, amount - taken, None));
+    }
+    matched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COIN: u64 = 100_000_000;
+
+    fn event(kind: LedgerEventKind, amount: u64, timestamp: i64, price: f64) -> LedgerEvent {
+        LedgerEvent {
+            kind,
+            amount,
+            timestamp,
+            price: Some(price),
+            currency: Some("USD".to_string()),
+        }
+    }
+
+    fn ledger() -> CostBasisLedger {
+        CostBasisLedger {
+            known_outputs: HashMap::new(),
+            events: vec![
+                event(LedgerEventKind::Acquisition, COIN, 1, 10.0),
+                event(LedgerEventKind::Acquisition, COIN, 2, 20.0),
+                event(LedgerEventKind::Disposal, COIN, 3, 30.0),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_fifo_uses_oldest_lot() {
+        let report = ledger().generate_report(CostBasisMethod::Fifo, "USD", None, None);
+        assert_eq!(report.lots.len(), 1);
+        assert!((report.total_cost_basis - 10.0).abs() < 1e-9);
+        assert!((report.total_gain - 20.0).abs() < 1e-9);
+        assert_eq!(report.remaining_amount, COIN);
+    }
+
+    #[test]
+    fn test_average_cost_uses_mean_price() {
+        let report = ledger().generate_report(CostBasisMethod::AverageCost, "USD", None, None);
+        assert!((report.total_cost_basis - 15.0).abs() < 1e-9);
+        assert!((report.total_gain - 15.0).abs() < 1e-9);
+        assert_eq!(report.remaining_amount, COIN);
+    }
+
+    #[test]
+    fn test_record_sync_nets_change() {
+        let utxo = |txid: &str, value: u64| Utxo {
+            txid: txid.to_string(),
+            vout: 0,
+            value,
+            script_pubkey: String::new(),
+            address: String::new(),
+            is_change: false,
+            height: None,
+        };
+
+        let mut ledger = CostBasisLedger::default();
+        assert!(ledger.record_sync(&[utxo("a", 5 * COIN)], 1, None));
+        // Spend the 5 coin output, getting 3 back as change
+        assert!(ledger.record_sync(&[utxo("b", 3 * COIN)], 2, None));
+        assert!(!ledger.record_sync(&[utxo("b", 3 * COIN)], 3, None));
+
+        assert_eq!(ledger.events.len(), 2);
+        assert_eq!(ledger.events[1].kind, LedgerEventKind::Disposal);
+        assert_eq!(ledger.events[1].amount, 2 * COIN);
+    }
+}
@@ -1,25 +1,73 @@
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
+use serde::Serialize;
 use std::path::PathBuf;
 use std::fs;
+use std::sync::Arc;
 use std::time::SystemTime;
-use tauri::command;
+use tauri::{command, Manager, State};
+use crate::block_store::{StoreBackend, StoreCopy};
+use crate::blockchain_database::{AsyncBlockchainDatabase, Block, ChainTip, Transaction};
+use crate::config::ConfigManager;
+use crate::errors::{AppErrorCode, CommandError, WalletError};
+use crate::events::{EventDescriptor, EVENTS};
+use crate::key_origin::{self, DerivationAudit};
+use crate::network_service::AsyncNetworkService;
+use crate::network_trace::PeerTrace;
+use crate::paths::PathProvider;
+use crate::security::AsyncSecurityManager;
+use crate::startup_timings::{StartupReport, StartupTimings};
+use crate::wallet_manager::AsyncWalletManager;
+
+/// Most simulated peers one spawn_simulated_peer call may start
+const MAX_SIMULATED_PEERS: usize = 16;
+
+/// Stored bytes of a block or transaction with their decoded form
+#[derive(Debug, Serialize)]
+pub struct RawRecord<T> {
+    /// Block hash or transaction id that was looked up
+    pub id: String,
+    /// Size of the stored record in bytes
+    pub size: usize,
+    /// Stored record as hex, exactly as kept in the database
+    pub hex: String,
+    /// Record decoded from those bytes, if they decode
+    pub decoded: Option<T>,
+    /// Why decoding failed, if it did
+    pub decode_error: Option<String>,
+}
+
+impl<T: bincode::Decode<()>> RawRecord<T> {
+    fn from_bytes(id: &str, bytes: Vec<u8>) -> Self {
+        let (decoded, decode_error) = match bincode::decode_from_slice::<T, _>(&bytes, bincode::config::standard()) {
+            Ok((record, _)) => (Some(record), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        Self {
+            id: id.to_string(),
+            size: bytes.len(),
+            hex: hex::encode(&bytes),
+            decoded,
+            decode_error,
+        }
+    }
+}
 
 /// Get recent log entries for the developer page
 #[command]
-pub async fn get_recent_logs() -> Result<String, String> {
+pub async fn get_recent_logs() -> Result<String, CommandError> {
     info!("Command: get_recent_logs");
     
     // Get the app data directory where logs are stored
-    let log_dir = match dirs::data_dir() {
-        Some(dir) => dir.join("com.b-rad-coin.app").join("logs"),
-        None => return Err("Failed to determine log directory".to_string()),
+    let log_dir = match PathProvider::logs_dir() {
+        Some(dir) => dir,
+        None => return Err("Failed to determine log directory".to_string().into()),
     };
     
     debug!("Looking for logs in directory: {}", log_dir.display());
     
     // Check if the directory exists
     if !log_dir.exists() {
-        return Err(format!("Log directory does not exist: {}", log_dir.display()));
+        return Err(format!("Log directory does not exist: {}", log_dir.display()).into());
     }
     
     // Get a list of all log files sorted by modification time (most recent first)
@@ -37,7 +85,7 @@ pub async fn get_recent_logs() -> Result<String, String> {
         },
         Err(e) => {
             error!("Failed to read log directory: {}", e);
-            return Err(format!("Failed to read log directory: {}", e));
+            return Err(format!("Failed to read log directory: {}", e).into());
         }
     }
       // Sort log files by modification time (newest first)
@@ -67,7 +115,7 @@ pub async fn get_recent_logs() -> Result<String, String> {
                 },
                 Err(e) => {
                     error!("Failed to read log file: {}", e);
-                    return Err(format!("Failed to read log file: {}", e));
+                    return Err(format!("Failed to read log file: {}", e).into());
                 }
             }
         },
@@ -79,22 +127,210 @@ pub async fn get_recent_logs() -> Result<String, String> {
 
 /// Echo a command for the developer page
 #[command]
-pub fn echo_command(command: String) -> Result<String, String> {
+pub fn echo_command(command: String) -> Result<String, CommandError> {
     info!("Command: echo_command - {}", command);
     Ok(format!("Command received: {}\nTimestamp: {}", command, chrono::Local::now().format("%Y-%m-%d %H:%M:%S")))
 }
 
 /// Command to get the configuration directory path
 #[command]
-pub fn get_config_directory() -> Result<String, String> {
+pub fn get_config_directory() -> Result<String, CommandError> {
     info!("Command: get_config_directory");
     
     // Get the app data directory
-    let config_dir = match dirs::data_dir() {
-        Some(dir) => dir.join("com.b-rad-coin.app").join("config"),
-        None => return Err("Failed to determine config directory".to_string()),
+    let config_dir = match PathProvider::config_dir() {
+        Some(dir) => dir,
+        None => return Err("Failed to determine config directory".to_string().into()),
     };
     
     debug!("Configuration directory path: {}", config_dir.display());
     Ok(config_dir.to_string_lossy().into_owned())
 }
+
+/// Get a stored block as hex plus its decoded structure
+#[command]
+pub async fn get_raw_block(hash: String, app_handle: tauri::AppHandle) -> Result<RawRecord<Block>, CommandError> {
+    info!("Command: get_raw_block {}", hash);
+
+    let blockchain_db = crate::services::get::<AsyncBlockchainDatabase>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Blockchain database is not initialized"))?;
+    let hash = hash.trim();
+    match blockchain_db.get_raw_block_by_hash(hash).await {
+        Ok(Some(bytes)) => Ok(RawRecord::from_bytes(hash, bytes)),
+        Ok(None) => Err(CommandError::new(AppErrorCode::NotFound, format!("Block {} not found", hash))),
+        Err(e) => {
+            error!("Failed to read raw block {}: {}", hash, e);
+            Err(format!("Failed to read block: {}", e).into())
+        }
+    }
+}
+
+/// Get a stored transaction as hex plus its decoded structure
+#[command]
+pub async fn get_raw_transaction(txid: String, app_handle: tauri::AppHandle) -> Result<RawRecord<Transaction>, CommandError> {
+    info!("Command: get_raw_transaction {}", txid);
+
+    let blockchain_db = crate::services::get::<AsyncBlockchainDatabase>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Blockchain database is not initialized"))?;
+    let txid = txid.trim();
+    match blockchain_db.get_raw_transaction(txid).await {
+        Ok(Some(bytes)) => Ok(RawRecord::from_bytes(txid, bytes)),
+        Ok(None) => Err(CommandError::new(AppErrorCode::NotFound, format!("Transaction {} not found", txid))),
+        Err(e) => {
+            error!("Failed to read raw transaction {}: {}", txid, e);
+            Err(format!("Failed to read transaction: {}", e).into())
+        }
+    }
+}
+
+/// Get the recorded network message trace, for all peers or one peer ("ip:port").
+/// Empty unless network tracing is enabled in settings.
+#[command]
+pub async fn get_network_trace(peer: Option<String>, app_handle: tauri::AppHandle) -> Result<Vec<PeerTrace>, CommandError> {
+    info!("Command: get_network_trace {:?}", peer);
+
+    let peer = match peer.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        Some(peer) => Some(
+            peer.parse::<std::net::SocketAddr>()
+                .map_err(|e| format!("Invalid peer address '{}': {}", peer, e))?,
+        ),
+        None => None,
+    };
+    let network_service = crate::services::get::<AsyncNetworkService>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Network service is not running"))?;
+
+    Ok(network_service.trace().await.snapshot(peer))
+}
+
+/// Clear the recorded network message trace
+#[command]
+pub async fn clear_network_trace(app_handle: tauri::AppHandle) -> Result<(), CommandError> {
+    info!("Command: clear_network_trace");
+
+    let network_service = crate::services::get::<AsyncNetworkService>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Network service is not running"))?;
+    network_service.trace().await.clear();
+    Ok(())
+}
+
+/// List every event the backend emits, with its payload version and type, so listeners know
+/// what to subscribe to
+#[command]
+pub fn subscribe_events() -> Result<Vec<EventDescriptor>, CommandError> {
+    info!("Command: subscribe_events");
+    Ok(EVENTS.to_vec())
+}
+
+/// Spawn in-process simulated peers that sync, relay and fork against this node.
+/// Peers copy the local chain, rewound by `fork_depth` blocks and extended past the original
+/// tip by `extra_blocks`. Requires developer mode. Returns the peers' virtual addresses.
+#[command]
+pub async fn spawn_simulated_peer(
+    count: usize,
+    extra_blocks: Option<u64>,
+    fork_depth: Option<u64>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<String>, CommandError> {
+    info!("Command: spawn_simulated_peer count={} extra_blocks={:?} fork_depth={:?}", count, extra_blocks, fork_depth);
+
+    let developer_mode = app_handle
+        .try_state::<Arc<ConfigManager>>()
+        .map(|config_manager| config_manager.get_config().app_settings.developer_mode)
+        .unwrap_or(false);
+    if !developer_mode {
+        return Err("Simulated peers are only available in developer mode".to_string().into());
+    }
+    if count == 0 || count > MAX_SIMULATED_PEERS {
+        return Err(format!("Peer count must be between 1 and {}", MAX_SIMULATED_PEERS).into());
+    }
+
+    let network_service = crate::services::get::<AsyncNetworkService>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Network service is not running"))?;
+    let addrs = network_service
+        .spawn_simulated_peers(count, extra_blocks.unwrap_or(0), fork_depth.unwrap_or(0))
+        .await
+        .map_err(|e| {
+            error!("Failed to spawn simulated peers: {}", e);
+            format!("Failed to spawn simulated peers: {}", e)
+        })?;
+    Ok(addrs.iter().map(|addr| addr.to_string()).collect())
+}
+
+/// Check that every address of an open wallet derives from its master key at its stored path,
+/// flagging orphans left by earlier versions. Verified addresses without a stored key origin get
+/// one, and the wallet is saved.
+#[command]
+pub async fn audit_derivation_paths(
+    wallet_id: String,
+    wallet_manager: State<'_, AsyncWalletManager>,
+) -> Result<DerivationAudit, CommandError> {
+    info!("Command: audit_derivation_paths {}", wallet_id);
+
+    let mut manager = wallet_manager.get_manager().await;
+    let wallet = manager.get_wallet_mut(Some(&wallet_id)).ok_or_else(|| WalletError::NotFound(wallet_id.clone()))?;
+    let audit = key_origin::audit(&mut wallet.data);
+    let writable = wallet.data.read_only.is_none();
+    drop(manager);
+
+    if let Some(reason) = &audit.master_key_error {
+        warn!("Could not audit the addresses of wallet {}: {}", wallet_id, reason);
+    }
+    for entry in audit.problems() {
+        warn!("Address {} of wallet {} failed the derivation audit: {:?}", entry.address, wallet_id, entry.status);
+    }
+
+    if audit.origins_recorded > 0 && writable {
+        wallet_manager.save_wallet(&wallet_id).await.map_err(|e| {
+            error!("Failed to save key origins of wallet {}: {}", wallet_id, e);
+            format!("Failed to save wallet data: {}", e)
+        })?;
+    }
+    Ok(audit)
+}
+
+/// Wipe the decrypted signing keys cached for one wallet, or for every wallet when no name is
+/// given, returning how many were wiped. Unlock sessions stay open; keys are decrypted again on
+/// the next signature.
+#[command]
+pub async fn flush_key_cache(
+    wallet_name: Option<String>,
+    security_manager: State<'_, AsyncSecurityManager>,
+) -> Result<usize, CommandError> {
+    info!("Command: flush_key_cache {:?}", wallet_name);
+    Ok(security_manager.get_manager().await.flush_key_cache(wallet_name.as_deref()))
+}
+
+/// Get every known tip of the block tree with its height, cumulative work and status, to inspect
+/// forks and reorgs
+#[command]
+pub async fn get_chain_tips(app_handle: tauri::AppHandle) -> Result<Vec<ChainTip>, CommandError> {
+    info!("Command: get_chain_tips");
+
+    let blockchain_db = crate::services::get::<AsyncBlockchainDatabase>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Blockchain database is not initialized"))?;
+    blockchain_db.chain_tips().await.map_err(|e| {
+        error!("Failed to read the chain tips: {}", e);
+        format!("Failed to read the chain tips: {}", e).into()
+    })
+}
+
+/// Get how long each startup phase took, to find startup regressions
+#[command]
+pub async fn get_startup_timings(timings: State<'_, StartupTimings>) -> Result<StartupReport, CommandError> {
+    info!("Command: get_startup_timings");
+    Ok(timings.report())
+}
+
+/// Move the blockchain database to another storage backend. Every record is copied and verified
+/// before the database switches over; the old store is kept beside the new one.
+#[command]
+pub async fn migrate_block_store(backend: StoreBackend, app_handle: tauri::AppHandle) -> Result<StoreCopy, CommandError> {
+    info!("Command: migrate_block_store to {:?}", backend);
+
+    let blockchain_db = crate::services::get::<AsyncBlockchainDatabase>(&app_handle)
+        .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Blockchain database is not initialized"))?;
+    blockchain_db.migrate_store(backend).await.map_err(|e| {
+        error!("Failed to migrate the blockchain store to {:?}: {:#}", backend, e);
+        format!("Failed to migrate the blockchain store: {:#}", e).into()
+    })
+}
@@ -1,3 +1,5 @@
+use crate::i18n::{self, Locale};
+use serde::Serialize;
 use serde_json;
 use std::error::Error;
 use std::fmt;
@@ -90,6 +92,10 @@ pub enum WalletError {
     ConfigError(String),
     KeyDerivationError(String),
     NoWalletOpen,
+    InvalidPassword,
+    InUse(String),
+    /// The wallet is open read-only; the message says why
+    ReadOnly(String),
     Generic(String),
 }
 
@@ -103,6 +109,9 @@ impl fmt::Display for WalletError {
             WalletError::ConfigError(msg) => write!(f, "Configuration error: {}", msg),
             WalletError::KeyDerivationError(msg) => write!(f, "Key derivation error: {}", msg),
             WalletError::NoWalletOpen => write!(f, "No wallet is currently open"),
+            WalletError::InvalidPassword => write!(f, "Invalid password"),
+            WalletError::InUse(name) => write!(f, "Wallet '{}' is in use by another process", name),
+            WalletError::ReadOnly(msg) => write!(f, "{}", msg),
             WalletError::Generic(msg) => write!(f, "{}", msg),
         }
     }
@@ -115,11 +124,26 @@ impl From<crate::wallet_data::WalletDataError> for WalletError {
     fn from(error: crate::wallet_data::WalletDataError) -> Self {
         use crate::wallet_data::WalletDataError;
         match error {
-            WalletDataError::InvalidPassword => WalletError::AccessDenied("Invalid password".to_string()),
+            WalletDataError::InvalidPassword => WalletError::InvalidPassword,
             WalletDataError::DecryptionError(msg) => WalletError::AccessDenied(format!("Decryption failed: {}", msg)),
             WalletDataError::EncryptionError(msg) => WalletError::Generic(format!("Encryption failed: {}", msg)),
             WalletDataError::IoError(err) => WalletError::Generic(format!("IO error: {}", err)),
             WalletDataError::SerializationError(err) => WalletError::Generic(format!("Serialization error: {}", err)),
+            WalletDataError::ReadOnly(_) => WalletError::ReadOnly(error.to_string()),
+            WalletDataError::ChecksumMismatch
+            | WalletDataError::UnsupportedVersion { .. }
+            | WalletDataError::InvalidFormat(_) => WalletError::Generic(error.to_string()),
+        }
+    }
+}
+
+// Convert LockError to WalletError
+impl From<crate::file_lock::LockError> for WalletError {
+    fn from(error: crate::file_lock::LockError) -> Self {
+        use crate::file_lock::LockError;
+        match error {
+            LockError::InUse { resource, .. } => WalletError::InUse(resource),
+            LockError::IoError(err) => WalletError::Generic(format!("Failed to lock wallet: {}", err)),
         }
     }
 }
@@ -131,6 +155,7 @@ pub enum ConfigError {
     SaveError(String),
     ParseError(String),
     PathError(String),
+    UnsupportedVersion { found: u32, supported: u32 },
     Generic(String),
 }
 
@@ -141,6 +166,11 @@ impl fmt::Display for ConfigError {
             ConfigError::SaveError(msg) => write!(f, "Failed to save configuration: {}", msg),
             ConfigError::ParseError(msg) => write!(f, "Failed to parse configuration: {}", msg),
             ConfigError::PathError(msg) => write!(f, "Configuration path error: {}", msg),
+            ConfigError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "Configuration version {} is newer than this app supports ({}). Please update the app.",
+                found, supported
+            ),
             ConfigError::Generic(msg) => write!(f, "{}", msg),
         }
     }
@@ -174,3 +204,228 @@ impl Error for SecurityError {}
 
 /// Result type alias for Application results
 pub type AppResult<T> = Result<T, AppError>;
+
+/// Error kinds reported to the frontend, which branches on them and localizes the message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AppErrorCode {
+    WalletNotFound,
+    WalletAlreadyExists,
+    WalletInUse,
+    WalletReadOnly,
+    NoWalletOpen,
+    PasswordRequired,
+    InvalidPassword,
+    AccessDenied,
+    KeyDerivationFailed,
+    InvalidOperation,
+    ConfigLoadFailed,
+    ConfigSaveFailed,
+    ConfigInvalid,
+    ConfigUnsupportedVersion,
+    AuthenticationFailed,
+    EncryptionFailed,
+    NetworkError,
+    ServiceUnavailable,
+    NotFound,
+    FileNotFound,
+    PermissionDenied,
+    IoError,
+    SerializationError,
+    InvalidInput,
+    MissingAddress,
+    InvalidAddress,
+    WrongNetwork,
+    InvalidAmount,
+    ZeroAmount,
+    AmountTooLarge,
+    InsufficientFunds,
+    UtxoFrozen,
+    WeakPassword,
+    /// A send would exceed the wallet's daily spending limit
+    SpendingLimitExceeded,
+    /// A send above the wallet's threshold needs its password typed again
+    SpendConfirmationRequired,
+    /// A large send is held back until its cooling-off period is over
+    CoolingOffPending,
+    Internal,
+}
+
+/// Error returned by every Tauri command: a code to branch on, a readable message, and
+/// optionally the underlying error for diagnostics
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommandError {
+    pub code: AppErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+}
+
+impl CommandError {
+    /// Outside English, a code with a translated summary leads with that summary and keeps the
+    /// specific English message as the details
+    pub fn new(code: AppErrorCode, message: impl Into<String>) -> Self {
+        let message = message.into();
+        let locale = i18n::current_locale();
+        let summary = match serde_json::to_value(code) {
+            Ok(serde_json::Value::String(name)) if locale != Locale::En => i18n::error_summary(locale, &name),
+            _ => None,
+        };
+        match summary {
+            Some(summary) => Self {
+                code,
+                message: summary.to_string(),
+                details: Some(message),
+            },
+            None => Self {
+                code,
+                message,
+                details: None,
+            },
+        }
+    }
+
+    /// Error without a more specific code
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(AppErrorCode::Internal, message)
+    }
+
+    pub fn with_details(mut self, details: impl fmt::Display) -> Self {
+        self.details = Some(details.to_string());
+        self
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.details {
+            Some(details) => write!(f, "{}: {}", self.message, details),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl Error for CommandError {}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::internal(message)
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        CommandError::internal(message)
+    }
+}
+
+impl From<WalletError> for CommandError {
+    fn from(error: WalletError) -> Self {
+        let code = match &error {
+            WalletError::NotFound(_) => AppErrorCode::WalletNotFound,
+            WalletError::AccessDenied(_) => AppErrorCode::AccessDenied,
+            WalletError::AlreadyExists(_) => AppErrorCode::WalletAlreadyExists,
+            WalletError::InvalidOperation(_) => AppErrorCode::InvalidOperation,
+            WalletError::ConfigError(_) => AppErrorCode::ConfigInvalid,
+            WalletError::KeyDerivationError(_) => AppErrorCode::KeyDerivationFailed,
+            WalletError::NoWalletOpen => AppErrorCode::NoWalletOpen,
+            WalletError::InvalidPassword => AppErrorCode::InvalidPassword,
+            WalletError::InUse(_) => AppErrorCode::WalletInUse,
+            WalletError::ReadOnly(_) => AppErrorCode::WalletReadOnly,
+            WalletError::Generic(_) => AppErrorCode::Internal,
+        };
+        CommandError::new(code, error.to_string())
+    }
+}
+
+impl From<ConfigError> for CommandError {
+    fn from(error: ConfigError) -> Self {
+        let code = match &error {
+            ConfigError::LoadError(_) => AppErrorCode::ConfigLoadFailed,
+            ConfigError::SaveError(_) => AppErrorCode::ConfigSaveFailed,
+            ConfigError::ParseError(_) | ConfigError::PathError(_) | ConfigError::Generic(_) => {
+                AppErrorCode::ConfigInvalid
+            }
+            ConfigError::UnsupportedVersion { .. } => AppErrorCode::ConfigUnsupportedVersion,
+        };
+        CommandError::new(code, error.to_string())
+    }
+}
+
+impl From<SecurityError> for CommandError {
+    fn from(error: SecurityError) -> Self {
+        let code = match &error {
+            SecurityError::AuthenticationFailed(_) => AppErrorCode::AuthenticationFailed,
+            SecurityError::InvalidCredentials(_) => AppErrorCode::InvalidPassword,
+            SecurityError::EncryptionError(_) | SecurityError::DecryptionError(_) => AppErrorCode::EncryptionFailed,
+            SecurityError::Generic(_) => AppErrorCode::Internal,
+        };
+        CommandError::new(code, error.to_string())
+    }
+}
+
+impl From<io::Error> for CommandError {
+    fn from(error: io::Error) -> Self {
+        let code = match error.kind() {
+            io::ErrorKind::NotFound => AppErrorCode::FileNotFound,
+            io::ErrorKind::PermissionDenied => AppErrorCode::PermissionDenied,
+            _ => AppErrorCode::IoError,
+        };
+        CommandError::new(code, format!("IO error: {}", error))
+    }
+}
+
+impl From<AppError> for CommandError {
+    fn from(error: AppError) -> Self {
+        match error {
+            AppError::Wallet(err) => err.into(),
+            AppError::Config(err) => err.into(),
+            AppError::Security(err) => err.into(),
+            AppError::Io(err) => err.into(),
+            AppError::Network(_) => CommandError::new(AppErrorCode::NetworkError, error.to_string()),
+            AppError::Json(_) => CommandError::new(AppErrorCode::SerializationError, error.to_string()),
+            AppError::Generic(msg) => CommandError::internal(msg),
+        }
+    }
+}
+
+impl From<crate::validation::ValidationError> for CommandError {
+    fn from(error: crate::validation::ValidationError) -> Self {
+        CommandError::new(error.code(), error.to_string())
+    }
+}
+
+impl From<crate::coin_selection::CoinSelectionError> for CommandError {
+    fn from(error: crate::coin_selection::CoinSelectionError) -> Self {
+        CommandError::new(error.code(), error.to_string())
+    }
+}
+
+impl From<crate::ownership_proof::ProofError> for CommandError {
+    fn from(error: crate::ownership_proof::ProofError) -> Self {
+        CommandError::new(error.code(), error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_errors_keep_their_code() {
+        let error: CommandError = AppError::Wallet(WalletError::InvalidPassword).into();
+        assert_eq!(error.code, AppErrorCode::InvalidPassword);
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({ "code": "INVALID_PASSWORD", "message": "Invalid password" })
+        );
+
+        let missing: CommandError = io::Error::new(io::ErrorKind::NotFound, "gone").into();
+        assert_eq!(missing.code, AppErrorCode::FileNotFound);
+
+        let plain: CommandError = "Something failed".into();
+        let detailed = plain.with_details("disk full");
+        assert_eq!(detailed.code, AppErrorCode::Internal);
+        assert_eq!(detailed.to_string(), "Something failed: disk full");
+    }
+}
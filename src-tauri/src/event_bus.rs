@@ -0,0 +1,87 @@
+//! Events passed between backend services.
+//!
+//! `events` is what the frontend hears about; this bus is how services hear about each other.
+//! A service publishes what happened (a block connected, a transaction accepted, a peer
+//! handshake finished, a wallet opened, settings saved) and whichever services care subscribe,
+//! so the publisher needs no reference to them. One bus lives in Tauri state for the app; a
+//! service constructed on its own, as in tests, can be handed a private bus instead.
+
+use crate::blockchain_database::{Block, Transaction};
+use crate::config::AppSettings;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast;
+
+/// Events kept for subscribers that fall behind
+const EVENT_BUS_CAPACITY: usize = 256;
+
+/// Something one backend service tells the others
+#[derive(Debug, Clone)]
+pub enum BackendEvent {
+    /// A block was stored at the tip or above it
+    BlockConnected(Arc<Block>),
+    /// A block was replaced by another at the same height; its BlockConnected event follows
+    BlockDisconnected(Arc<Block>),
+    /// A transaction was accepted into the mempool
+    TransactionAdded(Arc<Transaction>),
+    /// A peer finished the version handshake
+    PeerConnected { address: String },
+    WalletOpened { wallet_name: String },
+    /// The app settings were saved
+    SettingsChanged(Arc<AppSettings>),
+}
+
+/// A broadcast channel of backend events; clones share the channel
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<BackendEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self { sender: broadcast::channel(EVENT_BUS_CAPACITY).0 }
+    }
+}
+
+impl EventBus {
+    /// Send an event to the current subscribers; does nothing if there are none
+    pub fn publish(&self, event: BackendEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Receive events from now on. A subscriber that falls more than the bus capacity behind
+    /// gets `RecvError::Lagged` and should catch up from the source of truth.
+    pub fn subscribe(&self) -> broadcast::Receiver<BackendEvent> {
+        self.sender.subscribe()
+    }
+}
+
+/// Publish on the app's bus, if it has been set up
+pub fn publish(app_handle: &AppHandle, event: BackendEvent) {
+    if let Some(bus) = app_handle.try_state::<EventBus>() {
+        bus.publish(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_subscribers_receive_events_published_after_subscribing() {
+        let bus = EventBus::default();
+        bus.publish(BackendEvent::WalletOpened { wallet_name: "missed".to_string() });
+
+        let mut first = bus.subscribe();
+        let mut second = bus.clone().subscribe();
+        bus.publish(BackendEvent::PeerConnected { address: "127.0.0.1:8333".to_string() });
+
+        for receiver in [&mut first, &mut second] {
+            match receiver.recv().await.unwrap() {
+                BackendEvent::PeerConnected { address } => assert_eq!(address, "127.0.0.1:8333"),
+                other => panic!("unexpected event {:?}", other),
+            }
+            assert!(receiver.try_recv().is_err());
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Events the backend emits to the frontend.
+//!
+//! Every event is sent wrapped in an `EventEnvelope` carrying the event name, the version of
+//! its payload and when it was emitted, so listeners can check what they received instead of
+//! guessing from the shape of the payload. Events that only signal something still send an
+//! envelope, with a null payload. `EVENTS` lists every event for `subscribe_events`; bump an
+//! event's version there whenever its payload changes incompatibly. Some events are also
+//! streamed to external apps through the push API (see `push_api::PUSHED_EVENTS`).
+
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Runtime};
+
+pub const ADDRESS_INDEX_PROGRESS: &str = "address-index-progress";
+pub const APP_INITIALIZATION_ERROR: &str = "app-initialization-error";
+pub const APP_READY: &str = "app-ready";
+pub const APP_SHUTDOWN_COMPLETE: &str = "app-shutdown-complete";
+pub const BLOCKCHAIN_SERVICES_READY: &str = "blockchain-services-ready";
+pub const BLOCKCHAIN_SETUP_ERROR: &str = "blockchain-setup-error";
+pub const BLOCKCHAIN_SETUP_REQUIRED: &str = "blockchain-setup-required";
+pub const BLOCKCHAIN_STATUS: &str = "blockchain-status";
+pub const CLIPBOARD_CLEARED: &str = "clipboard-cleared";
+pub const CLOCK_SKEW_CHANGED: &str = "clock-skew-changed";
+pub const CONFIG_RECOVERED: &str = "config-recovered";
+pub const CONFIG_VERSION_UNSUPPORTED: &str = "config-version-unsupported";
+pub const DATABASE_MIGRATION_PROGRESS: &str = "database-migration-progress";
+pub const EXCHANGE_RATES_UPDATED: &str = "exchange-rates-updated";
+pub const MEMPOOL_UPDATE: &str = "mempool-update";
+pub const MINING_STATUS: &str = "mining-status";
+pub const NETWORK_STATS: &str = "network-stats";
+pub const PAYMENT_REQUEST_PAID: &str = "payment-request-paid";
+pub const PAYMENT_REQUEST_UPDATED: &str = "payment-request-updated";
+pub const PAYMENT_URI_ERROR: &str = "payment-uri-error";
+pub const PAYMENT_URI_RECEIVED: &str = "payment-uri-received";
+pub const POWER_STATE_CHANGED: &str = "power-state-changed";
+pub const PRIVATE_KEY_REVEALED: &str = "private-key-revealed";
+pub const SCHEDULED_PAYMENT_DUE: &str = "scheduled-payment-due";
+pub const SCHEDULED_PAYMENT_SENT: &str = "scheduled-payment-sent";
+pub const SCHEDULED_PAYMENT_SKIPPED: &str = "scheduled-payment-skipped";
+pub const SECOND_INSTANCE: &str = "second-instance";
+pub const SYNC_STALLED: &str = "sync-stalled";
+pub const TRAY_CREATE_WALLET: &str = "tray-create-wallet";
+pub const TRAY_OPEN_WALLET: &str = "tray-open-wallet";
+pub const UPDATE_AVAILABLE: &str = "update-available";
+pub const UPDATE_DOWNLOAD_PROGRESS: &str = "update-download-progress";
+pub const UPDATE_READY: &str = "update-ready";
+pub const WALLET_CLOSED: &str = "wallet-closed";
+pub const WALLET_COINS_RECEIVED: &str = "wallet-coins-received";
+pub const WALLET_LOCKED: &str = "wallet-locked";
+pub const WALLET_RENAMED: &str = "wallet-renamed";
+pub const WALLET_SESSION_EXPIRED: &str = "wallet-session-expired";
+pub const WALLET_SYNC_STATUS: &str = "wallet-sync-status";
+pub const WALLETS_DELETED: &str = "wallets-deleted";
+
+/// What every emitted event carries
+#[derive(Debug, Serialize)]
+pub struct EventEnvelope<'a, T: ?Sized> {
+    pub event: &'a str,
+    /// Version of the payload format
+    pub version: u32,
+    /// Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    pub payload: &'a T,
+}
+
+impl<T: ?Sized> Clone for EventEnvelope<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            event: self.event,
+            version: self.version,
+            timestamp: self.timestamp,
+            payload: self.payload,
+        }
+    }
+}
+
+/// An event the backend may emit, as listed by `subscribe_events`
+#[derive(Debug, Clone, Serialize)]
+pub struct EventDescriptor {
+    pub name: &'static str,
+    pub version: u32,
+    /// Rust type of the payload, or "null" for events that only signal something
+    pub payload: &'static str,
+    pub description: &'static str,
+}
+
+const fn event(name: &'static str, payload: &'static str, description: &'static str) -> EventDescriptor {
+    EventDescriptor {
+        name,
+        version: 1,
+        payload,
+        description,
+    }
+}
+
+/// Every event the backend emits
+pub const EVENTS: &[EventDescriptor] = &[
+    event(ADDRESS_INDEX_PROGRESS, "AddressIndexStatus", "The address index covers more of the chain"),
+    event(APP_INITIALIZATION_ERROR, "CommandError", "Startup failed before the wallet could be used"),
+    event(APP_READY, "null", "Settings and wallets are loaded; blockchain services are still starting"),
+    event(APP_SHUTDOWN_COMPLETE, "null", "Resources are flushed and the app is about to exit"),
+    event(BLOCKCHAIN_SERVICES_READY, "null", "Blockchain, network and mining services are running"),
+    event(BLOCKCHAIN_SETUP_ERROR, "CommandError", "Blockchain services failed to start"),
+    event(BLOCKCHAIN_SETUP_REQUIRED, "BlockchainSetupRequired", "No blockchain database could be set up automatically"),
+    EventDescriptor {
+        version: 2,
+        ..event(BLOCKCHAIN_STATUS, "NodeStatus", "Chain, sync, peer, mempool and mining state, every few seconds")
+    },
+    event(CLIPBOARD_CLEARED, "null", "Sensitive data copied to the clipboard was cleared"),
+    event(CLOCK_SKEW_CHANGED, "ClockSkewStatus", "The local clock started or stopped disagreeing with network time"),
+    event(CONFIG_RECOVERED, "null", "The configuration was corrupt and has been restored from its backup"),
+    event(CONFIG_VERSION_UNSUPPORTED, "ConfigVersionUnsupported", "The configuration was written by a newer version"),
+    event(DATABASE_MIGRATION_PROGRESS, "MigrationProgress", "The blockchain database is being upgraded to the current layout"),
+    event(EXCHANGE_RATES_UPDATED, "PriceSnapshot", "New exchange rates were fetched"),
+    event(MEMPOOL_UPDATE, "MempoolStats", "Transactions entered or left the mempool"),
+    event(MINING_STATUS, "MiningStatus | null", "Mining started, stopped or found a block"),
+    event(NETWORK_STATS, "NetworkStats", "Periodic peer and traffic statistics"),
+    event(PAYMENT_REQUEST_PAID, "PaymentRequestEvent", "A payment request received its full amount"),
+    event(PAYMENT_REQUEST_UPDATED, "PaymentRequestEvent", "A payment request was partly paid or expired"),
+    event(PAYMENT_URI_ERROR, "PaymentUriErrorEvent", "A payment link was opened but is invalid"),
+    event(PAYMENT_URI_RECEIVED, "PaymentUri", "A valid payment link was opened"),
+    event(POWER_STATE_CHANGED, "PowerStatus", "Mining was paused or resumed for sleep or battery power, or the power source changed"),
+    event(PRIVATE_KEY_REVEALED, "PrivateKeyAccessEvent", "A private key was exported"),
+    event(SCHEDULED_PAYMENT_DUE, "ScheduledPaymentEvent", "A scheduled payment is due"),
+    event(SCHEDULED_PAYMENT_SENT, "ScheduledPaymentEvent", "A scheduled payment was broadcast"),
+    event(SCHEDULED_PAYMENT_SKIPPED, "ScheduledPaymentEvent", "A due scheduled payment was held back or dropped"),
+    event(SECOND_INSTANCE, "SecondInstancePayload", "The app was launched again while running"),
+    event(SYNC_STALLED, "SyncStalled", "Block download stopped on a peer and was moved to another"),
+    event(TRAY_CREATE_WALLET, "null", "Create Wallet was chosen in the tray menu"),
+    event(TRAY_OPEN_WALLET, "null", "Open Wallet was chosen in the tray menu"),
+    event(UPDATE_AVAILABLE, "UpdateInfo", "A newer release is available"),
+    event(UPDATE_DOWNLOAD_PROGRESS, "UpdateDownloadProgress", "Bytes of an update downloaded so far"),
+    event(UPDATE_READY, "UpdateInfo", "An update is downloaded and installs on restart"),
+    event(WALLET_CLOSED, "null", "The active wallet was closed from the tray menu"),
+    event(WALLET_COINS_RECEIVED, "WalletCoinsReceived", "A wallet sync found new coins, including change from its own payments"),
+    event(WALLET_LOCKED, "WalletSessionEvent", "Unlocked wallets were locked because the app was idle"),
+    event(WALLET_RENAMED, "WalletRenamedEvent", "A wallet was renamed"),
+    event(WALLET_SESSION_EXPIRED, "WalletSessionEvent", "A wallet was locked by timeout or by the user"),
+    event(WALLET_SYNC_STATUS, "WalletSyncStatus", "Progress of scanning the chain for a wallet"),
+    event(WALLETS_DELETED, "null", "Wallets were deleted in developer mode"),
+];
+
+/// Payload of `blockchain-setup-required`
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockchainSetupRequired {
+    /// "no_default_location" or "auto_create_failed"
+    pub reason: &'static str,
+    pub message: String,
+}
+
+/// Payload of `config-version-unsupported`
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigVersionUnsupported {
+    pub found: u32,
+    pub supported: u32,
+}
+
+/// Payload of `wallet-coins-received`
+#[derive(Debug, Clone, Serialize)]
+pub struct WalletCoinsReceived {
+    pub wallet_name: String,
+    pub txid: String,
+    /// Satoshis the transaction paid to the wallet
+    pub amount: u64,
+    pub block_height: Option<u32>,
+}
+
+/// Emit events wrapped in their envelope. Implemented for everything that can emit: the app
+/// handle, windows and webviews.
+pub trait EmitEvent<R: Runtime> {
+    fn emit_event<T: Serialize + ?Sized>(&self, event: &str, payload: &T) -> tauri::Result<()>;
+}
+
+impl<R: Runtime, E: Emitter<R>> EmitEvent<R> for E {
+    fn emit_event<T: Serialize + ?Sized>(&self, event: &str, payload: &T) -> tauri::Result<()> {
+        let envelope = envelope(event, payload);
+        crate::push_api::publish(&envelope);
+        self.emit(event, envelope)
+    }
+}
+
+fn envelope<'a, T: ?Sized>(event: &'a str, payload: &'a T) -> EventEnvelope<'a, T> {
+    let version = EVENTS.iter().find(|e| e.name == event).map_or(1, |e| e.version);
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default();
+    EventEnvelope {
+        event,
+        version,
+        timestamp,
+        payload,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_shape() {
+        let value = serde_json::to_value(envelope(BLOCKCHAIN_SERVICES_READY, &())).unwrap();
+        assert_eq!(value["event"], "blockchain-services-ready");
+        assert_eq!(value["version"], 1);
+        assert!(value["timestamp"].as_u64().unwrap() > 0);
+        assert!(value["payload"].is_null());
+
+        let mut names: Vec<&str> = EVENTS.iter().map(|e| e.name).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), EVENTS.len(), "event names are unique");
+    }
+}
@@ -67,18 +67,23 @@ impl FeeEstimator {
     pub async fn estimate_fees(&self) -> AppResult<Vec<FeeEstimate>> {
         let mut estimates = Vec::new();
 
-        // Get current mempool state
-        let mempool_stats = if let Some(ref mempool) = self.mempool {
-            Some(mempool.get_stats().await)
-        } else {
-            None
+        // Get current mempool state. In blocks-only mode the mempool holds only this node's own
+        // transactions and says nothing about the network, so estimates rest on blocks alone.
+        let (mempool_stats, mempool_min_fee_rate) = match self.mempool {
+            Some(ref mempool) if mempool.policy().await.blocks_only => (None, mempool.get_min_fee_rate().await),
+            Some(ref mempool) => {
+                let stats = mempool.get_stats().await;
+                let min_fee_rate = stats.mempool_min_fee_rate;
+                (Some(stats), min_fee_rate)
+            }
+            None => (None, 0),
         };
 
         // Get historical data
         let historical = self.historical_data.read().await;
         
         for &target in &[FeeTarget::NextBlock, FeeTarget::Fast, FeeTarget::Normal, FeeTarget::Slow] {
-            let estimate = self.calculate_fee_for_target(target, &mempool_stats, &historical).await?;
+            let estimate = self.calculate_fee_for_target(target, &mempool_stats, mempool_min_fee_rate, &historical).await?;
             estimates.push(estimate);
         }
 
@@ -90,6 +95,7 @@ impl FeeEstimator {
         &self,
         target: FeeTarget,
         mempool_stats: &Option<crate::mempool_service::MempoolStats>,
+        mempool_min_fee_rate: u64,
         historical: &VecDeque<BlockFeeData>,
     ) -> AppResult<FeeEstimate> {
         let target_blocks = target as u64;
@@ -146,9 +152,10 @@ impl FeeEstimator {
         // Estimate confirmation time (60 seconds per block average)
         let estimated_time = target_blocks * 60;
 
+        // Never recommend less than the mempool currently accepts
         Ok(FeeEstimate {
             target,
-            fee_rate: estimated_fee_rate.max(1000), // Ensure minimum fee
+            fee_rate: estimated_fee_rate.max(1000).max(mempool_min_fee_rate), // Ensure minimum fee
             confidence,
             estimated_time,
         })
@@ -0,0 +1,143 @@
+//! Advisory locks that keep two processes from writing the same wallet or blockchain directory.
+//!
+//! A lock is an OS file lock on a small `.lock` file that records the owning process. The OS drops
+//! the lock when the process exits, so a lock file that still names an owner but can be locked was
+//! left behind by a crash and is taken over.
+
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Name of the lock file kept in a wallet directory
+pub const WALLET_LOCK_FILE: &str = "wallet.lock";
+
+/// Name of the lock file kept in the blockchain data directory
+pub const BLOCKCHAIN_LOCK_FILE: &str = "blockchain.lock";
+
+/// Error type for lock operations
+#[derive(Error, Debug)]
+pub enum LockError {
+    #[error("{resource} is in use by another process")]
+    InUse { resource: String, pid: Option<u32> },
+
+    #[error("I/O error: {0}")]
+    IoError(#[from] io::Error),
+}
+
+/// Owner details written into the lock file
+#[derive(Debug, Serialize, Deserialize)]
+struct LockOwner {
+    pid: u32,
+    acquired_at: i64,
+}
+
+/// An exclusive lock, released when dropped
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Take the lock at `lock_path` without waiting. `resource` names the locked thing in errors.
+    pub fn acquire(lock_path: &Path, resource: &str) -> Result<Self, LockError> {
+        if let Some(dir) = lock_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(lock_path)?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                // Windows does not allow reading a locked file, so the owner may be unknown
+                let pid = read_owner(lock_path).map(|owner| owner.pid);
+                warn!("{} is locked by process {:?} ({})", resource, pid, lock_path.display());
+                return Err(LockError::InUse {
+                    resource: resource.to_string(),
+                    pid,
+                });
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        // An owner is only recorded while a lock is held, so one left over means the holder crashed
+        let mut previous = String::new();
+        if file.read_to_string(&mut previous).is_ok() {
+            if let Ok(owner) = serde_json::from_str::<LockOwner>(&previous) {
+                warn!(
+                    "Recovered stale lock on {} left by process {} (acquired at {})",
+                    resource, owner.pid, owner.acquired_at
+                );
+            }
+        }
+
+        let owner = LockOwner {
+            pid: std::process::id(),
+            acquired_at: chrono::Utc::now().timestamp(),
+        };
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&serde_json::to_vec(&owner).map_err(io::Error::other)?)?;
+        file.sync_all()?;
+
+        info!("Acquired lock on {} ({})", resource, lock_path.display());
+        Ok(Self {
+            file,
+            path: lock_path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // The file is emptied rather than deleted: deleting would let another process lock
+        // the unlinked file while a third creates a new one
+        if let Err(e) = self.file.set_len(0) {
+            warn!("Failed to clear lock file {}: {}", self.path.display(), e);
+        }
+        if let Err(e) = self.file.unlock() {
+            warn!("Failed to release lock {}: {}", self.path.display(), e);
+        }
+        debug!("Released lock {}", self.path.display());
+    }
+}
+
+fn read_owner(lock_path: &Path) -> Option<LockOwner> {
+    let contents = fs::read_to_string(lock_path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive_and_recovers_stale_owner() {
+        let dir = std::env::temp_dir().join(format!("bradcoin_lock_test_{}", std::process::id()));
+        let lock_path = dir.join(WALLET_LOCK_FILE);
+
+        // Owner info left behind by a crashed process does not block the lock
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&lock_path, r#"{"pid":1,"acquired_at":0}"#).unwrap();
+
+        let lock = FileLock::acquire(&lock_path, "Test wallet").unwrap();
+        assert!(matches!(
+            FileLock::acquire(&lock_path, "Test wallet"),
+            Err(LockError::InUse { .. })
+        ));
+
+        drop(lock);
+        assert!(FileLock::acquire(&lock_path, "Test wallet").is_ok());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,291 @@
+//! Translations of user-facing strings the backend produces itself: tray menu labels and
+//! tooltip, and the summaries of command errors.
+//!
+//! Each locale has a catalog of key → template. Templates name their arguments in braces,
+//! e.g. "Wallet: {name}". A key missing from a catalog falls back to English, and a key missing
+//! from English is shown as is, so a gap in a translation never hides a message. Log messages
+//! stay in English.
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Languages the backend has catalogs for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+    De,
+    Fr,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 4] = [Locale::En, Locale::Es, Locale::De, Locale::Fr];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Es => "es",
+            Locale::De => "de",
+            Locale::Fr => "fr",
+        }
+    }
+
+    fn catalog(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::En => EN,
+            Locale::Es => ES,
+            Locale::De => DE,
+            Locale::Fr => FR,
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    /// Accepts a language code with or without a region, e.g. "de" or "de-AT"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase();
+        Locale::ALL
+            .into_iter()
+            .find(|locale| locale.code() == language)
+            .ok_or_else(|| {
+                let supported: Vec<&str> = Locale::ALL.iter().map(|locale| locale.code()).collect();
+                format!("Unsupported language '{}'. Supported: {}", s, supported.join(", "))
+            })
+    }
+}
+
+/// Locale of the running app, as the index into `Locale::ALL`
+static CURRENT_LOCALE: AtomicU8 = AtomicU8::new(0);
+
+pub fn current_locale() -> Locale {
+    Locale::ALL
+        .get(CURRENT_LOCALE.load(Ordering::Relaxed) as usize)
+        .copied()
+        .unwrap_or_default()
+}
+
+pub fn set_locale(locale: Locale) {
+    let index = Locale::ALL.iter().position(|l| *l == locale).unwrap_or(0);
+    CURRENT_LOCALE.store(index as u8, Ordering::Relaxed);
+}
+
+/// Set the locale from the language setting, keeping English if it is not supported
+pub fn apply_locale_setting(language: &str) {
+    match language.parse() {
+        Ok(locale) => set_locale(locale),
+        Err(e) => {
+            warn!("{}, using English", e);
+            set_locale(Locale::En);
+        }
+    }
+}
+
+/// Text for `key` in the current locale
+pub fn t(key: &str) -> String {
+    translate(current_locale(), key, &[])
+}
+
+/// Text for `key` in the current locale with `{name}` placeholders filled from `args`
+pub fn tf(key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    translate(current_locale(), key, args)
+}
+
+/// Text for `key` in `locale`, falling back to English and then to the key itself
+pub fn translate(locale: Locale, key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let template = lookup(locale, key).or_else(|| lookup(Locale::En, key)).unwrap_or(key);
+    let mut text = template.to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    text
+}
+
+/// Translated summary of an error code (e.g. "INVALID_PASSWORD"), if the locale has one
+pub fn error_summary(locale: Locale, code: &str) -> Option<&'static str> {
+    lookup(locale, &format!("error.{}", code))
+}
+
+fn lookup(locale: Locale, key: &str) -> Option<&'static str> {
+    locale
+        .catalog()
+        .iter()
+        .find(|(catalog_key, _)| *catalog_key == key)
+        .map(|(_, template)| *template)
+}
+
+const EN: &[(&str, &str)] = &[
+    ("tray.title", "B-Rad Coin Wallet"),
+    ("tray.wallet", "Wallet: {name}"),
+    ("tray.no_wallet", "No wallet open"),
+    ("tray.network_disconnected", "Network: Disconnected"),
+    ("tray.network_connected", "Network: Connected"),
+    ("tray.network_connected_peers", "Network: Connected ({peers} peers)"),
+    ("tray.sync_progress", "Sync: {percent}% ({current}/{target})"),
+    ("tray.sync_up_to_date", "Sync: Up to date ({height})"),
+    ("tray.sync_waiting", "Sync: Waiting for peers"),
+    ("tray.mining", "Mining"),
+    ("tray.clock_skew", "System clock is off by {minutes} min"),
+    ("tray.start_mining", "Start Mining"),
+    ("tray.stop_mining", "Stop Mining"),
+    ("tray.show_window", "Show Window"),
+    ("tray.hide_window", "Hide Window"),
+    ("tray.open_wallet", "Open Wallet..."),
+    ("tray.create_wallet", "Create Wallet..."),
+    ("tray.close_wallet", "Close Wallet"),
+    ("tray.quit", "Quit"),
+    ("error.WALLET_NOT_FOUND", "Wallet not found"),
+    ("error.WALLET_ALREADY_EXISTS", "A wallet with this name already exists"),
+    ("error.WALLET_IN_USE", "The wallet is in use by another process"),
+    ("error.NO_WALLET_OPEN", "No wallet is currently open"),
+    ("error.PASSWORD_REQUIRED", "A password is required"),
+    ("error.INVALID_PASSWORD", "Invalid password"),
+    ("error.SERVICE_UNAVAILABLE", "The service is not available yet"),
+    ("error.INVALID_ADDRESS", "Invalid address"),
+    ("error.WRONG_NETWORK", "The address belongs to a different network"),
+    ("error.INVALID_AMOUNT", "Invalid amount"),
+    ("error.SPENDING_LIMIT_EXCEEDED", "The daily spending limit of this wallet would be exceeded"),
+    ("error.SPEND_CONFIRMATION_REQUIRED", "Enter the wallet password to confirm this send"),
+    ("error.COOLING_OFF_PENDING", "This large send is held back for a cooling-off period"),
+];
+
+const ES: &[(&str, &str)] = &[
+    ("tray.title", "Monedero B-Rad Coin"),
+    ("tray.wallet", "Monedero: {name}"),
+    ("tray.no_wallet", "Ningún monedero abierto"),
+    ("tray.network_disconnected", "Red: Desconectado"),
+    ("tray.network_connected", "Red: Conectado"),
+    ("tray.network_connected_peers", "Red: Conectado ({peers} pares)"),
+    ("tray.sync_progress", "Sincronización: {percent}% ({current}/{target})"),
+    ("tray.sync_up_to_date", "Sincronización: Al día ({height})"),
+    ("tray.sync_waiting", "Sincronización: Esperando pares"),
+    ("tray.mining", "Minando"),
+    ("tray.clock_skew", "El reloj del sistema está desfasado {minutes} min"),
+    ("tray.start_mining", "Iniciar minería"),
+    ("tray.stop_mining", "Detener minería"),
+    ("tray.show_window", "Mostrar ventana"),
+    ("tray.hide_window", "Ocultar ventana"),
+    ("tray.open_wallet", "Abrir monedero..."),
+    ("tray.create_wallet", "Crear monedero..."),
+    ("tray.close_wallet", "Cerrar monedero"),
+    ("tray.quit", "Salir"),
+    ("error.WALLET_NOT_FOUND", "Monedero no encontrado"),
+    ("error.WALLET_ALREADY_EXISTS", "Ya existe un monedero con ese nombre"),
+    ("error.WALLET_IN_USE", "El monedero está en uso por otro proceso"),
+    ("error.NO_WALLET_OPEN", "No hay ningún monedero abierto"),
+    ("error.PASSWORD_REQUIRED", "Se requiere una contraseña"),
+    ("error.INVALID_PASSWORD", "Contraseña incorrecta"),
+    ("error.SERVICE_UNAVAILABLE", "El servicio todavía no está disponible"),
+    ("error.INVALID_ADDRESS", "Dirección no válida"),
+    ("error.WRONG_NETWORK", "La dirección pertenece a otra red"),
+    ("error.INVALID_AMOUNT", "Importe no válido"),
+    ("error.SPENDING_LIMIT_EXCEEDED", "Se superaría el límite de gasto diario de este monedero"),
+    ("error.SPEND_CONFIRMATION_REQUIRED", "Introduzca la contraseña del monedero para confirmar este envío"),
+    ("error.COOLING_OFF_PENDING", "Este envío grande queda retenido durante un periodo de espera"),
+];
+
+const DE: &[(&str, &str)] = &[
+    ("tray.title", "B-Rad Coin Wallet"),
+    ("tray.wallet", "Wallet: {name}"),
+    ("tray.no_wallet", "Keine Wallet geöffnet"),
+    ("tray.network_disconnected", "Netzwerk: Getrennt"),
+    ("tray.network_connected", "Netzwerk: Verbunden"),
+    ("tray.network_connected_peers", "Netzwerk: Verbunden ({peers} Peers)"),
+    ("tray.sync_progress", "Synchronisierung: {percent} % ({current}/{target})"),
+    ("tray.sync_up_to_date", "Synchronisierung: Aktuell ({height})"),
+    ("tray.sync_waiting", "Synchronisierung: Warte auf Peers"),
+    ("tray.mining", "Mining aktiv"),
+    ("tray.clock_skew", "Systemuhr weicht um {minutes} Min. ab"),
+    ("tray.start_mining", "Mining starten"),
+    ("tray.stop_mining", "Mining stoppen"),
+    ("tray.show_window", "Fenster anzeigen"),
+    ("tray.hide_window", "Fenster ausblenden"),
+    ("tray.open_wallet", "Wallet öffnen..."),
+    ("tray.create_wallet", "Wallet erstellen..."),
+    ("tray.close_wallet", "Wallet schließen"),
+    ("tray.quit", "Beenden"),
+    ("error.WALLET_NOT_FOUND", "Wallet nicht gefunden"),
+    ("error.WALLET_ALREADY_EXISTS", "Eine Wallet mit diesem Namen existiert bereits"),
+    ("error.WALLET_IN_USE", "Die Wallet wird von einem anderen Prozess verwendet"),
+    ("error.NO_WALLET_OPEN", "Es ist keine Wallet geöffnet"),
+    ("error.PASSWORD_REQUIRED", "Ein Passwort ist erforderlich"),
+    ("error.INVALID_PASSWORD", "Falsches Passwort"),
+    ("error.SERVICE_UNAVAILABLE", "Der Dienst ist noch nicht verfügbar"),
+    ("error.INVALID_ADDRESS", "Ungültige Adresse"),
+    ("error.WRONG_NETWORK", "Die Adresse gehört zu einem anderen Netzwerk"),
+    ("error.INVALID_AMOUNT", "Ungültiger Betrag"),
+    ("error.SPENDING_LIMIT_EXCEEDED", "Das tägliche Ausgabelimit dieser Wallet würde überschritten"),
+    ("error.SPEND_CONFIRMATION_REQUIRED", "Geben Sie das Wallet-Passwort ein, um diese Zahlung zu bestätigen"),
+    ("error.COOLING_OFF_PENDING", "Diese große Zahlung wird für eine Wartezeit zurückgehalten"),
+];
+
+const FR: &[(&str, &str)] = &[
+    ("tray.title", "Portefeuille B-Rad Coin"),
+    ("tray.wallet", "Portefeuille : {name}"),
+    ("tray.no_wallet", "Aucun portefeuille ouvert"),
+    ("tray.network_disconnected", "Réseau : Déconnecté"),
+    ("tray.network_connected", "Réseau : Connecté"),
+    ("tray.network_connected_peers", "Réseau : Connecté ({peers} pairs)"),
+    ("tray.sync_progress", "Synchronisation : {percent} % ({current}/{target})"),
+    ("tray.sync_up_to_date", "Synchronisation : À jour ({height})"),
+    ("tray.sync_waiting", "Synchronisation : En attente de pairs"),
+    ("tray.mining", "Minage en cours"),
+    ("tray.clock_skew", "L'horloge système est décalée de {minutes} min"),
+    ("tray.start_mining", "Démarrer le minage"),
+    ("tray.stop_mining", "Arrêter le minage"),
+    ("tray.show_window", "Afficher la fenêtre"),
+    ("tray.hide_window", "Masquer la fenêtre"),
+    ("tray.open_wallet", "Ouvrir un portefeuille..."),
+    ("tray.create_wallet", "Créer un portefeuille..."),
+    ("tray.close_wallet", "Fermer le portefeuille"),
+    ("tray.quit", "Quitter"),
+    ("error.WALLET_NOT_FOUND", "Portefeuille introuvable"),
+    ("error.WALLET_ALREADY_EXISTS", "Un portefeuille portant ce nom existe déjà"),
+    ("error.WALLET_IN_USE", "Le portefeuille est utilisé par un autre processus"),
+    ("error.NO_WALLET_OPEN", "Aucun portefeuille n'est ouvert"),
+    ("error.PASSWORD_REQUIRED", "Un mot de passe est requis"),
+    ("error.INVALID_PASSWORD", "Mot de passe incorrect"),
+    ("error.SERVICE_UNAVAILABLE", "Le service n'est pas encore disponible"),
+    ("error.INVALID_ADDRESS", "Adresse invalide"),
+    ("error.WRONG_NETWORK", "L'adresse appartient à un autre réseau"),
+    ("error.INVALID_AMOUNT", "Montant invalide"),
+    ("error.SPENDING_LIMIT_EXCEEDED", "La limite de dépenses quotidienne de ce portefeuille serait dépassée"),
+    ("error.SPEND_CONFIRMATION_REQUIRED", "Saisissez le mot de passe du portefeuille pour confirmer cet envoi"),
+    ("error.COOLING_OFF_PENDING", "Cet envoi important est retenu pendant un délai de réflexion"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_fills_arguments_and_falls_back() {
+        assert_eq!(translate(Locale::En, "tray.wallet", &[("name", &"Savings")]), "Wallet: Savings");
+        assert_eq!(translate(Locale::De, "tray.network_connected_peers", &[("peers", &3)]), "Netzwerk: Verbunden (3 Peers)");
+        assert_eq!(translate(Locale::Fr, "no.such.key", &[]), "no.such.key");
+        assert_eq!(error_summary(Locale::Es, "INVALID_PASSWORD"), Some("Contraseña incorrecta"));
+        assert_eq!(error_summary(Locale::Es, "INTERNAL"), None);
+
+        assert_eq!("de-AT".parse::<Locale>(), Ok(Locale::De));
+        assert!("xx".parse::<Locale>().is_err());
+
+        // Every translated key exists in English, so placeholders and fallbacks line up
+        for locale in Locale::ALL {
+            for (key, _) in locale.catalog() {
+                assert!(lookup(Locale::En, key).is_some(), "{} has unknown key {}", locale, key);
+            }
+        }
+    }
+}
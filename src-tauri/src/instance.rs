@@ -0,0 +1,45 @@
+use crate::events::{self, EmitEvent};
+use log::{debug, info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+/// Payload of the "second-instance" event
+#[derive(Debug, Clone, Serialize)]
+pub struct SecondInstancePayload {
+    /// Command line arguments of the second launch, without the executable path
+    pub args: Vec<String>,
+    /// Working directory of the second launch
+    pub cwd: String,
+}
+
+/// Called in the running instance when the app is launched again. The new process exits;
+/// its arguments are forwarded here and the existing window is brought to the front.
+pub fn handle_second_instance(app_handle: &AppHandle, args: Vec<String>, cwd: String) {
+    let args: Vec<String> = args.into_iter().skip(1).collect();
+    info!("Second launch detected with {} argument(s), focusing running instance", args.len());
+    debug!("Second launch arguments: {:?} (cwd: {})", args, cwd);
+
+    focus_main_window(app_handle);
+
+    let payload = SecondInstancePayload { args, cwd };
+    if let Err(e) = app_handle.emit_event(events::SECOND_INSTANCE, &payload) {
+        warn!("Failed to emit second-instance event: {}", e);
+    }
+}
+
+/// Show, restore and focus the main window, including when it is hidden in the tray
+pub fn focus_main_window(app_handle: &AppHandle) {
+    let Some(window) = app_handle.get_webview_window("main") else {
+        warn!("Main window not found, cannot focus running instance");
+        return;
+    };
+    if let Err(e) = window.unminimize() {
+        debug!("Failed to unminimize main window: {}", e);
+    }
+    if let Err(e) = window.show() {
+        warn!("Failed to show main window: {}", e);
+    }
+    if let Err(e) = window.set_focus() {
+        warn!("Failed to focus main window: {}", e);
+    }
+}
@@ -0,0 +1,63 @@
+//! Per-peer record of inventory (transaction ids) the peer is known to have, either because it
+//! announced or sent the item to us or because we announced or sent it to the peer.
+//! Used to avoid announcing the same transaction to a peer twice.
+
+use std::collections::{HashSet, VecDeque};
+
+/// Inventory hashes remembered per peer; the oldest are forgotten first
+pub const MAX_KNOWN_INVENTORY: usize = 5000;
+
+/// Bounded set of inventory hashes
+#[derive(Debug, Clone, Default)]
+pub struct KnownInventory {
+    hashes: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl KnownInventory {
+    /// Remember a hash. Returns true if it was not known before.
+    pub fn insert(&mut self, hash: &str) -> bool {
+        if self.hashes.contains(hash) {
+            return false;
+        }
+        if self.order.len() == MAX_KNOWN_INVENTORY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.hashes.remove(&oldest);
+            }
+        }
+        self.hashes.insert(hash.to_string());
+        self.order.push_back(hash.to_string());
+        true
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.hashes.contains(hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_inventory_is_bounded() {
+        let mut known = KnownInventory::default();
+        assert!(known.insert("a"));
+        assert!(!known.insert("a"), "a known hash is not new");
+
+        for i in 0..MAX_KNOWN_INVENTORY {
+            known.insert(&format!("tx{}", i));
+        }
+        assert_eq!(known.len(), MAX_KNOWN_INVENTORY);
+        assert!(!known.contains("a"), "the oldest hash is forgotten first");
+        assert!(known.contains(&format!("tx{}", MAX_KNOWN_INVENTORY - 1)));
+    }
+}
@@ -0,0 +1,151 @@
+//! Decrypted signing keys of unlocked wallets.
+//!
+//! Signing used to decode a private key from the wallet data every time one was needed, and the
+//! decoded copies lived on in whatever held them. The cache belongs to the `SecurityManager`: a
+//! secured wallet's keys are only decoded while its unlock session is live, kept for a short
+//! time so a batch of signatures does not decode the same key again, and wiped when the wallet is
+//! locked or closed, its session times out or the app goes idle.
+
+use crate::wallet_data::KeyPair;
+use bitcoin::key::FromWifError;
+use bitcoin::PrivateKey;
+use log::debug;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long a decrypted key stays cached after it was last used
+pub const KEY_CACHE_TTL: Duration = Duration::from_secs(120);
+
+/// A decrypted key, erased from memory when it leaves the cache
+struct CachedKey {
+    key: PrivateKey,
+    last_used: Instant,
+}
+
+impl Drop for CachedKey {
+    fn drop(&mut self) {
+        self.key.inner.non_secure_erase();
+    }
+}
+
+/// Decrypted keys per wallet (wallet_name -> address -> key)
+pub struct KeyCache {
+    wallets: HashMap<String, HashMap<String, CachedKey>>,
+    ttl: Duration,
+}
+
+impl Default for KeyCache {
+    fn default() -> Self {
+        Self::new(KEY_CACHE_TTL)
+    }
+}
+
+impl KeyCache {
+    pub fn new(ttl: Duration) -> Self {
+        KeyCache {
+            wallets: HashMap::new(),
+            ttl,
+        }
+    }
+
+    /// The private key of `key_pair`, decrypted now unless it is still cached
+    pub fn get_or_decrypt(&mut self, wallet_name: &str, key_pair: &KeyPair) -> Result<PrivateKey, FromWifError> {
+        let ttl = self.ttl;
+        let keys = self.wallets.entry(wallet_name.to_string()).or_default();
+        if let Some(cached) = keys.get_mut(&key_pair.address) {
+            if cached.last_used.elapsed() <= ttl {
+                cached.last_used = Instant::now();
+                return Ok(cached.key);
+            }
+        }
+
+        let key = key_pair.signing_key()?;
+        keys.insert(
+            key_pair.address.clone(),
+            CachedKey {
+                key,
+                last_used: Instant::now(),
+            },
+        );
+        Ok(key)
+    }
+
+    /// Drop keys unused for longer than the TTL, returning how many were dropped
+    pub fn evict_expired(&mut self) -> usize {
+        let ttl = self.ttl;
+        let mut evicted = 0;
+        for keys in self.wallets.values_mut() {
+            let before = keys.len();
+            keys.retain(|_, cached| cached.last_used.elapsed() <= ttl);
+            evicted += before - keys.len();
+        }
+        self.wallets.retain(|_, keys| !keys.is_empty());
+        if evicted > 0 {
+            debug!("Evicted {} expired keys from the key cache", evicted);
+        }
+        evicted
+    }
+
+    /// Drop every key of one wallet, returning how many were dropped
+    pub fn flush(&mut self, wallet_name: &str) -> usize {
+        let flushed = self.wallets.remove(wallet_name).map_or(0, |keys| keys.len());
+        if flushed > 0 {
+            debug!("Flushed {} cached keys of wallet {}", flushed, wallet_name);
+        }
+        flushed
+    }
+
+    /// Drop every key of every wallet, returning how many were dropped
+    pub fn flush_all(&mut self) -> usize {
+        let flushed = self.len();
+        self.wallets.clear();
+        flushed
+    }
+
+    /// Move the keys of a wallet to its new name
+    pub fn rename(&mut self, old_name: &str, new_name: &str) {
+        if let Some(keys) = self.wallets.remove(old_name) {
+            self.wallets.insert(new_name.to_string(), keys);
+        }
+    }
+
+    /// Number of keys cached across all wallets
+    pub fn len(&self) -> usize {
+        self.wallets.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet_manager::WalletManager;
+
+    const SEED: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_keys_are_reused_until_they_expire_or_are_flushed() {
+        let data = WalletManager::wallet_data_from_seed("cache", SEED, "", false).unwrap();
+        let key_pair = data.keys.values().next().unwrap();
+
+        let mut cache = KeyCache::default();
+        let key = cache.get_or_decrypt("cache", key_pair).unwrap();
+        assert_eq!(cache.get_or_decrypt("cache", key_pair).unwrap(), key);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.evict_expired(), 0);
+
+        cache.rename("cache", "renamed");
+        assert_eq!(cache.flush("cache"), 0);
+        assert_eq!(cache.flush("renamed"), 1);
+        assert!(cache.is_empty());
+
+        let mut expiring = KeyCache::new(Duration::ZERO);
+        expiring.get_or_decrypt("cache", key_pair).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(expiring.evict_expired(), 1);
+        assert!(expiring.is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+//! Where each wallet address comes from.
+//!
+//! Every address records its key origin: the fingerprint of the master key it derives from, its
+//! derivation path and its key type. Imported keys have no origin in the seed, and addresses
+//! retired by a re-key derive from a master key the wallet no longer holds. The audit derives
+//! every other address again from the master key, to find orphans: addresses earlier versions
+//! stored under a path they do not actually derive from.
+
+use crate::chain_params::ChainParams;
+use crate::wallet_data::{KeyType, WalletData};
+use bitcoin::bip32::{DerivationPath, Xpriv};
+use bitcoin::secp256k1::{Secp256k1, Signing};
+use bitcoin::{Address, CompressedPublicKey, PrivateKey};
+use serde::Serialize;
+use std::str::FromStr;
+
+/// What the audit found for one address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OriginStatus {
+    /// Derives from the master key at its path, and its stored key matches
+    Verified,
+    /// An imported key, outside the HD tree
+    Imported,
+    /// Retired by a re-key; derives from an earlier master key
+    Retired,
+    /// Does not derive from the master key at its path
+    Orphan,
+    /// The address derives correctly but the key stored for it is a different one
+    KeyMismatch,
+    /// Recorded as deriving from another master key
+    ForeignFingerprint,
+    /// The stored derivation path does not parse
+    InvalidPath,
+    /// Only native segwit addresses are derived by this wallet
+    UnsupportedKeyType,
+    /// The wallet's master key is missing or does not parse, so nothing can be derived
+    Unverifiable,
+}
+
+/// One address of the audit
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressAudit {
+    pub address: String,
+    pub derivation_path: String,
+    pub key_type: KeyType,
+    pub master_fingerprint: Option<String>,
+    pub status: OriginStatus,
+    /// Address the path actually derives, for orphans
+    pub derived_address: Option<String>,
+}
+
+/// Result of auditing every address of a wallet
+#[derive(Debug, Clone, Serialize)]
+pub struct DerivationAudit {
+    pub wallet_name: String,
+    /// Fingerprint of the wallet's current master key
+    pub master_fingerprint: Option<String>,
+    /// Why nothing could be derived, when the master key is missing or invalid
+    pub master_key_error: Option<String>,
+    pub addresses: Vec<AddressAudit>,
+    /// Verified addresses that had no fingerprint stored and got one
+    pub origins_recorded: usize,
+}
+
+impl DerivationAudit {
+    /// Addresses whose stored origin is wrong
+    pub fn problems(&self) -> impl Iterator<Item = &AddressAudit> {
+        self.addresses.iter().filter(|entry| {
+            !matches!(entry.status, OriginStatus::Verified | OriginStatus::Imported | OriginStatus::Retired)
+        })
+    }
+}
+
+/// Native segwit address and compressed public key (hex) at `path` below `master`
+pub fn derive_address<C: Signing>(
+    secp: &Secp256k1<C>,
+    master: &Xpriv,
+    path: &DerivationPath,
+) -> Result<(String, String), String> {
+    let derived = master.derive_priv(secp, path).map_err(|e| format!("Failed to derive private key: {}", e))?;
+    let private_key = PrivateKey::new(derived.private_key, ChainParams::active().address_network);
+    let public_key = CompressedPublicKey::from_private_key(secp, &private_key)
+        .map_err(|e| format!("Failed to create compressed public key: {}", e))?;
+    Ok((Address::p2wpkh(&public_key, ChainParams::active().hrp()).to_string(), public_key.to_string()))
+}
+
+/// Check the origin of every address of `data` against its master key. Verified addresses
+/// without a stored fingerprint get the master key's, so every address ends up with its origin.
+pub fn audit(data: &mut WalletData) -> DerivationAudit {
+    let secp = Secp256k1::new();
+    let master = match data.master_private_key.as_ref().filter(|key| !key.is_empty()) {
+        Some(key) => Xpriv::from_str(key.expose_secret())
+            .map_err(|e| format!("Master key is not a valid extended private key: {}", e)),
+        None => Err("Wallet has no master private key".to_string()),
+    };
+    let master_fingerprint = match &master {
+        Ok(master) => Some(master.fingerprint(&secp).to_string()),
+        Err(_) => data.master_fingerprint.clone(),
+    };
+
+    let mut addresses = Vec::with_capacity(data.addresses.len());
+    let mut origins_recorded = 0;
+    for info in &mut data.addresses {
+        let mut entry = AddressAudit {
+            address: info.address.clone(),
+            derivation_path: info.derivation_path.clone(),
+            key_type: info.key_type.clone(),
+            master_fingerprint: info.master_fingerprint.clone(),
+            status: OriginStatus::Verified,
+            derived_address: None,
+        };
+        entry.status = if info.retired {
+            OriginStatus::Retired
+        } else if info.is_imported() {
+            OriginStatus::Imported
+        } else if info.key_type != KeyType::NativeSegWit {
+            OriginStatus::UnsupportedKeyType
+        } else if info.master_fingerprint.is_some() && info.master_fingerprint != master_fingerprint {
+            OriginStatus::ForeignFingerprint
+        } else {
+            match (&master, DerivationPath::from_str(&info.derivation_path)) {
+                (Err(_), _) => OriginStatus::Unverifiable,
+                (_, Err(_)) => OriginStatus::InvalidPath,
+                (Ok(master), Ok(path)) => match derive_address(&secp, master, &path) {
+                    Err(_) => OriginStatus::InvalidPath,
+                    Ok((address, _)) if address != info.address => {
+                        entry.derived_address = Some(address);
+                        OriginStatus::Orphan
+                    }
+                    Ok((_, public_key)) => match data.keys.get(&info.address) {
+                        Some(key_pair) if key_pair.public_key != public_key => OriginStatus::KeyMismatch,
+                        _ => OriginStatus::Verified,
+                    },
+                },
+            }
+        };
+
+        if entry.status == OriginStatus::Verified && info.master_fingerprint.is_none() {
+            info.master_fingerprint = master_fingerprint.clone();
+            entry.master_fingerprint = master_fingerprint.clone();
+            origins_recorded += 1;
+        }
+        addresses.push(entry);
+    }
+
+    DerivationAudit {
+        wallet_name: data.name.clone(),
+        master_fingerprint,
+        master_key_error: master.err(),
+        addresses,
+        origins_recorded,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet_manager::WalletManager;
+
+    const SEED: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_audit_flags_addresses_that_do_not_derive_from_the_master_key() {
+        let mut data = WalletManager::wallet_data_from_seed("audit", SEED, "", false).unwrap();
+        data.addresses[0].master_fingerprint = None;
+        let mut orphan = data.addresses[0].clone();
+        orphan.address = "bc1qorphan".to_string();
+        orphan.derivation_path = "m/44'/0'/0'/0/1".to_string();
+        data.addresses.push(orphan);
+
+        let audit = audit(&mut data);
+        assert_eq!(audit.addresses[0].status, OriginStatus::Verified);
+        assert_eq!(audit.origins_recorded, 1);
+        assert_eq!(data.addresses[0].master_fingerprint, audit.master_fingerprint);
+        assert_eq!(data.addresses[0].master_fingerprint, data.master_fingerprint);
+
+        let problems: Vec<&AddressAudit> = audit.problems().collect();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].status, OriginStatus::Orphan);
+        assert!(problems[0].derived_address.as_deref().is_some_and(|address| address.starts_with("bc1q")));
+    }
+}
@@ -4,35 +4,97 @@ mod bip39_words;
 use log::{debug, error, info, warn, LevelFilter};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tauri::{generate_context, generate_handler, Manager, Emitter};
-use tauri::tray::{TrayIconBuilder, TrayIconEvent};
-//use tauri_plugin_updater::UpdaterExt;
+use std::time::Instant;
+use tauri::{generate_context, generate_handler, Manager};
 
 // Add static flag to track shutdown state
 static SHUTDOWN_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
 
 // Import modules
 pub mod commands;
+pub mod atomic_file;
+pub mod audit_log;
+pub mod chain_params;
+pub mod coin_selection;
 pub mod config;
+pub mod config_migrations;
+pub mod consensus;
 pub mod developer_commands;
 pub mod errors;
+pub mod events;
+pub mod event_bus;
+pub mod services;
+pub mod i18n;
+pub mod instance;
+pub mod payment_requests;
+pub mod payment_uri;
+pub mod validation;
+pub mod file_lock;
 pub mod logging;
+pub mod password_strength;
+pub mod power_monitor;
+pub mod memory_monitor;
+pub mod secret;
 pub mod security;
 pub mod wallet_data;
+pub mod wallet_file;
+pub mod wallet_keys;
 pub mod wallet_manager;
+pub mod wallet_persister;
+pub mod wallet_settings;
+pub mod wallet_activity;
+pub mod wallet_labels;
+pub mod key_origin;
+pub mod key_cache;
+pub mod ownership_proof;
+pub mod spending_policy;
+pub mod transaction_drafts;
+pub mod storage_media;
+pub mod paths;
+pub mod settings_profile;
+pub mod tray;
 // pub mod core;  // Temporarily commented out due to missing dependencies
 pub mod blockchain_sync;
+pub mod block_cache;
+pub mod block_store;
 pub mod blockchain_database;
 pub mod wallet_sync_service;
 pub mod mining_service;
+pub mod mining_worker;
 pub mod network_service;
 pub mod network_monitor;
 pub mod network_constants;
+pub mod network_codec;
+pub mod inventory;
+pub mod network_trace;
+pub mod network_time;
+pub mod node_status;
+pub mod address_relay;
+pub mod peer_policy;
+pub mod peer_queue;
+pub mod reachability;
+pub mod relay_policy;
+pub mod startup_timings;
+pub mod sync_progress;
+pub mod simulated_network;
+#[cfg(test)]
+pub(crate) mod test_support;
 pub mod dns_seeder;
 pub mod mempool_service;
+pub mod signature_cache;
 pub mod fee_estimator;
+pub mod price_service;
+pub mod push_api;
+pub mod metrics;
+pub mod stratum;
+pub mod command_metrics;
+pub mod compact_filters;
+pub mod scheduled_payments;
+pub mod cost_basis;
+pub mod updater;
 
 use commands::*;
+use events::EmitEvent;
 use developer_commands::*;
 use config::ConfigManager;
 use errors::AppResult;
@@ -46,27 +108,46 @@ use network_service::AsyncNetworkService;
 use mempool_service::AsyncMempoolService;
 use fee_estimator::AsyncFeeEstimator;
 use network_monitor::AsyncNetworkMonitor;
+use price_service::AsyncPriceService;
+use updater::AsyncUpdateService;
+use scheduled_payments::AsyncPaymentScheduler;
+use paths::PathProvider;
 
 /// Application version
 const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// Authentication timeout in seconds
-const AUTH_TIMEOUT_SECONDS: u64 = 1800; // 30 minutes
-
 /// Application entry point
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Startup phases are timed from here
+    let startup_timings = startup_timings::StartupTimings::new();
+
     // Setup logging first
+    // Resolve the data directory before anything touches the filesystem
+    PathProvider::init_from_args(std::env::args());
+
     setup_logging().expect("Failed to set up logging");    // Log application startup
     logging::log_app_startup(APP_VERSION);
+    if let Some(data_dir) = PathProvider::data_dir() {
+        info!("Data directory: {} (override: {})", data_dir.display(), PathProvider::is_overridden());
+    }
 
     // Build and run Tauri application
     let app = tauri::Builder::default()
+        // Must be registered first so a second launch exits before anything else starts
+        .plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
+            instance::handle_second_instance(app, args, cwd);
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .manage(startup_timings)
         .invoke_handler(generate_handler![
             check_wallet_status,
             close_wallet,
+            get_open_wallets,
+            set_active_wallet,
             get_available_wallets,
             get_wallet_details,
             is_current_wallet_secured,
@@ -78,24 +159,54 @@ pub fn run() {
             open_folder_in_explorer,
             open_folder_with_shell_command,
             delete_wallet,
+            rename_wallet,
             recover_wallet,
+            check_wallet_passphrase,
+            get_seed_verification_challenge,
+            verify_seed_challenge,
+            is_seed_backup_verified,
             get_current_wallet_name,
             update_app_settings,
             get_app_settings,
+            export_settings,
+            import_settings,
+            get_data_directory,
+            set_data_directory,
+            get_wallet_settings,
+            update_wallet_settings,
             secure_wallet,
+            set_duress_password,
+            remove_duress_password,
+            lock_wallet,
+            set_auto_lock_timeout,
+            set_auth_timeout,
+            get_wallet_session,
             shutdown_application,
             show_main_window,
             hide_to_tray,
             update_tray_wallet_status,
             update_tray_network_status,
             get_app_version,
+            get_command_metrics,
             greet,
+            // Payment link commands
+            parse_payment_uri,
+            take_pending_payment_uri,
+            validate_payment_details,
+            // Update commands
+            check_for_updates,
+            get_pending_update,
+            install_pending_update,
             // Blockchain commands
             get_network_status,
             get_block_height,
             is_blockchain_syncing,
             is_network_connected,
             get_peer_count,
+            get_peer_details,
+            get_external_address,
+            get_clock_status,
+            get_supply_info,
             force_sync,
             is_blockchain_ready,
             // Blockchain setup commands
@@ -103,6 +214,10 @@ pub fn run() {
             get_blockchain_database_path,
             get_default_blockchain_database_path,
             get_blockchain_database_size,
+            get_address_history,
+            get_address_index_status,
+            get_database_stats,
+            get_service_health,
             open_folder_picker,
             create_blockchain_database_at_location,
             set_blockchain_database_location,
@@ -116,29 +231,87 @@ pub fn run() {
             // Mining commands
             start_mining,
             stop_mining,
+            set_mining_intensity,
+            get_power_status,
             get_mining_status,
             get_all_mining_statuses,
+            get_mining_rewards,
+            get_difficulty_history,
+            get_network_hashrate_estimate,
+            get_block_template,
+            submit_block,
+            get_stratum_workers,
             // Developer commands
             get_recent_logs,
             echo_command,
             get_config_directory,
+            get_raw_block,
+            get_raw_transaction,
+            get_network_trace,
+            clear_network_trace,
+            subscribe_events,
+            spawn_simulated_peer,
+            audit_derivation_paths,
+            flush_key_cache,
+            migrate_block_store,
+            get_chain_tips,
+            get_startup_timings,
             cleanup_orphaned_wallets,
             delete_all_wallets,
             get_wallet_private_key,
+            copy_sensitive_to_clipboard,
             get_current_wallet_info,
+            get_wallet_addresses,
             get_cpu_cores,
             // Wallet address commands
             derive_new_address,
+            import_private_key,
+            sweep_private_key,
+            select_coins_for_payment,
+            preview_transaction,
+            commit_transaction,
+            list_utxos,
+            freeze_utxo,
+            unfreeze_utxo,
+            consolidate_utxos,
+            rekey_wallet,
+            sweep_retired_addresses,
+            list_scheduled_payments,
+            create_scheduled_payment,
+            delete_scheduled_payment,
+            set_scheduled_payment_enabled,
+            confirm_scheduled_payment,
+            skip_scheduled_payment,
+            create_payment_request,
+            list_payment_requests,
+            delete_payment_request,
+            get_wallet_activity,
+            export_wallet_labels,
+            import_wallet_labels,
+            create_ownership_proof,
+            verify_ownership_proof,
+            list_contacts,
+            save_contact,
+            remove_contact,
+            get_audit_log,
+            regenerate_push_api_token,
+            evaluate_password_strength,
             update_address_label,
             get_all_wallet_addresses,
             get_mining_configuration,
             // Transaction and mempool commands
             submit_transaction,
             get_mempool_status,
+            get_mempool_min_fee,
             get_pending_transactions,
             // Fee estimation commands
             get_fee_estimates,
             calculate_transaction_fee,
+            // Price feed commands
+            get_exchange_rate,
+            refresh_exchange_rates,
+            convert_to_fiat,
+            generate_cost_basis_report,
             // Network monitoring commands
             get_network_diagnostics,
             get_network_diagnostic_history,
@@ -149,10 +322,23 @@ pub fn run() {
         ])        .setup(|app| {
             info!("Setting up application");
             
+            // The window is up by now; everything after it loads in the background
+            let process_start = app.state::<startup_timings::StartupTimings>().process_start();
+            startup_timings::record(app.handle(), "window", process_start);
+            
+            // brad:// payment links, at launch or forwarded from a second launch
+            app.manage(payment_uri::PendingPaymentUri::default());
+            app.manage(transaction_drafts::TransactionDrafts::default());
+            app.manage(event_bus::EventBus::default());
+            app.manage(services::ServiceContainer::default());
+            app.manage(tray::TrayController::default());
+            setup_deep_links(app);
+            
             // Initialize basic app components first to access configuration
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 info!("Initializing basic application components");
+                let config_started = Instant::now();
                 match initialize_basic_app().await {
                     Ok(basic_state) => {
                         info!("Basic application components initialized successfully");
@@ -161,121 +347,90 @@ pub fn run() {
                         let should_enable_tray = basic_state.config_manager.get_config().app_settings.minimize_to_system_tray;
                         info!("System tray setting: {}", should_enable_tray);
                         
-                        // Add basic components to Tauri state
-                        app_handle.manage(basic_state.wallet_manager);
-                        app_handle.manage(basic_state.security_manager);
-                        app_handle.manage(basic_state.config_manager);
+                        // Expire idle wallet sessions in the background
+                        basic_state.security_manager.start_session_monitor(app_handle.clone());
                         
-                        // Create system tray if enabled in settings
-                        if should_enable_tray {
-                            info!("Setting up system tray (enabled in settings)");
-                            if let Err(e) = setup_system_tray_after_init(&app_handle) {
-                                error!("Failed to setup system tray: {}", e);
+                        // Let the user know if the config had to be restored from its backup
+                        if basic_state.config_manager.was_recovered_from_backup() {
+                            warn!("Configuration was corrupt and has been restored from backup");
+                            if let Err(e) = app_handle.emit_event(events::CONFIG_RECOVERED, &()) {
+                                error!("Failed to emit config-recovered event: {}", e);
                             }
-                        } else {
-                            info!("System tray disabled in settings, skipping initialization");
                         }
                         
-                        // Check if blockchain database exists
-                        info!("Checking for blockchain database");
-                        let config_manager = app_handle.state::<Arc<ConfigManager>>();
-                        let blockchain_exists = check_blockchain_exists(&config_manager).await;
-                        
-                        // Check if developer mode is enabled
-                        let config = config_manager.get_config();
-                        let is_developer_mode = config.app_settings.developer_mode;
-                        info!("Developer mode enabled: {}", is_developer_mode);
+                        // Write wallet changes made by labels, frozen coins and sync in the background
+                        basic_state.wallet_manager.start_persister();
                         
-                        // Start blockchain services if database exists
-                        if blockchain_exists {
-                            info!("Blockchain database found, starting all services");
-                            // Start blockchain services since database exists
-                            match commands::start_blockchain_services(app_handle.clone()).await {
-                                Ok(_) => {
-                                    info!("All blockchain services started successfully");
-                                    // Notify frontend that blockchain services are ready
-                                    if let Some(window) = app_handle.get_webview_window("main") {
-                                        let _ = window.emit("blockchain-services-ready", ());
-                                    }
-                                }
-                                Err(e) => {
-                                    error!("Failed to start blockchain services: {}", e);
-                                    // Notify frontend about the error
-                                    if let Some(window) = app_handle.get_webview_window("main") {
-                                        let _ = window.emit("blockchain-setup-error", e);
-                                    }
-                                }
-                            }
-                        } else {
-                            info!("Blockchain database not found, auto-creating in default location for production");
-                            
-                            // Get the default blockchain database path
-                            let default_db_path = match commands::get_default_blockchain_database_path().await {
-                                Ok(path) => path,
-                                Err(e) => {
-                                    error!("Failed to get default blockchain database path: {}", e);
-                                    // Fall back to setup dialog
-                                    if let Some(window) = app_handle.get_webview_window("main") {
-                                        info!("Main window found, emitting blockchain-setup-required event to frontend");
-                                        match window.emit("blockchain-setup-required", ()) {
-                                            Ok(_) => info!("Successfully emitted blockchain-setup-required event"),
-                                            Err(e) => error!("Failed to emit blockchain-setup-required event: {}", e),
-                                        }
-                                    } else {
-                                        warn!("Main window not found, cannot emit blockchain-setup-required event");
-                                    }
-                                    return;
-                                }
-                            };
-                            
-                            // Auto-create blockchain database in the default location
-                            let config_manager = app_handle.state::<Arc<ConfigManager>>();
-                            match commands::create_blockchain_database_at_location(default_db_path, config_manager, app_handle.clone()).await {
-                                Ok(_) => {
-                                    info!("Blockchain database auto-created successfully");
-                                    // Now start the blockchain services
-                                    match commands::start_blockchain_services(app_handle.clone()).await {
-                                        Ok(_) => {
-                                            info!("All blockchain services started successfully after auto-setup");
-                                            // Notify frontend that blockchain services are ready
-                                            if let Some(window) = app_handle.get_webview_window("main") {
-                                                let _ = window.emit("blockchain-services-ready", ());
-                                            }
-                                        }
-                                        Err(e) => {
-                                            error!("Failed to start blockchain services after auto-setup: {}", e);
-                                            // Notify frontend about the error
-                                            if let Some(window) = app_handle.get_webview_window("main") {
-                                                let _ = window.emit("blockchain-setup-error", e);
-                                            }
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    error!("Failed to auto-create blockchain database: {}", e);
-                                    // Fall back to showing setup dialog for manual configuration
-                                    info!("Falling back to manual blockchain setup dialog");
-                                    if let Some(window) = app_handle.get_webview_window("main") {
-                                        info!("Main window found, emitting blockchain-setup-required event to frontend");
-                                        match window.emit("blockchain-setup-required", ()) {
-                                            Ok(_) => info!("Successfully emitted blockchain-setup-required event"),
-                                            Err(e) => error!("Failed to emit blockchain-setup-required event: {}", e),
-                                        }
-                                    } else {
-                                        warn!("Main window not found, cannot emit blockchain-setup-required event");
-                                        // Try to list all windows for debugging
-                                        let windows = app_handle.webview_windows();
-                                        info!("Available windows: {:?}", windows.keys().collect::<Vec<_>>());
-                                    }
-                                }
-                            }
+                        // Add basic components to Tauri state; settings and wallet commands work from here
+                        let config_manager = basic_state.config_manager;
+                        app_handle.manage(basic_state.wallet_manager);
+                        app_handle.manage(basic_state.security_manager);
+                        app_handle.manage(config_manager.clone());
+                        startup_timings::record(&app_handle, "config_and_wallets", config_started);
+                        if let Err(e) = app_handle.emit_event(events::APP_READY, &()) {
+                            error!("Failed to emit app-ready event: {}", e);
                         }
+                        
+                        let services_started = Instant::now();
+                        
+                        // Price feed only needs the settings, so it runs independently of the blockchain services
+                        let price_service = AsyncPriceService::new();
+                        price_service.start(app_handle.clone(), config_manager.clone());
+                        
+                        // Scheduled update checks; installing always waits for the user
+                        let update_service = AsyncUpdateService::new();
+                        update_service.start(app_handle.clone(), config_manager.clone());
+                        
+                        // Optional WebSocket stream of node events for external apps, off by default
+                        let push_api = push_api::PushApiServer::default();
+                        push_api.apply_settings(&config_manager.get_config().app_settings);
+                        
+                        // Optional Prometheus-style metrics endpoint, off by default
+                        let metrics_server = metrics::MetricsServer::default();
+                        metrics_server.apply_settings(&app_handle, &config_manager.get_config().app_settings);
+                        
+                        // Optional stratum server for miners on the LAN, off by default
+                        let stratum_server = stratum::StratumServer::default();
+                        stratum_server.apply_settings(&app_handle, &config_manager.get_config().app_settings);
+                        
+                        // Recurring payments wait until their wallet is unlocked and the node is synced
+                        let payment_scheduler = AsyncPaymentScheduler::new();
+                        payment_scheduler.start(app_handle.clone());
+                        
+                        // Pause mining and quiesce the node around sleep, and on battery if configured
+                        let power_monitor = power_monitor::PowerMonitor::default();
+                        power_monitor.start(app_handle.clone());
+                        
+                        // Shrink the blockchain database caches while memory is short
+                        memory_monitor::start(app_handle.clone());
+                        
+                        app_handle.manage(price_service);
+                        app_handle.manage(update_service);
+                        app_handle.manage(payment_scheduler);
+                        app_handle.manage(push_api);
+                        app_handle.manage(metrics_server);
+                        app_handle.manage(stratum_server);
+                        app_handle.manage(power_monitor);
+                        
+                        // Create system tray if enabled in settings; it follows the setting from here on
+                        tray::apply_setting(&app_handle, should_enable_tray);
+                        startup_timings::record(&app_handle, "background_services", services_started);
+                        
+                        // The database, network and sync start last; the UI is usable without them
+                        start_blockchain_at_launch(&app_handle).await;
+                        app_handle.state::<startup_timings::StartupTimings>().complete();
                     }
                     Err(e) => {
                         error!("Failed to initialize basic application components: {}", e);
                         // Notify frontend about the error
                         if let Some(window) = app_handle.get_webview_window("main") {
-                            let _ = window.emit("app-initialization-error", e.to_string());
+                            if let errors::AppError::Config(errors::ConfigError::UnsupportedVersion { found, supported }) = &e {
+                                let _ = window.emit_event(events::CONFIG_VERSION_UNSUPPORTED, &events::ConfigVersionUnsupported {
+                                    found: *found,
+                                    supported: *supported,
+                                });
+                            }
+                            let _ = window.emit_event(events::APP_INITIALIZATION_ERROR, &errors::CommandError::from(e));
                         }
                     }
                 }
@@ -284,26 +439,16 @@ pub fn run() {
             Ok(())
         }).on_window_event(|window, event| {
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
-                // Check if system tray is enabled before deciding what to do
-                let app_handle = window.app_handle();
-                if let Some(config_manager) = app_handle.try_state::<Arc<ConfigManager>>() {
-                    let config = config_manager.get_config();
-                    if config.app_settings.minimize_to_system_tray {
-                        debug!("Window close requested - minimizing to tray (tray enabled)");
-                        // Hide the window instead of closing the app
-                        let _ = window.hide();
-                        // Prevent the default close behavior
-                        api.prevent_close();
-                    } else {
-                        debug!("Window close requested - closing application (tray disabled)");
-                        // Allow the window to close normally, which will exit the app
-                        // No need to call api.prevent_close()
-                    }
-                } else {
-                    // Fallback: if we can't access config, use default behavior (minimize to tray)
-                    debug!("Window close requested - minimizing to tray (config not available)");
+                // Hide to the tray only while it exists, so the window can always be brought back
+                if tray::close_hides_window(window.app_handle()) {
+                    debug!("Window close requested - minimizing to tray (tray enabled)");
+                    // Hide the window instead of closing the app
                     let _ = window.hide();
+                    // Prevent the default close behavior
                     api.prevent_close();
+                } else {
+                    debug!("Window close requested - closing application (tray disabled)");
+                    // Allow the window to close normally, which will exit the app
                 }
             }
         })
@@ -325,17 +470,14 @@ pub fn run() {
             let cleanup_task = tauri::async_runtime::spawn(async move {
                 info!("Starting shutdown cleanup process");
                 
-                // Stop all services first
-                if let Err(e) = commands::stop_blockchain_services(app_handle_clone.clone()).await {
-                    error!("Error stopping blockchain services during shutdown: {}", e);
+                // Wallet changes the persister has not written yet
+                if let Some(wallet_manager) = app_handle_clone.try_state::<AsyncWalletManager>() {
+                    wallet_manager.save_dirty_wallets().await;
                 }
                 
-                // Close database if it exists
-                if let Some(blockchain_db) = app_handle_clone.try_state::<Arc<AsyncBlockchainDatabase>>() {
-                    info!("Closing blockchain database during shutdown");
-                    if let Err(e) = flush_and_release_database(blockchain_db.inner().clone()).await {
-                        error!("Error closing blockchain database during shutdown: {}", e);
-                    }
+                // Stopping the services also closes the blockchain database
+                if let Err(e) = commands::stop_blockchain_services(app_handle_clone.clone()).await {
+                    error!("Error stopping blockchain services during shutdown: {}", e);
                 }
                 
                 info!("Shutdown cleanup completed");
@@ -391,10 +533,38 @@ struct BasicAppState {
 }
 
 /// Set up application logging
+/// Register the brad:// scheme and route payment links to the send screen
+fn setup_deep_links(app: &tauri::App) {
+    use tauri_plugin_deep_link::DeepLinkExt;
+
+    // Installed builds register the scheme from the bundle; this covers dev and portable runs
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        warn!("Failed to register deep link schemes: {}", e);
+    }
+
+    let app_handle = app.handle().clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            payment_uri::handle_payment_uri(&app_handle, url.as_str());
+        }
+    });
+
+    match app.deep_link().get_current() {
+        Ok(Some(urls)) => {
+            for url in urls {
+                payment_uri::handle_payment_uri(app.handle(), url.as_str());
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Failed to read launch deep link: {}", e),
+    }
+}
+
 fn setup_logging() -> Result<(), String> {
     // Use platform-specific directories in a way compatible with Tauri 2.0
-    let log_dir = match dirs::data_dir() {
-        Some(dir) => dir.join("com.b-rad-coin.app").join("logs"),
+    let log_dir = match PathProvider::logs_dir() {
+        Some(dir) => dir,
         None => return Err("Failed to determine log directory".to_string()),
     };
 
@@ -409,10 +579,11 @@ async fn initialize_app() -> AppResult<AppState> {
     // Initialize configuration manager
     debug!("Initializing configuration manager");
     let config_manager = Arc::new(ConfigManager::new().await?);
+    i18n::apply_locale_setting(&config_manager.get_config().app_settings.language);
 
     // Initialize security manager
     debug!("Initializing security manager");
-    let security_manager = SecurityManager::new(AUTH_TIMEOUT_SECONDS);
+    let security_manager = SecurityManager::new(config_manager.get_config().app_settings.auth_timeout_minutes * 60);
     let async_security_manager = AsyncSecurityManager::new(security_manager);
 
     // Initialize wallet manager with config
@@ -424,8 +595,8 @@ async fn initialize_app() -> AppResult<AppState> {
         .set_config_manager(config_manager.clone())
         .await;    // Initialize blockchain database first
     debug!("Initializing blockchain database");
-    let blockchain_data_dir = match dirs::data_dir() {
-        Some(dir) => dir.join("com.b-rad-coin.app").join("blockchain"),
+    let blockchain_data_dir = match PathProvider::blockchain_dir() {
+        Some(dir) => dir,
         None => return Err(errors::AppError::Generic("Failed to determine blockchain data directory".to_string())),
     };
     
@@ -444,10 +615,19 @@ async fn initialize_app() -> AppResult<AppState> {
       // Initialize mining service
     debug!("Initializing mining service");
     let mining_service = AsyncMiningService::new(blockchain_db.clone());
+    mining_service
+        .set_intensity(mining_worker::MiningIntensity::from_settings(&config_manager.get_config().app_settings))
+        .await;
     
     // Initialize mempool service
     debug!("Initializing mempool service");
     let mempool_service = AsyncMempoolService::new(blockchain_db.clone());
+    mempool_service
+        .set_max_size_mb(config_manager.get_config().app_settings.max_mempool_size_mb)
+        .await;
+    mempool_service
+        .set_policy(relay_policy::RelayPolicy::from_settings(&config_manager.get_config().app_settings))
+        .await;
     
     // Initialize network service
     debug!("Initializing network service");
@@ -481,6 +661,113 @@ async fn initialize_app() -> AppResult<AppState> {
     })
 }
 
+/// Start the blockchain services at launch, creating the database in the default location if
+/// there is none yet
+async fn start_blockchain_at_launch(app_handle: &tauri::AppHandle) {
+    // Check if blockchain database exists
+    info!("Checking for blockchain database");
+    let config_manager = app_handle.state::<Arc<ConfigManager>>();
+    let blockchain_exists = check_blockchain_exists(&config_manager).await;
+
+    // Check if developer mode is enabled
+    let config = config_manager.get_config();
+    let is_developer_mode = config.app_settings.developer_mode;
+    info!("Developer mode enabled: {}", is_developer_mode);
+
+    // Start blockchain services if database exists
+    if blockchain_exists {
+        info!("Blockchain database found, starting all services");
+        // Start blockchain services since database exists
+        match commands::start_blockchain_services(app_handle.clone()).await {
+            Ok(_) => {
+                info!("All blockchain services started successfully");
+                // Notify frontend that blockchain services are ready
+                if let Some(window) = app_handle.get_webview_window("main") {
+                    let _ = window.emit_event(events::BLOCKCHAIN_SERVICES_READY, &());
+                }
+            }
+            Err(e) => {
+                error!("Failed to start blockchain services: {}", e);
+                // Notify frontend about the error
+                if let Some(window) = app_handle.get_webview_window("main") {
+                    let _ = window.emit_event(events::BLOCKCHAIN_SETUP_ERROR, &e);
+                }
+            }
+        }
+    } else {
+        info!("Blockchain database not found, auto-creating in default location for production");
+
+        // Get the default blockchain database path
+        let default_db_path = match commands::get_default_blockchain_database_path().await {
+            Ok(path) => path,
+            Err(e) => {
+                error!("Failed to get default blockchain database path: {}", e);
+                // Fall back to setup dialog
+                if let Some(window) = app_handle.get_webview_window("main") {
+                    info!("Main window found, emitting blockchain-setup-required event to frontend");
+                    let payload = events::BlockchainSetupRequired {
+                        reason: "no_default_location",
+                        message: e.to_string(),
+                    };
+                    match window.emit_event(events::BLOCKCHAIN_SETUP_REQUIRED, &payload) {
+                        Ok(_) => info!("Successfully emitted blockchain-setup-required event"),
+                        Err(e) => error!("Failed to emit blockchain-setup-required event: {}", e),
+                    }
+                } else {
+                    warn!("Main window not found, cannot emit blockchain-setup-required event");
+                }
+                return;
+            }
+        };
+
+        // Auto-create blockchain database in the default location
+        let config_manager = app_handle.state::<Arc<ConfigManager>>();
+        match commands::create_blockchain_database_at_location(default_db_path, config_manager, app_handle.clone()).await {
+            Ok(_) => {
+                info!("Blockchain database auto-created successfully");
+                // Now start the blockchain services
+                match commands::start_blockchain_services(app_handle.clone()).await {
+                    Ok(_) => {
+                        info!("All blockchain services started successfully after auto-setup");
+                        // Notify frontend that blockchain services are ready
+                        if let Some(window) = app_handle.get_webview_window("main") {
+                            let _ = window.emit_event(events::BLOCKCHAIN_SERVICES_READY, &());
+                        }
+                    }
+                    Err(e) => {
+                        error!("Failed to start blockchain services after auto-setup: {}", e);
+                        // Notify frontend about the error
+                        if let Some(window) = app_handle.get_webview_window("main") {
+                            let _ = window.emit_event(events::BLOCKCHAIN_SETUP_ERROR, &e);
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to auto-create blockchain database: {}", e);
+                // Fall back to showing setup dialog for manual configuration
+                info!("Falling back to manual blockchain setup dialog");
+                if let Some(window) = app_handle.get_webview_window("main") {
+                    info!("Main window found, emitting blockchain-setup-required event to frontend");
+                    let payload = events::BlockchainSetupRequired {
+                        reason: "auto_create_failed",
+                        message: e.to_string(),
+                    };
+                    match window.emit_event(events::BLOCKCHAIN_SETUP_REQUIRED, &payload) {
+                        Ok(_) => info!("Successfully emitted blockchain-setup-required event"),
+                        Err(e) => error!("Failed to emit blockchain-setup-required event: {}", e),
+                    }
+                } else {
+                    warn!("Main window not found, cannot emit blockchain-setup-required event");
+                    // Try to list all windows for debugging
+                    let windows = app_handle.webview_windows();
+                    info!("Available windows: {:?}", windows.keys().collect::<Vec<_>>());
+                }
+            }
+        }
+    }
+}
+
 /// Initialize basic application components (config, wallet, security - no blockchain services)
 async fn initialize_basic_app() -> AppResult<BasicAppState> {
     debug!("Initializing basic application components");
@@ -488,10 +775,11 @@ async fn initialize_basic_app() -> AppResult<BasicAppState> {
     // Initialize configuration manager
     debug!("Initializing configuration manager");
     let config_manager = Arc::new(ConfigManager::new().await?);
+    i18n::apply_locale_setting(&config_manager.get_config().app_settings.language);
 
     // Initialize security manager
     debug!("Initializing security manager");
-    let security_manager = SecurityManager::new(AUTH_TIMEOUT_SECONDS);
+    let security_manager = SecurityManager::new(config_manager.get_config().app_settings.auth_timeout_minutes * 60);
     let async_security_manager = AsyncSecurityManager::new(security_manager);
 
     // Initialize wallet manager with config
@@ -528,8 +816,8 @@ async fn check_blockchain_exists(config_manager: &Arc<ConfigManager>) -> bool {
     }
     
     // Check default location
-    let blockchain_data_dir = match dirs::data_dir() {
-        Some(dir) => dir.join("com.b-rad-coin.app").join("blockchain"),
+    let blockchain_data_dir = match PathProvider::blockchain_dir() {
+        Some(dir) => dir,
         None => {
             error!("Failed to determine blockchain data directory");
             return false;
@@ -542,279 +830,6 @@ async fn check_blockchain_exists(config_manager: &Arc<ConfigManager>) -> bool {
     exists
 }
 
-/// Setup system tray with menu and event handlers
-fn setup_system_tray(app: &tauri::App) -> tauri::Result<()> {
-    use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
-    
-    info!("Setting up system tray");
-    
-    // Create tray menu items
-    let wallet_status_item = MenuItem::with_id(app, "wallet_status", "No wallet open", false, None::<&str>)?;
-    let network_status_item = MenuItem::with_id(app, "network_status", "Network: Disconnected", false, None::<&str>)?;
-    let separator1 = PredefinedMenuItem::separator(app)?;
-    
-    let show_item = MenuItem::with_id(app, "show", "Show Window", true, None::<&str>)?;
-    let hide_item = MenuItem::with_id(app, "hide", "Hide Window", true, None::<&str>)?;
-    let separator2 = PredefinedMenuItem::separator(app)?;
-    
-    let open_wallet_item = MenuItem::with_id(app, "open_wallet", "Open Wallet...", true, None::<&str>)?;
-    let create_wallet_item = MenuItem::with_id(app, "create_wallet", "Create Wallet...", true, None::<&str>)?;
-    let close_wallet_item = MenuItem::with_id(app, "close_wallet", "Close Wallet", false, None::<&str>)?;
-    let separator3 = PredefinedMenuItem::separator(app)?;
-    
-    let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-    
-    let menu = Menu::with_items(app, &[
-        &wallet_status_item,
-        &network_status_item,
-        &separator1,
-        &show_item,
-        &hide_item,
-        &separator2,
-        &open_wallet_item,
-        &create_wallet_item,
-        &close_wallet_item,
-        &separator3,
-        &quit_item,
-    ])?;
-    
-    // Create tray icon
-    let _tray = TrayIconBuilder::with_id("main-tray")
-        .tooltip("B-Rad Coin Wallet")
-        .icon(app.default_window_icon().unwrap().clone())
-        .menu(&menu)
-        .on_tray_icon_event(|tray, event| {
-            match event {
-                TrayIconEvent::Click { button: tauri::tray::MouseButton::Left, button_state: tauri::tray::MouseButtonState::Up, .. } => {
-                    debug!("Tray icon left clicked - showing window");
-                    let app = tray.app_handle();
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                    }
-                }
-                TrayIconEvent::DoubleClick { button: tauri::tray::MouseButton::Left, .. } => {
-                    debug!("Tray icon double clicked - showing window");
-                    let app = tray.app_handle();
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                    }
-                }
-                _ => {}
-            }
-        })        .on_menu_event(|app, event| {
-            match event.id.as_ref() {
-                "quit" => {
-                    info!("Quit selected from tray menu");
-                    // Set shutdown flag and exit the application
-                    SHUTDOWN_IN_PROGRESS.store(true, Ordering::SeqCst);
-                    
-                    let app_handle = app.clone();
-                    tauri::async_runtime::spawn(async move {
-                        match commands::shutdown_application(app_handle).await {
-                            Ok(_) => debug!("Shutdown completed successfully from tray menu"),
-                            Err(e) => error!("Failed to shutdown from tray menu: {}", e),
-                        }
-                    });
-                }
-                "show" => {
-                    debug!("Show selected from tray menu");
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                    }
-                }
-                "hide" => {
-                    debug!("Hide selected from tray menu");
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.hide();
-                    }
-                }
-                "open_wallet" => {
-                    debug!("Open wallet selected from tray menu");
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                        // Emit event to frontend to open wallet dialog
-                        let _ = window.emit("tray-open-wallet", ());
-                    }
-                }
-                "create_wallet" => {
-                    debug!("Create wallet selected from tray menu");
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                        // Emit event to frontend to open create wallet dialog
-                        let _ = window.emit("tray-create-wallet", ());
-                    }
-                }                "close_wallet" => {
-                    debug!("Close wallet selected from tray menu");
-                    let app_handle = app.clone();
-                    tauri::async_runtime::spawn(async move {
-                        let wallet_manager = app_handle.state::<AsyncWalletManager>();
-                        match commands::close_wallet(wallet_manager).await {
-                            Ok(_) => {
-                                debug!("Wallet closed successfully from tray menu");
-                                // Update tray menu to reflect wallet closed
-                                if let Some(window) = app_handle.get_webview_window("main") {
-                                    let _ = window.emit("wallet-closed", ());
-                                }
-                            },
-                            Err(e) => error!("Failed to close wallet from tray menu: {}", e),
-                        }
-                    });
-                }
-                _ => {}
-            }
-        })
-        .build(app)?;
-    
-    info!("System tray created successfully");
-    Ok(())
-}
-
-/// Setup system tray after application initialization (when we have access to AppHandle)
-fn setup_system_tray_after_init(app_handle: &tauri::AppHandle) -> tauri::Result<()> {
-    use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
-    
-    info!("Setting up system tray after initialization");
-    
-    // Create tray menu items
-    let wallet_status_item = MenuItem::with_id(app_handle, "wallet_status", "No wallet open", false, None::<&str>)?;
-    let network_status_item = MenuItem::with_id(app_handle, "network_status", "Network: Disconnected", false, None::<&str>)?;
-    let separator1 = PredefinedMenuItem::separator(app_handle)?;
-    
-    let show_item = MenuItem::with_id(app_handle, "show", "Show Window", true, None::<&str>)?;
-    let hide_item = MenuItem::with_id(app_handle, "hide", "Hide Window", true, None::<&str>)?;
-    let separator2 = PredefinedMenuItem::separator(app_handle)?;
-    
-    let open_wallet_item = MenuItem::with_id(app_handle, "open_wallet", "Open Wallet...", true, None::<&str>)?;
-    let create_wallet_item = MenuItem::with_id(app_handle, "create_wallet", "Create Wallet...", true, None::<&str>)?;
-    let close_wallet_item = MenuItem::with_id(app_handle, "close_wallet", "Close Wallet", false, None::<&str>)?;
-    let separator3 = PredefinedMenuItem::separator(app_handle)?;
-    
-    let quit_item = MenuItem::with_id(app_handle, "quit", "Quit", true, None::<&str>)?;
-    
-    let menu = Menu::with_items(app_handle, &[
-        &wallet_status_item,
-        &network_status_item,
-        &separator1,
-        &show_item,
-        &hide_item,
-        &separator2,
-        &open_wallet_item,
-        &create_wallet_item,
-        &close_wallet_item,
-        &separator3,
-        &quit_item,
-    ])?;
-    
-    // Get the default icon (we need to handle this differently for AppHandle)
-    let icon = app_handle.default_window_icon().cloned().unwrap_or_else(|| {
-        // Fallback: create a simple default icon
-        let rgba_data: &[u8] = &[0; 32 * 32 * 4]; // 32x32 RGBA pixels (all transparent)
-        tauri::image::Image::new(rgba_data, 32, 32)
-    });
-    
-    // Create tray icon
-    let _tray = tauri::tray::TrayIconBuilder::with_id("main-tray")
-        .tooltip("B-Rad Coin Wallet")
-        .icon(icon)
-        .menu(&menu)
-        .on_tray_icon_event(|tray, event| {
-            match event {
-                tauri::tray::TrayIconEvent::Click { button: tauri::tray::MouseButton::Left, button_state: tauri::tray::MouseButtonState::Up, .. } => {
-                    debug!("Tray icon left clicked - showing window");
-                    let app = tray.app_handle();
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                    }
-                }
-                tauri::tray::TrayIconEvent::DoubleClick { button: tauri::tray::MouseButton::Left, .. } => {
-                    debug!("Tray icon double clicked - showing window");
-                    let app = tray.app_handle();
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                    }
-                }
-                _ => {}
-            }
-        })
-        .on_menu_event(|app, event| {
-            match event.id.as_ref() {
-                "quit" => {
-                    info!("Quit selected from tray menu");
-                    // Set shutdown flag and exit the application
-                    SHUTDOWN_IN_PROGRESS.store(true, std::sync::atomic::Ordering::SeqCst);
-                    
-                    let app_handle = app.clone();
-                    tauri::async_runtime::spawn(async move {
-                        match commands::shutdown_application(app_handle).await {
-                            Ok(_) => debug!("Shutdown completed successfully from tray menu"),
-                            Err(e) => error!("Failed to shutdown from tray menu: {}", e),
-                        }
-                    });
-                }
-                "show" => {
-                    debug!("Show selected from tray menu");
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                    }
-                }
-                "hide" => {
-                    debug!("Hide selected from tray menu");
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.hide();
-                    }
-                }
-                "open_wallet" => {
-                    debug!("Open wallet selected from tray menu");
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                        // Emit event to frontend to open wallet dialog
-                        let _ = window.emit("tray-open-wallet", ());
-                    }
-                }
-                "create_wallet" => {
-                    debug!("Create wallet selected from tray menu");
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                        // Emit event to frontend to open create wallet dialog
-                        let _ = window.emit("tray-create-wallet", ());
-                    }
-                }
-                "close_wallet" => {
-                    debug!("Close wallet selected from tray menu");
-                    let app_handle = app.clone();
-                    tauri::async_runtime::spawn(async move {
-                        let wallet_manager = app_handle.state::<AsyncWalletManager>();
-                        match commands::close_wallet(wallet_manager).await {
-                            Ok(_) => {
-                                debug!("Wallet closed successfully from tray menu");
-                                // Update tray menu to reflect wallet closed
-                                if let Some(window) = app_handle.get_webview_window("main") {
-                                    let _ = window.emit("wallet-closed", ());
-                                }
-                            },
-                            Err(e) => error!("Failed to close wallet from tray menu: {}", e),
-                        }
-                    });
-                }
-                _ => {}
-            }
-        })
-        .build(app_handle)?;
-    
-    info!("System tray created successfully after initialization");
-    Ok(())
-}
-
 /// Setup resource cleanup handler for proper database flushing on shutdown
 fn setup_resource_cleanup_handler(app_handle: tauri::AppHandle, blockchain_db: Arc<AsyncBlockchainDatabase>) {
     // Set up a handler that will be called during shutdown
@@ -864,28 +879,3 @@ async fn flush_and_release_database(blockchain_db: Arc<AsyncBlockchainDatabase>)
         }
     }
 }
-
-
-//async fn update(app: tauri::AppHandle) -> tauri_plugin_updater::Result<()> {
-//    if let Some(update) = app.updater()?.check().await? {
-//      let mut downloaded = 0;
-//  
-//      // alternatively we could also call update.download() and update.install() separately
-//      update
-//        .download_and_install(
-//          |chunk_length, content_length| {
-//            downloaded += chunk_length;
-//            println!("downloaded {downloaded} from {content_length:?}");
-//          },
-//          || {
-//           println!("download finished");
-//         },
-//        )
-//        .await?;
-//  
-//      println!("update installed");
-//      app.restart();
-//    }
-//  
-//    Ok(())
-//  }
\ No newline at end of file
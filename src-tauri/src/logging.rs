@@ -126,6 +126,18 @@ pub fn log_app_shutdown() {
     log::info!("==================================================");
 }
 
+/// Log target used for security-sensitive operations
+pub const AUDIT_TARGET: &str = "audit";
+
+/// Record a security-sensitive operation in the debug log and the audit log.
+/// Logged at warn level so it is kept even when the configured log level is quiet.
+pub fn log_audit_event(event: &str, details: &str) {
+    log::warn!(target: AUDIT_TARGET, "{}: {}", event, details);
+    if let Err(e) = crate::audit_log::append(event, details) {
+        log::error!("Failed to write audit record for {}: {}", event, e);
+    }
+}
+
 /// Helper macro for logging within the application
 #[macro_export]
 macro_rules! app_log {
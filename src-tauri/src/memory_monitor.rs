@@ -0,0 +1,134 @@
+//! Low-memory mode of the blockchain database.
+//!
+//! The share of system memory still available is read from /proc/meminfo on Linux,
+//! GlobalMemoryStatusEx on Windows and the kern.memorystatus_level sysctl on macOS; elsewhere
+//! memory is never considered short. Once less than `SHORT_BELOW_PERCENT` is available the
+//! database shrinks its caches, if the `db_low_memory_mode` setting allows it, and restores them
+//! once more than `RECOVERED_ABOVE_PERCENT` is available again. The gap between the two keeps the
+//! caches from flapping around a single threshold.
+
+use crate::blockchain_database::AsyncBlockchainDatabase;
+use crate::config::ConfigManager;
+use log::{error, info, warn};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often available memory is read; on macOS that starts a process
+const MEMORY_CHECK_INTERVAL_SECONDS: u64 = 30;
+
+/// Memory is short below this share available, in percent
+const SHORT_BELOW_PERCENT: u32 = 10;
+
+/// Memory is no longer short above this share available, in percent
+const RECOVERED_ABOVE_PERCENT: u32 = 20;
+
+/// Whether memory is short now, for sizing caches when the database opens
+pub fn memory_is_short() -> bool {
+    available_memory_percent().is_some_and(|percent| percent < SHORT_BELOW_PERCENT)
+}
+
+/// Whether memory is short after a reading, given whether it was before. An unreadable
+/// reading keeps the previous state.
+fn next_state(short: bool, available_percent: Option<u32>) -> bool {
+    match available_percent {
+        Some(percent) if short => percent <= RECOVERED_ABOVE_PERCENT,
+        Some(percent) => percent < SHORT_BELOW_PERCENT,
+        None => short,
+    }
+}
+
+/// Read available memory periodically and switch the database's low-memory mode to match
+pub fn start(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(MEMORY_CHECK_INTERVAL_SECONDS));
+        let mut short = false;
+        loop {
+            interval.tick().await;
+            let available = tokio::task::spawn_blocking(available_memory_percent).await.unwrap_or(None);
+            let now_short = next_state(short, available);
+            if now_short != short {
+                match (now_short, available) {
+                    (true, Some(percent)) => warn!("System memory is short: {}% available", percent),
+                    (_, Some(percent)) => info!("System memory recovered: {}% available", percent),
+                    _ => {}
+                }
+                short = now_short;
+            }
+
+            // Picks up changes to the db_low_memory_mode setting, and a database reopened in
+            // another location
+            let enabled = app_handle
+                .try_state::<Arc<ConfigManager>>()
+                .map(|config_manager| config_manager.get_config().app_settings.db_low_memory_mode)
+                .unwrap_or(true);
+            if let Some(blockchain_db) = crate::services::get::<AsyncBlockchainDatabase>(&app_handle) {
+                if let Err(e) = blockchain_db.set_low_memory(enabled && short).await {
+                    error!("Failed to switch the blockchain database's low-memory mode: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// MemAvailable as a share of MemTotal
+#[cfg(target_os = "linux")]
+fn available_memory_percent() -> Option<u32> {
+    parse_meminfo(&std::fs::read_to_string("/proc/meminfo").ok()?)
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn parse_meminfo(meminfo: &str) -> Option<u32> {
+    let field = |name: &str| -> Option<u64> {
+        let line = meminfo.lines().find(|line| line.starts_with(name))?;
+        line[name.len()..].trim().trim_end_matches("kB").trim().parse().ok()
+    };
+    let total = field("MemTotal:")?;
+    let available = field("MemAvailable:")?;
+    (total > 0).then(|| (available.min(total) * 100 / total) as u32)
+}
+
+/// The kernel's memory status level is the share of memory available, 0 to 100
+#[cfg(target_os = "macos")]
+fn available_memory_percent() -> Option<u32> {
+    let output = std::process::Command::new("sysctl").args(["-n", "kern.memorystatus_level"]).output().ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+#[cfg(windows)]
+fn available_memory_percent() -> Option<u32> {
+    use windows_sys::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+
+    // SAFETY: MEMORYSTATUSEX is plain data; dwLength must be set before the call fills it in
+    let mut status: MEMORYSTATUSEX = unsafe { std::mem::zeroed() };
+    status.dwLength = std::mem::size_of::<MEMORYSTATUSEX>() as u32;
+    if unsafe { GlobalMemoryStatusEx(&mut status) } == 0 {
+        return None;
+    }
+    // dwMemoryLoad is the share of physical memory in use
+    Some(100u32.saturating_sub(status.dwMemoryLoad))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn available_memory_percent() -> Option<u32> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_pressure_switches_with_a_gap_between_thresholds() {
+        let meminfo = "MemTotal:       16000000 kB\nMemFree:          500000 kB\nMemAvailable:    1200000 kB\n";
+        assert_eq!(parse_meminfo(meminfo), Some(7));
+        assert_eq!(parse_meminfo("MemTotal: 0 kB\nMemAvailable: 0 kB\n"), None);
+
+        assert!(next_state(false, Some(7)));
+        // Between the thresholds the previous state holds
+        assert!(next_state(true, Some(15)));
+        assert!(!next_state(false, Some(15)));
+        assert!(!next_state(true, Some(25)));
+        assert!(next_state(true, None));
+    }
+}
@@ -1,21 +1,40 @@
 //! Transaction Mempool Service
 //! Manages pending transactions before they are included in blocks
+//!
+//! A transaction's fee is computed from the outputs it spends, found in the UTXO set or among
+//! its mempool parents, minus the outputs it pays; the fee it declares is not trusted. Eviction,
+//! the mempool minimum fee and block assembly all go by that computed fee.
+//!
+//! Input signatures are verified against the same spent outputs through `signature_cache`, whose
+//! cache block validation shares, so a block's transactions already accepted here are not
+//! verified again.
 
 use crate::blockchain_database::{AsyncBlockchainDatabase, Transaction, TransactionInput, TransactionOutput};
 use crate::errors::*;
+use crate::events::{self, EmitEvent};
+use crate::relay_policy::{RelayPolicy, TransactionSource};
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::ops::Deref;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
-use tauri::{AppHandle, Emitter};
+use tauri::AppHandle;
 use tokio::sync::RwLock;
 
-/// Maximum number of transactions to keep in mempool
-const MAX_MEMPOOL_SIZE: usize = 10000;
+/// Default mempool size cap in megabytes
+pub const DEFAULT_MAX_MEMPOOL_SIZE_MB: u32 = 300;
 
-/// Maximum transaction size in bytes
-const MAX_TRANSACTION_SIZE: usize = 100000; // 100KB
+/// Allowed range for the configurable mempool size cap, in megabytes
+pub const MIN_MEMPOOL_SIZE_MB: u32 = 5;
+pub const MAX_MEMPOOL_SIZE_MB: u32 = 4096;
+
+/// Added to the fee rate of the last evicted transaction to get the mempool minimum fee
+const INCREMENTAL_RELAY_FEE_RATE: u64 = 1;
+
+/// The mempool minimum fee halves this often once evictions stop
+const MIN_FEE_HALF_LIFE_SECS: u64 = 12 * 60 * 60;
 
 /// Transaction fee rate (satoshis per byte)
 const MIN_FEE_RATE: u64 = 1;
@@ -45,11 +64,27 @@ pub struct ReplacementResult {
 pub struct MempoolTransaction {
     pub transaction: Transaction,
     pub received_time: u64,
+    /// Value of the spent outputs minus value of the outputs, in satoshis
+    pub fee: u64,
     pub fee_rate: u64, // satoshis per byte
     pub size: usize,   // transaction size in bytes
     pub dependencies: Vec<String>, // txids this transaction depends on
 }
 
+/// A mempool transaction together with its unconfirmed ancestors. Miners judge a transaction
+/// by the fee rate of its whole package, so a high-fee child pays for a low-fee parent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageInfo {
+    pub txid: String,
+    /// Transactions in the package, including this one
+    pub ancestor_count: usize,
+    /// Simplified size of the package in bytes
+    pub ancestor_size: usize,
+    pub ancestor_fee: u64,
+    /// Package fee rate in satoshis per byte
+    pub ancestor_fee_rate: u64,
+}
+
 /// Mempool statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MempoolStats {
@@ -58,22 +93,177 @@ pub struct MempoolStats {
     pub min_fee_rate: u64,
     pub max_fee_rate: u64,
     pub avg_fee_rate: u64,
+    /// Lowest fee rate (sat/byte) a new transaction needs to be accepted
+    pub mempool_min_fee_rate: u64,
+    pub max_size_bytes: usize,
+}
+
+/// Mempool transactions by txid, indexed for eviction and for finding descendants
+#[derive(Default)]
+struct MempoolPool {
+    txs: HashMap<String, MempoolTransaction>,
+    /// Eviction order: lowest fee rate first, and among equal fee rates the newest
+    by_fee_rate: BTreeSet<(u64, Reverse<u64>, String)>,
+    /// Mempool transactions spending each transaction's outputs
+    children: HashMap<String, HashSet<String>>,
+    /// Mempool transaction spending each outpoint (txid, output index)
+    spent_by: HashMap<(String, u32), String>,
+    total_size: usize,
+}
+
+impl MempoolPool {
+    fn insert(&mut self, txid: String, tx: MempoolTransaction) {
+        self.remove(&txid);
+        self.by_fee_rate.insert((tx.fee_rate, Reverse(tx.received_time), txid.clone()));
+        for parent in &tx.dependencies {
+            self.children.entry(parent.clone()).or_default().insert(txid.clone());
+        }
+        for input in &tx.transaction.inputs {
+            self.spent_by.insert((input.previous_txid.clone(), input.previous_output_index), txid.clone());
+        }
+        self.total_size += tx.size;
+        self.txs.insert(txid, tx);
+    }
+
+    fn remove(&mut self, txid: &str) -> Option<MempoolTransaction> {
+        let tx = self.txs.remove(txid)?;
+        self.by_fee_rate.remove(&(tx.fee_rate, Reverse(tx.received_time), txid.to_string()));
+        for parent in &tx.dependencies {
+            if let Some(siblings) = self.children.get_mut(parent) {
+                siblings.remove(txid);
+                if siblings.is_empty() {
+                    self.children.remove(parent);
+                }
+            }
+        }
+        self.children.remove(txid);
+        for input in &tx.transaction.inputs {
+            let outpoint = (input.previous_txid.clone(), input.previous_output_index);
+            if self.spent_by.get(&outpoint).is_some_and(|spender| spender == txid) {
+                self.spent_by.remove(&outpoint);
+            }
+        }
+        self.total_size -= tx.size;
+        Some(tx)
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// The transaction eviction would take next
+    fn lowest_fee_rate(&self) -> Option<&str> {
+        self.by_fee_rate.first().map(|(_, _, txid)| txid.as_str())
+    }
+
+    fn children_of(&self, txid: &str) -> impl Iterator<Item = &String> {
+        self.children.get(txid).into_iter().flatten()
+    }
+
+    /// Inputs of `transaction` that other mempool transactions already spend, with those
+    /// transactions' txids
+    fn conflicts<'a>(&'a self, transaction: &'a Transaction) -> impl Iterator<Item = (&'a TransactionInput, &'a str)> {
+        transaction.inputs.iter().filter_map(|input| {
+            let spender = self.spent_by.get(&(input.previous_txid.clone(), input.previous_output_index))?;
+            (*spender != transaction.txid).then_some((input, spender.as_str()))
+        })
+    }
+}
+
+impl Deref for MempoolPool {
+    type Target = HashMap<String, MempoolTransaction>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.txs
+    }
+}
+
+impl FromIterator<(String, MempoolTransaction)> for MempoolPool {
+    fn from_iter<I: IntoIterator<Item = (String, MempoolTransaction)>>(iter: I) -> Self {
+        let mut pool = Self::default();
+        for (txid, tx) in iter {
+            pool.insert(txid, tx);
+        }
+        pool
+    }
+}
+
+/// Minimum fee raised by evictions, decaying back to the minimum relay fee rate over time
+#[derive(Debug, Clone, Copy)]
+struct RollingMinFee {
+    fee_rate: u64,
+    updated_at: u64,
 }
 
 /// Transaction mempool service
 pub struct MempoolService {
-    transactions: Arc<RwLock<HashMap<String, MempoolTransaction>>>,
+    transactions: Arc<RwLock<MempoolPool>>,
     blockchain_db: Arc<AsyncBlockchainDatabase>,
     app_handle: Option<AppHandle>,
+    max_size_bytes: usize,
+    rolling_min_fee: RwLock<RollingMinFee>,
+    policy: RelayPolicy,
 }
 
 impl MempoolService {
     /// Create new mempool service
     pub fn new(blockchain_db: Arc<AsyncBlockchainDatabase>) -> Self {
         Self {
-            transactions: Arc::new(RwLock::new(HashMap::new())),
+            transactions: Arc::new(RwLock::new(MempoolPool::default())),
             blockchain_db,
             app_handle: None,
+            max_size_bytes: DEFAULT_MAX_MEMPOOL_SIZE_MB as usize * 1_000_000,
+            rolling_min_fee: RwLock::new(RollingMinFee {
+                fee_rate: MIN_FEE_RATE,
+                updated_at: Self::current_timestamp(),
+            }),
+            policy: RelayPolicy::default(),
+        }
+    }
+
+    /// Change the relay policy. Transactions already in the mempool are kept.
+    pub fn set_policy(&mut self, policy: RelayPolicy) {
+        info!("Mempool relay policy set to {:?}", policy);
+        self.policy = policy;
+    }
+
+    pub fn policy(&self) -> RelayPolicy {
+        self.policy.clone()
+    }
+
+    /// Change the size cap, evicting transactions if the pool no longer fits
+    pub async fn set_max_size_mb(&mut self, max_size_mb: u32) {
+        self.max_size_bytes = max_size_mb as usize * 1_000_000;
+        info!("Mempool size cap set to {} MB", max_size_mb);
+
+        let evicted = {
+            let mut txs = self.transactions.write().await;
+            Self::trim_to_size(&mut txs, self.max_size_bytes)
+        };
+        if let Some(fee_rate) = evicted {
+            self.raise_min_fee_rate(fee_rate + INCREMENTAL_RELAY_FEE_RATE).await;
+            self.emit_mempool_update().await;
+        }
+    }
+
+    /// Lowest fee rate (sat/byte) a new transaction must pay: the minimum relay fee rate, raised
+    /// when transactions are evicted for space and halved every `MIN_FEE_HALF_LIFE_SECS` after that.
+    pub async fn get_min_fee_rate(&self) -> u64 {
+        let rolling = *self.rolling_min_fee.read().await;
+        decay_min_fee_rate(
+            rolling.fee_rate,
+            Self::current_timestamp().saturating_sub(rolling.updated_at),
+            self.policy.min_relay_fee_rate,
+        )
+    }
+
+    async fn raise_min_fee_rate(&self, fee_rate: u64) {
+        let now = Self::current_timestamp();
+        let mut rolling = self.rolling_min_fee.write().await;
+        let current = decay_min_fee_rate(rolling.fee_rate, now.saturating_sub(rolling.updated_at), self.policy.min_relay_fee_rate);
+        if fee_rate > current {
+            info!("Mempool minimum fee raised to {} sat/byte", fee_rate);
+            *rolling = RollingMinFee { fee_rate, updated_at: now };
         }
     }
 
@@ -83,8 +273,8 @@ impl MempoolService {
         Ok(())
     }
 
-    /// Add transaction to mempool
-    pub async fn add_transaction(&self, mut transaction: Transaction) -> AppResult<String> {
+    /// Add transaction to mempool, subject to the relay policy for its source
+    pub async fn add_transaction(&self, mut transaction: Transaction, source: TransactionSource) -> AppResult<String> {
         // Generate transaction hash if not provided
         if transaction.txid.is_empty() {
             transaction.txid = self.calculate_transaction_hash(&transaction)?;
@@ -93,39 +283,61 @@ impl MempoolService {
         info!("Adding transaction {} to mempool", transaction.txid);
 
         // Validate transaction
-        self.validate_transaction(&transaction).await?;
+        let spent = self.spent_outputs(&*self.transactions.read().await, &transaction).await?;
+        let fee = self.calculate_transaction_fee(&spent, &transaction)?;
+        self.validate_transaction(&transaction, &spent, fee, source).await?;
 
         // Calculate transaction metadata
         let transaction_size = self.estimate_transaction_size(&transaction)?;
-        let fee_rate = self.calculate_fee_rate(&transaction, transaction_size)?;
+        let fee_rate = self.calculate_fee_rate(fee, &transaction);
         let dependencies = self.find_dependencies(&transaction).await;
 
         let tx_hash = transaction.txid.clone();
         let mempool_tx = MempoolTransaction {
             transaction: transaction.clone(),
             received_time: Self::current_timestamp(),
+            fee,
             fee_rate,
             size: transaction_size,
             dependencies,
         };
 
-        // Add to mempool with eviction if necessary
-        {
+        // Add to mempool, then evict the lowest fee rates if it no longer fits. The new
+        // transaction itself may be the one evicted.
+        let (evicted, accepted) = {
             let mut txs = self.transactions.write().await;
-            
-            // Check if we need to evict transactions
-            if txs.len() >= MAX_MEMPOOL_SIZE {
-                self.evict_low_fee_transactions(&mut txs).await;
+            // Checked under the write lock so two spends of one output cannot both get in
+            if let Some((input, spender)) = txs.conflicts(&transaction).next() {
+                return Err(AppError::Generic(format!(
+                    "Transaction {} spends output {}:{} already spent by mempool transaction {}; \
+                     replace that transaction instead",
+                    transaction.txid, input.previous_txid, input.previous_output_index, spender
+                )));
             }
-
             txs.insert(transaction.txid.clone(), mempool_tx);
+            let evicted = Self::trim_to_size(&mut txs, self.max_size_bytes);
+            (evicted, txs.contains_key(&tx_hash))
+        };
+        if let Some(evicted_fee_rate) = evicted {
+            self.raise_min_fee_rate(evicted_fee_rate + INCREMENTAL_RELAY_FEE_RATE).await;
         }
 
         // Emit event for frontend
         self.emit_mempool_update().await;
 
+        if !accepted {
+            return Err(AppError::Generic(format!(
+                "Mempool full: fee rate {} sat/byte is below the mempool minimum of {}",
+                fee_rate,
+                self.get_min_fee_rate().await
+            )));
+        }
+
         info!("Transaction {} added to mempool (fee rate: {} sat/byte)", 
               tx_hash, fee_rate);
+        if let Some(ref app) = self.app_handle {
+            crate::event_bus::publish(app, crate::event_bus::BackendEvent::TransactionAdded(Arc::new(transaction)));
+        }
         Ok(tx_hash)
     }
 
@@ -147,38 +359,41 @@ impl MempoolService {
         txs.get(txid).map(|mempool_tx| mempool_tx.transaction.clone())
     }
 
-    /// Get transactions for mining (highest fee rate first)
+    /// Get transactions for mining, best package fee rate first. Parents always come before
+    /// the transactions spending them.
     pub async fn get_transactions_for_mining(&self, max_count: usize, max_size_bytes: usize) -> Vec<Transaction> {
         let txs = self.transactions.read().await;
-        
-        // Sort by fee rate (highest first)
-        let mut sorted_txs: Vec<_> = txs.values().collect();
-        sorted_txs.sort_by(|a, b| b.fee_rate.cmp(&a.fee_rate));
-
-        let mut selected = Vec::new();
-        let mut total_size = 0;
-
-        for mempool_tx in sorted_txs {
-            if selected.len() >= max_count {
-                break;
-            }
-            
-            if total_size + mempool_tx.size > max_size_bytes {
-                break;
-            }
 
-            // Check dependencies are satisfied
-            if self.dependencies_satisfied(&mempool_tx.dependencies, &selected) {
-                selected.push(mempool_tx.transaction.clone());
-                total_size += mempool_tx.size;
-            }
-        }
+        let selected: Vec<Transaction> = Self::select_packages(&txs, max_count, max_size_bytes)
+            .iter()
+            .filter_map(|txid| txs.get(txid))
+            .map(|mempool_tx| mempool_tx.transaction.clone())
+            .collect();
+        let total_size: usize = selected.iter().filter_map(|tx| txs.get(&tx.txid)).map(|tx| tx.size).sum();
 
         info!("Selected {} transactions for mining (total size: {} bytes)", 
               selected.len(), total_size);
         selected
     }
 
+    /// Package (ancestor) fee information for a mempool transaction
+    pub async fn get_package_info(&self, txid: &str) -> Option<PackageInfo> {
+        let txs = self.transactions.read().await;
+        if !txs.contains_key(txid) {
+            return None;
+        }
+
+        let package = Self::package_of(&txs, txid, &HashSet::new());
+        let (ancestor_fee, ancestor_size) = Self::package_fee_and_size(&txs, &package);
+        Some(PackageInfo {
+            txid: txid.to_string(),
+            ancestor_count: package.len(),
+            ancestor_size,
+            ancestor_fee,
+            ancestor_fee_rate: ancestor_fee / ancestor_size.max(1) as u64,
+        })
+    }
+
     /// Get all pending transactions
     pub async fn get_all_transactions(&self) -> Vec<Transaction> {
         let txs = self.transactions.read().await;
@@ -189,6 +404,8 @@ impl MempoolService {
     pub async fn get_stats(&self) -> MempoolStats {
         let txs = self.transactions.read().await;
         
+        let mempool_min_fee_rate = self.get_min_fee_rate().await;
+        
         if txs.is_empty() {
             return MempoolStats {
                 transaction_count: 0,
@@ -196,6 +413,8 @@ impl MempoolService {
                 min_fee_rate: 0,
                 max_fee_rate: 0,
                 avg_fee_rate: 0,
+                mempool_min_fee_rate,
+                max_size_bytes: self.max_size_bytes,
             };
         }
 
@@ -210,6 +429,8 @@ impl MempoolService {
             avg_fee_rate: if !fee_rates.is_empty() { 
                 fee_rates.iter().sum::<u64>() / fee_rates.len() as u64 
             } else { 0 },
+            mempool_min_fee_rate,
+            max_size_bytes: self.max_size_bytes,
         }
     }
 
@@ -221,22 +442,28 @@ impl MempoolService {
         self.emit_mempool_update().await;
     }
 
-    /// Validate transaction before adding to mempool
-    async fn validate_transaction(&self, transaction: &Transaction) -> AppResult<()> {
-        // Check transaction size
+    /// Validate transaction before adding to mempool. `spent` are the outputs it spends and `fee`
+    /// is the fee it actually pays.
+    async fn validate_transaction(
+        &self,
+        transaction: &Transaction,
+        spent: &[TransactionOutput],
+        fee: u64,
+        source: TransactionSource,
+    ) -> AppResult<()> {
+        // Check size, outputs and source against the relay policy
         let size = self.estimate_transaction_size(transaction)?;
-        if size > MAX_TRANSACTION_SIZE {
-            return Err(AppError::Generic(format!("Transaction too large: {} bytes", size)));
-        }
+        self.policy
+            .check(transaction, size, source)
+            .map_err(|e| AppError::Generic(format!("Rejected by relay policy: {}", e)))?;
 
         // Check if transaction already exists in mempool
-        let txs = self.transactions.read().await;
-        if txs.contains_key(&transaction.txid) {
+        if self.transactions.read().await.contains_key(&transaction.txid) {
             return Err(AppError::Generic("Transaction already in mempool".to_string()));
         }
 
         // Check if transaction already exists in blockchain
-        if self.blockchain_db.get_transaction(&transaction.txid).await.is_ok() {
+        if matches!(self.blockchain_db.get_transaction(&transaction.txid).await, Ok(Some(_))) {
             return Err(AppError::Generic("Transaction already in blockchain".to_string()));
         }
 
@@ -249,19 +476,19 @@ impl MempoolService {
             return Err(AppError::Generic("Transaction has no outputs".to_string()));
         }
 
-        // Calculate and verify fee
-        let fee_rate = self.calculate_fee_rate(transaction, size)?;
-        if fee_rate < MIN_FEE_RATE {
+        // Verify the fee against the current mempool minimum
+        let fee_rate = self.calculate_fee_rate(fee, transaction);
+        let min_fee_rate = self.get_min_fee_rate().await;
+        if fee_rate < min_fee_rate {
             return Err(AppError::Generic(format!(
                 "Fee rate too low: {} sat/byte (minimum: {})", 
-                fee_rate, MIN_FEE_RATE
+                fee_rate, min_fee_rate
             )));
         }
 
+        Self::verify_signatures(transaction, spent)?;
+
         // TODO: Add more sophisticated validation:
-        // - Verify signatures
-        // - Check double-spending
-        // - Validate input amounts
         // - Check locktime
         
         Ok(())
@@ -297,38 +524,67 @@ impl MempoolService {
         }
     }
 
-    /// Calculate fee rate for transaction
     /// Calculate transaction size (simplified)
     fn calculate_transaction_size(&self, transaction: &Transaction) -> usize {
-        // Simplified size calculation - in real implementation would properly serialize transaction
-        let base_size = 10; // base transaction overhead
-        let input_size = transaction.inputs.len() * 150; // ~150 bytes per input
-        let output_size = transaction.outputs.len() * 34; // ~34 bytes per output
-        base_size + input_size + output_size
-    }
-
-    /// Calculate transaction fee
-    async fn calculate_transaction_fee(&self, transaction: &Transaction) -> AppResult<u64> {
-        // Simple fee calculation - in real implementation would check input/output values
-        let size = self.calculate_transaction_size(transaction);
-        let base_fee = 1000; // 1000 satoshis base fee
-        let size_fee = size as u64 * MIN_FEE_RATE;
-        Ok(base_fee + size_fee)
-    }
-
-    fn calculate_fee_rate(&self, transaction: &Transaction, size: usize) -> AppResult<u64> {
-        // Simple fee calculation - in real implementation would check input/output values
-        let base_fee = 1000; // 1000 satoshis base fee
-        let size_fee = size as u64 * MIN_FEE_RATE;
-        let total_fee = base_fee + size_fee;
-        
-        Ok(total_fee / size as u64)
+        simplified_transaction_size(transaction)
+    }
+
+    /// Outputs the transaction spends, in input order, from `txs` or the UTXO set. Rejects a
+    /// transaction spending an output that cannot be found.
+    async fn spent_outputs(&self, txs: &MempoolPool, transaction: &Transaction) -> AppResult<Vec<TransactionOutput>> {
+        let mut spent = Vec::with_capacity(transaction.inputs.len());
+        for input in &transaction.inputs {
+            let output = match txs.get(&input.previous_txid) {
+                Some(parent) => parent.transaction.outputs.get(input.previous_output_index as usize).cloned(),
+                None => self
+                    .blockchain_db
+                    .get_utxo(&input.previous_txid, input.previous_output_index)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|utxo| TransactionOutput {
+                        value: utxo.value,
+                        script_pubkey: utxo.script_pubkey,
+                        address: utxo.address,
+                    }),
+            };
+            spent.push(output.ok_or_else(|| {
+                AppError::Generic(format!(
+                    "Transaction {} spends output {}:{}, which is neither unspent on chain nor in the mempool",
+                    transaction.txid, input.previous_txid, input.previous_output_index
+                ))
+            })?);
+        }
+        Ok(spent)
+    }
+
+    /// Fee the transaction pays: the value of the `spent` outputs minus the value of its
+    /// outputs. Rejects a transaction paying out more than it spends.
+    fn calculate_transaction_fee(&self, spent: &[TransactionOutput], transaction: &Transaction) -> AppResult<u64> {
+        let input_value = spent.iter().fold(0u64, |total, output| total.saturating_add(output.value));
+        crate::consensus::transaction_fee(transaction, input_value).map_err(|e| AppError::Generic(e.to_string()))
+    }
+
+    /// Check that each input is signed by the owner of the output it spends
+    fn verify_signatures(transaction: &Transaction, spent: &[TransactionOutput]) -> AppResult<()> {
+        let addresses: Vec<&str> = spent.iter().map(|output| output.address.as_str()).collect();
+        crate::signature_cache::verify_transaction(transaction, &addresses).map_err(|e| AppError::Generic(e.to_string()))
+    }
+
+    /// Fee rate `fee` amounts to, in satoshis per byte of the transaction's simplified size (the
+    /// size wallets use when choosing a fee)
+    fn calculate_fee_rate(&self, fee: u64, transaction: &Transaction) -> u64 {
+        fee / self.calculate_transaction_size(transaction) as u64
     }
 
     /// Find transaction dependencies
     async fn find_dependencies(&self, transaction: &Transaction) -> Vec<String> {
+        Self::dependencies_in(&*self.transactions.read().await, transaction)
+    }
+
+    /// Transactions in `txs` whose outputs `transaction` spends
+    fn dependencies_in(txs: &MempoolPool, transaction: &Transaction) -> Vec<String> {
         let mut dependencies = Vec::new();
-        let txs = self.transactions.read().await;
 
         for input in &transaction.inputs {
             // Use the correct field name for previous transaction ID
@@ -341,37 +597,147 @@ impl MempoolService {
         dependencies
     }
 
-    /// Check if transaction dependencies are satisfied
-    fn dependencies_satisfied(&self, dependencies: &[String], selected: &[Transaction]) -> bool {
-        for dep_txid in dependencies {
-            if !selected.iter().any(|tx| tx.txid == *dep_txid) {
-                return false;
+    /// A transaction and its mempool ancestors not in `exclude`, parents before children
+    fn package_of(txs: &HashMap<String, MempoolTransaction>, txid: &str, exclude: &HashSet<String>) -> Vec<String> {
+        fn visit(
+            txs: &HashMap<String, MempoolTransaction>,
+            txid: &str,
+            exclude: &HashSet<String>,
+            package: &mut Vec<String>,
+        ) {
+            if exclude.contains(txid) || package.iter().any(|id| id == txid) {
+                return;
             }
+            let Some(tx) = txs.get(txid) else {
+                return; // Confirmed or evicted parent
+            };
+            for parent in &tx.dependencies {
+                visit(txs, parent, exclude, package);
+            }
+            package.push(txid.to_string());
         }
-        true
+
+        let mut package = Vec::new();
+        visit(txs, txid, exclude, &mut package);
+        package
     }
 
-    /// Evict low fee rate transactions to make room
-    async fn evict_low_fee_transactions(&self, txs: &mut HashMap<String, MempoolTransaction>) {
-        let evict_count = txs.len() / 10; // Evict 10% of transactions
-        
-        // Sort by fee rate (lowest first)
-        let mut sorted_txids: Vec<_> = txs.iter()
-            .map(|(txid, mempool_tx)| (txid.clone(), mempool_tx.fee_rate))
+    /// Total fee and simplified size of a set of mempool transactions
+    fn package_fee_and_size(txs: &HashMap<String, MempoolTransaction>, package: &[String]) -> (u64, usize) {
+        package
+            .iter()
+            .filter_map(|txid| txs.get(txid))
+            .fold((0, 0), |(fee, size), tx| {
+                (fee + tx.fee, size + simplified_transaction_size(&tx.transaction))
+            })
+    }
+
+    /// Choose transactions for a block by ancestor package fee rate (child-pays-for-parent).
+    /// Repeatedly takes the package with the best fee rate counting only ancestors not yet
+    /// chosen; a package that does not fit is skipped. Returns txids in block order.
+    ///
+    /// Package fees and sizes are worked out once. Choosing a package takes its transactions'
+    /// fees and sizes off their descendants' packages and queues those again at the new rate;
+    /// queued entries that no longer match their package are passed over.
+    fn select_packages(txs: &MempoolPool, max_count: usize, max_size_bytes: usize) -> Vec<String> {
+        // Fee and size of each remaining transaction's package of ancestors not yet chosen
+        let mut packages: HashMap<&str, (u64, usize)> = txs
+            .keys()
+            .map(|txid| {
+                let package = Self::package_of(txs, txid, &HashSet::new());
+                (txid.as_str(), Self::package_fee_and_size(txs, &package))
+            })
             .collect();
-        sorted_txids.sort_by(|a, b| a.1.cmp(&b.1));
+        let mut queue: BinaryHeap<PackageCandidate> = packages
+            .iter()
+            .map(|(txid, (fee, size))| PackageCandidate { txid, fee: *fee, size: *size })
+            .collect();
+
+        let mut selected: Vec<String> = Vec::new();
+        let mut in_block: HashSet<String> = HashSet::new();
+        let mut total_size = 0;
+
+        while let Some(candidate) = queue.pop() {
+            // Chosen, skipped, or queued again at a new rate since
+            if packages.get(candidate.txid) != Some(&(candidate.fee, candidate.size)) {
+                continue;
+            }
+
+            let package = Self::package_of(txs, candidate.txid, &in_block);
+            let package_size: usize = package.iter().filter_map(|id| txs.get(id)).map(|tx| tx.size).sum();
+            if selected.len() + package.len() > max_count || total_size + package_size > max_size_bytes {
+                packages.remove(candidate.txid);
+                continue;
+            }
+
+            total_size += package_size;
+            for id in package {
+                let Some((txid, tx)) = txs.get_key_value(&id) else {
+                    continue;
+                };
+                packages.remove(txid.as_str());
+                let (fee, size) = (tx.fee, simplified_transaction_size(&tx.transaction));
+                for descendant in Self::with_descendants(txs, txid).iter().skip(1) {
+                    let Some((descendant, _)) = txs.get_key_value(descendant) else {
+                        continue;
+                    };
+                    if let Some(remaining) = packages.get_mut(descendant.as_str()) {
+                        *remaining = (remaining.0 - fee, remaining.1 - size);
+                        queue.push(PackageCandidate { txid: descendant, fee: remaining.0, size: remaining.1 });
+                    }
+                }
+                in_block.insert(id.clone());
+                selected.push(id);
+            }
+        }
+
+        selected
+    }
+
+    /// Evict the lowest fee rate transactions, together with any mempool transactions spending
+    /// them, until the pool fits in `max_size_bytes`. Among equal fee rates the newest goes
+    /// first. Returns the highest fee rate evicted.
+    fn trim_to_size(txs: &mut MempoolPool, max_size_bytes: usize) -> Option<u64> {
+        let mut highest_evicted = None;
+
+        while txs.total_size > max_size_bytes {
+            let Some(lowest) = txs.lowest_fee_rate().map(str::to_string) else {
+                break;
+            };
+
+            for txid in Self::with_descendants(txs, &lowest) {
+                if let Some(evicted) = txs.remove(&txid) {
+                    highest_evicted = highest_evicted.max(Some(evicted.fee_rate));
+                    warn!("Evicted transaction {} ({} sat/byte) due to mempool size limit", txid, evicted.fee_rate);
+                }
+            }
+        }
 
-        for (txid, _) in sorted_txids.into_iter().take(evict_count) {
-            txs.remove(&txid);
-            warn!("Evicted transaction {} due to mempool size limit", txid);
+        highest_evicted
+    }
+
+    /// A transaction and every mempool transaction that depends on it, directly or not
+    fn with_descendants(txs: &MempoolPool, txid: &str) -> Vec<String> {
+        let mut found = vec![txid.to_string()];
+        let mut seen: HashSet<String> = found.iter().cloned().collect();
+        let mut index = 0;
+        while index < found.len() {
+            let children: Vec<String> = txs.children_of(&found[index]).cloned().collect();
+            for child in children {
+                if seen.insert(child.clone()) {
+                    found.push(child);
+                }
+            }
+            index += 1;
         }
+        found
     }
 
     /// Emit mempool update event to frontend
     async fn emit_mempool_update(&self) {
         if let Some(app_handle) = &self.app_handle {
             let stats = self.get_stats().await;
-            if let Err(e) = app_handle.emit("mempool-update", &stats) {
+            if let Err(e) = app_handle.emit_event(events::MEMPOOL_UPDATE, &stats) {
                 warn!("Failed to emit mempool update: {}", e);
             }
         }
@@ -401,10 +767,23 @@ impl MempoolService {
         
         // Validate the replacement
         self.validate_replacement(&old_entry.transaction, &new_transaction, &reason)?;
+        let other_conflict = mempool_txs.conflicts(&new_transaction).find(|(_, spender)| *spender != old_tx_hash);
+        if let Some((input, spender)) = other_conflict {
+            return Err(AppError::Generic(format!(
+                "Replacement spends output {}:{} also spent by mempool transaction {}",
+                input.previous_txid, input.previous_output_index, spender
+            )));
+        }
+        let size = self.estimate_transaction_size(&new_transaction)?;
+        self.policy
+            .check(&new_transaction, size, TransactionSource::Local)
+            .map_err(|e| AppError::Generic(format!("Rejected by relay policy: {}", e)))?;
         
-        // Calculate new fee
-        let new_fee = self.calculate_transaction_fee(&new_transaction).await?;
-        let fee_increase = new_fee.saturating_sub(old_entry.fee_rate * old_entry.size as u64);
+        // The replacement spends the same outputs, so its fee is found the same way
+        let spent = self.spent_outputs(&mempool_txs, &new_transaction).await?;
+        let new_fee = self.calculate_transaction_fee(&spent, &new_transaction)?;
+        Self::verify_signatures(&new_transaction, &spent)?;
+        let fee_increase = new_fee.saturating_sub(old_entry.fee);
         
         // Create new entry
         let new_tx_hash = new_transaction.txid.clone();
@@ -412,27 +791,30 @@ impl MempoolService {
         let new_entry = MempoolTransaction {
             transaction: new_transaction.clone(),
             received_time: Self::current_timestamp(),
+            fee: new_fee,
             fee_rate: new_fee / transaction_size as u64,
             size: transaction_size,
-            dependencies: self.find_dependencies(&new_transaction).await,
+            // The write lock is held, so look the parents up in place
+            dependencies: Self::dependencies_in(&mempool_txs, &new_transaction),
         };
         
-        // Add new transaction and remove old one
-        mempool_txs.insert(new_tx_hash.clone(), new_entry);
+        // Remove old transaction and add new one, which takes over the outputs it spent
         mempool_txs.remove(old_tx_hash);
+        mempool_txs.insert(new_tx_hash.clone(), new_entry);
+        drop(mempool_txs);
         
         let result = ReplacementResult {
             success: true,
             old_tx_hash: old_tx_hash.to_string(),
             new_tx_hash: new_tx_hash.clone(),
             reason,
-            old_fee: old_entry.fee_rate * old_entry.size as u64,
+            old_fee: old_entry.fee,
             new_fee,
             fee_increase,
         };
         
         info!("Transaction replaced: {} -> {} (fee: {} -> {})", 
-              old_tx_hash, new_tx_hash, old_entry.fee_rate * old_entry.size as u64, new_fee);
+              old_tx_hash, new_tx_hash, old_entry.fee, new_fee);
         
         // Emit update
         self.emit_mempool_update().await;
@@ -537,9 +919,9 @@ impl AsyncMempoolService {
     }
 
     /// Add transaction to mempool
-    pub async fn add_transaction(&self, transaction: Transaction) -> AppResult<String> {
+    pub async fn add_transaction(&self, transaction: Transaction, source: TransactionSource) -> AppResult<String> {
         let service = self.inner.read().await;
-        service.add_transaction(transaction).await
+        service.add_transaction(transaction, source).await
     }
 
     /// Remove transaction from mempool
@@ -560,6 +942,12 @@ impl AsyncMempoolService {
         service.get_transactions_for_mining(max_count, max_size_bytes).await
     }
 
+    /// Get package (ancestor) fee information for a transaction
+    pub async fn get_package_info(&self, txid: &str) -> Option<PackageInfo> {
+        let service = self.inner.read().await;
+        service.get_package_info(txid).await
+    }
+
     /// Get all transactions
     pub async fn get_all_transactions(&self) -> Vec<Transaction> {
         let service = self.inner.read().await;
@@ -572,6 +960,30 @@ impl AsyncMempoolService {
         service.get_stats().await
     }
 
+    /// Lowest fee rate (sat/byte) the mempool currently accepts
+    pub async fn get_min_fee_rate(&self) -> u64 {
+        let service = self.inner.read().await;
+        service.get_min_fee_rate().await
+    }
+
+    /// Change the mempool size cap
+    pub async fn set_max_size_mb(&self, max_size_mb: u32) {
+        let mut service = self.inner.write().await;
+        service.set_max_size_mb(max_size_mb).await
+    }
+
+    /// Change the relay policy
+    pub async fn set_policy(&self, policy: RelayPolicy) {
+        let mut service = self.inner.write().await;
+        service.set_policy(policy)
+    }
+
+    /// The relay policy in force
+    pub async fn policy(&self) -> RelayPolicy {
+        let service = self.inner.read().await;
+        service.policy()
+    }
+
     /// Get mempool info (alias for get_stats for command compatibility)
     pub async fn get_mempool_info(&self) -> AppResult<MempoolStats> {
         Ok(self.get_stats().await)
@@ -609,3 +1021,239 @@ impl Clone for AsyncMempoolService {
         }
     }
 }
+
+/// A transaction queued for block assembly with the fee and size of its package. Ordered by
+/// package fee rate, compared without rounding; ties go to the lower txid for a stable order.
+struct PackageCandidate<'a> {
+    txid: &'a str,
+    fee: u64,
+    size: usize,
+}
+
+impl Ord for PackageCandidate<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.fee as u128 * other.size.max(1) as u128)
+            .cmp(&(other.fee as u128 * self.size.max(1) as u128))
+            .then_with(|| other.txid.cmp(self.txid))
+    }
+}
+
+impl PartialOrd for PackageCandidate<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for PackageCandidate<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PackageCandidate<'_> {}
+
+/// Simplified transaction size: base overhead plus per-input and per-output bytes. Wallets use
+/// the same sizing when choosing a fee, so fee rates are measured against it.
+fn simplified_transaction_size(transaction: &Transaction) -> usize {
+    let base_size = 10; // base transaction overhead
+    let input_size = transaction.inputs.len() * 150; // ~150 bytes per input
+    let output_size = transaction.outputs.len() * 34; // ~34 bytes per output
+    base_size + input_size + output_size
+}
+
+/// Validate a mempool size cap from settings
+pub fn validate_max_mempool_size_mb(max_size_mb: u32) -> Result<(), String> {
+    if !(MIN_MEMPOOL_SIZE_MB..=MAX_MEMPOOL_SIZE_MB).contains(&max_size_mb) {
+        return Err(format!(
+            "Mempool size must be between {} and {} MB",
+            MIN_MEMPOOL_SIZE_MB, MAX_MEMPOOL_SIZE_MB
+        ));
+    }
+    Ok(())
+}
+
+/// A minimum fee rate after `elapsed_secs` of halving, never below `floor`
+fn decay_min_fee_rate(fee_rate: u64, elapsed_secs: u64, floor: u64) -> u64 {
+    let halvings = (elapsed_secs / MIN_FEE_HALF_LIFE_SECS).min(63);
+    (fee_rate >> halvings).max(floor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(txid: &str, fee_rate: u64, received_time: u64, dependencies: &[&str]) -> (String, MempoolTransaction) {
+        let transaction = Transaction {
+            txid: txid.to_string(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            timestamp: received_time,
+            fee: 0,
+        };
+        let mempool_tx = MempoolTransaction {
+            transaction,
+            received_time,
+            fee: fee_rate * 10, // Simplified size of a transaction without inputs or outputs
+            fee_rate,
+            size: 100,
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+        };
+        (txid.to_string(), mempool_tx)
+    }
+
+    #[test]
+    fn test_trim_evicts_lowest_fee_rate_with_descendants() {
+        let mut txs: MempoolPool = [
+            entry("high", 50, 1, &[]),
+            entry("low", 2, 1, &[]),
+            entry("low_child", 40, 2, &["low"]),
+            entry("mid_old", 10, 1, &[]),
+            entry("mid_new", 10, 5, &[]),
+        ]
+        .into_iter()
+        .collect();
+
+        // Fits already: nothing to do
+        assert_eq!(MempoolService::trim_to_size(&mut txs, 500), None);
+
+        // The lowest fee rate goes, and its child with it
+        assert_eq!(MempoolService::trim_to_size(&mut txs, 300), Some(40));
+        assert!(!txs.contains_key("low") && !txs.contains_key("low_child"));
+
+        // Equal fee rates: the newest is evicted first
+        assert_eq!(MempoolService::trim_to_size(&mut txs, 200), Some(10));
+        assert!(txs.contains_key("mid_old") && !txs.contains_key("mid_new"));
+    }
+
+    /// `wallet`'s signed spend of the first output of `coinbase`, paying `pays` back to it
+    fn spend_coinbase(
+        wallet: &crate::test_support::TestWallet,
+        coinbase: &Transaction,
+        txid: &str,
+        pays: u64,
+        declared_fee: u64,
+    ) -> Transaction {
+        let mut transaction = Transaction {
+            txid: txid.to_string(),
+            inputs: vec![TransactionInput {
+                previous_txid: coinbase.txid.clone(),
+                previous_output_index: 0,
+                script_sig: String::new(),
+                sequence: u32::MAX,
+            }],
+            outputs: vec![TransactionOutput {
+                value: pays,
+                script_pubkey: coinbase.outputs[0].script_pubkey.clone(),
+                address: wallet.address.clone(),
+            }],
+            timestamp: coinbase.timestamp,
+            fee: declared_fee,
+        };
+        wallet.sign(&mut transaction);
+        transaction
+    }
+
+    #[tokio::test]
+    async fn test_fee_is_computed_from_spent_outputs() {
+        let node = crate::test_support::TestNode::start("mempool_fee").await;
+        let wallet = node.create_wallet("fee_payer");
+        let coinbase = node.mine_blocks(1, &wallet.address).await.remove(0).transactions.remove(0);
+        let spend = |txid: &str, pays: u64, declared_fee: u64| spend_coinbase(&wallet, &coinbase, txid, pays, declared_fee);
+        let input_value = coinbase.outputs[0].value;
+
+        // Paying out more than it spends is refused whatever fee it declares
+        let overspend = spend("overspend", input_value + 1, 0);
+        assert!(node.mempool.add_transaction(overspend, TransactionSource::Local).await.is_err());
+
+        // A declared fee far above what it pays does not count
+        let inflated = spend("inflated", input_value - 1_000, 1_000_000_000);
+        node.mempool.add_transaction(inflated, TransactionSource::Local).await.unwrap();
+        assert_eq!(node.mempool.get_package_info("inflated").await.unwrap().ancestor_fee, 1_000);
+    }
+
+    #[tokio::test]
+    async fn test_double_spend_is_refused_unless_it_replaces() {
+        let node = crate::test_support::TestNode::start("mempool_double_spend").await;
+        let wallet = node.create_wallet("double_spender");
+        let coinbase = node.mine_blocks(1, &wallet.address).await.remove(0).transactions.remove(0);
+        let spend = |txid: &str, fee: u64| spend_coinbase(&wallet, &coinbase, txid, coinbase.outputs[0].value - fee, fee);
+
+        node.mempool.add_transaction(spend("first", 1_000), TransactionSource::Local).await.unwrap();
+        assert!(node.mempool.add_transaction(spend("second", 2_000), TransactionSource::Local).await.is_err());
+        assert!(node.mempool.get_transaction("second").await.is_none());
+
+        // Replacing the first spend hands the output to the replacement
+        node.mempool
+            .replace_transaction("first", spend("second", 2_000), ReplacementReason::UserRequest)
+            .await
+            .unwrap();
+        assert!(node.mempool.get_transaction("first").await.is_none());
+        assert!(node.mempool.add_transaction(spend("third", 3_000), TransactionSource::Local).await.is_err());
+        assert!(node.mempool.get_transaction("second").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_inputs_must_be_signed_by_the_spent_output_owner() {
+        let node = crate::test_support::TestNode::start("mempool_signatures").await;
+        let owner = node.create_wallet("coin_owner");
+        let thief = node.create_wallet("coin_thief");
+        let coinbase = node.mine_blocks(1, &owner.address).await.remove(0).transactions.remove(0);
+        let mut spend = Transaction {
+            txid: String::new(),
+            inputs: vec![TransactionInput {
+                previous_txid: coinbase.txid.clone(),
+                previous_output_index: 0,
+                script_sig: String::new(),
+                sequence: u32::MAX,
+            }],
+            outputs: vec![TransactionOutput {
+                value: coinbase.outputs[0].value - 1_000,
+                script_pubkey: String::new(),
+                address: thief.address.clone(),
+            }],
+            timestamp: coinbase.timestamp,
+            fee: 1_000,
+        };
+
+        assert!(node.mempool.add_transaction(spend.clone(), TransactionSource::Local).await.is_err());
+        thief.sign(&mut spend);
+        assert!(node.mempool.add_transaction(spend.clone(), TransactionSource::Local).await.is_err());
+        owner.sign(&mut spend);
+        node.mempool.add_transaction(spend, TransactionSource::Local).await.unwrap();
+    }
+
+    #[test]
+    fn test_mining_selects_by_package_fee_rate() {
+        let txs: MempoolPool = [
+            entry("parent", 1, 1, &[]),
+            entry("child", 30, 2, &["parent"]),
+            entry("sibling", 20, 3, &["parent"]),
+            entry("middle", 10, 1, &[]),
+            entry("alone", 5, 1, &[]),
+        ]
+        .into_iter()
+        .collect();
+
+        // parent+child pay 31 over twice the size, beating middle on their own. Once parent is
+        // in, sibling's package is sibling alone.
+        assert_eq!(
+            MempoolService::select_packages(&txs, 10, usize::MAX),
+            vec!["parent", "child", "sibling", "middle", "alone"]
+        );
+
+        // A package that does not fit is skipped rather than ending the selection
+        assert_eq!(MempoolService::select_packages(&txs, 1, usize::MAX), vec!["middle"]);
+        assert_eq!(MempoolService::select_packages(&txs, 10, 250), vec!["parent", "child"]);
+    }
+
+    #[test]
+    fn test_min_fee_rate_decays() {
+        assert_eq!(decay_min_fee_rate(40, 0, MIN_FEE_RATE), 40);
+        assert_eq!(decay_min_fee_rate(40, MIN_FEE_HALF_LIFE_SECS, MIN_FEE_RATE), 20);
+        assert_eq!(decay_min_fee_rate(40, 3 * MIN_FEE_HALF_LIFE_SECS, MIN_FEE_RATE), 5);
+        assert_eq!(decay_min_fee_rate(40, u64::MAX, MIN_FEE_RATE), MIN_FEE_RATE);
+        assert_eq!(decay_min_fee_rate(40, 3 * MIN_FEE_HALF_LIFE_SECS, 8), 8);
+        assert!(validate_max_mempool_size_mb(DEFAULT_MAX_MEMPOOL_SIZE_MB).is_ok());
+        assert!(validate_max_mempool_size_mb(0).is_err());
+    }
+}
@@ -0,0 +1,284 @@
+//! Prometheus-style metrics endpoint.
+//!
+//! When enabled in settings, `http://127.0.0.1:<port>/metrics` serves node gauges and counters
+//! in the Prometheus text format so operators can scrape the app with standard tooling. Values
+//! are read from the running services on every scrape; metrics of services that are not running
+//! are left out. Only the loopback interface is bound and the endpoint is off by default.
+
+use crate::blockchain_database::AsyncBlockchainDatabase;
+use crate::command_metrics::CommandStats;
+use crate::config::{AppSettings, ConfigManager};
+use crate::mempool_service::AsyncMempoolService;
+use crate::mining_service::AsyncMiningService;
+use crate::network_service::AsyncNetworkService;
+use log::{debug, info, warn};
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Port the metrics endpoint listens on unless configured otherwise
+pub const DEFAULT_METRICS_PORT: u16 = 9477;
+
+/// Largest request head read from a scraper
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// How long a scraper may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Node values exported on a scrape; None when the service providing them is not running
+#[derive(Debug, Clone, Default)]
+pub struct NodeMetrics {
+    pub block_height: Option<u64>,
+    pub network_height: Option<u64>,
+    pub connected_peers: Option<u32>,
+    pub known_peers: Option<u32>,
+    pub bytes_sent: Option<u64>,
+    pub bytes_received: Option<u64>,
+    pub blocks_received: Option<u64>,
+    pub transactions_received: Option<u64>,
+    pub mempool_transactions: Option<usize>,
+    pub mempool_bytes: Option<usize>,
+    /// Combined hash rate of every wallet mining, in hashes per second
+    pub hash_rate: Option<f64>,
+    pub database_size_bytes: Option<u64>,
+}
+
+/// Collect the current values from the running services
+pub async fn collect(app_handle: &AppHandle) -> NodeMetrics {
+    let mut metrics = NodeMetrics::default();
+
+    if let Some(blockchain_db) = crate::services::get::<AsyncBlockchainDatabase>(app_handle) {
+        metrics.block_height = blockchain_db.get_block_height().await.ok();
+    }
+    if let Some(network_service) = crate::services::get::<AsyncNetworkService>(app_handle) {
+        let stats = network_service.get_stats().await;
+        metrics.network_height = Some(stats.network_height);
+        metrics.connected_peers = Some(stats.connected_peers);
+        metrics.known_peers = Some(stats.total_known_peers);
+        metrics.bytes_sent = Some(stats.bytes_sent);
+        metrics.bytes_received = Some(stats.bytes_received);
+        metrics.blocks_received = Some(stats.blocks_received);
+        metrics.transactions_received = Some(stats.transactions_received);
+    }
+    if let Some(mempool_service) = crate::services::get::<AsyncMempoolService>(app_handle) {
+        let stats = mempool_service.get_stats().await;
+        metrics.mempool_transactions = Some(stats.transaction_count);
+        metrics.mempool_bytes = Some(stats.total_size_bytes);
+    }
+    if let Some(mining_service) = crate::services::get::<AsyncMiningService>(app_handle) {
+        let statuses = mining_service.get_all_mining_statuses().await;
+        metrics.hash_rate = Some(statuses.values().filter(|s| s.is_mining).map(|s| s.hash_rate).sum());
+    }
+    if let Some(config_manager) = app_handle.try_state::<Arc<ConfigManager>>() {
+        let settings = config_manager.get_config().app_settings;
+        let size = tokio::task::spawn_blocking(move || crate::commands::blockchain_database_size(&settings)).await;
+        metrics.database_size_bytes = size.ok().and_then(Result::ok);
+    }
+
+    metrics
+}
+
+/// Render metrics in the Prometheus text exposition format
+pub fn render(metrics: &NodeMetrics) -> String {
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: Option<f64>| {
+        if let Some(value) = value {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            let _ = writeln!(out, "{} {}", name, value);
+        }
+    };
+
+    let sync_lag = metrics
+        .block_height
+        .zip(metrics.network_height)
+        .map(|(local, network)| network.saturating_sub(local));
+
+    metric("brad_block_height", "gauge", "Height of the local chain tip", metrics.block_height.map(|v| v as f64));
+    metric("brad_network_height", "gauge", "Best height reported by peers", metrics.network_height.map(|v| v as f64));
+    metric("brad_sync_lag_blocks", "gauge", "Blocks the local chain is behind the network", sync_lag.map(|v| v as f64));
+    metric("brad_peers_connected", "gauge", "Connected peers", metrics.connected_peers.map(f64::from));
+    metric("brad_peers_known", "gauge", "Peer addresses known", metrics.known_peers.map(f64::from));
+    metric("brad_network_sent_bytes_total", "counter", "Bytes sent to peers", metrics.bytes_sent.map(|v| v as f64));
+    metric("brad_network_received_bytes_total", "counter", "Bytes received from peers", metrics.bytes_received.map(|v| v as f64));
+    metric("brad_blocks_received_total", "counter", "Blocks received from peers", metrics.blocks_received.map(|v| v as f64));
+    metric(
+        "brad_transactions_received_total",
+        "counter",
+        "Transactions received from peers",
+        metrics.transactions_received.map(|v| v as f64),
+    );
+    metric("brad_mempool_transactions", "gauge", "Transactions in the mempool", metrics.mempool_transactions.map(|v| v as f64));
+    metric("brad_mempool_bytes", "gauge", "Size of the mempool in bytes", metrics.mempool_bytes.map(|v| v as f64));
+    metric("brad_hash_rate", "gauge", "Local mining hash rate in hashes per second", metrics.hash_rate);
+    metric("brad_database_size_bytes", "gauge", "Size of the blockchain database on disk", metrics.database_size_bytes.map(|v| v as f64));
+    out
+}
+
+/// Render command latencies as a Prometheus histogram and errors as a counter, per command
+pub fn render_commands(commands: &[CommandStats]) -> String {
+    let mut out = String::new();
+    if commands.is_empty() {
+        return out;
+    }
+
+    out.push_str("# HELP brad_command_duration_seconds Time taken by backend commands\n");
+    out.push_str("# TYPE brad_command_duration_seconds histogram\n");
+    for stats in commands {
+        let mut cumulative = 0;
+        for bucket in &stats.buckets {
+            cumulative += bucket.count;
+            let le = bucket.le_ms.map_or("+Inf".to_string(), |ms| (ms as f64 / 1000.0).to_string());
+            let _ = writeln!(out, "brad_command_duration_seconds_bucket{{command=\"{}\",le=\"{}\"}} {}", stats.command, le, cumulative);
+        }
+        let _ = writeln!(out, "brad_command_duration_seconds_sum{{command=\"{}\"}} {}", stats.command, stats.total_ms / 1000.0);
+        let _ = writeln!(out, "brad_command_duration_seconds_count{{command=\"{}\"}} {}", stats.command, stats.calls);
+    }
+
+    out.push_str("# HELP brad_command_errors_total Backend command calls that returned an error\n");
+    out.push_str("# TYPE brad_command_errors_total counter\n");
+    for stats in commands {
+        let _ = writeln!(out, "brad_command_errors_total{{command=\"{}\"}} {}", stats.command, stats.errors);
+    }
+    out
+}
+
+/// Check a metrics port; privileged ports are refused
+pub fn validate_port(port: u16) -> Result<(), String> {
+    if port < 1024 {
+        return Err(format!("Metrics port must be 1024 or higher, got {}", port));
+    }
+    Ok(())
+}
+
+/// Metrics endpoint shared through Tauri state
+#[derive(Default)]
+pub struct MetricsServer {
+    listener: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+}
+
+impl MetricsServer {
+    /// Stop the current listener, then start again if the settings enable the endpoint.
+    /// Called at startup and whenever the metrics settings change.
+    pub fn apply_settings(&self, app_handle: &AppHandle, settings: &AppSettings) {
+        let mut listener = self.listener.lock().unwrap();
+        if let Some(task) = listener.take() {
+            task.abort();
+            info!("Metrics endpoint stopped");
+        }
+
+        if settings.metrics_enabled {
+            *listener = Some(tauri::async_runtime::spawn(serve(app_handle.clone(), settings.metrics_port)));
+        }
+    }
+}
+
+async fn serve(app_handle: AppHandle, port: u16) {
+    let listener = match TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!("Metrics endpoint could not listen on 127.0.0.1:{}: {}", port, e);
+            return;
+        }
+    };
+    info!("Metrics endpoint listening on http://127.0.0.1:{}/metrics", port);
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, addr)) => {
+                let app_handle = app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = handle_scrape(stream, &app_handle).await {
+                        debug!("Metrics request from {} failed: {}", addr, e);
+                    }
+                });
+            }
+            Err(e) => warn!("Metrics endpoint failed to accept a connection: {}", e),
+        }
+    }
+}
+
+async fn handle_scrape(mut stream: TcpStream, app_handle: &AppHandle) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST_BYTES {
+        let read = tokio::time::timeout(REQUEST_TIMEOUT, stream.read(&mut buffer))
+            .await
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "request timed out"))??;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    let request_line = String::from_utf8_lossy(&request).lines().next().unwrap_or_default().to_string();
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(path)) if path == "/metrics" || path.starts_with("/metrics?") => {
+            let mut body = render(&collect(app_handle).await);
+            body.push_str(&render_commands(&crate::command_metrics::snapshot()));
+            ("200 OK", body)
+        }
+        (Some("GET"), Some(_)) => ("404 Not Found", "Not found; metrics are served at /metrics\n".to_string()),
+        _ => ("405 Method Not Allowed", "Only GET is supported\n".to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_metrics::HistogramBucket;
+
+    #[test]
+    fn test_render_skips_missing_services_and_computes_lag() {
+        let metrics = NodeMetrics {
+            block_height: Some(90),
+            network_height: Some(100),
+            connected_peers: Some(3),
+            ..NodeMetrics::default()
+        };
+
+        let text = render(&metrics);
+        assert!(text.contains("# TYPE brad_block_height gauge\nbrad_block_height 90\n"));
+        assert!(text.contains("brad_sync_lag_blocks 10\n"));
+        assert!(text.contains("brad_peers_connected 3\n"));
+        assert!(!text.contains("brad_mempool_transactions"));
+    }
+
+    #[test]
+    fn test_command_buckets_are_cumulative() {
+        let stats = CommandStats {
+            command: "get_balance".to_string(),
+            calls: 3,
+            errors: 1,
+            error_rate: 1.0 / 3.0,
+            total_ms: 1_500.0,
+            mean_ms: 500.0,
+            max_ms: 1_400.0,
+            buckets: vec![
+                HistogramBucket { le_ms: Some(5), count: 2 },
+                HistogramBucket { le_ms: Some(1_000), count: 0 },
+                HistogramBucket { le_ms: None, count: 1 },
+            ],
+        };
+
+        let text = render_commands(&[stats]);
+        assert!(text.contains("brad_command_duration_seconds_bucket{command=\"get_balance\",le=\"0.005\"} 2\n"));
+        assert!(text.contains("brad_command_duration_seconds_bucket{command=\"get_balance\",le=\"1\"} 2\n"));
+        assert!(text.contains("brad_command_duration_seconds_bucket{command=\"get_balance\",le=\"+Inf\"} 3\n"));
+        assert!(text.contains("brad_command_duration_seconds_sum{command=\"get_balance\"} 1.5\n"));
+        assert!(text.contains("brad_command_errors_total{command=\"get_balance\"} 1\n"));
+    }
+}
@@ -4,21 +4,28 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Emitter, Manager};
+use tauri::AppHandle;
 use tokio::sync::{RwLock, Mutex};
 use sha2::{Sha256, Digest};
 
-use crate::blockchain_database::{AsyncBlockchainDatabase, Block, Transaction, TransactionOutput};
+use crate::blockchain_database::{AsyncBlockchainDatabase, Block, BlockStats, MinedBlock, Transaction, TransactionOutput};
+use crate::chain_params::MAINNET;
 use crate::errors::*;
+use crate::events::{self, EmitEvent};
+use crate::mining_worker::{HashWorker, MiningIntensity, SolveJob};
 
 // Bitcoin-compatible constants
-const MAX_BLOCK_SIZE: usize = 1_000_000; // 1MB like Bitcoin
+pub(crate) const MAX_BLOCK_SIZE: usize = 1_000_000; // 1MB like Bitcoin
 const MAX_BLOCK_WEIGHT: usize = 4_000_000; // 4MB weight units like Bitcoin
-const TARGET_BLOCK_TIME: u64 = 60; // 1 minute instead of Bitcoin's 10 minutes
-const DIFFICULTY_ADJUSTMENT_INTERVAL: u64 = 144; // Adjust every 144 blocks (2.4 hours at 1 min/block)
-const INITIAL_DIFFICULTY_TARGET: u64 = 0x00000000FFFF0000; // Simplified target that fits in u64
-const COINBASE_REWARD: u64 = 5000000000; // 50 BTC in satoshis (will halve every 210,000 blocks)
-const HALVING_INTERVAL: u64 = 210000; // Halve reward every 210,000 blocks
+const TARGET_BLOCK_TIME: u64 = MAINNET.target_block_time; // 1 minute instead of Bitcoin's 10 minutes
+const DIFFICULTY_ADJUSTMENT_INTERVAL: u64 = MAINNET.difficulty_adjustment_interval; // 2.4 hours at 1 min/block
+const INITIAL_DIFFICULTY_TARGET: u64 = MAINNET.initial_target; // Simplified target that fits in u64
+
+/// Blocks the network hash rate is estimated over unless asked otherwise, about two hours
+pub const DEFAULT_HASH_RATE_WINDOW: u64 = 120;
+
+/// Most blocks returned for one chart
+pub const MAX_CHART_BLOCKS: u64 = 10_000;
 
 /// Mining status for a wallet
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,12 +41,112 @@ pub struct MiningStatus {
     pub network_hash_rate: f64,
 }
 
+/// A candidate block for an external miner. The miner searches nonces (and may move the
+/// timestamp forward) until the header hash meets `target`, then hands the block to `submit_block`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockTemplate {
+    pub height: u64,
+    pub previous_hash: String,
+    pub timestamp: u64,
+    pub difficulty: u64,
+    /// Highest acceptable value of the first 8 bytes of the header hash, as hex
+    pub target: String,
+    /// Target in compact form, as it goes into the header
+    pub bits: u32,
+    /// Coinbase first, then mempool transactions
+    pub transactions: Vec<Transaction>,
+    pub merkle_root: String,
+    pub coinbase_value: u64,
+    /// Largest block accepted, in serialized bytes
+    pub size_limit: usize,
+}
+
+impl BlockTemplate {
+    /// The block this template becomes with `nonce`, without checking its proof of work
+    pub fn into_block(self, nonce: u64) -> Block {
+        let target = u64::from_str_radix(&self.target, 16).unwrap_or(INITIAL_DIFFICULTY_TARGET);
+        let hash = header_hash(self.height, &self.previous_hash, &self.merkle_root, self.timestamp, target, nonce);
+        Block {
+            height: self.height,
+            hash,
+            previous_hash: self.previous_hash,
+            timestamp: self.timestamp,
+            nonce,
+            difficulty: self.difficulty,
+            transactions: self.transactions,
+            merkle_root: self.merkle_root,
+        }
+    }
+}
+
+/// Network hash rate estimated over a run of blocks
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkHashRate {
+    pub from_height: u64,
+    pub to_height: u64,
+    /// Hashes per second
+    pub hash_rate: f64,
+    /// Seconds between blocks over the window
+    pub average_solve_time: f64,
+}
+
+impl NetworkHashRate {
+    /// Expected hashes to find the blocks after the first of `stats`, divided by the time they took
+    pub fn estimate(stats: &[BlockStats]) -> Self {
+        let (Some(first), Some(last)) = (stats.first(), stats.last()) else {
+            return Self { from_height: 0, to_height: 0, hash_rate: 0.0, average_solve_time: 0.0 };
+        };
+        let span = last.timestamp.saturating_sub(first.timestamp) as f64;
+        let work: f64 = stats[1..].iter().map(|block| expected_hashes(block.difficulty)).sum();
+        let blocks = (stats.len() - 1) as f64;
+        Self {
+            from_height: first.height,
+            to_height: last.height,
+            hash_rate: if span > 0.0 { work / span } else { 0.0 },
+            average_solve_time: if blocks > 0.0 { span / blocks } else { 0.0 },
+        }
+    }
+}
+
+/// Hashes needed on average to find a block of `difficulty`, whose hash's first 8 bytes must not
+/// exceed its target
+fn expected_hashes(difficulty: u64) -> f64 {
+    2f64.powi(64) / (difficulty_to_target(difficulty) as f64 + 1.0)
+}
+
+/// Work a block of `difficulty` adds to its chain: the hashes needed on average to find it
+pub fn block_work(difficulty: u64) -> u128 {
+    (1u128 << 64) / (difficulty_to_target(difficulty) as u128 + 1)
+}
+
+/// Mining income of a wallet over a period, separate from its regular receives
+#[derive(Debug, Clone, Serialize)]
+pub struct MiningRewards {
+    /// Newest first
+    pub blocks: Vec<MinedBlock>,
+    /// Rewards of the blocks still on the chain
+    pub total_reward: u64,
+    pub orphaned_blocks: usize,
+}
+
+impl MiningRewards {
+    pub fn from_blocks(blocks: Vec<MinedBlock>) -> Self {
+        Self {
+            total_reward: blocks.iter().filter(|block| !block.orphaned).map(|block| block.reward).sum(),
+            orphaned_blocks: blocks.iter().filter(|block| block.orphaned).count(),
+            blocks,
+        }
+    }
+}
+
 /// Mining service for individual wallet mining
 pub struct MiningService {
     blockchain_db: Arc<AsyncBlockchainDatabase>,
     active_miners: Arc<RwLock<HashMap<String, MiningStatus>>>,
     app_handle: Option<AppHandle>,
     target_block_time: Duration, // Target time between blocks
+    /// Read by every mining loop before each work slice
+    intensity: Arc<std::sync::RwLock<MiningIntensity>>,
 }
 
 impl MiningService {    /// Create new mining service
@@ -49,9 +156,16 @@ impl MiningService {    /// Create new mining service
             active_miners: Arc::new(RwLock::new(HashMap::new())),
             app_handle: None,
             target_block_time: Duration::from_secs(TARGET_BLOCK_TIME), // 1 minute target block time
+            intensity: Arc::new(std::sync::RwLock::new(MiningIntensity::default())),
         }
     }
 
+    /// Change how hard mining works the CPU; running miners pick it up from their next work slice
+    pub fn set_intensity(&self, intensity: MiningIntensity) {
+        info!("Mining intensity set to {}% duty cycle, low priority: {}", intensity.duty_cycle, intensity.low_priority);
+        *self.intensity.write().unwrap() = intensity;
+    }
+
     /// Initialize with app handle for event emission
     pub async fn initialize(&mut self, app_handle: AppHandle) -> AppResult<()> {
         self.app_handle = Some(app_handle);
@@ -100,21 +214,29 @@ impl MiningService {    /// Create new mining service
         // Start mining process in background
         let blockchain_db = self.blockchain_db.clone();
         let active_miners = self.active_miners.clone();
-        let app_handle = self.app_handle.clone();        tokio::spawn(async move {
+        let app_handle = self.app_handle.clone();
+        let intensity = self.intensity.clone();        tokio::spawn(async move {
             let active_miners_clone = active_miners.clone();
+            let app_handle_clone = app_handle.clone();
             if let Err(e) = Self::perform_mining(
                 wallet_id.clone(),
                 mining_address,
                 blockchain_db,
                 active_miners,
                 app_handle,
+                intensity,
             ).await {
                 error!("Mining failed for {}: {}", wallet_id, e);
                 
                 // Mark mining as stopped
-                let mut miners = active_miners_clone.write().await;
-                if let Some(status) = miners.get_mut(&wallet_id) {
-                    status.is_mining = false;
+                {
+                    let mut miners = active_miners_clone.write().await;
+                    if let Some(status) = miners.get_mut(&wallet_id) {
+                        status.is_mining = false;
+                    }
+                }
+                if let Some(ref app) = app_handle_clone {
+                    crate::tray::set_mining_status(app, &wallet_id, false);
                 }
             }
         });
@@ -126,14 +248,25 @@ impl MiningService {    /// Create new mining service
     pub async fn stop_mining(&self, wallet_id: &str) -> AppResult<()> {
         info!("Stopping mining for wallet: {}", wallet_id);
 
-        let mut active_miners = self.active_miners.write().await;
-        if let Some(status) = active_miners.get_mut(wallet_id) {
-            status.is_mining = false;
+        {
+            let mut active_miners = self.active_miners.write().await;
+            if let Some(status) = active_miners.get_mut(wallet_id) {
+                status.is_mining = false;
+            }
         }
 
+        self.emit_mining_status(wallet_id).await;
         Ok(())
     }
 
+    /// Forget a wallet's mining state, which also ends its mining loop
+    pub async fn remove_wallet(&self, wallet_id: &str) -> Option<MiningStatus> {
+        debug!("Removing mining state for wallet: {}", wallet_id);
+
+        let mut active_miners = self.active_miners.write().await;
+        active_miners.remove(wallet_id)
+    }
+
     /// Get mining status for a wallet
     pub async fn get_mining_status(&self, wallet_id: &str) -> Option<MiningStatus> {
         let active_miners = self.active_miners.read().await;
@@ -146,6 +279,70 @@ impl MiningService {    /// Create new mining service
         active_miners.clone()
     }
 
+    /// Build a candidate block on the current tip paying the reward to `mining_address`
+    pub async fn block_template(&self, mining_address: &str) -> AppResult<BlockTemplate> {
+        let current_height = self.blockchain_db.get_block_height().await
+            .map_err(|e| AppError::Generic(format!("Failed to get block height: {}", e)))?;
+        let previous_hash = self.blockchain_db.get_block_by_height(current_height).await
+            .map_err(|e| AppError::Generic(format!("Failed to get previous block: {}", e)))?
+            .map(|block| block.hash)
+            .unwrap_or_else(|| "0".repeat(64));
+        let (difficulty, target) = self.calculate_current_difficulty().await?;
+
+        let height = current_height + 1;
+        let timestamp = crate::network_time::adjusted_time();
+        let mut transactions = vec![coinbase_transaction(height, mining_address, timestamp)];
+        if let Some(mempool) = self.app_handle.as_ref().and_then(|app| crate::services::get::<crate::mempool_service::AsyncMempoolService>(app)) {
+            transactions.extend(mempool.get_transactions_for_mining(100, MAX_BLOCK_SIZE - 1000).await);
+        }
+
+        Ok(BlockTemplate {
+            height,
+            previous_hash,
+            timestamp,
+            difficulty,
+            target: format!("{:016x}", target),
+            bits: target_to_bits(target),
+            merkle_root: calculate_merkle_root(&transactions),
+            transactions,
+            coinbase_value: crate::consensus::block_subsidy(height),
+            size_limit: MAX_BLOCK_SIZE,
+        })
+    }
+
+    /// Check the proof of work of a block mined outside the node against the current
+    /// difficulty, then validate, store and broadcast it like a block from a peer
+    pub async fn submit_block(&self, block: Block) -> AppResult<String> {
+        let app_handle = self.app_handle.as_ref()
+            .ok_or_else(|| AppError::Generic("Mining service is not initialized".to_string()))?;
+        let (difficulty, target) = self.calculate_current_difficulty().await?;
+        check_proof_of_work(&block, difficulty, target).map_err(AppError::Generic)?;
+        let block_size = serde_json::to_string(&block).map(|json| json.len()).unwrap_or_default();
+        if block_size > MAX_BLOCK_SIZE {
+            return Err(AppError::Generic(format!("Block exceeds maximum size limit: {} > {}", block_size, MAX_BLOCK_SIZE)));
+        }
+
+        crate::network_service::NetworkService::validate_submitted_block(&block, &self.blockchain_db, app_handle).await?;
+        self.blockchain_db.store_block(&block).await
+            .map_err(|e| AppError::Generic(format!("Failed to store submitted block: {}", e)))?;
+        if let Err(e) = self.blockchain_db.record_mined_block(&block).await {
+            warn!("Failed to record submitted block {} as mined: {}", block.hash, e);
+        }
+        if let Err(e) = self.blockchain_db.flush().await {
+            warn!("Failed to flush submitted block {}: {}", block.hash, e);
+        }
+        crate::network_time::record_block_time(block.timestamp);
+
+        let hash = block.hash.clone();
+        if let Some(network_service) = crate::services::get::<crate::network_service::AsyncNetworkService>(app_handle) {
+            if let Err(e) = network_service.broadcast_block(Arc::new(block)).await {
+                warn!("Failed to broadcast submitted block {}: {}", hash, e);
+            }
+        }
+        info!("Accepted externally mined block {}", hash);
+        Ok(hash)
+    }
+
     /// Calculate current mining difficulty using Bitcoin-style algorithm
     async fn calculate_current_difficulty(&self) -> AppResult<(u64, u64)> {
         let current_height = self.blockchain_db.get_block_height().await
@@ -209,52 +406,13 @@ impl MiningService {    /// Create new mining service
         Ok((new_difficulty, new_target))
     }
 
-    /// Calculate mining reward based on block height (with halving)
-    fn calculate_block_reward(height: u64) -> u64 {
-        let halvings = height / HALVING_INTERVAL;
-        if halvings >= 64 {
-            0 // After 64 halvings, reward becomes 0
-        } else {
-            COINBASE_REWARD >> halvings
-        }
-    }
-
-    /// Calculate network hash rate estimate
-    async fn estimate_network_hash_rate(&self) -> AppResult<f64> {
+    /// Estimate the network hash rate from the difficulty and timing of the last `window` blocks
+    pub async fn network_hash_rate(&self, window: u64) -> AppResult<NetworkHashRate> {
         let current_height = self.blockchain_db.get_block_height().await
             .map_err(|e| AppError::Generic(format!("Failed to get block height: {}", e)))?;
-
-        if current_height < 10 {
-            return Ok(0.0);
-        }
-
-        // Look at last 10 blocks to estimate hash rate
-        let mut total_work = 0.0;
-        let mut time_span = 0u64;
-
-        if let Some(latest_block) = self.blockchain_db.get_block_by_height(current_height).await
-            .map_err(|e| AppError::Generic(format!("Failed to get latest block: {}", e)))? {
-            
-            if let Some(older_block) = self.blockchain_db.get_block_by_height(current_height.saturating_sub(10)).await
-                .map_err(|e| AppError::Generic(format!("Failed to get older block: {}", e)))? {
-                
-                time_span = latest_block.timestamp - older_block.timestamp;
-                
-                // Estimate work done (simplified)
-                for height in (current_height.saturating_sub(10))..=current_height {
-                    if let Some(block) = self.blockchain_db.get_block_by_height(height).await
-                        .map_err(|e| AppError::Generic(format!("Failed to get block: {}", e)))? {
-                        total_work += block.difficulty as f64;
-                    }
-                }
-            }
-        }
-
-        if time_span > 0 {
-            Ok(total_work / time_span as f64)
-        } else {
-            Ok(0.0)
-        }
+        let stats = self.blockchain_db.get_block_stats(current_height.saturating_sub(window), current_height).await
+            .map_err(|e| AppError::Generic(format!("Failed to get block stats: {}", e)))?;
+        Ok(NetworkHashRate::estimate(&stats))
     }
 
     /// Perform the actual mining
@@ -264,11 +422,14 @@ impl MiningService {    /// Create new mining service
         blockchain_db: Arc<AsyncBlockchainDatabase>,
         active_miners: Arc<RwLock<HashMap<String, MiningStatus>>>,
         app_handle: Option<AppHandle>,
+        intensity: Arc<std::sync::RwLock<MiningIntensity>>,
     ) -> AppResult<()> {
         info!("Starting mining process for wallet: {}", wallet_id);
 
         let mut hash_count = 0u64;
         let mut last_hash_rate_update = std::time::Instant::now();
+        let mut worker = HashWorker::spawn(&wallet_id, intensity.read().unwrap().low_priority)
+            .map_err(|e| AppError::Generic(format!("Failed to start mining thread: {}", e)))?;
 
         loop {
             // Check if mining should continue
@@ -284,8 +445,23 @@ impl MiningService {    /// Create new mining service
                 }
             }
 
+            let current_intensity = *intensity.read().unwrap();
+            if current_intensity.low_priority != worker.low_priority() {
+                // The old thread exits once it is dropped
+                worker = HashWorker::spawn(&wallet_id, current_intensity.low_priority)
+                    .map_err(|e| AppError::Generic(format!("Failed to start mining thread: {}", e)))?;
+            }
+
             // Try to mine a block
-            if let Ok(true) = Self::try_mine_block_with_app_handle(&wallet_id, &mining_address, &blockchain_db, &active_miners, &app_handle).await {
+            let slice_started = std::time::Instant::now();
+            let (mined, hashes) = Self::try_mine_block_with_app_handle(&wallet_id, &mining_address, &blockchain_db, &active_miners, &app_handle, &worker)
+                .await
+                .unwrap_or_else(|e| {
+                    debug!("Mining attempt for {} failed: {}", wallet_id, e);
+                    (false, 0)
+                });
+            let busy = slice_started.elapsed();
+            if mined {
                 info!("Block successfully mined by wallet: {}", wallet_id);
                 
                 // Update blocks mined count
@@ -308,14 +484,14 @@ impl MiningService {    /// Create new mining service
                     };
                     
                     if let Some(status) = status {
-                        if let Err(e) = app.emit("mining-status", &status) {
+                        if let Err(e) = app.emit_event(events::MINING_STATUS, &status) {
                             warn!("Failed to emit mining status: {}", e);
                         }
                     }
                 }
             }
 
-            hash_count += 1;
+            hash_count += hashes;
 
             // Update hash rate every second
             if last_hash_rate_update.elapsed() >= Duration::from_secs(1) {
@@ -339,15 +515,24 @@ impl MiningService {    /// Create new mining service
                     };
                     
                     if let Some(status) = status {
-                        if let Err(e) = app.emit("mining-status", &status) {
+                        if let Err(e) = app.emit_event(events::MINING_STATUS, &status) {
                             warn!("Failed to emit mining status: {}", e);
                         }
                     }
                 }
             }
 
-            // Small delay to prevent overwhelming the CPU
-            tokio::time::sleep(Duration::from_millis(10)).await;
+            // Rest in proportion to the slice, so hashing takes the configured share of the time
+            let mut pause = current_intensity.pause_after(busy);
+            if hashes == 0 {
+                // Nothing was hashed, so do not spin on whatever failed
+                pause = pause.max(Duration::from_millis(10));
+            }
+            if pause.is_zero() {
+                tokio::task::yield_now().await;
+            } else {
+                tokio::time::sleep(pause).await;
+            }
         }
 
         Ok(())
@@ -360,18 +545,16 @@ impl MiningService {    /// Create new mining service
         blockchain_db: &Arc<AsyncBlockchainDatabase>,
         active_miners: &Arc<RwLock<HashMap<String, MiningStatus>>>,
         app_handle: &Option<AppHandle>,
-    ) -> AppResult<bool> {
+        worker: &HashWorker,
+    ) -> AppResult<(bool, u64)> {
         // Get current block height and last block hash
         let current_height = blockchain_db.get_block_height().await
             .map_err(|e| AppError::Generic(format!("Failed to get block height: {}", e)))?;
 
-        let previous_hash = if current_height > 0 {
-            let previous_block = blockchain_db.get_block_by_height(current_height).await
-                .map_err(|e| AppError::Generic(format!("Failed to get previous block: {}", e)))?;
-            previous_block.map(|block| block.hash).unwrap_or_else(|| "0".repeat(64))
-        } else {
-            "0".repeat(64)
-        };
+        // Height 0 is the genesis block, so the tip always exists once the database is initialized
+        let previous_block = blockchain_db.get_block_by_height(current_height).await
+            .map_err(|e| AppError::Generic(format!("Failed to get previous block: {}", e)))?;
+        let previous_hash = previous_block.map(|block| block.hash).unwrap_or_else(|| "0".repeat(64));
 
         // Get current difficulty and target
         let (difficulty, target) = {
@@ -389,28 +572,21 @@ impl MiningService {    /// Create new mining service
         };
 
         // Calculate mining reward with halving
-        let block_reward = Self::calculate_block_reward(current_height + 1);
+        let block_reward = crate::consensus::block_subsidy(current_height + 1);
 
         // Create coinbase transaction (mining reward)
-        let coinbase_tx = Transaction {
-            txid: format!("coinbase_{}", current_height + 1),
-            inputs: vec![],
-            outputs: vec![TransactionOutput {
-                value: block_reward,
-                script_pubkey: format!("OP_DUP OP_HASH160 {} OP_EQUALVERIFY OP_CHECKSIG", mining_address),
-                address: mining_address.to_string(),
-            }],
-            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)
-                .unwrap_or_default().as_secs(),
-            fee: 0,
-        };
+        let coinbase_tx = coinbase_transaction(
+            current_height + 1,
+            mining_address,
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        );
 
         // Get pending transactions from mempool if available
-        let mut transactions = vec![coinbase_tx.clone()];
+        let mut transactions = vec![coinbase_tx];
         
         // Try to get mempool transactions through app handle
         if let Some(app) = app_handle {
-            if let Some(mempool) = app.try_state::<crate::mempool_service::AsyncMempoolService>() {
+            if let Some(mempool) = crate::services::get::<crate::mempool_service::AsyncMempoolService>(app) {
                 let mempool_txs = mempool.get_transactions_for_mining(100, MAX_BLOCK_SIZE - 1000).await;
                 if !mempool_txs.is_empty() {
                     info!("Including {} transactions from mempool in block", mempool_txs.len());
@@ -423,79 +599,60 @@ impl MiningService {    /// Create new mining service
             }
         }
         
-        // Calculate merkle root
-        let merkle_root = calculate_merkle_root(&transactions);
-
         // Create block header for mining
         let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)
             .unwrap_or_default().as_secs();
 
-        // Start mining with random nonce
-        let mut nonce = rand::random::<u64>();
-
-        // Try a few nonces before yielding control (to prevent blocking)
-        for _ in 0..1000 {
-            // Create block header data
-            let block_header = create_block_header(
-                current_height + 1,
-                &previous_hash,
-                &merkle_root,
-                timestamp,
-                target_to_bits(target),
-                nonce,
-            );
-
-            // Calculate double SHA256 hash (Bitcoin-style)
-            let hash_bytes = double_sha256(&block_header);
-            let hash_hex = format_hash(&hash_bytes);
-
-            // Check if hash meets target (Bitcoin-style difficulty check)
-            if hash_meets_target(&hash_hex, target) {
-                // Block found! Create the complete block
-                let new_block = Block {
-                    height: current_height + 1,
-                    hash: hash_hex,
-                    previous_hash,
-                    timestamp,
-                    transactions,
-                    nonce,
-                    difficulty,
-                    merkle_root,
-                };
+        // Start mining with random nonce; the hashing thread reports back after one work slice
+        let job = SolveJob {
+            height: current_height + 1,
+            previous_hash,
+            transactions,
+            timestamp,
+            difficulty,
+            target,
+            start_nonce: rand::random::<u64>(),
+        };
+        let slice = worker.solve(job).await
+            .ok_or_else(|| AppError::Generic("Mining thread stopped".to_string()))?;
+        if let Some(new_block) = slice.block {
+            // Verify block size constraints
+            let block_json = serde_json::to_string(&new_block).unwrap_or_default();
+            if block_json.len() > MAX_BLOCK_SIZE {
+                warn!("Block exceeds maximum size limit: {} > {}", block_json.len(), MAX_BLOCK_SIZE);
+                return Ok((false, slice.hashes));
+            }
 
-                // Verify block size constraints
-                let block_json = serde_json::to_string(&new_block).unwrap_or_default();
-                if block_json.len() > MAX_BLOCK_SIZE {
-                    warn!("Block exceeds maximum size limit: {} > {}", block_json.len(), MAX_BLOCK_SIZE);
-                    return Ok(false);
-                }
+            // Store the mined block
+            blockchain_db.store_block(&new_block).await
+                .map_err(|e| AppError::Generic(format!("Failed to store mined block: {}", e)))?;
+            if let Err(e) = blockchain_db.record_mined_block(&new_block).await {
+                warn!("Failed to record mined block {}: {}", new_block.hash, e);
+            }
+            // Commit right away rather than with the next batch, so a crash cannot lose the reward
+            if let Err(e) = blockchain_db.flush().await {
+                warn!("Failed to flush mined block {}: {}", new_block.hash, e);
+            }
 
-                // Store the mined block
-                blockchain_db.store_block(&new_block).await
-                    .map_err(|e| AppError::Generic(format!("Failed to store mined block: {}", e)))?;
-
-                // Submit block to network
-                if let Some(app_handle) = app_handle {
-                    if let Some(network_service) = app_handle.try_state::<crate::network_service::AsyncNetworkService>() {
-                        if let Err(e) = network_service.announce_new_block(new_block.hash.clone()).await {
-                            warn!("Failed to announce mined block to network: {}", e);
-                        } else {
-                            info!("Successfully announced mined block {} to network", new_block.hash);
-                        }
+            // Submit block to network
+            if let Some(app_handle) = app_handle {
+                if let Some(network_service) = crate::services::get::<crate::network_service::AsyncNetworkService>(app_handle) {
+                    if let Err(e) = network_service.announce_new_block(new_block.hash.clone()).await {
+                        warn!("Failed to announce mined block to network: {}", e);
+                    } else {
+                        info!("Successfully announced mined block {} to network", new_block.hash);
                     }
                 }
-
-                info!(
-                    "Block {} mined successfully! Hash: {}, Difficulty: {}, Reward: {} satoshis",
-                    new_block.height, new_block.hash, difficulty, block_reward
-                );
-                return Ok(true);
             }
 
-            nonce = nonce.wrapping_add(1);
+            info!(
+                "Block {} mined successfully! Hash: {}, Difficulty: {}, Reward: {} satoshis",
+                new_block.height, new_block.hash, difficulty, block_reward
+            );
+            return Ok((true, slice.hashes));
         }
 
-        Ok(false)
+        Ok((false, slice.hashes))
     }
 
     /// Emit mining status event
@@ -503,9 +660,10 @@ impl MiningService {    /// Create new mining service
         if let Some(ref app) = self.app_handle {
             let status = self.get_mining_status(wallet_id).await;
             if let Some(status) = status {
-                if let Err(e) = app.emit("mining-status", &status) {
+                if let Err(e) = app.emit_event(events::MINING_STATUS, &status) {
                     warn!("Failed to emit mining status: {}", e);
                 }
+                crate::tray::set_mining_status(app, &status.wallet_id, status.is_mining);
             }
         }
     }
@@ -542,6 +700,12 @@ impl AsyncMiningService {
         service.stop_mining(wallet_id).await
     }
 
+    /// Forget a wallet's mining state, which also ends its mining loop
+    pub async fn remove_wallet(&self, wallet_id: &str) -> Option<MiningStatus> {
+        let service = self.inner.lock().await;
+        service.remove_wallet(wallet_id).await
+    }
+
     /// Get mining status for a wallet
     pub async fn get_mining_status(&self, wallet_id: &str) -> Option<MiningStatus> {
         let service = self.inner.lock().await;
@@ -553,6 +717,30 @@ impl AsyncMiningService {
         let service = self.inner.lock().await;
         service.get_all_mining_statuses().await
     }
+
+    /// Estimate the network hash rate over the last `window` blocks
+    pub async fn network_hash_rate(&self, window: u64) -> AppResult<NetworkHashRate> {
+        let service = self.inner.lock().await;
+        service.network_hash_rate(window).await
+    }
+
+    /// Change how hard mining works the CPU, including for wallets already mining
+    pub async fn set_intensity(&self, intensity: MiningIntensity) {
+        let service = self.inner.lock().await;
+        service.set_intensity(intensity)
+    }
+
+    /// Build a candidate block for an external miner
+    pub async fn block_template(&self, mining_address: &str) -> AppResult<BlockTemplate> {
+        let service = self.inner.lock().await;
+        service.block_template(mining_address).await
+    }
+
+    /// Accept a block mined outside the node
+    pub async fn submit_block(&self, block: Block) -> AppResult<String> {
+        let service = self.inner.lock().await;
+        service.submit_block(block).await
+    }
 }
 
 // Bitcoin-style difficulty conversion functions
@@ -619,7 +807,7 @@ fn target_to_bits(target: u64) -> u32 {
 }
 
 /// Check if a hash meets the target difficulty
-fn hash_meets_target(hash: &str, target: u64) -> bool {
+pub(crate) fn hash_meets_target(hash: &str, target: u64) -> bool {
     // Convert hash to numeric value for comparison
     if let Ok(hash_value) = u64::from_str_radix(&hash[0..16], 16) {
         hash_value <= target
@@ -656,8 +844,94 @@ fn create_block_header(
     header_data.into_bytes()
 }
 
+/// Coinbase transaction paying the block reward for `height` to `mining_address`
+pub(crate) fn coinbase_transaction(height: u64, mining_address: &str, timestamp: u64) -> Transaction {
+    Transaction {
+        txid: format!("coinbase_{}", height),
+        inputs: vec![],
+        outputs: vec![TransactionOutput {
+            value: crate::consensus::block_subsidy(height),
+            script_pubkey: format!("OP_DUP OP_HASH160 {} OP_EQUALVERIFY OP_CHECKSIG", mining_address),
+            address: mining_address.to_string(),
+        }],
+        timestamp,
+        fee: 0,
+    }
+}
+
+/// Search `attempts` nonces from `start_nonce` for a block on `previous_hash` whose hash meets
+/// `target`. Returns the complete block if one is found.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn solve_block(
+    height: u64,
+    previous_hash: &str,
+    transactions: Vec<Transaction>,
+    timestamp: u64,
+    difficulty: u64,
+    target: u64,
+    start_nonce: u64,
+    attempts: u64,
+) -> Option<Block> {
+    let merkle_root = calculate_merkle_root(&transactions);
+
+    let mut nonce = start_nonce;
+    for _ in 0..attempts {
+        let hash_hex = header_hash(height, previous_hash, &merkle_root, timestamp, target, nonce);
+
+        // Check if hash meets target (Bitcoin-style difficulty check)
+        if hash_meets_target(&hash_hex, target) {
+            return Some(Block {
+                height,
+                hash: hash_hex,
+                previous_hash: previous_hash.to_string(),
+                timestamp,
+                transactions,
+                nonce,
+                difficulty,
+                merkle_root,
+            });
+        }
+
+        nonce = nonce.wrapping_add(1);
+    }
+
+    None
+}
+
+/// Double SHA256 (Bitcoin-style) hash of a block header, as hex
+pub(crate) fn header_hash(
+    height: u64,
+    previous_hash: &str,
+    merkle_root: &str,
+    timestamp: u64,
+    target: u64,
+    nonce: u64,
+) -> String {
+    let block_header = create_block_header(height, previous_hash, merkle_root, timestamp, target_to_bits(target), nonce);
+    format_hash(&double_sha256(&block_header))
+}
+
+/// Check that a block's merkle root and hash are what its contents give and that the hash meets
+/// `target`, the target of the expected `difficulty`
+pub(crate) fn check_proof_of_work(block: &Block, difficulty: u64, target: u64) -> Result<(), String> {
+    if block.difficulty != difficulty {
+        return Err(format!("Block difficulty {} is not the expected {}", block.difficulty, difficulty));
+    }
+    if block.merkle_root != calculate_merkle_root(&block.transactions) {
+        return Err("Block merkle root does not match its transactions".to_string());
+    }
+    let hash = header_hash(block.height, &block.previous_hash, &block.merkle_root, block.timestamp, target, block.nonce);
+    if hash != block.hash {
+        return Err("Block hash does not match its header".to_string());
+    }
+    if !hash_meets_target(&hash, target) {
+        return Err("Block hash does not meet the target".to_string());
+    }
+    Ok(())
+}
+
 /// Calculate merkle root from transactions (simplified implementation)
-fn calculate_merkle_root(transactions: &[Transaction]) -> String {
+pub(crate) fn calculate_merkle_root(transactions: &[Transaction]) -> String {
     if transactions.is_empty() {
         return "0".repeat(64);
     }
@@ -676,3 +950,49 @@ fn calculate_merkle_root(transactions: &[Transaction]) -> String {
 fn format_hash(hash: &[u8]) -> String {
     hash.iter().map(|b| format!("{:02x}", b)).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_block_needs_valid_proof_of_work() {
+        let transactions = vec![coinbase_transaction(1, "bc1qminer", 1_700_000_000)];
+        let template = BlockTemplate {
+            height: 1,
+            previous_hash: "0".repeat(64),
+            timestamp: 1_700_000_000,
+            difficulty: 1,
+            target: format!("{:016x}", u64::MAX),
+            bits: target_to_bits(u64::MAX),
+            merkle_root: calculate_merkle_root(&transactions),
+            transactions,
+            coinbase_value: crate::consensus::block_subsidy(1),
+            size_limit: MAX_BLOCK_SIZE,
+        };
+        let block = template.into_block(42);
+        assert_eq!(check_proof_of_work(&block, 1, u64::MAX), Ok(()));
+        assert!(check_proof_of_work(&block, 2, u64::MAX).is_err());
+
+        let mut tampered = block.clone();
+        tampered.nonce += 1;
+        assert!(check_proof_of_work(&tampered, 1, u64::MAX).is_err());
+        let mut tampered = block;
+        tampered.transactions.push(coinbase_transaction(2, "bc1qminer", 1_700_000_000));
+        assert!(check_proof_of_work(&tampered, 1, u64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_hash_rate_is_work_over_time() {
+        let stats: Vec<BlockStats> = (0..3)
+            .map(|height| BlockStats { height, timestamp: 1_000 + height * 60, difficulty: 1, solve_time: 60, transaction_count: 1 })
+            .collect();
+        let estimate = NetworkHashRate::estimate(&stats);
+        assert_eq!((estimate.from_height, estimate.to_height), (0, 2));
+        assert_eq!(estimate.average_solve_time, 60.0);
+        let expected = 2.0 * expected_hashes(1) / 120.0;
+        assert!((estimate.hash_rate - expected).abs() <= expected * 1e-9);
+        assert_eq!(NetworkHashRate::estimate(&stats[..1]).hash_rate, 0.0);
+        assert_eq!(NetworkHashRate::estimate(&[]).to_height, 0);
+    }
+}
@@ -0,0 +1,169 @@
+//! Hashing threads and mining intensity.
+//!
+//! Each mining wallet hashes on its own OS thread, so the async runtime never runs proof of work.
+//! The mining loop hands the thread one work slice at a time and then pauses in proportion to
+//! the time the slice took, which keeps the CPU busy for the configured share of the time. The
+//! thread can also run at the lowest OS priority so the desktop stays responsive; an OS thread
+//! cannot raise its priority again without privileges, so turning that off starts a new thread.
+
+use crate::blockchain_database::{Block, Transaction};
+use crate::config::AppSettings;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// Lowest duty cycle accepted, in percent
+pub const MIN_DUTY_CYCLE: u8 = 10;
+
+/// Time a hashing thread spends on one slice before reporting back
+const WORK_SLICE: Duration = Duration::from_millis(100);
+
+/// Nonces tried between checks of the slice clock
+const NONCES_PER_CHECK: u64 = 1_000;
+
+/// How hard local mining works the CPU; adjustable while mining
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MiningIntensity {
+    /// Share of the time each hashing thread is busy, in percent
+    pub duty_cycle: u8,
+    /// Hash at the lowest OS thread priority
+    pub low_priority: bool,
+}
+
+impl Default for MiningIntensity {
+    fn default() -> Self {
+        Self { duty_cycle: 100, low_priority: true }
+    }
+}
+
+impl MiningIntensity {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self { duty_cycle: settings.mining_duty_cycle, low_priority: settings.mining_low_priority }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_DUTY_CYCLE..=100).contains(&self.duty_cycle) {
+            return Err(format!("Mining duty cycle must be between {} and 100 percent, got {}", MIN_DUTY_CYCLE, self.duty_cycle));
+        }
+        Ok(())
+    }
+
+    /// How long to rest after hashing for `busy`, so hashing takes `duty_cycle` percent of the time
+    pub fn pause_after(&self, busy: Duration) -> Duration {
+        let duty_cycle = u32::from(self.duty_cycle.clamp(MIN_DUTY_CYCLE, 100));
+        busy * (100 - duty_cycle) / duty_cycle
+    }
+}
+
+/// A block candidate to search nonces for
+pub struct SolveJob {
+    pub height: u64,
+    pub previous_hash: String,
+    pub transactions: Vec<Transaction>,
+    pub timestamp: u64,
+    pub difficulty: u64,
+    pub target: u64,
+    pub start_nonce: u64,
+}
+
+/// What one work slice produced
+pub struct SliceResult {
+    pub block: Option<Block>,
+    /// Nonces tried
+    pub hashes: u64,
+}
+
+impl SolveJob {
+    /// Search nonces until a block is found or `slice` has passed
+    fn run(self, slice: Duration) -> SliceResult {
+        let started = Instant::now();
+        let mut nonce = self.start_nonce;
+        let mut hashes = 0;
+        while started.elapsed() < slice {
+            let block = crate::mining_service::solve_block(
+                self.height,
+                &self.previous_hash,
+                self.transactions.clone(),
+                self.timestamp,
+                self.difficulty,
+                self.target,
+                nonce,
+                NONCES_PER_CHECK,
+            );
+            if let Some(block) = block {
+                hashes += block.nonce.wrapping_sub(nonce) + 1;
+                return SliceResult { block: Some(block), hashes };
+            }
+            hashes += NONCES_PER_CHECK;
+            nonce = nonce.wrapping_add(NONCES_PER_CHECK);
+        }
+        SliceResult { block: None, hashes }
+    }
+}
+
+/// A dedicated hashing thread; it exits when the worker is dropped
+pub struct HashWorker {
+    jobs: std::sync::mpsc::Sender<(SolveJob, oneshot::Sender<SliceResult>)>,
+    low_priority: bool,
+}
+
+impl HashWorker {
+    pub fn spawn(name: &str, low_priority: bool) -> std::io::Result<Self> {
+        let (jobs, queue) = std::sync::mpsc::channel::<(SolveJob, oneshot::Sender<SliceResult>)>();
+        std::thread::Builder::new().name(format!("miner-{}", name)).spawn(move || {
+            if low_priority {
+                lower_current_thread_priority();
+            }
+            while let Ok((job, reply)) = queue.recv() {
+                let _ = reply.send(job.run(WORK_SLICE));
+            }
+        })?;
+        Ok(Self { jobs, low_priority })
+    }
+
+    pub fn low_priority(&self) -> bool {
+        self.low_priority
+    }
+
+    /// Hash one work slice on the thread; None if the thread is gone
+    pub async fn solve(&self, job: SolveJob) -> Option<SliceResult> {
+        let (reply, result) = oneshot::channel();
+        self.jobs.send((job, reply)).ok()?;
+        result.await.ok()
+    }
+}
+
+/// Drop the calling thread to the lowest scheduling priority the OS offers without privileges
+fn lower_current_thread_priority() {
+    #[cfg(target_os = "linux")]
+    // On Linux the nice value is per thread, and 0 means the calling one
+    let lowered = unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, 19) == 0 };
+    #[cfg(target_os = "macos")]
+    let lowered = unsafe { libc::pthread_set_qos_class_self_np(libc::qos_class_t::QOS_CLASS_BACKGROUND, 0) == 0 };
+    #[cfg(windows)]
+    let lowered = unsafe {
+        use windows_sys::Win32::System::Threading::{GetCurrentThread, SetThreadPriority, THREAD_PRIORITY_IDLE};
+        SetThreadPriority(GetCurrentThread(), THREAD_PRIORITY_IDLE) != 0
+    };
+    #[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+    let lowered = false;
+
+    if !lowered {
+        log::warn!("Could not lower the priority of mining thread {:?}", std::thread::current().name());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_keeps_hashing_to_the_duty_cycle() {
+        let busy = Duration::from_millis(100);
+        assert_eq!(MiningIntensity { duty_cycle: 100, low_priority: false }.pause_after(busy), Duration::ZERO);
+        assert_eq!(MiningIntensity { duty_cycle: 50, low_priority: false }.pause_after(busy), busy);
+        assert_eq!(MiningIntensity { duty_cycle: 25, low_priority: false }.pause_after(busy), Duration::from_millis(300));
+        assert!(MiningIntensity { duty_cycle: 5, low_priority: true }.validate().is_err());
+        assert!(MiningIntensity::default().validate().is_ok());
+    }
+}
@@ -0,0 +1,104 @@
+//! Binary wire format of peer messages.
+//!
+//! A frame is the active network's magic bytes, the payload length as a little-endian u32 and
+//! the `NetworkMessage` encoded with bincode. Frames are encoded straight into one buffer and
+//! decoded straight from the read buffer, so a large block is not copied through an
+//! intermediate representation on its way in or out. Block messages carry `Arc<Block>`, so
+//! relaying one block to many peers shares a single copy until it is encoded.
+
+use crate::chain_params::ChainParams;
+use crate::errors::{AppError, AppResult};
+use crate::network_constants::MAX_MESSAGE_SIZE;
+use crate::network_service::NetworkMessage;
+
+/// Bytes before the payload: magic and payload length
+pub const FRAME_HEADER_LEN: usize = 8;
+
+fn config() -> bincode::config::Configuration {
+    bincode::config::standard()
+}
+
+/// Encode a message into a frame for the active network
+pub fn encode_frame(message: &NetworkMessage) -> AppResult<Vec<u8>> {
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + encoded_size(message));
+    frame.extend_from_slice(&ChainParams::active().magic);
+    frame.extend_from_slice(&[0; 4]);
+    bincode::serde::encode_into_std_write(message, &mut frame, config())
+        .map_err(|e| AppError::Network(format!("Failed to encode message: {}", e)))?;
+
+    let payload_len = frame.len() - FRAME_HEADER_LEN;
+    if payload_len > MAX_MESSAGE_SIZE {
+        return Err(AppError::Network(format!("Message of {} bytes is too large to send", payload_len)));
+    }
+    frame[4..FRAME_HEADER_LEN].copy_from_slice(&(payload_len as u32).to_le_bytes());
+    Ok(frame)
+}
+
+/// Decode the first frame in `buffer`. Returns the message and the bytes it used, or None if
+/// the frame is not complete yet. Fails on a foreign magic or an oversized frame, after which
+/// the stream cannot be trusted.
+pub fn decode_frame(buffer: &[u8]) -> AppResult<Option<(NetworkMessage, usize)>> {
+    if buffer.len() < FRAME_HEADER_LEN {
+        return Ok(None);
+    }
+    if buffer[..4] != ChainParams::active().magic {
+        return Err(AppError::Network("Message is for another network".to_string()));
+    }
+    let payload_len = u32::from_le_bytes(buffer[4..FRAME_HEADER_LEN].try_into().expect("4 bytes")) as usize;
+    if payload_len > MAX_MESSAGE_SIZE {
+        return Err(AppError::Network(format!("Peer sent a {} byte message", payload_len)));
+    }
+
+    let frame_len = FRAME_HEADER_LEN + payload_len;
+    let Some(payload) = buffer.get(FRAME_HEADER_LEN..frame_len) else {
+        return Ok(None);
+    };
+    let (message, used) = bincode::serde::decode_from_slice(payload, config())
+        .map_err(|e| AppError::Network(format!("Failed to decode message: {}", e)))?;
+    if used != payload_len {
+        return Err(AppError::Network("Message has trailing bytes".to_string()));
+    }
+    Ok(Some((message, frame_len)))
+}
+
+/// Size of a message's payload on the wire, without encoding it into a buffer
+pub fn encoded_size(message: &NetworkMessage) -> usize {
+    let mut writer = bincode::enc::write::SizeWriter::default();
+    match bincode::serde::encode_into_writer(message, &mut writer, config()) {
+        Ok(()) => writer.bytes_written,
+        Err(_) => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain_database::Block;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_frames_round_trip_and_wait_for_more_bytes() {
+        let block = Arc::new(Block {
+            height: 7,
+            hash: "hash7".to_string(),
+            previous_hash: "hash6".to_string(),
+            timestamp: 1,
+            nonce: 2,
+            difficulty: 3,
+            transactions: Vec::new(),
+            merkle_root: String::new(),
+        });
+        let mut stream = encode_frame(&NetworkMessage::NewBlock { block }).unwrap();
+        stream.extend(encode_frame(&NetworkMessage::GetHeight).unwrap());
+
+        assert!(decode_frame(&stream[..stream.len() / 3]).unwrap().is_none());
+        let (first, used) = decode_frame(&stream).unwrap().unwrap();
+        assert!(matches!(first, NetworkMessage::NewBlock { block } if block.hash == "hash7"));
+        let (second, rest) = decode_frame(&stream[used..]).unwrap().unwrap();
+        assert!(matches!(second, NetworkMessage::GetHeight));
+        assert_eq!(used + rest, stream.len());
+
+        stream[0] ^= 0xff;
+        assert!(decode_frame(&stream).is_err());
+    }
+}
@@ -1,12 +1,12 @@
 //! Network constants and seed nodes for BradCoin
 //! Independent B-rad-coin network - does not use Bitcoin infrastructure
 
-use crate::network_service::PeerAddress;
+use crate::network_service::{AddressFamily, PeerAddress};
 use std::net::{IpAddr, Ipv4Addr};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// B-rad-coin default port
-pub const BRADCOIN_DEFAULT_PORT: u16 = 8333;
+pub const BRADCOIN_DEFAULT_PORT: u16 = crate::chain_params::MAINNET.default_port;
 
 /// B-rad-coin protocol version
 pub const BRADCOIN_PROTOCOL_VERSION: u32 = 10001;
@@ -32,12 +32,22 @@ pub const NODE_GETUTXO: u64 = 1 << 1;          // Can serve UTXO queries
 pub const NODE_BLOOM: u64 = 1 << 2;            // Supports bloom filters
 pub const NODE_WITNESS: u64 = 1 << 3;          // Supports witness transactions
 pub const NODE_XTHIN: u64 = 1 << 4;            // Supports Xtreme Thinblocks
+pub const NODE_COMPACT_FILTERS: u64 = 1 << 6;  // Serves compact block filters
+pub const NODE_COMPACT_BLOCKS: u64 = 1 << 7;   // Relays blocks as compact blocks
 pub const NODE_NETWORK_LIMITED: u64 = 1 << 10; // Pruned node, limited blocks
 
+/// Services this node offers in its Version message and address announcements
+pub const LOCAL_SERVICES: u64 = NODE_NETWORK | NODE_COMPACT_FILTERS;
+
 /// Protocol version constants
 pub const PROTOCOL_VERSION: u32 = 10001;       // B-rad-coin protocol version
 pub const MIN_PROTOCOL_VERSION: u32 = 10000;   // Minimum supported version
 
+/// First protocol versions with each optional feature; older peers do not get the feature even
+/// if they set its service bit
+pub const COMPACT_FILTERS_VERSION: u32 = 10001;
+pub const COMPACT_BLOCKS_VERSION: u32 = 10001;
+
 /// User agent for network identification
 pub const USER_AGENT: &str = "/BradCoin:0.2.5/";
 
@@ -48,6 +58,18 @@ pub const PEER_DISCOVERY_INTERVAL_SECS: u64 = 300; // 5 minutes
 pub const MAX_PEERS: usize = 125;
 pub const MAX_OUTBOUND_PEERS: usize = 8;
 pub const MAX_INBOUND_PEERS: usize = 117;
+pub const BLOCKS_ONLY_MAX_OUTBOUND_PEERS: usize = 4; // Caps on the configured limits in blocks-only mode
+pub const BLOCKS_ONLY_MAX_INBOUND_PEERS: usize = 8;
+pub const MAX_INBOUND_PER_IP: usize = 3;
+pub const MAX_INBOUND_PER_MINUTE: usize = 30;
+pub const SYNC_STALL_TIMEOUT_SECS: u64 = 60; // No block while behind before the download moves
+pub const BLOCK_REQUEST_TIMEOUT_SECS: u64 = 30; // A requested block not received by then is asked of another peer
+
+/// Block download window
+pub const BLOCK_DOWNLOAD_WINDOW: u64 = 1024; // Heights above the chain tip blocks are downloaded for
+pub const MAX_BLOCKS_IN_FLIGHT_PER_PEER: usize = 16; // For peers scoring FULL_IN_FLIGHT_SCORE or more
+pub const MIN_BLOCKS_IN_FLIGHT_PER_PEER: usize = 2;
+pub const FULL_IN_FLIGHT_SCORE: i32 = 300; // Lower scores get a proportional share of the maximum
 
 /// Message size limits
 pub const MAX_MESSAGE_SIZE: usize = 32 * 1024 * 1024; // 32MB
@@ -61,6 +83,7 @@ pub fn create_peer_address(ip: IpAddr, port: u16, services: u64) -> PeerAddress
         port,
         last_seen: current_timestamp(),
         services,
+        family: AddressFamily::of(ip),
     }
 }
 
@@ -2,19 +2,30 @@
 //! Handles peer discovery, block propagation, and network communication
 //! Implements B-rad-coin protocol for independent network connectivity
 
+use crate::address_relay::{self, AddrBudget};
 use crate::blockchain_database::{AsyncBlockchainDatabase, Block, Transaction, TransactionInput, TransactionOutput};
+use crate::chain_params::ChainParams;
+use crate::events::{self, EmitEvent};
 use crate::mempool_service::AsyncMempoolService;
 use crate::errors::*;
+use crate::inventory::KnownInventory;
 use crate::network_constants::*;
+use crate::network_trace::{NetworkTrace, TraceDirection, TRACE_DUMP_FILE};
+use crate::peer_policy::{self, ConnectionLimits, InboundGate, PeerFeatures};
+use crate::peer_queue::{self, Enqueued, PeerMessageReceiver, PeerMessageSender};
+use crate::reachability::{Reachability, ReachabilityStatus};
+use crate::relay_policy::TransactionSource;
+use crate::simulated_network::{self, SimulatedNetwork};
+use crate::sync_progress::DownloadPeer;
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tauri::{AppHandle, Emitter, Manager};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{Notify, RwLock};
 use tokio::time::{interval, timeout};
 
 /// Default ports for BradCoin network
@@ -22,8 +33,9 @@ pub const DEFAULT_P2P_PORT: u16 = 8333;
 pub const DEFAULT_RPC_PORT: u16 = 8334;
 
 /// Network message types (B-rad-coin protocol style)
+/// Block messages share their block, so relaying one to many peers does not copy it; see
+/// `network_codec` for how messages are framed on the wire.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type")]
 pub enum NetworkMessage {
     /// Ping message to check if peer is alive
     Ping {
@@ -54,11 +66,11 @@ pub enum NetworkMessage {
     },
     /// Response with block data
     Block {
-        block: Block,
+        block: Arc<Block>,
     },
     /// Announcement of new block
     NewBlock {
-        block: Block,
+        block: Arc<Block>,
     },
     /// Request for transaction
     GetTransaction {
@@ -88,7 +100,7 @@ pub enum NetworkMessage {
     },
     /// Response with multiple blocks
     Blocks {
-        blocks: Vec<Block>,
+        blocks: Vec<Arc<Block>>,
     },
     /// Version handshake message
     Version {
@@ -117,6 +129,17 @@ pub enum NetworkMessage {
     Tx {
         transaction: Transaction,
     },
+    /// Request compact filters of the blocks from `start_height` up to `stop_hash` (getcfilters)
+    GetCFilters {
+        start_height: u64,
+        stop_hash: String,
+    },
+    /// Compact filter of one block (cfilter)
+    CFilter {
+        block_hash: String,
+        height: u64,
+        filter: Vec<u8>,
+    },
 }
 
 /// Inventory item types (B-rad-coin protocol)
@@ -153,6 +176,24 @@ pub struct PeerAddress {
     pub port: u16,
     pub last_seen: u64,
     pub services: u64, // Bitfield for supported services
+    pub family: AddressFamily,
+}
+
+/// IP version of an address. IPv4-mapped IPv6 addresses count as IPv4.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum AddressFamily {
+    Ipv4,
+    Ipv6,
+}
+
+impl AddressFamily {
+    pub fn of(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(_) => AddressFamily::Ipv4,
+            IpAddr::V6(v6) if v6.to_ipv4_mapped().is_some() => AddressFamily::Ipv4,
+            IpAddr::V6(_) => AddressFamily::Ipv6,
+        }
+    }
 }
 
 /// Peer connection information with scoring
@@ -162,9 +203,72 @@ pub struct PeerConnection {
     pub connected_at: u64,
     pub last_ping: u64,
     pub version: Option<String>,
+    pub user_agent: Option<String>,
     pub height: Option<u64>,
     pub is_outbound: bool,
     pub score: PeerScore,
+    /// Transactions the peer already has, so they are not announced to it again
+    pub known_inventory: KnownInventory,
+    /// Addresses the peer may still add to the address store
+    pub addr_budget: AddrBudget,
+    /// Negotiated in the handshake; None until the peer's Version message arrives
+    pub features: Option<PeerFeatures>,
+}
+
+impl PeerConnection {
+    /// Score breakdown and connection facts for the network page
+    pub fn details(&self) -> PeerDetails {
+        PeerDetails {
+            address: SocketAddr::new(self.address.ip, self.address.port).to_string(),
+            direction: if self.is_outbound { "outbound" } else { "inbound" },
+            user_agent: self.user_agent.clone(),
+            protocol_version: self.version.clone(),
+            height: self.height,
+            connected_at: self.connected_at,
+            last_seen: self.last_ping,
+            ping_ms: self.score.average_ping,
+            blocks_received: self.score.blocks_received,
+            transactions_received: self.score.transactions_received,
+            invalid_messages: self.score.invalid_messages,
+            connection_failures: self.score.connection_failures,
+            sync_stalls: self.score.sync_stalls,
+            last_valid_block: self.score.last_valid_block,
+            uptime_percentage: self.score.uptime_percentage,
+            base_score: self.score.base_score,
+            total_score: self.score.calculate_total_score(),
+            features: self.features,
+        }
+    }
+}
+
+/// Why a peer scores the way it does, as returned by get_peer_details
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerDetails {
+    pub address: String,
+    /// "inbound" or "outbound"
+    pub direction: &'static str,
+    pub user_agent: Option<String>,
+    pub protocol_version: Option<String>,
+    pub height: Option<u64>,
+    pub connected_at: u64,
+    /// Last time a message was exchanged with the peer
+    pub last_seen: u64,
+    /// Moving average ping in milliseconds, 0 until measured
+    pub ping_ms: u64,
+    pub blocks_received: u32,
+    pub transactions_received: u32,
+    pub invalid_messages: u32,
+    pub connection_failures: u32,
+    /// Times block download stalled on this peer and was moved elsewhere
+    pub sync_stalls: u32,
+    pub last_valid_block: u64,
+    pub uptime_percentage: f32,
+    /// Score before traffic bonuses and penalties
+    pub base_score: i32,
+    /// Overall score (0-1000) used to choose and drop peers
+    pub total_score: i32,
+    /// Features negotiated in the handshake, once it happened
+    pub features: Option<PeerFeatures>,
 }
 
 /// Peer scoring system for connection quality assessment
@@ -175,6 +279,7 @@ pub struct PeerScore {
     pub transactions_received: u32,
     pub invalid_messages: u32,
     pub connection_failures: u32,
+    pub sync_stalls: u32,
     pub last_valid_block: u64,
     pub average_ping: u64,
     pub uptime_percentage: f32,
@@ -188,6 +293,7 @@ impl Default for PeerScore {
             transactions_received: 0,
             invalid_messages: 0,
             connection_failures: 0,
+            sync_stalls: 0,
             last_valid_block: 0,
             average_ping: 0,
             uptime_percentage: 100.0,
@@ -207,6 +313,7 @@ impl PeerScore {
         // Negative factors
         score -= (self.invalid_messages * 10) as i32;
         score -= (self.connection_failures * 5) as i32;
+        score -= (self.sync_stalls * 20) as i32;
         
         // Ping penalty (higher ping = lower score)
         if self.average_ping > 1000 {
@@ -240,6 +347,12 @@ impl PeerScore {
         self.invalid_messages += 1;
         self.base_score -= 5;
     }
+
+    /// Penalize for holding up block download
+    pub fn on_sync_stall(&mut self) {
+        self.sync_stalls += 1;
+        self.base_score -= 10;
+    }
     
     /// Update ping statistics
     pub fn update_ping(&mut self, ping_ms: u64) {
@@ -252,6 +365,19 @@ impl PeerScore {
     }
 }
 
+/// Payload of the sync-stalled event
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncStalled {
+    /// Peer no block arrived from
+    pub peer: String,
+    /// Peer the download moved to, if another could serve it
+    pub new_peer: Option<String>,
+    pub stalled_secs: u64,
+    pub blocks_reassigned: usize,
+    pub local_height: u64,
+    pub network_height: u64,
+}
+
 /// Network statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkStats {
@@ -269,37 +395,57 @@ pub struct NetworkStats {
 pub struct NetworkService {
     blockchain_db: Arc<AsyncBlockchainDatabase>,
     mempool: Option<AsyncMempoolService>,
-    listen_addr: SocketAddr,
+    listen_port: u16,
     peers: Arc<RwLock<HashMap<SocketAddr, PeerConnection>>>,
     known_addresses: Arc<RwLock<HashSet<PeerAddress>>>,
-    message_sender: Option<mpsc::UnboundedSender<(SocketAddr, NetworkMessage)>>,
+    message_sender: Option<PeerMessageSender>,
     stats: Arc<RwLock<NetworkStats>>,
     app_handle: Option<AppHandle>,
     is_running: Arc<RwLock<bool>>,
+    transport: Transport,
+    reachability: Arc<std::sync::Mutex<Reachability>>,
+    /// Wakes peer discovery before its next scheduled round
+    discovery_wake: Arc<Notify>,
+}
+
+/// Outbound side of the message path: traces messages and delivers them to simulated peers
+#[derive(Clone, Default)]
+struct Transport {
+    trace: Arc<NetworkTrace>,
+    simulated: Arc<SimulatedNetwork>,
+}
+
+impl Transport {
+    /// Record an outbound message and hand it to a simulated peer if `peer_addr` is one.
+    /// Returns false when the message is for a socket peer.
+    fn deliver(&self, peer_addr: SocketAddr, message: NetworkMessage) -> bool {
+        self.trace.record(peer_addr, TraceDirection::Outbound, &message);
+        self.simulated.deliver(peer_addr, message)
+    }
 }
 
 impl NetworkService {
     /// Create a new network service
     pub fn new(blockchain_db: Arc<AsyncBlockchainDatabase>, port: Option<u16>) -> Self {
-        let port = port.unwrap_or(DEFAULT_P2P_PORT);
-        let listen_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), port);
-
         Self {
             blockchain_db,
             mempool: None,
-            listen_addr,
+            listen_port: port.unwrap_or(DEFAULT_P2P_PORT),
             peers: Arc::new(RwLock::new(HashMap::new())),
             known_addresses: Arc::new(RwLock::new(HashSet::new())),
             message_sender: None,
             stats: Arc::new(RwLock::new(NetworkStats::default())),
             app_handle: None,
             is_running: Arc::new(RwLock::new(false)),
+            transport: Transport::default(),
+            reachability: Arc::default(),
+            discovery_wake: Arc::default(),
         }
     }
 
     /// Initialize the network service
     pub async fn initialize(&mut self, app_handle: AppHandle) -> AppResult<()> {
-        info!("Initializing BradCoin network service on {}", self.listen_addr);
+        info!("Initializing BradCoin network service on port {}", self.listen_port);
         self.app_handle = Some(app_handle);
 
         // Add some bootstrap nodes (in a real implementation, these would be well-known nodes)
@@ -326,14 +472,13 @@ impl NetworkService {
         drop(is_running);
 
         // Create message channel
-        let (tx, mut rx) = mpsc::unbounded_channel();
+        let (tx, rx) = peer_queue::channel();
         self.message_sender = Some(tx.clone());
 
-        // Start TCP listener
-        let listener = TcpListener::bind(&self.listen_addr).await
-            .map_err(|e| AppError::Network(format!("Failed to bind to {}: {}", self.listen_addr, e)))?;
-
-        info!("Network service listening on {}", self.listen_addr);
+        // Start TCP listeners
+        let bind_addresses = Self::bind_addresses(&self.app_handle);
+        let listeners = Self::bind_listeners(&bind_addresses, self.listen_port).await?;
+        let listen_families = Self::listen_families(&listeners);
 
         // Clone references for async tasks
         let peers = Arc::clone(&self.peers);
@@ -343,36 +488,49 @@ impl NetworkService {
         let app_handle = self.app_handle.clone();
         let is_running_clone = Arc::clone(&self.is_running);
 
-        // Start connection acceptor
-        let acceptor_peers = Arc::clone(&peers);
-        let acceptor_tx = tx.clone();
-        tokio::spawn(async move {
-            Self::accept_connections(listener, acceptor_peers, acceptor_tx).await;
-        });
+        // Start a connection acceptor per listener; they share the inbound limits
+        let gate = Arc::new(std::sync::Mutex::new(InboundGate::default()));
+        for listener in listeners {
+            let acceptor_peers = Arc::clone(&peers);
+            let acceptor_tx = tx.clone();
+            let acceptor_reachability = Arc::clone(&self.reachability);
+            let acceptor_gate = Arc::clone(&gate);
+            let acceptor_app_handle = app_handle.clone();
+            tokio::spawn(async move {
+                Self::accept_connections(listener, acceptor_peers, acceptor_tx, acceptor_reachability, acceptor_gate, acceptor_app_handle).await;
+            });
+        }
 
         // Start message handler
         let handler_peers = Arc::clone(&peers);
         let handler_blockchain = Arc::clone(&blockchain_db);
         let handler_stats = Arc::clone(&stats);
         let handler_mempool = self.mempool.clone();
+        let handler_transport = self.transport.clone();
+        let handler_reachability = Arc::clone(&self.reachability);
+        let handler_known = Arc::clone(&known_addresses);
+        let handler_app_handle = app_handle.clone();
         tokio::spawn(async move {
-            Self::handle_messages(rx, handler_peers, handler_blockchain, handler_stats, app_handle, handler_mempool).await;
+            Self::handle_messages(rx, handler_peers, handler_blockchain, handler_stats, handler_app_handle, handler_mempool, handler_transport, handler_reachability, handler_known).await;
         });
 
         // Start peer discovery
         let discovery_known = Arc::clone(&known_addresses);
         let discovery_peers = Arc::clone(&peers);
         let discovery_tx = tx.clone();
+        let discovery_app_handle = app_handle.clone();
+        let discovery_wake = Arc::clone(&self.discovery_wake);
         tokio::spawn(async move {
-            Self::peer_discovery_loop(discovery_known, discovery_peers, discovery_tx, is_running_clone).await;
+            Self::peer_discovery_loop(discovery_known, discovery_peers, discovery_tx, listen_families, is_running_clone, discovery_wake, discovery_app_handle).await;
         });
 
         // Start periodic tasks
         let periodic_peers = Arc::clone(&peers);
         let periodic_stats = Arc::clone(&stats);
         let periodic_blockchain = Arc::clone(&blockchain_db);
+        let periodic_transport = self.transport.clone();
         tokio::spawn(async move {
-            Self::periodic_tasks(periodic_peers, periodic_stats, periodic_blockchain).await;
+            Self::periodic_tasks(periodic_peers, periodic_stats, periodic_blockchain, periodic_transport, app_handle).await;
         });
 
         info!("BradCoin network service started successfully");
@@ -389,11 +547,29 @@ impl NetworkService {
         // Close all peer connections
         let mut peers = self.peers.write().await;
         peers.clear();
+        self.transport.simulated.clear();
         
         info!("BradCoin network service stopped");
         Ok(())
     }
 
+    /// Drop every socket peer, e.g. before the machine sleeps or after it wakes when the
+    /// connections have most likely timed out on the other end. Simulated peers live in-process
+    /// and are kept. Unless `reconnect` is false, discovery dials new peers straight away.
+    pub async fn reset_peers(&self, reconnect: bool) -> usize {
+        let dropped = {
+            let mut peers = self.peers.write().await;
+            let before = peers.len();
+            peers.retain(|addr, _| self.transport.simulated.is_simulated(addr));
+            before - peers.len()
+        };
+        info!("Dropped {} peer connections", dropped);
+        if reconnect {
+            self.discovery_wake.notify_one();
+        }
+        dropped
+    }
+
     /// Add bootstrap nodes for initial peer discovery
     async fn add_bootstrap_nodes(&self) {
         info!("Starting B-rad-coin peer discovery process...");
@@ -452,12 +628,25 @@ impl NetworkService {
     async fn accept_connections(
         listener: TcpListener,
         peers: Arc<RwLock<HashMap<SocketAddr, PeerConnection>>>,
-        message_sender: mpsc::UnboundedSender<(SocketAddr, NetworkMessage)>,
+        message_sender: PeerMessageSender,
+        reachability: Arc<std::sync::Mutex<Reachability>>,
+        gate: Arc<std::sync::Mutex<InboundGate>>,
+        app_handle: Option<AppHandle>,
     ) {
         loop {
             match listener.accept().await {
                 Ok((stream, addr)) => {
+                    let from_address = peers.read().await
+                        .iter()
+                        .filter(|(peer_addr, peer)| !peer.is_outbound && peer_addr.ip() == addr.ip())
+                        .count();
+                    if let Err(reason) = gate.lock().unwrap().admit(addr.ip(), from_address, Instant::now()) {
+                        info!("Refusing connection from {}: {}", addr, reason);
+                        continue;
+                    }
+
                     info!("Accepted connection from {}", addr);
+                    let limits = Self::connection_limits(&app_handle);
                     
                     let peer_connection = PeerConnection {
                         address: PeerAddress {
@@ -465,26 +654,53 @@ impl NetworkService {
                             port: addr.port(),
                             last_seen: Self::current_timestamp(),
                             services: 0,
+                            family: AddressFamily::of(addr.ip()),
                         },
                         connected_at: Self::current_timestamp(),
                         last_ping: 0,
                         version: None,
+                        user_agent: None,
                         height: None,
                         is_outbound: false,
                         score: PeerScore::default(),
+                        known_inventory: KnownInventory::default(),
+                        addr_budget: AddrBudget::default(),
+                        features: None,
                     };
 
-                    // Add peer to connections
+                    // Add peer to connections, making room by evicting the weakest inbound peer
                     {
                         let mut peers_guard = peers.write().await;
+                        let inbound = peers_guard.values().filter(|peer| !peer.is_outbound).count();
+                        if inbound >= limits.max_inbound {
+                            let evicted = if limits.max_inbound > 0 {
+                                peer_policy::select_inbound_eviction(peers_guard.iter())
+                            } else {
+                                None
+                            };
+                            match evicted {
+                                Some(evicted) => {
+                                    info!("Inbound limit of {} reached, evicting lowest-scoring peer {}", limits.max_inbound, evicted);
+                                    peers_guard.remove(&evicted);
+                                }
+                                None => {
+                                    info!("Inbound limit of {} reached, refusing {}", limits.max_inbound, addr);
+                                    continue;
+                                }
+                            }
+                        }
                         peers_guard.insert(addr, peer_connection);
                     }
+                    reachability.lock().unwrap().record_inbound(Self::current_timestamp());
 
                     // Handle this connection
                     let connection_peers = Arc::clone(&peers);
                     let connection_sender = message_sender.clone();
+                    let connection_gate = Arc::clone(&gate);
                     tokio::spawn(async move {
+                        let started = Instant::now();
                         Self::handle_peer_connection(stream, addr, connection_peers, connection_sender).await;
+                        connection_gate.lock().unwrap().record_disconnect(addr.ip(), started.elapsed(), Instant::now());
                     });
                 },
                 Err(e) => {
@@ -496,16 +712,25 @@ impl NetworkService {
     }
 
     /// Handle messages from peers
+    #[allow(clippy::too_many_arguments)]
     async fn handle_messages(
-        mut rx: mpsc::UnboundedReceiver<(SocketAddr, NetworkMessage)>,
+        mut rx: PeerMessageReceiver,
         peers: Arc<RwLock<HashMap<SocketAddr, PeerConnection>>>,
         blockchain_db: Arc<AsyncBlockchainDatabase>,
         stats: Arc<RwLock<NetworkStats>>,
         app_handle: Option<AppHandle>,
         mempool: Option<AsyncMempoolService>,
+        transport: Transport,
+        reachability: Arc<std::sync::Mutex<Reachability>>,
+        known_addresses: Arc<RwLock<HashSet<PeerAddress>>>,
     ) {
         while let Some((peer_addr, message)) = rx.recv().await {
-            match Self::process_message(peer_addr, message, &peers, &blockchain_db, &stats, &mempool).await {
+            for flooder in rx.take_disconnected() {
+                Self::disconnect_peer(flooder, &peers, &transport).await;
+            }
+
+            transport.trace.record(peer_addr, TraceDirection::Inbound, &message);
+            match Self::process_message(peer_addr, message, &peers, &blockchain_db, &stats, &mempool, &transport, &reachability, &known_addresses, &app_handle).await {
                 Ok(_) => {
                     debug!("Successfully processed message from {}", peer_addr);
                 },
@@ -517,12 +742,25 @@ impl NetworkService {
             // Emit network status update
             if let Some(ref app) = app_handle {
                 let stats_guard = stats.read().await;
-                if let Err(e) = app.emit("network-stats", &*stats_guard) {
+                if let Err(e) = app.emit_event(events::NETWORK_STATS, &*stats_guard) {
                     debug!("Failed to emit network stats: {}", e);
                 }
             }
         }
-    }    /// Process a network message
+    }    /// Drop a peer that flooded the message queue
+    async fn disconnect_peer(
+        peer_addr: SocketAddr,
+        peers: &Arc<RwLock<HashMap<SocketAddr, PeerConnection>>>,
+        transport: &Transport,
+    ) {
+        if peers.write().await.remove(&peer_addr).is_some() {
+            info!("Disconnected {} for exceeding its message quota", peer_addr);
+        }
+        transport.simulated.disconnect(peer_addr);
+    }
+
+    /// Process a network message
+    #[allow(clippy::too_many_arguments)]
     async fn process_message(
         peer_addr: SocketAddr,
         message: NetworkMessage,
@@ -530,6 +768,10 @@ impl NetworkService {
         blockchain_db: &Arc<AsyncBlockchainDatabase>,
         stats: &Arc<RwLock<NetworkStats>>,
         mempool: &Option<AsyncMempoolService>,
+        transport: &Transport,
+        reachability: &std::sync::Mutex<Reachability>,
+        known_addresses: &RwLock<HashSet<PeerAddress>>,
+        app_handle: &Option<AppHandle>,
     ) -> AppResult<()> {
         match message {
             NetworkMessage::Ping { timestamp, nonce } => {
@@ -539,7 +781,7 @@ impl NetworkService {
                     timestamp: Self::current_timestamp(),
                     nonce,
                 };
-                Self::send_message_to_peer(peer_addr, pong_message, peers).await?;
+                Self::send_message_to_peer(peer_addr, pong_message, peers, transport).await?;
             },
             NetworkMessage::Pong { timestamp, nonce } => {
                 debug!("Received pong from {} (nonce: {})", peer_addr, nonce);
@@ -555,12 +797,51 @@ impl NetworkService {
                     }
                 }
             },
+            NetworkMessage::GetAddr => {
+                let addresses = address_relay::sample_for_getaddr(&*known_addresses.read().await, Self::current_timestamp());
+                debug!("Sending {} addresses to {}", addresses.len(), peer_addr);
+                if !addresses.is_empty() {
+                    Self::send_message_to_peer(peer_addr, NetworkMessage::Addr { addresses }, peers, transport).await?;
+                }
+            },
+            NetworkMessage::Addr { addresses } => {
+                if addresses.len() > address_relay::MAX_ADDR_PER_MESSAGE {
+                    warn!("Peer {} sent {} addresses in one message", peer_addr, addresses.len());
+                    if let Some(peer) = peers.write().await.get_mut(&peer_addr) {
+                        peer.score.on_invalid_message();
+                    }
+                    return Ok(());
+                }
+
+                // Addresses beyond the peer's budget are ignored
+                let now = Self::current_timestamp();
+                let allowed = match peers.write().await.get_mut(&peer_addr) {
+                    Some(peer) => peer.addr_budget.take(addresses.len(), now),
+                    None => 0,
+                };
+                if allowed < addresses.len() {
+                    debug!("Ignoring {} of {} addresses from {}", addresses.len() - allowed, addresses.len(), peer_addr);
+                }
+                let accepted: Vec<PeerAddress> = addresses
+                    .into_iter()
+                    .take(allowed)
+                    .filter_map(|address| address_relay::sanitize(address, now))
+                    .collect();
+
+                let known_count = {
+                    let mut known = known_addresses.write().await;
+                    let added = address_relay::merge(&mut known, accepted);
+                    debug!("Learned {} new addresses from {}", added, peer_addr);
+                    known.len()
+                };
+                stats.write().await.total_known_peers = known_count as u32;
+            },
             NetworkMessage::GetHeight => {
                 debug!("Received height request from {}", peer_addr);
                 // Send height response
                 let height = blockchain_db.get_block_height().await.unwrap_or(0);
                 let height_message = NetworkMessage::Height { height };
-                Self::send_message_to_peer(peer_addr, height_message, peers).await?;
+                Self::send_message_to_peer(peer_addr, height_message, peers, transport).await?;
             },
             NetworkMessage::Height { height } => {
                 debug!("Received height {} from {}", height, peer_addr);
@@ -613,7 +894,7 @@ impl NetworkService {
                     
                     // Send headers response
                     let headers_message = NetworkMessage::Headers { headers };
-                    Self::send_message_to_peer(peer_addr, headers_message, peers).await?;
+                    Self::send_message_to_peer(peer_addr, headers_message, peers, transport).await?;
                 } else {
                     warn!("Could not find fork point for getblocks request from {}", peer_addr);
                 }
@@ -621,19 +902,35 @@ impl NetworkService {
             NetworkMessage::Inv { inventory } => {
                 info!("Received inventory of {} items from {}", inventory.len(), peer_addr);
                 
-                // Check which blocks/transactions we need and request them
+                // Check which blocks/transactions we need and request them; transactions are not
+                // fetched in blocks-only mode
+                let blocks_only = Self::blocks_only(app_handle);
                 let mut needed_blocks = Vec::new();
+                let mut needed_transactions = Vec::new();
                 
                 for item in inventory {
                     match item.item_type {
-                        InventoryType::Block => {
-                            // Check if we have this block
-                            if blockchain_db.get_block_by_hash(&item.hash).await.is_err() {
-                                needed_blocks.push(item);
-                            }
+                        // Request blocks we do not have yet
+                        InventoryType::Block
+                            if !matches!(blockchain_db.get_block_by_hash(&item.hash).await, Ok(Some(_))) =>
+                        {
+                            needed_blocks.push(item);
                         },
                         InventoryType::Transaction => {
-                            // TODO: Check transaction pool and request if needed
+                            // The peer has it, so never announce it back
+                            if let Some(peer) = peers.write().await.get_mut(&peer_addr) {
+                                peer.known_inventory.insert(&item.hash);
+                            }
+                            if blocks_only {
+                                continue;
+                            }
+                            let in_mempool = match mempool {
+                                Some(mempool_service) => mempool_service.get_transaction(&item.hash).await.is_some(),
+                                None => true, // Nowhere to put it
+                            };
+                            if !in_mempool {
+                                needed_transactions.push(item);
+                            }
                         },
                         _ => {}
                     }
@@ -644,9 +941,15 @@ impl NetworkService {
                     let getdata_message = NetworkMessage::GetData { 
                         inventory: needed_blocks.clone()
                     };
-                    Self::send_message_to_peer(peer_addr, getdata_message, peers).await?;
+                    Self::send_message_to_peer(peer_addr, getdata_message, peers, transport).await?;
                     info!("Requesting {} blocks from {}", needed_blocks.len(), peer_addr);
                 }
+
+                if !needed_transactions.is_empty() {
+                    debug!("Requesting {} transactions from {}", needed_transactions.len(), peer_addr);
+                    let getdata_message = NetworkMessage::GetData { inventory: needed_transactions };
+                    Self::send_message_to_peer(peer_addr, getdata_message, peers, transport).await?;
+                }
             },
             NetworkMessage::GetData { inventory } => {
                 info!("Received getdata request for {} items from {}", inventory.len(), peer_addr);
@@ -656,15 +959,27 @@ impl NetworkService {
                     match item.item_type {
                         InventoryType::Block => {
                             if let Ok(Some(block)) = blockchain_db.get_block_by_hash(&item.hash).await {
+                                info!("Sending block {} to {}", block.hash, peer_addr);
                                 // Send Block message back to peer
-                                let block_message = NetworkMessage::Block { block: block.clone() };
-                                Self::send_message_to_peer(peer_addr, block_message, peers).await?;
-                                info!("Sent block {} to {}", block.hash, peer_addr);
+                                let block_message = NetworkMessage::Block { block: Arc::new(block) };
+                                Self::send_message_to_peer(peer_addr, block_message, peers, transport).await?;
                             }
                         },
                         InventoryType::Transaction => {
-                            // TODO: Implement transaction retrieval from mempool
-                            debug!("Transaction request for {} - mempool not implemented yet", item.hash);
+                            let transaction = match mempool {
+                                Some(mempool_service) => mempool_service.get_transaction(&item.hash).await,
+                                None => None,
+                            };
+                            match transaction {
+                                Some(transaction) => {
+                                    if let Some(peer) = peers.write().await.get_mut(&peer_addr) {
+                                        peer.known_inventory.insert(&transaction.txid);
+                                    }
+                                    Self::send_message_to_peer(peer_addr, NetworkMessage::Tx { transaction }, peers, transport).await?;
+                                    debug!("Sent transaction {} to {}", item.hash, peer_addr);
+                                }
+                                None => debug!("Requested transaction {} is not in the mempool", item.hash),
+                            }
                         },
                         _ => {}
                     }
@@ -701,7 +1016,7 @@ impl NetworkService {
                     
                     // Send Headers message back to peer
                     let headers_message = NetworkMessage::Headers { headers: headers.clone() };
-                    Self::send_message_to_peer(peer_addr, headers_message, peers).await?;
+                    Self::send_message_to_peer(peer_addr, headers_message, peers, transport).await?;
                     info!("Sent {} headers to {}", headers.len(), peer_addr);
                 }
             },
@@ -709,23 +1024,34 @@ impl NetworkService {
                 info!("Received {} headers from {} - processing for headers-first sync", headers.len(), peer_addr);
                 
                 // Headers-first synchronization: validate headers and queue block downloads
+                let checkpoints = Self::active_checkpoints(app_handle);
                 let mut blocks_to_download = Vec::new();
                 let mut last_valid_height = blockchain_db.get_block_height().await.unwrap_or(0);
+
+                // Headers that continue the best header of an interrupted sync extend it
+                let progress = blockchain_db.sync_progress().await;
+                if let (Some(first), Some(best_hash)) = (headers.first(), progress.best_header_hash.as_deref()) {
+                    if first.previous_hash == best_hash && first.height == progress.best_header_height + 1 {
+                        last_valid_height = progress.best_header_height;
+                    }
+                }
                 
                 for header in headers {
+                    // A chain that contradicts a checkpoint is rejected before any block is fetched
+                    if let Err(e) = crate::consensus::check_checkpoint(checkpoints, header.height, &header.hash) {
+                        warn!("Rejecting headers from {}: {}", peer_addr, e);
+                        if let Some(peer) = peers.write().await.get_mut(&peer_addr) {
+                            peer.score.on_invalid_message();
+                        }
+                        break;
+                    }
+
                     // Validate header sequence and difficulty
                     if header.height == last_valid_height + 1 {
                         // Check if we already have this block
-                        if blockchain_db.get_block_by_hash(&header.hash).await.is_err() {
+                        if !matches!(blockchain_db.get_block_by_hash(&header.hash).await, Ok(Some(_))) {
                             // We need to download this block
-                            blocks_to_download.push(InventoryItem {
-                                item_type: InventoryType::Block,
-                                hash: header.hash.clone(),
-                            });
-                            
-                            // Store header for validation when block arrives
-                            // TODO: Add header to pending blocks queue
-                            debug!("Queued block {} (height {}) for download", header.hash, header.height);
+                            blocks_to_download.push((header.height, header.hash.clone()));
                         }
                         last_valid_height = header.height;
                     } else {
@@ -735,65 +1061,66 @@ impl NetworkService {
                     }
                 }
                 
-                // Request the blocks we need using GetData
-                if !blocks_to_download.is_empty() {
-                    let getdata_message = NetworkMessage::GetData { inventory: blocks_to_download.clone() };
-                    Self::send_message_to_peer(peer_addr, getdata_message, peers).await?;
-                    info!("Requested {} blocks from {} via GetData", blocks_to_download.len(), peer_addr);
+                // Record the download so it survives a restart, skipping blocks already asked for
+                let queued = blockchain_db
+                    .update_sync_progress(|progress| {
+                        blocks_to_download
+                            .into_iter()
+                            .filter(|(height, hash)| progress.queue_block(*height, hash))
+                            .inspect(|(height, hash)| debug!("Queued block {} (height {}) for download", hash, height))
+                            .count()
+                    })
+                    .await
+                    .map_err(|e| AppError::Generic(format!("Failed to record sync progress: {}", e)))?;
+                if queued > 0 {
+                    info!("Queued {} blocks from the headers of {}", queued, peer_addr);
                 }
                 
-                // Update peer with highest header we've seen
+                // Update peer with highest header we've seen, so the blocks can be asked of it
                 if let Some(peer) = peers.write().await.get_mut(&peer_addr) {
                     peer.height = Some(last_valid_height);
                 }
+
+                Self::schedule_block_downloads(peers, blockchain_db, transport).await;
             },
             NetworkMessage::NewBlock { block } => {
                 info!("Received new block {} (height: {}) from {}", block.hash, block.height, peer_addr);
-                
-                // Validate block before storing
-                if let Err(e) = Self::validate_block(&block, blockchain_db).await {
-                    warn!("Received invalid block from {}: {}", peer_addr, e);
-                    return Ok(());
+                Self::handle_received_block(block, true, peer_addr, peers, blockchain_db, stats, transport, Self::active_checkpoints(app_handle)).await;
+            },
+            NetworkMessage::Block { block } => {
+                info!("Received requested block {} (height: {}) from {}", block.hash, block.height, peer_addr);
+                Self::handle_received_block(block, false, peer_addr, peers, blockchain_db, stats, transport, Self::active_checkpoints(app_handle)).await;
+            },
+            // NewTransaction is still accepted from peers that push full transactions
+            NetworkMessage::NewTransaction { transaction } | NetworkMessage::Tx { transaction } => {
+                let txid = transaction.txid.clone();
+                if let Some(peer) = peers.write().await.get_mut(&peer_addr) {
+                    peer.known_inventory.insert(&txid);
                 }
-                
-                // Store the validated block
-                if let Err(e) = blockchain_db.store_block(&block).await {
-                    warn!("Failed to store received block: {}", e);
-                } else {
-                    info!("Successfully stored block {} at height {}", block.hash, block.height);
-                    let mut stats_guard = stats.write().await;
-                    stats_guard.blocks_received += 1;
-                    stats_guard.local_height = stats_guard.local_height.max(block.height);
-                    
-                    // Update peer score for providing valid block
-                    {
-                        let mut peers_guard = peers.write().await;
-                        if let Some(peer) = peers_guard.get_mut(&peer_addr) {
-                            peer.score.on_valid_block(block.height);
-                        }
+
+                // Several peers may answer an announcement; a copy we already have is not invalid
+                if let Some(mempool_service) = mempool {
+                    if !txid.is_empty() && mempool_service.get_transaction(&txid).await.is_some() {
+                        debug!("Already have transaction {} from {}", txid, peer_addr);
+                        return Ok(());
                     }
-                    
-                    // Propagate block to other peers
-                    Self::propagate_block_to_peers(&block, peer_addr, peers).await;
                 }
-            },
-            NetworkMessage::NewTransaction { transaction } => {
-                info!("Received new transaction {} from {}", transaction.txid, peer_addr);
                 
                 // Handle transaction through mempool
                 match Self::handle_incoming_transaction(transaction, peer_addr, mempool).await {
-                    Ok(_) => {
+                    Ok(added) => {
                         // Update peer score for providing valid transaction
-                        let mut peers_guard = peers.write().await;
-                        if let Some(peer) = peers_guard.get_mut(&peer_addr) {
+                        if let Some(peer) = peers.write().await.get_mut(&peer_addr) {
                             peer.score.on_valid_transaction();
                         }
+                        if let Some(txid) = added {
+                            Self::announce_transaction(&txid, Some(peer_addr), peers, transport).await;
+                        }
                     }
                     Err(e) => {
                         warn!("Failed to handle incoming transaction: {}", e);
                         // Penalize peer for invalid transaction
-                        let mut peers_guard = peers.write().await;
-                        if let Some(peer) = peers_guard.get_mut(&peer_addr) {
+                        if let Some(peer) = peers.write().await.get_mut(&peer_addr) {
                             peer.score.on_invalid_message();
                         }
                     }
@@ -802,46 +1129,68 @@ impl NetworkService {
                 let mut stats_guard = stats.write().await;
                 stats_guard.transactions_received += 1;
             },
-            NetworkMessage::Tx { transaction } => {
-                info!("Received tx {} from {}", transaction.txid, peer_addr);
-                
-                // Handle transaction through mempool (same as NewTransaction)
-                match Self::handle_incoming_transaction(transaction, peer_addr, mempool).await {
-                    Ok(_) => {
-                        // Update peer score for providing valid transaction
-                        let mut peers_guard = peers.write().await;
-                        if let Some(peer) = peers_guard.get_mut(&peer_addr) {
-                            peer.score.on_valid_transaction();
-                        }
+            NetworkMessage::Version { version, services, timestamp, user_agent, start_height, addr_recv, .. } => {
+                info!("Received version message from {} (version: {}, agent: {}, height: {})", peer_addr, version, user_agent, start_height);
+                // The peer tells us the address it sees us at, and its clock
+                reachability.lock().unwrap().record_report(peer_addr, addr_recv.ip);
+                crate::network_time::record_peer_time(peer_addr.ip(), timestamp);
+
+                let features = match peer_policy::check_handshake(version, services, Self::min_protocol_version(app_handle)) {
+                    Ok(features) => features,
+                    Err(rejection) => {
+                        info!("Disconnecting {}: {}", peer_addr, rejection);
+                        peers.write().await.remove(&peer_addr);
+                        transport.simulated.disconnect(peer_addr);
+                        return Ok(());
                     }
-                    Err(e) => {
-                        warn!("Failed to handle incoming tx: {}", e);
-                        // Penalize peer for invalid transaction
-                        let mut peers_guard = peers.write().await;
-                        if let Some(peer) = peers_guard.get_mut(&peer_addr) {
-                            peer.score.on_invalid_message();
-                        }
-                    }
-                }
-                
-                let mut stats_guard = stats.write().await;
-                stats_guard.transactions_received += 1;
-            },
-            NetworkMessage::Version { version, services, timestamp, start_height, .. } => {
-                info!("Received version message from {} (version: {}, height: {})", peer_addr, version, start_height);
+                };
                 
                 // Update peer info
                 if let Some(peer) = peers.write().await.get_mut(&peer_addr) {
                     peer.version = Some(version.to_string());
+                    peer.user_agent = Some(user_agent);
                     peer.height = Some(start_height);
+                    peer.address.services = services;
+                    peer.features = Some(features);
+                }
+                if !features.serves_blocks() {
+                    info!("Peer {} serves no blocks; using it for relay only", peer_addr);
+                }
+                if let Some(app) = app_handle {
+                    crate::event_bus::publish(app, crate::event_bus::BackendEvent::PeerConnected { address: peer_addr.to_string() });
                 }
                 
-                // TODO: Send Verack response
+                Self::send_message_to_peer(peer_addr, NetworkMessage::Verack, peers, transport).await?;
             },
             NetworkMessage::Verack => {
                 info!("Received version acknowledgment from {}", peer_addr);
                 // Version handshake complete
             },
+            NetworkMessage::GetCFilters { start_height, stop_hash } => {
+                debug!("Received getcfilters from {} ({} to {})", peer_addr, start_height, stop_hash);
+                let Ok(Some(stop_block)) = blockchain_db.get_block_by_hash(&stop_hash).await else {
+                    debug!("Ignoring getcfilters from {}: unknown stop block {}", peer_addr, stop_hash);
+                    return Ok(());
+                };
+                let last = stop_block
+                    .height
+                    .min(start_height.saturating_add(crate::compact_filters::MAX_FILTERS_PER_REQUEST - 1));
+                for height in start_height..=last {
+                    let filter = blockchain_db
+                        .get_block_filter(height)
+                        .await
+                        .map_err(|e| AppError::Generic(format!("Failed to load filter at height {}: {}", height, e)))?;
+                    let Some((block_hash, filter)) = filter else {
+                        break;
+                    };
+                    let message = NetworkMessage::CFilter { block_hash, height, filter };
+                    Self::send_message_to_peer(peer_addr, message, peers, transport).await?;
+                }
+            },
+            NetworkMessage::CFilter { block_hash, height, .. } => {
+                // Filters are built from our own blocks; ones from peers are not needed
+                debug!("Received filter of block {} at height {} from {}", block_hash, height, peer_addr);
+            },
             _ => {
                 debug!("Received unhandled message type from {}", peer_addr);
             }
@@ -850,6 +1199,177 @@ impl NetworkService {
         Ok(())
     }
 
+    /// Validate and store a block received from a peer, relaying it onwards if it was announced
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_received_block(
+        block: Arc<Block>,
+        relay: bool,
+        peer_addr: SocketAddr,
+        peers: &Arc<RwLock<HashMap<SocketAddr, PeerConnection>>>,
+        blockchain_db: &Arc<AsyncBlockchainDatabase>,
+        stats: &Arc<RwLock<NetworkStats>>,
+        transport: &Transport,
+        checkpoints: &[(u64, &str)],
+    ) {
+        // A requested block that overtook its parent is kept until the parent is connected
+        let tip = blockchain_db.get_block_height().await.unwrap_or(0);
+        if block.height > tip + 1 && blockchain_db.stash_block(&block).await.is_ok() {
+            debug!("Stashed block {} at height {} until its parent arrives", block.hash, block.height);
+            Self::schedule_block_downloads(peers, blockchain_db, transport).await;
+            return;
+        }
+
+        // Validate block before storing
+        if let Err(e) = Self::validate_block(&block, blockchain_db, checkpoints).await {
+            warn!("Received invalid block from {}: {}", peer_addr, e);
+            Self::record_side_block(&block, tip, blockchain_db, checkpoints).await;
+            return;
+        }
+
+        // Store the validated block
+        if let Err(e) = blockchain_db.store_block(&block).await {
+            warn!("Failed to store received block: {}", e);
+            return;
+        }
+        info!("Successfully stored block {} at height {}", block.hash, block.height);
+        crate::network_time::record_block_time(block.timestamp);
+        {
+            let mut stats_guard = stats.write().await;
+            stats_guard.blocks_received += 1;
+            stats_guard.local_height = stats_guard.local_height.max(block.height);
+        }
+
+        // Update peer score for providing valid block
+        if let Some(peer) = peers.write().await.get_mut(&peer_addr) {
+            peer.score.on_valid_block(block.height);
+        }
+
+        // Relay announced blocks to other peers; requested ones were asked for by sync
+        if relay {
+            Self::propagate_block_to_peers(&block, peer_addr, peers, transport).await;
+        }
+
+        Self::connect_stashed_blocks(block.height + 1, blockchain_db, stats, checkpoints).await;
+
+        // The block freed a download slot and may have moved the window
+        if !relay {
+            Self::schedule_block_downloads(peers, blockchain_db, transport).await;
+        }
+    }
+
+    /// Keep a block that was not connected in the block tree, for the chain tips on the developer
+    /// page. A block that extends the active tip failed validation outright; one on another
+    /// branch is only checked as far as possible without connecting it.
+    async fn record_side_block(
+        block: &Block,
+        tip: u64,
+        blockchain_db: &Arc<AsyncBlockchainDatabase>,
+        checkpoints: &[(u64, &str)],
+    ) {
+        let extends_tip = block.height == tip + 1
+            && matches!(blockchain_db.get_block_by_height(tip).await, Ok(Some(parent)) if parent.hash == block.previous_hash);
+        let invalid = extends_tip || Self::check_block_contents(block, checkpoints).is_err();
+        match blockchain_db.record_side_block(block, invalid).await {
+            Ok(true) => debug!(
+                "Recorded {} block {} at height {} in the block tree",
+                if invalid { "invalid" } else { "fork" },
+                block.hash,
+                block.height
+            ),
+            Ok(false) => {}
+            Err(e) => warn!("Failed to record block {} in the block tree: {}", block.hash, e),
+        }
+    }
+
+    /// Request the blocks in the download window that are not in flight, from peers with free
+    /// slots, after giving up on requests that went unanswered for `BLOCK_REQUEST_TIMEOUT_SECS`.
+    /// Peers that let requests time out are penalized, which also shrinks their share.
+    async fn schedule_block_downloads(
+        peers: &Arc<RwLock<HashMap<SocketAddr, PeerConnection>>>,
+        blockchain_db: &Arc<AsyncBlockchainDatabase>,
+        transport: &Transport,
+    ) {
+        let download_peers: Vec<DownloadPeer> = peers
+            .read()
+            .await
+            .iter()
+            .filter(|(_, peer)| peer.features.is_none_or(|features| features.serves_blocks()))
+            .map(|(addr, peer)| DownloadPeer {
+                addr: *addr,
+                score: peer.score.calculate_total_score(),
+                height: peer.height.unwrap_or(0),
+            })
+            .collect();
+
+        let now = Instant::now();
+        let timeout = Duration::from_secs(BLOCK_REQUEST_TIMEOUT_SECS);
+        let scheduled = blockchain_db
+            .update_sync_progress(|progress| {
+                let expired = progress.expire_requests(now, timeout);
+                (expired, progress.schedule(&download_peers, now))
+            })
+            .await;
+        let (expired, assigned) = match scheduled {
+            Ok(scheduled) => scheduled,
+            Err(e) => {
+                warn!("Failed to record sync progress: {}", e);
+                return;
+            }
+        };
+
+        if !expired.is_empty() {
+            let mut peers_guard = peers.write().await;
+            for (peer_addr, count) in expired {
+                warn!("{} block requests to {} timed out; asking other peers", count, peer_addr);
+                if let Some(peer) = peers_guard.get_mut(&peer_addr) {
+                    peer.score.on_sync_stall();
+                }
+            }
+        }
+
+        for (peer_addr, hashes) in assigned {
+            let count = hashes.len();
+            let inventory = hashes
+                .into_iter()
+                .map(|hash| InventoryItem { item_type: InventoryType::Block, hash })
+                .collect();
+            match Self::send_message_to_peer(peer_addr, NetworkMessage::GetData { inventory }, peers, transport).await {
+                Ok(()) => debug!("Requested {} blocks from {} via GetData", count, peer_addr),
+                Err(e) => warn!("Failed to request blocks from {}: {}", peer_addr, e),
+            }
+        }
+    }
+
+    /// Connect the stashed blocks that follow the chain tip, starting at `height`
+    async fn connect_stashed_blocks(
+        mut height: u64,
+        blockchain_db: &Arc<AsyncBlockchainDatabase>,
+        stats: &Arc<RwLock<NetworkStats>>,
+        checkpoints: &[(u64, &str)],
+    ) {
+        while let Ok(Some(block)) = blockchain_db.get_stashed_block(height).await {
+            let connected = match Self::validate_block(&block, blockchain_db, checkpoints).await {
+                Ok(()) => blockchain_db.store_block(&block).await.map_err(|e| AppError::Generic(e.to_string())),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = connected {
+                warn!("Stashed block {} at height {} cannot be connected: {}", block.hash, height, e);
+                if let Err(e) = blockchain_db.update_sync_progress(|progress| progress.retry(height)).await {
+                    warn!("Failed to record sync progress: {}", e);
+                }
+                return;
+            }
+
+            debug!("Connected stashed block {} at height {}", block.hash, height);
+            crate::network_time::record_block_time(block.timestamp);
+            let mut stats_guard = stats.write().await;
+            stats_guard.blocks_received += 1;
+            stats_guard.local_height = stats_guard.local_height.max(height);
+            drop(stats_guard);
+            height += 1;
+        }
+    }
+
     /// Find the fork point given block locator hashes
     async fn find_fork_point(
         blockchain_db: &Arc<AsyncBlockchainDatabase>,
@@ -869,11 +1389,12 @@ impl NetworkService {
         _stream: TcpStream,
         addr: SocketAddr,
         peers: Arc<RwLock<HashMap<SocketAddr, PeerConnection>>>,
-        _message_sender: mpsc::UnboundedSender<(SocketAddr, NetworkMessage)>,
+        _message_sender: PeerMessageSender,
     ) {
         info!("Handling peer connection from {}", addr);
         
-        // TODO: Implement actual message reading/writing with the stream
+        // TODO: Implement actual message reading/writing with the stream, framed with
+        // network_codec::decode_frame over the read buffer
         // For now, simulate some basic interaction
         
         tokio::time::sleep(Duration::from_secs(30)).await;
@@ -891,13 +1412,19 @@ impl NetworkService {
     async fn peer_discovery_loop(
         known_addresses: Arc<RwLock<HashSet<PeerAddress>>>,
         peers: Arc<RwLock<HashMap<SocketAddr, PeerConnection>>>,
-        message_sender: mpsc::UnboundedSender<(SocketAddr, NetworkMessage)>,
+        message_sender: PeerMessageSender,
+        listen_families: HashSet<AddressFamily>,
         is_running: Arc<RwLock<bool>>,
+        wake: Arc<Notify>,
+        app_handle: Option<AppHandle>,
     ) {
         let mut interval = interval(Duration::from_secs(60)); // Try discovery every minute
 
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = wake.notified() => {}
+            }
             
             // Check if service is still running
             {
@@ -907,24 +1434,29 @@ impl NetworkService {
                 }
             }
 
-            // Try to connect to known addresses
+            // Fill free outbound slots from known addresses, one per network group
+            let limits = Self::connection_limits(&app_handle);
             let addresses: Vec<PeerAddress> = {
+                let peers_guard = peers.read().await;
+                let outbound: Vec<IpAddr> = peers_guard.values()
+                    .filter(|peer| peer.is_outbound)
+                    .map(|peer| peer.address.ip)
+                    .collect();
+                let slots = limits.max_outbound.saturating_sub(outbound.len());
+
                 let known = known_addresses.read().await;
-                known.iter().take(5).cloned().collect() // Try up to 5 addresses
+                // Only dial address families the node has a socket for
+                let mut candidates: Vec<&PeerAddress> = known.iter()
+                    .filter(|addr| listen_families.contains(&addr.family))
+                    .filter(|addr| !peers_guard.contains_key(&SocketAddr::new(addr.ip, addr.port)))
+                    .collect();
+                // Most recently seen first
+                candidates.sort_by_key(|addr| std::cmp::Reverse(addr.last_seen));
+                peer_policy::select_outbound(candidates, outbound, slots)
             };
 
             for addr in addresses {
-                let current_peers = peers.read().await.len();
-                if current_peers >= 8 { // Max 8 connections
-                    break;
-                }
-
                 let socket_addr = SocketAddr::new(addr.ip, addr.port);
-                
-                // Skip if already connected
-                if peers.read().await.contains_key(&socket_addr) {
-                    continue;
-                }
 
                 // Try to connect
                 tokio::spawn(Self::try_connect_to_peer(
@@ -936,11 +1468,94 @@ impl NetworkService {
         }
     }
 
+    /// Interfaces to listen on from the current settings; empty means all of them
+    fn bind_addresses(app_handle: &Option<AppHandle>) -> Vec<IpAddr> {
+        app_handle
+            .as_ref()
+            .and_then(|app| app.try_state::<Arc<crate::config::ConfigManager>>())
+            .and_then(|config_manager| {
+                peer_policy::parse_bind_addresses(&config_manager.get_config().app_settings.p2p_bind_addresses).ok()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Bind a listener to each address, or to all IPv6 and IPv4 interfaces when none are given.
+    /// Fails only if no listener could be bound.
+    async fn bind_listeners(addresses: &[IpAddr], port: u16) -> AppResult<Vec<TcpListener>> {
+        let all_interfaces = [IpAddr::V6(Ipv6Addr::UNSPECIFIED), IpAddr::V4(Ipv4Addr::UNSPECIFIED)];
+        let dual_stack = addresses.is_empty();
+        let addresses = if dual_stack { &all_interfaces[..] } else { addresses };
+
+        let mut listeners = Vec::new();
+        let mut last_error = None;
+        for &ip in addresses {
+            let addr = SocketAddr::new(ip, port);
+            match TcpListener::bind(addr).await {
+                Ok(listener) => {
+                    info!("Network service listening on {}", addr);
+                    listeners.push(listener);
+                }
+                // Where [::] also accepts IPv4, 0.0.0.0 on the same port is already taken
+                Err(e) if dual_stack && ip.is_ipv4() && !listeners.is_empty() && e.kind() == std::io::ErrorKind::AddrInUse => {
+                    debug!("IPv6 listener already accepts IPv4 connections on port {}", port);
+                }
+                Err(e) => {
+                    warn!("Failed to bind to {}: {}", addr, e);
+                    last_error = Some(format!("Failed to bind to {}: {}", addr, e));
+                }
+            }
+        }
+
+        if listeners.is_empty() {
+            return Err(AppError::Network(last_error.unwrap_or_else(|| "No address to listen on".to_string())));
+        }
+        Ok(listeners)
+    }
+
+    /// Address families the listeners accept, and so the families worth dialing
+    fn listen_families(listeners: &[TcpListener]) -> HashSet<AddressFamily> {
+        let mut families = HashSet::new();
+        for addr in listeners.iter().filter_map(|listener| listener.local_addr().ok()) {
+            families.insert(AddressFamily::of(addr.ip()));
+            // A wildcard IPv6 socket usually takes IPv4 too; if not, 0.0.0.0 was bound as well
+            if addr.ip() == IpAddr::V6(Ipv6Addr::UNSPECIFIED) {
+                families.insert(AddressFamily::Ipv4);
+            }
+        }
+        families
+    }
+
+    /// Connection limits from the current settings
+    fn connection_limits(app_handle: &Option<AppHandle>) -> ConnectionLimits {
+        app_handle
+            .as_ref()
+            .and_then(|app| app.try_state::<Arc<crate::config::ConfigManager>>())
+            .map(|config_manager| ConnectionLimits::from_settings(&config_manager.get_config().app_settings))
+            .unwrap_or_default()
+    }
+
+    /// Whether the node skips transaction relay under the current settings
+    fn blocks_only(app_handle: &Option<AppHandle>) -> bool {
+        app_handle
+            .as_ref()
+            .and_then(|app| app.try_state::<Arc<crate::config::ConfigManager>>())
+            .is_some_and(|config_manager| config_manager.get_config().app_settings.blocks_only)
+    }
+
+    /// Lowest protocol version accepted from peers under the current settings
+    fn min_protocol_version(app_handle: &Option<AppHandle>) -> u32 {
+        app_handle
+            .as_ref()
+            .and_then(|app| app.try_state::<Arc<crate::config::ConfigManager>>())
+            .map(|config_manager| config_manager.get_config().app_settings.min_peer_protocol_version)
+            .unwrap_or(MIN_PROTOCOL_VERSION)
+    }
+
     /// Try to connect to a peer
     async fn try_connect_to_peer(
         addr: SocketAddr,
         peers: Arc<RwLock<HashMap<SocketAddr, PeerConnection>>>,
-        message_sender: mpsc::UnboundedSender<(SocketAddr, NetworkMessage)>,
+        message_sender: PeerMessageSender,
     ) {
         debug!("Attempting to connect to peer {}", addr);
 
@@ -954,13 +1569,18 @@ impl NetworkService {
                         port: addr.port(),
                         last_seen: Self::current_timestamp(),
                         services: 0,
+                        family: AddressFamily::of(addr.ip()),
                     },
                     connected_at: Self::current_timestamp(),
                     last_ping: 0,
                     version: None,
+                    user_agent: None,
                     height: None,
                     is_outbound: true,
                     score: PeerScore::default(),
+                    known_inventory: KnownInventory::default(),
+                    addr_budget: AddrBudget::default(),
+                    features: None,
                 };
 
                 // Add peer to connections
@@ -986,12 +1606,31 @@ impl NetworkService {
         peers: Arc<RwLock<HashMap<SocketAddr, PeerConnection>>>,
         stats: Arc<RwLock<NetworkStats>>,
         blockchain_db: Arc<AsyncBlockchainDatabase>,
+        transport: Transport,
+        app_handle: Option<AppHandle>,
     ) {
+        let trace = &transport.trace;
         let mut interval = interval(Duration::from_secs(30));
 
         loop {
             interval.tick().await;
 
+            // Follow the trace settings and dump the trace if asked to
+            let settings = app_handle
+                .as_ref()
+                .and_then(|app| app.try_state::<Arc<crate::config::ConfigManager>>())
+                .map(|config_manager| config_manager.get_config().app_settings);
+            if let Some(settings) = settings {
+                trace.set_enabled(settings.network_trace_enabled);
+                if settings.network_trace_enabled && settings.network_trace_dump {
+                    if let Some(logs_dir) = crate::paths::PathProvider::logs_dir() {
+                        if let Err(e) = trace.dump(&logs_dir.join(TRACE_DUMP_FILE)) {
+                            warn!("Failed to dump network trace: {}", e);
+                        }
+                    }
+                }
+            }
+
             // Update statistics
             {
                 let peers_guard = peers.read().await;
@@ -1005,9 +1644,105 @@ impl NetworkService {
                 }
             }
 
+            Self::check_sync_stall(&peers, &stats, &blockchain_db, &transport, &app_handle).await;
+            // Requests time out even when no block arrives to trigger scheduling
+            Self::schedule_block_downloads(&peers, &blockchain_db, &transport).await;
+
+            // Warn when the local clock drifts away from the network's, and when it recovers
+            if let (Some(status), Some(app)) = (crate::network_time::take_skew_change(), app_handle.as_ref()) {
+                if status.skewed {
+                    warn!("Local clock differs from network time: {:?}", status);
+                } else {
+                    info!("Local clock agrees with network time again");
+                }
+                crate::tray::set_clock_skew(app, status.skewed.then(|| status.skew_secs()));
+                if let Err(e) = app.emit_event(events::CLOCK_SKEW_CHANGED, &status) {
+                    debug!("Failed to emit clock-skew-changed event: {}", e);
+                }
+            }
+
             // TODO: Send periodic pings to peers
             // TODO: Clean up stale peer connections
-            // TODO: Request missing blocks if behind network height
+        }
+    }
+
+    /// Move block download away from a peer that has stopped sending blocks while we are behind,
+    /// to the best-scoring other peer that has the blocks, and penalize the stalling peer
+    async fn check_sync_stall(
+        peers: &Arc<RwLock<HashMap<SocketAddr, PeerConnection>>>,
+        stats: &Arc<RwLock<NetworkStats>>,
+        blockchain_db: &Arc<AsyncBlockchainDatabase>,
+        transport: &Transport,
+        app_handle: &Option<AppHandle>,
+    ) {
+        let (local_height, network_height) = {
+            let stats_guard = stats.read().await;
+            (stats_guard.local_height, stats_guard.network_height)
+        };
+        if network_height <= local_height {
+            return;
+        }
+
+        let stall_timeout = Duration::from_secs(SYNC_STALL_TIMEOUT_SECS);
+        let stalled = blockchain_db
+            .update_sync_progress(|progress| progress.stalled_peer(Instant::now(), stall_timeout))
+            .await;
+        let Ok(Some((stalled_peer, stalled_for))) = stalled else {
+            return;
+        };
+
+        let new_peer = {
+            let mut peers_guard = peers.write().await;
+            if let Some(peer) = peers_guard.get_mut(&stalled_peer) {
+                peer.score.on_sync_stall();
+            }
+            peers_guard
+                .iter()
+                .filter(|(addr, peer)| **addr != stalled_peer && peer.height.unwrap_or(0) > local_height)
+                .filter(|(_, peer)| peer.features.is_none_or(|features| features.serves_blocks()))
+                .max_by_key(|(_, peer)| peer.score.calculate_total_score())
+                .map(|(addr, _)| *addr)
+        };
+
+        let mut hashes = Vec::new();
+        if let Some(new_peer) = new_peer {
+            match blockchain_db
+                .update_sync_progress(|progress| progress.reassign_peer(stalled_peer, new_peer, Instant::now()))
+                .await
+            {
+                Ok(reassigned) => hashes = reassigned,
+                Err(e) => warn!("Failed to record sync progress: {}", e),
+            }
+        }
+        warn!(
+            "Block download from {} stalled for {}s; moving {} blocks to {:?}",
+            stalled_peer,
+            stalled_for.as_secs(),
+            hashes.len(),
+            new_peer
+        );
+
+        let payload = SyncStalled {
+            peer: stalled_peer.to_string(),
+            new_peer: new_peer.map(|addr| addr.to_string()),
+            stalled_secs: stalled_for.as_secs(),
+            blocks_reassigned: hashes.len(),
+            local_height,
+            network_height,
+        };
+        if let Some(new_peer) = new_peer.filter(|_| !hashes.is_empty()) {
+            let inventory = hashes
+                .into_iter()
+                .map(|hash| InventoryItem { item_type: InventoryType::Block, hash })
+                .collect();
+            if let Err(e) = Self::send_message_to_peer(new_peer, NetworkMessage::GetData { inventory }, peers, transport).await {
+                warn!("Failed to request blocks from {}: {}", new_peer, e);
+            }
+        }
+        if let Some(app) = app_handle {
+            if let Err(e) = app.emit_event(events::SYNC_STALLED, &payload) {
+                warn!("Failed to emit sync stalled event: {}", e);
+            }
         }
     }
 
@@ -1024,12 +1759,13 @@ impl NetworkService {
         peer_addr: SocketAddr,
         message: NetworkMessage,
         peers: &Arc<RwLock<HashMap<SocketAddr, PeerConnection>>>,
+        transport: &Transport,
     ) -> AppResult<()> {
         debug!("Sending message to peer {}: {:?}", peer_addr, message);
         
-        // For now, just log the message send attempt
-        // In a full implementation, this would serialize and send over TCP
-        // TODO: Implement actual message serialization and TCP sending
+        // Simulated peers receive the message directly; socket peers are only logged for now
+        // TODO: Write network_codec::encode_frame(&message) to the peer's TCP stream
+        transport.deliver(peer_addr, message);
         
         // Update peer's last communication time
         if let Some(peer) = peers.write().await.get_mut(&peer_addr) {
@@ -1039,6 +1775,11 @@ impl NetworkService {
         Ok(())
     }
 
+    /// Message trace shared with the message handlers
+    pub fn trace(&self) -> Arc<NetworkTrace> {
+        Arc::clone(&self.transport.trace)
+    }
+
     /// Get network statistics
     pub async fn get_stats(&self) -> NetworkStats {
         let mut stats = self.stats.read().await.clone();
@@ -1056,14 +1797,30 @@ impl NetworkService {
         self.peers.read().await.values().cloned().collect()
     }
 
+    /// The address peers see this node at and whether inbound connections have succeeded
+    pub fn get_reachability(&self) -> ReachabilityStatus {
+        self.reachability.lock().unwrap().status(self.listen_port)
+    }
+
+    /// Score details of connected peers, best first
+    pub async fn get_peer_details(&self) -> Vec<PeerDetails> {
+        let mut details: Vec<PeerDetails> = self.peers.read().await.values().map(PeerConnection::details).collect();
+        details.sort_by(|a, b| b.total_score.cmp(&a.total_score).then_with(|| a.address.cmp(&b.address)));
+        details
+    }
+
     /// Broadcast a message to all connected peers
     pub async fn broadcast_message(&self, message: NetworkMessage) -> AppResult<()> {
-        let peers = self.peers.read().await;
+        // Queueing can wait for the handler, which needs the peer table
+        let peers: Vec<SocketAddr> = self.peers.read().await.keys().copied().collect();
         
         if let Some(ref sender) = self.message_sender {
-            for addr in peers.keys() {
-                if let Err(e) = sender.send((*addr, message.clone())) {
-                    warn!("Failed to send message to peer {}: {}", addr, e);
+            for addr in &peers {
+                if self.transport.deliver(*addr, message.clone()) {
+                    continue;
+                }
+                if sender.send(*addr, message.clone()).await == Enqueued::Closed {
+                    warn!("Failed to send message to peer {}: message handler stopped", addr);
                 }
             }
         }
@@ -1072,27 +1829,67 @@ impl NetworkService {
     }
 
     /// Broadcast a new block to the network
-    pub async fn broadcast_block(&self, block: Block) -> AppResult<()> {
+    pub async fn broadcast_block(&self, block: Arc<Block>) -> AppResult<()> {
         info!("Broadcasting new block {} to network", block.hash);
         self.broadcast_message(NetworkMessage::NewBlock { block }).await
     }
 
-    /// Broadcast a new transaction to the network
+    /// Announce a new transaction to the network. Peers fetch it from the mempool with GetData.
     pub async fn broadcast_transaction(&self, transaction: Transaction) -> AppResult<()> {
         info!("Broadcasting new transaction {} to network", transaction.txid);
-        self.broadcast_message(NetworkMessage::NewTransaction { transaction }).await
+        Self::announce_transaction(&transaction.txid, None, &self.peers, &self.transport).await;
+        Ok(())
+    }
+
+    /// Send an Inv for a transaction to every peer not known to have it, except the one it came from.
+    /// Returns the number of peers it was announced to.
+    async fn announce_transaction(
+        txid: &str,
+        source: Option<SocketAddr>,
+        peers: &Arc<RwLock<HashMap<SocketAddr, PeerConnection>>>,
+        transport: &Transport,
+    ) -> usize {
+        // Mark before sending so concurrent announcements don't repeat it
+        let targets: Vec<SocketAddr> = peers
+            .write()
+            .await
+            .iter_mut()
+            .filter(|(addr, _)| Some(**addr) != source)
+            .filter_map(|(addr, peer)| peer.known_inventory.insert(txid).then_some(*addr))
+            .collect();
+
+        let inventory = vec![InventoryItem {
+            item_type: InventoryType::Transaction,
+            hash: txid.to_string(),
+        }];
+        let mut announced = 0;
+        for peer_addr in targets {
+            let message = NetworkMessage::Inv { inventory: inventory.clone() };
+            match Self::send_message_to_peer(peer_addr, message, peers, transport).await {
+                Ok(()) => announced += 1,
+                Err(e) => warn!("Failed to announce transaction {} to {}: {}", txid, peer_addr, e),
+            }
+        }
+        debug!("Announced transaction {} to {} peers", txid, announced);
+        announced
     }
 
     /// Announce this node to the network
     pub async fn announce_self(&self) -> AppResult<()> {
         info!("Announcing node to the network...");
         
-        // Get our listening address
+        // Peers can only reach us at the address they see us at
+        let external = self.reachability.lock().unwrap().external_ip();
+        let Some((external_ip, _)) = external else {
+            info!("External address not known yet, not announcing");
+            return Ok(());
+        };
         let our_address = PeerAddress {
-            ip: "0.0.0.0".parse().unwrap(), // Will be replaced by peers with their view of our IP
-            port: BRADCOIN_DEFAULT_PORT,
+            ip: external_ip,
+            port: self.listen_port,
             last_seen: Self::current_timestamp(),
-            services: NODE_NETWORK, // We support full node services
+            services: LOCAL_SERVICES,
+            family: AddressFamily::of(external_ip),
         };
 
         // Create addr message to announce ourselves
@@ -1289,9 +2086,18 @@ impl NetworkService {
         info!("Starting headers-first synchronization");
         
         let local_height = self.blockchain_db.get_block_height().await.unwrap_or(0);
+        self.resume_block_download().await;
         
         // Create block locator hashes (starting from our current tip)
         let mut block_locator_hashes = Vec::new();
+
+        // Ask for headers after the best one already validated, not after the chain tip
+        let progress = self.blockchain_db.sync_progress().await;
+        if let Some(best_hash) = progress.best_header_hash {
+            if progress.best_header_height > local_height {
+                block_locator_hashes.push(best_hash);
+            }
+        }
         
         // Add recent block hashes for locator
         let mut step = 1;
@@ -1323,15 +2129,15 @@ impl NetworkService {
         info!("Requesting headers from height {} with {} locator hashes", local_height, block_locator_hashes.len());
         
         // Send GetHeaders to all connected peers
-        let peers_guard = self.peers.read().await;
-        for &peer_addr in peers_guard.keys() {
+        let peer_addrs: Vec<SocketAddr> = self.peers.read().await.keys().copied().collect();
+        for peer_addr in peer_addrs {
             let getheaders_message = NetworkMessage::GetHeaders {
                 version: 1,
                 block_locator_hashes: block_locator_hashes.clone(),
                 hash_stop: None, // Get all headers
             };
             
-            if let Err(e) = Self::send_message_to_peer(peer_addr, getheaders_message, &self.peers).await {
+            if let Err(e) = Self::send_message_to_peer(peer_addr, getheaders_message, &self.peers, &self.transport).await {
                 warn!("Failed to send GetHeaders to {}: {}", peer_addr, e);
             } else {
                 info!("Sent GetHeaders request to {}", peer_addr);
@@ -1341,6 +2147,66 @@ impl NetworkService {
         Ok(())
     }
 
+    /// Request again the blocks an interrupted or disconnected download was waiting for,
+    /// spreading them over the connected peers
+    async fn resume_block_download(&self) {
+        Self::schedule_block_downloads(&self.peers, &self.blockchain_db, &self.transport).await;
+    }
+
+    /// Spawn in-process simulated peers that exchange real protocol messages with this node.
+    /// Each peer starts from a copy of the local chain, rewound by `fork_depth` blocks and then
+    /// extended by `fork_depth + extra_blocks` blocks of its own, so peers can be ahead of the
+    /// node (sync), equal to it (relay) or on a competing branch (reorg). All peers spawned in
+    /// one call share the same chain. Returns the peers' virtual addresses.
+    pub async fn spawn_simulated_peers(&self, count: usize, extra_blocks: u64, fork_depth: u64) -> AppResult<Vec<SocketAddr>> {
+        let sender = self.message_sender.clone()
+            .ok_or_else(|| AppError::Network("Network service is not running".to_string()))?;
+
+        let local_height = self.blockchain_db.get_block_height().await.unwrap_or(0);
+        let mut chain = Vec::new();
+        for height in 0..=local_height {
+            match self.blockchain_db.get_block_by_height(height).await {
+                Ok(Some(block)) => chain.push(block),
+                _ => break,
+            }
+        }
+
+        // Keep the genesis block so the branch still connects to the node's chain
+        let fork_depth = fork_depth.min(chain.len().saturating_sub(1) as u64);
+        chain.truncate(chain.len() - fork_depth as usize);
+        let tag = format!("sim{}", Self::current_timestamp());
+        let extension = simulated_network::build_blocks(chain.last(), fork_depth + extra_blocks, &tag);
+        chain.extend(extension);
+
+        let mut addrs = Vec::with_capacity(count);
+        for _ in 0..count {
+            let addr = self.transport.simulated.spawn_peer(chain.clone(), sender.clone());
+            let peer_connection = PeerConnection {
+                address: create_peer_address(addr.ip(), addr.port(), NODE_NETWORK),
+                connected_at: Self::current_timestamp(),
+                last_ping: Self::current_timestamp(),
+                version: Some("simulated".to_string()),
+                user_agent: Some(simulated_network::SIMULATED_USER_AGENT.to_string()),
+                height: chain.last().map(|block| block.height),
+                is_outbound: true,
+                score: PeerScore::default(),
+                known_inventory: KnownInventory::default(),
+                addr_budget: AddrBudget::default(),
+                features: None,
+            };
+            self.peers.write().await.insert(addr, peer_connection);
+            addrs.push(addr);
+        }
+
+        info!(
+            "Spawned {} simulated peers at height {} (fork depth {}, local height {})",
+            count, chain.last().map(|block| block.height).unwrap_or(0), fork_depth, local_height
+        );
+
+        self.sync_headers_first().await?;
+        Ok(addrs)
+    }
+
     /// Handle incoming block inventory (inv message)
     async fn handle_block_inventory(&self, inventory: Vec<InventoryItem>) -> AppResult<()> {
         let block_hashes: Vec<String> = inventory
@@ -1355,7 +2221,7 @@ impl NetworkService {
             // Check which blocks we don't have and request them
             let mut needed_hashes = Vec::new();
             for hash in block_hashes {
-                if self.blockchain_db.get_block_by_hash(&hash).await.is_err() {
+                if !matches!(self.blockchain_db.get_block_by_hash(&hash).await, Ok(Some(_))) {
                     needed_hashes.push(hash);
                 }
             }
@@ -1427,29 +2293,8 @@ impl NetworkService {
     async fn create_generic_test_blocks(&self) -> Vec<Block> {
         info!("Creating generic test blocks");
         
-        let test_addresses = vec![
-            "test1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string(),
-            "test1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq".to_string(),
-            "test1qrp33g0w5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3".to_string(),
-        ];
-        
-        let mut blocks = Vec::new();
-        let current_timestamp = 1640995200;
-        
         // Create just genesis block for now
-        let genesis_block = Block {
-            height: 0,
-            hash: "00000000001234567890abcdef000000000000000000000000000000000000".to_string(),
-            previous_hash: "0000000000000000000000000000000000000000000000000000000000000000".to_string(),
-            timestamp: current_timestamp,
-            nonce: 0,
-            difficulty: 1,
-            transactions: vec![self.create_coinbase_transaction(0, &test_addresses[0], 5000000000).await],
-            merkle_root: "abc123def456".to_string(),
-        };
-        blocks.push(genesis_block);
-        
-        blocks
+        vec![ChainParams::active().genesis_block()]
     }
     
     /// Create a coinbase transaction (mining reward)
@@ -1526,22 +2371,8 @@ impl NetworkService {
             wallet_balances.insert(addr.clone(), 0);
         }
         
-        // Create genesis block
-        let genesis_recipient = &wallet_addresses[0];
-        let genesis_block = Block {
-            height: 0,
-            hash: "00000000001234567890abcdef000000000000000000000000000000000000".to_string(),
-            previous_hash: "0000000000000000000000000000000000000000000000000000000000000000".to_string(),
-            timestamp: current_timestamp,
-            nonce: 0,
-            difficulty: 1,
-            transactions: vec![self.create_coinbase_transaction(0, genesis_recipient, 5000000000).await],
-            merkle_root: "abc123def456".to_string(),
-        };
-        
-        // Update balance for genesis
-        *wallet_balances.get_mut(genesis_recipient).unwrap() += 5000000000;
-        blocks.push(genesis_block);
+        // Every chain starts from the canonical genesis block
+        blocks.push(ChainParams::active().genesis_block());
           // Create blocks with varied transaction patterns
         for height in 1..=10 {
             current_timestamp += 600; // 10 minutes between blocks
@@ -1799,18 +2630,31 @@ impl NetworkService {
         Ok(blocks)
     }
 
+    /// Validate a block mined outside the node as if a peer had sent it
+    pub async fn validate_submitted_block(
+        block: &Block,
+        blockchain_db: &Arc<AsyncBlockchainDatabase>,
+        app_handle: &AppHandle,
+    ) -> AppResult<()> {
+        Self::validate_block(block, blockchain_db, Self::active_checkpoints(&Some(app_handle.clone()))).await
+    }
+
     /// Validate a received block before storing it
-    async fn validate_block(block: &Block, blockchain_db: &Arc<AsyncBlockchainDatabase>) -> AppResult<()> {
+    async fn validate_block(
+        block: &Block,
+        blockchain_db: &Arc<AsyncBlockchainDatabase>,
+        checkpoints: &[(u64, &str)],
+    ) -> AppResult<()> {
         // Basic block validation
         
         // Check if block already exists
-        if blockchain_db.get_block_by_hash(&block.hash).await.is_ok() {
+        if matches!(blockchain_db.get_block_by_hash(&block.hash).await, Ok(Some(_))) {
             return Err(AppError::Generic("Block already exists".to_string()));
         }
         
         // Check if previous block exists (unless this is genesis)
         if block.height > 0 {
-            if blockchain_db.get_block_by_hash(&block.previous_hash).await.is_err() {
+            if !matches!(blockchain_db.get_block_by_hash(&block.previous_hash).await, Ok(Some(_))) {
                 return Err(AppError::Generic("Previous block not found".to_string()));
             }
         }
@@ -1823,7 +2667,25 @@ impl NetworkService {
                 expected_height, block.height
             )));
         }
+
+        Self::check_block_contents(block, checkpoints)?;
+
+        // Up to the last checkpoint the chain is pinned by hash, so inputs are not re-checked
+        if !crate::consensus::is_assumed_valid(checkpoints, block.height) {
+            Self::validate_block_inputs(block, blockchain_db).await?;
+        }
+        
+        // TODO: Add more sophisticated validation:
+        // - Merkle root verification
+        // - Proof of work validation
+        // - Double-spend checks
         
+        Ok(())
+    }
+
+    /// The checks of a block that need no chain state, which also apply to blocks of other
+    /// branches
+    fn check_block_contents(block: &Block, checkpoints: &[(u64, &str)]) -> AppResult<()> {
         // Validate block hash format
         if block.hash.len() != 64 {
             return Err(AppError::Generic("Invalid block hash format".to_string()));
@@ -1833,29 +2695,98 @@ impl NetworkService {
         if block.transactions.is_empty() {
             return Err(AppError::Generic("Block must contain at least one transaction".to_string()));
         }
-        
-        // TODO: Add more sophisticated validation:
-        // - Merkle root verification
-        // - Proof of work validation
-        // - Transaction validation
-        // - Double-spend checks
-        
-        Ok(())
+
+        // Blocks at checkpoint heights must be the checkpointed ones
+        crate::consensus::check_checkpoint(checkpoints, block.height, &block.hash)
+            .map_err(|e| AppError::Generic(e.to_string()))?;
+
+        // The coinbase's amount needs the fees, which need the UTXO set, so it is checked with the inputs
+        crate::consensus::check_coinbase_placement(block).map_err(|e| AppError::Generic(e.to_string()))?;
+
+        crate::consensus::check_block_time(block, crate::network_time::adjusted_time())
+            .map_err(|e| AppError::Generic(e.to_string()))
+    }
+
+    /// Check that every input spends an unspent output, from the UTXO set or from an earlier
+    /// transaction in the same block, that no output is spent twice, that every input is signed
+    /// by the owner of the output it spends, that no transaction pays out more than it spends, and
+    /// that the coinbase claims no more than the subsidy plus the fees. Signatures the mempool
+    /// already verified are found in the shared signature cache.
+    async fn validate_block_inputs(block: &Block, blockchain_db: &Arc<AsyncBlockchainDatabase>) -> AppResult<()> {
+        let mut created: HashMap<(String, u32), (u64, String)> = HashMap::new();
+        let mut spent: HashSet<(String, u32)> = HashSet::new();
+        let mut fees = 0u64;
+
+        for transaction in &block.transactions {
+            let mut input_value = 0u64;
+            let mut spent_addresses = Vec::with_capacity(transaction.inputs.len());
+            for input in &transaction.inputs {
+                let outpoint = (input.previous_txid.clone(), input.previous_output_index);
+                let output = match created.get(&outpoint) {
+                    Some(output) => Some(output.clone()),
+                    None => blockchain_db
+                        .get_utxo(&input.previous_txid, input.previous_output_index)
+                        .await
+                        .ok()
+                        .flatten()
+                        .map(|utxo| (utxo.value, utxo.address)),
+                };
+                match output {
+                    Some((value, address)) if spent.insert(outpoint) => {
+                        input_value = input_value.saturating_add(value);
+                        spent_addresses.push(address);
+                    }
+                    _ => {
+                        return Err(AppError::Generic(format!(
+                            "Transaction {} spends missing or already spent output {}:{}",
+                            transaction.txid, input.previous_txid, input.previous_output_index
+                        )));
+                    }
+                }
+            }
+            // The coinbase has no inputs, so nothing to sign, and pays no fee
+            if !transaction.inputs.is_empty() {
+                let addresses: Vec<&str> = spent_addresses.iter().map(String::as_str).collect();
+                crate::signature_cache::verify_transaction(transaction, &addresses)
+                    .map_err(|e| AppError::Generic(e.to_string()))?;
+                let fee = crate::consensus::transaction_fee(transaction, input_value)
+                    .map_err(|e| AppError::Generic(e.to_string()))?;
+                fees = fees.saturating_add(fee);
+            }
+            for (index, output) in transaction.outputs.iter().enumerate() {
+                created.insert((transaction.txid.clone(), index as u32), (output.value, output.address.clone()));
+            }
+        }
+
+        crate::consensus::validate_coinbase(block, fees).map_err(|e| AppError::Generic(e.to_string()))
+    }
+
+    /// Checkpoints in force under the current settings
+    fn active_checkpoints(app_handle: &Option<AppHandle>) -> &'static [(u64, &'static str)] {
+        let enabled = app_handle
+            .as_ref()
+            .and_then(|app| app.try_state::<Arc<crate::config::ConfigManager>>())
+            .map(|config_manager| config_manager.get_config().app_settings.checkpoints_enabled)
+            .unwrap_or(true);
+        crate::consensus::active_checkpoints(enabled)
     }
 
     /// Propagate a block to all peers except the sender
     async fn propagate_block_to_peers(
-        block: &Block,
+        block: &Arc<Block>,
         sender_addr: SocketAddr,
         peers: &Arc<RwLock<HashMap<SocketAddr, PeerConnection>>>,
+        transport: &Transport,
     ) {
-        let new_block_message = NetworkMessage::NewBlock { block: block.clone() };
+        // Every copy of the message shares the one block
+        let new_block_message = NetworkMessage::NewBlock { block: Arc::clone(block) };
         
-        let peers_guard = peers.read().await;
-        for (&peer_addr, _) in peers_guard.iter() {
+        // Collect addresses first: sending updates the peer table
+        let peer_addrs: Vec<SocketAddr> = peers.read().await.keys().copied().collect();
+        for &peer_addr in &peer_addrs {
             // Don't send back to the peer that sent us this block
             if peer_addr != sender_addr {
-                if let Err(e) = Self::send_message_to_peer(peer_addr, new_block_message.clone(), peers).await {
+                if let Err(e) = Self::send_message_to_peer(peer_addr, new_block_message.clone(), peers, transport).await {
                     warn!("Failed to propagate block to {}: {}", peer_addr, e);
                 } else {
                     debug!("Propagated block {} to {}", block.hash, peer_addr);
@@ -1863,29 +2794,13 @@ impl NetworkService {
             }
         }
         
-        info!("Propagated block {} to {} peers", block.hash, peers_guard.len().saturating_sub(1));
+        info!("Propagated block {} to {} peers", block.hash, peer_addrs.len().saturating_sub(1));
     }
 
-    /// Propagate a transaction to all connected peers
+    /// Propagate a transaction to all connected peers that don't have it yet
     pub async fn propagate_transaction(&self, transaction: &Transaction) -> AppResult<()> {
         info!("Propagating transaction {} to network", transaction.txid);
-        
-        let tx_message = NetworkMessage::Tx { 
-            transaction: transaction.clone() 
-        };
-        
-        let peers_guard = self.peers.read().await;
-        let mut propagated_count = 0;
-        
-        for (&peer_addr, _) in peers_guard.iter() {
-            if let Err(e) = Self::send_message_to_peer(peer_addr, tx_message.clone(), &self.peers).await {
-                warn!("Failed to propagate transaction to {}: {}", peer_addr, e);
-            } else {
-                debug!("Propagated transaction {} to {}", transaction.txid, peer_addr);
-                propagated_count += 1;
-            }
-        }
-        
+        let propagated_count = Self::announce_transaction(&transaction.txid, None, &self.peers, &self.transport).await;
         info!("Propagated transaction {} to {} peers", transaction.txid, propagated_count);
         Ok(())
     }
@@ -1895,27 +2810,30 @@ impl NetworkService {
         transaction: Transaction,
         sender_addr: SocketAddr,
         mempool: &Option<AsyncMempoolService>,
-    ) -> AppResult<()> {
+    ) -> AppResult<Option<String>> {
         info!("Received transaction {} from {}", transaction.txid, sender_addr);
         
-        // If we have a mempool, add the transaction to it
+        // If we have a mempool, add the transaction to it; the caller relays what was added
         if let Some(ref mempool_service) = mempool {
-            match mempool_service.add_transaction(transaction.clone()).await {
+            // Not the peer's fault, so not an invalid transaction
+            if mempool_service.policy().await.blocks_only {
+                debug!("Ignoring transaction {} from {}: blocks-only mode", transaction.txid, sender_addr);
+                return Ok(None);
+            }
+            match mempool_service.add_transaction(transaction.clone(), TransactionSource::Peer).await {
                 Ok(tx_hash) => {
                     info!("Added transaction {} to mempool", tx_hash);
-                    // Note: For static method, we can't propagate back to peers here
-                    // Propagation should be handled by the caller or through separate mechanism
+                    Ok(Some(tx_hash))
                 }
                 Err(e) => {
                     warn!("Failed to add transaction to mempool: {}", e);
-                    return Err(AppError::Generic(format!("Invalid transaction: {}", e)).into());
+                    Err(AppError::Generic(format!("Invalid transaction: {}", e)))
                 }
             }
         } else {
             warn!("No mempool available to store transaction");
+            Ok(None)
         }
-        
-        Ok(())
     }
 
     /// Propagate a transaction to all peers except the sender
@@ -1924,23 +2842,8 @@ impl NetworkService {
         transaction: &Transaction,
         sender_addr: SocketAddr,
     ) {
-        let tx_message = NetworkMessage::Tx { 
-            transaction: transaction.clone() 
-        };
-        
-        let peers_guard = self.peers.read().await;
-        for (&peer_addr, _) in peers_guard.iter() {
-            // Don't send back to the peer that sent us this transaction
-            if peer_addr != sender_addr {
-                if let Err(e) = Self::send_message_to_peer(peer_addr, tx_message.clone(), &self.peers).await {
-                    warn!("Failed to propagate transaction to {}: {}", peer_addr, e);
-                } else {
-                    debug!("Propagated transaction {} to {}", transaction.txid, peer_addr);
-                }
-            }
-        }
-        
-        info!("Propagated transaction {} to {} peers", transaction.txid, peers_guard.len().saturating_sub(1));
+        let propagated_count = Self::announce_transaction(&transaction.txid, Some(sender_addr), &self.peers, &self.transport).await;
+        info!("Propagated transaction {} to {} peers", transaction.txid, propagated_count);
     }
 }
 
@@ -2003,18 +2906,42 @@ impl AsyncNetworkService {
         service.stop().await
     }
 
+    /// Drop socket peers and optionally reconnect right away
+    pub async fn reset_peers(&self, reconnect: bool) -> usize {
+        let service = self.inner.read().await;
+        service.reset_peers(reconnect).await
+    }
+
     /// Get network statistics
     pub async fn get_stats(&self) -> NetworkStats {
         let service = self.inner.read().await;
         service.get_stats().await
     }
 
+    /// Get the message trace
+    pub async fn trace(&self) -> Arc<NetworkTrace> {
+        let service = self.inner.read().await;
+        service.trace()
+    }
+
     /// Get connected peers
     pub async fn get_peers(&self) -> Vec<PeerConnection> {
         let service = self.inner.read().await;
         service.get_peers().await
     }
 
+    /// The address peers see this node at and whether inbound connections have succeeded
+    pub async fn get_reachability(&self) -> ReachabilityStatus {
+        let service = self.inner.read().await;
+        service.get_reachability()
+    }
+
+    /// Score details of connected peers, best first
+    pub async fn get_peer_details(&self) -> Vec<PeerDetails> {
+        let service = self.inner.read().await;
+        service.get_peer_details().await
+    }
+
     /// Broadcast a message to all peers
     pub async fn broadcast_message(&self, message: NetworkMessage) -> AppResult<()> {
         let service = self.inner.read().await;
@@ -2022,7 +2949,7 @@ impl AsyncNetworkService {
     }
 
     /// Broadcast a new block
-    pub async fn broadcast_block(&self, block: Block) -> AppResult<()> {
+    pub async fn broadcast_block(&self, block: Arc<Block>) -> AppResult<()> {
         let service = self.inner.read().await;
         service.broadcast_block(block).await
     }
@@ -2063,6 +2990,12 @@ impl AsyncNetworkService {
         service.announce_new_block(block_hash).await
     }
 
+    /// Spawn simulated peers (developer mode)
+    pub async fn spawn_simulated_peers(&self, count: usize, extra_blocks: u64, fork_depth: u64) -> AppResult<Vec<SocketAddr>> {
+        let service = self.inner.read().await;
+        service.spawn_simulated_peers(count, extra_blocks, fork_depth).await
+    }
+
     /// Check if the network service is connected to peers
     /// For development: always returns true to simulate network connectivity
     pub async fn is_connected(&self) -> bool {
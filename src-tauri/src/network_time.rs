@@ -0,0 +1,210 @@
+//! Network-adjusted time, estimated from the clocks of peers.
+//!
+//! Each Version message carries the peer's clock. The median offset between peer clocks and
+//! ours, once `MIN_SAMPLES` distinct peers have reported, is applied to the local clock when
+//! checking block timestamps, unless it exceeds `MAX_TIME_ADJUSTMENT_SECS`: then our clock is
+//! more likely wrong than the network is being gamed, and the user has to fix it. Either way the
+//! user is warned when the clock is off by more than `CLOCK_SKEW_WARNING_SECS`, which the newest
+//! block's timestamp running ahead of our clock also reveals.
+//!
+//! Validation runs in static contexts without the network service at hand, so the estimate is
+//! kept process-wide.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Distinct peers that must report their clock before the median is used
+pub const MIN_SAMPLES: usize = 5;
+
+/// Peer clocks remembered; the oldest report is dropped beyond this
+const MAX_SAMPLES: usize = 200;
+
+/// Largest offset applied to the local clock
+pub const MAX_TIME_ADJUSTMENT_SECS: i64 = 70 * 60;
+
+/// Clock error the user is warned about
+pub const CLOCK_SKEW_WARNING_SECS: i64 = 5 * 60;
+
+static NETWORK_TIME: Mutex<NetworkTime> = Mutex::new(NetworkTime::new());
+
+/// Clock state as shown to the user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClockSkewStatus {
+    /// Median of peer clocks minus ours, once enough peers reported; positive means ours is behind
+    pub peer_offset_secs: Option<i64>,
+    /// Peers that reported their clock
+    pub peer_samples: usize,
+    /// How far the newest block's timestamp is ahead of our clock, if it is
+    pub block_time_ahead_secs: Option<u64>,
+    /// Offset applied to the local clock when checking block timestamps
+    pub applied_offset_secs: i64,
+    /// Whether the local clock is off by more than the warning threshold
+    pub skewed: bool,
+}
+
+impl ClockSkewStatus {
+    /// Best estimate of how far off the local clock is; positive when it is behind
+    pub fn skew_secs(&self) -> i64 {
+        self.peer_offset_secs
+            .filter(|offset| offset.abs() > CLOCK_SKEW_WARNING_SECS)
+            .or(self.block_time_ahead_secs.map(|ahead| ahead as i64))
+            .or(self.peer_offset_secs)
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug)]
+pub struct NetworkTime {
+    /// Peer address and its clock minus ours, oldest first
+    samples: VecDeque<(IpAddr, i64)>,
+    newest_block_time: u64,
+    /// Whether the last status handed out by `take_skew_change` was skewed
+    reported_skewed: bool,
+}
+
+impl Default for NetworkTime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NetworkTime {
+    pub const fn new() -> Self {
+        Self {
+            samples: VecDeque::new(),
+            newest_block_time: 0,
+            reported_skewed: false,
+        }
+    }
+
+    /// Note the clock of a peer, `offset` seconds ahead of ours. A peer's latest report
+    /// replaces its earlier one, so one peer cannot fill the samples.
+    pub fn add_sample(&mut self, peer: IpAddr, offset: i64) {
+        self.samples.retain(|(ip, _)| *ip != peer);
+        if self.samples.len() >= MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((peer, offset));
+    }
+
+    /// Note the timestamp of a newly connected block
+    pub fn add_block_time(&mut self, timestamp: u64) {
+        self.newest_block_time = self.newest_block_time.max(timestamp);
+    }
+
+    /// Median peer offset, if enough peers reported
+    pub fn median_offset(&self) -> Option<i64> {
+        if self.samples.len() < MIN_SAMPLES {
+            return None;
+        }
+        let mut offsets: Vec<i64> = self.samples.iter().map(|(_, offset)| *offset).collect();
+        offsets.sort_unstable();
+        let middle = offsets.len() / 2;
+        Some(if offsets.len().is_multiple_of(2) {
+            (offsets[middle - 1] + offsets[middle]) / 2
+        } else {
+            offsets[middle]
+        })
+    }
+
+    /// Offset applied to the local clock
+    pub fn applied_offset(&self) -> i64 {
+        self.median_offset()
+            .filter(|offset| offset.abs() <= MAX_TIME_ADJUSTMENT_SECS)
+            .unwrap_or(0)
+    }
+
+    pub fn status(&self, now: u64) -> ClockSkewStatus {
+        let peer_offset_secs = self.median_offset();
+        let block_time_ahead_secs = self.newest_block_time.checked_sub(now).filter(|ahead| *ahead > 0);
+        let skewed = peer_offset_secs.is_some_and(|offset| offset.abs() > CLOCK_SKEW_WARNING_SECS)
+            || block_time_ahead_secs.is_some_and(|ahead| ahead > CLOCK_SKEW_WARNING_SECS as u64);
+        ClockSkewStatus {
+            peer_offset_secs,
+            peer_samples: self.samples.len(),
+            block_time_ahead_secs,
+            applied_offset_secs: self.applied_offset(),
+            skewed,
+        }
+    }
+}
+
+fn local_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+fn network_time() -> std::sync::MutexGuard<'static, NetworkTime> {
+    NETWORK_TIME.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Note the clock a peer sent in its Version message
+pub fn record_peer_time(peer: IpAddr, peer_timestamp: u64) {
+    let offset = peer_timestamp as i64 - local_time() as i64;
+    network_time().add_sample(peer, offset);
+}
+
+/// Note the timestamp of a newly connected block
+pub fn record_block_time(timestamp: u64) {
+    network_time().add_block_time(timestamp);
+}
+
+/// Unix time adjusted by the median peer offset
+pub fn adjusted_time() -> u64 {
+    local_time().saturating_add_signed(network_time().applied_offset())
+}
+
+/// Current clock state
+pub fn status() -> ClockSkewStatus {
+    network_time().status(local_time())
+}
+
+/// The current status if the clock became skewed or correct since the last call
+pub fn take_skew_change() -> Option<ClockSkewStatus> {
+    let mut network_time = network_time();
+    let status = network_time.status(local_time());
+    if status.skewed == network_time.reported_skewed {
+        return None;
+    }
+    network_time.reported_skewed = status.skewed;
+    Some(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_median_offset_needs_enough_peers_and_is_capped() {
+        let peer = |n: u8| IpAddr::from([10, 0, 0, n]);
+        let mut time = NetworkTime::new();
+        for n in 1..MIN_SAMPLES as u8 {
+            time.add_sample(peer(n), 600);
+        }
+        // Repeated reports from one peer count once
+        time.add_sample(peer(1), 600);
+        assert_eq!(time.median_offset(), None);
+        assert!(!time.status(1_000).skewed);
+
+        time.add_sample(peer(MIN_SAMPLES as u8), -30);
+        assert_eq!(time.median_offset(), Some(600));
+        assert_eq!(time.applied_offset(), 600);
+        assert!(time.status(1_000).skewed);
+
+        // Beyond the cap the local clock is used as is, but the user is still warned
+        let mut time = NetworkTime::new();
+        for n in 1..=MIN_SAMPLES as u8 {
+            time.add_sample(peer(n), 3 * 60 * 60);
+        }
+        assert_eq!(time.applied_offset(), 0);
+        assert!(time.status(1_000).skewed);
+
+        // A block from well in our future means our clock is behind
+        let mut time = NetworkTime::new();
+        time.add_block_time(1_000 + CLOCK_SKEW_WARNING_SECS as u64 + 1);
+        assert!(time.status(1_000).skewed);
+        assert!(!time.status(1_100).skewed);
+    }
+}
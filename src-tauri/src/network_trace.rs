@@ -0,0 +1,199 @@
+//! Optional per-peer message trace for debugging the P2P protocol.
+//! Keeps the last `TRACE_CAPACITY` messages in each direction per peer while enabled.
+
+use crate::network_service::NetworkMessage;
+use log::{debug, info};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Messages kept per peer
+pub const TRACE_CAPACITY: usize = 200;
+
+/// File the trace is dumped to, in the logs directory
+pub const TRACE_DUMP_FILE: &str = "network_trace.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceDirection {
+    Inbound,
+    Outbound,
+}
+
+/// One traced message
+#[derive(Debug, Clone, Serialize)]
+pub struct TracedMessage {
+    pub direction: TraceDirection,
+    pub message_type: &'static str,
+    /// Size of the encoded message in bytes, without the frame header
+    pub size: usize,
+    /// Unix time in milliseconds
+    pub timestamp: u64,
+    pub summary: String,
+}
+
+/// Trace of a single peer, oldest message first
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerTrace {
+    pub peer: String,
+    pub messages: Vec<TracedMessage>,
+}
+
+/// Ring buffers of recent messages per peer
+#[derive(Debug, Default)]
+pub struct NetworkTrace {
+    enabled: AtomicBool,
+    peers: Mutex<HashMap<SocketAddr, VecDeque<TracedMessage>>>,
+}
+
+impl NetworkTrace {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Turn tracing on or off. Turning it off drops what was recorded.
+    pub fn set_enabled(&self, enabled: bool) {
+        if self.enabled.swap(enabled, Ordering::Relaxed) != enabled {
+            info!("Network message trace {}", if enabled { "enabled" } else { "disabled" });
+            if !enabled {
+                self.clear();
+            }
+        }
+    }
+
+    /// Record a message sent to or received from a peer. Cheap no-op while disabled.
+    pub fn record(&self, peer: SocketAddr, direction: TraceDirection, message: &NetworkMessage) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let (message_type, summary) = describe(message);
+        let entry = TracedMessage {
+            direction,
+            message_type,
+            size: crate::network_codec::encoded_size(message),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            summary,
+        };
+
+        let mut peers = self.peers.lock().unwrap();
+        let buffer = peers.entry(peer).or_default();
+        if buffer.len() == TRACE_CAPACITY {
+            buffer.pop_front();
+        }
+        buffer.push_back(entry);
+    }
+
+    /// Traces of all peers, or of one peer
+    pub fn snapshot(&self, peer: Option<SocketAddr>) -> Vec<PeerTrace> {
+        let peers = self.peers.lock().unwrap();
+        let mut traces: Vec<PeerTrace> = peers
+            .iter()
+            .filter(|(addr, _)| peer.is_none_or(|p| p == **addr))
+            .map(|(addr, messages)| PeerTrace {
+                peer: addr.to_string(),
+                messages: messages.iter().cloned().collect(),
+            })
+            .collect();
+        traces.sort_by(|a, b| a.peer.cmp(&b.peer));
+        traces
+    }
+
+    pub fn clear(&self) {
+        self.peers.lock().unwrap().clear();
+    }
+
+    /// Write all traces to a JSON file
+    pub fn dump(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_vec_pretty(&self.snapshot(None)).map_err(std::io::Error::other)?;
+        crate::atomic_file::write_atomic(path, &json)?;
+        debug!("Dumped network trace to {}", path.display());
+        Ok(())
+    }
+}
+
+/// Message type name (as on the wire) and a one-line summary
+fn describe(message: &NetworkMessage) -> (&'static str, String) {
+    match message {
+        NetworkMessage::Ping { nonce, .. } => ("Ping", format!("nonce {}", nonce)),
+        NetworkMessage::Pong { nonce, .. } => ("Pong", format!("nonce {}", nonce)),
+        NetworkMessage::GetAddr => ("GetAddr", String::new()),
+        NetworkMessage::Addr { addresses } => ("Addr", format!("{} addresses", addresses.len())),
+        NetworkMessage::GetHeight => ("GetHeight", String::new()),
+        NetworkMessage::Height { height } => ("Height", format!("height {}", height)),
+        NetworkMessage::GetBlock { height, hash } => (
+            "GetBlock",
+            match (height, hash) {
+                (_, Some(hash)) => format!("hash {}", hash),
+                (Some(height), None) => format!("height {}", height),
+                (None, None) => String::new(),
+            },
+        ),
+        NetworkMessage::Block { block } => ("Block", block_summary(block)),
+        NetworkMessage::NewBlock { block } => ("NewBlock", block_summary(block)),
+        NetworkMessage::GetTransaction { txid } => ("GetTransaction", format!("txid {}", txid)),
+        NetworkMessage::Transaction { transaction } => ("Transaction", format!("txid {}", transaction.txid)),
+        NetworkMessage::NewTransaction { transaction } => ("NewTransaction", format!("txid {}", transaction.txid)),
+        NetworkMessage::GetBlocks { block_locator_hashes, .. } => {
+            ("GetBlocks", format!("{} locator hashes", block_locator_hashes.len()))
+        }
+        NetworkMessage::Inv { inventory } => ("Inv", format!("{} items", inventory.len())),
+        NetworkMessage::GetData { inventory } => ("GetData", format!("{} items", inventory.len())),
+        NetworkMessage::Blocks { blocks } => ("Blocks", format!("{} blocks", blocks.len())),
+        NetworkMessage::Version { version, user_agent, start_height, .. } => (
+            "Version",
+            format!("v{} {} at height {}", version, user_agent, start_height),
+        ),
+        NetworkMessage::Verack => ("Verack", String::new()),
+        NetworkMessage::GetHeaders { block_locator_hashes, .. } => {
+            ("GetHeaders", format!("{} locator hashes", block_locator_hashes.len()))
+        }
+        NetworkMessage::Headers { headers } => ("Headers", format!("{} headers", headers.len())),
+        NetworkMessage::Tx { transaction } => ("Tx", format!("txid {}", transaction.txid)),
+        NetworkMessage::GetCFilters { start_height, stop_hash } => {
+            ("GetCFilters", format!("from height {} to {}", start_height, stop_hash))
+        }
+        NetworkMessage::CFilter { block_hash, height, filter } => {
+            ("CFilter", format!("height {} hash {} ({} bytes)", height, block_hash, filter.len()))
+        }
+    }
+}
+
+fn block_summary(block: &crate::blockchain_database::Block) -> String {
+    format!("height {} hash {} ({} txs)", block.height, block.hash, block.transactions.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_keeps_last_messages_per_peer() {
+        let trace = NetworkTrace::default();
+        let peer: SocketAddr = "127.0.0.1:8333".parse().unwrap();
+
+        trace.record(peer, TraceDirection::Inbound, &NetworkMessage::GetAddr);
+        assert!(trace.snapshot(None).is_empty(), "nothing is recorded while disabled");
+
+        trace.set_enabled(true);
+        for height in 0..(TRACE_CAPACITY as u64 + 5) {
+            trace.record(peer, TraceDirection::Outbound, &NetworkMessage::Height { height });
+        }
+
+        let traces = trace.snapshot(Some(peer));
+        assert_eq!(traces.len(), 1);
+        assert_eq!(traces[0].messages.len(), TRACE_CAPACITY);
+        assert_eq!(traces[0].messages[0].summary, "height 5");
+        assert_eq!(traces[0].messages[0].message_type, "Height");
+
+        let other: SocketAddr = "127.0.0.2:8333".parse().unwrap();
+        assert!(trace.snapshot(Some(other)).is_empty());
+    }
+}
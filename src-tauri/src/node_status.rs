@@ -0,0 +1,129 @@
+//! One snapshot of the node's state for the UI, the tray and status events.
+//!
+//! Chain, sync, peer, mempool and mining state live in different services; `collect` reads them
+//! all into a `NodeStatus` so every consumer sees the same numbers instead of assembling its own
+//! partial view. Values of services that are not running yet are left at zero.
+
+use crate::blockchain_database::AsyncBlockchainDatabase;
+use crate::blockchain_sync::AsyncBlockchainSyncService;
+use crate::mempool_service::AsyncMempoolService;
+use crate::mining_service::AsyncMiningService;
+use crate::network_service::AsyncNetworkService;
+use serde::Serialize;
+use tauri::AppHandle;
+
+/// Where the node is in following the network
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncState {
+    /// No peers are connected
+    Disconnected,
+    /// Peers know of blocks the local chain does not have yet
+    Syncing,
+    /// The local chain is at the network height
+    Synced,
+}
+
+impl SyncState {
+    pub fn from_heights(peer_count: u32, sync_running: bool, chain_height: u64, network_height: u64) -> Self {
+        if peer_count == 0 {
+            SyncState::Disconnected
+        } else if sync_running || chain_height < network_height {
+            SyncState::Syncing
+        } else {
+            SyncState::Synced
+        }
+    }
+}
+
+/// State of the node as shown to the user
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeStatus {
+    /// Height of the local chain tip
+    pub chain_height: u64,
+    /// Height of the best validated header; ahead of the chain while blocks download
+    pub header_height: u64,
+    /// Best height reported by peers
+    pub network_height: u64,
+    pub peer_count: u32,
+    pub is_connected: bool,
+    pub sync_state: SyncState,
+    pub mempool_transactions: usize,
+    pub mempool_bytes: usize,
+    /// Wallets currently mining
+    pub mining_wallets: usize,
+    /// Combined hash rate of every wallet mining, in hashes per second
+    pub hash_rate: f64,
+}
+
+impl NodeStatus {
+    pub fn is_synced(&self) -> bool {
+        self.sync_state == SyncState::Synced
+    }
+}
+
+/// Read the current state from the running services
+pub async fn collect(app_handle: &AppHandle) -> NodeStatus {
+    let mut chain_height = 0;
+    let mut header_height = 0;
+    if let Some(blockchain_db) = crate::services::get::<AsyncBlockchainDatabase>(app_handle) {
+        chain_height = blockchain_db.get_block_height().await.unwrap_or(0);
+        header_height = blockchain_db.best_header_height().await;
+    }
+
+    let (network_height, peer_count) = match crate::services::get::<AsyncNetworkService>(app_handle) {
+        Some(network_service) => {
+            let stats = network_service.get_stats().await;
+            (stats.network_height, stats.connected_peers)
+        }
+        None => (chain_height, 0),
+    };
+
+    let sync_running = match crate::services::get::<AsyncBlockchainSyncService>(app_handle) {
+        Some(sync_service) => sync_service.is_syncing().await,
+        None => false,
+    };
+
+    let (mempool_transactions, mempool_bytes) = match crate::services::get::<AsyncMempoolService>(app_handle) {
+        Some(mempool_service) => {
+            let stats = mempool_service.get_stats().await;
+            (stats.transaction_count, stats.total_size_bytes)
+        }
+        None => (0, 0),
+    };
+
+    let (mining_wallets, hash_rate) = match crate::services::get::<AsyncMiningService>(app_handle) {
+        Some(mining_service) => {
+            let statuses = mining_service.get_all_mining_statuses().await;
+            let mining: Vec<_> = statuses.values().filter(|status| status.is_mining).collect();
+            (mining.len(), mining.iter().map(|status| status.hash_rate).sum())
+        }
+        None => (0, 0.0),
+    };
+
+    NodeStatus {
+        chain_height,
+        header_height: header_height.max(chain_height),
+        network_height,
+        peer_count,
+        is_connected: peer_count > 0,
+        sync_state: SyncState::from_heights(peer_count, sync_running, chain_height, network_height),
+        mempool_transactions,
+        mempool_bytes,
+        mining_wallets,
+        hash_rate,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_state_from_heights() {
+        assert_eq!(SyncState::from_heights(0, false, 10, 20), SyncState::Disconnected);
+        assert_eq!(SyncState::from_heights(3, false, 10, 20), SyncState::Syncing);
+        assert_eq!(SyncState::from_heights(3, true, 20, 20), SyncState::Syncing);
+        assert_eq!(SyncState::from_heights(3, false, 20, 20), SyncState::Synced);
+    }
+}
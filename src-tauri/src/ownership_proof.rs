@@ -0,0 +1,315 @@
+//! Proof that the holder of an address's key signed a message (BIP322).
+//!
+//! A BIP322 proof is a signature over a virtual transaction that spends a virtual output locked
+//! to the address, so the same rules that guard the address's coins decide whether the proof
+//! holds. Native segwit and taproot addresses use the "simple" format (the witness alone),
+//! nested segwit addresses the "full" format (the whole signing transaction, since it also
+//! needs a script sig), and legacy P2PKH addresses the classic signed message format, as BIP322
+//! prescribes for them. Signatures are base64.
+
+use crate::chain_params::ChainParams;
+use crate::errors::AppErrorCode;
+use crate::wallet_data::KeyType;
+use base64::Engine;
+use bitcoin::absolute::LockTime;
+use bitcoin::consensus::encode::{deserialize, serialize};
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use bitcoin::key::{Keypair, TapTweak, XOnlyPublicKey};
+use bitcoin::opcodes::all::OP_RETURN;
+use bitcoin::opcodes::OP_0;
+use bitcoin::script::{Builder, PushBytes};
+use bitcoin::secp256k1::{Message, Secp256k1, Signing, Verification};
+use bitcoin::sighash::{EcdsaSighashType, Prevouts, SighashCache, TapSighashType};
+use bitcoin::sign_message::{signed_msg_hash, MessageSignature};
+use bitcoin::transaction::Version;
+use bitcoin::{
+    Address, AddressType, Amount, CompressedPublicKey, OutPoint, PrivateKey, Script, ScriptBuf,
+    Sequence, Transaction, TxIn, TxOut, Txid, Witness,
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Tag of the BIP340 tagged hash a BIP322 message is committed to
+const MESSAGE_TAG: &[u8] = b"BIP0322-signed-message";
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ProofError {
+    #[error("Invalid address '{0}'")]
+    InvalidAddress(String),
+
+    #[error("Ownership proofs are not supported for address '{0}'")]
+    UnsupportedAddress(String),
+
+    #[error("The key does not belong to address '{0}'")]
+    WrongKey(String),
+
+    #[error("Malformed ownership proof: {0}")]
+    Malformed(String),
+}
+
+impl ProofError {
+    /// Code the frontend can match on
+    pub fn code(&self) -> AppErrorCode {
+        match self {
+            ProofError::WrongKey(_) => AppErrorCode::KeyDerivationFailed,
+            _ => AppErrorCode::InvalidInput,
+        }
+    }
+}
+
+/// A message signed by the key of an address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnershipProof {
+    pub address: String,
+    pub message: String,
+    /// Base64 BIP322 signature
+    pub signature: String,
+}
+
+/// BIP322 message hash: the BIP340 tagged hash of the message
+pub fn message_hash(message: &[u8]) -> [u8; 32] {
+    let tag = sha256::Hash::hash(MESSAGE_TAG);
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag.as_ref());
+    engine.input(tag.as_ref());
+    engine.input(message);
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+/// The virtual transaction whose only output the proof spends
+fn to_spend(script_pubkey: &Script, message: &[u8]) -> Transaction {
+    Transaction {
+        version: Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint { txid: Txid::all_zeros(), vout: 0xFFFFFFFF },
+            script_sig: Builder::new().push_opcode(OP_0).push_slice(message_hash(message)).into_script(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut { value: Amount::ZERO, script_pubkey: script_pubkey.to_owned() }],
+    }
+}
+
+/// The virtual transaction that carries the signature
+fn to_sign(to_spend: &Transaction, script_sig: ScriptBuf, witness: Witness) -> Transaction {
+    Transaction {
+        version: Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint { txid: to_spend.compute_txid(), vout: 0 },
+            script_sig,
+            sequence: Sequence::ZERO,
+            witness,
+        }],
+        output: vec![TxOut { value: Amount::ZERO, script_pubkey: Builder::new().push_opcode(OP_RETURN).into_script() }],
+    }
+}
+
+/// Address of `key` for an address of type `key_type`, as the wallet derives it
+fn address_for<C: Signing + Verification>(secp: &Secp256k1<C>, key: &PrivateKey, key_type: &KeyType) -> Option<Address> {
+    let public_key = CompressedPublicKey::from_private_key(secp, key).ok()?;
+    Some(match key_type {
+        KeyType::Legacy => Address::p2pkh(public_key.pubkey_hash(), ChainParams::active().address_network),
+        KeyType::SegWit => Address::p2shwpkh(&public_key, ChainParams::active().address_network),
+        KeyType::NativeSegWit => Address::p2wpkh(&public_key, ChainParams::active().hrp()),
+        KeyType::Taproot => Address::p2tr(secp, public_key.0.x_only_public_key().0, None, ChainParams::active().hrp()),
+    })
+}
+
+/// Witness of a P2WPKH spend of `to_sign`'s input, with `script_code` the P2WPKH script
+fn p2wpkh_witness<C: Signing>(
+    secp: &Secp256k1<C>,
+    to_sign: &Transaction,
+    script_code: &Script,
+    key: &PrivateKey,
+) -> Result<Witness, ProofError> {
+    let sighash = SighashCache::new(to_sign)
+        .p2wpkh_signature_hash(0, script_code, Amount::ZERO, EcdsaSighashType::All)
+        .map_err(|e| ProofError::Malformed(e.to_string()))?;
+    let signature = bitcoin::ecdsa::Signature {
+        signature: secp.sign_ecdsa(&Message::from_digest(sighash.to_byte_array()), &key.inner),
+        sighash_type: EcdsaSighashType::All,
+    };
+    Ok(Witness::p2wpkh(&signature, &key.public_key(secp).inner))
+}
+
+/// Sign `message` with the key of `address`, whose type is `key_type`
+pub fn sign(address: &str, message: &str, key: &PrivateKey, key_type: &KeyType) -> Result<OwnershipProof, ProofError> {
+    let secp = Secp256k1::new();
+    let expected = address_for(&secp, key, key_type).ok_or_else(|| ProofError::WrongKey(address.to_string()))?;
+    if expected.to_string() != address {
+        return Err(ProofError::WrongKey(address.to_string()));
+    }
+
+    let to_spend = to_spend(&expected.script_pubkey(), message.as_bytes());
+    let signature = match key_type {
+        KeyType::Legacy => {
+            let digest = Message::from_digest(signed_msg_hash(message).to_byte_array());
+            MessageSignature::new(secp.sign_ecdsa_recoverable(&digest, &key.inner), true).serialize().to_vec()
+        }
+        KeyType::NativeSegWit => {
+            let unsigned = to_sign(&to_spend, ScriptBuf::new(), Witness::new());
+            serialize(&p2wpkh_witness(&secp, &unsigned, &expected.script_pubkey(), key)?)
+        }
+        KeyType::SegWit => {
+            let public_key = CompressedPublicKey::from_private_key(&secp, key).map_err(|e| ProofError::Malformed(e.to_string()))?;
+            let redeem_script = ScriptBuf::new_p2wpkh(&public_key.wpubkey_hash());
+            let push: &PushBytes = redeem_script.as_bytes().try_into().map_err(|_| ProofError::UnsupportedAddress(address.to_string()))?;
+            let script_sig = Builder::new().push_slice(push).into_script();
+            let mut signed = to_sign(&to_spend, script_sig, Witness::new());
+            signed.input[0].witness = p2wpkh_witness(&secp, &signed, &redeem_script, key)?;
+            serialize(&signed)
+        }
+        KeyType::Taproot => {
+            let unsigned = to_sign(&to_spend, ScriptBuf::new(), Witness::new());
+            let sighash = SighashCache::new(&unsigned)
+                .taproot_key_spend_signature_hash(0, &Prevouts::All(to_spend.output.as_slice()), TapSighashType::Default)
+                .map_err(|e| ProofError::Malformed(e.to_string()))?;
+            let keypair = Keypair::from_secret_key(&secp, &key.inner).tap_tweak(&secp, None).to_keypair();
+            let signature = bitcoin::taproot::Signature {
+                signature: secp.sign_schnorr_no_aux_rand(&Message::from_digest(sighash.to_byte_array()), &keypair),
+                sighash_type: TapSighashType::Default,
+            };
+            serialize(&Witness::p2tr_key_spend(&signature))
+        }
+    };
+
+    Ok(OwnershipProof {
+        address: address.to_string(),
+        message: message.to_string(),
+        signature: base64::engine::general_purpose::STANDARD.encode(signature),
+    })
+}
+
+/// Check a P2WPKH witness spending `to_sign`'s input, with `script_code` the P2WPKH script
+fn verify_p2wpkh<C: Verification>(secp: &Secp256k1<C>, to_sign: &Transaction, script_code: &Script) -> bool {
+    let witness = &to_sign.input[0].witness;
+    let (Some(signature), Some(public_key), 2) = (witness.nth(0), witness.nth(1), witness.len()) else {
+        return false;
+    };
+    let (Ok(signature), Ok(public_key)) = (bitcoin::ecdsa::Signature::from_slice(signature), CompressedPublicKey::from_slice(public_key)) else {
+        return false;
+    };
+    if signature.sighash_type != EcdsaSighashType::All || ScriptBuf::new_p2wpkh(&public_key.wpubkey_hash()).as_script() != script_code {
+        return false;
+    }
+    let Ok(sighash) = SighashCache::new(to_sign).p2wpkh_signature_hash(0, script_code, Amount::ZERO, EcdsaSighashType::All) else {
+        return false;
+    };
+    secp.verify_ecdsa(&Message::from_digest(sighash.to_byte_array()), &signature.signature, &public_key.0).is_ok()
+}
+
+/// Whether `proof` holds: its signature was made by the key of its address over its message
+pub fn verify(proof: &OwnershipProof) -> Result<bool, ProofError> {
+    let address = Address::from_str(proof.address.trim())
+        .map_err(|_| ProofError::InvalidAddress(proof.address.clone()))?
+        .assume_checked();
+    let signature = base64::engine::general_purpose::STANDARD
+        .decode(proof.signature.trim())
+        .map_err(|e| ProofError::Malformed(e.to_string()))?;
+    let secp = Secp256k1::verification_only();
+    let message = proof.message.as_bytes();
+
+    if address.address_type() == Some(AddressType::P2pkh) {
+        let signature = MessageSignature::from_slice(&signature).map_err(|e| ProofError::Malformed(e.to_string()))?;
+        return Ok(signature.is_signed_by_address(&secp, &address, signed_msg_hash(&proof.message)).unwrap_or(false));
+    }
+
+    let script_pubkey = address.script_pubkey();
+    let to_spend = to_spend(&script_pubkey, message);
+    // A simple proof is the witness alone; anything else must be the whole signing transaction
+    let signed = match deserialize::<Witness>(&signature) {
+        Ok(witness) => to_sign(&to_spend, ScriptBuf::new(), witness),
+        Err(_) => {
+            let signed: Transaction = deserialize(&signature).map_err(|e| ProofError::Malformed(e.to_string()))?;
+            let expected = to_sign(&to_spend, signed.input.first().map(|input| input.script_sig.clone()).unwrap_or_default(), Witness::new());
+            let matches_template = signed.version == expected.version
+                && signed.lock_time == expected.lock_time
+                && signed.input.len() == 1
+                && signed.input[0].previous_output == expected.input[0].previous_output
+                && signed.input[0].sequence == expected.input[0].sequence
+                && signed.output == expected.output;
+            if !matches_template {
+                return Ok(false);
+            }
+            signed
+        }
+    };
+    let script_sig = &signed.input[0].script_sig;
+
+    Ok(match address.address_type() {
+        Some(AddressType::P2wpkh) => script_sig.is_empty() && verify_p2wpkh(&secp, &signed, &script_pubkey),
+        Some(AddressType::P2sh) => {
+            // Nested segwit: the script sig pushes the P2WPKH redeem script the address hashes
+            let redeem_script = match script_sig.instructions().collect::<Result<Vec<_>, _>>() {
+                Ok(pushes) => match pushes.as_slice() {
+                    [bitcoin::script::Instruction::PushBytes(bytes)] => ScriptBuf::from_bytes(bytes.as_bytes().to_vec()),
+                    _ => return Ok(false),
+                },
+                Err(_) => return Ok(false),
+            };
+            ScriptBuf::new_p2sh(&redeem_script.script_hash()) == script_pubkey
+                && redeem_script.is_p2wpkh()
+                && verify_p2wpkh(&secp, &signed, &redeem_script)
+        }
+        Some(AddressType::P2tr) => {
+            let witness = &signed.input[0].witness;
+            let (Some(signature), 1) = (witness.nth(0), witness.len()) else {
+                return Ok(false);
+            };
+            let (Ok(signature), Ok(output_key)) = (
+                bitcoin::taproot::Signature::from_slice(signature),
+                XOnlyPublicKey::from_slice(&script_pubkey.as_bytes()[2..]),
+            ) else {
+                return Ok(false);
+            };
+            let Ok(sighash) = SighashCache::new(&signed)
+                .taproot_key_spend_signature_hash(0, &Prevouts::All(to_spend.output.as_slice()), signature.sighash_type)
+            else {
+                return Ok(false);
+            };
+            script_sig.is_empty()
+                && secp
+                    .verify_schnorr(&signature.signature, &Message::from_digest(sighash.to_byte_array()), &output_key)
+                    .is_ok()
+        }
+        _ => return Err(ProofError::UnsupportedAddress(proof.address.clone())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIF: &str = "L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k";
+
+    #[test]
+    fn test_proofs_verify_for_every_address_type() {
+        // Test vectors from BIP322
+        assert_eq!(hex::encode(message_hash(b"")), "c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1");
+        let vector = OwnershipProof {
+            address: "bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l".to_string(),
+            message: "Hello World".to_string(),
+            signature: "AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptFluMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=".to_string(),
+        };
+        assert_eq!(verify(&vector), Ok(true));
+
+        let key = PrivateKey::from_wif(WIF).unwrap();
+        let secp = Secp256k1::new();
+        for key_type in [KeyType::Legacy, KeyType::SegWit, KeyType::NativeSegWit, KeyType::Taproot] {
+            let address = address_for(&secp, &key, &key_type).unwrap().to_string();
+            let proof = sign(&address, "I own this address", &key, &key_type).unwrap();
+            assert_eq!(verify(&proof), Ok(true), "{:?}", key_type);
+
+            let tampered = OwnershipProof { message: "I own that address".to_string(), ..proof };
+            assert_eq!(verify(&tampered), Ok(false), "{:?}", key_type);
+        }
+
+        assert_eq!(
+            sign("bc1qorphan", "", &key, &KeyType::NativeSegWit),
+            Err(ProofError::WrongKey("bc1qorphan".to_string()))
+        );
+    }
+}
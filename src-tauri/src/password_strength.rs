@@ -0,0 +1,106 @@
+//! Password strength estimation for wallet passwords.
+//!
+//! Passwords are scored with zxcvbn, which estimates how many guesses an attacker needs by
+//! matching dictionary words, keyboard patterns, dates and repeats rather than counting character
+//! classes. Scores run from 0 (too guessable) to 4 (very unguessable). Wallets are only secured
+//! with passwords that reach the minimum score in the app settings.
+
+use serde::Serialize;
+use zxcvbn::time_estimates::CrackTimeSeconds;
+
+/// Highest score zxcvbn gives
+pub const MAX_PASSWORD_SCORE: u8 = 4;
+
+/// Minimum score for wallet passwords unless configured otherwise
+pub const DEFAULT_MIN_PASSWORD_SCORE: u8 = 3;
+
+/// Estimated strength of a password
+#[derive(Debug, Clone, Serialize)]
+pub struct PasswordStrength {
+    /// 0 to 4
+    pub score: u8,
+    /// Estimated entropy in bits, from the number of guesses needed
+    pub entropy_bits: f64,
+    /// Seconds an offline attacker needs at 10,000 guesses per second, the rate slow key
+    /// derivation like the wallet's allows
+    pub crack_time_seconds: f64,
+    /// The same estimate for display, e.g. "3 hours"
+    pub crack_time_display: String,
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+    /// Whether the score reaches the configured minimum
+    pub meets_minimum: bool,
+}
+
+/// Check a configured minimum score
+pub fn validate_min_score(min_score: u8) -> Result<(), String> {
+    if min_score > MAX_PASSWORD_SCORE {
+        return Err(format!("Minimum password score must be between 0 and {}", MAX_PASSWORD_SCORE));
+    }
+    Ok(())
+}
+
+/// Estimate the strength of `password`. `user_inputs` are words that make the password easier to
+/// guess in context, such as the wallet name.
+pub fn evaluate(password: &str, user_inputs: &[&str], min_score: u8) -> PasswordStrength {
+    let estimate = zxcvbn::zxcvbn(password, user_inputs);
+    let score = u8::from(estimate.score());
+    let crack_time = estimate.crack_times().offline_slow_hashing_1e4_per_second();
+    let (warning, suggestions) = match estimate.feedback() {
+        Some(feedback) => (
+            feedback.warning().map(|warning| warning.to_string()),
+            feedback.suggestions().iter().map(|suggestion| suggestion.to_string()).collect(),
+        ),
+        None => (None, Vec::new()),
+    };
+
+    PasswordStrength {
+        score,
+        entropy_bits: estimate.guesses_log10() * std::f64::consts::LOG2_10,
+        crack_time_seconds: match crack_time {
+            CrackTimeSeconds::Integer(seconds) => seconds as f64,
+            CrackTimeSeconds::Float(seconds) => seconds,
+        },
+        crack_time_display: crack_time.to_string(),
+        warning,
+        suggestions,
+        meets_minimum: score >= min_score,
+    }
+}
+
+/// Reject a password below `min_score`, explaining why
+pub fn require_minimum(password: &str, user_inputs: &[&str], min_score: u8) -> Result<(), String> {
+    let strength = evaluate(password, user_inputs, min_score);
+    if strength.meets_minimum {
+        return Ok(());
+    }
+    let mut message = format!(
+        "Password is too weak (score {} of {}, at least {} required)",
+        strength.score, MAX_PASSWORD_SCORE, min_score
+    );
+    if let Some(warning) = strength.warning {
+        message.push_str(&format!(": {}", warning));
+    }
+    Err(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guessable_passwords_are_rejected() {
+        let weak = evaluate("password123", &[], DEFAULT_MIN_PASSWORD_SCORE);
+        assert!(weak.score < DEFAULT_MIN_PASSWORD_SCORE);
+        assert!(!weak.meets_minimum);
+
+        let strong = evaluate("correct-staple-orbit-violin-gravel", &[], DEFAULT_MIN_PASSWORD_SCORE);
+        assert_eq!(strong.score, MAX_PASSWORD_SCORE);
+        assert!(strong.entropy_bits > weak.entropy_bits);
+
+        // Words from the context, like the wallet name, count against the password
+        assert!(require_minimum("mysavingswallet", &[], DEFAULT_MIN_PASSWORD_SCORE).is_ok());
+        assert!(require_minimum("mysavingswallet", &["mysavingswallet"], DEFAULT_MIN_PASSWORD_SCORE).is_err());
+        assert!(validate_min_score(MAX_PASSWORD_SCORE + 1).is_err());
+    }
+}
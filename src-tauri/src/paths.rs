@@ -0,0 +1,136 @@
+use log::{debug, info, warn};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Application identifier, matching the identifier in tauri.conf.json
+pub const APP_IDENTIFIER: &str = "com.b-rad-coin.app";
+
+/// Command line flag that overrides the data directory root
+pub const DATA_DIR_FLAG: &str = "--data-dir";
+
+/// File in the default data directory that points at a user-chosen data directory.
+/// The override cannot live in app_config.json because the config itself lives under the data directory.
+const DATA_DIR_POINTER_FILE: &str = "data_dir";
+
+/// Data directory override chosen at startup (CLI flag or pointer file)
+static DATA_DIR_OVERRIDE: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Single place that decides where config, wallets, logs and blockchain data live
+pub struct PathProvider;
+
+impl PathProvider {
+    /// Resolve the data directory override from the command line or the pointer file.
+    /// Must be called once at startup before any path is used.
+    pub fn init_from_args<I: IntoIterator<Item = String>>(args: I) {
+        let from_args = Self::parse_data_dir_arg(args);
+        let data_dir = from_args.or_else(Self::read_pointer_file);
+
+        if DATA_DIR_OVERRIDE.set(data_dir).is_err() {
+            warn!("Data directory was already initialized, ignoring second initialization");
+        }
+    }
+
+    fn parse_data_dir_arg<I: IntoIterator<Item = String>>(args: I) -> Option<PathBuf> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == DATA_DIR_FLAG {
+                return args.next().map(PathBuf::from);
+            }
+            if let Some(value) = arg.strip_prefix(&format!("{}=", DATA_DIR_FLAG)) {
+                return Some(PathBuf::from(value));
+            }
+        }
+        None
+    }
+
+    fn read_pointer_file() -> Option<PathBuf> {
+        let pointer = Self::default_data_dir()?.join(DATA_DIR_POINTER_FILE);
+        let contents = std::fs::read_to_string(pointer).ok()?;
+        let trimmed = contents.trim();
+        if trimmed.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(trimmed))
+        }
+    }
+
+    /// Platform default data directory, ignoring any override
+    pub fn default_data_dir() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join(APP_IDENTIFIER))
+    }
+
+    /// Root directory under which all application data lives
+    pub fn data_dir() -> Option<PathBuf> {
+        match DATA_DIR_OVERRIDE.get() {
+            Some(Some(dir)) => Some(dir.clone()),
+            _ => Self::default_data_dir(),
+        }
+    }
+
+    /// Whether the data directory comes from an override rather than the platform default
+    pub fn is_overridden() -> bool {
+        matches!(DATA_DIR_OVERRIDE.get(), Some(Some(_)))
+    }
+
+    /// Directory holding app_config.json
+    pub fn config_dir() -> Option<PathBuf> {
+        Self::data_dir().map(|dir| dir.join("config"))
+    }
+
+    /// Directory holding one sub-directory per wallet
+    pub fn wallets_dir() -> Option<PathBuf> {
+        Self::data_dir().map(|dir| dir.join("wallets"))
+    }
+
+    /// Directory holding log files
+    pub fn logs_dir() -> Option<PathBuf> {
+        Self::data_dir().map(|dir| dir.join("logs"))
+    }
+
+    /// Default directory for the blockchain database
+    pub fn blockchain_dir() -> Option<PathBuf> {
+        Self::data_dir().map(|dir| dir.join("blockchain"))
+    }
+
+    /// Resolve a wallet path from the config. Relative paths live under the data directory;
+    /// older installs stored them relative to the working directory, which is still honoured if present.
+    pub fn resolve_wallet_path(path: &str) -> PathBuf {
+        let path = Path::new(path);
+        if path.is_absolute() {
+            return path.to_path_buf();
+        }
+
+        if let Some(data_dir) = Self::data_dir() {
+            let resolved = data_dir.join(path);
+            if resolved.exists() || !path.exists() {
+                return resolved;
+            }
+        }
+
+        debug!("Using legacy working-directory wallet path: {}", path.display());
+        path.to_path_buf()
+    }
+
+    /// Persist a data directory override for the next start, or clear it with None
+    pub fn set_persisted_data_dir(data_dir: Option<&Path>) -> std::io::Result<()> {
+        let default_dir = Self::default_data_dir().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "Failed to determine app data directory")
+        })?;
+        let pointer = default_dir.join(DATA_DIR_POINTER_FILE);
+
+        match data_dir {
+            Some(dir) => {
+                std::fs::create_dir_all(&default_dir)?;
+                std::fs::write(&pointer, dir.to_string_lossy().as_bytes())?;
+                info!("Data directory set to {} from next start", dir.display());
+            }
+            None => {
+                if pointer.exists() {
+                    std::fs::remove_file(&pointer)?;
+                }
+                info!("Data directory override cleared from next start");
+            }
+        }
+        Ok(())
+    }
+}
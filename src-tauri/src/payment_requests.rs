@@ -0,0 +1,198 @@
+//! Receive-side payment requests.
+//!
+//! Each request gets a fresh address, so every coin arriving on that address counts towards it.
+//! Wallet sync passes the wallet's coins to `PaymentRequestBook::record_sync`, which moves requests
+//! from pending to partial or paid. A request that is not fully paid by its expiry is expired;
+//! coins arriving later are still counted and can complete it.
+
+use crate::atomic_file;
+use crate::wallet_data::{Utxo, WalletDataError};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// File name of the payment requests, stored next to wallet.dat.
+/// Kept outside wallet.dat so sync can update it without the wallet password.
+pub const PAYMENT_REQUESTS_FILE: &str = "payment_requests.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentRequestStatus {
+    /// Nothing received yet
+    Pending,
+    /// Less than the requested amount received
+    Partial,
+    /// The requested amount or more received
+    Paid,
+    /// Not fully paid before the expiry
+    Expired,
+}
+
+/// A request for a payment to a fresh address of the wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentRequest {
+    pub id: String,
+    pub address: String,
+    /// Requested amount in satoshis
+    pub amount: u64,
+    pub label: Option<String>,
+    pub message: Option<String>,
+    /// brad:// URI to share with the payer
+    pub uri: String,
+    pub created_at: i64,
+    /// Unix timestamp after which the request expires; None never expires
+    pub expires_at: Option<i64>,
+    pub status: PaymentRequestStatus,
+    /// Satoshis received on the address so far
+    pub received: u64,
+    pub paid_at: Option<i64>,
+    /// Outputs ("txid:vout") that paid towards the request
+    #[serde(default)]
+    pub outputs: BTreeSet<String>,
+}
+
+impl PaymentRequest {
+    fn status_at(&self, now: i64) -> PaymentRequestStatus {
+        if self.received >= self.amount {
+            PaymentRequestStatus::Paid
+        } else if self.expires_at.is_some_and(|expires_at| now >= expires_at) {
+            PaymentRequestStatus::Expired
+        } else if self.received > 0 {
+            PaymentRequestStatus::Partial
+        } else {
+            PaymentRequestStatus::Pending
+        }
+    }
+}
+
+/// Payload of `payment-request-paid` and `payment-request-updated`
+#[derive(Debug, Clone, Serialize)]
+pub struct PaymentRequestEvent {
+    pub wallet_name: String,
+    pub request: PaymentRequest,
+}
+
+/// Payment requests of one wallet
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PaymentRequestBook {
+    #[serde(default)]
+    requests: Vec<PaymentRequest>,
+}
+
+impl PaymentRequestBook {
+    /// Load the requests from a wallet directory, starting empty if there are none yet
+    pub fn load(wallet_dir: &Path) -> Result<Self, WalletDataError> {
+        let path = wallet_dir.join(PAYMENT_REQUESTS_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Save the requests into a wallet directory
+    pub fn save(&self, wallet_dir: &Path) -> Result<(), WalletDataError> {
+        let path = wallet_dir.join(PAYMENT_REQUESTS_FILE);
+        atomic_file::write_atomic(&path, serde_json::to_string_pretty(self)?.as_bytes())?;
+        debug!("Payment requests saved to {}", path.display());
+        Ok(())
+    }
+
+    pub fn add(&mut self, request: PaymentRequest) {
+        self.requests.push(request);
+    }
+
+    /// Every request, newest first
+    pub fn requests(&self) -> Vec<PaymentRequest> {
+        let mut requests = self.requests.clone();
+        requests.sort_by_key(|request| Reverse(request.created_at));
+        requests
+    }
+
+    /// Delete a request, returning whether it existed
+    pub fn remove(&mut self, id: &str) -> bool {
+        let before = self.requests.len();
+        self.requests.retain(|request| request.id != id);
+        self.requests.len() != before
+    }
+
+    /// Count newly seen coins on request addresses and expire overdue requests.
+    /// Returns the requests whose status changed.
+    pub fn record_sync(&mut self, utxos: &[Utxo], now: i64) -> Vec<PaymentRequest> {
+        let mut changed = Vec::new();
+        for request in self.requests.iter_mut().filter(|r| r.status != PaymentRequestStatus::Paid) {
+            for utxo in utxos.iter().filter(|utxo| utxo.address == request.address) {
+                if request.outputs.insert(format!("{}:{}", utxo.txid, utxo.vout)) {
+                    request.received += utxo.value;
+                }
+            }
+
+            let status = request.status_at(now);
+            if status != request.status {
+                request.status = status;
+                if status == PaymentRequestStatus::Paid {
+                    request.paid_at = Some(now);
+                }
+                changed.push(request.clone());
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utxo(txid: &str, address: &str, value: u64) -> Utxo {
+        Utxo {
+            txid: txid.to_string(),
+            vout: 0,
+            value,
+            script_pubkey: String::new(),
+            address: address.to_string(),
+            is_change: false,
+            height: Some(1),
+        }
+    }
+
+    fn request(address: &str, amount: u64, expires_at: Option<i64>) -> PaymentRequest {
+        PaymentRequest {
+            id: address.to_string(),
+            address: address.to_string(),
+            amount,
+            label: None,
+            message: None,
+            uri: String::new(),
+            created_at: 0,
+            expires_at,
+            status: PaymentRequestStatus::Pending,
+            received: 0,
+            paid_at: None,
+            outputs: BTreeSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_requests_move_from_partial_to_paid_or_expired() {
+        let mut book = PaymentRequestBook::default();
+        book.add(request("a", 1_000, None));
+        book.add(request("b", 1_000, Some(100)));
+
+        let changed = book.record_sync(&[utxo("t1", "a", 400), utxo("t2", "other", 5_000)], 10);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].status, PaymentRequestStatus::Partial);
+
+        // The same coin seen again is not counted twice
+        assert!(book.record_sync(&[utxo("t1", "a", 400)], 20).is_empty());
+
+        let changed = book.record_sync(&[utxo("t1", "a", 400), utxo("t3", "a", 600)], 150);
+        let statuses: Vec<_> = changed.iter().map(|r| (r.id.as_str(), r.status)).collect();
+        assert_eq!(statuses, [("a", PaymentRequestStatus::Paid), ("b", PaymentRequestStatus::Expired)]);
+        assert_eq!(changed[0].paid_at, Some(150));
+    }
+}
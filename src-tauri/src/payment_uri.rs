@@ -0,0 +1,225 @@
+//! brad:// payment URIs, e.g. `brad://pay?address=bc1...&amount=1.5&label=Coffee`
+//!
+//! URIs arrive through the deep-link plugin, either at launch or forwarded from a second launch.
+//! A valid request is kept until the send screen takes it, since the frontend may still be loading.
+
+use crate::chain_params::ChainParams;
+use crate::events::{self, EmitEvent};
+use crate::instance;
+use crate::validation::{self, ValidationError};
+use log::{info, warn};
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Url};
+use thiserror::Error;
+
+/// URI scheme registered for payment links
+pub const PAYMENT_URI_SCHEME: &str = "brad";
+
+/// Error type for payment URI parsing
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PaymentUriError {
+    #[error("Not a payment link: {0}")]
+    Malformed(String),
+
+    #[error("Unsupported payment link action '{0}'")]
+    UnsupportedAction(String),
+
+    #[error("Payment link parameter '{0}' is repeated or not supported")]
+    InvalidParameter(String),
+
+    #[error(transparent)]
+    Invalid(#[from] ValidationError),
+}
+
+/// A parsed payment request used to pre-fill the send screen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PaymentUri {
+    pub address: String,
+    /// Requested amount in satoshis
+    pub amount: Option<u64>,
+    pub label: Option<String>,
+    pub message: Option<String>,
+}
+
+/// Payload of the "payment-uri-error" event
+#[derive(Debug, Clone, Serialize)]
+pub struct PaymentUriErrorEvent {
+    pub uri: String,
+    pub error: String,
+}
+
+/// Last payment request not yet taken by the frontend
+#[derive(Default)]
+pub struct PendingPaymentUri(Mutex<Option<PaymentUri>>);
+
+impl PendingPaymentUri {
+    /// Take the pending request, leaving none behind
+    pub fn take(&self) -> Option<PaymentUri> {
+        self.0.lock().unwrap().take()
+    }
+
+    fn set(&self, payment: PaymentUri) {
+        *self.0.lock().unwrap() = Some(payment);
+    }
+}
+
+/// Parse and validate a brad:// payment URI
+pub fn parse_payment_uri(uri: &str) -> Result<PaymentUri, PaymentUriError> {
+    let url = Url::parse(uri.trim()).map_err(|e| PaymentUriError::Malformed(e.to_string()))?;
+    if !url.scheme().eq_ignore_ascii_case(PAYMENT_URI_SCHEME) {
+        return Err(PaymentUriError::Malformed(format!(
+            "expected a {}:// link",
+            PAYMENT_URI_SCHEME
+        )));
+    }
+
+    // Both brad://pay?... and brad:pay?... name the action
+    let action = url
+        .host_str()
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| url.path().trim_matches('/'));
+    if !action.eq_ignore_ascii_case("pay") {
+        return Err(PaymentUriError::UnsupportedAction(action.to_string()));
+    }
+
+    let mut address = None;
+    let mut amount = None;
+    let mut label = None;
+    let mut message = None;
+    for (key, value) in url.query_pairs() {
+        let slot = match key.as_ref() {
+            "address" => &mut address,
+            "amount" => &mut amount,
+            "label" => &mut label,
+            "message" => &mut message,
+            // Unknown optional parameters are ignored; required ones ("req-") cannot be honoured
+            other if other.starts_with("req-") => {
+                return Err(PaymentUriError::InvalidParameter(other.to_string()))
+            }
+            _ => continue,
+        };
+        if slot.replace(value.into_owned()).is_some() {
+            return Err(PaymentUriError::InvalidParameter(key.into_owned()));
+        }
+    }
+
+    let address = validation::validate_address(address.as_deref().unwrap_or(""), ChainParams::active().network)?;
+    let amount = amount.map(|a| validation::parse_amount(&a)).transpose()?;
+
+    Ok(PaymentUri {
+        address,
+        amount,
+        label: label.filter(|l| !l.is_empty()),
+        message: message.filter(|m| !m.is_empty()),
+    })
+}
+
+/// Build a brad:// payment URI, the inverse of `parse_payment_uri`
+pub fn format_payment_uri(payment: &PaymentUri) -> String {
+    let mut url = Url::parse(&format!("{}://pay", PAYMENT_URI_SCHEME)).expect("payment URI base is valid");
+    {
+        let mut query = url.query_pairs_mut();
+        query.append_pair("address", &payment.address);
+        if let Some(amount) = payment.amount {
+            query.append_pair("amount", &format_coin_amount(amount));
+        }
+        if let Some(label) = &payment.label {
+            query.append_pair("label", label);
+        }
+        if let Some(message) = &payment.message {
+            query.append_pair("message", message);
+        }
+    }
+    url.to_string()
+}
+
+/// Satoshis as a decimal amount of BRAD without trailing zeros, e.g. "1.5"
+fn format_coin_amount(satoshis: u64) -> String {
+    let (whole, fraction) = (satoshis / 100_000_000, satoshis % 100_000_000);
+    if fraction == 0 {
+        return whole.to_string();
+    }
+    format!("{}.{:08}", whole, fraction).trim_end_matches('0').to_string()
+}
+
+/// Handle a payment URI opened from outside the app: keep it for the send screen, bring the
+/// window forward and tell the frontend. Malformed links are reported with "payment-uri-error".
+pub fn handle_payment_uri(app_handle: &AppHandle, uri: &str) {
+    info!("Received payment link");
+    match parse_payment_uri(uri) {
+        Ok(payment) => {
+            if let Some(pending) = app_handle.try_state::<PendingPaymentUri>() {
+                pending.set(payment.clone());
+            }
+            instance::focus_main_window(app_handle);
+            if let Err(e) = app_handle.emit_event(events::PAYMENT_URI_RECEIVED, &payment) {
+                warn!("Failed to emit payment-uri-received event: {}", e);
+            }
+        }
+        Err(e) => {
+            warn!("Rejected payment link: {}", e);
+            let event = PaymentUriErrorEvent {
+                uri: uri.to_string(),
+                error: e.to_string(),
+            };
+            if let Err(e) = app_handle.emit_event(events::PAYMENT_URI_ERROR, &event) {
+                warn!("Failed to emit payment-uri-error event: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+
+    #[test]
+    fn test_parse_payment_uri() {
+        let uri = format!("brad://pay?address={}&amount=1.5&label=Coffee%20shop", ADDRESS);
+        let payment = parse_payment_uri(&uri).unwrap();
+        assert_eq!(payment.address, ADDRESS);
+        assert_eq!(payment.amount, Some(150_000_000));
+        assert_eq!(payment.label.as_deref(), Some("Coffee shop"));
+        assert_eq!(payment.message, None);
+
+        let bare = parse_payment_uri(&format!("brad:pay?address={}", ADDRESS)).unwrap();
+        assert_eq!(bare.amount, None);
+
+        let formatted = format_payment_uri(&payment);
+        assert!(formatted.starts_with("brad://pay?address="));
+        assert_eq!(parse_payment_uri(&formatted).unwrap(), payment);
+    }
+
+    #[test]
+    fn test_invalid_payment_uris_are_rejected() {
+        let with_amount = |amount: &str| format!("brad://pay?address={}&amount={}", ADDRESS, amount);
+        for amount in ["", "abc", "-1", "1.123456789", "1e5", "1.2.3"] {
+            assert_eq!(
+                parse_payment_uri(&with_amount(amount)),
+                Err(ValidationError::InvalidAmount(amount.to_string()).into())
+            );
+        }
+        assert_eq!(parse_payment_uri(&with_amount("0")), Err(ValidationError::ZeroAmount.into()));
+
+        assert_eq!(
+            parse_payment_uri("brad://pay?address=notanaddress"),
+            Err(ValidationError::InvalidAddress("notanaddress".to_string()).into())
+        );
+        assert!(matches!(
+            parse_payment_uri("brad://pay?address=tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"),
+            Err(PaymentUriError::Invalid(ValidationError::WrongNetwork { .. }))
+        ));
+        assert_eq!(parse_payment_uri("brad://pay?amount=1"), Err(ValidationError::MissingAddress.into()));
+        assert!(matches!(
+            parse_payment_uri(&format!("brad://send?address={}", ADDRESS)),
+            Err(PaymentUriError::UnsupportedAction(_))
+        ));
+        assert!(matches!(
+            parse_payment_uri(&format!("brad://pay?address={}&req-expiry=1", ADDRESS)),
+            Err(PaymentUriError::InvalidParameter(_))
+        ));
+    }
+}
@@ -0,0 +1,429 @@
+//! Peer connection policy: how many peers to keep, which addresses to dial and which inbound
+//! peer to drop when the inbound slots are full.
+//!
+//! Outbound peers are chosen from distinct network groups (IPv4 /16, IPv6 /32) so a single
+//! operator or subnet cannot fill every outbound slot. When inbound slots are full, the
+//! lowest-scoring inbound peer makes room for the newcomer instead of every new peer being refused.
+//!
+//! Before that, `InboundGate` turns away inbound connections that would flood the node: more than
+//! `MAX_INBOUND_PER_IP` from one address, more than `MAX_INBOUND_PER_MINUTE` in total, and any from
+//! an address greylisted for connecting or dropping short connections again and again. Loopback
+//! addresses are exempt, so several local nodes can run side by side.
+//!
+//! In blocks-only mode the node needs few peers, so both limits are capped lower to save bandwidth.
+//!
+//! During the handshake, peers below the configured minimum protocol version are disconnected.
+//! The features both sides support are recorded on the connection; peers that serve no blocks
+//! are kept for transaction and address relay but not asked for blocks.
+
+use crate::config::AppSettings;
+use crate::network_constants::{
+    BLOCKS_ONLY_MAX_INBOUND_PEERS, BLOCKS_ONLY_MAX_OUTBOUND_PEERS, COMPACT_BLOCKS_VERSION, COMPACT_FILTERS_VERSION, LOCAL_SERVICES, MAX_INBOUND_PEERS, MAX_INBOUND_PER_IP,
+    MAX_INBOUND_PER_MINUTE, MAX_OUTBOUND_PEERS, MAX_PEERS, MIN_PROTOCOL_VERSION, NODE_COMPACT_BLOCKS,
+    NODE_COMPACT_FILTERS, NODE_NETWORK, NODE_NETWORK_LIMITED, PROTOCOL_VERSION,
+};
+use crate::network_service::{PeerAddress, PeerConnection};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+/// Window over which connection attempts and short connections are counted
+const CHURN_WINDOW: Duration = Duration::from_secs(60);
+
+/// Connection attempts from one address within `CHURN_WINDOW` that get it greylisted
+const MAX_ATTEMPTS_PER_IP: usize = 6;
+
+/// Connections shorter than this count as churn
+const SHORT_CONNECTION: Duration = Duration::from_secs(10);
+
+/// Short connections from one address within `CHURN_WINDOW` that get it greylisted
+const MAX_SHORT_CONNECTIONS_PER_IP: usize = 3;
+
+/// How long a greylisted address is refused
+const GREYLIST_DURATION: Duration = Duration::from_secs(10 * 60);
+
+/// Connection limits, read from the settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionLimits {
+    pub max_outbound: usize,
+    pub max_inbound: usize,
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        Self {
+            max_outbound: MAX_OUTBOUND_PEERS,
+            max_inbound: MAX_INBOUND_PEERS,
+        }
+    }
+}
+
+impl ConnectionLimits {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        let limits = Self {
+            max_outbound: settings.max_outbound_peers as usize,
+            max_inbound: settings.max_inbound_peers as usize,
+        };
+        if settings.blocks_only {
+            Self {
+                max_outbound: limits.max_outbound.min(BLOCKS_ONLY_MAX_OUTBOUND_PEERS),
+                max_inbound: limits.max_inbound.min(BLOCKS_ONLY_MAX_INBOUND_PEERS),
+            }
+        } else {
+            limits
+        }
+    }
+}
+
+/// Check limits from settings: at least one outbound peer and no more than `MAX_PEERS` in total
+pub fn validate_connection_limits(max_outbound: u32, max_inbound: u32) -> Result<(), String> {
+    if max_outbound == 0 {
+        return Err("At least one outbound peer is required".to_string());
+    }
+    if max_outbound as usize + max_inbound as usize > MAX_PEERS {
+        return Err(format!(
+            "Outbound and inbound peers together may not exceed {}",
+            MAX_PEERS
+        ));
+    }
+    Ok(())
+}
+
+/// Check a configured minimum peer protocol version: it cannot drop below what this node
+/// understands or exclude nodes running this very version
+pub fn validate_min_protocol_version(version: u32) -> Result<(), String> {
+    if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version) {
+        return Err(format!(
+            "Minimum peer protocol version must be between {} and {}",
+            MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        ));
+    }
+    Ok(())
+}
+
+/// Features negotiated with a peer in the handshake: those it advertises, that its protocol
+/// version supports and that this node uses too
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PeerFeatures {
+    pub protocol_version: u32,
+    /// Service bits the peer advertised
+    pub services: u64,
+    /// Serves the whole chain
+    pub full_blocks: bool,
+    /// Serves recent blocks only
+    pub limited_blocks: bool,
+    pub compact_filters: bool,
+    pub compact_blocks: bool,
+}
+
+impl PeerFeatures {
+    pub fn negotiate(protocol_version: u32, services: u64) -> Self {
+        let shared = |bit: u64, since: u32| services & bit != 0 && LOCAL_SERVICES & bit != 0 && protocol_version >= since;
+        Self {
+            protocol_version,
+            services,
+            full_blocks: services & NODE_NETWORK != 0,
+            limited_blocks: services & NODE_NETWORK_LIMITED != 0,
+            compact_filters: shared(NODE_COMPACT_FILTERS, COMPACT_FILTERS_VERSION),
+            compact_blocks: shared(NODE_COMPACT_BLOCKS, COMPACT_BLOCKS_VERSION),
+        }
+    }
+
+    /// Whether blocks can be downloaded from the peer
+    pub fn serves_blocks(&self) -> bool {
+        self.full_blocks || self.limited_blocks
+    }
+}
+
+/// Why a peer was disconnected during the handshake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeRejection {
+    ObsoleteVersion { version: u32, minimum: u32 },
+}
+
+impl fmt::Display for HandshakeRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandshakeRejection::ObsoleteVersion { version, minimum } => {
+                write!(f, "protocol version {} is below the minimum {}", version, minimum)
+            }
+        }
+    }
+}
+
+/// Decide on a peer's Version message
+pub fn check_handshake(protocol_version: u32, services: u64, min_version: u32) -> Result<PeerFeatures, HandshakeRejection> {
+    if protocol_version < min_version {
+        return Err(HandshakeRejection::ObsoleteVersion { version: protocol_version, minimum: min_version });
+    }
+    Ok(PeerFeatures::negotiate(protocol_version, services))
+}
+
+/// Parse the interface addresses to listen on from settings
+pub fn parse_bind_addresses(addresses: &[String]) -> Result<Vec<IpAddr>, String> {
+    addresses
+        .iter()
+        .map(|address| {
+            address
+                .trim()
+                .parse::<IpAddr>()
+                .map_err(|_| format!("'{}' is not an IP address", address))
+        })
+        .collect()
+}
+
+/// Network group of an address: the IPv4 /16 or IPv6 /32 it belongs to
+pub fn network_group(ip: IpAddr) -> Vec<u8> {
+    match ip {
+        IpAddr::V4(v4) => v4.octets()[..2].to_vec(),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => v4.octets()[..2].to_vec(),
+            None => v6.octets()[..4].to_vec(),
+        },
+    }
+}
+
+/// Pick up to `slots` addresses to dial, at most one per network group and none in a group
+/// an outbound peer already occupies. Candidates are taken in the given order.
+pub fn select_outbound<'a>(
+    candidates: impl IntoIterator<Item = &'a PeerAddress>,
+    connected_outbound: impl IntoIterator<Item = IpAddr>,
+    slots: usize,
+) -> Vec<PeerAddress> {
+    let mut used_groups: HashSet<Vec<u8>> = connected_outbound.into_iter().map(network_group).collect();
+
+    let mut selected = Vec::new();
+    for candidate in candidates {
+        if selected.len() >= slots {
+            break;
+        }
+        if used_groups.insert(network_group(candidate.ip)) {
+            selected.push(candidate.clone());
+        }
+    }
+    selected
+}
+
+/// The inbound peer to disconnect to make room for a new one: lowest total score, and among
+/// equal scores the most recently connected, so long-standing peers are kept
+pub fn select_inbound_eviction<'a>(
+    peers: impl IntoIterator<Item = (&'a SocketAddr, &'a PeerConnection)>,
+) -> Option<SocketAddr> {
+    peers
+        .into_iter()
+        .filter(|(_, peer)| !peer.is_outbound)
+        .min_by_key(|(_, peer)| (peer.score.calculate_total_score(), std::cmp::Reverse(peer.connected_at)))
+        .map(|(addr, _)| *addr)
+}
+
+/// Why an inbound connection was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InboundRefusal {
+    TooManyFromAddress,
+    TooManyConnecting,
+    Greylisted,
+}
+
+impl fmt::Display for InboundRefusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InboundRefusal::TooManyFromAddress => write!(f, "{} connections from this address already", MAX_INBOUND_PER_IP),
+            InboundRefusal::TooManyConnecting => write!(f, "more than {} inbound connections in the last minute", MAX_INBOUND_PER_MINUTE),
+            InboundRefusal::Greylisted => write!(f, "address is greylisted for connecting too often"),
+        }
+    }
+}
+
+/// Rate limits and greylist for inbound connections
+#[derive(Debug, Default)]
+pub struct InboundGate {
+    /// Accepted connections, oldest first
+    accepted: VecDeque<Instant>,
+    /// Recent connection attempts per address
+    attempts: HashMap<IpAddr, VecDeque<Instant>>,
+    /// Recent short connections per address
+    short_connections: HashMap<IpAddr, VecDeque<Instant>>,
+    /// Greylisted addresses and when they are let in again
+    greylist: HashMap<IpAddr, Instant>,
+}
+
+/// Drop the times that have left the window and return how many remain
+fn count_recent(times: &mut VecDeque<Instant>, now: Instant) -> usize {
+    while times.front().is_some_and(|&time| now.duration_since(time) > CHURN_WINDOW) {
+        times.pop_front();
+    }
+    times.len()
+}
+
+impl InboundGate {
+    /// Decide whether to accept a connection from `ip`, which already has `connected` inbound peers
+    pub fn admit(&mut self, ip: IpAddr, connected: usize, now: Instant) -> Result<(), InboundRefusal> {
+        if ip.is_loopback() {
+            return Ok(());
+        }
+        self.forget_stale(now);
+
+        if self.greylist.contains_key(&ip) {
+            return Err(InboundRefusal::Greylisted);
+        }
+
+        let attempts = self.attempts.entry(ip).or_default();
+        attempts.push_back(now);
+        if count_recent(attempts, now) > MAX_ATTEMPTS_PER_IP {
+            self.greylist.insert(ip, now + GREYLIST_DURATION);
+            return Err(InboundRefusal::Greylisted);
+        }
+        if connected >= MAX_INBOUND_PER_IP {
+            return Err(InboundRefusal::TooManyFromAddress);
+        }
+        if count_recent(&mut self.accepted, now) >= MAX_INBOUND_PER_MINUTE {
+            return Err(InboundRefusal::TooManyConnecting);
+        }
+
+        self.accepted.push_back(now);
+        Ok(())
+    }
+
+    /// Note that an inbound connection from `ip` ended after `duration`
+    pub fn record_disconnect(&mut self, ip: IpAddr, duration: Duration, now: Instant) {
+        if ip.is_loopback() || duration >= SHORT_CONNECTION {
+            return;
+        }
+        let short = self.short_connections.entry(ip).or_default();
+        short.push_back(now);
+        if count_recent(short, now) >= MAX_SHORT_CONNECTIONS_PER_IP {
+            self.greylist.insert(ip, now + GREYLIST_DURATION);
+        }
+    }
+
+    /// Drop expired greylist entries and addresses with no recent activity
+    fn forget_stale(&mut self, now: Instant) {
+        self.greylist.retain(|_, until| *until > now);
+        self.attempts.retain(|_, times| count_recent(times, now) > 0);
+        self.short_connections.retain(|_, times| count_recent(times, now) > 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network_constants::create_peer_address;
+    use crate::network_service::PeerScore;
+    use std::collections::HashMap;
+
+    fn peer(addr: &str, is_outbound: bool, connected_at: u64, invalid_messages: u32) -> (SocketAddr, PeerConnection) {
+        let addr: SocketAddr = addr.parse().unwrap();
+        let connection = PeerConnection {
+            address: create_peer_address(addr.ip(), addr.port(), 0),
+            connected_at,
+            last_ping: 0,
+            version: None,
+            user_agent: None,
+            height: None,
+            is_outbound,
+            score: PeerScore {
+                invalid_messages,
+                ..PeerScore::default()
+            },
+            known_inventory: Default::default(),
+            addr_budget: Default::default(),
+            features: None,
+        };
+        (addr, connection)
+    }
+
+    #[test]
+    fn test_outbound_selection_prefers_distinct_groups() {
+        let candidates: Vec<PeerAddress> = ["10.1.0.1", "10.1.9.9", "10.2.0.1", "192.168.0.1", "10.3.0.1"]
+            .iter()
+            .map(|ip| create_peer_address(ip.parse().unwrap(), 8333, 0))
+            .collect();
+        let connected = ["192.168.5.5".parse().unwrap()];
+
+        let selected = select_outbound(&candidates, connected, 2);
+        let ips: Vec<String> = selected.iter().map(|a| a.ip.to_string()).collect();
+        assert_eq!(ips, vec!["10.1.0.1", "10.2.0.1"]);
+
+        let all = select_outbound(&candidates, connected, 10);
+        assert_eq!(all.len(), 3, "same-group and already connected groups are skipped");
+    }
+
+    #[test]
+    fn test_blocks_only_mode_caps_connection_limits() {
+        let mut settings = AppSettings { max_inbound_peers: 5, ..AppSettings::default() };
+        assert_eq!(ConnectionLimits::from_settings(&settings).max_outbound, MAX_OUTBOUND_PEERS);
+
+        settings.blocks_only = true;
+        let limits = ConnectionLimits::from_settings(&settings);
+        assert_eq!(limits.max_outbound, BLOCKS_ONLY_MAX_OUTBOUND_PEERS);
+        // Limits already below the caps are kept
+        assert_eq!(limits.max_inbound, 5);
+    }
+
+    #[test]
+    fn test_eviction_drops_lowest_scoring_inbound_peer() {
+        let peers: HashMap<SocketAddr, PeerConnection> = [
+            peer("10.0.0.1:8333", false, 100, 0),
+            peer("10.0.0.2:8333", false, 200, 3),
+            peer("10.0.0.3:8333", false, 300, 3),
+            peer("10.0.0.4:8333", true, 400, 9),
+        ]
+        .into_iter()
+        .collect();
+
+        // Outbound peers are never evicted; ties go to the newest connection
+        assert_eq!(select_inbound_eviction(&peers), Some("10.0.0.3:8333".parse().unwrap()));
+        assert_eq!(validate_connection_limits(8, 117), Ok(()));
+        assert!(validate_connection_limits(0, 10).is_err());
+        assert!(validate_connection_limits(8, 200).is_err());
+    }
+
+    #[test]
+    fn test_handshake_enforces_minimum_version_and_negotiates_features() {
+        assert_eq!(
+            check_handshake(MIN_PROTOCOL_VERSION, NODE_NETWORK, PROTOCOL_VERSION),
+            Err(HandshakeRejection::ObsoleteVersion { version: MIN_PROTOCOL_VERSION, minimum: PROTOCOL_VERSION })
+        );
+
+        let features = check_handshake(PROTOCOL_VERSION, NODE_NETWORK | NODE_COMPACT_FILTERS | NODE_COMPACT_BLOCKS, MIN_PROTOCOL_VERSION).unwrap();
+        assert!(features.serves_blocks());
+        assert!(features.compact_filters);
+        // This node does not relay compact blocks, so they are not negotiated
+        assert!(!features.compact_blocks);
+
+        // Features need the protocol version that introduced them
+        let old = PeerFeatures::negotiate(MIN_PROTOCOL_VERSION, NODE_COMPACT_FILTERS);
+        assert!(!old.compact_filters);
+        assert!(!old.serves_blocks());
+        assert!(validate_min_protocol_version(PROTOCOL_VERSION + 1).is_err());
+    }
+
+    #[test]
+    fn test_inbound_gate_caps_addresses_and_greylists_churn() {
+        let mut gate = InboundGate::default();
+        let start = Instant::now();
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+        assert_eq!(gate.admit(ip, 0, start), Ok(()));
+        assert_eq!(gate.admit(ip, MAX_INBOUND_PER_IP, start), Err(InboundRefusal::TooManyFromAddress));
+        assert_eq!(gate.admit("127.0.0.1".parse().unwrap(), 50, start), Ok(()));
+
+        // Connections that keep dropping right away get the address greylisted
+        let churner: IpAddr = "10.0.0.2".parse().unwrap();
+        for _ in 0..MAX_SHORT_CONNECTIONS_PER_IP {
+            assert_eq!(gate.admit(churner, 0, start), Ok(()));
+            gate.record_disconnect(churner, Duration::from_secs(1), start);
+        }
+        assert_eq!(gate.admit(churner, 0, start), Err(InboundRefusal::Greylisted));
+        assert_eq!(gate.admit(churner, 0, start + GREYLIST_DURATION + Duration::from_secs(1)), Ok(()));
+
+        // A global cap applies across addresses
+        let later = start + GREYLIST_DURATION + CHURN_WINDOW * 2;
+        for i in 0..MAX_INBOUND_PER_MINUTE {
+            assert_eq!(gate.admit(IpAddr::from([10, 1, (i / 250) as u8, (i % 250) as u8]), 0, later), Ok(()));
+        }
+        assert_eq!(gate.admit("10.2.0.1".parse().unwrap(), 0, later), Err(InboundRefusal::TooManyConnecting));
+    }
+}
@@ -0,0 +1,172 @@
+//! Bounded queue of messages from peer connections to the message handler.
+//!
+//! Every connection feeds one handler, so a flood from one peer or a slow disk must not let the
+//! queue grow without bound. The queue holds at most `QUEUE_CAPACITY` messages and each peer at
+//! most `PEER_QUOTA` of them. When the queue is filling up, low-priority messages (pings and
+//! address gossip) are dropped; other messages wait for room, which throttles the connection
+//! that sent them. A peer that keeps sending past its quota is reported for disconnection.
+
+use crate::network_service::NetworkMessage;
+use log::{debug, warn};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+/// Messages the handler queue holds across all peers
+pub const QUEUE_CAPACITY: usize = 4096;
+
+/// Messages one peer may have waiting in the queue
+pub const PEER_QUOTA: usize = 256;
+
+/// Low-priority messages are dropped once fewer than this many slots are free
+pub const LOW_PRIORITY_RESERVE: usize = QUEUE_CAPACITY / 2;
+
+/// Messages refused over quota, without the peer's backlog draining in between, before the peer
+/// is disconnected
+pub const MAX_QUOTA_STRIKES: u32 = 64;
+
+/// What happened to a message handed to the queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Enqueued {
+    Queued,
+    /// Dropped because the queue or the peer's quota is full
+    Dropped,
+    /// Dropped, and the peer has exceeded its quota for too long; stop reading from it
+    Disconnect,
+    /// The handler has stopped
+    Closed,
+}
+
+#[derive(Debug, Default)]
+struct PeerQuota {
+    queued: usize,
+    strikes: u32,
+}
+
+#[derive(Debug, Default)]
+struct Quotas {
+    peers: HashMap<SocketAddr, PeerQuota>,
+    /// Peers to disconnect, collected by the handler
+    disconnect: HashSet<SocketAddr>,
+}
+
+/// Sending half, cloned into every connection
+#[derive(Debug, Clone)]
+pub struct PeerMessageSender {
+    sender: mpsc::Sender<(SocketAddr, NetworkMessage)>,
+    quotas: Arc<Mutex<Quotas>>,
+}
+
+/// Receiving half, owned by the message handler
+#[derive(Debug)]
+pub struct PeerMessageReceiver {
+    receiver: mpsc::Receiver<(SocketAddr, NetworkMessage)>,
+    quotas: Arc<Mutex<Quotas>>,
+}
+
+/// Create the handler queue
+pub fn channel() -> (PeerMessageSender, PeerMessageReceiver) {
+    let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+    let quotas = Arc::new(Mutex::new(Quotas::default()));
+    (
+        PeerMessageSender { sender, quotas: Arc::clone(&quotas) },
+        PeerMessageReceiver { receiver, quotas },
+    )
+}
+
+/// Messages that can be lost without harm when the node is busy
+fn is_low_priority(message: &NetworkMessage) -> bool {
+    matches!(
+        message,
+        NetworkMessage::Ping { .. } | NetworkMessage::Pong { .. } | NetworkMessage::GetAddr | NetworkMessage::Addr { .. }
+    )
+}
+
+impl PeerMessageSender {
+    /// Queue a message from a peer, waiting for room unless it is low priority
+    pub async fn send(&self, peer: SocketAddr, message: NetworkMessage) -> Enqueued {
+        {
+            let mut quotas = self.quotas.lock().unwrap();
+            let quota = quotas.peers.entry(peer).or_default();
+            if quota.queued >= PEER_QUOTA {
+                quota.strikes += 1;
+                if quota.strikes < MAX_QUOTA_STRIKES {
+                    return Enqueued::Dropped;
+                }
+                warn!("Peer {} keeps exceeding its message quota, disconnecting", peer);
+                quotas.disconnect.insert(peer);
+                return Enqueued::Disconnect;
+            }
+            if is_low_priority(&message) && self.sender.capacity() < LOW_PRIORITY_RESERVE {
+                debug!("Message queue is busy, dropping low-priority message from {}", peer);
+                if quota.queued == 0 {
+                    quotas.peers.remove(&peer);
+                }
+                return Enqueued::Dropped;
+            }
+            quota.queued += 1;
+        }
+
+        if self.sender.send((peer, message)).await.is_err() {
+            return Enqueued::Closed;
+        }
+        Enqueued::Queued
+    }
+}
+
+impl PeerMessageReceiver {
+    /// Next queued message
+    pub async fn recv(&mut self) -> Option<(SocketAddr, NetworkMessage)> {
+        let (peer, message) = self.receiver.recv().await?;
+        let mut quotas = self.quotas.lock().unwrap();
+        if let Some(quota) = quotas.peers.get_mut(&peer) {
+            quota.queued = quota.queued.saturating_sub(1);
+            if quota.queued == 0 {
+                quotas.peers.remove(&peer);
+            }
+        }
+        Some((peer, message))
+    }
+
+    /// Peers that exceeded their quota since the last call
+    pub fn take_disconnected(&self) -> Vec<SocketAddr> {
+        let mut quotas = self.quotas.lock().unwrap();
+        let peers: Vec<SocketAddr> = quotas.disconnect.drain().collect();
+        for peer in &peers {
+            quotas.peers.remove(peer);
+        }
+        peers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_flooding_peer_is_dropped_then_disconnected() {
+        let (sender, mut receiver) = channel();
+        let flooder: SocketAddr = "10.0.0.1:8333".parse().unwrap();
+        let other: SocketAddr = "10.0.0.2:8333".parse().unwrap();
+
+        for _ in 0..PEER_QUOTA {
+            assert_eq!(sender.send(flooder, NetworkMessage::GetHeight).await, Enqueued::Queued);
+        }
+        assert_eq!(sender.send(flooder, NetworkMessage::GetHeight).await, Enqueued::Dropped);
+        // Other peers keep their own quota
+        assert_eq!(sender.send(other, NetworkMessage::GetHeight).await, Enqueued::Queued);
+
+        // A handled message frees a slot in the quota
+        receiver.recv().await.unwrap();
+        assert_eq!(sender.send(flooder, NetworkMessage::GetHeight).await, Enqueued::Queued);
+
+        let mut outcome = Enqueued::Dropped;
+        for _ in 0..MAX_QUOTA_STRIKES {
+            outcome = sender.send(flooder, NetworkMessage::GetHeight).await;
+        }
+        assert_eq!(outcome, Enqueued::Disconnect);
+        assert_eq!(receiver.take_disconnected(), vec![flooder]);
+        assert!(receiver.take_disconnected().is_empty());
+    }
+}
@@ -0,0 +1,550 @@
+//! Reaction to the machine sleeping, waking and switching to battery power.
+//!
+//! Before a suspend, mining is paused, peers are dropped and the blockchain database is flushed,
+//! so nothing is left half written while the machine is off. Linux announces suspends through
+//! logind and Windows through a power notification; macOS gives no warning this app can receive
+//! without a native run loop, so there only the wake-up is handled. Wake-ups are also noticed on
+//! every platform by the wall clock jumping ahead between two ticks of the monitor, which catches
+//! a missed or unsupported announcement.
+//!
+//! After waking, time spent asleep is counted towards wallet auto-lock timeouts, peers are
+//! reconnected, the chain is synced and paused mining resumes. With `mine_on_battery` turned off
+//! in the settings, mining is also paused while the machine runs on battery.
+
+use crate::blockchain_database::AsyncBlockchainDatabase;
+use crate::config::ConfigManager;
+use crate::events::{self, EmitEvent};
+use crate::mining_service::AsyncMiningService;
+use crate::network_service::AsyncNetworkService;
+use crate::security::AsyncSecurityManager;
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Manager};
+use tokio::sync::{mpsc, Mutex};
+
+/// How often the monitor looks at the clock
+const POWER_CHECK_INTERVAL_SECONDS: u64 = 5;
+
+/// How often the power source is read; on macOS that starts a process
+const POWER_SOURCE_CHECK_INTERVAL_SECONDS: u64 = 30;
+
+/// Wall-clock time beyond the check interval that means the machine was asleep
+const SLEEP_GAP: Duration = Duration::from_secs(30);
+
+/// A suspend announced this long ago without the machine sleeping was cancelled
+const SUSPEND_ABANDONED_AFTER: Duration = Duration::from_secs(120);
+
+/// Announcement from the operating system
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PowerSignal {
+    Suspending,
+    Resumed,
+}
+
+/// Both clocks read at the same moment
+#[derive(Debug, Clone, Copy)]
+struct Reading {
+    wall: SystemTime,
+    mono: Instant,
+}
+
+impl Reading {
+    fn now() -> Self {
+        Self { wall: SystemTime::now(), mono: Instant::now() }
+    }
+
+    /// Wall-clock and monotonic time since `earlier`
+    fn since(&self, earlier: &Reading) -> (Duration, Duration) {
+        (
+            self.wall.duration_since(earlier.wall).unwrap_or_default(),
+            self.mono.saturating_duration_since(earlier.mono),
+        )
+    }
+}
+
+/// How long the machine was asleep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Wake {
+    slept: Duration,
+    /// The part of `slept` that `Instant` did not count. It stops during sleep on Linux and
+    /// macOS, so timers built on it lag behind by this much.
+    unclocked: Duration,
+}
+
+/// Works out when the machine slept from suspend announcements and clock readings
+#[derive(Debug)]
+struct SleepTracker {
+    last: Reading,
+    suspended_at: Option<Reading>,
+}
+
+impl SleepTracker {
+    fn new(now: Reading) -> Self {
+        Self { last: now, suspended_at: None }
+    }
+
+    /// The system announced a suspend. Returns false if one is already pending.
+    fn suspending(&mut self, now: Reading) -> bool {
+        if self.suspended_at.is_some() {
+            return false;
+        }
+        self.suspended_at = Some(now);
+        true
+    }
+
+    /// The system announced a wake-up
+    fn resumed(&mut self, now: Reading) -> Option<Wake> {
+        let at = self.suspended_at.take()?;
+        self.last = now;
+        Some(Self::wake_between(&at, &now))
+    }
+
+    /// Regular check, `expected` after the previous one
+    fn tick(&mut self, now: Reading, expected: Duration) -> Option<Wake> {
+        let (wall, mono) = now.since(&self.last);
+        self.last = now;
+        let slept = wall.saturating_sub(expected) > SLEEP_GAP;
+
+        match self.suspended_at {
+            Some(at) if slept || now.since(&at).0 > SUSPEND_ABANDONED_AFTER => {
+                self.suspended_at = None;
+                Some(Self::wake_between(&at, &now))
+            }
+            // Announced but not asleep yet
+            Some(_) => None,
+            None if slept => Some(Wake {
+                slept: wall.saturating_sub(expected),
+                unclocked: wall.saturating_sub(mono),
+            }),
+            None => None,
+        }
+    }
+
+    fn wake_between(at: &Reading, now: &Reading) -> Wake {
+        let (wall, mono) = now.since(at);
+        Wake { slept: wall, unclocked: wall.saturating_sub(mono) }
+    }
+}
+
+/// Why mining is paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MiningPauseReason {
+    Sleep,
+    Battery,
+}
+
+impl MiningPauseReason {
+    pub fn describe(self) -> &'static str {
+        match self {
+            MiningPauseReason::Sleep => "Mining is paused while the computer sleeps",
+            MiningPauseReason::Battery => "Mining is paused while the computer runs on battery",
+        }
+    }
+}
+
+/// Payload of `power-state-changed` and result of `get_power_status`
+#[derive(Debug, Clone, Serialize)]
+pub struct PowerStatus {
+    /// None when the power source is unknown, e.g. on a desktop without a battery
+    pub on_battery: Option<bool>,
+    pub mining_paused: Option<MiningPauseReason>,
+    /// Wallets that resume mining once the pause ends
+    pub paused_wallets: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct PowerState {
+    asleep: bool,
+    on_battery: Option<bool>,
+    /// Wallet name to mining address of miners stopped by the monitor
+    paused_miners: HashMap<String, String>,
+}
+
+impl PowerState {
+    fn pause_reason(&self, mine_on_battery: bool) -> Option<MiningPauseReason> {
+        if self.asleep {
+            Some(MiningPauseReason::Sleep)
+        } else if self.on_battery == Some(true) && !mine_on_battery {
+            Some(MiningPauseReason::Battery)
+        } else {
+            None
+        }
+    }
+
+    fn status(&self, mine_on_battery: bool) -> PowerStatus {
+        let mut paused_wallets: Vec<String> = self.paused_miners.keys().cloned().collect();
+        paused_wallets.sort();
+        PowerStatus {
+            on_battery: self.on_battery,
+            mining_paused: self.pause_reason(mine_on_battery),
+            paused_wallets,
+        }
+    }
+}
+
+/// Tauri state tracking the power state and the miners it paused
+#[derive(Clone, Default)]
+pub struct PowerMonitor {
+    state: Arc<Mutex<PowerState>>,
+}
+
+impl PowerMonitor {
+    /// Watch for sleep, wake and power source changes in the background
+    pub fn start(&self, app_handle: AppHandle) {
+        let monitor = self.clone();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        subscribe_system_signals(tx.clone());
+
+        tauri::async_runtime::spawn(async move {
+            // Keeps the channel open on platforms without announcements
+            let _tx = tx;
+            let expected = Duration::from_secs(POWER_CHECK_INTERVAL_SECONDS);
+            let mut interval = tokio::time::interval(expected);
+            let mut tracker = SleepTracker::new(Reading::now());
+            let mut last_source_check: Option<Instant> = None;
+
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        if let Some(wake) = tracker.tick(Reading::now(), expected) {
+                            monitor.on_resume(&app_handle, wake).await;
+                        }
+                        let source_due = last_source_check.is_none_or(|checked| {
+                            checked.elapsed() >= Duration::from_secs(POWER_SOURCE_CHECK_INTERVAL_SECONDS)
+                        });
+                        if source_due {
+                            last_source_check = Some(Instant::now());
+                            let on_battery = tokio::task::spawn_blocking(on_battery_power).await.unwrap_or(None);
+                            monitor.on_power_source(&app_handle, on_battery).await;
+                        } else {
+                            // Picks up changes to the mine_on_battery setting
+                            monitor.apply_mining_policy(&app_handle).await;
+                        }
+                    }
+                    Some(signal) = rx.recv() => match signal {
+                        PowerSignal::Suspending => {
+                            if tracker.suspending(Reading::now()) {
+                                monitor.on_suspend(&app_handle).await;
+                            }
+                        }
+                        PowerSignal::Resumed => {
+                            if let Some(wake) = tracker.resumed(Reading::now()) {
+                                monitor.on_resume(&app_handle, wake).await;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Current power source and mining pause
+    pub async fn status(&self, app_handle: &AppHandle) -> PowerStatus {
+        self.state.lock().await.status(mine_on_battery(app_handle))
+    }
+
+    /// Why mining cannot be started right now, if it cannot
+    pub async fn mining_blocked(&self, app_handle: &AppHandle) -> Option<MiningPauseReason> {
+        self.state.lock().await.pause_reason(mine_on_battery(app_handle))
+    }
+
+    /// Stop tracking a paused miner, e.g. when its wallet is closed or mining is stopped by hand
+    pub async fn forget_miner(&self, wallet_name: &str) {
+        self.state.lock().await.paused_miners.remove(wallet_name);
+    }
+
+    async fn on_suspend(&self, app_handle: &AppHandle) {
+        info!("System is going to sleep, pausing mining and network activity");
+        self.state.lock().await.asleep = true;
+        self.apply_mining_policy(app_handle).await;
+
+        if let Some(network_service) = crate::services::get::<AsyncNetworkService>(app_handle) {
+            network_service.reset_peers(false).await;
+        }
+        if let Some(blockchain_db) = crate::services::get::<AsyncBlockchainDatabase>(app_handle) {
+            if let Err(e) = blockchain_db.flush().await {
+                error!("Failed to flush blockchain database before sleep: {}", e);
+            }
+        }
+    }
+
+    async fn on_resume(&self, app_handle: &AppHandle, wake: Wake) {
+        info!("System woke up after {} seconds asleep", wake.slept.as_secs());
+        self.state.lock().await.asleep = false;
+
+        if !wake.unclocked.is_zero() {
+            if let Some(security_manager) = app_handle.try_state::<AsyncSecurityManager>() {
+                security_manager.get_manager().await.account_for_sleep(wake.unclocked);
+            }
+        }
+
+        // Connections have most likely timed out on the other end while asleep
+        if let Some(network_service) = crate::services::get::<AsyncNetworkService>(app_handle) {
+            network_service.reset_peers(true).await;
+            if let Err(e) = network_service.sync_blockchain().await {
+                warn!("Failed to start sync after wake-up: {}", e);
+            }
+        }
+
+        // The power source may have changed while asleep
+        let on_battery = tokio::task::spawn_blocking(on_battery_power).await.unwrap_or(None);
+        self.state.lock().await.on_battery = on_battery;
+        self.apply_mining_policy(app_handle).await;
+    }
+
+    async fn on_power_source(&self, app_handle: &AppHandle, on_battery: Option<bool>) {
+        let changed = {
+            let mut state = self.state.lock().await;
+            let changed = state.on_battery != on_battery;
+            state.on_battery = on_battery;
+            changed
+        };
+        if changed {
+            info!("Power source changed, on battery: {:?}", on_battery);
+        }
+        if !self.apply_mining_policy(app_handle).await && changed {
+            self.emit_status(app_handle).await;
+        }
+    }
+
+    /// Pause or resume mining to match the power state. Returns true if anything changed, in
+    /// which case the new status has been emitted.
+    async fn apply_mining_policy(&self, app_handle: &AppHandle) -> bool {
+        let Some(mining_service) = crate::services::get::<AsyncMiningService>(app_handle) else {
+            return false;
+        };
+        let reason = self.state.lock().await.pause_reason(mine_on_battery(app_handle));
+
+        let changed = match reason {
+            Some(reason) => {
+                let active: Vec<(String, String)> = mining_service
+                    .get_all_mining_statuses()
+                    .await
+                    .into_values()
+                    .filter(|status| status.is_mining)
+                    .map(|status| (status.wallet_id, status.mining_address))
+                    .collect();
+                for (wallet_name, mining_address) in &active {
+                    info!("Pausing mining for {}: {}", wallet_name, reason.describe());
+                    if let Err(e) = mining_service.stop_mining(wallet_name).await {
+                        warn!("Failed to pause mining for {}: {}", wallet_name, e);
+                        continue;
+                    }
+                    self.state.lock().await.paused_miners.insert(wallet_name.clone(), mining_address.clone());
+                }
+                !active.is_empty()
+            }
+            None => {
+                let paused = std::mem::take(&mut self.state.lock().await.paused_miners);
+                for (wallet_name, mining_address) in &paused {
+                    info!("Resuming mining for {}", wallet_name);
+                    if let Err(e) = mining_service.start_mining(wallet_name.clone(), mining_address.clone()).await {
+                        warn!("Failed to resume mining for {}: {}", wallet_name, e);
+                    }
+                }
+                !paused.is_empty()
+            }
+        };
+
+        if changed {
+            self.emit_status(app_handle).await;
+        }
+        changed
+    }
+
+    async fn emit_status(&self, app_handle: &AppHandle) {
+        let status = self.status(app_handle).await;
+        if let Err(e) = app_handle.emit_event(events::POWER_STATE_CHANGED, &status) {
+            debug!("Failed to emit power-state-changed event: {}", e);
+        }
+    }
+}
+
+fn mine_on_battery(app_handle: &AppHandle) -> bool {
+    app_handle
+        .try_state::<Arc<ConfigManager>>()
+        .map(|config_manager| config_manager.get_config().app_settings.mine_on_battery)
+        .unwrap_or(true)
+}
+
+/// Follow logind's PrepareForSleep signal through gdbus, which ships with GLib on desktop systems
+#[cfg(target_os = "linux")]
+fn subscribe_system_signals(tx: mpsc::UnboundedSender<PowerSignal>) {
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use tokio::process::Command;
+
+    tauri::async_runtime::spawn(async move {
+        let child = Command::new("gdbus")
+            .args(["monitor", "--system", "--dest", "org.freedesktop.login1", "--object-path", "/org/freedesktop/login1"])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                info!("Suspend announcements unavailable ({}), relying on wake-up detection", e);
+                return;
+            }
+        };
+        let Some(stdout) = child.stdout.take() else {
+            return;
+        };
+
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if !line.contains("PrepareForSleep") {
+                continue;
+            }
+            let signal = if line.contains("(true") { PowerSignal::Suspending } else { PowerSignal::Resumed };
+            if tx.send(signal).is_err() {
+                break;
+            }
+        }
+        debug!("Stopped listening for suspend announcements");
+    });
+}
+
+#[cfg(windows)]
+static WINDOWS_POWER_SIGNALS: std::sync::OnceLock<mpsc::UnboundedSender<PowerSignal>> = std::sync::OnceLock::new();
+
+/// Register for suspend and resume notifications delivered to a callback
+#[cfg(windows)]
+fn subscribe_system_signals(tx: mpsc::UnboundedSender<PowerSignal>) {
+    use std::ffi::c_void;
+    use windows_sys::Win32::System::Power::{PowerRegisterSuspendResumeNotification, DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS};
+
+    const DEVICE_NOTIFY_CALLBACK: u32 = 2;
+    const PBT_APMSUSPEND: u32 = 0x4;
+    const PBT_APMRESUMEAUTOMATIC: u32 = 0x12;
+
+    unsafe extern "system" fn on_power_broadcast(_context: *const c_void, event: u32, _setting: *const c_void) -> u32 {
+        let signal = match event {
+            PBT_APMSUSPEND => Some(PowerSignal::Suspending),
+            PBT_APMRESUMEAUTOMATIC => Some(PowerSignal::Resumed),
+            _ => None,
+        };
+        if let (Some(signal), Some(tx)) = (signal, WINDOWS_POWER_SIGNALS.get()) {
+            let _ = tx.send(signal);
+        }
+        0
+    }
+
+    if WINDOWS_POWER_SIGNALS.set(tx).is_err() {
+        return;
+    }
+    // Registered for the lifetime of the process, so the parameters are never freed
+    let parameters: &'static mut DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS = Box::leak(Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
+        Callback: Some(on_power_broadcast),
+        Context: std::ptr::null_mut(),
+    }));
+    let mut registration = std::ptr::null_mut();
+    // SAFETY: `parameters` lives for the rest of the process and the callback only touches a static
+    let result = unsafe {
+        PowerRegisterSuspendResumeNotification(
+            DEVICE_NOTIFY_CALLBACK,
+            parameters as *mut DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS as *mut c_void,
+            &mut registration,
+        )
+    };
+    if result != 0 {
+        warn!("Failed to register for suspend notifications (error {}), relying on wake-up detection", result);
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn subscribe_system_signals(_tx: mpsc::UnboundedSender<PowerSignal>) {}
+
+/// Whether a discharging system battery powers the machine. Peripheral batteries such as a
+/// mouse's report a "Device" scope and are ignored.
+#[cfg(target_os = "linux")]
+fn on_battery_power() -> Option<bool> {
+    let entries = std::fs::read_dir("/sys/class/power_supply").ok()?;
+    let mut has_battery = false;
+    for entry in entries.flatten() {
+        let read = |name: &str| std::fs::read_to_string(entry.path().join(name)).map(|value| value.trim().to_string());
+        if read("type").ok().as_deref() != Some("Battery") || read("scope").ok().as_deref() == Some("Device") {
+            continue;
+        }
+        has_battery = true;
+        if read("status").ok().as_deref() == Some("Discharging") {
+            return Some(true);
+        }
+    }
+    has_battery.then_some(false)
+}
+
+/// `pmset -g batt` starts with "Now drawing from 'AC Power'" or "'Battery Power'"
+#[cfg(target_os = "macos")]
+fn on_battery_power() -> Option<bool> {
+    let output = std::process::Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let first_line = text.lines().next()?;
+    if first_line.contains("Battery Power") {
+        Some(true)
+    } else if first_line.contains("AC Power") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(windows)]
+fn on_battery_power() -> Option<bool> {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    // SAFETY: SYSTEM_POWER_STATUS is plain data that GetSystemPowerStatus fills in
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+    // 0 is offline, 1 online and 255 unknown
+    match status.ACLineStatus {
+        0 => Some(true),
+        1 => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn on_battery_power() -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sleep_is_detected_from_clock_jumps_and_announcements() {
+        let start = Reading::now();
+        let at = |wall_secs: u64, mono_secs: u64| Reading {
+            wall: start.wall + Duration::from_secs(wall_secs),
+            mono: start.mono + Duration::from_secs(mono_secs),
+        };
+        let expected = Duration::from_secs(POWER_CHECK_INTERVAL_SECONDS);
+
+        // Regular ticks, then one after an hour of wall-clock time in which Instant stood still
+        let mut tracker = SleepTracker::new(start);
+        assert_eq!(tracker.tick(at(5, 5), expected), None);
+        let wake = tracker.tick(at(3610, 10), expected).unwrap();
+        assert_eq!(wake.slept, Duration::from_secs(3600));
+        assert_eq!(wake.unclocked, Duration::from_secs(3600));
+
+        // An announced suspend is not over until the machine has slept or gives up
+        let mut tracker = SleepTracker::new(start);
+        assert!(tracker.suspending(at(1, 1)));
+        assert!(!tracker.suspending(at(2, 2)));
+        assert_eq!(tracker.tick(at(5, 5), expected), None);
+        let wake = tracker.resumed(at(601, 6)).unwrap();
+        assert_eq!(wake.slept, Duration::from_secs(600));
+        assert_eq!(wake.unclocked, Duration::from_secs(595));
+        // The next tick does not report the same sleep again
+        assert_eq!(tracker.tick(at(606, 11), expected), None);
+        assert_eq!(tracker.resumed(at(607, 12)), None);
+    }
+}
@@ -0,0 +1,359 @@
+//! Price Feed Service
+//! Periodically fetches BRAD/BTC and BTC/fiat rates from a configurable HTTP source and caches them
+
+use crate::atomic_file;
+use crate::config::ConfigManager;
+use crate::errors::*;
+use crate::events::{self, EmitEvent};
+use crate::paths::PathProvider;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::RwLock;
+
+/// Satoshis per BRAD
+pub const SATOSHIS_PER_COIN: f64 = 100_000_000.0;
+
+/// Default display currency for fiat equivalents
+pub const DEFAULT_FIAT_CURRENCY: &str = "USD";
+
+/// File in the data directory holding the last fetched rates, used while offline
+const PRICE_CACHE_FILE: &str = "price_cache.json";
+
+/// Shortest allowed refresh interval, to stay friendly with public rate APIs
+pub const MIN_REFRESH_INTERVAL_SECS: u64 = 60;
+
+/// How often to re-check the settings while the feed is disabled
+const DISABLED_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Response expected from the price source, e.g.
+/// `{ "brad_btc": 0.0000015, "btc": { "usd": 65000.0, "eur": 60000.0 } }`
+#[derive(Debug, Deserialize)]
+struct PriceSourceResponse {
+    /// Price of one BRAD in BTC
+    brad_btc: f64,
+    /// Price of one BTC per fiat currency
+    btc: HashMap<String, f64>,
+}
+
+/// Last rates fetched from the price source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceSnapshot {
+    /// Price of one BRAD in BTC
+    pub brad_btc: f64,
+    /// Price of one BTC keyed by upper-case currency code
+    pub btc_rates: HashMap<String, f64>,
+    /// Unix timestamp of the fetch
+    pub fetched_at: i64,
+}
+
+/// Exchange rate of BRAD into one currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeRate {
+    pub currency: String,
+    /// Price of one BRAD in the currency
+    pub rate: f64,
+    /// Unix timestamp of the underlying fetch
+    pub as_of: i64,
+}
+
+/// Fiat equivalent of an amount of BRAD
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FiatAmount {
+    pub currency: String,
+    pub amount: f64,
+    /// Amount formatted for display, e.g. "$1,234.56"
+    pub formatted: String,
+    /// Unix timestamp of the rate used
+    pub as_of: i64,
+    /// Whether the rate is older than expected, e.g. because the app is offline
+    pub is_stale: bool,
+}
+
+impl PriceSnapshot {
+    /// Rate of one BRAD in the given currency; "BTC" is supported directly
+    pub fn rate(&self, currency: &str) -> Option<ExchangeRate> {
+        let currency = currency.to_uppercase();
+        let rate = if currency == "BTC" {
+            self.brad_btc
+        } else {
+            self.brad_btc * self.btc_rates.get(&currency)?
+        };
+
+        Some(ExchangeRate {
+            currency,
+            rate,
+            as_of: self.fetched_at,
+        })
+    }
+}
+
+impl ExchangeRate {
+    /// Convert an amount in satoshis
+    pub fn convert(&self, satoshis: u64) -> FiatAmount {
+        let amount = satoshis as f64 / SATOSHIS_PER_COIN * self.rate;
+        FiatAmount {
+            currency: self.currency.clone(),
+            amount,
+            formatted: format_currency(amount, &self.currency),
+            as_of: self.as_of,
+            is_stale: false,
+        }
+    }
+}
+
+/// Whether a currency code looks valid: three ASCII letters, e.g. "USD"
+pub fn is_valid_currency_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Format an amount for display with the currency symbol where one is well known
+pub fn format_currency(amount: f64, currency: &str) -> String {
+    let decimals = match currency {
+        "BTC" => 8,
+        "JPY" | "KRW" => 0,
+        _ => 2,
+    };
+    let number = group_thousands(&format!("{:.*}", decimals, amount.abs()));
+    let sign = if amount < 0.0 { "-" } else { "" };
+
+    match currency {
+        "USD" | "CAD" | "AUD" => format!("{}${}", sign, number),
+        "EUR" => format!("{}€{}", sign, number),
+        "GBP" => format!("{}£{}", sign, number),
+        "JPY" => format!("{}¥{}", sign, number),
+        "BTC" => format!("{}₿{}", sign, number),
+        _ => format!("{}{} {}", sign, number, currency),
+    }
+}
+
+/// Insert thousands separators into the integer part of a formatted number
+fn group_thousands(number: &str) -> String {
+    let (integer, fraction) = match number.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (number, None),
+    };
+
+    let mut grouped = String::with_capacity(number.len() + integer.len() / 3);
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    if let Some(fraction) = fraction {
+        grouped.push('.');
+        grouped.push_str(fraction);
+    }
+    grouped
+}
+
+/// Thread-safe price feed shared through Tauri state
+#[derive(Clone)]
+pub struct AsyncPriceService {
+    snapshot: Arc<RwLock<Option<PriceSnapshot>>>,
+    client: reqwest::Client,
+    running: Arc<AtomicBool>,
+}
+
+impl AsyncPriceService {
+    /// Create a new price service; nothing is fetched until `start` is called
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            snapshot: Arc::new(RwLock::new(None)),
+            client,
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Start the background refresh loop. Settings are re-read on every iteration,
+    /// so enabling the feed or changing the source takes effect without a restart.
+    pub fn start(&self, app_handle: AppHandle, config_manager: Arc<ConfigManager>) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            debug!("Price service already running");
+            return;
+        }
+
+        let service = self.clone();
+        tauri::async_runtime::spawn(async move {
+            // Seed the cache from disk so fiat values are available while offline
+            if let Some(snapshot) = load_cached_snapshot().await {
+                info!("Loaded cached exchange rates from {}", snapshot.fetched_at);
+                *service.snapshot.write().await = Some(snapshot);
+            }
+
+            info!("Price service started");
+            loop {
+                let settings = config_manager.get_config().app_settings;
+                let source = settings.price_feed_url.filter(|_| settings.price_feed_enabled);
+
+                let Some(url) = source else {
+                    tokio::time::sleep(DISABLED_POLL_INTERVAL).await;
+                    continue;
+                };
+
+                match service.refresh(&url).await {
+                    Ok(snapshot) => {
+                        if let Err(e) = app_handle.emit_event(events::EXCHANGE_RATES_UPDATED, &snapshot) {
+                            warn!("Failed to emit exchange rates: {}", e);
+                        }
+                    }
+                    Err(e) => warn!("Failed to fetch exchange rates from {}: {}", url, e),
+                }
+
+                let interval = settings.price_feed_interval_secs.max(MIN_REFRESH_INTERVAL_SECS);
+                tokio::time::sleep(Duration::from_secs(interval)).await;
+            }
+        });
+    }
+
+    /// Fetch rates from the source and replace the cached snapshot
+    pub async fn refresh(&self, url: &str) -> AppResult<PriceSnapshot> {
+        debug!("Fetching exchange rates from {}", url);
+        let response: PriceSourceResponse = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| AppError::Generic(format!("Price request failed: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| AppError::Generic(format!("Invalid price response: {}", e)))?;
+
+        if !response.brad_btc.is_finite() || response.brad_btc < 0.0 {
+            return Err(AppError::Generic(format!(
+                "Invalid BRAD/BTC rate: {}",
+                response.brad_btc
+            )));
+        }
+
+        let snapshot = PriceSnapshot {
+            brad_btc: response.brad_btc,
+            btc_rates: response
+                .btc
+                .into_iter()
+                .filter(|(_, rate)| rate.is_finite() && *rate >= 0.0)
+                .map(|(currency, rate)| (currency.to_uppercase(), rate))
+                .collect(),
+            fetched_at: chrono::Utc::now().timestamp(),
+        };
+
+        info!(
+            "Exchange rates updated: BRAD/BTC {} with {} fiat currencies",
+            snapshot.brad_btc,
+            snapshot.btc_rates.len()
+        );
+        *self.snapshot.write().await = Some(snapshot.clone());
+        save_cached_snapshot(&snapshot).await;
+        Ok(snapshot)
+    }
+
+    /// Cached rate for a currency, if rates have been fetched
+    pub async fn get_exchange_rate(&self, currency: &str) -> Option<ExchangeRate> {
+        self.snapshot.read().await.as_ref()?.rate(currency)
+    }
+
+    /// Fiat equivalent of an amount in satoshis using the cached rate.
+    /// Never touches the network; rates older than `max_age_secs` are returned but marked stale.
+    pub async fn to_fiat(&self, satoshis: u64, currency: &str, max_age_secs: u64) -> Option<FiatAmount> {
+        let rate = self.get_exchange_rate(currency).await?;
+        let mut fiat = rate.convert(satoshis);
+        let age = chrono::Utc::now().timestamp() - fiat.as_of;
+        fiat.is_stale = age > max_age_secs as i64;
+        Some(fiat)
+    }
+}
+
+/// Cached rate in the display currency, or None when the price feed is disabled or has no rate
+pub async fn display_rate(app_handle: &AppHandle) -> Option<ExchangeRate> {
+    let config_manager = app_handle.try_state::<Arc<ConfigManager>>()?;
+    let settings = config_manager.get_config().app_settings;
+    if !settings.price_feed_enabled {
+        return None;
+    }
+    let price_service = app_handle.try_state::<AsyncPriceService>()?;
+    price_service.get_exchange_rate(&settings.display_currency).await
+}
+
+fn price_cache_path() -> Option<std::path::PathBuf> {
+    PathProvider::data_dir().map(|dir| dir.join(PRICE_CACHE_FILE))
+}
+
+async fn load_cached_snapshot() -> Option<PriceSnapshot> {
+    let path = price_cache_path()?;
+    let contents = tokio::fs::read_to_string(&path).await.ok()?;
+    match serde_json::from_str(&contents) {
+        Ok(snapshot) => Some(snapshot),
+        Err(e) => {
+            warn!("Ignoring unreadable price cache {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+async fn save_cached_snapshot(snapshot: &PriceSnapshot) {
+    let Some(path) = price_cache_path() else {
+        return;
+    };
+    let contents = match serde_json::to_vec(snapshot) {
+        Ok(contents) => contents,
+        Err(e) => {
+            warn!("Failed to serialize price cache: {}", e);
+            return;
+        }
+    };
+
+    let write_result =
+        tokio::task::spawn_blocking(move || atomic_file::write_atomic(&path, &contents)).await;
+    match write_result {
+        Ok(Ok(())) => debug!("Price cache saved"),
+        Ok(Err(e)) => warn!("Failed to write price cache: {}", e),
+        Err(e) => warn!("Price cache write task failed: {}", e),
+    }
+}
+
+impl Default for AsyncPriceService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_combines_brad_btc_and_btc_fiat() {
+        let snapshot = PriceSnapshot {
+            brad_btc: 0.00001,
+            btc_rates: HashMap::from([("USD".to_string(), 50_000.0)]),
+            fetched_at: 1_700_000_000,
+        };
+
+        let usd = snapshot.rate("usd").unwrap();
+        assert!((usd.rate - 0.5).abs() < 1e-9);
+        assert!((usd.convert(200_000_000).amount - 1.0).abs() < 1e-9);
+        assert_eq!(snapshot.rate("BTC").unwrap().rate, 0.00001);
+        assert!(snapshot.rate("EUR").is_none());
+    }
+
+    #[test]
+    fn test_format_currency() {
+        assert_eq!(format_currency(1234.5, "USD"), "$1,234.50");
+        assert_eq!(format_currency(1234567.0, "JPY"), "¥1,234,567");
+        assert_eq!(format_currency(0.5, "CHF"), "0.50 CHF");
+        assert_eq!(format_currency(-12.0, "EUR"), "-€12.00");
+    }
+}
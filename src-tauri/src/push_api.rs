@@ -0,0 +1,256 @@
+//! Local WebSocket push API for external integrations.
+//!
+//! When enabled in settings, external apps connect to `ws://127.0.0.1:<port>/?token=<token>`
+//! (or send `Authorization: Bearer <token>`) and receive node events as the same JSON envelopes
+//! the frontend gets. Only the loopback interface is bound and the API is off by default.
+//! Clients cannot send commands; anything they send other than a close is ignored.
+
+use crate::config::AppSettings;
+use crate::events::{self, EventEnvelope};
+use futures_util::{SinkExt, StreamExt};
+use log::{debug, info, warn};
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, watch};
+use tokio_tungstenite::tungstenite::handshake::server::{Callback, ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Port the push API listens on unless configured otherwise
+pub const DEFAULT_PUSH_API_PORT: u16 = 8765;
+
+/// Clients connected at once; further connections are refused
+const MAX_CLIENTS: usize = 8;
+
+/// Events a slow client may fall behind by before it misses some
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Events streamed to external clients: new blocks and sync progress, mempool and wallet
+/// activity, peers and mining
+pub const PUSHED_EVENTS: &[&str] = &[
+    events::BLOCKCHAIN_STATUS,
+    events::MEMPOOL_UPDATE,
+    events::MINING_STATUS,
+    events::NETWORK_STATS,
+    events::PAYMENT_REQUEST_PAID,
+    events::SCHEDULED_PAYMENT_SENT,
+    events::WALLET_COINS_RECEIVED,
+    events::WALLET_SYNC_STATUS,
+];
+
+static CHANNEL: OnceLock<broadcast::Sender<Arc<str>>> = OnceLock::new();
+
+fn channel() -> &'static broadcast::Sender<Arc<str>> {
+    CHANNEL.get_or_init(|| broadcast::channel(CHANNEL_CAPACITY).0)
+}
+
+/// Pass an emitted event on to connected clients. Cheap when nobody is connected.
+pub(crate) fn publish<T: Serialize + ?Sized>(envelope: &EventEnvelope<'_, T>) {
+    if !PUSHED_EVENTS.contains(&envelope.event) {
+        return;
+    }
+    let Some(sender) = CHANNEL.get().filter(|sender| sender.receiver_count() > 0) else {
+        return;
+    };
+    match serde_json::to_string(envelope) {
+        Ok(json) => {
+            let _ = sender.send(Arc::from(json));
+        }
+        Err(e) => warn!("Failed to serialize {} for the push API: {}", envelope.event, e),
+    }
+}
+
+/// Check a push API port; privileged ports are refused
+pub fn validate_port(port: u16) -> Result<(), String> {
+    if port < 1024 {
+        return Err(format!("Push API port must be 1024 or higher, got {}", port));
+    }
+    Ok(())
+}
+
+/// A new random token for clients to authenticate with
+pub fn generate_token() -> String {
+    use rand::Rng;
+    hex::encode(rand::rng().random::<[u8; 32]>())
+}
+
+/// Listener task and a switch that disconnects its clients
+struct RunningServer {
+    task: tauri::async_runtime::JoinHandle<()>,
+    _shutdown: watch::Sender<()>,
+}
+
+/// Push API server shared through Tauri state
+#[derive(Default)]
+pub struct PushApiServer {
+    running: Mutex<Option<RunningServer>>,
+}
+
+impl PushApiServer {
+    /// Stop the current listener and its clients, then start again if the settings enable it.
+    /// Called at startup and whenever the push API settings change.
+    pub fn apply_settings(&self, settings: &AppSettings) {
+        let mut running = self.running.lock().unwrap();
+        if let Some(server) = running.take() {
+            server.task.abort();
+            info!("Push API stopped");
+        }
+
+        if !settings.push_api_enabled {
+            return;
+        }
+        if settings.push_api_token.is_empty() {
+            warn!("Push API is enabled without a token, not starting it");
+            return;
+        }
+
+        let (shutdown, shutdown_rx) = watch::channel(());
+        let task = tauri::async_runtime::spawn(serve(
+            settings.push_api_port,
+            settings.push_api_token.clone(),
+            shutdown_rx,
+        ));
+        *running = Some(RunningServer { task, _shutdown: shutdown });
+    }
+}
+
+async fn serve(port: u16, token: String, shutdown: watch::Receiver<()>) {
+    let listener = match TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!("Push API could not listen on 127.0.0.1:{}: {}", port, e);
+            return;
+        }
+    };
+    info!("Push API listening on ws://127.0.0.1:{}", port);
+
+    let token: Arc<str> = Arc::from(token);
+    let clients = Arc::new(AtomicUsize::new(0));
+    loop {
+        let (stream, addr) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!("Push API failed to accept a connection: {}", e);
+                continue;
+            }
+        };
+        if clients.load(Ordering::SeqCst) >= MAX_CLIENTS {
+            warn!("Push API refused {}: {} clients already connected", addr, MAX_CLIENTS);
+            continue;
+        }
+
+        clients.fetch_add(1, Ordering::SeqCst);
+        let (token, clients, shutdown) = (token.clone(), clients.clone(), shutdown.clone());
+        tauri::async_runtime::spawn(async move {
+            handle_client(stream, addr, &token, shutdown).await;
+            clients.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+}
+
+/// Handshake check that the client presents the API token. A `Callback` rather than a closure
+/// because tungstenite fixes its large error type, the 401 response sent back.
+struct Authenticate<'a> {
+    token: &'a str,
+}
+
+impl Callback for Authenticate<'_> {
+    fn on_request(self, request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+        if request_token(request).is_some_and(|given| tokens_match(given, self.token)) {
+            return Ok(response);
+        }
+        let mut rejection = ErrorResponse::new(Some("Invalid or missing token".to_string()));
+        *rejection.status_mut() = StatusCode::UNAUTHORIZED;
+        Err(rejection)
+    }
+}
+
+async fn handle_client(stream: TcpStream, addr: SocketAddr, token: &str, mut shutdown: watch::Receiver<()>) {
+    let mut socket = match tokio_tungstenite::accept_hdr_async(stream, Authenticate { token }).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            warn!("Push API rejected {}: {}", addr, e);
+            crate::logging::log_audit_event("push_api_client_rejected", &format!("addr={} reason={}", addr, e));
+            return;
+        }
+    };
+    info!("Push API client connected from {}", addr);
+    crate::logging::log_audit_event("push_api_client_connected", &format!("addr={}", addr));
+
+    // Subscribing after the handshake, so rejected clients never hold a receiver
+    let mut events = channel().subscribe();
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(json) => {
+                    if socket.send(Message::text(json.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("Push API client {} fell behind and missed {} events", addr, missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            // Pings are answered by the WebSocket layer; other client messages are ignored
+            incoming = socket.next() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            _ = shutdown.changed() => break,
+        }
+    }
+
+    let _ = socket.close(None).await;
+    debug!("Push API client {} disconnected", addr);
+}
+
+/// Token from the `token` query parameter or a bearer Authorization header
+fn request_token(request: &Request) -> Option<&str> {
+    let from_query = request
+        .uri()
+        .query()
+        .and_then(|query| query.split('&').find_map(|pair| pair.strip_prefix("token=")));
+    let from_header = || {
+        request
+            .headers()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+    };
+    from_query.or_else(from_header).map(str::trim)
+}
+
+/// Compare tokens without returning early on the first differing byte
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_is_read_from_query_or_header() {
+        let request = Request::builder().uri("/?foo=1&token=abc").body(()).unwrap();
+        assert_eq!(request_token(&request), Some("abc"));
+
+        let request = Request::builder().uri("/").header("Authorization", "Bearer xyz").body(()).unwrap();
+        assert_eq!(request_token(&request), Some("xyz"));
+
+        let request = Request::builder().uri("/").body(()).unwrap();
+        assert_eq!(request_token(&request), None);
+
+        assert!(tokens_match("abc", "abc"));
+        assert!(!tokens_match("abd", "abc"));
+        assert!(!tokens_match("ab", "abc"));
+    }
+}
@@ -0,0 +1,105 @@
+//! Whether this node can be reached from the internet, and at which address.
+//!
+//! Behind NAT the node cannot see its own public address, but peers can: each Version message
+//! carries the address the peer sees us at (`addr_recv`). The external address is the one most
+//! peers report, once at least `MIN_REPORTS` distinct peers agree. Inbound connections are
+//! counted too, since a node that has never been connected to is probably not reachable.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+
+/// Distinct peers that must report an address before it is trusted
+pub const MIN_REPORTS: usize = 2;
+
+/// Reachability as shown to the user
+#[derive(Debug, Clone, Serialize)]
+pub struct ReachabilityStatus {
+    /// Address peers see this node at, with the listening port
+    pub external_address: Option<String>,
+    /// Peers that reported `external_address`
+    pub reports: usize,
+    /// Whether any inbound connection has been accepted since the node started
+    pub inbound_ever_succeeded: bool,
+    pub inbound_connections: u64,
+    /// Unix time of the last accepted inbound connection
+    pub last_inbound_at: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+pub struct Reachability {
+    /// Peers that reported each address
+    reports: HashMap<IpAddr, HashSet<SocketAddr>>,
+    inbound_connections: u64,
+    last_inbound_at: Option<u64>,
+}
+
+impl Reachability {
+    /// Note the address `reporter` sees this node at. Unspecified and loopback addresses say
+    /// nothing about how the node is reached and are ignored.
+    pub fn record_report(&mut self, reporter: SocketAddr, observed: IpAddr) {
+        if observed.is_unspecified() || observed.is_loopback() {
+            return;
+        }
+        // A peer's latest report replaces its earlier one
+        for reporters in self.reports.values_mut() {
+            reporters.remove(&reporter);
+        }
+        self.reports.retain(|_, reporters| !reporters.is_empty());
+        self.reports.entry(observed).or_default().insert(reporter);
+    }
+
+    /// Note an accepted inbound connection
+    pub fn record_inbound(&mut self, now: u64) {
+        self.inbound_connections += 1;
+        self.last_inbound_at = Some(now);
+    }
+
+    /// The external address with the number of peers reporting it, if enough peers agree
+    pub fn external_ip(&self) -> Option<(IpAddr, usize)> {
+        self.reports
+            .iter()
+            .map(|(ip, reporters)| (*ip, reporters.len()))
+            .filter(|(_, count)| *count >= MIN_REPORTS)
+            .max_by_key(|(ip, count)| (*count, *ip))
+    }
+
+    pub fn status(&self, port: u16) -> ReachabilityStatus {
+        let external = self.external_ip();
+        ReachabilityStatus {
+            external_address: external.map(|(ip, _)| SocketAddr::new(ip, port).to_string()),
+            reports: external.map(|(_, count)| count).unwrap_or(0),
+            inbound_ever_succeeded: self.inbound_connections > 0,
+            inbound_connections: self.inbound_connections,
+            last_inbound_at: self.last_inbound_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_external_address_needs_agreeing_peers() {
+        let mut reachability = Reachability::default();
+        let peer = |n: u8| SocketAddr::from(([10, 0, 0, n], 8333));
+        let public: IpAddr = "203.0.113.7".parse().unwrap();
+
+        reachability.record_report(peer(1), public);
+        reachability.record_report(peer(2), "0.0.0.0".parse().unwrap());
+        assert_eq!(reachability.external_ip(), None);
+
+        reachability.record_report(peer(2), public);
+        assert_eq!(reachability.external_ip(), Some((public, 2)));
+
+        // A peer changing its mind withdraws its earlier report
+        reachability.record_report(peer(2), "198.51.100.1".parse().unwrap());
+        assert_eq!(reachability.external_ip(), None);
+
+        reachability.record_inbound(1_700_000_000);
+        let status = reachability.status(8333);
+        assert!(status.inbound_ever_succeeded);
+        assert_eq!(status.external_address, None);
+    }
+}
@@ -0,0 +1,182 @@
+//! Node policy for the transactions the mempool accepts and relays.
+//!
+//! Consensus decides which transactions a block may contain; policy is what this node is
+//! willing to hold and pass on before that. Each rule is a setting: the minimum relay fee rate,
+//! the largest standard transaction, the dust threshold below which an output costs more to
+//! spend than it is worth, whether outputs with non-standard scripts are relayed, and whether
+//! transactions from peers are accepted at all (blocks-only). The node's own transactions are
+//! accepted in blocks-only mode, but still have to meet the other rules.
+
+use crate::blockchain_database::Transaction;
+use crate::config::AppSettings;
+use serde::Serialize;
+
+/// Default lowest fee rate relayed, in satoshis per byte
+pub const DEFAULT_MIN_RELAY_FEE_RATE: u64 = 1;
+
+/// Default largest transaction relayed, in bytes
+pub const DEFAULT_MAX_STANDARD_TX_SIZE: usize = 100_000;
+
+/// Default smallest output value relayed, in satoshis
+pub const DEFAULT_DUST_THRESHOLD: u64 = 546;
+
+/// Highest minimum relay fee rate the settings accept, in satoshis per byte
+const MAX_MIN_RELAY_FEE_RATE: u64 = 10_000;
+
+/// Allowed range of the largest standard transaction, in bytes
+const MIN_MAX_STANDARD_TX_SIZE: usize = 1_000;
+const MAX_MAX_STANDARD_TX_SIZE: usize = 1_000_000;
+
+/// Data an OP_RETURN output may carry and still be standard, in hex characters
+const MAX_DATA_CARRIER_HEX: usize = 160;
+
+/// Where a transaction offered to the mempool came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionSource {
+    /// Created or submitted on this node
+    Local,
+    /// Relayed by a peer
+    Peer,
+}
+
+/// Rules the mempool applies before accepting a transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RelayPolicy {
+    /// Satoshis per byte; the mempool minimum can rise above it while the mempool is full
+    pub min_relay_fee_rate: u64,
+    /// Bytes
+    pub max_standard_tx_size: usize,
+    /// Satoshis; data carrier outputs are exempt
+    pub dust_threshold: u64,
+    pub relay_non_standard_scripts: bool,
+    /// Refuse every transaction from peers
+    pub blocks_only: bool,
+}
+
+impl Default for RelayPolicy {
+    fn default() -> Self {
+        RelayPolicy {
+            min_relay_fee_rate: DEFAULT_MIN_RELAY_FEE_RATE,
+            max_standard_tx_size: DEFAULT_MAX_STANDARD_TX_SIZE,
+            dust_threshold: DEFAULT_DUST_THRESHOLD,
+            relay_non_standard_scripts: false,
+            blocks_only: false,
+        }
+    }
+}
+
+impl RelayPolicy {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        RelayPolicy {
+            min_relay_fee_rate: settings.min_relay_fee_rate,
+            max_standard_tx_size: settings.max_standard_tx_size,
+            dust_threshold: settings.dust_threshold,
+            relay_non_standard_scripts: settings.relay_non_standard_scripts,
+            blocks_only: settings.blocks_only,
+        }
+    }
+
+    /// Check a transaction of `size` bytes against every rule but the fee rate, which the
+    /// mempool checks against its own, possibly higher, minimum
+    pub fn check(&self, transaction: &Transaction, size: usize, source: TransactionSource) -> Result<(), String> {
+        if self.blocks_only && source == TransactionSource::Peer {
+            return Err("Transactions from peers are not accepted in blocks-only mode".to_string());
+        }
+        if size > self.max_standard_tx_size {
+            return Err(format!(
+                "Transaction too large: {} bytes (maximum: {})",
+                size, self.max_standard_tx_size
+            ));
+        }
+        for (index, output) in transaction.outputs.iter().enumerate() {
+            if !self.relay_non_standard_scripts && !is_standard_script(&output.script_pubkey) {
+                return Err(format!("Output {} has a non-standard script", index));
+            }
+            if output.value < self.dust_threshold && !is_data_carrier(&output.script_pubkey) {
+                return Err(format!(
+                    "Output {} is dust: {} satoshis (minimum: {})",
+                    index, output.value, self.dust_threshold
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Scripts relayed by default: none (the output names only its address), pay to public key
+/// hash, and a small OP_RETURN data carrier
+pub fn is_standard_script(script_pubkey: &str) -> bool {
+    let ops: Vec<&str> = script_pubkey.split_whitespace().collect();
+    match ops.as_slice() {
+        [] => true,
+        ["OP_DUP", "OP_HASH160", _, "OP_EQUALVERIFY", "OP_CHECKSIG"] => true,
+        _ => is_data_carrier(script_pubkey),
+    }
+}
+
+/// An OP_RETURN output carrying at most one small push of data, which can never be spent
+fn is_data_carrier(script_pubkey: &str) -> bool {
+    match script_pubkey.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["OP_RETURN"] => true,
+        ["OP_RETURN", data] => data.len() <= MAX_DATA_CARRIER_HEX && data.chars().all(|c| c.is_ascii_hexdigit()),
+        _ => false,
+    }
+}
+
+/// Validate the relay policy settings
+pub fn validate_relay_policy(settings: &AppSettings) -> Result<(), String> {
+    if !(1..=MAX_MIN_RELAY_FEE_RATE).contains(&settings.min_relay_fee_rate) {
+        return Err(format!(
+            "Minimum relay fee rate must be between 1 and {} sat/byte",
+            MAX_MIN_RELAY_FEE_RATE
+        ));
+    }
+    if !(MIN_MAX_STANDARD_TX_SIZE..=MAX_MAX_STANDARD_TX_SIZE).contains(&settings.max_standard_tx_size) {
+        return Err(format!(
+            "Maximum standard transaction size must be between {} and {} bytes",
+            MIN_MAX_STANDARD_TX_SIZE, MAX_MAX_STANDARD_TX_SIZE
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain_database::TransactionOutput;
+
+    fn transaction(outputs: &[(u64, &str)]) -> Transaction {
+        Transaction {
+            txid: "tx".to_string(),
+            inputs: Vec::new(),
+            outputs: outputs
+                .iter()
+                .map(|(value, script_pubkey)| TransactionOutput {
+                    value: *value,
+                    script_pubkey: script_pubkey.to_string(),
+                    address: "addr".to_string(),
+                })
+                .collect(),
+            timestamp: 0,
+            fee: 0,
+        }
+    }
+
+    #[test]
+    fn test_policy_rejects_dust_non_standard_and_peer_transactions_as_configured() {
+        let p2pkh = "OP_DUP OP_HASH160 abcd OP_EQUALVERIFY OP_CHECKSIG";
+        let mut policy = RelayPolicy::default();
+        assert!(policy.check(&transaction(&[(1_000, p2pkh), (0, "OP_RETURN 00ff")]), 500, TransactionSource::Peer).is_ok());
+        assert!(policy.check(&transaction(&[(1_000, "")]), DEFAULT_MAX_STANDARD_TX_SIZE + 1, TransactionSource::Local).is_err());
+        assert!(policy.check(&transaction(&[(100, p2pkh)]), 500, TransactionSource::Local).is_err());
+        assert!(policy.check(&transaction(&[(1_000, "OP_TRUE")]), 500, TransactionSource::Local).is_err());
+
+        policy.relay_non_standard_scripts = true;
+        policy.dust_threshold = 0;
+        assert!(policy.check(&transaction(&[(100, "OP_TRUE")]), 500, TransactionSource::Local).is_ok());
+
+        policy.blocks_only = true;
+        assert!(policy.check(&transaction(&[(1_000, p2pkh)]), 500, TransactionSource::Local).is_ok());
+        assert!(policy.check(&transaction(&[(1_000, p2pkh)]), 500, TransactionSource::Peer).is_err());
+    }
+}
@@ -0,0 +1,471 @@
+//! Scheduled Payments Service
+//! Stores recurring payment definitions and pays them from their wallet when due.
+//!
+//! A payment is only sent while its wallet is open and unlocked and the node has caught up
+//! with the network; until then it stays due and a `scheduled-payment-skipped` event says why.
+//! Payments that require confirmation wait for `confirm_scheduled_payment` once due. When the
+//! app was closed for several intervals only one payment is made and the missed ones are dropped.
+
+use crate::atomic_file;
+use crate::errors::{AppErrorCode, CommandError};
+use crate::events::{self, EmitEvent};
+use crate::paths::PathProvider;
+use crate::security::AsyncSecurityManager;
+use crate::wallet_manager::AsyncWalletManager;
+use log::{debug, info, warn};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::RwLock;
+
+/// Shortest allowed interval between payments
+pub const MIN_INTERVAL_SECS: u64 = 3600;
+
+/// File in the data directory holding the payment definitions
+const SCHEDULE_FILE: &str = "scheduled_payments.json";
+
+/// How often due payments are looked for
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Skip reason while the wallet is closed or its session has expired
+pub const SKIP_WALLET_LOCKED: &str = "wallet_locked";
+/// Skip reason while the node is disconnected or behind the network
+pub const SKIP_NOT_SYNCED: &str = "not_synced";
+/// Skip reason when building or sending the payment failed; that occurrence is dropped
+pub const SKIP_PAYMENT_FAILED: &str = "payment_failed";
+/// Skip reason when the user skipped the occurrence
+pub const SKIP_BY_USER: &str = "skipped_by_user";
+
+/// A recurring payment from one wallet to one recipient
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledPayment {
+    pub id: String,
+    pub wallet_name: String,
+    pub recipient: String,
+    /// Amount in satoshis
+    pub amount: u64,
+    /// Fee rate in sat/byte
+    pub fee_rate: u64,
+    pub interval_secs: u64,
+    pub label: Option<String>,
+    /// Wait for the user to confirm each payment instead of sending it when due
+    pub require_confirmation: bool,
+    pub enabled: bool,
+    /// Unix timestamp the next payment is due
+    pub next_due: i64,
+    pub last_sent_at: Option<i64>,
+    pub last_txid: Option<String>,
+    /// Whether `scheduled-payment-due` was emitted for the current occurrence
+    #[serde(default)]
+    pub due_notified: bool,
+    /// Why the current occurrence is held back, so the skip is only reported once
+    #[serde(default)]
+    pub skip_reason: Option<String>,
+}
+
+/// Definition of a new scheduled payment as sent by the frontend
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewScheduledPayment {
+    pub wallet_name: String,
+    pub recipient: String,
+    pub amount: u64,
+    pub fee_rate: u64,
+    pub interval_secs: u64,
+    pub label: Option<String>,
+    #[serde(default)]
+    pub require_confirmation: bool,
+    /// Unix timestamp of the first payment; now when omitted
+    pub first_due: Option<i64>,
+}
+
+/// Payload of `scheduled-payment-due`, `scheduled-payment-sent` and `scheduled-payment-skipped`
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledPaymentEvent {
+    pub id: String,
+    pub wallet_name: String,
+    pub recipient: String,
+    pub amount: u64,
+    pub due_at: i64,
+    /// Set on `scheduled-payment-sent`
+    pub txid: Option<String>,
+    /// Set on `scheduled-payment-skipped`, one of the `SKIP_*` reasons
+    pub reason: Option<String>,
+    /// Details of a failed payment
+    pub message: Option<String>,
+    /// Whether the payment waits for `confirm_scheduled_payment`
+    pub awaiting_confirmation: bool,
+}
+
+impl ScheduledPayment {
+    fn event(&self) -> ScheduledPaymentEvent {
+        ScheduledPaymentEvent {
+            id: self.id.clone(),
+            wallet_name: self.wallet_name.clone(),
+            recipient: self.recipient.clone(),
+            amount: self.amount,
+            due_at: self.next_due,
+            txid: None,
+            reason: None,
+            message: None,
+            awaiting_confirmation: self.require_confirmation,
+        }
+    }
+
+    /// Move on to the next occurrence after the current one was sent or skipped
+    fn advance(&mut self, now: i64) {
+        self.next_due = next_due_after(self.next_due, self.interval_secs, now);
+        self.due_notified = false;
+        self.skip_reason = None;
+    }
+}
+
+/// First occurrence after `now` of a schedule that was due at `due`, dropping missed ones
+pub fn next_due_after(due: i64, interval_secs: u64, now: i64) -> i64 {
+    let interval = interval_secs.max(1) as i64;
+    if due > now {
+        return due;
+    }
+    due + ((now - due) / interval + 1) * interval
+}
+
+/// Thread-safe payment scheduler shared through Tauri state
+#[derive(Clone)]
+pub struct AsyncPaymentScheduler {
+    payments: Arc<RwLock<Vec<ScheduledPayment>>>,
+    running: Arc<AtomicBool>,
+}
+
+impl AsyncPaymentScheduler {
+    /// Create an empty scheduler; definitions are loaded when `start` is called
+    pub fn new() -> Self {
+        Self {
+            payments: Arc::new(RwLock::new(Vec::new())),
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Load the saved definitions and start looking for due payments in the background
+    pub fn start(&self, app_handle: AppHandle) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            debug!("Payment scheduler already running");
+            return;
+        }
+
+        let scheduler = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let loaded = load_schedule().await;
+            info!("Payment scheduler started with {} scheduled payments", loaded.len());
+            *scheduler.payments.write().await = loaded;
+
+            loop {
+                scheduler.process_due(&app_handle).await;
+                tokio::time::sleep(CHECK_INTERVAL).await;
+            }
+        });
+    }
+
+    /// Every scheduled payment, soonest first
+    pub async fn list(&self) -> Vec<ScheduledPayment> {
+        let mut payments = self.payments.read().await.clone();
+        payments.sort_by_key(|payment| payment.next_due);
+        payments
+    }
+
+    /// Store a new definition. The caller validates the recipient, amount and wallet.
+    pub async fn add(&self, new: NewScheduledPayment) -> Result<ScheduledPayment, CommandError> {
+        if new.interval_secs < MIN_INTERVAL_SECS {
+            return Err(CommandError::new(
+                AppErrorCode::InvalidInput,
+                format!("Interval must be at least {} seconds", MIN_INTERVAL_SECS),
+            ));
+        }
+        if new.fee_rate == 0 {
+            return Err(CommandError::new(AppErrorCode::InvalidInput, "Fee rate must be greater than zero"));
+        }
+
+        let payment = ScheduledPayment {
+            id: hex::encode(rand::rng().random::<[u8; 8]>()),
+            wallet_name: new.wallet_name,
+            recipient: new.recipient,
+            amount: new.amount,
+            fee_rate: new.fee_rate,
+            interval_secs: new.interval_secs,
+            label: new.label.filter(|label| !label.trim().is_empty()),
+            require_confirmation: new.require_confirmation,
+            enabled: true,
+            next_due: new.first_due.unwrap_or_else(|| chrono::Utc::now().timestamp()),
+            last_sent_at: None,
+            last_txid: None,
+            due_notified: false,
+            skip_reason: None,
+        };
+        info!("Scheduled payment {} of {} satoshis from wallet {}", payment.id, payment.amount, payment.wallet_name);
+
+        let mut payments = self.payments.write().await;
+        payments.push(payment.clone());
+        save_schedule(&payments).await;
+        Ok(payment)
+    }
+
+    /// Delete a definition, returning whether it existed
+    pub async fn remove(&self, id: &str) -> bool {
+        let mut payments = self.payments.write().await;
+        let before = payments.len();
+        payments.retain(|payment| payment.id != id);
+        let removed = payments.len() != before;
+        if removed {
+            save_schedule(&payments).await;
+        }
+        removed
+    }
+
+    /// Pause or resume a payment
+    pub async fn set_enabled(&self, id: &str, enabled: bool) -> Result<ScheduledPayment, CommandError> {
+        self.update(id, |payment| {
+            payment.enabled = enabled;
+            payment.skip_reason = None;
+        })
+        .await
+    }
+
+    /// Point payments of a renamed wallet at its new name
+    pub async fn rename_wallet(&self, old_name: &str, new_name: &str) {
+        let mut payments = self.payments.write().await;
+        let mut changed = false;
+        for payment in payments.iter_mut().filter(|payment| payment.wallet_name == old_name) {
+            payment.wallet_name = new_name.to_string();
+            changed = true;
+        }
+        if changed {
+            save_schedule(&payments).await;
+        }
+    }
+
+    /// Skip the current occurrence of a due payment
+    pub async fn skip(&self, app_handle: &AppHandle, id: &str) -> Result<ScheduledPayment, CommandError> {
+        let now = chrono::Utc::now().timestamp();
+        let mut event = None;
+        let payment = self
+            .update(id, |payment| {
+                let mut skipped = payment.event();
+                skipped.reason = Some(SKIP_BY_USER.to_string());
+                event = Some(skipped);
+                payment.advance(now);
+            })
+            .await?;
+        if let Some(event) = event {
+            emit(app_handle, events::SCHEDULED_PAYMENT_SKIPPED, &event);
+        }
+        Ok(payment)
+    }
+
+    /// Send a due payment that is waiting for confirmation, returning its txid
+    pub async fn confirm(&self, app_handle: &AppHandle, id: &str) -> Result<String, CommandError> {
+        let payment = self.get(id).await?;
+        if payment.next_due > chrono::Utc::now().timestamp() {
+            return Err(CommandError::new(AppErrorCode::InvalidOperation, "This payment is not due yet"));
+        }
+        if let Err(reason) = readiness(app_handle, &payment.wallet_name).await {
+            let message = match reason {
+                SKIP_WALLET_LOCKED => "Unlock the wallet before confirming this payment",
+                _ => "Wait for the node to finish syncing before confirming this payment",
+            };
+            return Err(CommandError::new(AppErrorCode::InvalidOperation, message));
+        }
+        self.send(app_handle, payment).await
+    }
+
+    async fn get(&self, id: &str) -> Result<ScheduledPayment, CommandError> {
+        self.payments
+            .read()
+            .await
+            .iter()
+            .find(|payment| payment.id == id)
+            .cloned()
+            .ok_or_else(|| CommandError::new(AppErrorCode::InvalidInput, format!("No scheduled payment with id {}", id)))
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        change: impl FnOnce(&mut ScheduledPayment),
+    ) -> Result<ScheduledPayment, CommandError> {
+        let mut payments = self.payments.write().await;
+        let payment = payments
+            .iter_mut()
+            .find(|payment| payment.id == id)
+            .ok_or_else(|| CommandError::new(AppErrorCode::InvalidInput, format!("No scheduled payment with id {}", id)))?;
+        change(payment);
+        let updated = payment.clone();
+        save_schedule(&payments).await;
+        Ok(updated)
+    }
+
+    /// Notify, hold back or send every enabled payment that is due
+    async fn process_due(&self, app_handle: &AppHandle) {
+        let now = chrono::Utc::now().timestamp();
+        let due: Vec<ScheduledPayment> = self
+            .payments
+            .read()
+            .await
+            .iter()
+            .filter(|payment| payment.enabled && payment.next_due <= now)
+            .cloned()
+            .collect();
+
+        for payment in due {
+            if !payment.due_notified {
+                emit(app_handle, events::SCHEDULED_PAYMENT_DUE, &payment.event());
+                let _ = self.update(&payment.id, |p| p.due_notified = true).await;
+            }
+            if payment.require_confirmation {
+                continue;
+            }
+
+            if let Err(reason) = readiness(app_handle, &payment.wallet_name).await {
+                if payment.skip_reason.as_deref() != Some(reason) {
+                    debug!("Scheduled payment {} held back: {}", payment.id, reason);
+                    let mut event = payment.event();
+                    event.reason = Some(reason.to_string());
+                    emit(app_handle, events::SCHEDULED_PAYMENT_SKIPPED, &event);
+                    let _ = self.update(&payment.id, |p| p.skip_reason = Some(reason.to_string())).await;
+                }
+                continue;
+            }
+
+            // Failures are already reported through the skipped event
+            let _ = self.send(app_handle, payment).await;
+        }
+    }
+
+    /// Pay the current occurrence and move on to the next, whether it succeeded or not
+    async fn send(&self, app_handle: &AppHandle, payment: ScheduledPayment) -> Result<String, CommandError> {
+        let result = crate::commands::pay_from_wallet(
+            app_handle,
+            &payment.wallet_name,
+            &payment.recipient,
+            payment.amount,
+            payment.fee_rate,
+        )
+        .await;
+
+        let now = chrono::Utc::now().timestamp();
+        let mut event = payment.event();
+        match &result {
+            Ok(txid) => {
+                info!("Sent scheduled payment {} in transaction {}", payment.id, txid);
+                event.txid = Some(txid.clone());
+                emit(app_handle, events::SCHEDULED_PAYMENT_SENT, &event);
+            }
+            Err(e) => {
+                warn!("Scheduled payment {} failed: {}", payment.id, e.message);
+                event.reason = Some(SKIP_PAYMENT_FAILED.to_string());
+                event.message = Some(e.message.clone());
+                emit(app_handle, events::SCHEDULED_PAYMENT_SKIPPED, &event);
+            }
+        }
+
+        let txid = result.as_ref().ok().cloned();
+        let _ = self
+            .update(&payment.id, |p| {
+                if let Some(txid) = txid {
+                    p.last_sent_at = Some(now);
+                    p.last_txid = Some(txid);
+                }
+                p.advance(now);
+            })
+            .await;
+        result
+    }
+}
+
+impl Default for AsyncPaymentScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether a payment from `wallet_name` can be sent now, or the skip reason if not
+async fn readiness(app_handle: &AppHandle, wallet_name: &str) -> Result<(), &'static str> {
+    let secured = {
+        let wallet_manager = app_handle.try_state::<AsyncWalletManager>().ok_or(SKIP_WALLET_LOCKED)?;
+        let manager = wallet_manager.read_manager().await;
+        manager.get_wallet(Some(wallet_name)).ok_or(SKIP_WALLET_LOCKED)?;
+        manager.find_wallet_by_name(wallet_name).is_some_and(|info| info.secured)
+    };
+    if secured {
+        let security_manager = app_handle.try_state::<AsyncSecurityManager>().ok_or(SKIP_WALLET_LOCKED)?;
+        if !security_manager.get_manager().await.has_active_session(wallet_name) {
+            return Err(SKIP_WALLET_LOCKED);
+        }
+    }
+
+    // Without the network service no peers are counted, so the node is not synced
+    if !crate::node_status::collect(app_handle).await.is_synced() {
+        return Err(SKIP_NOT_SYNCED);
+    }
+    Ok(())
+}
+
+fn emit(app_handle: &AppHandle, event: &str, payload: &ScheduledPaymentEvent) {
+    if let Err(e) = app_handle.emit_event(event, payload) {
+        warn!("Failed to emit {} event: {}", event, e);
+    }
+}
+
+fn schedule_path() -> Option<std::path::PathBuf> {
+    PathProvider::data_dir().map(|dir| dir.join(SCHEDULE_FILE))
+}
+
+async fn load_schedule() -> Vec<ScheduledPayment> {
+    let Some(path) = schedule_path() else {
+        return Vec::new();
+    };
+    let Ok(contents) = tokio::fs::read_to_string(&path).await else {
+        return Vec::new();
+    };
+    match serde_json::from_str(&contents) {
+        Ok(payments) => payments,
+        Err(e) => {
+            warn!("Ignoring unreadable payment schedule {}: {}", path.display(), e);
+            Vec::new()
+        }
+    }
+}
+
+async fn save_schedule(payments: &[ScheduledPayment]) {
+    let Some(path) = schedule_path() else {
+        return;
+    };
+    let contents = match serde_json::to_vec_pretty(payments) {
+        Ok(contents) => contents,
+        Err(e) => {
+            warn!("Failed to serialize payment schedule: {}", e);
+            return;
+        }
+    };
+
+    let write_result =
+        tokio::task::spawn_blocking(move || atomic_file::write_atomic(&path, &contents)).await;
+    match write_result {
+        Ok(Ok(())) => debug!("Payment schedule saved"),
+        Ok(Err(e)) => warn!("Failed to write payment schedule: {}", e),
+        Err(e) => warn!("Payment schedule write task failed: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_due_drops_missed_occurrences() {
+        let day = 86_400;
+        assert_eq!(next_due_after(1_000, day, 500), 1_000);
+        assert_eq!(next_due_after(1_000, day, 1_000), 1_000 + day as i64);
+        assert_eq!(next_due_after(1_000, day, 1_000 + 3 * day as i64 + 5), 1_000 + 4 * day as i64);
+    }
+}
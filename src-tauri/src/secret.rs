@@ -0,0 +1,83 @@
+//! Zeroizing containers for key material
+//! Seed phrases and private keys are wiped from memory when dropped and never show up in Debug output
+
+use serde::{Deserialize, Deserializer, Serializer};
+use std::fmt;
+use zeroize::{Zeroize, Zeroizing};
+
+/// A string holding secret key material (seed phrase, xpriv, WIF)
+///
+/// The contents are zeroized on drop. `SecretString` deliberately does not implement
+/// `Serialize`; structs that must persist a secret opt in with `#[serde(with = "...")]`
+/// using the helpers in this module, so secrets can't leak into command responses by accident.
+#[derive(Clone, Default)]
+pub struct SecretString(Zeroizing<String>);
+
+impl SecretString {
+    /// Wrap a string as a secret
+    pub fn new(value: String) -> Self {
+        Self(Zeroizing::new(value))
+    }
+
+    /// Borrow the secret value. Keep the borrow as short-lived as possible
+    pub fn expose_secret(&self) -> &str {
+        self.0.as_str()
+    }
+
+    /// Check whether the secret is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Wipe the secret in place
+    pub fn clear(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self::new(value.to_string())
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString([REDACTED])")
+    }
+}
+
+/// Serde helpers for persisting a `SecretString` field
+pub mod serde_secret {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(secret: &SecretString, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(secret.expose_secret())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SecretString, D::Error> {
+        String::deserialize(deserializer).map(SecretString::new)
+    }
+}
+
+/// Serde helpers for persisting an `Option<SecretString>` field
+pub mod serde_secret_option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(secret: &Option<SecretString>, serializer: S) -> Result<S::Ok, S::Error> {
+        match secret {
+            Some(secret) => serializer.serialize_some(secret.expose_secret()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<SecretString>, D::Error> {
+        Option::<String>::deserialize(deserializer).map(|value| value.map(SecretString::new))
+    }
+}
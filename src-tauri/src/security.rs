@@ -1,10 +1,69 @@
+use crate::command_metrics;
 use crate::errors::SecurityError;
-use log::{debug, error, info};
+use crate::events::{self, EmitEvent};
+use crate::key_cache::KeyCache;
+use crate::wallet_data::KeyPair;
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tauri::AppHandle;
 use tokio::sync::Mutex;
 
+/// Number of failed unlock attempts allowed before backoff is applied
+const MAX_ATTEMPTS_BEFORE_BACKOFF: u32 = 3;
+
+/// Initial backoff after exceeding the allowed attempts, doubled for each further failure
+const BASE_BACKOFF_SECONDS: u64 = 2;
+
+/// Upper bound for the unlock backoff
+const MAX_BACKOFF_SECONDS: u64 = 300;
+
+/// How often the session monitor checks for expired sessions
+const SESSION_MONITOR_INTERVAL_SECONDS: u64 = 5;
+
+/// Longest auth timeout accepted, in minutes
+pub const MAX_AUTH_TIMEOUT_MINUTES: u64 = 24 * 60;
+
+/// Unlock session for a single wallet
+#[derive(Debug, Clone)]
+struct WalletSession {
+    unlocked_at: Instant,
+    last_activity: Instant,
+    timeout: Duration,
+}
+
+impl WalletSession {
+    fn is_expired(&self) -> bool {
+        self.last_activity.elapsed() > self.timeout
+    }
+}
+
+/// Failed unlock attempt tracking for a single wallet
+#[derive(Debug, Clone, Default)]
+struct FailedAttempts {
+    count: u32,
+    locked_until: Option<Instant>,
+}
+
+/// Session information exposed to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletSessionInfo {
+    pub wallet_name: String,
+    pub unlocked: bool,
+    pub unlocked_for_seconds: u64,
+    pub seconds_remaining: u64,
+    pub timeout_seconds: u64,
+}
+
+/// Payload emitted when a wallet session expires or is locked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletSessionEvent {
+    pub wallet_name: String,
+    pub reason: String,
+}
+
 /// Security Manager handles authentication and encryption
 pub struct SecurityManager {
     /// Authentication timeout in seconds
@@ -15,6 +74,16 @@ pub struct SecurityManager {
     authenticated: bool,
     /// Storage for wallet passwords (wallet_name -> password_hash)
     wallet_passwords: HashMap<String, String>,
+    /// Duress passwords that open a wallet's decoy profile (wallet_name -> password_hash)
+    duress_passwords: HashMap<String, String>,
+    /// Active unlock sessions (wallet_name -> session)
+    sessions: HashMap<String, WalletSession>,
+    /// Per-wallet auto-lock timeout overrides in seconds
+    session_timeouts: HashMap<String, u64>,
+    /// Failed unlock attempts (wallet_name -> attempts)
+    failed_attempts: HashMap<String, FailedAttempts>,
+    /// Signing keys decrypted during the current sessions
+    key_cache: KeyCache,
 }
 
 impl SecurityManager {
@@ -29,6 +98,11 @@ impl SecurityManager {
             last_auth_time: None,
             authenticated: false,
             wallet_passwords: HashMap::new(),
+            duress_passwords: HashMap::new(),
+            sessions: HashMap::new(),
+            session_timeouts: HashMap::new(),
+            failed_attempts: HashMap::new(),
+            key_cache: KeyCache::default(),
         }
     }
 
@@ -126,6 +200,19 @@ impl SecurityManager {
         info!("Stored password for wallet: {}", wallet_name);
     }
 
+    /// Accept the duress password of a wallet alongside its password, or stop accepting it
+    pub fn store_duress_password(&mut self, wallet_name: &str, duress_password: Option<&str>) {
+        match duress_password {
+            Some(password) => {
+                let password_hash = self.simple_hash(password);
+                self.duress_passwords.insert(wallet_name.to_string(), password_hash);
+            }
+            None => {
+                self.duress_passwords.remove(wallet_name);
+            }
+        }
+    }
+
     /// Authenticate against a specific wallet's password
     pub fn authenticate_wallet(&mut self, wallet_name: &str, password: &str) -> Result<bool, SecurityError> {
         debug!("Authenticating wallet: {}", wallet_name);
@@ -137,25 +224,296 @@ impl SecurityManager {
             ));
         }
 
+        self.check_rate_limit(wallet_name)?;
+
         // Check if we have a stored password for this wallet
         if let Some(stored_hash) = self.wallet_passwords.get(wallet_name) {
             let provided_hash = self.simple_hash(password);
-            if &provided_hash == stored_hash {
+            // The duress password authenticates the same way, so nothing here tells them apart
+            let is_duress = self.duress_passwords.get(wallet_name) == Some(&provided_hash);
+            if &provided_hash == stored_hash || is_duress {
                 self.authenticated = true;
                 self.last_auth_time = Some(Instant::now());
+                self.failed_attempts.remove(wallet_name);
+                self.start_session(wallet_name);
                 info!("Authentication successful for wallet: {}", wallet_name);
                 Ok(true)
             } else {
                 error!("Authentication failed: Invalid password for wallet: {}", wallet_name);
+                self.record_failed_attempt(wallet_name);
                 Err(SecurityError::InvalidCredentials(
                     "Invalid password".to_string(),
                 ))
             }
         } else {
-            // For unsecured wallets, use the old behavior
+            // Without a stored hash, for instance after a restart, the caller checks the password
+            // by decrypting the wallet file and reports the result with `record_failed_attempt`
+            // or `clear_failed_attempts`, so the backoff above still applies
             debug!("No stored password for wallet: {}, using legacy authentication", wallet_name);
-            self.authenticate(password)
+            let result = self.authenticate(password)?;
+            self.start_session(wallet_name);
+            Ok(result)
+        }
+    }
+
+    /// Reject the attempt if the wallet is still in its backoff window
+    fn check_rate_limit(&self, wallet_name: &str) -> Result<(), SecurityError> {
+        if let Some(attempts) = self.failed_attempts.get(wallet_name) {
+            if let Some(locked_until) = attempts.locked_until {
+                let now = Instant::now();
+                if now < locked_until {
+                    let wait = locked_until.duration_since(now).as_secs().max(1);
+                    warn!(
+                        "Unlock attempt for wallet {} rejected, retry allowed in {} seconds",
+                        wallet_name, wait
+                    );
+                    return Err(SecurityError::AuthenticationFailed(format!(
+                        "Too many failed attempts. Try again in {} seconds",
+                        wait
+                    )));
+                }
+            }
         }
+        Ok(())
+    }
+
+    /// Record a failed unlock attempt and apply exponential backoff once the allowance is used up
+    pub fn record_failed_attempt(&mut self, wallet_name: &str) {
+        let attempts = self
+            .failed_attempts
+            .entry(wallet_name.to_string())
+            .or_default();
+        attempts.count += 1;
+
+        if attempts.count >= MAX_ATTEMPTS_BEFORE_BACKOFF {
+            let exponent = (attempts.count - MAX_ATTEMPTS_BEFORE_BACKOFF).min(16);
+            let backoff = (BASE_BACKOFF_SECONDS << exponent).min(MAX_BACKOFF_SECONDS);
+            attempts.locked_until = Some(Instant::now() + Duration::from_secs(backoff));
+            warn!(
+                "Wallet {} has {} failed unlock attempts, backing off for {} seconds",
+                wallet_name, attempts.count, backoff
+            );
+        }
+    }
+
+    /// Forget a wallet's failed unlock attempts after the right password was given
+    pub fn clear_failed_attempts(&mut self, wallet_name: &str) {
+        self.failed_attempts.remove(wallet_name);
+    }
+
+    /// Timeout applied to sessions of the given wallet
+    fn session_timeout_for(&self, wallet_name: &str) -> Duration {
+        let seconds = self
+            .session_timeouts
+            .get(wallet_name)
+            .copied()
+            .unwrap_or(self.auth_timeout_seconds);
+        Duration::from_secs(seconds)
+    }
+
+    /// Start (or restart) an unlock session for a wallet
+    pub fn start_session(&mut self, wallet_name: &str) {
+        let now = Instant::now();
+        let timeout = self.session_timeout_for(wallet_name);
+        self.sessions.insert(
+            wallet_name.to_string(),
+            WalletSession {
+                unlocked_at: now,
+                last_activity: now,
+                timeout,
+            },
+        );
+        debug!(
+            "Started session for wallet {} with timeout of {} seconds",
+            wallet_name,
+            timeout.as_secs()
+        );
+    }
+
+    /// Check whether a wallet has a live session, refreshing its activity time if so
+    pub fn touch_session(&mut self, wallet_name: &str) -> bool {
+        match self.sessions.get_mut(wallet_name) {
+            Some(session) if !session.is_expired() => {
+                session.last_activity = Instant::now();
+                true
+            }
+            Some(_) => {
+                debug!("Session for wallet {} has expired", wallet_name);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Check whether a wallet has a live session without refreshing it
+    pub fn has_active_session(&self, wallet_name: &str) -> bool {
+        self.sessions
+            .get(wallet_name)
+            .map(|session| !session.is_expired())
+            .unwrap_or(false)
+    }
+
+    /// Move everything stored for a wallet to its new name
+    pub fn rename_wallet(&mut self, old_name: &str, new_name: &str) {
+        if let Some(hash) = self.wallet_passwords.remove(old_name) {
+            self.wallet_passwords.insert(new_name.to_string(), hash);
+        }
+        if let Some(hash) = self.duress_passwords.remove(old_name) {
+            self.duress_passwords.insert(new_name.to_string(), hash);
+        }
+        if let Some(session) = self.sessions.remove(old_name) {
+            self.sessions.insert(new_name.to_string(), session);
+        }
+        if let Some(timeout) = self.session_timeouts.remove(old_name) {
+            self.session_timeouts.insert(new_name.to_string(), timeout);
+        }
+        if let Some(attempts) = self.failed_attempts.remove(old_name) {
+            self.failed_attempts.insert(new_name.to_string(), attempts);
+        }
+        self.key_cache.rename(old_name, new_name);
+        debug!("Moved security state from wallet {} to {}", old_name, new_name);
+    }
+
+    /// End the unlock session for a wallet. Returns true if a session was active
+    pub fn lock_wallet(&mut self, wallet_name: &str) -> bool {
+        self.key_cache.flush(wallet_name);
+        let was_active = self.sessions.remove(wallet_name).is_some();
+        if was_active {
+            info!("Locked wallet: {}", wallet_name);
+        }
+        was_active
+    }
+
+    /// Set the auto-lock timeout for one wallet, or the default for all wallets when no name is given
+    pub fn set_session_timeout(&mut self, wallet_name: Option<&str>, timeout_seconds: u64) {
+        match wallet_name {
+            Some(name) => {
+                self.session_timeouts.insert(name.to_string(), timeout_seconds);
+                if let Some(session) = self.sessions.get_mut(name) {
+                    session.timeout = Duration::from_secs(timeout_seconds);
+                }
+                info!("Set auto-lock timeout for wallet {} to {} seconds", name, timeout_seconds);
+            }
+            None => {
+                self.auth_timeout_seconds = timeout_seconds;
+                let overrides = &self.session_timeouts;
+                for (name, session) in self.sessions.iter_mut() {
+                    if !overrides.contains_key(name) {
+                        session.timeout = Duration::from_secs(timeout_seconds);
+                    }
+                }
+                info!("Set default auto-lock timeout to {} seconds", timeout_seconds);
+            }
+        }
+    }
+
+    /// Get session information for a wallet
+    pub fn get_session_info(&self, wallet_name: &str) -> WalletSessionInfo {
+        match self.sessions.get(wallet_name) {
+            Some(session) if !session.is_expired() => WalletSessionInfo {
+                wallet_name: wallet_name.to_string(),
+                unlocked: true,
+                unlocked_for_seconds: session.unlocked_at.elapsed().as_secs(),
+                seconds_remaining: session
+                    .timeout
+                    .saturating_sub(session.last_activity.elapsed())
+                    .as_secs(),
+                timeout_seconds: session.timeout.as_secs(),
+            },
+            _ => WalletSessionInfo {
+                wallet_name: wallet_name.to_string(),
+                unlocked: false,
+                unlocked_for_seconds: 0,
+                seconds_remaining: 0,
+                timeout_seconds: self.session_timeout_for(wallet_name).as_secs(),
+            },
+        }
+    }
+
+    /// Count time the machine slept towards session timeouts. `Instant` stops while suspended on
+    /// some platforms, which would otherwise leave a wallet unlocked overnight.
+    pub fn account_for_sleep(&mut self, unclocked: Duration) {
+        for session in self.sessions.values_mut() {
+            match session.last_activity.checked_sub(unclocked) {
+                Some(last_activity) => session.last_activity = last_activity,
+                None => session.timeout = Duration::ZERO,
+            }
+        }
+    }
+
+    /// Remove expired sessions and return the names of the wallets they belonged to
+    pub fn take_expired_sessions(&mut self) -> Vec<String> {
+        let expired: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.is_expired())
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        for name in &expired {
+            self.sessions.remove(name);
+            self.key_cache.flush(name);
+        }
+
+        expired
+    }
+
+    /// Lock every wallet once the app has gone `idle` without a command for the auth timeout,
+    /// returning the wallets that were unlocked
+    pub fn lock_if_idle(&mut self, idle: Duration) -> Vec<String> {
+        if self.sessions.is_empty() || idle < Duration::from_secs(self.auth_timeout_seconds) {
+            return Vec::new();
+        }
+        self.invalidate_authentication();
+        self.key_cache.flush_all();
+        let mut locked: Vec<String> = self.sessions.drain().map(|(name, _)| name).collect();
+        locked.sort();
+        info!("Locked {} wallets after {} seconds without activity", locked.len(), idle.as_secs());
+        locked
+    }
+
+    /// Private keys of `key_pairs` for signing. A secured wallet's keys are only handed out while
+    /// its session is live, which signing keeps alive; they are cached until the session ends.
+    pub fn signing_keys(
+        &mut self,
+        wallet_name: &str,
+        secured: bool,
+        key_pairs: &[KeyPair],
+    ) -> Result<Vec<bitcoin::PrivateKey>, SecurityError> {
+        let invalid_key =
+            |key_pair: &KeyPair, e: bitcoin::key::FromWifError| SecurityError::DecryptionError(format!("Invalid private key for {}: {}", key_pair.address, e));
+        if !secured {
+            return key_pairs
+                .iter()
+                .map(|key_pair| key_pair.signing_key().map_err(|e| invalid_key(key_pair, e)))
+                .collect();
+        }
+
+        if !self.touch_session(wallet_name) {
+            self.key_cache.flush(wallet_name);
+            return Err(SecurityError::AuthenticationFailed(format!(
+                "Wallet '{}' is locked; unlock it to sign",
+                wallet_name
+            )));
+        }
+        key_pairs
+            .iter()
+            .map(|key_pair| {
+                self.key_cache
+                    .get_or_decrypt(wallet_name, key_pair)
+                    .map_err(|e| invalid_key(key_pair, e))
+            })
+            .collect()
+    }
+
+    /// Wipe the cached keys of one wallet, or of every wallet, returning how many were wiped
+    pub fn flush_key_cache(&mut self, wallet_name: Option<&str>) -> usize {
+        let flushed = match wallet_name {
+            Some(name) => self.key_cache.flush(name),
+            None => self.key_cache.flush_all(),
+        };
+        info!("Flushed {} cached signing keys", flushed);
+        flushed
     }
 
     /// Simple hash function for demo purposes
@@ -187,4 +545,103 @@ impl AsyncSecurityManager {
     pub async fn get_manager(&self) -> tokio::sync::MutexGuard<'_, SecurityManager> {
         self.inner.lock().await
     }
+    /// Spawn a background task that expires idle sessions, locks every wallet when the app has
+    /// been idle for the auth timeout, and notifies the frontend
+    pub fn start_session_monitor(&self, app_handle: AppHandle) {
+        let inner = Arc::clone(&self.inner);
+
+        tauri::async_runtime::spawn(async move {
+            let mut interval =
+                tokio::time::interval(Duration::from_secs(SESSION_MONITOR_INTERVAL_SECONDS));
+
+            loop {
+                interval.tick().await;
+
+                let (expired, idle_locked) = {
+                    let mut manager = inner.lock().await;
+                    manager.key_cache.evict_expired();
+                    let expired = manager.take_expired_sessions();
+                    (expired, manager.lock_if_idle(command_metrics::idle_for()))
+                };
+
+                for wallet_name in expired {
+                    info!("Session expired for wallet: {}", wallet_name);
+                    let payload = WalletSessionEvent {
+                        wallet_name,
+                        reason: "timeout".to_string(),
+                    };
+                    if let Err(e) = app_handle.emit_event(events::WALLET_SESSION_EXPIRED, &payload) {
+                        warn!("Failed to emit wallet-session-expired event: {}", e);
+                    }
+                }
+
+                for wallet_name in idle_locked {
+                    let payload = WalletSessionEvent {
+                        wallet_name,
+                        reason: "idle".to_string(),
+                    };
+                    if let Err(e) = app_handle.emit_event(events::WALLET_LOCKED, &payload) {
+                        warn!("Failed to emit wallet-locked event: {}", e);
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_app_locks_every_unlocked_wallet() {
+        let mut manager = SecurityManager::new(60);
+        assert!(manager.lock_if_idle(Duration::from_secs(600)).is_empty());
+
+        manager.start_session("savings");
+        manager.start_session("daily");
+        assert!(manager.lock_if_idle(Duration::from_secs(59)).is_empty());
+        assert!(manager.has_active_session("savings"));
+
+        manager.set_session_timeout(None, 30);
+        assert_eq!(manager.lock_if_idle(Duration::from_secs(30)), ["daily", "savings"]);
+        assert!(!manager.has_active_session("savings") && !manager.has_active_session("daily"));
+    }
+
+    #[test]
+    fn test_secured_wallet_keys_need_a_live_session() {
+        let seed = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let data = crate::wallet_manager::WalletManager::wallet_data_from_seed("vault", seed, "", false).unwrap();
+        let key_pairs: Vec<KeyPair> = data.keys.values().cloned().collect();
+
+        let mut manager = SecurityManager::new(60);
+        assert!(manager.signing_keys("vault", true, &key_pairs).is_err());
+        assert_eq!(manager.signing_keys("vault", false, &key_pairs).unwrap().len(), key_pairs.len());
+
+        manager.start_session("vault");
+        let keys = manager.signing_keys("vault", true, &key_pairs).unwrap();
+        assert_eq!(keys.len(), key_pairs.len());
+        assert_eq!(manager.key_cache.len(), key_pairs.len());
+
+        manager.lock_wallet("vault");
+        assert!(manager.key_cache.is_empty());
+        assert!(manager.signing_keys("vault", true, &key_pairs).is_err());
+    }
+
+    #[test]
+    fn test_failed_decryption_backs_off_without_a_stored_password() {
+        // As after a restart: nothing stored, so the wallet file decides and reports back
+        let mut manager = SecurityManager::new(60);
+        for _ in 0..MAX_ATTEMPTS_BEFORE_BACKOFF {
+            assert!(manager.authenticate_wallet("vault", "guess").is_ok());
+            manager.record_failed_attempt("vault");
+        }
+        assert!(matches!(
+            manager.authenticate_wallet("vault", "guess"),
+            Err(SecurityError::AuthenticationFailed(_))
+        ));
+
+        manager.clear_failed_attempts("vault");
+        assert!(manager.authenticate_wallet("vault", "right").is_ok());
+    }
 }
@@ -0,0 +1,132 @@
+//! The running set of blockchain services.
+//!
+//! The database, sync, mining, mempool and network services are started together by
+//! `start_blockchain_services` and replaced together when they are restarted, for example after
+//! the blockchain location changes. Tauri state cannot replace a value once managed, so they
+//! live in one `ServiceContainer` instead: a restart swaps in the new set in one step, and the
+//! old instances are dropped once the last caller still holding them is done. Callers look up
+//! the current instance on every use rather than keeping one.
+
+use crate::blockchain_database::AsyncBlockchainDatabase;
+use crate::blockchain_sync::AsyncBlockchainSyncService;
+use crate::errors::{AppErrorCode, CommandError};
+use crate::fee_estimator::AsyncFeeEstimator;
+use crate::mempool_service::AsyncMempoolService;
+use crate::mining_service::AsyncMiningService;
+use crate::network_monitor::AsyncNetworkMonitor;
+use crate::network_service::AsyncNetworkService;
+use crate::wallet_sync_service::AsyncWalletSyncService;
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, Manager};
+
+/// One start of the blockchain services
+pub struct BlockchainServices {
+    pub blockchain_db: Arc<AsyncBlockchainDatabase>,
+    pub blockchain_sync: Arc<AsyncBlockchainSyncService>,
+    pub wallet_sync: Arc<AsyncWalletSyncService>,
+    pub mining: Arc<AsyncMiningService>,
+    pub mempool: Arc<AsyncMempoolService>,
+    pub fee_estimator: Arc<AsyncFeeEstimator>,
+    pub network_monitor: Arc<AsyncNetworkMonitor>,
+    pub network: Arc<AsyncNetworkService>,
+}
+
+/// A service that belongs to `BlockchainServices`
+pub trait BlockchainService: Send + Sync + 'static {
+    fn in_services(services: &BlockchainServices) -> &Arc<Self>;
+}
+
+macro_rules! blockchain_service {
+    ($service:ty, $field:ident) => {
+        impl BlockchainService for $service {
+            fn in_services(services: &BlockchainServices) -> &Arc<Self> {
+                &services.$field
+            }
+        }
+    };
+}
+
+blockchain_service!(AsyncBlockchainDatabase, blockchain_db);
+blockchain_service!(AsyncBlockchainSyncService, blockchain_sync);
+blockchain_service!(AsyncWalletSyncService, wallet_sync);
+blockchain_service!(AsyncMiningService, mining);
+blockchain_service!(AsyncMempoolService, mempool);
+blockchain_service!(AsyncFeeEstimator, fee_estimator);
+blockchain_service!(AsyncNetworkMonitor, network_monitor);
+blockchain_service!(AsyncNetworkService, network);
+
+/// Holds the current blockchain services, if they are running; kept in Tauri state
+#[derive(Default)]
+pub struct ServiceContainer {
+    blockchain: RwLock<Option<Arc<BlockchainServices>>>,
+}
+
+impl ServiceContainer {
+    /// The current set of blockchain services
+    pub fn blockchain(&self) -> Option<Arc<BlockchainServices>> {
+        self.blockchain.read().unwrap().clone()
+    }
+
+    /// The current instance of one service
+    pub fn get<T: BlockchainService>(&self) -> Option<Arc<T>> {
+        self.blockchain().map(|services| T::in_services(&services).clone())
+    }
+
+    /// The current instance of one service, or the error commands report while the blockchain
+    /// services are not running
+    pub fn require<T: BlockchainService>(&self) -> Result<Arc<T>, CommandError> {
+        self.get()
+            .ok_or_else(|| CommandError::new(AppErrorCode::ServiceUnavailable, "Blockchain services are not running"))
+    }
+
+    /// Make `services` the current set, returning the set it replaces
+    pub fn install(&self, services: BlockchainServices) -> Option<Arc<BlockchainServices>> {
+        self.blockchain.write().unwrap().replace(Arc::new(services))
+    }
+
+    /// Remove the current set, returning it so it can be shut down
+    pub fn take(&self) -> Option<Arc<BlockchainServices>> {
+        self.blockchain.write().unwrap().take()
+    }
+}
+
+/// The current instance of one blockchain service, if the services are running
+pub fn get<T: BlockchainService>(app_handle: &AppHandle) -> Option<Arc<T>> {
+    app_handle.try_state::<ServiceContainer>()?.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestNode;
+
+    fn services_on(node: &TestNode) -> BlockchainServices {
+        let db = node.blockchain_db.clone();
+        BlockchainServices {
+            blockchain_db: db.clone(),
+            blockchain_sync: Arc::new(AsyncBlockchainSyncService::new(db.clone())),
+            wallet_sync: Arc::new(AsyncWalletSyncService::new(db.clone())),
+            mining: Arc::new(AsyncMiningService::new(db.clone())),
+            mempool: Arc::new(node.mempool.clone()),
+            fee_estimator: Arc::new(AsyncFeeEstimator::new(db.clone())),
+            network_monitor: Arc::new(AsyncNetworkMonitor::new()),
+            network: Arc::new(AsyncNetworkService::new(db, None)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restart_replaces_every_service() {
+        let (first, second) = (TestNode::start("services_first").await, TestNode::start("services_second").await);
+        let container = ServiceContainer::default();
+        assert!(container.require::<AsyncMiningService>().is_err());
+
+        assert!(container.install(services_on(&first)).is_none());
+        let replaced = container.install(services_on(&second)).unwrap();
+        assert!(Arc::ptr_eq(&replaced.blockchain_db, &first.blockchain_db));
+        assert!(Arc::ptr_eq(&container.get::<AsyncBlockchainDatabase>().unwrap(), &second.blockchain_db));
+        assert!(!Arc::ptr_eq(&container.get::<AsyncMiningService>().unwrap(), &replaced.mining));
+
+        assert!(container.take().is_some());
+        assert!(container.get::<AsyncNetworkService>().is_none());
+    }
+}
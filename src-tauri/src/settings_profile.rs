@@ -0,0 +1,279 @@
+use crate::config::AppSettings;
+use crate::errors::ConfigError;
+use crate::price_service::{is_valid_currency_code, MIN_REFRESH_INTERVAL_SECS};
+use crate::updater::UpdateChannel;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::str::FromStr;
+
+/// Format version of exported settings files
+pub const SETTINGS_PROFILE_VERSION: u32 = 1;
+
+/// Settings that only make sense on the machine they were written on.
+/// They are left out of exports and kept as-is on import unless explicitly requested.
+pub const MACHINE_SPECIFIC_SETTINGS: [&str; 1] = ["local_blockchain_file_location"];
+
+/// Secrets that never leave this machine, even when machine paths are exported
+const SECRET_SETTINGS: [&str; 1] = ["push_api_token"];
+
+const VALID_THEMES: [&str; 3] = ["light", "dark", "system"];
+
+/// Portable settings file written by `export_settings`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettingsProfile {
+    /// Format version of this file
+    pub profile_version: u32,
+    /// Application version that wrote the file
+    pub app_version: String,
+    /// Unix timestamp of the export
+    pub exported_at: i64,
+    /// Exported settings; missing keys keep their current value on import
+    pub settings: Map<String, Value>,
+}
+
+/// A single setting that an import would change
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SettingChange {
+    pub key: String,
+    pub current: Value,
+    pub incoming: Value,
+}
+
+/// Build an export of the given settings
+pub fn export_profile(
+    settings: &AppSettings,
+    include_machine_paths: bool,
+) -> Result<SettingsProfile, ConfigError> {
+    let mut map = settings_to_map(settings)?;
+    for key in SECRET_SETTINGS {
+        map.remove(key);
+    }
+    if !include_machine_paths {
+        for key in MACHINE_SPECIFIC_SETTINGS {
+            map.remove(key);
+        }
+    }
+
+    Ok(SettingsProfile {
+        profile_version: SETTINGS_PROFILE_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: chrono::Utc::now().timestamp(),
+        settings: map,
+    })
+}
+
+/// Parse a settings file and merge it over the current settings.
+/// Returns the validated result together with the list of settings that would change.
+pub fn apply_profile(
+    current: &AppSettings,
+    contents: &str,
+    include_machine_paths: bool,
+) -> Result<(AppSettings, Vec<SettingChange>), ConfigError> {
+    let profile: SettingsProfile = serde_json::from_str(contents)
+        .map_err(|e| ConfigError::ParseError(format!("Invalid settings file: {}", e)))?;
+
+    if profile.profile_version > SETTINGS_PROFILE_VERSION {
+        return Err(ConfigError::UnsupportedVersion {
+            found: profile.profile_version,
+            supported: SETTINGS_PROFILE_VERSION,
+        });
+    }
+
+    let current_map = settings_to_map(current)?;
+    let mut merged = current_map.clone();
+    for (key, value) in profile.settings {
+        if !current_map.contains_key(&key) {
+            warn!("Ignoring unknown setting '{}' in settings file", key);
+            continue;
+        }
+        if SECRET_SETTINGS.contains(&key.as_str()) {
+            warn!("Ignoring secret setting '{}' in settings file", key);
+            continue;
+        }
+        if !include_machine_paths && MACHINE_SPECIFIC_SETTINGS.contains(&key.as_str()) {
+            info!("Keeping local value of machine-specific setting '{}'", key);
+            continue;
+        }
+        merged.insert(key, value);
+    }
+
+    let mut incoming: AppSettings = serde_json::from_value(Value::Object(merged))
+        .map_err(|e| ConfigError::ParseError(format!("Invalid setting value: {}", e)))?;
+    validate(&mut incoming)?;
+
+    let changes = diff(current, &incoming)?;
+    Ok((incoming, changes))
+}
+
+/// Check imported values and adjust the ones that depend on this machine
+fn validate(settings: &mut AppSettings) -> Result<(), ConfigError> {
+    if !VALID_THEMES.contains(&settings.theme.as_str()) {
+        return Err(ConfigError::Generic(format!(
+            "Unknown theme '{}'",
+            settings.theme
+        )));
+    }
+
+    if log::LevelFilter::from_str(&settings.log_level).is_err() {
+        return Err(ConfigError::Generic(format!(
+            "Unknown log level '{}'",
+            settings.log_level
+        )));
+    }
+
+    // Same rule as update_app_settings: skipping seed dialogs requires developer mode
+    if settings.skip_seed_phrase_dialogs && !settings.developer_mode {
+        warn!("Imported settings skip seed phrase dialogs without developer mode, disabling it");
+        settings.skip_seed_phrase_dialogs = false;
+    }
+
+    // The exporting machine may have had more cores than this one
+    let max_cores = std::thread::available_parallelism()
+        .map(|n| n.get() as u32)
+        .unwrap_or(1);
+    if settings.mining_threads == 0 || settings.mining_threads > max_cores {
+        let clamped = settings.mining_threads.clamp(1, max_cores);
+        warn!(
+            "Imported mining_threads {} is outside 1..={}, using {}",
+            settings.mining_threads, max_cores, clamped
+        );
+        settings.mining_threads = clamped;
+    }
+
+    if settings.price_feed_interval_secs < MIN_REFRESH_INTERVAL_SECS {
+        return Err(ConfigError::Generic(format!(
+            "Price feed interval must be at least {} seconds",
+            MIN_REFRESH_INTERVAL_SECS
+        )));
+    }
+    if !is_valid_currency_code(&settings.display_currency) {
+        return Err(ConfigError::Generic(format!(
+            "Unknown display currency '{}'",
+            settings.display_currency
+        )));
+    }
+    settings.display_currency = settings.display_currency.to_uppercase();
+    if settings.update_channel.parse::<UpdateChannel>().is_err() {
+        return Err(ConfigError::Generic(format!(
+            "Unknown update channel '{}'",
+            settings.update_channel
+        )));
+    }
+    crate::peer_policy::validate_connection_limits(settings.max_outbound_peers, settings.max_inbound_peers)
+        .map_err(ConfigError::Generic)?;
+    crate::peer_policy::validate_min_protocol_version(settings.min_peer_protocol_version).map_err(ConfigError::Generic)?;
+    crate::peer_policy::parse_bind_addresses(&settings.p2p_bind_addresses).map_err(ConfigError::Generic)?;
+    crate::mempool_service::validate_max_mempool_size_mb(settings.max_mempool_size_mb).map_err(ConfigError::Generic)?;
+    crate::relay_policy::validate_relay_policy(settings).map_err(ConfigError::Generic)?;
+    crate::blockchain_database::validate_cache_mb("Database cache", settings.db_cache_capacity_mb)
+        .map_err(ConfigError::Generic)?;
+    crate::blockchain_database::validate_cache_mb("Block cache", settings.db_block_cache_mb).map_err(ConfigError::Generic)?;
+    settings.language = settings
+        .language
+        .parse::<crate::i18n::Locale>()
+        .map_err(ConfigError::Generic)?
+        .code()
+        .to_string();
+    if settings.price_feed_enabled && settings.price_feed_url.is_none() {
+        return Err(ConfigError::Generic(
+            "Price feed is enabled but has no source URL".to_string(),
+        ));
+    }
+    crate::push_api::validate_port(settings.push_api_port).map_err(ConfigError::Generic)?;
+    if settings.push_api_enabled && settings.push_api_token.is_empty() {
+        settings.push_api_token = crate::push_api::generate_token();
+    }
+    crate::metrics::validate_port(settings.metrics_port).map_err(ConfigError::Generic)?;
+    crate::password_strength::validate_min_score(settings.min_password_score).map_err(ConfigError::Generic)?;
+    crate::mining_worker::MiningIntensity::from_settings(settings).validate().map_err(ConfigError::Generic)?;
+    crate::stratum::validate_port(settings.stratum_port).map_err(ConfigError::Generic)?;
+    if settings.stratum_enabled && settings.stratum_payout_address.is_none() {
+        return Err(ConfigError::Generic(
+            "Stratum server is enabled but has no payout address".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn diff(current: &AppSettings, incoming: &AppSettings) -> Result<Vec<SettingChange>, ConfigError> {
+    let current = settings_to_map(current)?;
+    let incoming = settings_to_map(incoming)?;
+
+    let mut changes: Vec<SettingChange> = incoming
+        .into_iter()
+        .filter_map(|(key, incoming_value)| {
+            let current_value = current.get(&key).cloned().unwrap_or(Value::Null);
+            (current_value != incoming_value).then_some(SettingChange {
+                key,
+                current: current_value,
+                incoming: incoming_value,
+            })
+        })
+        .collect();
+    changes.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(changes)
+}
+
+fn settings_to_map(settings: &AppSettings) -> Result<Map<String, Value>, ConfigError> {
+    match serde_json::to_value(settings) {
+        Ok(Value::Object(map)) => Ok(map),
+        Ok(_) => Err(ConfigError::Generic("App settings did not serialize to an object".to_string())),
+        Err(e) => Err(ConfigError::SaveError(e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_omits_machine_paths_by_default() {
+        let settings = AppSettings {
+            local_blockchain_file_location: Some("/mnt/chain".to_string()),
+            push_api_token: "secret".to_string(),
+            ..AppSettings::default()
+        };
+
+        let profile = export_profile(&settings, false).unwrap();
+        assert!(!profile.settings.contains_key("local_blockchain_file_location"));
+
+        let profile = export_profile(&settings, true).unwrap();
+        assert!(profile.settings.contains_key("local_blockchain_file_location"));
+        assert!(!profile.settings.contains_key("push_api_token"));
+    }
+
+    #[test]
+    fn test_import_reports_changes_and_keeps_local_paths() {
+        let current = AppSettings {
+            local_blockchain_file_location: Some("/local/chain".to_string()),
+            ..AppSettings::default()
+        };
+        let exported = AppSettings {
+            theme: "dark".to_string(),
+            local_blockchain_file_location: Some("/other/chain".to_string()),
+            ..AppSettings::default()
+        };
+        let contents = serde_json::to_string(&export_profile(&exported, true).unwrap()).unwrap();
+
+        let (merged, changes) = apply_profile(&current, &contents, false).unwrap();
+        assert_eq!(merged.theme, "dark");
+        assert_eq!(merged.local_blockchain_file_location.as_deref(), Some("/local/chain"));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].key, "theme");
+    }
+
+    #[test]
+    fn test_import_rejects_invalid_values() {
+        let contents = serde_json::json!({
+            "profile_version": SETTINGS_PROFILE_VERSION,
+            "app_version": "0.0.0",
+            "exported_at": 0,
+            "settings": { "log_level": "chatty" }
+        })
+        .to_string();
+
+        assert!(apply_profile(&AppSettings::default(), &contents, false).is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! Input signatures, and the cache of inputs whose signature has already been verified.
+//!
+//! A wallet signs a transaction by putting `<DER signature hex> <public key hex>` in the script
+//! sig of each input. The signature covers the spent outpoints and sequences, the outputs and the
+//! timestamp (`signing_message`), and the key must be the one behind the P2WPKH address of the
+//! output the input spends.
+//!
+//! A transaction is normally verified twice: when it enters the mempool and again when a block
+//! includes it. Both go through `verify_transaction`, which remembers every verified
+//! (txid, input) in one LRU cache shared by the whole node, so the block check of an input the
+//! mempool already verified is a lookup. Txids are not checked against the transactions they
+//! name, so an entry also keeps a digest of what was verified: the signed message, the script sig
+//! and the spent address. A cached (txid, input) with different contents is verified afresh.
+
+use crate::blockchain_database::Transaction;
+use crate::chain_params::ChainParams;
+use bitcoin::hashes::{sha256d, Hash, HashEngine};
+use bitcoin::secp256k1::{ecdsa::Signature, Message, Secp256k1, VerifyOnly};
+use bitcoin::{Address, CompressedPublicKey, PrivateKey, PublicKey};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use thiserror::Error;
+
+/// Verified inputs kept by the shared cache
+pub const SIGNATURE_CACHE_CAPACITY: usize = 100_000;
+
+/// Error type for input signature checks
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum SignatureError {
+    #[error("Transaction {0} cannot be serialized for signing")]
+    Unserializable(String),
+
+    #[error("Transaction {txid} has {inputs} inputs but {spent} spent outputs were given")]
+    SpentOutputCount { txid: String, inputs: usize, spent: usize },
+
+    #[error("Input {index} of transaction {txid} has a malformed script sig")]
+    MalformedScriptSig { txid: String, index: usize },
+
+    #[error("Input {index} of transaction {txid} is signed by a key that does not own {address}")]
+    WrongKey { txid: String, index: usize, address: String },
+
+    #[error("Input {index} of transaction {txid} has an invalid signature")]
+    InvalidSignature { txid: String, index: usize },
+}
+
+/// Message every input of a transaction signs
+pub fn signing_message(transaction: &Transaction) -> Result<Message, SignatureError> {
+    // Commit to the spent outpoints and all outputs so signatures can't be reused on another spend
+    let outpoints: Vec<(String, u32, u32)> = transaction
+        .inputs
+        .iter()
+        .map(|input| (input.previous_txid.clone(), input.previous_output_index, input.sequence))
+        .collect();
+    let preimage = serde_json::to_vec(&(&outpoints, &transaction.outputs, transaction.timestamp))
+        .map_err(|_| SignatureError::Unserializable(transaction.txid.clone()))?;
+    Ok(Message::from_digest(sha256d::Hash::hash(&preimage).to_byte_array()))
+}
+
+/// Script sig proving ownership of `private_key`
+pub fn sign(message: &Message, private_key: &PrivateKey) -> String {
+    let secp = Secp256k1::new();
+    let signature = secp.sign_ecdsa(message, &private_key.inner);
+    format!("{} {}", hex::encode(signature.serialize_der()), private_key.public_key(&secp))
+}
+
+/// Verify the signature of every input of a non-coinbase transaction. `spent_addresses[i]` is the
+/// address of the output input `i` spends. Inputs found in the cache are not verified again.
+pub fn verify_transaction(transaction: &Transaction, spent_addresses: &[&str]) -> Result<(), SignatureError> {
+    // Every input must be checked; zipping a short list would skip the rest
+    if spent_addresses.len() != transaction.inputs.len() {
+        return Err(SignatureError::SpentOutputCount {
+            txid: transaction.txid.clone(),
+            inputs: transaction.inputs.len(),
+            spent: spent_addresses.len(),
+        });
+    }
+    let message = signing_message(transaction)?;
+    for (index, (input, address)) in transaction.inputs.iter().zip(spent_addresses).enumerate() {
+        let key = (transaction.txid.clone(), index as u32);
+        let digest = checked_digest(&message, &input.script_sig, address);
+        if cache().lock().unwrap().contains(&key, &digest) {
+            continue;
+        }
+        verify_input(transaction, index, &message, &input.script_sig, address)?;
+        cache().lock().unwrap().insert(key, digest);
+    }
+    Ok(())
+}
+
+fn verify_input(
+    transaction: &Transaction,
+    index: usize,
+    message: &Message,
+    script_sig: &str,
+    address: &str,
+) -> Result<(), SignatureError> {
+    let malformed = || SignatureError::MalformedScriptSig { txid: transaction.txid.clone(), index };
+    let (signature, public_key) = script_sig.split_once(' ').ok_or_else(malformed)?;
+    let signature = hex::decode(signature)
+        .ok()
+        .and_then(|der| Signature::from_der(&der).ok())
+        .ok_or_else(malformed)?;
+    let public_key = PublicKey::from_str(public_key)
+        .ok()
+        .and_then(|key| CompressedPublicKey::try_from(key).ok())
+        .ok_or_else(malformed)?;
+
+    if Address::p2wpkh(&public_key, ChainParams::active().hrp()).to_string() != address {
+        return Err(SignatureError::WrongKey {
+            txid: transaction.txid.clone(),
+            index,
+            address: address.to_string(),
+        });
+    }
+    secp()
+        .verify_ecdsa(message, &signature, &public_key.0)
+        .map_err(|_| SignatureError::InvalidSignature { txid: transaction.txid.clone(), index })
+}
+
+/// Digest of everything a successful check depended on
+fn checked_digest(message: &Message, script_sig: &str, address: &str) -> [u8; 32] {
+    let mut engine = sha256d::Hash::engine();
+    engine.input(message.as_ref());
+    engine.input(&(script_sig.len() as u64).to_le_bytes());
+    engine.input(script_sig.as_bytes());
+    engine.input(address.as_bytes());
+    sha256d::Hash::from_engine(engine).to_byte_array()
+}
+
+/// Verified (txid, input index) pairs, least recently used first out
+struct SignatureCache {
+    capacity: usize,
+    entries: HashMap<(String, u32), ([u8; 32], u64)>,
+    /// The keys of `entries` by the tick of their last use
+    recency: BTreeMap<u64, (String, u32)>,
+    tick: u64,
+}
+
+impl SignatureCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// Whether `key` was verified with contents hashing to `digest`. A hit counts as a use.
+    fn contains(&mut self, key: &(String, u32), digest: &[u8; 32]) -> bool {
+        let Some((cached, used)) = self.entries.get_mut(key) else {
+            return false;
+        };
+        if cached != digest {
+            return false;
+        }
+        self.tick += 1;
+        self.recency.remove(used);
+        *used = self.tick;
+        self.recency.insert(self.tick, key.clone());
+        true
+    }
+
+    fn insert(&mut self, key: (String, u32), digest: [u8; 32]) {
+        self.tick += 1;
+        if let Some((_, used)) = self.entries.insert(key.clone(), (digest, self.tick)) {
+            self.recency.remove(&used);
+        }
+        self.recency.insert(self.tick, key);
+        while self.entries.len() > self.capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+static CACHE: OnceLock<Mutex<SignatureCache>> = OnceLock::new();
+
+static SECP: OnceLock<Secp256k1<VerifyOnly>> = OnceLock::new();
+
+fn cache() -> &'static Mutex<SignatureCache> {
+    CACHE.get_or_init(|| Mutex::new(SignatureCache::new(SIGNATURE_CACHE_CAPACITY)))
+}
+
+fn secp() -> &'static Secp256k1<VerifyOnly> {
+    SECP.get_or_init(Secp256k1::verification_only)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain_database::{TransactionInput, TransactionOutput};
+
+    fn key(seed: u8) -> (PrivateKey, String) {
+        let secp = Secp256k1::new();
+        let secret = bitcoin::secp256k1::SecretKey::from_slice(&[seed; 32]).unwrap();
+        let private_key = PrivateKey::new(secret, ChainParams::active().address_network);
+        let public_key = CompressedPublicKey::from_private_key(&secp, &private_key).unwrap();
+        (private_key, Address::p2wpkh(&public_key, ChainParams::active().hrp()).to_string())
+    }
+
+    fn signed_spend(txid: &str, private_key: &PrivateKey) -> Transaction {
+        let mut transaction = Transaction {
+            txid: txid.to_string(),
+            inputs: vec![TransactionInput {
+                previous_txid: "funding".to_string(),
+                previous_output_index: 0,
+                script_sig: String::new(),
+                sequence: u32::MAX,
+            }],
+            outputs: vec![TransactionOutput {
+                value: 1_000,
+                script_pubkey: String::new(),
+                address: "payee".to_string(),
+            }],
+            timestamp: 1,
+            fee: 0,
+        };
+        let script_sig = sign(&signing_message(&transaction).unwrap(), private_key);
+        transaction.inputs[0].script_sig = script_sig;
+        transaction
+    }
+
+    #[test]
+    fn test_verify_signed_inputs() {
+        let (owner, owner_address) = key(1);
+        let (_, other_address) = key(2);
+        let transaction = signed_spend("sig_verify", &owner);
+        assert_eq!(verify_transaction(&transaction, &[&owner_address]), Ok(()));
+
+        // The key must own the spent output
+        assert!(matches!(
+            verify_transaction(&transaction, &[&other_address]),
+            Err(SignatureError::WrongKey { index: 0, .. })
+        ));
+
+        // Changing what was signed breaks the signature
+        let mut tampered = transaction.clone();
+        tampered.outputs[0].value += 1;
+        assert!(matches!(
+            verify_transaction(&tampered, &[&owner_address]),
+            Err(SignatureError::InvalidSignature { index: 0, .. })
+        ));
+
+        let mut unsigned = transaction;
+        unsigned.txid = "sig_unsigned".to_string();
+        unsigned.inputs[0].script_sig = "signature_placeholder".to_string();
+        assert!(matches!(
+            verify_transaction(&unsigned, &[&owner_address]),
+            Err(SignatureError::MalformedScriptSig { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_every_input_needs_a_spent_output() {
+        let (owner, owner_address) = key(4);
+        let mut transaction = signed_spend("sig_count", &owner);
+        transaction.inputs.push(transaction.inputs[0].clone());
+        assert!(matches!(
+            verify_transaction(&transaction, &[&owner_address]),
+            Err(SignatureError::SpentOutputCount { inputs: 2, spent: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_cached_input_must_match_what_was_verified() {
+        let (owner, owner_address) = key(3);
+        let transaction = signed_spend("sig_cached", &owner);
+        assert_eq!(verify_transaction(&transaction, &[&owner_address]), Ok(()));
+        assert_eq!(verify_transaction(&transaction, &[&owner_address]), Ok(()));
+
+        // Same txid and input, different contents: no free pass from the cache
+        let mut reused_txid = transaction;
+        reused_txid.outputs[0].address = "thief".to_string();
+        assert!(matches!(
+            verify_transaction(&reused_txid, &[&owner_address]),
+            Err(SignatureError::InvalidSignature { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let mut cache = SignatureCache::new(2);
+        let key = |txid: &str| (txid.to_string(), 0);
+        cache.insert(key("a"), [1; 32]);
+        cache.insert(key("b"), [2; 32]);
+        assert!(cache.contains(&key("a"), &[1; 32]));
+        assert!(!cache.contains(&key("a"), &[2; 32]));
+
+        // b was used longest ago
+        cache.insert(key("c"), [3; 32]);
+        assert!(!cache.contains(&key("b"), &[2; 32]));
+        assert!(cache.contains(&key("a"), &[1; 32]));
+        assert!(cache.contains(&key("c"), &[3; 32]));
+    }
+}
@@ -0,0 +1,335 @@
+//! Simulated peers for tests and developer mode.
+//!
+//! Each simulated peer is an in-process virtual node with its own chain. It receives the
+//! NetworkMessages the node sends it and answers with real NetworkMessages through the node's
+//! inbound channel, so sync, relay and fork handling run exactly as with remote peers.
+
+use crate::blockchain_database::{Block, Transaction, TransactionOutput};
+use crate::network_constants::{create_peer_address, BRADCOIN_DEFAULT_PORT, MAX_HEADERS_COUNT, NODE_NETWORK, PROTOCOL_VERSION};
+use crate::network_service::{BlockHeader, InventoryItem, InventoryType, NetworkMessage};
+use crate::peer_queue::{Enqueued, PeerMessageSender};
+use log::{debug, info, warn};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+
+/// User agent simulated peers announce
+pub const SIMULATED_USER_AGENT: &str = "/BradCoin:simulated/";
+
+/// Registry of running simulated peers, keyed by their virtual address
+#[derive(Debug, Default)]
+pub struct SimulatedNetwork {
+    peers: Mutex<HashMap<SocketAddr, mpsc::UnboundedSender<NetworkMessage>>>,
+    next_id: AtomicU32,
+}
+
+impl SimulatedNetwork {
+    /// Whether an address belongs to a simulated peer
+    pub fn is_simulated(&self, addr: &SocketAddr) -> bool {
+        self.peers.lock().unwrap().contains_key(addr)
+    }
+
+    /// Hand a message to a simulated peer. Returns false if the address is not a simulated peer.
+    pub fn deliver(&self, addr: SocketAddr, message: NetworkMessage) -> bool {
+        let peers = self.peers.lock().unwrap();
+        let Some(inbox) = peers.get(&addr) else {
+            return false;
+        };
+        if inbox.send(message).is_err() {
+            warn!("Simulated peer {} is no longer running", addr);
+        }
+        true
+    }
+
+    /// Start a virtual node with the given chain. Its replies go to `node_inbox` as if received
+    /// from the network. Returns the peer's virtual address.
+    pub fn spawn_peer(
+        &self,
+        chain: Vec<Block>,
+        node_inbox: PeerMessageSender,
+    ) -> SocketAddr {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let addr = SocketAddr::new(
+            IpAddr::V4(Ipv4Addr::new(127, 77, (id >> 8) as u8, id as u8)),
+            BRADCOIN_DEFAULT_PORT,
+        );
+        let (inbox, mut messages) = mpsc::unbounded_channel();
+        self.peers.lock().unwrap().insert(addr, inbox);
+
+        let mut peer = SimulatedPeer::new(chain);
+        info!("Spawned simulated peer {} at height {}", addr, peer.height());
+
+        tokio::spawn(async move {
+            for message in peer.handshake() {
+                node_inbox.send(addr, message).await;
+            }
+            while let Some(message) = messages.recv().await {
+                for reply in peer.handle(message) {
+                    match node_inbox.send(addr, reply).await {
+                        Enqueued::Queued | Enqueued::Dropped => {}
+                        Enqueued::Disconnect | Enqueued::Closed => {
+                            debug!("Node stopped or disconnected, shutting down simulated peer {}", addr);
+                            return;
+                        }
+                    }
+                }
+            }
+            debug!("Simulated peer {} disconnected", addr);
+        });
+
+        addr
+    }
+
+    /// Disconnect one simulated peer
+    pub fn disconnect(&self, addr: SocketAddr) {
+        self.peers.lock().unwrap().remove(&addr);
+    }
+
+    pub fn peer_count(&self) -> usize {
+        self.peers.lock().unwrap().len()
+    }
+
+    /// Disconnect all simulated peers; their tasks end once their inboxes close
+    pub fn clear(&self) {
+        let mut peers = self.peers.lock().unwrap();
+        if !peers.is_empty() {
+            info!("Disconnecting {} simulated peers", peers.len());
+        }
+        peers.clear();
+    }
+}
+
+/// A virtual node: serves its chain and accepts blocks that extend it
+#[derive(Debug)]
+pub struct SimulatedPeer {
+    chain: Vec<Arc<Block>>,
+    mempool: HashMap<String, Transaction>,
+}
+
+impl SimulatedPeer {
+    pub fn new(chain: Vec<Block>) -> Self {
+        Self {
+            chain: chain.into_iter().map(Arc::new).collect(),
+            mempool: HashMap::new(),
+        }
+    }
+
+    pub fn height(&self) -> u64 {
+        self.chain.last().map(|block| block.height).unwrap_or(0)
+    }
+
+    fn tip_hash(&self) -> Option<&str> {
+        self.chain.last().map(|block| block.hash.as_str())
+    }
+
+    fn block_by_hash(&self, hash: &str) -> Option<&Arc<Block>> {
+        self.chain.iter().find(|block| block.hash == hash)
+    }
+
+    /// Messages sent when the peer connects
+    fn handshake(&self) -> Vec<NetworkMessage> {
+        let addr = create_peer_address(IpAddr::V4(Ipv4Addr::UNSPECIFIED), BRADCOIN_DEFAULT_PORT, NODE_NETWORK);
+        vec![
+            NetworkMessage::Version {
+                version: PROTOCOL_VERSION,
+                services: NODE_NETWORK,
+                timestamp: now(),
+                addr_recv: addr.clone(),
+                addr_from: addr,
+                nonce: rand::random(),
+                user_agent: SIMULATED_USER_AGENT.to_string(),
+                start_height: self.height(),
+            },
+            NetworkMessage::Height { height: self.height() },
+        ]
+    }
+
+    /// Answer one message from the node
+    pub fn handle(&mut self, message: NetworkMessage) -> Vec<NetworkMessage> {
+        match message {
+            NetworkMessage::Ping { nonce, .. } => vec![NetworkMessage::Pong { timestamp: now(), nonce }],
+            NetworkMessage::GetHeight => vec![NetworkMessage::Height { height: self.height() }],
+            NetworkMessage::Version { .. } => vec![NetworkMessage::Verack],
+            NetworkMessage::GetHeaders { block_locator_hashes, hash_stop, .. }
+            | NetworkMessage::GetBlocks { block_locator_hashes, hash_stop, .. } => {
+                vec![NetworkMessage::Headers {
+                    headers: self.headers_after(&block_locator_hashes, hash_stop.as_deref()),
+                }]
+            }
+            NetworkMessage::GetBlock { height, hash } => {
+                let block = match (hash, height) {
+                    (Some(hash), _) => self.block_by_hash(&hash),
+                    (None, Some(height)) => self.chain.iter().find(|block| block.height == height),
+                    (None, None) => None,
+                };
+                block.map(|block| NetworkMessage::Block { block: Arc::clone(block) }).into_iter().collect()
+            }
+            NetworkMessage::GetData { inventory } => inventory
+                .iter()
+                .filter_map(|item| match item.item_type {
+                    InventoryType::Block => self
+                        .block_by_hash(&item.hash)
+                        .map(|block| NetworkMessage::Block { block: Arc::clone(block) }),
+                    InventoryType::Transaction => self
+                        .mempool
+                        .get(&item.hash)
+                        .map(|transaction| NetworkMessage::Transaction { transaction: transaction.clone() }),
+                    _ => None,
+                })
+                .collect(),
+            NetworkMessage::Inv { inventory } => {
+                let wanted: Vec<InventoryItem> = inventory
+                    .into_iter()
+                    .filter(|item| match item.item_type {
+                        InventoryType::Transaction => !self.mempool.contains_key(&item.hash),
+                        InventoryType::Block => self.block_by_hash(&item.hash).is_none(),
+                        _ => false,
+                    })
+                    .collect();
+                if wanted.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![NetworkMessage::GetData { inventory: wanted }]
+                }
+            }
+            NetworkMessage::GetTransaction { txid } => self
+                .mempool
+                .get(&txid)
+                .map(|transaction| NetworkMessage::Transaction { transaction: transaction.clone() })
+                .into_iter()
+                .collect(),
+            NetworkMessage::NewBlock { block } | NetworkMessage::Block { block } => {
+                if Some(block.previous_hash.as_str()) == self.tip_hash() && block.height == self.height() + 1 {
+                    for transaction in &block.transactions {
+                        self.mempool.remove(&transaction.txid);
+                    }
+                    self.chain.push(block);
+                }
+                Vec::new()
+            }
+            NetworkMessage::NewTransaction { transaction }
+            | NetworkMessage::Transaction { transaction }
+            | NetworkMessage::Tx { transaction } => {
+                self.mempool.insert(transaction.txid.clone(), transaction);
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Headers following the newest locator hash this peer knows, like the node's getheaders reply
+    fn headers_after(&self, locator: &[String], hash_stop: Option<&str>) -> Vec<BlockHeader> {
+        let fork_height = locator
+            .iter()
+            .find_map(|hash| self.block_by_hash(hash))
+            .map(|block| block.height)
+            .unwrap_or(0);
+
+        let mut headers = Vec::new();
+        for block in self.chain.iter().filter(|block| block.height > fork_height) {
+            headers.push(BlockHeader {
+                hash: block.hash.clone(),
+                previous_hash: block.previous_hash.clone(),
+                height: block.height,
+                timestamp: block.timestamp,
+                merkle_root: block.merkle_root.clone(),
+                nonce: block.nonce,
+                difficulty: block.difficulty as f64,
+            });
+            if headers.len() >= MAX_HEADERS_COUNT || Some(block.hash.as_str()) == hash_stop {
+                break;
+            }
+        }
+        headers
+    }
+}
+
+/// Build `count` blocks with a coinbase each on top of `parent` (or from genesis).
+/// `tag` makes chains built from the same parent differ, which is how forks are made.
+pub fn build_blocks(parent: Option<&Block>, count: u64, tag: &str) -> Vec<Block> {
+    let mut blocks = Vec::with_capacity(count as usize);
+    let mut previous_hash = parent.map(|block| block.hash.clone()).unwrap_or_else(|| "0".repeat(64));
+    let first_height = parent.map(|block| block.height + 1).unwrap_or(0);
+    let timestamp = now();
+
+    for (height, _) in (first_height..).zip(0..count) {
+        let coinbase_txid = sha256_hex(&format!("{}:coinbase:{}:{}", tag, height, previous_hash));
+        let coinbase = Transaction {
+            txid: coinbase_txid.clone(),
+            inputs: Vec::new(),
+            outputs: vec![TransactionOutput {
+                value: crate::consensus::block_subsidy(height),
+                script_pubkey: String::new(),
+                address: format!("simulated_{}", tag),
+            }],
+            timestamp,
+            fee: 0,
+        };
+        let hash = sha256_hex(&format!("{}:block:{}:{}", tag, height, previous_hash));
+        blocks.push(Block {
+            height,
+            hash: hash.clone(),
+            previous_hash,
+            timestamp,
+            nonce: height,
+            difficulty: 1,
+            transactions: vec![coinbase],
+            merkle_root: coinbase_txid,
+        });
+        previous_hash = hash;
+    }
+    blocks
+}
+
+fn sha256_hex(data: &str) -> String {
+    hex::encode(Sha256::digest(data.as_bytes()))
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulated_peer_serves_and_extends_its_chain() {
+        let chain = build_blocks(None, 5, "test");
+        let mut peer = SimulatedPeer::new(chain.clone());
+        assert_eq!(peer.height(), 4);
+
+        // Headers after the newest known locator hash
+        let replies = peer.handle(NetworkMessage::GetHeaders {
+            version: 1,
+            block_locator_hashes: vec![chain[2].hash.clone(), chain[0].hash.clone()],
+            hash_stop: None,
+        });
+        match &replies[..] {
+            [NetworkMessage::Headers { headers }] => {
+                assert_eq!(headers.iter().map(|h| h.height).collect::<Vec<_>>(), vec![3, 4]);
+            }
+            other => panic!("expected headers, got {:?}", other),
+        }
+
+        let replies = peer.handle(NetworkMessage::GetData {
+            inventory: vec![InventoryItem {
+                item_type: InventoryType::Block,
+                hash: chain[3].hash.clone(),
+            }],
+        });
+        assert!(matches!(&replies[..], [NetworkMessage::Block { block }] if block.height == 3));
+
+        // Only blocks extending the tip are accepted
+        let next = build_blocks(chain.last(), 1, "test").remove(0);
+        let fork = build_blocks(Some(&chain[2]), 1, "fork").remove(0);
+        peer.handle(NetworkMessage::NewBlock { block: Arc::new(fork) });
+        assert_eq!(peer.height(), 4);
+        peer.handle(NetworkMessage::NewBlock { block: Arc::new(next) });
+        assert_eq!(peer.height(), 5);
+    }
+}
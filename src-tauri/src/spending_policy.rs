@@ -0,0 +1,240 @@
+//! Per-wallet spending policies.
+//!
+//! A wallet can cap what it sends per day, ask for its password again before large sends, and
+//! hold very large sends back for a cooling-off period: the first attempt only starts the clock,
+//! and the same send goes through when it is repeated after the delay. Sends are counted from the
+//! wallet's activity log, so only payments made through this app count towards the daily cap.
+//!
+//! Pending cooling-off sends are kept next to wallet.dat, so restarting the app does not reset
+//! the delay.
+
+use crate::atomic_file;
+use crate::errors::{AppErrorCode, CommandError};
+use crate::wallet_data::WalletDataError;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// File name of the pending cooling-off sends, stored next to wallet.dat
+pub const PENDING_SENDS_FILE: &str = "pending_sends.json";
+
+/// Window the daily cap applies to
+pub const SPEND_WINDOW_SECS: i64 = 24 * 60 * 60;
+
+/// How long a send stays releasable once its cooling-off period is over
+pub const RELEASE_WINDOW_SECS: i64 = 24 * 60 * 60;
+
+/// Spending limits of one wallet; every limit is off when unset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpendingPolicy {
+    /// Most satoshis sent in any 24 hours
+    #[serde(default)]
+    pub daily_limit: Option<u64>,
+    /// Sends above this many satoshis need the wallet password typed again
+    #[serde(default)]
+    pub confirm_password_above: Option<u64>,
+    /// Sends above this many satoshis are held back for `cooling_off_secs` first
+    #[serde(default)]
+    pub cooling_off_above: Option<u64>,
+    #[serde(default = "default_cooling_off_secs")]
+    pub cooling_off_secs: u64,
+}
+
+fn default_cooling_off_secs() -> u64 {
+    24 * 60 * 60
+}
+
+impl Default for SpendingPolicy {
+    fn default() -> Self {
+        Self {
+            daily_limit: None,
+            confirm_password_above: None,
+            cooling_off_above: None,
+            cooling_off_secs: default_cooling_off_secs(),
+        }
+    }
+}
+
+/// Why a send was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyBlock {
+    DailyLimitExceeded { spent: u64, limit: u64 },
+    PasswordConfirmationRequired { threshold: u64 },
+    CoolingOff { ready_at: i64 },
+}
+
+impl fmt::Display for PolicyBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyBlock::DailyLimitExceeded { spent, limit } => write!(
+                f,
+                "This send would exceed the daily limit of {} satoshis; {} were already sent in the last 24 hours",
+                limit, spent
+            ),
+            PolicyBlock::PasswordConfirmationRequired { threshold } => write!(
+                f,
+                "Sends above {} satoshis require the wallet password",
+                threshold
+            ),
+            PolicyBlock::CoolingOff { ready_at } => write!(
+                f,
+                "Large sends are held back; send again after {}",
+                chrono::DateTime::from_timestamp(*ready_at, 0).map_or_else(|| ready_at.to_string(), |time| time.to_rfc3339())
+            ),
+        }
+    }
+}
+
+impl From<PolicyBlock> for CommandError {
+    fn from(block: PolicyBlock) -> Self {
+        let code = match block {
+            PolicyBlock::DailyLimitExceeded { .. } => AppErrorCode::SpendingLimitExceeded,
+            PolicyBlock::PasswordConfirmationRequired { .. } => AppErrorCode::SpendConfirmationRequired,
+            PolicyBlock::CoolingOff { .. } => AppErrorCode::CoolingOffPending,
+        };
+        CommandError::new(code, block.to_string())
+    }
+}
+
+impl SpendingPolicy {
+    /// Check a send of `amount` against the daily cap, given what was sent in the last 24 hours,
+    /// and against the password threshold
+    pub fn check_limits(&self, amount: u64, spent_today: u64, password_confirmed: bool) -> Result<(), PolicyBlock> {
+        if let Some(limit) = self.daily_limit {
+            if spent_today.saturating_add(amount) > limit {
+                return Err(PolicyBlock::DailyLimitExceeded { spent: spent_today, limit });
+            }
+        }
+        if let Some(threshold) = self.confirm_password_above {
+            if amount > threshold && !password_confirmed {
+                return Err(PolicyBlock::PasswordConfirmationRequired { threshold });
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a send of `amount` is large enough to be held back
+    pub fn needs_cooling_off(&self, amount: u64) -> bool {
+        self.cooling_off_above.is_some_and(|threshold| amount > threshold)
+    }
+
+    /// Check a large send first attempted at `requested_at`
+    pub fn check_cooling_off(&self, requested_at: i64, now: i64) -> Result<(), PolicyBlock> {
+        let ready_at = requested_at + self.cooling_off_secs as i64;
+        if now < ready_at {
+            return Err(PolicyBlock::CoolingOff { ready_at });
+        }
+        Ok(())
+    }
+
+    /// Whether `new` lifts or raises any limit of this policy, which needs the wallet password
+    pub fn loosened_by(&self, new: &SpendingPolicy) -> bool {
+        let raised = |old: Option<u64>, new: Option<u64>| match (old, new) {
+            (Some(_), None) => true,
+            (Some(old), Some(new)) => new > old,
+            (None, _) => false,
+        };
+        raised(self.daily_limit, new.daily_limit)
+            || raised(self.confirm_password_above, new.confirm_password_above)
+            || raised(self.cooling_off_above, new.cooling_off_above)
+            || (self.cooling_off_above.is_some() && new.cooling_off_secs < self.cooling_off_secs)
+    }
+}
+
+/// A large send waiting out its cooling-off period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingSend {
+    /// Recipients and amounts of the send, see `send_key`
+    key: String,
+    requested_at: i64,
+}
+
+/// Identifies a send by what it pays to whom, so repeating it releases it
+pub fn send_key(payments: &[(&str, u64)]) -> String {
+    let mut payments: Vec<String> = payments.iter().map(|(address, value)| format!("{}:{}", address, value)).collect();
+    payments.sort();
+    payments.join(",")
+}
+
+fn load_pending(wallet_dir: &Path) -> Result<Vec<PendingSend>, WalletDataError> {
+    let path = wallet_dir.join(PENDING_SENDS_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(&path)?)?)
+}
+
+fn save_pending(wallet_dir: &Path, pending: &[PendingSend]) -> Result<(), WalletDataError> {
+    let path = wallet_dir.join(PENDING_SENDS_FILE);
+    atomic_file::write_atomic(&path, serde_json::to_string_pretty(pending)?.as_bytes())?;
+    debug!("Pending sends saved to {}", path.display());
+    Ok(())
+}
+
+/// When the send `key` was first attempted, starting its cooling-off period now if it was not
+/// held back before or its release window has passed
+pub fn pending_since(wallet_dir: &Path, key: &str, policy: &SpendingPolicy, now: i64) -> Result<i64, WalletDataError> {
+    let expiry = policy.cooling_off_secs as i64 + RELEASE_WINDOW_SECS;
+    let mut pending = load_pending(wallet_dir)?;
+    pending.retain(|send| now - send.requested_at < expiry);
+    let requested_at = match pending.iter().find(|send| send.key == key) {
+        Some(send) => send.requested_at,
+        None => {
+            pending.push(PendingSend { key: key.to_string(), requested_at: now });
+            now
+        }
+    };
+    save_pending(wallet_dir, &pending)?;
+    Ok(requested_at)
+}
+
+/// Forget the send `key` once it went out, so repeating it starts a new cooling-off period
+pub fn release(wallet_dir: &Path, key: &str) {
+    let result = load_pending(wallet_dir).and_then(|mut pending| {
+        let before = pending.len();
+        pending.retain(|send| send.key != key);
+        if pending.len() != before {
+            save_pending(wallet_dir, &pending)?;
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        warn!("Failed to update pending sends in {}: {}", wallet_dir.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_limits_and_cooling_off() {
+        let policy = SpendingPolicy {
+            daily_limit: Some(10_000),
+            confirm_password_above: Some(1_000),
+            cooling_off_above: Some(5_000),
+            cooling_off_secs: 3_600,
+        };
+        assert_eq!(policy.check_limits(500, 0, false), Ok(()));
+        assert_eq!(
+            policy.check_limits(2_000, 9_000, true),
+            Err(PolicyBlock::DailyLimitExceeded { spent: 9_000, limit: 10_000 })
+        );
+        assert_eq!(
+            policy.check_limits(2_000, 0, false),
+            Err(PolicyBlock::PasswordConfirmationRequired { threshold: 1_000 })
+        );
+        assert_eq!(policy.check_limits(2_000, 0, true), Ok(()));
+        assert!(!policy.needs_cooling_off(5_000));
+        assert!(policy.needs_cooling_off(6_000));
+        assert_eq!(policy.check_cooling_off(100, 200), Err(PolicyBlock::CoolingOff { ready_at: 3_700 }));
+        assert_eq!(policy.check_cooling_off(100, 3_700), Ok(()));
+
+        assert!(!policy.loosened_by(&SpendingPolicy { daily_limit: Some(5_000), ..policy.clone() }));
+        assert!(policy.loosened_by(&SpendingPolicy { daily_limit: None, ..policy.clone() }));
+        assert!(policy.loosened_by(&SpendingPolicy { cooling_off_secs: 60, ..policy.clone() }));
+        assert_eq!(send_key(&[("b", 2), ("a", 1)]), send_key(&[("a", 1), ("b", 2)]));
+    }
+}
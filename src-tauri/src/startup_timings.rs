@@ -0,0 +1,124 @@
+//! Durations of the startup phases, for `get_startup_timings`.
+//!
+//! Startup runs in phases so the window is usable early: the window opens first, then the
+//! configuration and wallet manager load and `APP_READY` is emitted, then the background services
+//! start, and the blockchain database, network and sync come last without holding up the UI.
+//! Each phase is recorded with its start and duration, measured from process start, so a
+//! regression in one of them shows up on the developer page. Phases recorded after startup
+//! completes, such as a later restart of the blockchain services, are not part of the report.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// One timed phase of startup
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupPhase {
+    pub name: &'static str,
+    /// Milliseconds from process start to the start of the phase
+    pub started_ms: u64,
+    pub duration_ms: u64,
+}
+
+/// Startup phases in the order they finished
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupReport {
+    pub phases: Vec<StartupPhase>,
+    /// Milliseconds from process start until startup completed; None while it is still running
+    pub total_ms: Option<u64>,
+}
+
+struct Recorded {
+    phases: Vec<StartupPhase>,
+    completed: Option<Duration>,
+}
+
+/// Startup phases as they finish; kept in Tauri state
+pub struct StartupTimings {
+    process_start: Instant,
+    recorded: Mutex<Recorded>,
+}
+
+impl StartupTimings {
+    /// Start the clock; created first thing in `run`
+    pub fn new() -> Self {
+        StartupTimings {
+            process_start: Instant::now(),
+            recorded: Mutex::new(Recorded {
+                phases: Vec::new(),
+                completed: None,
+            }),
+        }
+    }
+
+    pub fn process_start(&self) -> Instant {
+        self.process_start
+    }
+
+    /// Record a phase that started at `started` and finished now
+    pub fn record(&self, name: &'static str, started: Instant) {
+        let mut recorded = self.recorded.lock().unwrap();
+        if recorded.completed.is_some() {
+            return;
+        }
+        let phase = StartupPhase {
+            name,
+            started_ms: started.saturating_duration_since(self.process_start).as_millis() as u64,
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        log::info!("Startup phase {} took {} ms", phase.name, phase.duration_ms);
+        recorded.phases.push(phase);
+    }
+
+    /// Mark startup as complete; later phases are not recorded
+    pub fn complete(&self) {
+        let mut recorded = self.recorded.lock().unwrap();
+        if recorded.completed.is_none() {
+            let total = self.process_start.elapsed();
+            log::info!("Startup completed in {} ms", total.as_millis());
+            recorded.completed = Some(total);
+        }
+    }
+
+    pub fn report(&self) -> StartupReport {
+        let recorded = self.recorded.lock().unwrap();
+        StartupReport {
+            phases: recorded.phases.clone(),
+            total_ms: recorded.completed.map(|total| total.as_millis() as u64),
+        }
+    }
+}
+
+impl Default for StartupTimings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Record a phase in the app's startup timings
+pub fn record(app_handle: &AppHandle, name: &'static str, started: Instant) {
+    if let Some(timings) = app_handle.try_state::<StartupTimings>() {
+        timings.record(name, started);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phases_are_recorded_until_startup_completes() {
+        let timings = StartupTimings::new();
+        let started = Instant::now();
+        timings.record("config", started);
+        assert!(timings.report().total_ms.is_none());
+
+        timings.complete();
+        timings.record("network", Instant::now());
+        let report = timings.report();
+        assert_eq!(report.phases.iter().map(|phase| phase.name).collect::<Vec<_>>(), ["config"]);
+        assert!(report.total_ms.is_some());
+        assert!(report.phases[0].started_ms <= report.total_ms.unwrap());
+    }
+}
@@ -0,0 +1,154 @@
+//! Detection of wallet directories that should not be written to.
+//!
+//! A wallet opened from a backup on a read-only or removable drive is opened in a read-only
+//! session instead of failing on the first save, or losing changes when the drive is pulled
+//! halfway through one. Read-only media are found by trying to create a file next to wallet.dat;
+//! removable drives are recognised per platform.
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::Path;
+
+/// File created and removed again to find out whether a directory is writable
+const WRITE_PROBE_FILE: &str = ".write_probe";
+
+/// Why a wallet is opened read-only
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadOnlyReason {
+    /// wallet.dat itself is marked read-only
+    ReadOnlyFile,
+    /// The directory cannot be written, e.g. a write-protected drive or a read-only mount
+    ReadOnlyMedia,
+    /// The wallet is on a removable drive that may go away during a save
+    RemovableMedia,
+}
+
+impl ReadOnlyReason {
+    pub fn describe(self) -> &'static str {
+        match self {
+            ReadOnlyReason::ReadOnlyFile => "wallet.dat is read-only",
+            ReadOnlyReason::ReadOnlyMedia => "the wallet is on read-only media",
+            ReadOnlyReason::RemovableMedia => "the wallet is on a removable drive",
+        }
+    }
+}
+
+/// Whether the wallet in `wallet_dir` should be opened read-only, and why
+pub fn read_only_reason(wallet_dir: &Path) -> Option<ReadOnlyReason> {
+    let wallet_file = wallet_dir.join("wallet.dat");
+    if fs::metadata(&wallet_file).is_ok_and(|metadata| metadata.permissions().readonly()) {
+        return Some(ReadOnlyReason::ReadOnlyFile);
+    }
+    if !wallet_dir.exists() {
+        return None;
+    }
+    if !is_writable(wallet_dir) {
+        return Some(ReadOnlyReason::ReadOnlyMedia);
+    }
+    let canonical = fs::canonicalize(wallet_dir).unwrap_or_else(|_| wallet_dir.to_path_buf());
+    if is_removable(&canonical) {
+        return Some(ReadOnlyReason::RemovableMedia);
+    }
+    None
+}
+
+fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(WRITE_PROBE_FILE);
+    match OpenOptions::new().write(true).create(true).truncate(true).open(&probe) {
+        Ok(_) => {
+            let _ = fs::remove_file(&probe);
+            true
+        }
+        Err(e) if matches!(e.kind(), io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem) => {
+            debug!("{} is not writable: {}", dir.display(), e);
+            false
+        }
+        // Other failures say nothing about the media; the save itself will report them
+        Err(_) => true,
+    }
+}
+
+/// Whether the block device behind the mount holding `path` is removable
+#[cfg(target_os = "linux")]
+fn is_removable(path: &Path) -> bool {
+    let Ok(mounts) = fs::read_to_string("/proc/mounts") else {
+        return false;
+    };
+    // The mount point that is the longest prefix of the path holds it
+    let device = mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some((fields.next()?, fields.next()?.replace("\\040", " ")))
+        })
+        .filter(|(_, mount_point)| path.starts_with(mount_point))
+        .max_by_key(|(_, mount_point)| mount_point.len())
+        .map(|(device, _)| device.to_string());
+    let Some(device_name) = device.as_deref().and_then(|device| device.strip_prefix("/dev/")) else {
+        return false;
+    };
+
+    // A partition has no removable flag of its own; its parent disk does
+    let Ok(block) = fs::canonicalize(Path::new("/sys/class/block").join(device_name)) else {
+        return false;
+    };
+    std::iter::once(block.as_path())
+        .chain(block.parent())
+        .filter_map(|dir| fs::read_to_string(dir.join("removable")).ok())
+        .any(|flag| flag.trim() == "1")
+}
+
+/// External and removable volumes are mounted under /Volumes; the boot volume resolves to /
+#[cfg(target_os = "macos")]
+fn is_removable(path: &Path) -> bool {
+    path.starts_with("/Volumes")
+}
+
+#[cfg(windows)]
+fn is_removable(path: &Path) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Component;
+
+    /// Drive type of removable media such as USB sticks and SD cards
+    const DRIVE_REMOVABLE: u32 = 2;
+
+    let Some(Component::Prefix(prefix)) = path.components().next() else {
+        return false;
+    };
+    let mut root: Vec<u16> = prefix.as_os_str().encode_wide().collect();
+    root.extend("\\\0".encode_utf16());
+    // SAFETY: `root` is a NUL-terminated UTF-16 string that outlives the call
+    unsafe { windows_sys::Win32::Storage::FileSystem::GetDriveTypeW(root.as_ptr()) == DRIVE_REMOVABLE }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn is_removable(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_only_wallet_file_is_detected() {
+        let dir = std::env::temp_dir().join(format!("bradcoin_storage_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let wallet_file = dir.join("wallet.dat");
+        fs::write(&wallet_file, b"{}").unwrap();
+        assert_ne!(read_only_reason(&dir), Some(ReadOnlyReason::ReadOnlyFile));
+
+        let mut permissions = fs::metadata(&wallet_file).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&wallet_file, permissions.clone()).unwrap();
+        assert_eq!(read_only_reason(&dir), Some(ReadOnlyReason::ReadOnlyFile));
+
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&wallet_file, permissions).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
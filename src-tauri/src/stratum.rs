@@ -0,0 +1,524 @@
+//! Stratum-style mining server for miners on the local network.
+//!
+//! When enabled in settings, the node accepts miner processes over TCP and hands them work built
+//! from the mining service's block template, so several machines can mine for one wallet. The
+//! protocol is newline-delimited JSON-RPC in the style of stratum v1:
+//!
+//! - `mining.subscribe` returns `[null, nonce_prefix, 6]`. Every connection gets its own 2-byte
+//!   prefix for the top of the 8-byte nonce, so workers never search the same nonces.
+//! - `mining.authorize [worker]` names the worker its shares are counted for.
+//! - `mining.notify [job_id, height, previous_hash, merkle_root, timestamp, bits, share_target, clean]`
+//!   is sent on subscribe and whenever the work changes; `clean` means older jobs are stale.
+//!   The header hashed is the one of `mining_service::header_hash`.
+//! - `mining.submit [worker, job_id, nonce]` with the nonce as hex. A share is accepted when its
+//!   hash is below the share target, which is easier than the network target so workers report
+//!   progress; a share that also meets the network target becomes a block and is submitted.
+//!
+//! Blocks pay the configured payout address, never an address chosen by a worker. The server is
+//! off by default and, unlike the other local servers, binds every interface so LAN machines can
+//! reach it.
+
+use crate::blockchain_database::AsyncBlockchainDatabase;
+use crate::config::AppSettings;
+use crate::event_bus::{BackendEvent, EventBus};
+use crate::mining_service::{header_hash, hash_meets_target, AsyncMiningService, BlockTemplate};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU16, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, watch};
+
+/// Port the stratum server listens on unless configured otherwise
+pub const DEFAULT_STRATUM_PORT: u16 = 3333;
+
+/// Most miner connections served at once
+const MAX_CONNECTIONS: usize = 64;
+
+/// Longest request line accepted from a miner
+const MAX_LINE_BYTES: usize = 16 * 1024;
+
+/// Longest worker name accepted
+const MAX_WORKER_NAME: usize = 64;
+
+/// How often the chain tip is checked for new work, besides right after each new block
+const TIP_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Work is rebuilt at least this often, to pick up new mempool transactions
+const JOB_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Jobs on the current tip that shares are still accepted for
+const RECENT_JOBS: usize = 4;
+
+/// Shares are accepted at this many times the network target
+const SHARE_TARGET_FACTOR: u64 = 256;
+
+/// Shares and blocks of one worker since the app started
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WorkerStats {
+    pub worker: String,
+    /// Connections currently authorized as this worker
+    pub connections: u32,
+    pub accepted_shares: u64,
+    pub rejected_shares: u64,
+    pub blocks_found: u64,
+    pub last_share_at: Option<i64>,
+}
+
+type WorkerMap = Arc<Mutex<HashMap<String, WorkerStats>>>;
+
+/// Work handed to miners
+struct Job {
+    id: String,
+    template: BlockTemplate,
+    target: u64,
+    share_target: u64,
+    /// Whether jobs sent before this one are stale
+    clean: bool,
+    /// Nonces already submitted, so the same share is not counted twice
+    submitted: Mutex<HashSet<u64>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ShareOutcome {
+    Rejected(&'static str),
+    Share,
+    Block,
+}
+
+impl Job {
+    fn new(id: String, template: BlockTemplate, clean: bool) -> Self {
+        let target = u64::from_str_radix(&template.target, 16).unwrap_or(0);
+        Self {
+            id,
+            template,
+            target,
+            share_target: target.saturating_mul(SHARE_TARGET_FACTOR),
+            clean,
+            submitted: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn notify_params(&self) -> Value {
+        json!([
+            self.id,
+            self.template.height,
+            self.template.previous_hash,
+            self.template.merkle_root,
+            self.template.timestamp,
+            self.template.bits,
+            format!("{:016x}", self.share_target),
+            self.clean,
+        ])
+    }
+
+    /// Check a share from the connection that was given `nonce_prefix`
+    fn check_share(&self, nonce: u64, nonce_prefix: u16) -> ShareOutcome {
+        if (nonce >> 48) as u16 != nonce_prefix {
+            return ShareOutcome::Rejected("Nonce outside the assigned range");
+        }
+        if !self.submitted.lock().unwrap().insert(nonce) {
+            return ShareOutcome::Rejected("Duplicate share");
+        }
+        let template = &self.template;
+        let hash = header_hash(template.height, &template.previous_hash, &template.merkle_root, template.timestamp, self.target, nonce);
+        if hash_meets_target(&hash, self.target) {
+            ShareOutcome::Block
+        } else if hash_meets_target(&hash, self.share_target) {
+            ShareOutcome::Share
+        } else {
+            ShareOutcome::Rejected("Share above target")
+        }
+    }
+}
+
+/// Current jobs and the channel announcing new ones
+struct Work {
+    jobs: Mutex<VecDeque<Arc<Job>>>,
+    announcements: broadcast::Sender<Arc<Job>>,
+}
+
+impl Work {
+    fn publish(&self, job: Job) {
+        let job = Arc::new(job);
+        let mut jobs = self.jobs.lock().unwrap();
+        if job.clean {
+            jobs.clear();
+        }
+        jobs.push_back(job.clone());
+        while jobs.len() > RECENT_JOBS {
+            jobs.pop_front();
+        }
+        let _ = self.announcements.send(job);
+    }
+
+    fn latest(&self) -> Option<Arc<Job>> {
+        self.jobs.lock().unwrap().back().cloned()
+    }
+
+    fn find(&self, id: &str) -> Option<Arc<Job>> {
+        self.jobs.lock().unwrap().iter().find(|job| job.id == id).cloned()
+    }
+}
+
+/// Check a stratum port; privileged ports are refused
+pub fn validate_port(port: u16) -> Result<(), String> {
+    if port < 1024 {
+        return Err(format!("Stratum port must be 1024 or higher, got {}", port));
+    }
+    Ok(())
+}
+
+/// Listener task and a switch that disconnects its miners
+struct RunningServer {
+    task: tauri::async_runtime::JoinHandle<()>,
+    _shutdown: watch::Sender<()>,
+}
+
+/// Stratum server shared through Tauri state
+#[derive(Default)]
+pub struct StratumServer {
+    running: Mutex<Option<RunningServer>>,
+    workers: WorkerMap,
+}
+
+impl StratumServer {
+    /// Stop the current listener and its miners, then start again if the settings enable it.
+    /// Called at startup and whenever the stratum settings change.
+    pub fn apply_settings(&self, app_handle: &AppHandle, settings: &AppSettings) {
+        let mut running = self.running.lock().unwrap();
+        if let Some(server) = running.take() {
+            server.task.abort();
+            info!("Stratum server stopped");
+        }
+
+        if !settings.stratum_enabled {
+            return;
+        }
+        let Some(payout_address) = settings.stratum_payout_address.clone() else {
+            warn!("Stratum server is enabled without a payout address, not starting it");
+            return;
+        };
+
+        let (shutdown, shutdown_rx) = watch::channel(());
+        let task = tauri::async_runtime::spawn(serve(
+            app_handle.clone(),
+            settings.stratum_port,
+            payout_address,
+            self.workers.clone(),
+            shutdown_rx,
+        ));
+        *running = Some(RunningServer { task, _shutdown: shutdown });
+    }
+
+    /// Statistics of every worker seen since the app started, by name
+    pub fn workers(&self) -> Vec<WorkerStats> {
+        let mut workers: Vec<WorkerStats> = self.workers.lock().unwrap().values().cloned().collect();
+        workers.sort_by(|a, b| a.worker.cmp(&b.worker));
+        workers
+    }
+}
+
+async fn serve(app_handle: AppHandle, port: u16, payout_address: String, workers: WorkerMap, shutdown: watch::Receiver<()>) {
+    let listener = match TcpListener::bind((std::net::Ipv4Addr::UNSPECIFIED, port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!("Stratum server could not listen on port {}: {}", port, e);
+            return;
+        }
+    };
+    info!("Stratum server listening on 0.0.0.0:{}, paying {}", port, payout_address);
+
+    let work = Arc::new(Work { jobs: Mutex::new(VecDeque::new()), announcements: broadcast::channel(16).0 });
+    let connections = Arc::new(AtomicUsize::new(0));
+    let next_prefix = AtomicU16::new(0);
+    let mut tip_poll = tokio::time::interval(TIP_POLL_INTERVAL);
+    let mut backend_events = app_handle.state::<EventBus>().subscribe();
+    let mut last_tip = None;
+    let mut last_job_at = Instant::now();
+    let mut job_counter = 0u64;
+
+    loop {
+        tokio::select! {
+            _ = tip_poll.tick() => {
+                let Some(blockchain_db) = crate::services::get::<AsyncBlockchainDatabase>(&app_handle) else { continue };
+                let Ok(height) = blockchain_db.get_block_height().await else { continue };
+                let clean = last_tip != Some(height);
+                if !clean && last_job_at.elapsed() < JOB_REFRESH_INTERVAL {
+                    continue;
+                }
+                let Some(mining_service) = crate::services::get::<AsyncMiningService>(&app_handle) else { continue };
+                match mining_service.block_template(&payout_address).await {
+                    Ok(template) => {
+                        job_counter += 1;
+                        debug!("Stratum job {} for height {}", job_counter, template.height);
+                        work.publish(Job::new(format!("{:x}", job_counter), template, clean));
+                        last_tip = Some(height);
+                        last_job_at = Instant::now();
+                    }
+                    Err(e) => warn!("Stratum server could not build a block template: {}", e),
+                }
+            }
+            Ok(event) = backend_events.recv() => {
+                // A new block makes the current jobs stale, so look for work straight away
+                if matches!(event, BackendEvent::BlockConnected(_)) {
+                    tip_poll.reset_immediately();
+                }
+            }
+            accepted = listener.accept() => {
+                let (stream, addr) = match accepted {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        warn!("Stratum server failed to accept a connection: {}", e);
+                        continue;
+                    }
+                };
+                if connections.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+                    warn!("Stratum server refused {}: {} miners already connected", addr, MAX_CONNECTIONS);
+                    continue;
+                }
+
+                connections.fetch_add(1, Ordering::SeqCst);
+                let prefix = next_prefix.fetch_add(1, Ordering::SeqCst);
+                let (app_handle, work, workers, connections, shutdown) =
+                    (app_handle.clone(), work.clone(), workers.clone(), connections.clone(), shutdown.clone());
+                tauri::async_runtime::spawn(async move {
+                    let mut session = Session { addr, nonce_prefix: prefix, subscribed: false, worker: None };
+                    if let Err(e) = session.run(stream, &app_handle, &work, &workers, shutdown).await {
+                        debug!("Stratum connection from {} ended: {}", addr, e);
+                    }
+                    if let Some(worker) = session.worker {
+                        if let Some(stats) = workers.lock().unwrap().get_mut(&worker) {
+                            stats.connections = stats.connections.saturating_sub(1);
+                        }
+                    }
+                    connections.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Vec<Value>,
+}
+
+/// One miner connection
+struct Session {
+    addr: SocketAddr,
+    nonce_prefix: u16,
+    subscribed: bool,
+    worker: Option<String>,
+}
+
+impl Session {
+    async fn run(
+        &mut self,
+        stream: TcpStream,
+        app_handle: &AppHandle,
+        work: &Work,
+        workers: &WorkerMap,
+        mut shutdown: watch::Receiver<()>,
+    ) -> std::io::Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut announcements = work.announcements.subscribe();
+        let mut line = Vec::new();
+
+        loop {
+            let mut limited = (&mut reader).take((MAX_LINE_BYTES - line.len()) as u64);
+            tokio::select! {
+                // read_until keeps partial lines in `line`, so losing the race to a job is harmless
+                read = limited.read_until(b'\n', &mut line) => {
+                    if read? == 0 {
+                        return Ok(());
+                    }
+                    if line.last() != Some(&b'\n') {
+                        if line.len() >= MAX_LINE_BYTES {
+                            warn!("Stratum miner {} sent an oversized request", self.addr);
+                            return Ok(());
+                        }
+                        continue;
+                    }
+                    let request = serde_json::from_slice::<Request>(&line);
+                    line.clear();
+                    let request = match request {
+                        Ok(request) => request,
+                        Err(e) => {
+                            send(&mut writer, &reply(Value::Null, Err(format!("Malformed request: {}", e)))).await?;
+                            continue;
+                        }
+                    };
+                    let was_subscribed = self.subscribed;
+                    let result = self.handle(&request, app_handle, work, workers).await;
+                    send(&mut writer, &reply(request.id, result)).await?;
+                    if self.subscribed && !was_subscribed {
+                        if let Some(job) = work.latest() {
+                            send(&mut writer, &notify(&job)).await?;
+                        }
+                    }
+                }
+                job = announcements.recv(), if self.subscribed => match job {
+                    Ok(job) => send(&mut writer, &notify(&job)).await?,
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                },
+                _ = shutdown.changed() => return Ok(()),
+            }
+        }
+    }
+
+    async fn handle(&mut self, request: &Request, app_handle: &AppHandle, work: &Work, workers: &WorkerMap) -> Result<Value, String> {
+        match request.method.as_str() {
+            "mining.subscribe" => {
+                self.subscribed = true;
+                Ok(json!([null, format!("{:04x}", self.nonce_prefix), 6]))
+            }
+            "mining.authorize" => {
+                let name = request.params.first().and_then(Value::as_str).map(str::trim).unwrap_or_default();
+                if name.is_empty() || name.len() > MAX_WORKER_NAME {
+                    return Err(format!("Worker name must be 1 to {} characters", MAX_WORKER_NAME));
+                }
+                if self.worker.as_deref() != Some(name) {
+                    let mut workers = workers.lock().unwrap();
+                    if let Some(previous) = self.worker.take().and_then(|previous| workers.get_mut(&previous)) {
+                        previous.connections = previous.connections.saturating_sub(1);
+                    }
+                    let stats = workers.entry(name.to_string()).or_insert_with(|| WorkerStats { worker: name.to_string(), ..WorkerStats::default() });
+                    stats.connections += 1;
+                    self.worker = Some(name.to_string());
+                }
+                info!("Stratum worker {} authorized from {}", name, self.addr);
+                crate::logging::log_audit_event("stratum_worker_authorized", &format!("worker={} addr={}", name, self.addr));
+                Ok(json!(true))
+            }
+            "mining.submit" => {
+                let worker = self.worker.clone().ok_or("Not authorized")?;
+                if request.params.first().and_then(Value::as_str) != Some(worker.as_str()) {
+                    return Err("Unauthorized worker".to_string());
+                }
+                let job_id = request.params.get(1).and_then(Value::as_str).ok_or("Missing job id")?;
+                let nonce = request
+                    .params
+                    .get(2)
+                    .and_then(Value::as_str)
+                    .and_then(|nonce| u64::from_str_radix(nonce.trim_start_matches("0x"), 16).ok())
+                    .ok_or("Missing or malformed nonce")?;
+
+                let outcome = match work.find(job_id) {
+                    Some(job) => {
+                        let outcome = job.check_share(nonce, self.nonce_prefix);
+                        if outcome == ShareOutcome::Block {
+                            submit_block(app_handle, &job, nonce, &worker).await
+                        } else {
+                            outcome
+                        }
+                    }
+                    None => ShareOutcome::Rejected("Stale job"),
+                };
+
+                let mut workers = workers.lock().unwrap();
+                let stats = workers.entry(worker.clone()).or_default();
+                match outcome {
+                    ShareOutcome::Rejected(reason) => {
+                        stats.rejected_shares += 1;
+                        Err(reason.to_string())
+                    }
+                    ShareOutcome::Share | ShareOutcome::Block => {
+                        stats.accepted_shares += 1;
+                        stats.blocks_found += u64::from(outcome == ShareOutcome::Block);
+                        stats.last_share_at = Some(chrono::Utc::now().timestamp());
+                        Ok(json!(true))
+                    }
+                }
+            }
+            other => Err(format!("Unknown method {}", other)),
+        }
+    }
+}
+
+/// Assemble the block a winning share completes and hand it to the mining service. A block the
+/// node refuses still counts as a share, since the worker did the work for the job it was given.
+async fn submit_block(app_handle: &AppHandle, job: &Job, nonce: u64, worker: &str) -> ShareOutcome {
+    let Some(mining_service) = crate::services::get::<AsyncMiningService>(app_handle) else {
+        return ShareOutcome::Share;
+    };
+    match mining_service.submit_block(job.template.clone().into_block(nonce)).await {
+        Ok(hash) => {
+            info!("Stratum worker {} found block {} at height {}", worker, hash, job.template.height);
+            crate::logging::log_audit_event(
+                "stratum_block_found",
+                &format!("worker={} height={} hash={}", worker, job.template.height, hash),
+            );
+            ShareOutcome::Block
+        }
+        Err(e) => {
+            warn!("Block from stratum worker {} was rejected: {}", worker, e);
+            ShareOutcome::Share
+        }
+    }
+}
+
+fn reply(id: Value, result: Result<Value, String>) -> Value {
+    match result {
+        Ok(result) => json!({ "id": id, "result": result, "error": null }),
+        Err(message) => json!({ "id": id, "result": null, "error": [20, message, null] }),
+    }
+}
+
+fn notify(job: &Job) -> Value {
+    json!({ "id": null, "method": "mining.notify", "params": job.notify_params() })
+}
+
+async fn send(writer: &mut OwnedWriteHalf, message: &Value) -> std::io::Result<()> {
+    let mut line = message.to_string();
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mining_service::{calculate_merkle_root, coinbase_transaction};
+
+    #[test]
+    fn test_shares_are_checked_against_range_duplicates_and_targets() {
+        let transactions = vec![coinbase_transaction(1, "bc1qpayout", 1_700_000_000)];
+        let template = BlockTemplate {
+            height: 1,
+            previous_hash: "0".repeat(64),
+            timestamp: 1_700_000_000,
+            difficulty: 1,
+            target: format!("{:016x}", u64::MAX),
+            bits: 0,
+            merkle_root: calculate_merkle_root(&transactions),
+            transactions,
+            coinbase_value: 0,
+            size_limit: 0,
+        };
+
+        let job = Job::new("1".to_string(), template.clone(), true);
+        let prefix = 7u16;
+        let nonce = (u64::from(prefix) << 48) | 42;
+        assert_eq!(job.check_share(nonce, prefix), ShareOutcome::Block);
+        assert_eq!(job.check_share(nonce, prefix), ShareOutcome::Rejected("Duplicate share"));
+        assert_eq!(job.check_share(42, prefix), ShareOutcome::Rejected("Nonce outside the assigned range"));
+
+        let job = Job { target: 0, share_target: u64::MAX, ..Job::new("2".to_string(), template.clone(), false) };
+        assert_eq!(job.check_share(nonce, prefix), ShareOutcome::Share);
+        let job = Job { target: 0, share_target: 0, ..Job::new("3".to_string(), template, false) };
+        assert_eq!(job.check_share(nonce, prefix), ShareOutcome::Rejected("Share above target"));
+    }
+}
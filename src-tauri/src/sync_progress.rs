@@ -0,0 +1,323 @@
+//! Progress of initial block download, kept across restarts.
+//!
+//! Headers are validated ahead of blocks, so at any point sync knows of blocks it has asked a
+//! peer for, and of blocks that arrived ahead of their parent and wait to be connected. This
+//! state is stored with the blocks, so a node that quits mid-sync resumes from its best header
+//! instead of asking for the chain again from its tip. Requests that were in flight when the node
+//! stopped are sent again, to the same peer if it is connected and otherwise to another one.
+//!
+//! Blocks are only asked for within a window of `BLOCK_DOWNLOAD_WINDOW` heights above the chain
+//! tip, so blocks stashed ahead of their parent stay bounded. The window's lowest blocks go to the
+//! best-scoring peers, and each peer has a limit of blocks in flight that grows with its score: a
+//! slow peer holds few blocks and the ones the chain needs next are with fast peers. A request
+//! not answered within `BLOCK_REQUEST_TIMEOUT_SECS` goes to another peer.
+
+use crate::network_constants::{
+    BLOCK_DOWNLOAD_WINDOW, FULL_IN_FLIGHT_SCORE, MAX_BLOCKS_IN_FLIGHT_PER_PEER, MIN_BLOCKS_IN_FLIGHT_PER_PEER,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// A block sync has asked for, or is about to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockRequest {
+    pub hash: String,
+    /// Peer the block is assigned to; None once that peer is gone
+    pub peer: Option<SocketAddr>,
+    /// The block arrived ahead of its parent and is stored until it can be connected
+    pub downloaded: bool,
+    /// Peer a request timed out at, passed over while others can take the block
+    #[serde(skip)]
+    timed_out_at: Option<SocketAddr>,
+}
+
+/// A connected peer blocks can be downloaded from
+#[derive(Debug, Clone, Copy)]
+pub struct DownloadPeer {
+    pub addr: SocketAddr,
+    /// Total score, 0 to 1000
+    pub score: i32,
+    /// Best height the peer has announced
+    pub height: u64,
+}
+
+impl DownloadPeer {
+    /// Blocks the peer may have in flight at once
+    pub fn in_flight_limit(&self) -> usize {
+        let share = self.score.clamp(0, FULL_IN_FLIGHT_SCORE) as usize;
+        (MAX_BLOCKS_IN_FLIGHT_PER_PEER * share / FULL_IN_FLIGHT_SCORE as usize).max(MIN_BLOCKS_IN_FLIGHT_PER_PEER)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncProgress {
+    /// Height and hash of the last header that passed validation
+    pub best_header_height: u64,
+    pub best_header_hash: Option<String>,
+    /// Blocks between the chain tip and the best header, by height
+    requests: BTreeMap<u64, BlockRequest>,
+    /// Heights asked for since the node started, and when; requests from before a restart will
+    /// not be answered
+    #[serde(skip)]
+    in_flight: HashMap<u64, Instant>,
+    /// When a block last arrived or was asked for
+    #[serde(skip)]
+    last_progress: Option<Instant>,
+}
+
+impl SyncProgress {
+    /// Note a validated header whose block is to be downloaded; `schedule` assigns it to a peer.
+    /// Returns false if the block is already queued.
+    pub fn queue_block(&mut self, height: u64, hash: &str) -> bool {
+        match self.requests.get(&height) {
+            Some(request) if request.hash == hash => return false,
+            Some(_) => {
+                // A different header at this height invalidates everything queued from here on
+                self.requests.retain(|h, _| *h < height);
+                self.in_flight.retain(|h, _| *h < height);
+                self.best_header_height = height;
+            }
+            None => {}
+        }
+        if height >= self.best_header_height {
+            self.best_header_height = height;
+            self.best_header_hash = Some(hash.to_string());
+        }
+        self.requests.insert(
+            height,
+            BlockRequest { hash: hash.to_string(), peer: None, downloaded: false, timed_out_at: None },
+        );
+        true
+    }
+
+    /// Whether `hash` at `height` is a block sync is waiting for
+    pub fn is_expected(&self, height: u64, hash: &str) -> bool {
+        self.requests.get(&height).is_some_and(|request| request.hash == hash)
+    }
+
+    /// Note that a requested block arrived before its parent and was stored aside
+    pub fn mark_downloaded(&mut self, height: u64) {
+        if let Some(request) = self.requests.get_mut(&height) {
+            request.downloaded = true;
+        }
+        self.in_flight.remove(&height);
+        self.last_progress = Some(Instant::now());
+    }
+
+    /// Whether the block at `height` is stored aside, waiting for its parent
+    pub fn is_downloaded(&self, height: u64) -> bool {
+        self.requests.get(&height).is_some_and(|request| request.downloaded)
+    }
+
+    /// Ask for the block at `height` again, after the stored copy failed validation
+    pub fn retry(&mut self, height: u64) {
+        if let Some(request) = self.requests.get_mut(&height) {
+            request.downloaded = false;
+            request.peer = None;
+        }
+        self.in_flight.remove(&height);
+    }
+
+    /// Note that the chain tip advanced to `height`
+    pub fn mark_connected(&mut self, height: u64) {
+        self.requests = self.requests.split_off(&(height + 1));
+        self.in_flight.retain(|h, _| *h > height);
+        self.last_progress = Some(Instant::now());
+        if self.best_header_height < height {
+            self.best_header_height = height;
+            self.best_header_hash = None;
+        }
+    }
+
+    /// Assign the blocks in the download window that still need asking for to connected peers:
+    /// those neither downloaded nor in flight to a connected peer. Lower heights go to
+    /// higher-scoring peers, up to each peer's in-flight limit, and only to peers that announced
+    /// the height. Returns the hashes to request from each peer.
+    pub fn schedule(&mut self, peers: &[DownloadPeer], now: Instant) -> BTreeMap<SocketAddr, Vec<String>> {
+        let mut assigned: BTreeMap<SocketAddr, Vec<String>> = BTreeMap::new();
+        let Some(&window_start) = self.requests.keys().next() else {
+            return assigned;
+        };
+        let connected = |peer: Option<SocketAddr>| peer.and_then(|peer| peers.iter().find(|p| p.addr == peer));
+
+        let mut ranked: Vec<&DownloadPeer> = peers.iter().collect();
+        ranked.sort_by_key(|peer| std::cmp::Reverse(peer.score));
+        let mut free: HashMap<SocketAddr, usize> = ranked.iter().map(|peer| (peer.addr, peer.in_flight_limit())).collect();
+        for (height, request) in &self.requests {
+            if let Some(peer) = connected(request.peer).filter(|_| self.in_flight.contains_key(height)) {
+                let slots = free.get_mut(&peer.addr).expect("every connected peer has slots");
+                *slots = slots.saturating_sub(1);
+            }
+        }
+
+        for (height, request) in self.requests.range_mut(window_start..window_start + BLOCK_DOWNLOAD_WINDOW) {
+            if request.downloaded || (connected(request.peer).is_some() && self.in_flight.contains_key(height)) {
+                continue;
+            }
+            let available = |peer: &&&DownloadPeer| peer.height >= *height && free[&peer.addr] > 0;
+            let peer = ranked
+                .iter()
+                .filter(available)
+                .find(|peer| request.timed_out_at != Some(peer.addr))
+                .or_else(|| ranked.iter().find(available));
+            let Some(peer) = peer else {
+                continue;
+            };
+            *free.get_mut(&peer.addr).expect("every peer has slots") -= 1;
+            request.peer = Some(peer.addr);
+            self.in_flight.insert(*height, now);
+            assigned.entry(peer.addr).or_default().push(request.hash.clone());
+        }
+        if !assigned.is_empty() {
+            self.last_progress = Some(now);
+        }
+        assigned
+    }
+
+    /// Give up on requests that have gone unanswered for `timeout`, so `schedule` asks another
+    /// peer. Returns how many requests each peer let time out.
+    pub fn expire_requests(&mut self, now: Instant, timeout: Duration) -> BTreeMap<SocketAddr, usize> {
+        let mut expired: BTreeMap<SocketAddr, usize> = BTreeMap::new();
+        let requests = &mut self.requests;
+        self.in_flight.retain(|height, requested_at| {
+            if now.saturating_duration_since(*requested_at) < timeout {
+                return true;
+            }
+            if let Some(request) = requests.get_mut(height) {
+                if let Some(peer) = request.peer.take() {
+                    *expired.entry(peer).or_default() += 1;
+                    request.timed_out_at = Some(peer);
+                }
+            }
+            false
+        });
+        expired
+    }
+
+    /// The peer holding up the download, if no block has arrived for `timeout` while the lowest
+    /// missing block is in flight to it. Returns the peer and how long the download has stalled.
+    pub fn stalled_peer(&mut self, now: Instant, timeout: Duration) -> Option<(SocketAddr, Duration)> {
+        let (height, request) = self.requests.iter().find(|(_, request)| !request.downloaded)?;
+        if !self.in_flight.contains_key(height) {
+            return None;
+        }
+        // Progress from before a restart is unknown, so the clock starts at the first check
+        let stalled_for = now.saturating_duration_since(*self.last_progress.get_or_insert(now));
+        if stalled_for < timeout {
+            return None;
+        }
+        request.peer.map(|peer| (peer, stalled_for))
+    }
+
+    /// Move the blocks still expected from `from` to `to` and restart the stall clock. Returns the
+    /// hashes to request from `to`.
+    pub fn reassign_peer(&mut self, from: SocketAddr, to: SocketAddr, now: Instant) -> Vec<String> {
+        let mut hashes = Vec::new();
+        for (height, request) in self.requests.iter_mut() {
+            if request.peer == Some(from) && !request.downloaded {
+                request.peer = Some(to);
+                self.in_flight.insert(*height, now);
+                hashes.push(request.hash.clone());
+            }
+        }
+        self.last_progress = Some(now);
+        hashes
+    }
+
+    /// Blocks waiting for download or connection
+    pub fn outstanding(&self) -> usize {
+        self.requests.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(addr: SocketAddr, score: i32, height: u64) -> DownloadPeer {
+        DownloadPeer { addr, score, height }
+    }
+
+    #[test]
+    fn test_progress_survives_restart_and_reassigns_blocks() {
+        let peer_a: SocketAddr = "10.0.0.1:8333".parse().unwrap();
+        let peer_b: SocketAddr = "10.0.0.2:8333".parse().unwrap();
+        let start = Instant::now();
+
+        let mut progress = SyncProgress::default();
+        for height in 11..=14 {
+            assert!(progress.queue_block(height, &format!("hash{}", height)));
+        }
+        assert!(!progress.queue_block(12, "hash12"));
+        assert_eq!(progress.schedule(&[peer(peer_a, 300, 14)], start).get(&peer_a).map(Vec::len), Some(4));
+        progress.mark_downloaded(13);
+        progress.mark_connected(11);
+        assert_eq!(progress.best_header_height, 14);
+        assert_eq!(progress.outstanding(), 3);
+
+        // Nothing is sent twice while the requests are in flight
+        assert!(progress.schedule(&[peer(peer_a, 300, 14)], start).is_empty());
+
+        let bytes = bincode::serde::encode_to_vec(&progress, bincode::config::standard()).unwrap();
+        let mut restored: SyncProgress = bincode::serde::decode_from_slice(&bytes, bincode::config::standard()).unwrap().0;
+        assert!(restored.is_downloaded(13));
+        assert!(restored.is_expected(14, "hash14"));
+
+        // After a restart the blocks not yet downloaded go to whichever peers are connected
+        let assigned = restored.schedule(&[peer(peer_b, 300, 14)], start);
+        assert_eq!(assigned.get(&peer_b), Some(&vec!["hash12".to_string(), "hash14".to_string()]));
+        assert!(restored.schedule(&[peer(peer_a, 300, 14), peer(peer_b, 300, 14)], start).is_empty());
+
+        // Requests to a peer that went away are moved to one that is still connected
+        let assigned = restored.schedule(&[peer(peer_a, 300, 14)], start);
+        assert_eq!(assigned.get(&peer_a).map(Vec::len), Some(2));
+
+        // A peer that sits on the lowest missing block is found once the timeout passes
+        let timeout = Duration::from_secs(60);
+        assert_eq!(restored.stalled_peer(start, timeout), None);
+        let later = start + timeout;
+        assert_eq!(restored.stalled_peer(later, timeout).map(|(peer, _)| peer), Some(peer_a));
+        assert_eq!(restored.reassign_peer(peer_a, peer_b, later).len(), 2);
+        assert_eq!(restored.stalled_peer(later, timeout), None);
+    }
+
+    #[test]
+    fn test_window_goes_to_the_best_peers_first_and_timed_out_requests_move() {
+        let fast: SocketAddr = "10.0.0.1:8333".parse().unwrap();
+        let slow: SocketAddr = "10.0.0.2:8333".parse().unwrap();
+        let start = Instant::now();
+
+        let mut progress = SyncProgress::default();
+        for height in 1..=BLOCK_DOWNLOAD_WINDOW + 10 {
+            progress.queue_block(height, &format!("hash{}", height));
+        }
+        let peers = [peer(slow, 0, 2000), peer(fast, 1000, 2000)];
+        assert_eq!(peers[0].in_flight_limit(), MIN_BLOCKS_IN_FLIGHT_PER_PEER);
+        assert_eq!(peers[1].in_flight_limit(), MAX_BLOCKS_IN_FLIGHT_PER_PEER);
+
+        // The lowest heights go to the fast peer, the next few to the slow one
+        let assigned = progress.schedule(&peers, start);
+        assert_eq!(assigned[&fast].first().map(String::as_str), Some("hash1"));
+        assert_eq!(assigned[&fast].len(), MAX_BLOCKS_IN_FLIGHT_PER_PEER);
+        assert_eq!(assigned[&slow].len(), MIN_BLOCKS_IN_FLIGHT_PER_PEER);
+        assert!(progress.schedule(&peers, start).is_empty());
+
+        // Connected blocks free slots and move the window
+        progress.mark_connected(BLOCK_DOWNLOAD_WINDOW - 4);
+        let assigned = progress.schedule(&peers, start);
+        let heights: Vec<u64> = assigned[&fast].iter().map(|hash| hash[4..].parse().unwrap()).collect();
+        assert_eq!(heights.first(), Some(&(BLOCK_DOWNLOAD_WINDOW - 3)));
+        assert!(heights.iter().all(|height| *height < 2 * BLOCK_DOWNLOAD_WINDOW - 3));
+
+        // Unanswered requests are given up on and go to another peer
+        let later = start + Duration::from_secs(30);
+        assert!(progress.expire_requests(start, Duration::from_secs(30)).is_empty());
+        let expired = progress.expire_requests(later, Duration::from_secs(30));
+        assert_eq!(expired[&fast], 14);
+        let equals = [peer(fast, 1000, 2000), peer(slow, 1000, 2000)];
+        let assigned = progress.schedule(&equals, later);
+        assert_eq!(assigned[&slow].first().map(String::as_str), Some(format!("hash{}", BLOCK_DOWNLOAD_WINDOW - 3).as_str()));
+    }
+}
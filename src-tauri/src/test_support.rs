@@ -0,0 +1,278 @@
+//! Deterministic node stack for end-to-end tests.
+//!
+//! A `TestNode` runs the real blockchain database and mempool against a temporary directory,
+//! starting from the regtest genesis block. Blocks are assembled by the mining service's own code
+//! at a target every hash meets, and block times follow a fixed clock, so a test produces the
+//! same chain on every run. The sync service needs a running app, so `catch_up` copies blocks
+//! between nodes the way a syncing node accepts them: in order, each extending the tip.
+
+use crate::blockchain_database::{AsyncBlockchainDatabase, Block, Transaction, TransactionInput, TransactionOutput};
+use crate::chain_params::REGTEST;
+use crate::errors::{AppError, AppResult};
+use crate::mempool_service::AsyncMempoolService;
+use crate::mining_service::{coinbase_transaction, solve_block, MAX_BLOCK_SIZE};
+use crate::relay_policy::TransactionSource;
+use crate::signature_cache;
+use crate::wallet_data::{KeyPair, WalletData};
+use crate::wallet_manager::WalletManager;
+use bip39::Mnemonic;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Target every block hash meets, so regtest blocks are found on the first nonce
+pub const REGTEST_TARGET: u64 = REGTEST.initial_target;
+
+/// Timestamp of the regtest genesis block
+pub const REGTEST_GENESIS_TIMESTAMP: u64 = REGTEST.genesis_timestamp;
+
+/// Seconds between regtest blocks
+pub const REGTEST_BLOCK_INTERVAL: u64 = 60;
+
+/// Fee paid by transactions built with `send`
+pub const REGTEST_FEE: u64 = 1_000;
+
+/// A wallet with one derived key, saved under the node's directory
+pub struct TestWallet {
+    pub name: String,
+    pub address: String,
+    pub key_pair: KeyPair,
+}
+
+impl TestWallet {
+    /// Sign every input of `transaction` with the wallet's key, as the wallet's send commands do
+    pub fn sign(&self, transaction: &mut Transaction) {
+        let private_key = self.key_pair.signing_key().expect("test wallet key is valid");
+        let message = signature_cache::signing_message(transaction).expect("test transaction serializes");
+        let script_sig = signature_cache::sign(&message, &private_key);
+        for input in &mut transaction.inputs {
+            input.script_sig = script_sig.clone();
+        }
+    }
+}
+
+/// One node's services on a private data directory
+pub struct TestNode {
+    dir: PathBuf,
+    pub blockchain_db: Arc<AsyncBlockchainDatabase>,
+    pub mempool: AsyncMempoolService,
+}
+
+impl TestNode {
+    /// Boot a node with only the regtest genesis block. `name` keeps the data directories of
+    /// tests running in parallel apart.
+    pub async fn start(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("bradcoin_node_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        // Opening an empty store writes the regtest genesis block
+        let blockchain_db = Arc::new(
+            AsyncBlockchainDatabase::open(dir.join("blockchain"), &REGTEST)
+                .await
+                .expect("failed to open test blockchain database"),
+        );
+        let mempool = AsyncMempoolService::new(Arc::clone(&blockchain_db));
+
+        Self { dir, blockchain_db, mempool }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub async fn height(&self) -> u64 {
+        self.blockchain_db.get_block_height().await.unwrap_or(0)
+    }
+
+    pub async fn tip(&self) -> Block {
+        let height = self.height().await;
+        self.blockchain_db
+            .get_block_by_height(height)
+            .await
+            .ok()
+            .flatten()
+            .expect("node has no tip block")
+    }
+
+    pub async fn balance(&self, address: &str) -> u64 {
+        self.blockchain_db.get_address_balance(address).await.unwrap_or(0)
+    }
+
+    /// Create a wallet whose seed is derived from its name, so the same name always yields
+    /// the same address. The wallet file is written to the node's directory.
+    pub fn create_wallet(&self, name: &str) -> TestWallet {
+        let entropy = Sha256::digest(name.as_bytes());
+        let seed_phrase = Mnemonic::from_entropy(&entropy[..16])
+            .expect("16 bytes is valid BIP39 entropy")
+            .to_string();
+        let (master_public_key, master_private_key, key_pair) =
+            WalletManager::derive_keys_from_seed(&seed_phrase, "", name).expect("failed to derive test wallet keys");
+
+        let mut wallet_data = WalletData::new(name, &master_public_key, false);
+        wallet_data.set_sensitive_data(&seed_phrase, master_private_key.expose_secret());
+        wallet_data.add_key_pair(key_pair.clone()).expect("failed to add test wallet key");
+        wallet_data
+            .save(&self.dir.join("wallets").join(name).join("wallet.dat"), None)
+            .expect("failed to save test wallet");
+
+        TestWallet {
+            name: name.to_string(),
+            address: key_pair.address.clone(),
+            key_pair,
+        }
+    }
+
+    /// Mine `count` blocks paying `address`, each including what the mempool offers for mining.
+    /// Included transactions leave the mempool.
+    pub async fn mine_blocks(&self, count: u64, address: &str) -> Vec<Block> {
+        let mut blocks = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let tip = self.tip().await;
+            let height = tip.height + 1;
+            let timestamp = REGTEST_GENESIS_TIMESTAMP + height * REGTEST_BLOCK_INTERVAL;
+
+            let mut transactions = vec![coinbase_transaction(height, address, timestamp)];
+            transactions.extend(self.mempool.get_transactions_for_mining(100, MAX_BLOCK_SIZE - 1000).await);
+
+            let block = solve_block(height, &tip.hash, transactions, timestamp, 1, REGTEST_TARGET, 0, 1)
+                .expect("regtest target accepts any hash");
+            self.blockchain_db.store_block(&block).await.expect("failed to store mined block");
+            for transaction in block.transactions.iter().skip(1) {
+                self.mempool.remove_transaction(&transaction.txid).await;
+            }
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    /// Build a transaction paying `amount` from the wallet's confirmed coins, with change back
+    /// to the wallet, and submit it to the mempool. Coins already spent by a mempool
+    /// transaction are skipped. Returns the txid.
+    pub async fn send(&self, from: &TestWallet, to: &str, amount: u64) -> AppResult<String> {
+        let pending: HashSet<(String, u32)> = self
+            .mempool
+            .get_all_transactions()
+            .await
+            .iter()
+            .flat_map(|tx| tx.inputs.iter().map(|input| (input.previous_txid.clone(), input.previous_output_index)))
+            .collect();
+        let mut utxos = self
+            .blockchain_db
+            .get_address_utxos(&from.address)
+            .await
+            .map_err(|e| AppError::Generic(format!("Failed to read UTXOs: {}", e)))?;
+        utxos.retain(|utxo| !pending.contains(&(utxo.txid.clone(), utxo.output_index)));
+        utxos.sort_by(|a, b| (a.block_height, &a.txid, a.output_index).cmp(&(b.block_height, &b.txid, b.output_index)));
+
+        let needed = amount + REGTEST_FEE;
+        let mut inputs = Vec::new();
+        let mut total = 0;
+        for utxo in utxos {
+            if total >= needed {
+                break;
+            }
+            total += utxo.value;
+            inputs.push(TransactionInput {
+                previous_txid: utxo.txid,
+                previous_output_index: utxo.output_index,
+                script_sig: String::new(),
+                sequence: u32::MAX,
+            });
+        }
+        if total < needed {
+            return Err(AppError::Generic(format!(
+                "Insufficient funds in {}: have {}, need {}",
+                from.name, total, needed
+            )));
+        }
+
+        let mut outputs = vec![output(to, amount)];
+        if total > needed {
+            outputs.push(output(&from.address, total - needed));
+        }
+
+        let mut transaction = Transaction {
+            txid: String::new(),
+            inputs,
+            outputs,
+            timestamp: REGTEST_GENESIS_TIMESTAMP + (self.height().await + 1) * REGTEST_BLOCK_INTERVAL,
+            fee: REGTEST_FEE,
+        };
+        from.sign(&mut transaction);
+        self.mempool.add_transaction(transaction, TransactionSource::Local).await
+    }
+
+    /// Accept the blocks `source` has above this node's tip, as a syncing node would.
+    /// Stops at the first block that does not extend the tip. Returns the new height.
+    pub async fn catch_up(&self, source: &TestNode) -> u64 {
+        let target = source.height().await;
+        for height in (self.height().await + 1)..=target {
+            let Ok(Some(block)) = source.blockchain_db.get_block_by_height(height).await else {
+                break;
+            };
+            if block.previous_hash != self.tip().await.hash {
+                break;
+            }
+            self.blockchain_db.store_block(&block).await.expect("failed to store synced block");
+            for transaction in &block.transactions {
+                self.mempool.remove_transaction(&transaction.txid).await;
+            }
+        }
+        self.height().await
+    }
+}
+
+impl Drop for TestNode {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// The regtest genesis block, identical for every node
+pub fn regtest_genesis() -> Block {
+    REGTEST.genesis_block()
+}
+
+fn output(address: &str, value: u64) -> TransactionOutput {
+    TransactionOutput {
+        value,
+        script_pubkey: format!("OP_DUP OP_HASH160 {} OP_EQUALVERIFY OP_CHECKSIG", address),
+        address: address.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mine_send_and_sync_end_to_end() {
+        let node = TestNode::start("e2e_a").await;
+        let alice = node.create_wallet("alice");
+        let bob = node.create_wallet("bob");
+        assert!(node.dir().join("wallets/alice/wallet.dat").exists());
+
+        let mined = node.mine_blocks(2, &alice.address).await;
+        assert_eq!(node.height().await, 2);
+        let reward = mined[0].transactions[0].outputs[0].value;
+        assert_eq!(node.balance(&alice.address).await, 2 * reward);
+
+        let txid = node.send(&alice, &bob.address, 10 * 100_000_000).await.unwrap();
+        assert!(node.mempool.get_transaction(&txid).await.is_some());
+
+        let block = node.mine_blocks(1, &bob.address).await.remove(0);
+        assert!(block.transactions.iter().any(|tx| tx.txid == txid));
+        assert!(node.mempool.get_all_transactions().await.is_empty());
+        assert_eq!(node.balance(&bob.address).await, reward + 10 * 100_000_000);
+        assert_eq!(node.balance(&alice.address).await, 2 * reward - 10 * 100_000_000 - REGTEST_FEE);
+
+        // A second node with the same genesis follows the first
+        let follower = TestNode::start("e2e_b").await;
+        assert_eq!(follower.tip().await.hash, regtest_genesis().hash);
+        assert_eq!(follower.catch_up(&node).await, 3);
+        assert_eq!(follower.tip().await.hash, node.tip().await.hash);
+        assert_eq!(follower.balance(&bob.address).await, node.balance(&bob.address).await);
+    }
+}
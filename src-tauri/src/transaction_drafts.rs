@@ -0,0 +1,101 @@
+//! Unsigned transaction drafts.
+//!
+//! Sending takes two steps: `preview_transaction` runs coin selection and keeps the result as a
+//! draft, so the confirmation screen shows the inputs, change and fee that will actually be used,
+//! and `commit_transaction` signs and broadcasts exactly that draft. Drafts live in memory only,
+//! are used at most once, and expire after `DRAFT_TTL_SECS` since the wallet's coins may have
+//! changed by then.
+
+use crate::blockchain_database::TransactionOutput;
+use crate::wallet_data::Utxo;
+use rand::Rng;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// How long a preview can be committed
+pub const DRAFT_TTL_SECS: i64 = 10 * 60;
+
+/// A payment with its coins chosen, ready to be signed
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionPreview {
+    /// Handle to pass to `commit_transaction`
+    pub id: String,
+    pub wallet_name: String,
+    /// Satoshis paid to the recipient
+    pub amount: u64,
+    pub inputs: Vec<Utxo>,
+    pub total_input: u64,
+    /// Recipient first, then the change output if there is change
+    pub outputs: Vec<TransactionOutput>,
+    pub change: u64,
+    pub fee: u64,
+    /// Estimated size in bytes
+    pub size: usize,
+    /// Fee divided by size, which can be above the requested rate when leftover dust went to the fee
+    pub effective_fee_rate: f64,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+impl TransactionPreview {
+    pub fn new_id() -> String {
+        hex::encode(rand::rng().random::<[u8; 16]>())
+    }
+}
+
+/// Previews waiting to be committed, by id
+#[derive(Debug, Default)]
+pub struct TransactionDrafts(Mutex<HashMap<String, TransactionPreview>>);
+
+impl TransactionDrafts {
+    /// Keep a preview until it is committed or expires; also puts back a preview whose commit failed
+    pub fn insert(&self, preview: TransactionPreview) {
+        let now = chrono::Utc::now().timestamp();
+        let mut drafts = self.0.lock().unwrap();
+        drafts.retain(|_, draft| draft.expires_at > now);
+        drafts.insert(preview.id.clone(), preview);
+    }
+
+    /// Take the preview `id` out, if it has not expired by `now`
+    pub fn take(&self, id: &str, now: i64) -> Option<TransactionPreview> {
+        self.0.lock().unwrap().remove(id).filter(|draft| draft.expires_at > now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preview(created_at: i64) -> TransactionPreview {
+        TransactionPreview {
+            id: TransactionPreview::new_id(),
+            wallet_name: "main".to_string(),
+            amount: 1_000,
+            inputs: Vec::new(),
+            total_input: 2_000,
+            outputs: Vec::new(),
+            change: 500,
+            fee: 500,
+            size: 250,
+            effective_fee_rate: 2.0,
+            created_at,
+            expires_at: created_at + DRAFT_TTL_SECS,
+        }
+    }
+
+    #[test]
+    fn test_drafts_are_used_once_and_expire() {
+        let drafts = TransactionDrafts::default();
+        let fresh = preview(0);
+        let id = fresh.id.clone();
+        drafts.insert(fresh);
+        assert!(drafts.take(&id, 10).is_some());
+        assert!(drafts.take(&id, 10).is_none());
+
+        let stale = preview(0);
+        let id = stale.id.clone();
+        drafts.insert(stale);
+        assert!(drafts.take(&id, DRAFT_TTL_SECS).is_none());
+    }
+}
@@ -0,0 +1,541 @@
+use crate::blockchain_sync::AsyncBlockchainSyncService;
+use crate::node_status::NodeStatus;
+use crate::i18n::{t, tf};
+use crate::mining_service::AsyncMiningService;
+use crate::power_monitor::PowerMonitor;
+use crate::wallet_manager::AsyncWalletManager;
+use crate::wallet_settings::WalletSettings;
+use crate::events::{self, EmitEvent};
+use log::{debug, error, info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, Wry};
+
+/// Id of the application tray icon
+pub const TRAY_ID: &str = "main-tray";
+
+/// Creates and removes the tray icon as the minimize_to_system_tray setting changes. Managed by
+/// Tauri from startup; the status keeps being tracked while there is no tray, so a tray created
+/// later shows it straight away.
+#[derive(Default)]
+pub struct TrayController {
+    menu: Mutex<Option<Arc<TrayMenu>>>,
+    /// Whether the tray exists; read on the main thread when the window is closed, so it must not
+    /// wait for the menu lock
+    enabled: AtomicBool,
+    status: Mutex<TrayStatus>,
+}
+
+/// Tray menu items whose text or enabled state follows application state
+struct TrayMenu {
+    wallet_status_item: MenuItem<Wry>,
+    network_status_item: MenuItem<Wry>,
+    sync_status_item: MenuItem<Wry>,
+    mining_item: MenuItem<Wry>,
+    close_wallet_item: MenuItem<Wry>,
+    /// Items with fixed text, with their message key, relabelled when the language changes
+    static_items: Vec<(MenuItem<Wry>, &'static str)>,
+}
+
+/// Last known state shown in the tray
+#[derive(Debug, Default, Clone)]
+struct TrayStatus {
+    wallet_name: Option<String>,
+    is_connected: bool,
+    peer_count: Option<u32>,
+    current_height: u64,
+    network_height: u64,
+    /// Whether the active wallet is mining
+    is_mining: bool,
+    /// Seconds the local clock is off from network time, when enough to warn about
+    clock_skew_secs: Option<i64>,
+}
+
+impl TrayController {
+    /// Whether the tray icon exists, in which case closing the window hides it to the tray
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    /// Create or remove the tray icon
+    pub fn set_enabled(&self, app_handle: &AppHandle, enabled: bool) -> tauri::Result<()> {
+        let mut menu = self.menu.lock().unwrap();
+        if menu.is_some() == enabled {
+            return Ok(());
+        }
+        if enabled {
+            info!("Creating system tray");
+            let created = Arc::new(TrayMenu::build(app_handle)?);
+            *menu = Some(created.clone());
+            self.enabled.store(true, Ordering::SeqCst);
+            drop(menu);
+            let status = self.status.lock().unwrap().clone();
+            created.refresh(app_handle, &status);
+        } else {
+            info!("Removing system tray");
+            self.enabled.store(false, Ordering::SeqCst);
+            *menu = None;
+            app_handle.remove_tray_by_id(TRAY_ID);
+        }
+        Ok(())
+    }
+
+    /// Apply a change to the tray status and refresh the menu if there is one
+    fn update(&self, app_handle: &AppHandle, change: impl FnOnce(&mut TrayStatus)) {
+        let status = {
+            let mut status = self.status.lock().unwrap();
+            change(&mut status);
+            status.clone()
+        };
+        match self.menu() {
+            Some(menu) => menu.refresh(app_handle, &status),
+            None => debug!("System tray not enabled, skipping tray update"),
+        }
+    }
+
+    fn menu(&self) -> Option<Arc<TrayMenu>> {
+        self.menu.lock().unwrap().clone()
+    }
+}
+
+impl TrayMenu {
+    /// Create the tray icon with its menu
+    fn build(app_handle: &AppHandle) -> tauri::Result<Self> {
+        let wallet_status_item = MenuItem::with_id(app_handle, "wallet_status", t("tray.no_wallet"), false, None::<&str>)?;
+        let network_status_item = MenuItem::with_id(app_handle, "network_status", t("tray.network_disconnected"), false, None::<&str>)?;
+        let sync_status_item = MenuItem::with_id(app_handle, "sync_status", t("tray.sync_waiting"), true, None::<&str>)?;
+        let show_item = MenuItem::with_id(app_handle, "show", t("tray.show_window"), true, None::<&str>)?;
+        let hide_item = MenuItem::with_id(app_handle, "hide", t("tray.hide_window"), true, None::<&str>)?;
+        let open_wallet_item = MenuItem::with_id(app_handle, "open_wallet", t("tray.open_wallet"), true, None::<&str>)?;
+        let create_wallet_item = MenuItem::with_id(app_handle, "create_wallet", t("tray.create_wallet"), true, None::<&str>)?;
+        let close_wallet_item = MenuItem::with_id(app_handle, "close_wallet", t("tray.close_wallet"), false, None::<&str>)?;
+        let mining_item = MenuItem::with_id(app_handle, "toggle_mining", t("tray.start_mining"), false, None::<&str>)?;
+        let quit_item = MenuItem::with_id(app_handle, "quit", t("tray.quit"), true, None::<&str>)?;
+
+        let menu = Menu::with_items(app_handle, &[
+            &wallet_status_item,
+            &network_status_item,
+            &sync_status_item,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &show_item,
+            &hide_item,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &open_wallet_item,
+            &create_wallet_item,
+            &close_wallet_item,
+            &mining_item,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &quit_item,
+        ])?;
+
+        let icon = app_handle.default_window_icon().cloned().unwrap_or_else(|| {
+            // A transparent 32x32 icon rather than no tray at all
+            let rgba_data: &[u8] = &[0; 32 * 32 * 4];
+            tauri::image::Image::new(rgba_data, 32, 32)
+        });
+        TrayIconBuilder::with_id(TRAY_ID)
+            .tooltip(t("tray.title"))
+            .icon(icon)
+            .menu(&menu)
+            .on_tray_icon_event(handle_icon_event)
+            .on_menu_event(handle_menu_event)
+            .build(app_handle)?;
+
+        Ok(TrayMenu {
+            wallet_status_item,
+            network_status_item,
+            sync_status_item,
+            mining_item,
+            close_wallet_item,
+            static_items: vec![
+                (show_item, "tray.show_window"),
+                (hide_item, "tray.hide_window"),
+                (open_wallet_item, "tray.open_wallet"),
+                (create_wallet_item, "tray.create_wallet"),
+                (quit_item, "tray.quit"),
+            ],
+        })
+    }
+
+    /// Push the status into the menu items and tooltip
+    fn refresh(&self, app_handle: &AppHandle, status: &TrayStatus) {
+        let wallet_text = match &status.wallet_name {
+            Some(name) => tf("tray.wallet", &[("name", name)]),
+            None => t("tray.no_wallet"),
+        };
+        let mut network_text = network_text(status);
+        if status.clock_skew_secs.is_some() {
+            network_text = format!("\u{26a0} {}", network_text);
+        }
+
+        if let Err(e) = self.wallet_status_item.set_text(&wallet_text) {
+            warn!("Failed to update tray wallet status: {}", e);
+        }
+        if let Err(e) = self.network_status_item.set_text(&network_text) {
+            warn!("Failed to update tray network status: {}", e);
+        }
+        if let Err(e) = self.sync_status_item.set_text(sync_text(status)) {
+            warn!("Failed to update tray sync status: {}", e);
+        }
+        let mining_text = if status.is_mining { t("tray.stop_mining") } else { t("tray.start_mining") };
+        if let Err(e) = self.mining_item.set_text(mining_text) {
+            warn!("Failed to update tray mining item: {}", e);
+        }
+        if let Err(e) = self.mining_item.set_enabled(status.wallet_name.is_some()) {
+            warn!("Failed to update tray mining item: {}", e);
+        }
+        if let Err(e) = self.close_wallet_item.set_enabled(status.wallet_name.is_some()) {
+            warn!("Failed to update tray close wallet item: {}", e);
+        }
+
+        let mut tooltip = format!("{}\n{}\n{}", t("tray.title"), wallet_text, network_text);
+        if let Some(progress) = sync_progress_text(status) {
+            tooltip.push('\n');
+            tooltip.push_str(&progress);
+        }
+        if status.is_mining {
+            tooltip.push('\n');
+            tooltip.push_str(&t("tray.mining"));
+        }
+        if let Some(skew) = status.clock_skew_secs {
+            tooltip.push('\n');
+            tooltip.push_str(&tf("tray.clock_skew", &[("minutes", &(skew.abs() / 60))]));
+        }
+        if let Some(tray) = app_handle.tray_by_id(TRAY_ID) {
+            if let Err(e) = tray.set_tooltip(Some(&tooltip)) {
+                warn!("Failed to update tray tooltip: {}", e);
+            }
+        }
+    }
+}
+
+fn show_main_window(app_handle: &AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn handle_icon_event(tray: &TrayIcon, event: TrayIconEvent) {
+    match event {
+        TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } => {
+            debug!("Tray icon left clicked - showing window");
+            show_main_window(tray.app_handle());
+        }
+        TrayIconEvent::DoubleClick { button: MouseButton::Left, .. } => {
+            debug!("Tray icon double clicked - showing window");
+            show_main_window(tray.app_handle());
+        }
+        _ => {}
+    }
+}
+
+fn handle_menu_event(app_handle: &AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        "quit" => {
+            info!("Quit selected from tray menu");
+            // Set shutdown flag and exit the application
+            crate::SHUTDOWN_IN_PROGRESS.store(true, Ordering::SeqCst);
+
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                match crate::commands::shutdown_application(app_handle).await {
+                    Ok(_) => debug!("Shutdown completed successfully from tray menu"),
+                    Err(e) => error!("Failed to shutdown from tray menu: {}", e),
+                }
+            });
+        }
+        "show" => {
+            debug!("Show selected from tray menu");
+            show_main_window(app_handle);
+        }
+        "hide" => {
+            debug!("Hide selected from tray menu");
+            if let Some(window) = app_handle.get_webview_window("main") {
+                let _ = window.hide();
+            }
+        }
+        "open_wallet" => {
+            debug!("Open wallet selected from tray menu");
+            show_main_window(app_handle);
+            // Let the frontend open the wallet dialog
+            if let Some(window) = app_handle.get_webview_window("main") {
+                let _ = window.emit_event(events::TRAY_OPEN_WALLET, &());
+            }
+        }
+        "create_wallet" => {
+            debug!("Create wallet selected from tray menu");
+            show_main_window(app_handle);
+            // Let the frontend open the create wallet dialog
+            if let Some(window) = app_handle.get_webview_window("main") {
+                let _ = window.emit_event(events::TRAY_CREATE_WALLET, &());
+            }
+        }
+        "close_wallet" => {
+            debug!("Close wallet selected from tray menu");
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                let wallet_manager = app_handle.state::<AsyncWalletManager>();
+                match crate::commands::close_wallet(None, wallet_manager, app_handle.clone()).await {
+                    Ok(_) => {
+                        debug!("Wallet closed successfully from tray menu");
+                        // close_wallet already updated the tray; let the frontend know too
+                        if let Some(window) = app_handle.get_webview_window("main") {
+                            let _ = window.emit_event(events::WALLET_CLOSED, &());
+                        }
+                    }
+                    Err(e) => error!("Failed to close wallet from tray menu: {}", e),
+                }
+            });
+        }
+        "toggle_mining" => {
+            debug!("Toggle mining selected from tray menu");
+            toggle_mining(app_handle.clone());
+        }
+        "sync_status" => {
+            debug!("Sync status selected from tray menu");
+            request_sync(app_handle.clone());
+        }
+        _ => {}
+    }
+}
+
+fn network_text(status: &TrayStatus) -> String {
+    if !status.is_connected {
+        return t("tray.network_disconnected");
+    }
+    match status.peer_count {
+        Some(count) => tf("tray.network_connected_peers", &[("peers", &count)]),
+        None => t("tray.network_connected"),
+    }
+}
+
+/// "Sync: 84% (12,345/14,700)" while the local chain is behind the network
+fn sync_progress_text(status: &TrayStatus) -> Option<String> {
+    if status.network_height == 0 || status.current_height >= status.network_height {
+        return None;
+    }
+    let percent = (status.current_height as f64 / status.network_height as f64 * 100.0).floor();
+    Some(tf(
+        "tray.sync_progress",
+        &[
+            ("percent", &percent),
+            ("current", &format_count(status.current_height)),
+            ("target", &format_count(status.network_height)),
+        ],
+    ))
+}
+
+/// Text of the sync menu line
+fn sync_text(status: &TrayStatus) -> String {
+    if let Some(progress) = sync_progress_text(status) {
+        return progress;
+    }
+    if status.is_connected && status.network_height > 0 {
+        tf("tray.sync_up_to_date", &[("height", &format_count(status.current_height))])
+    } else {
+        t("tray.sync_waiting")
+    }
+}
+
+/// Format a number with thousands separators
+fn format_count(value: u64) -> String {
+    let digits = value.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(c);
+    }
+    formatted
+}
+
+/// Create or remove the tray icon to match the minimize_to_system_tray setting
+pub fn apply_setting(app_handle: &AppHandle, enabled: bool) {
+    let Some(controller) = app_handle.try_state::<TrayController>() else {
+        return;
+    };
+    if let Err(e) = controller.set_enabled(app_handle, enabled) {
+        error!("Failed to {} the system tray: {}", if enabled { "create" } else { "remove" }, e);
+    }
+}
+
+/// Whether closing the main window should hide it to the tray rather than quit
+pub fn close_hides_window(app_handle: &AppHandle) -> bool {
+    app_handle.try_state::<TrayController>().is_some_and(|controller| controller.is_enabled())
+}
+
+/// Apply a change to the tray status and refresh the menu
+fn update(app_handle: &AppHandle, change: impl FnOnce(&mut TrayStatus)) {
+    if let Some(controller) = app_handle.try_state::<TrayController>() {
+        controller.update(app_handle, change);
+    }
+}
+
+/// Relabel every tray item after the display language changed
+pub fn refresh_language(app_handle: &AppHandle) {
+    let Some(controller) = app_handle.try_state::<TrayController>() else {
+        return;
+    };
+    let Some(menu) = controller.menu() else {
+        return;
+    };
+    for (item, key) in &menu.static_items {
+        if let Err(e) = item.set_text(t(key)) {
+            warn!("Failed to relabel tray item {}: {}", key, e);
+        }
+    }
+    let status = controller.status.lock().unwrap().clone();
+    menu.refresh(app_handle, &status);
+}
+
+/// Show the active wallet in the tray, or None when no wallet is open
+pub fn set_wallet_status(app_handle: &AppHandle, wallet_name: Option<String>) {
+    update(app_handle, |status| status.wallet_name = wallet_name.clone());
+
+    // The mining toggle follows the active wallet
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let is_mining = match (&wallet_name, crate::services::get::<AsyncMiningService>(&app_handle)) {
+            (Some(name), Some(mining_service)) => mining_service
+                .get_mining_status(name)
+                .await
+                .map(|status| status.is_mining)
+                .unwrap_or(false),
+            _ => false,
+        };
+        update(&app_handle, |status| {
+            if status.wallet_name == wallet_name {
+                status.is_mining = is_mining;
+            }
+        });
+    });
+}
+
+/// Reflect a wallet's mining state in the tray if it is the active wallet
+pub fn set_mining_status(app_handle: &AppHandle, wallet_id: &str, is_mining: bool) {
+    update(app_handle, |status| {
+        if status.wallet_name.as_deref() == Some(wallet_id) {
+            status.is_mining = is_mining;
+        }
+    });
+}
+
+/// Flag a local clock that is off from network time, or clear the flag with None
+pub fn set_clock_skew(app_handle: &AppHandle, skew_secs: Option<i64>) {
+    update(app_handle, |status| status.clock_skew_secs = skew_secs);
+}
+
+/// Show connection state and peer count in the tray
+pub fn set_connection_status(app_handle: &AppHandle, is_connected: bool, peer_count: Option<u32>) {
+    update(app_handle, |status| {
+        status.is_connected = is_connected;
+        status.peer_count = peer_count;
+    });
+}
+
+/// Update the tray from a node status snapshot
+pub fn set_node_status(app_handle: &AppHandle, node_status: &NodeStatus) {
+    update(app_handle, |status| {
+        status.is_connected = node_status.is_connected;
+        status.peer_count = Some(node_status.peer_count);
+        status.current_height = node_status.chain_height;
+        // The network height is unknown until the network service runs; keep the last known one
+        if node_status.network_height > 0 {
+            status.network_height = node_status.network_height;
+        }
+    });
+}
+
+/// Start or stop mining for the active wallet from the tray menu
+pub fn toggle_mining(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = toggle_mining_for_active_wallet(&app_handle).await {
+            error!("Failed to toggle mining from tray menu: {}", e);
+        }
+    });
+}
+
+async fn toggle_mining_for_active_wallet(app_handle: &AppHandle) -> Result<(), String> {
+    let mining_service = crate::services::get::<AsyncMiningService>(app_handle)
+        .ok_or("Mining service is not ready yet")?;
+
+    // Same address choice as get_mining_configuration: the configured address or the first one
+    let (wallet_name, default_address) = {
+        let wallet_manager = app_handle.state::<AsyncWalletManager>();
+        let manager = wallet_manager.read_manager().await;
+        let wallet = manager.get_current_wallet().ok_or("No wallet is open")?;
+        let address = WalletSettings::load(&wallet.path)
+            .ok()
+            .and_then(|settings| settings.mining_address)
+            .or_else(|| wallet.data.receiving_addresses().next().map(|a| a.address.clone()));
+        (wallet.name.clone(), address)
+    };
+
+    let current = mining_service.get_mining_status(&wallet_name).await;
+    if current.as_ref().is_some_and(|status| status.is_mining) {
+        info!("Stopping mining for {} from tray menu", wallet_name);
+        mining_service.stop_mining(&wallet_name).await.map_err(|e| e.to_string())?;
+        if let Some(power_monitor) = app_handle.try_state::<PowerMonitor>() {
+            power_monitor.forget_miner(&wallet_name).await;
+        }
+    } else {
+        if let Some(power_monitor) = app_handle.try_state::<PowerMonitor>() {
+            if let Some(reason) = power_monitor.mining_blocked(app_handle).await {
+                return Err(reason.describe().to_string());
+            }
+        }
+        let mining_address = current
+            .map(|status| status.mining_address)
+            .or(default_address)
+            .ok_or("Wallet has no address to mine to")?;
+        info!("Starting mining for {} from tray menu", wallet_name);
+        mining_service
+            .start_mining(wallet_name, mining_address)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Ask the sync service to check peers for new blocks and refresh the sync line
+pub fn request_sync(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let Some(sync_service) = crate::services::get::<AsyncBlockchainSyncService>(&app_handle) else {
+            warn!("Blockchain sync service is not ready yet");
+            return;
+        };
+        if !sync_service.is_syncing().await {
+            if let Err(e) = sync_service.trigger_sync(&app_handle).await {
+                error!("Failed to trigger sync from tray menu: {}", e);
+            }
+        }
+        let status = crate::node_status::collect(&app_handle).await;
+        set_node_status(&app_handle, &status);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_progress_text() {
+        let status = TrayStatus {
+            current_height: 12_345,
+            network_height: 14_700,
+            ..TrayStatus::default()
+        };
+        assert_eq!(sync_progress_text(&status).unwrap(), "Sync: 83% (12,345/14,700)");
+
+        let synced = TrayStatus {
+            current_height: 14_700,
+            network_height: 14_700,
+            ..TrayStatus::default()
+        };
+        assert!(sync_progress_text(&synced).is_none());
+    }
+}
@@ -0,0 +1,252 @@
+//! Application Update Service
+//! Checks the release channel for updates on a schedule, downloads them in the background
+//! and installs only once the user confirms
+
+use crate::config::ConfigManager;
+use crate::errors::*;
+use crate::events::{self, EmitEvent};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+use tokio::sync::Mutex;
+
+/// Release manifest for the stable channel, matching the endpoint in tauri.conf.json
+const STABLE_ENDPOINT: &str = "https://github.com/bacathey/b-rad-coin/releases/download/latest/latest.json";
+
+/// Release manifest for the beta channel
+const BETA_ENDPOINT: &str = "https://github.com/bacathey/b-rad-coin/releases/download/beta/latest.json";
+
+/// Delay before the first scheduled check, so startup is not slowed down
+const INITIAL_CHECK_DELAY: Duration = Duration::from_secs(60);
+
+/// Time between scheduled checks
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Release channel to follow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    fn endpoint(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => STABLE_ENDPOINT,
+            UpdateChannel::Beta => BETA_ENDPOINT,
+        }
+    }
+}
+
+impl std::str::FromStr for UpdateChannel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "stable" => Ok(UpdateChannel::Stable),
+            "beta" => Ok(UpdateChannel::Beta),
+            _ => Err(format!("Unknown update channel '{}', expected 'stable' or 'beta'", s)),
+        }
+    }
+}
+
+/// Details of an available update shown to the user before installing
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub channel: UpdateChannel,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+    /// Whether the package has been downloaded and is ready to install
+    pub downloaded: bool,
+}
+
+/// Download progress payload for the "update-download-progress" event
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateDownloadProgress {
+    pub version: String,
+    pub downloaded: u64,
+    pub total: Option<u64>,
+}
+
+struct PendingUpdate {
+    update: Update,
+    channel: UpdateChannel,
+    package: Option<Vec<u8>>,
+}
+
+impl PendingUpdate {
+    fn info(&self) -> UpdateInfo {
+        UpdateInfo {
+            version: self.update.version.clone(),
+            current_version: self.update.current_version.clone(),
+            channel: self.channel,
+            notes: self.update.body.clone(),
+            date: self.update.date.map(|date| date.to_string()),
+            downloaded: self.package.is_some(),
+        }
+    }
+}
+
+/// Update state shared through Tauri state
+#[derive(Clone)]
+pub struct AsyncUpdateService {
+    pending: Arc<Mutex<Option<PendingUpdate>>>,
+    running: Arc<AtomicBool>,
+}
+
+impl AsyncUpdateService {
+    pub fn new() -> Self {
+        Self {
+            pending: Arc::new(Mutex::new(None)),
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Start scheduled checks. Settings are re-read before each check.
+    pub fn start(&self, app_handle: AppHandle, config_manager: Arc<ConfigManager>) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            debug!("Update service already running");
+            return;
+        }
+
+        let service = self.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(INITIAL_CHECK_DELAY).await;
+            loop {
+                let settings = config_manager.get_config().app_settings;
+                if settings.auto_update_check {
+                    let channel = settings.update_channel.parse().unwrap_or(UpdateChannel::Stable);
+                    match service.check(&app_handle, channel).await {
+                        Ok(Some(info)) => {
+                            // Fetch the package in the background; installing still waits for the user
+                            if let Err(e) = service.download(&app_handle).await {
+                                warn!("Failed to download update {}: {}", info.version, e);
+                            }
+                        }
+                        Ok(None) => debug!("No update available on the {:?} channel", channel),
+                        Err(e) => warn!("Scheduled update check failed: {}", e),
+                    }
+                }
+                tokio::time::sleep(CHECK_INTERVAL).await;
+            }
+        });
+    }
+
+    /// Check the channel for a newer version and remember it as the pending update
+    pub async fn check(&self, app_handle: &AppHandle, channel: UpdateChannel) -> AppResult<Option<UpdateInfo>> {
+        info!("Checking for updates on the {:?} channel", channel);
+
+        let endpoint = Url::parse(channel.endpoint())
+            .map_err(|e| AppError::Generic(format!("Invalid update endpoint: {}", e)))?;
+        let update = app_handle
+            .updater_builder()
+            .endpoints(vec![endpoint])
+            .and_then(|builder| builder.build())
+            .map_err(|e| AppError::Generic(format!("Failed to create updater: {}", e)))?
+            .check()
+            .await
+            .map_err(|e| AppError::Generic(format!("Update check failed: {}", e)))?;
+
+        let mut pending = self.pending.lock().await;
+        let Some(update) = update else {
+            *pending = None;
+            return Ok(None);
+        };
+
+        // Keep an already downloaded package if the same version is offered again
+        if let Some(existing) = pending.as_ref() {
+            if existing.update.version == update.version && existing.channel == channel {
+                return Ok(Some(existing.info()));
+            }
+        }
+
+        info!("Update {} available (current {})", update.version, update.current_version);
+        let next = PendingUpdate {
+            update,
+            channel,
+            package: None,
+        };
+        let info = next.info();
+        *pending = Some(next);
+
+        if let Err(e) = app_handle.emit_event(events::UPDATE_AVAILABLE, &info) {
+            warn!("Failed to emit update-available event: {}", e);
+        }
+        Ok(Some(info))
+    }
+
+    /// Download the pending update, emitting "update-download-progress" as bytes arrive
+    pub async fn download(&self, app_handle: &AppHandle) -> AppResult<UpdateInfo> {
+        let mut pending = self.pending.lock().await;
+        let pending = pending
+            .as_mut()
+            .ok_or_else(|| AppError::Generic("No update is pending".to_string()))?;
+        if pending.package.is_some() {
+            return Ok(pending.info());
+        }
+
+        let version = pending.update.version.clone();
+        let progress_handle = app_handle.clone();
+        let mut downloaded: u64 = 0;
+        let package = pending
+            .update
+            .download(
+                |chunk_length, content_length| {
+                    downloaded += chunk_length as u64;
+                    let progress = UpdateDownloadProgress {
+                        version: version.clone(),
+                        downloaded,
+                        total: content_length,
+                    };
+                    if let Err(e) = progress_handle.emit_event(events::UPDATE_DOWNLOAD_PROGRESS, &progress) {
+                        debug!("Failed to emit update progress: {}", e);
+                    }
+                },
+                || debug!("Update download finished"),
+            )
+            .await
+            .map_err(|e| AppError::Generic(format!("Update download failed: {}", e)))?;
+
+        info!("Update {} downloaded ({} bytes)", pending.update.version, package.len());
+        pending.package = Some(package);
+        let info = pending.info();
+        if let Err(e) = app_handle.emit_event(events::UPDATE_READY, &info) {
+            warn!("Failed to emit update-ready event: {}", e);
+        }
+        Ok(info)
+    }
+
+    /// Install the pending update. Only called after the user confirmed.
+    pub async fn install(&self, app_handle: &AppHandle) -> AppResult<()> {
+        self.download(app_handle).await?;
+
+        let mut pending = self.pending.lock().await;
+        let Some(PendingUpdate { update, package: Some(package), .. }) = pending.take() else {
+            return Err(AppError::Generic("No downloaded update to install".to_string()));
+        };
+
+        info!("Installing update {}", update.version);
+        update.install(package).map_err(|e| {
+            error!("Failed to install update {}: {}", update.version, e);
+            AppError::Generic(format!("Update install failed: {}", e))
+        })
+    }
+
+    /// The update waiting for confirmation, if any
+    pub async fn pending_update(&self) -> Option<UpdateInfo> {
+        self.pending.lock().await.as_ref().map(PendingUpdate::info)
+    }
+}
+
+impl Default for AsyncUpdateService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,143 @@
+//! Validation of user-supplied addresses and amounts, shared by the send commands and
+//! payment links so both accept and reject exactly the same input.
+//!
+//! Amounts are decimal coin strings ("1.5") converted to satoshis with integer arithmetic only;
+//! floats cannot represent most decimal amounts and would round away satoshis.
+
+use crate::chain_params::{ChainNetwork, ChainParams};
+use crate::consensus::{COIN, MAX_SUPPLY};
+use crate::errors::AppErrorCode;
+use bitcoin::Address;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Decimal places a coin amount may have
+pub const MAX_AMOUNT_DECIMALS: usize = 8;
+
+/// Error type for address and amount validation
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    #[error("No address given")]
+    MissingAddress,
+
+    #[error("Invalid address '{0}'")]
+    InvalidAddress(String),
+
+    #[error("Address '{address}' is not a {expected} address")]
+    WrongNetwork { address: String, expected: String },
+
+    #[error("Invalid amount '{0}'")]
+    InvalidAmount(String),
+
+    #[error("Amount must be greater than zero")]
+    ZeroAmount,
+
+    #[error("Amount {0} satoshis exceeds the total coin supply")]
+    AmountTooLarge(u64),
+}
+
+impl ValidationError {
+    /// Code the frontend can match on
+    pub fn code(&self) -> AppErrorCode {
+        match self {
+            ValidationError::MissingAddress => AppErrorCode::MissingAddress,
+            ValidationError::InvalidAddress(_) => AppErrorCode::InvalidAddress,
+            ValidationError::WrongNetwork { .. } => AppErrorCode::WrongNetwork,
+            ValidationError::InvalidAmount(_) => AppErrorCode::InvalidAmount,
+            ValidationError::ZeroAmount => AppErrorCode::ZeroAmount,
+            ValidationError::AmountTooLarge(_) => AppErrorCode::AmountTooLarge,
+        }
+    }
+}
+
+/// Check that `address` parses and belongs to `network`. Returns the trimmed address.
+pub fn validate_address(address: &str, network: ChainNetwork) -> Result<String, ValidationError> {
+    let address = address.trim();
+    if address.is_empty() {
+        return Err(ValidationError::MissingAddress);
+    }
+
+    let params = ChainParams::for_network(network);
+    let parsed = Address::from_str(address).map_err(|_| ValidationError::InvalidAddress(address.to_string()))?;
+    if !parsed.is_valid_for_network(params.address_network) {
+        return Err(ValidationError::WrongNetwork {
+            address: address.to_string(),
+            expected: params.name.to_string(),
+        });
+    }
+    Ok(address.to_string())
+}
+
+/// Parse a decimal coin amount ("1.5") into satoshis
+pub fn parse_amount(amount: &str) -> Result<u64, ValidationError> {
+    let invalid = || ValidationError::InvalidAmount(amount.to_string());
+
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty())
+        || !is_digits(whole)
+        || !is_digits(fraction)
+        || fraction.len() > MAX_AMOUNT_DECIMALS
+    {
+        return Err(invalid());
+    }
+
+    let whole: u64 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| invalid())? };
+    let fraction: u64 = format!("{:0<width$}", fraction, width = MAX_AMOUNT_DECIMALS)
+        .parse()
+        .map_err(|_| invalid())?;
+
+    let satoshis = whole
+        .checked_mul(COIN)
+        .and_then(|sats| sats.checked_add(fraction))
+        .ok_or_else(invalid)?;
+    validate_amount(satoshis)
+}
+
+/// Check an amount already in satoshis: more than zero and no more than can ever exist
+pub fn validate_amount(satoshis: u64) -> Result<u64, ValidationError> {
+    if satoshis == 0 {
+        return Err(ValidationError::ZeroAmount);
+    }
+    if satoshis > MAX_SUPPLY {
+        return Err(ValidationError::AmountTooLarge(satoshis));
+    }
+    Ok(satoshis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_amount_is_exact() {
+        assert_eq!(parse_amount("1.5"), Ok(150_000_000));
+        assert_eq!(parse_amount(".00000001"), Ok(1));
+        assert_eq!(parse_amount("0.1"), Ok(10_000_000));
+        assert_eq!(parse_amount("20999999.9769"), Ok(MAX_SUPPLY));
+
+        for amount in ["", ".", "abc", "-1", "1.123456789", "1e5", "1.2.3", " 1"] {
+            assert_eq!(parse_amount(amount), Err(ValidationError::InvalidAmount(amount.to_string())));
+        }
+        assert_eq!(parse_amount("0.0"), Err(ValidationError::ZeroAmount));
+        assert!(matches!(parse_amount("21000000"), Err(ValidationError::AmountTooLarge(_))));
+    }
+
+    #[test]
+    fn test_validate_address_checks_network() {
+        let mainnet = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+        let testnet = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+        assert_eq!(validate_address(&format!(" {} ", mainnet), ChainNetwork::Mainnet), Ok(mainnet.to_string()));
+        assert_eq!(validate_address(testnet, ChainNetwork::Testnet), Ok(testnet.to_string()));
+        assert_eq!(
+            validate_address(testnet, ChainNetwork::Mainnet).map_err(|e| e.code()),
+            Err(AppErrorCode::WrongNetwork)
+        );
+        assert_eq!(
+            validate_address("notanaddress", ChainNetwork::Mainnet),
+            Err(ValidationError::InvalidAddress("notanaddress".to_string()))
+        );
+        assert_eq!(validate_address("  ", ChainNetwork::Mainnet), Err(ValidationError::MissingAddress));
+    }
+}
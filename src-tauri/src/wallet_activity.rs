@@ -0,0 +1,154 @@
+//! Per-wallet activity feed.
+//!
+//! Notable things that happen to a wallet, on chain or in the app, are appended to a log next to
+//! wallet.dat so the UI can show what happened and when. Recording is best effort: a log that
+//! cannot be written is reported in the application log but never fails the operation it
+//! describes. The log keeps the newest `MAX_ACTIVITY_ENTRIES` entries.
+
+use crate::atomic_file;
+use crate::wallet_data::WalletDataError;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+
+/// File name of the activity log, stored next to wallet.dat.
+/// Kept outside wallet.dat so it can be written without the wallet password.
+pub const WALLET_ACTIVITY_FILE: &str = "activity.json";
+
+/// Entries kept per wallet; the oldest are dropped first
+pub const MAX_ACTIVITY_ENTRIES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    WalletOpened,
+    AddressCreated,
+    TransactionSent,
+    TransactionReceived,
+    /// The seed phrase backup was verified
+    BackupVerified,
+    PasswordChanged,
+}
+
+/// One thing that happened to a wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityEntry {
+    pub kind: ActivityKind,
+    pub timestamp: i64,
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub txid: Option<String>,
+    /// Amount in satoshis
+    #[serde(default)]
+    pub amount: Option<u64>,
+}
+
+impl ActivityEntry {
+    /// An entry of `kind` happening now, without details
+    pub fn now(kind: ActivityKind) -> Self {
+        Self {
+            kind,
+            timestamp: chrono::Utc::now().timestamp(),
+            address: None,
+            txid: None,
+            amount: None,
+        }
+    }
+}
+
+/// Activity log of one wallet
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WalletActivityLog {
+    #[serde(default)]
+    entries: VecDeque<ActivityEntry>,
+}
+
+impl WalletActivityLog {
+    /// Load the log from a wallet directory, starting empty if there is none yet
+    pub fn load(wallet_dir: &Path) -> Result<Self, WalletDataError> {
+        let path = wallet_dir.join(WALLET_ACTIVITY_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Save the log into a wallet directory
+    pub fn save(&self, wallet_dir: &Path) -> Result<(), WalletDataError> {
+        let path = wallet_dir.join(WALLET_ACTIVITY_FILE);
+        atomic_file::write_atomic(&path, serde_json::to_string_pretty(self)?.as_bytes())?;
+        debug!("Wallet activity saved to {}", path.display());
+        Ok(())
+    }
+
+    /// Append an entry. Coins received from a transaction the wallet sent itself are change and
+    /// are not recorded; returns whether the entry was added.
+    pub fn push(&mut self, entry: ActivityEntry) -> bool {
+        if entry.kind == ActivityKind::TransactionReceived {
+            let own = self.entries.iter().any(|e| e.kind == ActivityKind::TransactionSent && e.txid == entry.txid);
+            if own {
+                return false;
+            }
+        }
+        self.entries.push_back(entry);
+        while self.entries.len() > MAX_ACTIVITY_ENTRIES {
+            self.entries.pop_front();
+        }
+        true
+    }
+
+    /// Satoshis sent from the wallet since `since`
+    pub fn sent_since(&self, since: i64) -> u64 {
+        self.entries
+            .iter()
+            .filter(|entry| entry.kind == ActivityKind::TransactionSent && entry.timestamp >= since)
+            .filter_map(|entry| entry.amount)
+            .sum()
+    }
+
+    /// The newest `limit` entries, newest first
+    pub fn recent(&self, limit: usize) -> Vec<ActivityEntry> {
+        self.entries.iter().rev().take(limit).cloned().collect()
+    }
+}
+
+/// Append an entry to the activity log in `wallet_dir`, logging any failure instead of returning it
+pub fn record(wallet_dir: &Path, entry: ActivityEntry) {
+    let result = WalletActivityLog::load(wallet_dir).and_then(|mut log| {
+        if log.push(entry) {
+            log.save(wallet_dir)?;
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        warn!("Failed to record wallet activity in {}: {}", wallet_dir.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_is_not_recorded_and_log_is_bounded() {
+        let mut log = WalletActivityLog::default();
+        let sent = ActivityEntry { txid: Some("tx1".to_string()), amount: Some(500), ..ActivityEntry::now(ActivityKind::TransactionSent) };
+        assert!(log.push(sent));
+
+        let change = ActivityEntry { txid: Some("tx1".to_string()), amount: Some(200), ..ActivityEntry::now(ActivityKind::TransactionReceived) };
+        assert!(!log.push(change));
+        let payment = ActivityEntry { txid: Some("tx2".to_string()), ..ActivityEntry::now(ActivityKind::TransactionReceived) };
+        assert!(log.push(payment));
+
+        for _ in 0..MAX_ACTIVITY_ENTRIES {
+            log.push(ActivityEntry::now(ActivityKind::AddressCreated));
+        }
+        assert_eq!(log.recent(usize::MAX).len(), MAX_ACTIVITY_ENTRIES);
+        assert!(log.recent(3).iter().all(|entry| entry.kind == ActivityKind::AddressCreated));
+    }
+}
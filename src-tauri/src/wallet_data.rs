@@ -1,7 +1,12 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use log::{error, info}; // Removed debug
+use crate::chain_params::ChainParams;
+use crate::secret::SecretString;
+use crate::storage_media::ReadOnlyReason;
+use crate::wallet_keys::WalletKeys;
+use zeroize::{Zeroize, Zeroizing};
 use ring::pbkdf2;
 use ring::aead::{self, Aad, BoundKey, Nonce, NonceSequence, UnboundKey};
 use ring::rand::{SecureRandom, SystemRandom};
@@ -27,6 +32,18 @@ pub enum WalletDataError {
     
     #[error("Invalid password")]
     InvalidPassword,
+    
+    #[error("Wallet file is corrupted (checksum mismatch)")]
+    ChecksumMismatch,
+    
+    #[error("Wallet file format version {found} is newer than this build supports ({supported})")]
+    UnsupportedVersion { found: u16, supported: u16 },
+    
+    #[error("Invalid wallet file: {0}")]
+    InvalidFormat(String),
+    
+    #[error("Wallet is open read-only because {}; changes cannot be saved", .0.describe())]
+    ReadOnly(ReadOnlyReason),
 }
 
 /// A transaction output that hasn't been spent
@@ -112,6 +129,9 @@ pub enum KeyType {
     Taproot,
 }
 
+/// Derivation path marker used for keys imported from WIF rather than derived from the seed
+pub const IMPORTED_KEY_DERIVATION_PATH: &str = "imported";
+
 /// Address with metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddressInfo {
@@ -121,15 +141,34 @@ pub struct AddressInfo {
     pub key_type: KeyType,
     /// Path in BIP44 (e.g. m/44'/0'/0'/0/0)
     pub derivation_path: String,
+    /// Fingerprint of the master key the address derives from; None for imported keys
+    #[serde(default)]
+    pub master_fingerprint: Option<String>,
+    /// Public key in hex, so listing addresses does not need the key pairs; empty if the wallet
+    /// holds no key pair for the address
+    #[serde(default)]
+    pub public_key: String,
     /// Address label
     pub label: Option<String>,
+    /// Belongs to a seed the wallet was re-keyed away from. Still synced and spendable, so coins
+    /// that reach it can be swept, but never handed out to receive again.
+    #[serde(default)]
+    pub retired: bool,
+}
+
+impl AddressInfo {
+    /// Whether the address's key was imported rather than derived from the seed
+    pub fn is_imported(&self) -> bool {
+        self.derivation_path == IMPORTED_KEY_DERIVATION_PATH
+    }
 }
 
 /// Key pair for a specific address
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyPair {
     /// The private key in WIF format
-    pub private_key: String,
+    #[serde(with = "crate::secret::serde_secret")]
+    pub private_key: SecretString,
     /// The public key in hex format
     pub public_key: String,
     /// The address derived from the public key
@@ -140,6 +179,49 @@ pub struct KeyPair {
     pub derivation_path: String,
 }
 
+impl KeyPair {
+    /// The private key. The first key of a seed is stored as raw hex rather than WIF, so both are read.
+    pub fn signing_key(&self) -> Result<bitcoin::PrivateKey, bitcoin::key::FromWifError> {
+        let secret = self.private_key.expose_secret();
+        bitcoin::PrivateKey::from_wif(secret).or_else(|e| {
+            hex::decode(secret)
+                .ok()
+                .and_then(|bytes| bitcoin::PrivateKey::from_slice(&bytes, ChainParams::active().address_network).ok())
+                .ok_or(e)
+        })
+    }
+}
+
+/// Which profile of a wallet file was unlocked.
+///
+/// A secured wallet file holds a main profile and a decoy, each encrypted with its own password.
+/// The duress password opens the decoy, a separate wallet meant to hold limited funds, so an
+/// unlock under coercion does not expose the main keys, balance or history. Wallets without a
+/// decoy get a section of random bytes in its place, so the file does not show whether one exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WalletProfile {
+    #[default]
+    Main,
+    Decoy,
+}
+
+impl WalletProfile {
+    /// Position of the profile's section in the wallet file
+    fn section(self) -> usize {
+        match self {
+            WalletProfile::Main => 0,
+            WalletProfile::Decoy => 1,
+        }
+    }
+
+    fn from_section(index: usize) -> Self {
+        if index == 0 { WalletProfile::Main } else { WalletProfile::Decoy }
+    }
+}
+
+/// Profile sections written to every encrypted wallet file
+const PROFILE_SECTIONS: usize = 2;
+
 /// Core wallet data structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WalletData {
@@ -152,13 +234,15 @@ pub struct WalletData {
     /// Last synced block height
     pub block_height: u32,
     /// BIP39 seed phrase (12/24 words), encrypted if wallet is secured
-    pub seed_phrase: Option<String>,
+    #[serde(with = "crate::secret::serde_secret_option")]
+    pub seed_phrase: Option<SecretString>,
     /// Master private key (xpriv), encrypted if wallet is secured
-    pub master_private_key: Option<String>,
+    #[serde(with = "crate::secret::serde_secret_option")]
+    pub master_private_key: Option<SecretString>,
     /// Master public key (xpub)
     pub master_public_key: String,
-    /// Key pairs in the wallet (address -> key pair)
-    pub keys: HashMap<String, KeyPair>,
+    /// Key pairs in the wallet (address -> key pair), parsed on first use
+    pub keys: WalletKeys,
     /// Addresses in the wallet with metadata
     pub addresses: Vec<AddressInfo>,
     /// List of UTXOs
@@ -168,9 +252,39 @@ pub struct WalletData {
     /// Current balance in satoshis
     pub balance: u64,
     /// Account indexes for BIP44 paths
+    #[serde(serialize_with = "serialize_sorted")]
     pub account_indexes: HashMap<u32, u32>,
     /// Is this wallet password protected
     pub is_encrypted: bool,
+    /// BIP32 master key fingerprint, used to detect a wrong BIP39 passphrase
+    #[serde(default)]
+    pub master_fingerprint: Option<String>,
+    /// Whether a BIP39 passphrase was used when the wallet was created
+    #[serde(default)]
+    pub has_passphrase: bool,
+    /// Whether the user has proven they wrote down the seed phrase
+    #[serde(default)]
+    pub backup_verified: bool,
+    /// Outpoints ("txid:vout") the user froze; coin selection never spends them
+    #[serde(default)]
+    pub frozen_utxos: BTreeSet<String>,
+    /// Profile this data was loaded from; never written into the JSON itself
+    #[serde(skip)]
+    pub profile: WalletProfile,
+    /// Set when the wallet was opened from media it must not be written to; saving fails
+    #[serde(skip)]
+    pub read_only: Option<ReadOnlyReason>,
+}
+
+/// Write map entries in key order so saving the same wallet always produces the same bytes
+fn serialize_sorted<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Serialize + Ord,
+    V: Serialize,
+    S: serde::Serializer,
+{
+    let sorted: BTreeMap<&K, &V> = map.iter().collect();
+    sorted.serialize(serializer)
 }
 
 // Encryption related constants
@@ -202,44 +316,79 @@ impl WalletData {
             seed_phrase: None,
             master_private_key: None,
             master_public_key: master_public_key.to_string(),
-            keys: HashMap::new(),
+            keys: WalletKeys::default(),
             addresses: Vec::new(),
             utxos: Vec::new(),
             transactions: Vec::new(),
             balance: 0,
             account_indexes: HashMap::new(),
             is_encrypted: is_encrypted,
+            master_fingerprint: None,
+            has_passphrase: false,
+            backup_verified: false,
+            frozen_utxos: BTreeSet::new(),
+            profile: WalletProfile::Main,
+            read_only: None,
         }
     }
     
     /// Set sensitive data for wallet (only for newly created wallets, before saving)
     pub fn set_sensitive_data(&mut self, seed_phrase: &str, master_private_key: &str) {
-        self.seed_phrase = Some(seed_phrase.to_string());
-        self.master_private_key = Some(master_private_key.to_string());
+        self.seed_phrase = Some(SecretString::from(seed_phrase));
+        self.master_private_key = Some(SecretString::from(master_private_key));
     }
     
-    /// Add a new key pair to the wallet
-    pub fn add_key_pair(&mut self, key_pair: KeyPair) {
-        let address = key_pair.address.clone();
+    /// Whether this wallet only tracks addresses and holds no private key material
+    pub fn is_watch_only(&self) -> bool {
+        let has_master_key = self.master_private_key.as_ref().map(|key| !key.is_empty()).unwrap_or(false);
+        let has_address_keys = self.keys.values().any(|key_pair| !key_pair.private_key.is_empty());
+        !has_master_key && !has_address_keys
+    }
+    
+    /// Wipe seed phrase and private keys from memory (called when the wallet is closed)
+    pub fn clear_sensitive_data(&mut self) {
+        if let Some(seed_phrase) = self.seed_phrase.as_mut() {
+            seed_phrase.clear();
+        }
+        if let Some(master_private_key) = self.master_private_key.as_mut() {
+            master_private_key.clear();
+        }
+        self.seed_phrase = None;
+        self.master_private_key = None;
         
-        // Add the address info
-        self.addresses.push(AddressInfo {
-            address: address.clone(),
+        self.keys.wipe_private_keys();
+    }
+    
+    /// Add a new key pair to the wallet. A key that was not imported is recorded as deriving from
+    /// the wallet's master key.
+    pub fn add_key_pair(&mut self, key_pair: KeyPair) -> Result<(), WalletDataError> {
+        let address_info = AddressInfo {
+            address: key_pair.address.clone(),
             key_type: key_pair.key_type.clone(),
             derivation_path: key_pair.derivation_path.clone(),
+            master_fingerprint: self.master_fingerprint.clone().filter(|_| key_pair.derivation_path != IMPORTED_KEY_DERIVATION_PATH),
+            public_key: key_pair.public_key.clone(),
             label: None,
-        });
+            retired: false,
+        };
         
-        // Add the key pair
-        self.keys.insert(address, key_pair);
+        // Add the key pair, then its address info
+        self.keys.insert(key_pair.address.clone(), key_pair)?;
+        self.addresses.push(address_info);
         
         // Update modified time
         self.modified_at = chrono::Utc::now().timestamp();
+        Ok(())
     }
     
     /// Save wallet data to file, encrypting if necessary
     pub fn save(&self, path: &PathBuf, password: Option<&str>) -> Result<(), WalletDataError> {
-        let serialized = serde_json::to_string_pretty(&self)?;
+        if let Some(reason) = self.read_only {
+            error!("Not saving {}: {}", path.display(), reason.describe());
+            return Err(WalletDataError::ReadOnly(reason));
+        }
+        
+        let serialized = Zeroizing::new(serde_json::to_string_pretty(&self)?);
         
         // If wallet is encrypted but no password provided, return error
         if self.is_encrypted && password.is_none() {
@@ -248,35 +397,147 @@ impl WalletData {
             ));
         }
         
-        // If the wallet is encrypted, encrypt the data
+        // If the wallet is encrypted, encrypt the data into this profile's section and keep the
+        // sections of the other profiles, which this password cannot read
         let file_data = if self.is_encrypted {
             let password = password.unwrap(); // Safe because we checked above
-            self.encrypt_data(&serialized, password)?
+            let section = self.encrypt_data(&serialized, password)?;
+            let section_len = section.len();
+            let mut sections = Self::existing_sections(path);
+            sections.resize_with(PROFILE_SECTIONS, Vec::new);
+            sections[self.profile.section()] = section;
+            for other in sections.iter_mut().filter(|other| other.is_empty()) {
+                *other = Self::filler_section(section_len)?;
+            }
+            Zeroizing::new(crate::wallet_file::encode_profiles(&sections))
         } else {
-            serialized.into_bytes()
+            Zeroizing::new(crate::wallet_file::encode(serialized.as_bytes(), false))
         };
         
-        // Create directory if it doesn't exist
-        if let Some(dir) = path.parent() {
-            fs::create_dir_all(dir)?;
-        }
-        
-        // Write the data to file
-        fs::write(path, file_data)?;
+        // Write the data atomically so a crash cannot leave a truncated wallet file
+        crate::atomic_file::write_atomic(path, &file_data)?;
         info!("Wallet data saved to {}", path.display());
         
         Ok(())
     }
     
-    /// Load wallet data from file
+    /// Save on the blocking thread pool, so key stretching and the file write do not hold up the
+    /// async runtime
+    pub async fn save_async(&self, path: &Path, password: Option<&str>) -> Result<(), WalletDataError> {
+        let (data, path, password) = (self.clone(), path.to_path_buf(), password.map(SecretString::from));
+        tokio::task::spawn_blocking(move || data.save(&path, password.as_ref().map(SecretString::expose_secret)))
+            .await
+            .map_err(|e| WalletDataError::IoError(std::io::Error::other(e)))?
+    }
+    
+    /// Load on the blocking thread pool; see `save_async`
+    pub async fn load_async(path: &Path, password: Option<&str>) -> Result<Self, WalletDataError> {
+        let (path, password) = (path.to_path_buf(), password.map(SecretString::from));
+        tokio::task::spawn_blocking(move || Self::load(&path, password.as_ref().map(SecretString::expose_secret)))
+            .await
+            .map_err(|e| WalletDataError::IoError(std::io::Error::other(e)))?
+    }
+    
+    /// Load wallet data from file. Files from before the versioned format are still read
+    /// and are upgraded to the current format the next time the wallet is saved.
     pub fn load(path: &PathBuf, password: Option<&str>) -> Result<Self, WalletDataError> {
         info!("Loading wallet data from {}", path.display());
         
         // Read the file
         let file_data = fs::read(path)?;
         
+        if !crate::wallet_file::is_versioned(&file_data) {
+            return Self::load_legacy(&file_data, password);
+        }
+        
+        let file = crate::wallet_file::decode(&file_data).map_err(|e| {
+            error!("Rejecting wallet file {}: {}", path.display(), e);
+            e
+        })?;
+        
+        let (json, profile) = if file.encrypted {
+            let password = password.ok_or_else(|| {
+                WalletDataError::DecryptionError("Password required for encrypted wallet".to_string())
+            })?;
+            let (json, profile) = Self::decrypt_profile(&file.sections()?, password)?;
+            (Zeroizing::new(json), profile)
+        } else {
+            let json = String::from_utf8(file.payload.to_vec())
+                .map_err(|_| WalletDataError::InvalidFormat("wallet data is not valid UTF-8".to_string()))?;
+            (Zeroizing::new(json), WalletProfile::Main)
+        };
+        
+        let mut wallet = Self::from_json(&json, file.version)?;
+        wallet.profile = profile;
+        Ok(wallet)
+    }
+    
+    /// Decrypt whichever section the password opens. Every section is tried, so how long an
+    /// unlock takes does not tell which profile was opened.
+    fn decrypt_profile(sections: &[&[u8]], password: &str) -> Result<(String, WalletProfile), WalletDataError> {
+        let mut opened = None;
+        let mut last_error = WalletDataError::InvalidPassword;
+        for (index, section) in sections.iter().enumerate() {
+            match Self::decrypt_data(section, password) {
+                Ok(json) if opened.is_none() => opened = Some((json, WalletProfile::from_section(index))),
+                Ok(_) => {}
+                Err(e) => last_error = e,
+            }
+        }
+        match opened {
+            Some(opened) => Ok(opened),
+            // A single section keeps its own error; with several, the password matched none
+            None if sections.len() == 1 => Err(last_error),
+            None => Err(WalletDataError::InvalidPassword),
+        }
+    }
+    
+    /// Encrypted sections of the wallet file at `path`, or none if it has no profile sections
+    fn existing_sections(path: &PathBuf) -> Vec<Vec<u8>> {
+        let Ok(file_data) = fs::read(path) else {
+            return Vec::new();
+        };
+        crate::wallet_file::decode(&file_data)
+            .ok()
+            .filter(|file| file.profiles)
+            .and_then(|file| file.sections().ok())
+            .map(|sections| sections.into_iter().map(<[u8]>::to_vec).collect())
+            .unwrap_or_default()
+    }
+    
+    /// Random bytes standing in for a profile that does not exist, between half and all of the
+    /// size of a real section
+    fn filler_section(section_len: usize) -> Result<Vec<u8>, WalletDataError> {
+        let rand = SystemRandom::new();
+        let mut length_bytes = [0u8; 4];
+        rand.fill(&mut length_bytes)
+            .map_err(|_| WalletDataError::EncryptionError("Failed to generate filler".to_string()))?;
+        let min_len = (section_len / 2).max(SALT_LEN + NONCE_LEN + TAG_LEN);
+        let spread = section_len.saturating_sub(min_len) + 1;
+        let mut filler = vec![0u8; min_len + u32::from_le_bytes(length_bytes) as usize % spread];
+        rand.fill(&mut filler)
+            .map_err(|_| WalletDataError::EncryptionError("Failed to generate filler".to_string()))?;
+        Ok(filler)
+    }
+    
+    /// Replace the decoy profile of the wallet file at `path` with random bytes. The caller must
+    /// have checked the main password.
+    pub fn remove_decoy(path: &PathBuf) -> Result<(), WalletDataError> {
+        let mut sections = Self::existing_sections(path);
+        if sections.len() != PROFILE_SECTIONS {
+            return Err(WalletDataError::InvalidFormat("wallet file has no profile sections".to_string()));
+        }
+        let main_len = sections[WalletProfile::Main.section()].len();
+        sections[WalletProfile::Decoy.section()] = Self::filler_section(main_len)?;
+        let file_data = crate::wallet_file::encode_profiles(&sections);
+        crate::atomic_file::write_atomic(path, &file_data)?;
+        Ok(())
+    }
+    
+    /// Read an unversioned wallet file: plain JSON, or the encrypted blob without a header
+    fn load_legacy(file_data: &[u8], password: Option<&str>) -> Result<Self, WalletDataError> {
         // Try to parse as JSON first (unencrypted wallet)
-        match serde_json::from_slice::<WalletData>(&file_data) {
+        match serde_json::from_slice::<WalletData>(file_data) {
             Ok(wallet) => {
                 // If the wallet is encrypted but no password provided, return error
                 if wallet.is_encrypted && password.is_none() {
@@ -291,29 +552,34 @@ impl WalletData {
                 }
                 
                 // Otherwise, we parsed an encrypted wallet as unencrypted JSON, which should never happen
-                return Err(WalletDataError::DecryptionError(
+                Err(WalletDataError::DecryptionError(
                     "Wallet is marked as encrypted but data is not encrypted".to_string()
-                ));
+                ))
             },
             Err(_) => {
                 // If we can't parse as JSON, assume it's encrypted
-                if password.is_none() {
-                    return Err(WalletDataError::DecryptionError(
-                        "Password required for encrypted wallet".to_string()
-                    ));
-                }
+                let password = password.ok_or_else(|| {
+                    WalletDataError::DecryptionError("Password required for encrypted wallet".to_string())
+                })?;
                 
                 // Try to decrypt
-                let password = password.unwrap(); // Safe because we checked above
-                let decrypted_data = Self::decrypt_data(&file_data, password)?;
-                
-                // Parse the decrypted data
-                let wallet = serde_json::from_str(&decrypted_data)?;
-                Ok(wallet)
+                let decrypted_data = Zeroizing::new(Self::decrypt_data(file_data, password)?);
+                Self::from_json(&decrypted_data, 0)
             }
         }
     }
     
+    /// Parse wallet JSON written by the given format version
+    fn from_json(json: &str, version: u16) -> Result<Self, WalletDataError> {
+        if version == crate::wallet_file::WALLET_FORMAT_VERSION {
+            return Ok(serde_json::from_str(json)?);
+        }
+        let value = crate::wallet_file::migrate(serde_json::from_str(json)?, version)?;
+        // Back through text, as the key pairs are kept as the JSON read
+        let json = Zeroizing::new(serde_json::to_string(&value)?);
+        Ok(serde_json::from_str(&json)?)
+    }
+    
     /// Encrypt data using password-based AES-256-GCM
     fn encrypt_data(&self, data: &str, password: &str) -> Result<Vec<u8>, WalletDataError> {
         let rand = SystemRandom::new();
@@ -339,7 +605,9 @@ impl WalletData {
         );
 
         // Set up AES-GCM for encryption
-        let unbound_key = UnboundKey::new(&aead::AES_256_GCM, &key_bytes)
+        let unbound_key = UnboundKey::new(&aead::AES_256_GCM, &key_bytes);
+        key_bytes.zeroize();
+        let unbound_key = unbound_key
             .map_err(|_| WalletDataError::EncryptionError("Failed to create encryption key".to_string()))?;
 
         let nonce = Nonce::assume_unique_for_key(nonce_bytes);
@@ -388,7 +656,9 @@ impl WalletData {
         );
 
         // Set up AES-GCM for decryption
-        let unbound_key = UnboundKey::new(&aead::AES_256_GCM, &key_bytes)
+        let unbound_key = UnboundKey::new(&aead::AES_256_GCM, &key_bytes);
+        key_bytes.zeroize();
+        let unbound_key = unbound_key
             .map_err(|_| WalletDataError::DecryptionError("Failed to create decryption key".to_string()))?;
 
         let mut nonce_array = [0u8; NONCE_LEN];
@@ -424,6 +694,39 @@ impl WalletData {
     pub fn get_addresses(&self) -> Vec<String> {
         self.addresses.iter().map(|addr| addr.address.clone()).collect()
     }
+
+    /// Addresses that may be handed out to receive or take change, oldest first
+    pub fn receiving_addresses(&self) -> impl Iterator<Item = &AddressInfo> {
+        self.addresses.iter().filter(|addr| !addr.retired)
+    }
+
+    /// Whether `address` belongs to a seed the wallet was re-keyed away from
+    pub fn is_retired(&self, address: &str) -> bool {
+        self.addresses.iter().any(|addr| addr.retired && addr.address == address)
+    }
+
+    /// Move the wallet onto the seed of `fresh`, wallet data built from a new seed phrase. Every
+    /// address the wallet had, imported ones included, is retired; its key is kept so coins
+    /// still on it can be swept. The new seed has not been backed up yet.
+    pub fn rekey(&mut self, fresh: WalletData) -> Result<(), WalletDataError> {
+        for addr in &mut self.addresses {
+            addr.retired = true;
+        }
+        self.seed_phrase = fresh.seed_phrase;
+        self.master_private_key = fresh.master_private_key;
+        self.master_public_key = fresh.master_public_key;
+        self.master_fingerprint = fresh.master_fingerprint;
+        self.has_passphrase = fresh.has_passphrase;
+        self.backup_verified = false;
+        for addr in fresh.addresses {
+            if let Some(key_pair) = fresh.keys.get(&addr.address) {
+                self.keys.insert(addr.address.clone(), key_pair.clone())?;
+            }
+            self.addresses.push(addr);
+        }
+        self.modified_at = chrono::Utc::now().timestamp();
+        Ok(())
+    }
     
     /// Add a UTXO to the wallet
     pub fn add_utxo(&mut self, utxo: Utxo) {
@@ -438,10 +741,31 @@ impl WalletData {
     /// Remove a spent UTXO from the wallet
     pub fn remove_utxo(&mut self, txid: &str, vout: u32) {
         self.utxos.retain(|utxo| !(utxo.txid == txid && utxo.vout == vout));
+        self.frozen_utxos.remove(&format!("{}:{}", txid, vout));
         self.balance = self.calculate_balance();
         self.modified_at = chrono::Utc::now().timestamp();
     }
     
+    /// Freeze or unfreeze one of the wallet's coins. Returns false if the wallet has no such coin
+    /// (a stale frozen entry can still be unfrozen).
+    pub fn set_utxo_frozen(&mut self, txid: &str, vout: u32, frozen: bool) -> bool {
+        let outpoint = format!("{}:{}", txid, vout);
+        let exists = self.utxos.iter().any(|utxo| utxo.txid == txid && utxo.vout == vout);
+        let changed = if frozen {
+            exists && self.frozen_utxos.insert(outpoint)
+        } else {
+            self.frozen_utxos.remove(&outpoint)
+        };
+        if changed {
+            self.modified_at = chrono::Utc::now().timestamp();
+        }
+        exists || changed
+    }
+
+    pub fn is_utxo_frozen(&self, utxo: &Utxo) -> bool {
+        self.frozen_utxos.contains(&format!("{}:{}", utxo.txid, utxo.vout))
+    }
+
     /// Add a transaction to the history
     pub fn add_transaction(&mut self, tx: Transaction) {
         // Check if transaction already exists
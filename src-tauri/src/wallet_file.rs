@@ -0,0 +1,283 @@
+//! On-disk container for wallet.dat
+//!
+//! Layout (integers little-endian):
+//! `magic (8) | format version (u16) | flags (u16) | payload length (u64) | SHA-256 checksum (32) | payload`
+//!
+//! The checksum covers the header fields before it and the payload, so a truncated or
+//! bit-flipped file is rejected before decryption is attempted. The payload is the wallet
+//! JSON, encrypted when the encrypted flag is set. Files written before the container
+//! existed are raw JSON or a raw encrypted blob and are read as format version 0.
+//!
+//! With the profiles flag the encrypted payload holds one section per profile, each encrypted
+//! with its own password: `section count (u8) | { section length (u64) | section }...`.
+
+use crate::wallet_data::WalletDataError;
+use log::info;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Marks a versioned wallet file
+pub const WALLET_FILE_MAGIC: [u8; 8] = *b"BRADWLT\0";
+
+/// Format version written by this build. Bump it and add a step to `MIGRATIONS` when the
+/// wallet schema changes (new key types, multisig, labels, ...).
+pub const WALLET_FORMAT_VERSION: u16 = 3;
+
+/// Payload is encrypted with the wallet password
+const FLAG_ENCRYPTED: u16 = 0x0001;
+
+/// Encrypted payload is split into profile sections
+const FLAG_PROFILES: u16 = 0x0002;
+
+const CHECKSUM_LEN: usize = 32;
+const HEADER_LEN: usize = WALLET_FILE_MAGIC.len() + 2 + 2 + 8;
+
+/// A single upgrade step of the wallet JSON from version N to N + 1
+type Migration = fn(Value) -> Result<Value, WalletDataError>;
+
+/// Upgrade steps, indexed by the version they upgrade from
+const MIGRATIONS: [Migration; WALLET_FORMAT_VERSION as usize] = [migrate_v0_to_v1, migrate_v1_to_v2, migrate_v2_to_v3];
+
+/// Contents of a versioned wallet file
+#[derive(Debug, PartialEq, Eq)]
+pub struct WalletFile<'a> {
+    pub version: u16,
+    pub encrypted: bool,
+    pub profiles: bool,
+    pub payload: &'a [u8],
+}
+
+impl<'a> WalletFile<'a> {
+    /// The encrypted sections of the payload; a file without profiles has one
+    pub fn sections(&self) -> Result<Vec<&'a [u8]>, WalletDataError> {
+        if !self.profiles {
+            return Ok(vec![self.payload]);
+        }
+        let truncated = || WalletDataError::InvalidFormat("profile section is truncated".to_string());
+        let (&count, mut rest) = self.payload.split_first().ok_or_else(truncated)?;
+        let mut sections = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            if rest.len() < 8 {
+                return Err(truncated());
+            }
+            let (length_bytes, after) = rest.split_at(8);
+            let mut length = [0u8; 8];
+            length.copy_from_slice(length_bytes);
+            let length = usize::try_from(u64::from_le_bytes(length)).map_err(|_| truncated())?;
+            if after.len() < length {
+                return Err(truncated());
+            }
+            let (section, after) = after.split_at(length);
+            sections.push(section);
+            rest = after;
+        }
+        if !rest.is_empty() {
+            return Err(WalletDataError::InvalidFormat("trailing data after profile sections".to_string()));
+        }
+        Ok(sections)
+    }
+}
+
+/// Whether the bytes start with the versioned container header
+pub fn is_versioned(data: &[u8]) -> bool {
+    data.starts_with(&WALLET_FILE_MAGIC)
+}
+
+/// Wrap a payload in the current container format
+pub fn encode(payload: &[u8], encrypted: bool) -> Vec<u8> {
+    encode_with_flags(payload, if encrypted { FLAG_ENCRYPTED } else { 0 })
+}
+
+/// Wrap encrypted profile sections in the current container format
+pub fn encode_profiles(sections: &[Vec<u8>]) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(1 + sections.iter().map(|section| 8 + section.len()).sum::<usize>());
+    payload.push(sections.len() as u8);
+    for section in sections {
+        payload.extend_from_slice(&(section.len() as u64).to_le_bytes());
+        payload.extend_from_slice(section);
+    }
+    encode_with_flags(&payload, FLAG_ENCRYPTED | FLAG_PROFILES)
+}
+
+fn encode_with_flags(payload: &[u8], flags: u16) -> Vec<u8> {
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(&WALLET_FILE_MAGIC);
+    header.extend_from_slice(&WALLET_FORMAT_VERSION.to_le_bytes());
+    header.extend_from_slice(&flags.to_le_bytes());
+    header.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+
+    let checksum = checksum(&header, payload);
+
+    let mut file = Vec::with_capacity(HEADER_LEN + CHECKSUM_LEN + payload.len());
+    file.extend_from_slice(&header);
+    file.extend_from_slice(&checksum);
+    file.extend_from_slice(payload);
+    file
+}
+
+/// Parse and verify a versioned wallet file
+pub fn decode(data: &[u8]) -> Result<WalletFile<'_>, WalletDataError> {
+    if !is_versioned(data) {
+        return Err(WalletDataError::InvalidFormat("missing wallet file header".to_string()));
+    }
+    if data.len() < HEADER_LEN + CHECKSUM_LEN {
+        return Err(WalletDataError::InvalidFormat("wallet file header is truncated".to_string()));
+    }
+
+    let (header, rest) = data.split_at(HEADER_LEN);
+    let (stored_checksum, payload) = rest.split_at(CHECKSUM_LEN);
+
+    let version = u16::from_le_bytes([header[8], header[9]]);
+    let flags = u16::from_le_bytes([header[10], header[11]]);
+    let mut length_bytes = [0u8; 8];
+    length_bytes.copy_from_slice(&header[12..HEADER_LEN]);
+    let payload_len = u64::from_le_bytes(length_bytes);
+
+    if payload_len != payload.len() as u64 || checksum(header, payload)[..] != stored_checksum[..] {
+        return Err(WalletDataError::ChecksumMismatch);
+    }
+    if version > WALLET_FORMAT_VERSION {
+        return Err(WalletDataError::UnsupportedVersion {
+            found: version,
+            supported: WALLET_FORMAT_VERSION,
+        });
+    }
+
+    Ok(WalletFile {
+        version,
+        encrypted: flags & FLAG_ENCRYPTED != 0,
+        profiles: flags & FLAG_PROFILES != 0,
+        payload,
+    })
+}
+
+/// Upgrade wallet JSON written by an older format version step by step
+pub fn migrate(mut value: Value, from_version: u16) -> Result<Value, WalletDataError> {
+    let mut version = from_version;
+    while version < WALLET_FORMAT_VERSION {
+        info!("Migrating wallet data from format version {} to {}", version, version + 1);
+        value = MIGRATIONS[version as usize](value)?;
+        version += 1;
+    }
+    Ok(value)
+}
+
+fn checksum(header: &[u8], payload: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut hasher = Sha256::new();
+    hasher.update(header);
+    hasher.update(payload);
+    hasher.finalize().into()
+}
+
+/// Version 0 is the unversioned JSON layout. Fields added since then carry serde defaults,
+/// so the schema itself is unchanged; only the container is new.
+fn migrate_v0_to_v1(value: Value) -> Result<Value, WalletDataError> {
+    if !value.is_object() {
+        return Err(WalletDataError::InvalidFormat("wallet data is not an object".to_string()));
+    }
+    Ok(value)
+}
+
+/// Version 2 splits encrypted payloads into profile sections; the JSON inside is unchanged
+fn migrate_v1_to_v2(value: Value) -> Result<Value, WalletDataError> {
+    Ok(value)
+}
+
+/// Version 3 copies each address's public key from its key pair into the address list, so the
+/// list can be shown without parsing the key pairs
+fn migrate_v2_to_v3(mut value: Value) -> Result<Value, WalletDataError> {
+    let public_keys: std::collections::HashMap<String, Value> = value
+        .get("keys")
+        .and_then(Value::as_object)
+        .map(|keys| {
+            keys.iter()
+                .filter_map(|(address, key_pair)| Some((address.clone(), key_pair.get("public_key")?.clone())))
+                .collect()
+        })
+        .unwrap_or_default();
+    if let Some(addresses) = value.get_mut("addresses").and_then(Value::as_array_mut) {
+        for address in addresses.iter_mut().filter_map(Value::as_object_mut) {
+            let public_key = address
+                .get("address")
+                .and_then(Value::as_str)
+                .and_then(|address| public_keys.get(address))
+                .cloned();
+            if let Some(public_key) = public_key {
+                address.insert("public_key".to_string(), public_key);
+            }
+        }
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let file = encode(b"{\"name\":\"test\"}", true);
+        assert!(is_versioned(&file));
+
+        let decoded = decode(&file).unwrap();
+        assert_eq!(decoded.version, WALLET_FORMAT_VERSION);
+        assert!(decoded.encrypted);
+        assert_eq!(decoded.payload, b"{\"name\":\"test\"}");
+    }
+
+    #[test]
+    fn test_profile_sections_round_trip() {
+        let file = encode_profiles(&[b"main".to_vec(), b"decoy".to_vec()]);
+        let decoded = decode(&file).unwrap();
+        assert!(decoded.encrypted && decoded.profiles);
+        assert_eq!(decoded.sections().unwrap(), vec![&b"main"[..], &b"decoy"[..]]);
+
+        let plain = encode(b"{}", false);
+        assert_eq!(decode(&plain).unwrap().sections().unwrap(), vec![&b"{}"[..]]);
+    }
+
+    #[test]
+    fn test_corruption_is_detected() {
+        let mut flipped = encode(b"{\"name\":\"test\"}", false);
+        let last = flipped.len() - 1;
+        flipped[last] ^= 0x01;
+        assert!(matches!(decode(&flipped), Err(WalletDataError::ChecksumMismatch)));
+
+        let mut truncated = encode(b"{\"name\":\"test\"}", false);
+        truncated.pop();
+        assert!(matches!(decode(&truncated), Err(WalletDataError::ChecksumMismatch)));
+    }
+
+    #[test]
+    fn test_migration_copies_public_keys_into_the_address_list() {
+        let v2 = serde_json::json!({
+            "keys": { "addr1": { "public_key": "02ab", "address": "addr1" } },
+            "addresses": [{ "address": "addr1" }, { "address": "watched" }],
+        });
+        let migrated = migrate(v2, 2).unwrap();
+        assert_eq!(migrated["addresses"][0]["public_key"], "02ab");
+        assert!(migrated["addresses"][1].get("public_key").is_none());
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let payload = b"{}";
+        let mut header = Vec::new();
+        header.extend_from_slice(&WALLET_FILE_MAGIC);
+        header.extend_from_slice(&(WALLET_FORMAT_VERSION + 1).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        let mut file = header.clone();
+        file.extend_from_slice(&checksum(&header, payload));
+        file.extend_from_slice(payload);
+
+        match decode(&file) {
+            Err(WalletDataError::UnsupportedVersion { found, supported }) => {
+                assert_eq!(found, WALLET_FORMAT_VERSION + 1);
+                assert_eq!(supported, WALLET_FORMAT_VERSION);
+            }
+            other => panic!("expected UnsupportedVersion, got {:?}", other),
+        }
+    }
+}
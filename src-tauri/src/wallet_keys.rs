@@ -0,0 +1,190 @@
+//! Key pairs of an open wallet, parsed on first use.
+//!
+//! In a wallet with many addresses the key pairs are most of the wallet JSON, yet only signing,
+//! key export and a few audits read them; the address list, balances and history do not. Loading
+//! keeps the `keys` JSON as it was read and parses it the first time a key pair is looked up, so a
+//! large wallet opens without waiting for it. Until the key pairs change, saving writes that JSON
+//! back unchanged. It holds the private keys, so it is wiped from memory like them.
+
+use crate::wallet_data::{KeyPair, WalletDataError};
+use log::{debug, error};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::value::RawValue;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::Instant;
+use zeroize::Zeroize;
+
+/// Key pairs by address
+#[derive(Clone)]
+pub struct WalletKeys {
+    /// The `keys` JSON as loaded; dropped once the key pairs change
+    raw: Option<Box<RawValue>>,
+    /// None if the loaded JSON does not hold valid key pairs
+    parsed: OnceLock<Option<HashMap<String, KeyPair>>>,
+}
+
+impl Default for WalletKeys {
+    fn default() -> Self {
+        WalletKeys {
+            raw: None,
+            parsed: OnceLock::from(Some(HashMap::new())),
+        }
+    }
+}
+
+impl WalletKeys {
+    /// Whether the key pairs have been parsed
+    pub fn is_loaded(&self) -> bool {
+        self.parsed.get().is_some()
+    }
+
+    pub fn get(&self, address: &str) -> Option<&KeyPair> {
+        self.loaded()?.get(address)
+    }
+
+    pub fn contains_key(&self, address: &str) -> bool {
+        self.get(address).is_some()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &KeyPair> {
+        self.loaded().into_iter().flat_map(HashMap::values)
+    }
+
+    /// Add or replace the key pair of `address`. Fails if the loaded key pairs are unreadable, so
+    /// a save cannot replace them with the new key alone.
+    pub fn insert(&mut self, address: String, key_pair: KeyPair) -> Result<(), WalletDataError> {
+        self.loaded_mut()?.insert(address, key_pair);
+        Ok(())
+    }
+
+    /// Wipe the private keys from memory, those not parsed yet included
+    pub fn wipe_private_keys(&mut self) {
+        wipe(&mut self.raw);
+        if let Some(Some(keys)) = self.parsed.get_mut() {
+            for key_pair in keys.values_mut() {
+                key_pair.private_key.clear();
+            }
+        }
+    }
+
+    fn loaded(&self) -> Option<&HashMap<String, KeyPair>> {
+        self.parsed.get_or_init(|| self.parse()).as_ref()
+    }
+
+    fn loaded_mut(&mut self) -> Result<&mut HashMap<String, KeyPair>, WalletDataError> {
+        if self.loaded().is_none() {
+            return Err(unreadable());
+        }
+        // The JSON as loaded no longer matches once the key pairs change
+        wipe(&mut self.raw);
+        self.parsed.get_mut().and_then(Option::as_mut).ok_or_else(unreadable)
+    }
+
+    fn parse(&self) -> Option<HashMap<String, KeyPair>> {
+        let Some(raw) = &self.raw else {
+            return Some(HashMap::new());
+        };
+        let started = Instant::now();
+        match serde_json::from_str::<HashMap<String, KeyPair>>(raw.get()) {
+            Ok(keys) => {
+                debug!("Parsed {} key pairs in {} ms", keys.len(), started.elapsed().as_millis());
+                Some(keys)
+            }
+            Err(e) => {
+                error!("The wallet's key pairs are unreadable: {}", e);
+                None
+            }
+        }
+    }
+}
+
+fn unreadable() -> WalletDataError {
+    WalletDataError::InvalidFormat("the wallet's key pairs are unreadable".to_string())
+}
+
+/// Drop the loaded JSON, overwriting it first
+fn wipe(raw: &mut Option<Box<RawValue>>) {
+    if let Some(raw) = raw.take() {
+        let raw: Box<str> = raw.into();
+        raw.into_string().zeroize();
+    }
+}
+
+impl Drop for WalletKeys {
+    fn drop(&mut self) {
+        wipe(&mut self.raw);
+    }
+}
+
+impl Serialize for WalletKeys {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.raw {
+            Some(raw) => raw.serialize(serializer),
+            // In address order so saving the same wallet always produces the same bytes
+            None => self.loaded().into_iter().flatten().collect::<BTreeMap<_, _>>().serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for WalletKeys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(WalletKeys {
+            raw: Some(Box::<RawValue>::deserialize(deserializer)?),
+            parsed: OnceLock::new(),
+        })
+    }
+}
+
+impl fmt::Debug for WalletKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.parsed.get() {
+            Some(Some(keys)) => f.debug_map().entries(keys.iter()).finish(),
+            Some(None) => f.write_str("WalletKeys(unreadable)"),
+            None => f.write_str("WalletKeys(not loaded)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet_data::KeyType;
+
+    fn key_pair(address: &str) -> KeyPair {
+        KeyPair {
+            private_key: format!("wif-{}", address).into(),
+            public_key: format!("pub-{}", address),
+            address: address.to_string(),
+            key_type: KeyType::NativeSegWit,
+            derivation_path: "m/84'/0'/0'/0/0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_key_pairs_are_parsed_on_first_use_and_kept_verbatim_until_changed() {
+        let mut keys = WalletKeys::default();
+        keys.insert("b".to_string(), key_pair("b")).unwrap();
+        keys.insert("a".to_string(), key_pair("a")).unwrap();
+        let json = serde_json::to_string(&keys).unwrap();
+        assert!(json.find("\"a\"").unwrap() < json.find("\"b\"").unwrap());
+
+        let mut loaded: WalletKeys = serde_json::from_str(&json).unwrap();
+        assert!(!loaded.is_loaded());
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+        assert_eq!(loaded.get("a").unwrap().public_key, "pub-a");
+        assert!(loaded.is_loaded());
+
+        loaded.insert("c".to_string(), key_pair("c")).unwrap();
+        assert!(serde_json::to_string(&loaded).unwrap().contains("pub-c"));
+        loaded.wipe_private_keys();
+        assert!(loaded.values().all(|key_pair| key_pair.private_key.is_empty()));
+
+        // Unreadable key pairs are written back as they were rather than lost
+        let mut unreadable: WalletKeys = serde_json::from_str(r#"{"a":5}"#).unwrap();
+        assert!(unreadable.get("a").is_none());
+        assert!(unreadable.insert("b".to_string(), key_pair("b")).is_err());
+        assert_eq!(serde_json::to_string(&unreadable).unwrap(), r#"{"a":5}"#);
+    }
+}
@@ -0,0 +1,264 @@
+//! Wallet annotations that can be moved between copies of a wallet.
+//!
+//! Address labels, transaction notes and contacts are exported to a small JSON file without any
+//! keys, so a user running the same wallet on two machines can keep them in sync. The file is
+//! signed with the key of the wallet's first address; on import the signature must come from an
+//! address of the importing wallet, so annotations from another wallet or an edited file are
+//! refused. Watch-only copies can import but not export, since they cannot sign.
+//!
+//! Contacts are kept next to wallet.dat, so they can be edited without the wallet password.
+
+use crate::atomic_file;
+use crate::chain_params::ChainParams;
+use crate::wallet_data::{WalletData, WalletDataError};
+use bitcoin::hashes::{sha256d, Hash};
+use bitcoin::secp256k1::{ecdsa::Signature, Message, Secp256k1};
+use bitcoin::{Address, CompressedPublicKey, PrivateKey};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+/// File name of the contacts, stored next to wallet.dat
+pub const CONTACTS_FILE: &str = "contacts.json";
+
+/// Format version of label exports
+pub const LABEL_EXPORT_VERSION: u32 = 1;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum LabelError {
+    #[error("Not a label export: {0}")]
+    Malformed(String),
+
+    #[error("Label export version {0} is not supported")]
+    UnsupportedVersion(u32),
+
+    #[error("The label export is not signed")]
+    Unsigned,
+
+    #[error("The label export signature is invalid")]
+    BadSignature,
+
+    #[error("The label export was signed by another wallet")]
+    ForeignWallet,
+}
+
+/// Someone the user pays, by name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contact {
+    pub name: String,
+    pub address: String,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Contacts of one wallet
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContactBook {
+    #[serde(default)]
+    pub contacts: Vec<Contact>,
+}
+
+impl ContactBook {
+    /// Load the contacts from a wallet directory, starting empty if there are none yet
+    pub fn load(wallet_dir: &Path) -> Result<Self, WalletDataError> {
+        let path = wallet_dir.join(CONTACTS_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(&path)?)?)
+    }
+
+    /// Save the contacts into a wallet directory
+    pub fn save(&self, wallet_dir: &Path) -> Result<(), WalletDataError> {
+        let path = wallet_dir.join(CONTACTS_FILE);
+        atomic_file::write_atomic(&path, serde_json::to_string_pretty(self)?.as_bytes())?;
+        Ok(())
+    }
+
+    /// Add a contact, or replace the one with the same address; returns whether anything changed
+    pub fn upsert(&mut self, contact: Contact) -> bool {
+        match self.contacts.iter_mut().find(|c| c.address == contact.address) {
+            Some(existing) if *existing == contact => false,
+            Some(existing) => {
+                *existing = contact;
+                true
+            }
+            None => {
+                self.contacts.push(contact);
+                true
+            }
+        }
+    }
+
+    /// Remove the contact with `address`; returns whether there was one
+    pub fn remove(&mut self, address: &str) -> bool {
+        let before = self.contacts.len();
+        self.contacts.retain(|c| c.address != address);
+        self.contacts.len() != before
+    }
+}
+
+/// What an import changed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LabelImportSummary {
+    pub labels_updated: usize,
+    pub notes_updated: usize,
+    pub contacts_updated: usize,
+    /// Labels and notes for addresses or transactions this copy does not know yet
+    pub skipped: usize,
+}
+
+/// Contents of a label export file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelExport {
+    pub version: u32,
+    pub exported_at: i64,
+    /// Label by address
+    pub address_labels: BTreeMap<String, String>,
+    /// Note by txid
+    pub transaction_notes: BTreeMap<String, String>,
+    pub contacts: Vec<Contact>,
+    /// Hex DER signature and the signing public key, over everything above
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+impl LabelExport {
+    /// The annotations of a wallet, unsigned
+    pub fn collect(data: &WalletData, contacts: &ContactBook, now: i64) -> Self {
+        Self {
+            version: LABEL_EXPORT_VERSION,
+            exported_at: now,
+            address_labels: data
+                .addresses
+                .iter()
+                .filter_map(|a| a.label.clone().map(|label| (a.address.clone(), label)))
+                .collect(),
+            transaction_notes: data
+                .transactions
+                .iter()
+                .filter_map(|tx| tx.memo.clone().map(|memo| (tx.txid.clone(), memo)))
+                .collect(),
+            contacts: contacts.contacts.clone(),
+            signature: None,
+        }
+    }
+
+    /// Parse an export file and check that its signature is valid and comes from one of
+    /// `addresses`, which belong to the importing wallet
+    pub fn parse_verified<'a>(contents: &str, mut addresses: impl Iterator<Item = &'a str>) -> Result<Self, LabelError> {
+        let export: LabelExport = serde_json::from_str(contents).map_err(|e| LabelError::Malformed(e.to_string()))?;
+        if export.version != LABEL_EXPORT_VERSION {
+            return Err(LabelError::UnsupportedVersion(export.version));
+        }
+        let signer = export.verify()?;
+        if !addresses.any(|address| address == signer) {
+            return Err(LabelError::ForeignWallet);
+        }
+        Ok(export)
+    }
+
+    fn signing_message(&self) -> Message {
+        let unsigned = LabelExport { signature: None, ..self.clone() };
+        // Maps serialize in key order, so the same annotations always give the same bytes
+        let preimage = serde_json::to_vec(&unsigned).unwrap_or_default();
+        Message::from_digest(sha256d::Hash::hash(&preimage).to_byte_array())
+    }
+
+    pub fn sign(&mut self, key: &PrivateKey) {
+        let secp = Secp256k1::new();
+        let signature = secp.sign_ecdsa(&self.signing_message(), &key.inner);
+        self.signature = Some(format!("{} {}", hex::encode(signature.serialize_der()), key.public_key(&secp)));
+    }
+
+    /// Check the signature, returning the address of the key that made it
+    pub fn verify(&self) -> Result<String, LabelError> {
+        let (signature, public_key) = self
+            .signature
+            .as_deref()
+            .and_then(|signature| signature.split_once(' '))
+            .ok_or(LabelError::Unsigned)?;
+        let signature = hex::decode(signature)
+            .ok()
+            .and_then(|der| Signature::from_der(&der).ok())
+            .ok_or(LabelError::BadSignature)?;
+        let public_key: CompressedPublicKey = public_key.parse().map_err(|_| LabelError::BadSignature)?;
+        Secp256k1::verification_only()
+            .verify_ecdsa(&self.signing_message(), &signature, &public_key.0)
+            .map_err(|_| LabelError::BadSignature)?;
+        Ok(Address::p2wpkh(&public_key, ChainParams::active().hrp()).to_string())
+    }
+
+    /// Copy the annotations into a wallet. Imported labels and notes replace local ones, since
+    /// the file comes from the copy that was annotated last; contacts are merged by address.
+    pub fn apply(&self, data: &mut WalletData, contacts: &mut ContactBook) -> LabelImportSummary {
+        let mut summary = LabelImportSummary::default();
+        for (address, label) in &self.address_labels {
+            match data.addresses.iter_mut().find(|a| &a.address == address) {
+                Some(info) if info.label.as_ref() != Some(label) => {
+                    info.label = Some(label.clone());
+                    summary.labels_updated += 1;
+                }
+                Some(_) => {}
+                None => summary.skipped += 1,
+            }
+        }
+        for (txid, note) in &self.transaction_notes {
+            match data.transactions.iter_mut().find(|tx| &tx.txid == txid) {
+                Some(tx) if tx.memo.as_ref() != Some(note) => {
+                    tx.memo = Some(note.clone());
+                    summary.notes_updated += 1;
+                }
+                Some(_) => {}
+                None => summary.skipped += 1,
+            }
+        }
+        for contact in &self.contacts {
+            if contacts.upsert(contact.clone()) {
+                summary.contacts_updated += 1;
+            }
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_export_round_trips_and_rejects_tampering() {
+        let key = PrivateKey::from_slice(&[7u8; 32], ChainParams::active().address_network).unwrap();
+        let secp = Secp256k1::new();
+        let address = Address::p2wpkh(&CompressedPublicKey::from_private_key(&secp, &key).unwrap(), ChainParams::active().hrp()).to_string();
+
+        let mut export = LabelExport {
+            version: LABEL_EXPORT_VERSION,
+            exported_at: 1_700_000_000,
+            address_labels: BTreeMap::from([(address.clone(), "Savings".to_string())]),
+            transaction_notes: BTreeMap::new(),
+            contacts: vec![Contact { name: "Alice".to_string(), address: "bc1qalice".to_string(), note: None }],
+            signature: None,
+        };
+        export.sign(&key);
+        let contents = serde_json::to_string(&export).unwrap();
+        assert!(LabelExport::parse_verified(&contents, [address.as_str()].into_iter()).is_ok());
+        assert_eq!(
+            LabelExport::parse_verified(&contents, ["bc1qother"].into_iter()).unwrap_err(),
+            LabelError::ForeignWallet
+        );
+
+        let tampered = contents.replace("Savings", "Spending");
+        assert_eq!(
+            LabelExport::parse_verified(&tampered, [address.as_str()].into_iter()).unwrap_err(),
+            LabelError::BadSignature
+        );
+
+        let mut contacts = ContactBook::default();
+        let mut data = WalletData::new("copy", "xpub", false);
+        assert_eq!(export.apply(&mut data, &mut contacts).contacts_updated, 1);
+        assert_eq!(export.apply(&mut data, &mut contacts).contacts_updated, 0);
+    }
+}
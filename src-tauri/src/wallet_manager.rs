@@ -1,29 +1,52 @@
+use crate::chain_params::ChainParams;
 use crate::config::{Config, ConfigManager, WalletInfo};
 use crate::errors::WalletError;
+use crate::file_lock::{FileLock, WALLET_LOCK_FILE};
+use crate::paths::PathProvider;
+use crate::secret::SecretString;
 // Import KeyType and remove unused AddressInfo
-use crate::wallet_data::{WalletData, WalletDataError, KeyPair, KeyType};
+use crate::wallet_data::{WalletData, WalletDataError, WalletProfile, KeyPair, KeyType};
+use crate::wallet_persister::{DirtyWallets, SAVE_CHECK_INTERVAL};
 use log::{debug, error, info, warn};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::ops::{Deref, DerefMut};
+use tokio::sync::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use bip39::Mnemonic;
 use bitcoin::secp256k1::{Secp256k1, PublicKey};
 use bitcoin::bip32::{Xpriv, Xpub, DerivationPath};
-use bitcoin::{Network, CompressedPublicKey, KnownHrp};
+use bitcoin::CompressedPublicKey;
 use std::str::FromStr;
 
+/// Number of seed words the user must re-enter to prove they backed up the seed
+const SEED_CHALLENGE_WORD_COUNT: usize = 3;
+
 /// Wallet type representing an open wallet
 pub struct Wallet {
     pub name: String,
     pub path: PathBuf,
     pub data: WalletData, // Store the loaded wallet data
+    /// Password the wallet was unlocked with, which every save encrypts it with; None if it is not secured
+    secret: Option<SecretString>,
+}
+
+impl Wallet {
+    /// Password to save the wallet with
+    fn password(&self) -> Option<&str> {
+        self.secret.as_ref().map(SecretString::expose_secret)
+    }
 }
 
 /// WalletManager handles all wallet operations
 pub struct WalletManager {
     config: Config,
     config_manager: Option<Arc<ConfigManager>>,
-    current_wallet: Option<Wallet>, // This state is not persisted
+    open_wallets: HashMap<String, Wallet>, // This state is not persisted
+    active_wallet: Option<String>, // Wallet the UI works with when none is named
+    seed_challenges: HashMap<String, Vec<usize>>, // Pending backup verification challenges
+    wallet_locks: HashMap<String, FileLock>, // Held while a wallet is open so other processes cannot write it
+    dirty: Arc<std::sync::Mutex<DirtyWallets>>, // Open wallets with changes the persister has yet to save
 }
 
 impl WalletManager {
@@ -33,10 +56,19 @@ impl WalletManager {
         WalletManager {
             config,
             config_manager: None,
-            current_wallet: None,
+            open_wallets: HashMap::new(),
+            active_wallet: None,
+            seed_challenges: HashMap::new(),
+            wallet_locks: HashMap::new(),
+            dirty: Arc::default(),
         }
     }
 
+    /// Mark an open wallet as changed; the persister saves it shortly, or it is saved on close
+    pub fn mark_dirty(&self, name: &str) {
+        self.dirty.lock().unwrap().mark(name, std::time::Instant::now());
+    }
+
     /// Set the ConfigManager to enable persistence
     pub fn set_config_manager(&mut self, config_manager: Arc<ConfigManager>) {
         debug!("Setting ConfigManager for wallet persistence");
@@ -44,8 +76,14 @@ impl WalletManager {
     }    /// Get a list of available wallets
     pub fn list_wallets(&mut self) -> Vec<&WalletInfo> {
         debug!("Listing available wallets");
+        self.reload_config();
         
-        // If we have a config manager, refresh our config from the file to ensure we have the latest data
+        debug!("Current config has {} wallets", self.config.wallets.len());
+        self.config.wallets.iter().collect()
+    }
+
+    /// Refresh the wallet list from the config file, for changes made without the manager
+    pub fn reload_config(&mut self) {
         if let Some(config_manager) = &self.config_manager {
             let fresh_config = config_manager.get_config();
             if self.config.wallets.len() != fresh_config.wallets.len() {
@@ -55,9 +93,6 @@ impl WalletManager {
             }
             self.config = fresh_config;
         }
-        
-        debug!("Current config has {} wallets", self.config.wallets.len());
-        self.config.wallets.iter().collect()
     }
 
     /// Find a wallet by name
@@ -66,8 +101,14 @@ impl WalletManager {
         self.config.wallets.iter().find(|w| w.name == name)
     }
 
-    /// Open a wallet with the given name and optional password
-    pub fn open_wallet(&mut self, name: &str, password: Option<&str>) -> Result<(), WalletError> {
+    /// Get the directory of a configured wallet, as used for its wallet.dat
+    pub fn get_wallet_dir(&self, name: &str) -> Option<PathBuf> {
+        self.find_wallet_by_name(name).map(|w| PathProvider::resolve_wallet_path(&w.path))
+    }
+
+    /// Open a wallet with the given name and optional password.
+    /// Other open wallets stay open; the newly opened wallet becomes the active one.
+    pub async fn open_wallet(&mut self, name: &str, password: Option<&str>) -> Result<(), WalletError> {
         info!("Attempting to open wallet: {}", name);        // Find the wallet in available wallets and clone it to avoid borrow checker issues
         let wallet_info = self
             .config
@@ -103,26 +144,42 @@ impl WalletManager {
         // Store the path before closing any wallet to avoid borrowing issues
         let wallet_path = wallet_info.path.clone();
 
-        // Close any currently open wallet first
-        if self.current_wallet.is_some() {
-            debug!("Closing previously open wallet before opening new one");
-            self.close_wallet();
+        // Reopening an already open wallet replaces its in-memory state
+        if self.open_wallets.contains_key(name) {
+            debug!("Wallet {} is already open, reloading it", name);
+            self.close_wallet_by_name(name);
         }
 
         // Attempt to load the wallet data file
-        let wallet_dir_path = PathBuf::from(&wallet_path);
+        let wallet_dir_path = PathProvider::resolve_wallet_path(&wallet_path);
         let wallet_data_path = wallet_dir_path.join("wallet.dat");
         
+        // A wallet on read-only or removable media is opened without writing anything to it,
+        // not even the lock file
+        let read_only = crate::storage_media::read_only_reason(&wallet_dir_path);
+        if let Some(reason) = read_only {
+            warn!("Opening wallet {} read-only: {}", name, reason.describe());
+        }
+        
+        // Keep other instances from writing wallet.dat while it is open here
+        let wallet_lock = match read_only {
+            Some(_) => None,
+            None => Some(FileLock::acquire(&wallet_dir_path.join(WALLET_LOCK_FILE), &format!("Wallet '{}'", name))
+                .map_err(|e| {
+                    error!("Failed to lock wallet {}: {}", name, e);
+                    match WalletError::from(e) {
+                        WalletError::InUse(_) => WalletError::InUse(name.to_string()),
+                        other => other,
+                    }
+                })?),
+        };
+        
         debug!("Loading wallet data from: {}", wallet_data_path.display());
         
-        // Use tokio block_in_place since we're in a sync function but need to call sync
-        let wallet_data_result = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current().block_on(async {
-                // Remove .await as WalletData::load is sync
-                WalletData::load(&wallet_data_path, password)
-            })
-        });        // Check if we succeeded in loading wallet data
-        let final_wallet_data = match wallet_data_result {
+        let wallet_data_result = WalletData::load_async(&wallet_data_path, password).await;
+
+        // Check if we succeeded in loading wallet data
+        let mut final_wallet_data = match wallet_data_result {
             Ok(wallet_data) => {
                 debug!("Successfully loaded wallet data for: {}", name);
                 debug!("Wallet balance: {}, addresses: {}", wallet_data.balance, wallet_data.addresses.len());
@@ -168,12 +225,12 @@ impl WalletManager {
                             key_type: KeyType::Legacy,
                             derivation_path: "m/44'/0'/0'/0/0".to_string(),
                             public_key: "dummy_public_key".to_string(),
-                            private_key: if wallet_info.secured { "dummy_encrypted_private_key".to_string() } else { "dummy_private_key".to_string() },
-                        });
+                            private_key: if wallet_info.secured { "dummy_encrypted_private_key".into() } else { "dummy_private_key".into() },
+                        })?;
                         
                         // Save the wallet data
                         let password_option = if wallet_info.secured { password } else { None };
-                        if let Err(save_err) = wallet_data.save(&wallet_data_path, password_option) {
+                        if let Err(save_err) = wallet_data.save_async(&wallet_data_path, password_option).await {
                             error!("Failed to create initial wallet data file: {}", save_err);
                             return Err(WalletError::Generic(format!(
                                 "Failed to create initial wallet data file: {}", save_err
@@ -195,56 +252,141 @@ impl WalletManager {
             }
         };
 
+        final_wallet_data.read_only = read_only;
+
         // Create a wallet object with the loaded data
         let opened_wallet = Wallet {
             name: name.to_string(),
-            path: PathBuf::from(&wallet_path),
+            path: wallet_dir_path,
             data: final_wallet_data,
+            secret: password.filter(|_| wallet_info.secured).map(SecretString::from),
         };
 
-        // Set current wallet in memory only
-        self.current_wallet = Some(opened_wallet);
+        // Keep the wallet in memory only and make it the active one
+        self.open_wallets.insert(name.to_string(), opened_wallet);
+        if let Some(wallet_lock) = wallet_lock {
+            self.wallet_locks.insert(name.to_string(), wallet_lock);
+        }
+        self.active_wallet = Some(name.to_string());
 
         info!("Successfully opened wallet: {}", name);
         Ok(())
     }
 
-    /// Close the currently open wallet
+    /// Close the active wallet
     pub fn close_wallet(&mut self) {
-        if let Some(wallet) = &self.current_wallet {
-            info!("Closing wallet: {}", wallet.name);
-
-            // Perform any necessary cleanup here
+        match self.active_wallet.clone() {
+            Some(name) => self.close_wallet_by_name(&name),
+            None => debug!("No wallet is currently open to close"),
+        }
+    }
 
-            // Clear the current wallet from memory
-            self.current_wallet = None;
+    /// Close an open wallet by name. If it was the active wallet, another open wallet takes its place.
+    pub fn close_wallet_by_name(&mut self, name: &str) {
+        // Clear the wallet from memory, wiping key material first
+        if let Some(mut wallet) = self.open_wallets.remove(name) {
+            info!("Closing wallet: {}", wallet.name);
+            // Changes the persister has not written yet would be lost with the wallet's data
+            if self.dirty.lock().unwrap().take(name) {
+                if let Err(e) = wallet.data.save(&wallet.path.join("wallet.dat"), wallet.password()) {
+                    error!("Failed to save changes to wallet {} on close: {}", name, e);
+                }
+            }
+            wallet.data.clear_sensitive_data();
+            self.seed_challenges.remove(name);
+            self.wallet_locks.remove(name);
             debug!("Wallet closed successfully");
         } else {
-            debug!("No wallet is currently open to close");
+            debug!("Wallet {} is not open", name);
         }
-    }    /// Get the currently open wallet
+
+        if self.active_wallet.as_deref() == Some(name) {
+            self.active_wallet = self.open_wallet_names().into_iter().next();
+        }
+    }
+
+    /// Close every open wallet
+    pub fn close_all_wallets(&mut self) {
+        for name in self.open_wallet_names() {
+            self.close_wallet_by_name(&name);
+        }
+    }
+
+    /// Names of all open wallets, sorted
+    pub fn open_wallet_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.open_wallets.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Check whether a wallet is open
+    pub fn is_wallet_open(&self, name: &str) -> bool {
+        self.open_wallets.contains_key(name)
+    }
+
+    /// Snapshot of the configured and open wallets
+    pub fn status(&self) -> WalletStatus {
+        WalletStatus {
+            wallets: self.config.wallets.clone(),
+            open_wallets: self.open_wallet_names(),
+            active_wallet: self.active_wallet.clone(),
+        }
+    }
+
+    /// Make an open wallet the active one
+    pub fn set_active_wallet(&mut self, name: &str) -> Result<(), WalletError> {
+        if !self.open_wallets.contains_key(name) {
+            return Err(WalletError::InvalidOperation(format!("Wallet '{}' is not open", name)));
+        }
+        info!("Active wallet set to: {}", name);
+        self.active_wallet = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Get an open wallet by name, or the active wallet when no name is given
+    pub fn get_wallet(&self, name: Option<&str>) -> Option<&Wallet> {
+        match name {
+            Some(name) => self.open_wallets.get(name),
+            None => self.get_current_wallet(),
+        }
+    }
+
+    /// Get a mutable reference to an open wallet by name, or the active wallet when no name is given
+    pub fn get_wallet_mut(&mut self, name: Option<&str>) -> Option<&mut Wallet> {
+        match name {
+            Some(name) => self.open_wallets.get_mut(name),
+            None => self.get_current_wallet_mut(),
+        }
+    }
+
+    /// Get the active wallet
     pub fn get_current_wallet(&self) -> Option<&Wallet> {
-        if let Some(wallet) = &self.current_wallet {
+        let wallet = self.active_wallet.as_ref().and_then(|name| self.open_wallets.get(name));
+        if let Some(wallet) = wallet {
             debug!("Retrieved current wallet: {}", wallet.name);
         } else {
             debug!("No wallet is currently open");
         }
-        self.current_wallet.as_ref()
+        wallet
     }
 
-    /// Get a mutable reference to the currently open wallet
+    /// Get a mutable reference to the active wallet
     pub fn get_current_wallet_mut(&mut self) -> Option<&mut Wallet> {
-        if let Some(wallet) = &self.current_wallet {
+        let wallet = match &self.active_wallet {
+            Some(name) => self.open_wallets.get_mut(name),
+            None => None,
+        };
+        if let Some(wallet) = &wallet {
             debug!("Retrieved mutable current wallet: {}", wallet.name);
         } else {
             debug!("No wallet is currently open");
         }
-        self.current_wallet.as_mut()
+        wallet
     }
 
     /// Update the current wallet's data
     pub fn update_current_wallet_data(&mut self, new_data: WalletData) -> Result<(), WalletError> {
-        if let Some(wallet) = &mut self.current_wallet {
+        if let Some(wallet) = self.get_current_wallet_mut() {
             info!("Updating wallet data for: {}", wallet.name);
             wallet.data = new_data;
             Ok(())
@@ -253,50 +395,17 @@ impl WalletManager {
         }
     }/// Get the base directory for wallets
     pub fn get_wallets_dir(&self) -> PathBuf {
-        // Determine the wallets directory based on the platform
-        // First try to get it from the app configuration
-        if let Some(config_manager) = &self.config_manager {
-            // Try to get the instance config dir first (synchronous method)
-            if let Ok(config_dir) = config_manager.get_config_dir_path() {
-                // Go up one level from config directory and join with "wallets"
-                let wallets_dir = config_dir.parent()
-                    .unwrap_or(&config_dir) // Fallback to config_dir if parent doesn't exist
-                    .join("wallets");
-                
-                debug!("Using wallets directory from config: {}", wallets_dir.display());
-                return wallets_dir;
-            }
-            
-            // Fall back to the static async method if needed
-            if let Ok(config_dir) = tokio::task::block_in_place(|| {
-                tokio::runtime::Handle::current().block_on(async {
-                    ConfigManager::get_config_dir().await
-                })
-            }) {
-                // Go up one level from config directory and join with "wallets"
-                let wallets_dir = config_dir.parent()
-                    .unwrap_or(&config_dir) // Fallback to config_dir if parent doesn't exist
-                    .join("wallets");
-                
-                debug!("Using wallets directory from static config: {}", wallets_dir.display());
-                return wallets_dir;
-            }
-        }
-        
-        // Fallback to a default directory
-        let default_dir = dirs::data_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("com.b-rad-coin.app")
-            .join("wallets");
-        
-        debug!("Using default wallets directory: {}", default_dir.display());
-        default_dir
+        // All data paths are resolved through the PathProvider so a data directory override is honoured
+        let wallets_dir = PathProvider::wallets_dir().unwrap_or_else(|| PathBuf::from("wallets"));
+
+        debug!("Using wallets directory: {}", wallets_dir.display());
+        wallets_dir
     }
 
     /// Create a new wallet
     /// NOTE: This function creates a basic wallet structure without seed phrase or master keys.
     /// Use create_wallet_with_seed for a more complete wallet.
-    pub fn create_wallet(&mut self, name: &str, password: &str) -> Result<(), WalletError> {
+    pub async fn create_wallet(&mut self, name: &str, password: &str) -> Result<(), WalletError> {
         info!("Attempting to create new wallet: {}", name);
 
         // Check if wallet with this name already exists
@@ -313,7 +422,7 @@ impl WalletManager {
         debug!("Creating wallet with path: {}", wallet_path);
 
         // Create wallet directory if it doesn't exist
-        let wallet_dir_path = PathBuf::from(&wallet_path);
+        let wallet_dir_path = PathProvider::resolve_wallet_path(&wallet_path);
         if let Err(e) = std::fs::create_dir_all(&wallet_dir_path) {
             error!("Failed to create wallet directory: {}", e);
             return Err(WalletError::Generic(format!(
@@ -331,8 +440,7 @@ impl WalletManager {
         let wallet_data_path = wallet_dir_path.join("wallet.dat");
         let password_option = if is_secured { Some(password) } else { None };
 
-        // Call save (it's synchronous)
-        if let Err(e) = wallet_data.save(&wallet_data_path, password_option) {
+        if let Err(e) = wallet_data.save_async(&wallet_data_path, password_option).await {
              error!("Failed to write wallet data to disk: {}", e);
              return Err(WalletError::Generic(format!(
                  "Failed to write wallet data to disk: {}",
@@ -360,11 +468,7 @@ impl WalletManager {
 
         // Persist to disk if we have a ConfigManager
         if let Some(config_manager) = &self.config_manager {
-            // Use tokio block_in_place since we're in a sync function but need to call async
-            match tokio::task::block_in_place(|| {
-                tokio::runtime::Handle::current()
-                    .block_on(async { config_manager.add_wallet(wallet_info).await })
-            }) {
+            match config_manager.add_wallet(wallet_info).await {
                 Ok(_) => {
                     info!("Wallet configuration persisted to disk: {}", name);
                 }
@@ -380,7 +484,7 @@ impl WalletManager {
         // Automatically open the newly created wallet
         info!("Opening newly created wallet: {}", name);
         let password_option = if is_secured { Some(password) } else { None };
-        match self.open_wallet(name, password_option) {
+        match self.open_wallet(name, password_option).await {
             Ok(_) => {
                 info!("Newly created wallet opened successfully: {}", name);
             }
@@ -393,8 +497,8 @@ impl WalletManager {
         info!("Successfully created wallet: {}", name);
         Ok(())
     }    /// Create a wallet with a seed phrase
-    // Make this function sync as save is sync
-    pub fn create_wallet_with_seed(&mut self, name: &str, password: &str, seed_phrase: &str, is_secured: bool) -> Result<(), WalletError> {
+    /// An optional BIP39 passphrase is mixed into the seed; only its master key fingerprint is stored.
+    pub async fn create_wallet_with_seed(&mut self, name: &str, password: &str, seed_phrase: &str, passphrase: Option<&str>, is_secured: bool) -> Result<(), WalletError> {
         info!("Attempting to create new wallet with seed phrase: {}", name);
 
         // Check if wallet with this name already exists
@@ -408,7 +512,7 @@ impl WalletManager {
         debug!("Creating wallet with path: {}", wallet_path);
 
         // Create wallet directory if it doesn't exist
-        let wallet_dir_path = PathBuf::from(&wallet_path);
+        let wallet_dir_path = PathProvider::resolve_wallet_path(&wallet_path);
         if let Err(e) = std::fs::create_dir_all(&wallet_dir_path) {
             error!("Failed to create wallet directory: {}", e);
             return Err(WalletError::Generic(format!(
@@ -419,16 +523,7 @@ impl WalletManager {
         }
 
         // Generate keys from the seed phrase
-        let (master_public_key, master_private_key, key_pair) = self.derive_keys_from_seed(seed_phrase, name)?;
-
-        // Create new WalletData object
-        let mut wallet_data = WalletData::new(name, &master_public_key, is_secured);
-        
-        // Set the seed phrase and master private key
-        wallet_data.set_sensitive_data(seed_phrase, &master_private_key);
-
-        // Add the derived key pair
-        wallet_data.add_key_pair(key_pair);
+        let wallet_data = Self::wallet_data_from_seed(name, seed_phrase, passphrase.unwrap_or(""), is_secured)?;
         
         // Save the wallet data to disk
         let wallet_data_path = wallet_dir_path.join("wallet.dat");
@@ -436,8 +531,7 @@ impl WalletManager {
         // Password is only used if the wallet is secured
         let password_option = if is_secured { Some(password) } else { None };
         
-        // Remove .await as save is sync
-        match wallet_data.save(&wallet_data_path, password_option) {
+        match wallet_data.save_async(&wallet_data_path, password_option).await {
             Ok(_) => {
                 debug!("Wallet data saved to disk: {}", wallet_data_path.display());
             },
@@ -469,11 +563,7 @@ impl WalletManager {
 
         // Persist to configuration if we have a ConfigManager
         if let Some(config_manager) = &self.config_manager {
-            // Use tokio block_in_place since we're in a sync function but need to call async
-            match tokio::task::block_in_place(|| {
-                tokio::runtime::Handle::current()
-                    .block_on(config_manager.add_wallet(wallet_info))
-            }) {
+            match config_manager.add_wallet(wallet_info).await {
                 Ok(_) => {
                     info!("Wallet configuration persisted to disk: {}", name);
                 }
@@ -489,7 +579,7 @@ impl WalletManager {
         // Automatically open the newly created wallet
         info!("Opening newly created wallet: {}", name);
         let password_option = if is_secured { Some(password) } else { None };
-        match self.open_wallet(name, password_option) {
+        match self.open_wallet(name, password_option).await {
             Ok(_) => {
                 info!("Newly created wallet opened successfully: {}", name);
             }
@@ -503,8 +593,89 @@ impl WalletManager {
         Ok(())
     }
 
+    /// Build the data of a new wallet from a seed phrase and optional BIP39 passphrase
+    pub(crate) fn wallet_data_from_seed(name: &str, seed_phrase: &str, passphrase: &str, is_secured: bool) -> Result<WalletData, WalletError> {
+        let (master_public_key, master_private_key, key_pair) = Self::derive_keys_from_seed(seed_phrase, passphrase, name)?;
+
+        // Create new WalletData object
+        let mut wallet_data = WalletData::new(name, &master_public_key, is_secured);
+        
+        // Set the seed phrase and master private key
+        wallet_data.set_sensitive_data(seed_phrase, master_private_key.expose_secret());
+        
+        // Remember the fingerprint so a mistyped passphrase can be detected later
+        wallet_data.master_fingerprint = Some(Self::master_fingerprint(seed_phrase, passphrase)?);
+        wallet_data.has_passphrase = !passphrase.is_empty();
+
+        // Add the derived key pair
+        wallet_data.add_key_pair(key_pair)?;
+        Ok(wallet_data)
+    }
+
+    /// Give a secured wallet a decoy profile, opened by `duress_password`, holding a separate
+    /// wallet derived from `decoy_seed_phrase`. Replaces any decoy the wallet already has.
+    pub fn set_duress_profile(&mut self, name: &str, password: &str, duress_password: &str, decoy_seed_phrase: &str) -> Result<(), WalletError> {
+        let wallet_data_path = self.main_profile_path(name, password)?;
+        if duress_password.is_empty() || duress_password == password {
+            return Err(WalletError::Generic("The duress password must differ from the wallet password".to_string()));
+        }
+
+        let mut decoy = Self::wallet_data_from_seed(name, decoy_seed_phrase, "", true)?;
+        decoy.profile = WalletProfile::Decoy;
+        decoy.save(&wallet_data_path, Some(duress_password))?;
+        Ok(())
+    }
+
+    /// Remove the decoy profile of a secured wallet
+    pub fn remove_duress_profile(&mut self, name: &str, password: &str) -> Result<(), WalletError> {
+        let wallet_data_path = self.main_profile_path(name, password)?;
+        WalletData::remove_decoy(&wallet_data_path)?;
+        Ok(())
+    }
+
+    /// Check the password of a secured wallet. While the wallet is open the password must open the
+    /// profile in use, so a decoy session cannot be confirmed with the main password or the other way round.
+    pub fn verify_password(&self, name: &str, password: &str) -> Result<(), WalletError> {
+        let wallet_info = self.find_wallet_by_name(name).ok_or_else(|| WalletError::NotFound(name.to_string()))?;
+        if !wallet_info.secured {
+            return Err(WalletError::InvalidOperation(format!("Wallet '{}' has no password", name)));
+        }
+        let wallet_dir = PathProvider::resolve_wallet_path(&wallet_info.path);
+        let data = WalletData::load(&wallet_dir.join("wallet.dat"), Some(password))?;
+        let expected = self.open_wallets.get(name).map_or(WalletProfile::Main, |wallet| wallet.data.profile);
+        if data.profile != expected {
+            return Err(WalletError::InvalidPassword);
+        }
+        Ok(())
+    }
+
+    /// Path of a secured wallet's wallet.dat, after checking that `password` opens its main profile
+    fn main_profile_path(&self, name: &str, password: &str) -> Result<PathBuf, WalletError> {
+        let wallet_info = self.find_wallet_by_name(name).ok_or_else(|| WalletError::NotFound(name.to_string()))?;
+        if !wallet_info.secured {
+            return Err(WalletError::Generic(format!("Wallet '{}' must be secured with a password first", name)));
+        }
+        let wallet_dir = PathProvider::resolve_wallet_path(&wallet_info.path);
+        Self::ensure_writable(&wallet_dir)?;
+        let wallet_data_path = wallet_dir.join("wallet.dat");
+
+        let main = WalletData::load(&wallet_data_path, Some(password))?;
+        if main.profile != WalletProfile::Main {
+            return Err(WalletError::InvalidPassword);
+        }
+        Ok(wallet_data_path)
+    }
+
+    /// Refuse to rewrite a wallet whose directory is on read-only or removable media
+    fn ensure_writable(wallet_dir: &std::path::Path) -> Result<(), WalletError> {
+        match crate::storage_media::read_only_reason(wallet_dir) {
+            Some(reason) => Err(WalletDataError::ReadOnly(reason).into()),
+            None => Ok(()),
+        }
+    }
+
     /// Derive keys from a real seed phrase using BIP39/BIP32 standards
-    fn derive_keys_from_seed(&self, seed_phrase: &str, name: &str) -> Result<(String, String, KeyPair), WalletError> {
+    pub(crate) fn derive_keys_from_seed(seed_phrase: &str, passphrase: &str, name: &str) -> Result<(String, SecretString, KeyPair), WalletError> {
         use bitcoin::{Address, PrivateKey};
         
         info!("Deriving keys from seed phrase for wallet: {} using BIP39/BIP32 standards", name);
@@ -513,14 +684,14 @@ impl WalletManager {
         let mnemonic = Mnemonic::from_str(seed_phrase)
             .map_err(|e| WalletError::KeyDerivationError(format!("Invalid mnemonic: {}", e)))?;
         
-        // Generate seed from mnemonic (this creates the root seed)
-        let seed = mnemonic.to_seed("");
+        // Generate seed from mnemonic and optional passphrase (this creates the root seed)
+        let seed = mnemonic.to_seed(passphrase);
         
         // Initialize secp256k1 context
         let secp = Secp256k1::new();
         
         // Create master extended private key from seed
-        let master_xpriv = Xpriv::new_master(Network::Bitcoin, &seed)
+        let master_xpriv = Xpriv::new_master(ChainParams::active().address_network, &seed)
             .map_err(|e| WalletError::KeyDerivationError(format!("Failed to create master key: {}", e)))?;
         
         // Create master extended public key
@@ -542,17 +713,17 @@ impl WalletManager {
         
         // Generate Bitcoin address (using P2WPKH - native segwit)
         // Convert SecretKey to PrivateKey for address generation
-        let bitcoin_private_key = PrivateKey::new(private_key, Network::Bitcoin);
+        let bitcoin_private_key = PrivateKey::new(private_key, ChainParams::active().address_network);
         
         // Convert to compressed public key for address generation
         let compressed_pubkey = CompressedPublicKey::from_private_key(&secp, &bitcoin_private_key)
             .map_err(|e| WalletError::KeyDerivationError(format!("Failed to create compressed public key: {}", e)))?;
         
         // Generate P2WPKH address
-        let address = Address::p2wpkh(&compressed_pubkey, KnownHrp::Mainnet);
+        let address = Address::p2wpkh(&compressed_pubkey, ChainParams::active().hrp());
         
         // Format keys as strings
-        let master_private_key = master_xpriv.to_string();
+        let master_private_key = SecretString::new(master_xpriv.to_string());
         let master_public_key = master_xpub.to_string();
         let private_key_hex = hex::encode(private_key.secret_bytes());
         let public_key_hex = hex::encode(public_key.serialize());
@@ -562,15 +733,28 @@ impl WalletManager {
             key_type: KeyType::NativeSegWit,
             derivation_path: derivation_path.to_string(),
             public_key: public_key_hex,
-            private_key: private_key_hex,
+            private_key: SecretString::new(private_key_hex),
         };
         
         info!("Successfully derived keys using BIP39/BIP32 for wallet: {}", name);
         Ok((master_public_key, master_private_key, key_pair))
     }
 
+    /// Compute the BIP32 master key fingerprint for a seed phrase and passphrase
+    pub fn master_fingerprint(seed_phrase: &str, passphrase: &str) -> Result<String, WalletError> {
+        let mnemonic = Mnemonic::from_str(seed_phrase)
+            .map_err(|e| WalletError::KeyDerivationError(format!("Invalid mnemonic: {}", e)))?;
+        let seed = zeroize::Zeroizing::new(mnemonic.to_seed(passphrase));
+        
+        let secp = Secp256k1::new();
+        let master_xpriv = Xpriv::new_master(ChainParams::active().address_network, seed.as_ref())
+            .map_err(|e| WalletError::KeyDerivationError(format!("Failed to create master key: {}", e)))?;
+        
+        Ok(master_xpriv.fingerprint(&secp).to_string())
+    }
+
     /// Update a wallet to be secured with a password
-    pub fn secure_wallet(&mut self, name: &str, password: &str) -> Result<(), WalletError> {
+    pub async fn secure_wallet(&mut self, name: &str, password: &str) -> Result<(), WalletError> {
         info!("Attempting to secure wallet: {}", name);
 
         // Validate input
@@ -592,6 +776,7 @@ impl WalletManager {
                         name
                     )));
                 }
+                Self::ensure_writable(&PathProvider::resolve_wallet_path(&self.config.wallets[index].path))?;
 
                 // Update the wallet to be secured in memory
                 debug!("Updating wallet '{}' to be secured", name);
@@ -599,13 +784,7 @@ impl WalletManager {
 
                 // Persist changes to disk if we have a ConfigManager
                 if let Some(config_manager) = &self.config_manager {
-                    // Use tokio block_in_place since we're in a sync function but need to call async
-                    match tokio::task::block_in_place(|| {
-                        tokio::runtime::Handle::current().block_on(async {
-                            // Use the new update_wallet_security method
-                            config_manager.update_wallet_security(name, true).await
-                        })
-                    }) {
+                    match config_manager.update_wallet_security(name, true).await {
                         Ok(_) => {
                             info!("Updated wallet security status persisted to disk: {}", name);
                         }
@@ -620,14 +799,19 @@ impl WalletManager {
 
                 // Actually encrypt the wallet data with the password
                 // Load the current wallet data, encrypt it, and save it back
-                let wallet_path = std::path::PathBuf::from(format!("wallets/{}/wallet.dat", name));
-                match WalletData::load(&wallet_path, None) {
+                let wallet_path = PathProvider::resolve_wallet_path(&self.config.wallets[index].path).join("wallet.dat");
+                match WalletData::load_async(&wallet_path, None).await {
                     Ok(mut wallet_data) => {
                         // Set the wallet as encrypted and save with the password
                         wallet_data.is_encrypted = true;
-                        match wallet_data.save(&wallet_path, Some(password)) {
+                        match wallet_data.save_async(&wallet_path, Some(password)).await {
                             Ok(_) => {
                                 info!("Wallet data encrypted and saved with password for: {}", name);
+                                // If the wallet is open, its later saves must be encrypted too
+                                if let Some(wallet) = self.open_wallets.get_mut(name) {
+                                    wallet.data.is_encrypted = true;
+                                    wallet.secret = Some(SecretString::from(password));
+                                }
                             }
                             Err(e) => {
                                 error!("Failed to encrypt wallet data: {}", e);
@@ -661,9 +845,92 @@ impl WalletManager {
         }
     }
 
+    /// Pick random seed word positions (1-based) the user must re-enter for the given wallet.
+    /// The wallet must be open, since its seed phrase is needed to check answers.
+    pub fn create_seed_challenge(&mut self, name: &str) -> Result<Vec<usize>, WalletError> {
+        let wallet = self.open_wallets.get(name).ok_or_else(|| {
+            WalletError::InvalidOperation(format!(
+                "Wallet '{}' must be open to verify its seed phrase",
+                name
+            ))
+        })?;
+
+        let word_count = match &wallet.data.seed_phrase {
+            Some(seed_phrase) => seed_phrase.expose_secret().split_whitespace().count(),
+            None => {
+                return Err(WalletError::InvalidOperation(
+                    "Wallet has no seed phrase to verify".to_string(),
+                ))
+            }
+        };
+
+        let mut positions: Vec<usize> = rand::seq::index::sample(
+            &mut rand::rng(),
+            word_count,
+            SEED_CHALLENGE_WORD_COUNT.min(word_count),
+        )
+        .into_iter()
+        .map(|i| i + 1)
+        .collect();
+        positions.sort_unstable();
+
+        debug!("Created seed verification challenge for wallet: {}", name);
+        self.seed_challenges.insert(name.to_string(), positions.clone());
+        Ok(positions)
+    }
+
+    /// Check answers to the pending seed challenge of an open wallet (the active one by default).
+    /// On success the wallet is marked as backup verified and saved; either way the challenge is consumed.
+    pub fn verify_seed_challenge(&mut self, name: Option<&str>, answers: &HashMap<usize, String>, password: Option<&str>) -> Result<bool, WalletError> {
+        let name = name.map(str::to_string).or_else(|| self.active_wallet.clone());
+        let wallet = name
+            .and_then(|name| self.open_wallets.get_mut(&name))
+            .ok_or(WalletError::NoWalletOpen)?;
+
+        let positions = self.seed_challenges.remove(&wallet.name).ok_or_else(|| {
+            WalletError::InvalidOperation("No seed verification challenge is pending".to_string())
+        })?;
+
+        let seed_phrase = wallet.data.seed_phrase.as_ref().ok_or_else(|| {
+            WalletError::InvalidOperation("Wallet has no seed phrase to verify".to_string())
+        })?;
+        let words: Vec<&str> = seed_phrase.expose_secret().split_whitespace().collect();
+
+        // A position past the end of the phrase (it changed since the challenge) fails the check
+        let all_correct = positions.iter().all(|position| {
+            let word = position.checked_sub(1).and_then(|index| words.get(index));
+            match (answers.get(position), word) {
+                (Some(answer), Some(word)) => answer.trim().eq_ignore_ascii_case(word),
+                _ => false,
+            }
+        });
+
+        if !all_correct {
+            warn!("Seed verification failed for wallet: {}", wallet.name);
+            return Ok(false);
+        }
+
+        wallet.data.backup_verified = true;
+        wallet.data.modified_at = chrono::Utc::now().timestamp();
+
+        let password_option = if wallet.data.is_encrypted { password } else { None };
+        wallet.data.save(&wallet.path.join("wallet.dat"), password_option)?;
+
+        info!("Seed backup verified for wallet: {}", wallet.name);
+        Ok(true)
+    }
+
+    /// Drop the pending seed challenge of a wallet, as when its seed phrase is replaced and the
+    /// challenge would ask about words of the old one
+    pub fn cancel_seed_challenge(&mut self, name: &str) {
+        if self.seed_challenges.remove(name).is_some() {
+            debug!("Cancelled seed verification challenge for wallet: {}", name);
+        }
+    }
+
     /// Get current wallet security status
     pub fn is_current_wallet_secured(&self) -> Option<bool> {
-        if let Some(wallet) = &self.current_wallet {
+        if let Some(wallet) = self.get_current_wallet() {
             // Find the wallet in config to get its secured status
             if let Some(wallet_info) = self.config.wallets.iter().find(|w| w.name == wallet.name) {
                 return Some(wallet_info.secured);
@@ -676,13 +943,124 @@ impl WalletManager {
     pub fn shutdown(&mut self) -> Result<(), WalletError> {
         info!("Shutting down wallet manager");
 
-        // Close any open wallet
-        if self.current_wallet.is_some() {
-            self.close_wallet();
-        }        debug!("Wallet manager shutdown complete");
+        // Close all open wallets
+        self.close_all_wallets();
+        debug!("Wallet manager shutdown complete");
         Ok(())
     }
 
+    /// Rename a wallet: moves its directory, updates the config entry and any open wallet state
+    pub async fn rename_wallet(&mut self, old_name: &str, new_name: &str) -> Result<(), WalletError> {
+        info!("Renaming wallet '{}' to '{}'", old_name, new_name);
+
+        if let Some(reason) = self.open_wallets.get(old_name).and_then(|wallet| wallet.data.read_only) {
+            return Err(WalletDataError::ReadOnly(reason).into());
+        }
+
+        let new_name = new_name.trim();
+        if new_name.is_empty()
+            || new_name.contains(['/', '\\'])
+            || new_name == "."
+            || new_name == ".."
+        {
+            return Err(WalletError::InvalidOperation(format!(
+                "'{}' is not a valid wallet name",
+                new_name
+            )));
+        }
+        if new_name == old_name {
+            return Ok(());
+        }
+        if self.config.wallets.iter().any(|w| w.name == new_name) {
+            return Err(WalletError::AlreadyExists(new_name.to_string()));
+        }
+
+        let config_manager = self.config_manager.clone().ok_or_else(|| {
+            error!("No config manager available");
+            WalletError::Generic("No config manager available".to_string())
+        })?;
+
+        let mut updated_config = config_manager.get_config();
+        let wallet_info = updated_config
+            .wallets
+            .iter_mut()
+            .find(|w| w.name == old_name)
+            .ok_or_else(|| WalletError::NotFound(old_name.to_string()))?;
+
+        // The wallet directory is named after the wallet, so it moves along with the name
+        let old_dir = PathProvider::resolve_wallet_path(&wallet_info.path);
+        let new_dir = old_dir.with_file_name(new_name);
+        if new_dir.exists() {
+            error!("Target wallet directory already exists: {}", new_dir.display());
+            return Err(WalletError::AlreadyExists(new_name.to_string()));
+        }
+
+        // Windows cannot move a directory with an open lock file; it is retaken at the new path below
+        let had_lock = self.wallet_locks.remove(old_name).is_some();
+
+        if old_dir.exists() {
+            if let Err(e) = std::fs::rename(&old_dir, &new_dir) {
+                error!("Failed to move wallet directory: {}", e);
+                if had_lock {
+                    self.relock_wallet(old_name, &old_dir);
+                }
+                return Err(WalletError::Generic(format!("Failed to move wallet directory: {}", e)));
+            }
+            debug!("Moved wallet directory {} -> {}", old_dir.display(), new_dir.display());
+        } else {
+            warn!("Wallet directory {} does not exist, only renaming in config", old_dir.display());
+        }
+
+        wallet_info.name = new_name.to_string();
+        wallet_info.path = new_dir.to_string_lossy().to_string();
+
+        if let Err(e) = config_manager.update_config(updated_config.clone()).await {
+            error!("Failed to update configuration, restoring wallet directory: {}", e);
+            if new_dir.exists() {
+                let _ = std::fs::rename(&new_dir, &old_dir);
+            }
+            if had_lock {
+                self.relock_wallet(old_name, &old_dir);
+            }
+            return Err(WalletError::ConfigError(format!(
+                "Failed to update configuration: {}",
+                e
+            )));
+        }
+        self.config = updated_config;
+
+        // Keep an open wallet open under its new name
+        self.dirty.lock().unwrap().rename(old_name, new_name);
+        if let Some(mut wallet) = self.open_wallets.remove(old_name) {
+            wallet.name = new_name.to_string();
+            wallet.path = new_dir.clone();
+            wallet.data.name = new_name.to_string();
+            self.open_wallets.insert(new_name.to_string(), wallet);
+        }
+        if had_lock {
+            self.relock_wallet(new_name, &new_dir);
+        }
+        if self.active_wallet.as_deref() == Some(old_name) {
+            self.active_wallet = Some(new_name.to_string());
+        }
+        if let Some(challenge) = self.seed_challenges.remove(old_name) {
+            self.seed_challenges.insert(new_name.to_string(), challenge);
+        }
+
+        info!("Wallet '{}' renamed to '{}'", old_name, new_name);
+        Ok(())
+    }
+
+    /// Take the lock of an open wallet again after its directory moved
+    fn relock_wallet(&mut self, name: &str, wallet_dir: &std::path::Path) {
+        match FileLock::acquire(&wallet_dir.join(WALLET_LOCK_FILE), &format!("Wallet '{}'", name)) {
+            Ok(lock) => {
+                self.wallet_locks.insert(name.to_string(), lock);
+            }
+            Err(e) => warn!("Failed to relock wallet {}: {}", name, e),
+        }
+    }
+
     /// Remove a wallet from configuration
     pub async fn remove_wallet_from_config(&mut self, wallet_name: &str) -> Result<(), WalletError> {
         if let Some(config_manager) = &self.config_manager {
@@ -728,45 +1106,295 @@ impl WalletManager {
     }
 }
 
-/// Async wrapper for WalletManager to be used with Tauri state
+/// What the wallet status commands need, copied out of the manager whenever a change to it is
+/// released
+#[derive(Debug, Clone, Default)]
+pub struct WalletStatus {
+    /// Configured wallets
+    pub wallets: Vec<WalletInfo>,
+    /// Open wallets, sorted
+    pub open_wallets: Vec<String>,
+    pub active_wallet: Option<String>,
+}
+
+impl WalletStatus {
+    /// Configuration of a wallet by name
+    pub fn wallet(&self, name: &str) -> Option<&WalletInfo> {
+        self.wallets.iter().find(|wallet| wallet.name == name)
+    }
+}
+
+/// Exclusive access to the wallet manager; the status snapshot is refreshed when it is dropped
+pub struct WalletManagerGuard<'a> {
+    manager: RwLockWriteGuard<'a, WalletManager>,
+    status: &'a std::sync::RwLock<Arc<WalletStatus>>,
+}
+
+impl Deref for WalletManagerGuard<'_> {
+    type Target = WalletManager;
+
+    fn deref(&self) -> &WalletManager {
+        &self.manager
+    }
+}
+
+impl DerefMut for WalletManagerGuard<'_> {
+    fn deref_mut(&mut self) -> &mut WalletManager {
+        &mut self.manager
+    }
+}
+
+impl Drop for WalletManagerGuard<'_> {
+    fn drop(&mut self) {
+        *self.status.write().unwrap() = Arc::new(self.manager.status());
+    }
+}
+
+/// Async wrapper for WalletManager to be used with Tauri state.
+///
+/// The state is split so that reads do not queue behind slow writes:
+/// - the manager itself sits behind a read-write lock, so commands that only look at it share it;
+/// - a snapshot of which wallets exist and are open is refreshed after every change and read
+///   without waiting at all, which is what the UI polls;
+/// - writing a wallet file happens after the manager is released, under a lock of that wallet
+///   alone, so a slow disk only holds up further saves of the same wallet.
+///
+/// Clones share all of it.
 #[derive(Clone)]
 pub struct AsyncWalletManager {
-    inner: Arc<Mutex<WalletManager>>,
+    inner: Arc<RwLock<WalletManager>>,
+    status: Arc<std::sync::RwLock<Arc<WalletStatus>>>,
+    data_locks: Arc<std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>>,
+    /// Shared with the `WalletManager`, so the persister finds dirty wallets without its lock
+    dirty: Arc<std::sync::Mutex<DirtyWallets>>,
 }
 
 impl AsyncWalletManager {
     /// Create a new AsyncWalletManager
     pub fn new(wallet_manager: WalletManager) -> Self {
         AsyncWalletManager {
-            inner: Arc::new(Mutex::new(wallet_manager)),
+            status: Arc::new(std::sync::RwLock::new(Arc::new(wallet_manager.status()))),
+            dirty: wallet_manager.dirty.clone(),
+            inner: Arc::new(RwLock::new(wallet_manager)),
+            data_locks: Arc::default(),
+        }
+    }
+
+    /// Save dirty wallets in the background as they fall due
+    pub fn start_persister(&self) {
+        let wallet_manager = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut interval = tokio::time::interval(SAVE_CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                let due = wallet_manager.dirty.lock().unwrap().due(std::time::Instant::now());
+                for name in due {
+                    wallet_manager.save_if_dirty(&name).await;
+                }
+            }
+        });
+    }
+
+    /// Mark an open wallet as changed; see `WalletManager::mark_dirty`
+    pub fn mark_dirty(&self, name: &str) {
+        self.dirty.lock().unwrap().mark(name, std::time::Instant::now());
+    }
+
+    /// Save every dirty wallet now
+    pub async fn save_dirty_wallets(&self) {
+        let names = self.dirty.lock().unwrap().names();
+        for name in names {
+            self.save_if_dirty(&name).await;
+        }
+    }
+
+    /// Save a wallet if it is still dirty. The mark is cleared and the data copied under the
+    /// manager lock, so a wallet closing meanwhile is saved by exactly one of the two.
+    async fn save_if_dirty(&self, name: &str) {
+        let data_lock = self.data_locks.lock().unwrap().entry(name.to_string()).or_default().clone();
+        let _writing = data_lock.lock().await;
+
+        let save = {
+            let manager = self.read_manager().await;
+            if !self.dirty.lock().unwrap().take(name) {
+                return;
+            }
+            manager.get_wallet(Some(name)).map(|wallet| (wallet.data.clone(), wallet.path.join("wallet.dat"), wallet.secret.clone()))
+        };
+        let Some((data, wallet_data_path, secret)) = save else {
+            return;
+        };
+        match data.save_async(&wallet_data_path, secret.as_ref().map(SecretString::expose_secret)).await {
+            Ok(()) => debug!("Saved changes to wallet {}", name),
+            Err(e) => error!("Failed to save changes to wallet {}: {}", name, e),
         }
     }
 
     /// Set the ConfigManager for persistence
     pub async fn set_config_manager(&self, config_manager: Arc<ConfigManager>) {
-        let mut manager = self.inner.lock().await;
+        let mut manager = self.get_manager().await;
         manager.set_config_manager(config_manager);
     }
 
-    /// Get a reference to the inner wallet manager
-    pub async fn get_manager(&self) -> tokio::sync::MutexGuard<'_, WalletManager> {
-        self.inner.lock().await
+    /// Get the inner wallet manager for changes
+    pub async fn get_manager(&self) -> WalletManagerGuard<'_> {
+        WalletManagerGuard { manager: self.inner.write().await, status: &self.status }
+    }
+
+    /// Get the inner wallet manager for reading, shared with other readers
+    pub async fn read_manager(&self) -> RwLockReadGuard<'_, WalletManager> {
+        self.inner.read().await
+    }
+
+    /// Which wallets exist and are open, as of the last change; never waits for the manager
+    pub fn status(&self) -> Arc<WalletStatus> {
+        self.status.read().unwrap().clone()
     }
 
     /// Shutdown the wallet manager safely
     pub async fn shutdown(&self) -> Result<(), WalletError> {
-        let mut manager = self.inner.lock().await;
+        // Written off the runtime here; closing would otherwise save them on the spot
+        self.save_dirty_wallets().await;
+        let mut manager = self.get_manager().await;
         manager.shutdown()
-    }    /// Create a wallet with a seed phrase
-    pub async fn create_wallet_with_seed(&self, name: &str, password: &str, seed_phrase: &str, is_secured: bool) -> Result<(), WalletError> {
-        let mut manager = self.inner.lock().await;
-        // Call the synchronous version
-        manager.create_wallet_with_seed(name, password, seed_phrase, is_secured)
+    }
+
+    /// Open a wallet; it becomes the active one
+    pub async fn open_wallet(&self, name: &str, password: Option<&str>) -> Result<(), WalletError> {
+        self.get_manager().await.open_wallet(name, password).await
+    }
+
+    /// Close an open wallet by name
+    pub async fn close_wallet(&self, name: &str) {
+        self.get_manager().await.close_wallet_by_name(name);
+    }
+
+    /// Names of all open wallets, sorted
+    pub fn open_wallet_names(&self) -> Vec<String> {
+        self.status().open_wallets.clone()
+    }
+
+    /// Check whether a wallet is open
+    pub fn is_wallet_open(&self, name: &str) -> bool {
+        self.status().open_wallets.iter().any(|open| open == name)
+    }
+
+    /// Name of the active wallet, if any is open
+    pub fn current_wallet_name(&self) -> Option<String> {
+        self.status().active_wallet.clone()
+    }
+
+    /// Configuration of a wallet by name
+    pub fn find_wallet_by_name(&self, name: &str) -> Option<WalletInfo> {
+        self.status().wallet(name).cloned()
+    }
+
+    /// Write an open wallet's data to its wallet.dat, encrypted with the password it was unlocked
+    /// with. The data is copied out and the manager released before the write, and saves of one
+    /// wallet run one at a time in the order they copied the data, so the file always ends up with
+    /// the latest changes.
+    pub async fn save_wallet(&self, name: &str) -> Result<(), WalletError> {
+        let data_lock = self.data_locks.lock().unwrap().entry(name.to_string()).or_default().clone();
+        let _writing = data_lock.lock().await;
+
+        let (data, wallet_data_path, secret) = {
+            let manager = self.read_manager().await;
+            let wallet = manager.get_wallet(Some(name)).ok_or_else(|| WalletError::NotFound(name.to_string()))?;
+            // This save carries every change so far
+            self.dirty.lock().unwrap().take(name);
+            (wallet.data.clone(), wallet.path.join("wallet.dat"), wallet.secret.clone())
+        };
+        data.save_async(&wallet_data_path, secret.as_ref().map(SecretString::expose_secret)).await?;
+        Ok(())
+    }
+
+    /// Create a wallet with a seed phrase
+    pub async fn create_wallet_with_seed(&self, name: &str, password: &str, seed_phrase: &str, passphrase: Option<&str>, is_secured: bool) -> Result<(), WalletError> {
+        let mut manager = self.get_manager().await;
+        manager.create_wallet_with_seed(name, password, seed_phrase, passphrase, is_secured).await
     }
 
     /// Update the current wallet's data
     pub async fn update_current_wallet_data(&self, new_data: WalletData) -> Result<(), WalletError> {
-        let mut manager = self.inner.lock().await;
+        let mut manager = self.get_manager().await;
         manager.update_current_wallet_data(new_data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A manager knowing one secured wallet, "savings", whose wallet.dat holds `data`
+    async fn manager_with_secured_wallet(test: &str, data: WalletData) -> (AsyncWalletManager, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("bradcoin_wallet_manager_{}_{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        data.save_async(&dir.join("wallet.dat"), Some("correct horse")).await.unwrap();
+
+        let mut config = Config::default();
+        config.wallets.push(WalletInfo {
+            name: "savings".to_string(),
+            path: dir.to_string_lossy().into_owned(),
+            secured: true,
+            addresses: Vec::new(),
+            block_height: 0,
+            last_sync: None,
+        });
+        (AsyncWalletManager::new(WalletManager::new(config)), dir)
+    }
+
+    #[tokio::test]
+    async fn test_open_wallet_is_shared_and_saved_outside_the_manager_lock() {
+        let (manager, dir) = manager_with_secured_wallet("shared", WalletData::new("savings", "xpub_test", true)).await;
+        assert!(manager.open_wallet("savings", Some("wrong password")).await.is_err());
+        manager.open_wallet("savings", Some("correct horse")).await.unwrap();
+
+        let (first, second) = (manager.read_manager().await, manager.read_manager().await);
+        assert!(first.is_wallet_open("savings"));
+        assert_eq!(second.get_current_wallet().map(|wallet| wallet.data.name.as_str()), Some("savings"));
+        drop((first, second));
+
+        // The status snapshot is readable while a change holds the manager
+        let mut held = manager.get_manager().await;
+        assert_eq!(manager.current_wallet_name().as_deref(), Some("savings"));
+        held.get_wallet_mut(Some("savings")).unwrap().data.balance = 21;
+        drop(held);
+        manager.save_wallet("savings").await.unwrap();
+        let saved = WalletData::load_async(&dir.join("wallet.dat"), Some("correct horse")).await.unwrap();
+        assert_eq!(saved.balance, 21);
+
+        // A dirty wallet the persister has not reached yet is saved when it closes
+        let mut held = manager.get_manager().await;
+        held.get_wallet_mut(Some("savings")).unwrap().data.balance = 42;
+        held.mark_dirty("savings");
+        drop(held);
+        manager.close_wallet("savings").await;
+        assert!(manager.open_wallet_names().is_empty());
+        let saved = WalletData::load_async(&dir.join("wallet.dat"), Some("correct horse")).await.unwrap();
+        assert_eq!(saved.balance, 42);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_rekeyed_secured_wallet_reopens_with_its_password() {
+        const OLD_SEED: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        const NEW_SEED: &str = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+        let data = WalletManager::wallet_data_from_seed("savings", OLD_SEED, "", true).unwrap();
+        let (manager, dir) = manager_with_secured_wallet("rekey", data).await;
+        manager.open_wallet("savings", Some("correct horse")).await.unwrap();
+
+        let fresh = WalletManager::wallet_data_from_seed("savings", NEW_SEED, "", true).unwrap();
+        let new_fingerprint = fresh.master_fingerprint.clone();
+        manager.get_manager().await.get_wallet_mut(Some("savings")).unwrap().data.rekey(fresh).unwrap();
+        manager.save_wallet("savings").await.unwrap();
+        manager.close_wallet("savings").await;
+
+        assert!(WalletData::load_async(&dir.join("wallet.dat"), Some("")).await.is_err());
+        manager.open_wallet("savings", Some("correct horse")).await.unwrap();
+        let reopened = manager.read_manager().await.get_wallet(Some("savings")).unwrap().data.master_fingerprint.clone();
+        assert_eq!(reopened, new_fingerprint);
+        manager.close_wallet("savings").await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
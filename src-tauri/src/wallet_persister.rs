@@ -0,0 +1,117 @@
+//! Debounced saving of open wallets.
+//!
+//! Labels, frozen coins and the balances sync finds change an open wallet's data often, and
+//! writing wallet.dat each time held up the command that made the change; on a secured wallet the
+//! key stretching alone takes a noticeable moment. Such changes mark the wallet dirty instead. The
+//! wallet manager's persister writes a dirty wallet once it has gone `SAVE_DEBOUNCE` without
+//! another change, and at the latest `SAVE_MAX_DELAY` after its first unsaved one. A dirty wallet
+//! is also written when it is closed, which includes shutdown.
+//!
+//! Changes that must be on disk before the command returns, like new keys or a re-key, still
+//! save directly; a direct save also clears the wallet's dirty mark. Either way a secured wallet
+//! is encrypted with the password it was unlocked with, which the open wallet keeps.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// A dirty wallet is saved once it has gone this long without another change
+pub const SAVE_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// A dirty wallet is saved at the latest this long after its first unsaved change
+pub const SAVE_MAX_DELAY: Duration = Duration::from_secs(10);
+
+/// How often the persister looks for wallets due to be saved
+pub const SAVE_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+struct DirtyWallet {
+    first_change: Instant,
+    last_change: Instant,
+}
+
+/// Open wallets with changes not yet written to disk, by name
+#[derive(Default)]
+pub struct DirtyWallets {
+    wallets: HashMap<String, DirtyWallet>,
+}
+
+impl DirtyWallets {
+    /// Record a change to a wallet
+    pub fn mark(&mut self, name: &str, now: Instant) {
+        match self.wallets.get_mut(name) {
+            Some(dirty) => dirty.last_change = now,
+            None => {
+                self.wallets.insert(
+                    name.to_string(),
+                    DirtyWallet {
+                        first_change: now,
+                        last_change: now,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Names of the wallets due to be saved at `now`
+    pub fn due(&self, now: Instant) -> Vec<String> {
+        self.wallets
+            .iter()
+            .filter(|(_, dirty)| {
+                now.duration_since(dirty.last_change) >= SAVE_DEBOUNCE
+                    || now.duration_since(dirty.first_change) >= SAVE_MAX_DELAY
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Names of every dirty wallet
+    pub fn names(&self) -> Vec<String> {
+        self.wallets.keys().cloned().collect()
+    }
+
+    /// Clear a wallet's dirty mark, returning whether it was dirty
+    pub fn take(&mut self, name: &str) -> bool {
+        self.wallets.remove(name).is_some()
+    }
+
+    /// Follow a wallet to its new name
+    pub fn rename(&mut self, old_name: &str, new_name: &str) {
+        if let Some(dirty) = self.wallets.remove(old_name) {
+            self.wallets.insert(new_name.to_string(), dirty);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.wallets.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wallets_are_due_after_a_quiet_spell_or_the_maximum_delay() {
+        let start = Instant::now();
+        let mut dirty = DirtyWallets::default();
+        dirty.mark("quiet", start);
+        dirty.mark("busy", start);
+        assert!(dirty.due(start + Duration::from_secs(1)).is_empty());
+
+        // Changed every second, so only the delay cap makes it due
+        for second in 1..10 {
+            dirty.mark("busy", start + Duration::from_secs(second));
+        }
+        let mut due = dirty.due(start + SAVE_DEBOUNCE);
+        due.sort();
+        assert_eq!(due, vec!["quiet".to_string()]);
+        let mut due = dirty.due(start + SAVE_MAX_DELAY);
+        due.sort();
+        assert_eq!(due, vec!["busy".to_string(), "quiet".to_string()]);
+
+        dirty.rename("busy", "renamed");
+        assert!(dirty.take("renamed"));
+        assert!(!dirty.take("busy"));
+        assert!(dirty.take("quiet"));
+        assert!(dirty.is_empty());
+    }
+}
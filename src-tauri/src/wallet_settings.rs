@@ -0,0 +1,71 @@
+use crate::atomic_file;
+use crate::coin_selection::DEFAULT_MIN_CONFIRMATIONS;
+use crate::spending_policy::SpendingPolicy;
+use crate::wallet_data::WalletDataError;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// File name of the per-wallet settings, stored next to wallet.dat.
+/// Kept outside wallet.dat so it can be changed without the wallet password.
+pub const WALLET_SETTINGS_FILE: &str = "wallet_settings.json";
+
+/// Settings that apply to a single wallet rather than the whole application
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WalletSettings {
+    /// Whether to start wallet sync automatically when the wallet is opened
+    #[serde(default = "default_auto_sync_on_open")]
+    pub auto_sync_on_open: bool,
+    /// Address that receives mining rewards; None means the wallet's first address
+    #[serde(default)]
+    pub mining_address: Option<String>,
+    /// Confirmations a received coin needs before it can be spent
+    #[serde(default = "default_min_confirmations")]
+    pub min_confirmations: u32,
+    /// Daily cap, password confirmation and cooling-off for sends from this wallet
+    #[serde(default)]
+    pub spending_policy: SpendingPolicy,
+}
+
+fn default_auto_sync_on_open() -> bool {
+    true
+}
+
+fn default_min_confirmations() -> u32 {
+    DEFAULT_MIN_CONFIRMATIONS
+}
+
+impl Default for WalletSettings {
+    fn default() -> Self {
+        Self {
+            auto_sync_on_open: default_auto_sync_on_open(),
+            mining_address: None,
+            min_confirmations: default_min_confirmations(),
+            spending_policy: SpendingPolicy::default(),
+        }
+    }
+}
+
+impl WalletSettings {
+    /// Load settings from a wallet directory, falling back to defaults if none were saved yet
+    pub fn load(wallet_dir: &Path) -> Result<Self, WalletDataError> {
+        let path = wallet_dir.join(WALLET_SETTINGS_FILE);
+        if !path.exists() {
+            debug!("No wallet settings at {}, using defaults", path.display());
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Save settings into a wallet directory
+    pub fn save(&self, wallet_dir: &Path) -> Result<(), WalletDataError> {
+        let path = wallet_dir.join(WALLET_SETTINGS_FILE);
+        atomic_file::write_atomic(&path, serde_json::to_string_pretty(self)?.as_bytes())?;
+        info!("Wallet settings saved to {}", path.display());
+
+        Ok(())
+    }
+}
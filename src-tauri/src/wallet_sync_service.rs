@@ -1,18 +1,39 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::Instant;
 
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Emitter};
-use tokio::sync::{RwLock, Mutex};
-
-use crate::blockchain_database::AsyncBlockchainDatabase;
-use crate::wallet_manager::AsyncWalletManager;
+use tauri::AppHandle;
+use rayon::prelude::*;
+use tokio::sync::{broadcast, mpsc, RwLock, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::blockchain_database::{AsyncBlockchainDatabase, Block, UTXO};
+use crate::compact_filters;
+use crate::event_bus::{BackendEvent, EventBus};
+use crate::events::{self, EmitEvent};
+use crate::wallet_manager::{AsyncWalletManager, Wallet};
 use crate::wallet_data::Utxo;
 use crate::config::ConfigManager;
+use crate::cost_basis::CostBasisLedger;
+use crate::price_service::ExchangeRate;
+use crate::payment_requests::{PaymentRequestBook, PaymentRequestEvent, PaymentRequestStatus};
+use crate::wallet_activity::{self, ActivityEntry, ActivityKind};
 use crate::errors::*;
 
+/// Addresses looked up together when reading the UTXO set
+const ADDRESS_BATCH: usize = 64;
+
+/// Blocks whose compact filters are read and matched as one batch
+const SCAN_BATCH_BLOCKS: u64 = 500;
+
+/// Filter batches read ahead of matching; bounds the memory a scan holds
+const SCAN_QUEUE_DEPTH: usize = 4;
+
+/// Connected blocks whose wallet changes are kept per wallet, to roll back a replaced block
+const UNDO_DEPTH: usize = 100;
+
 /// Wallet sync status
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WalletSyncStatus {
@@ -23,9 +44,90 @@ pub struct WalletSyncStatus {
     pub current_balance: u64,
     pub transaction_count: u32,
     pub utxo_count: u32,
+    /// Blocks scanned per second by a compact filter sync
+    #[serde(default)]
+    pub blocks_per_second: f64,
+}
+
+/// Updates the status of one running sync
+struct SyncReporter<'a> {
+    wallet_id: &'a str,
+    active_syncs: &'a Arc<RwLock<HashMap<String, WalletSyncStatus>>>,
+    app_handle: &'a Option<AppHandle>,
+}
+
+impl SyncReporter<'_> {
+    /// Apply `update` to the sync status and emit it. Returns false if the sync was stopped or
+    /// its wallet removed.
+    async fn report(&self, update: impl FnOnce(&mut WalletSyncStatus)) -> bool {
+        let status = {
+            let mut syncs = self.active_syncs.write().await;
+            match syncs.get_mut(self.wallet_id) {
+                Some(status) if status.is_syncing => {
+                    update(status);
+                    status.clone()
+                }
+                _ => return false,
+            }
+        };
+        if let Some(app) = self.app_handle {
+            if let Err(e) = app.emit_event(events::WALLET_SYNC_STATUS, &status) {
+                warn!("Failed to emit wallet sync status: {}", e);
+            }
+        }
+        true
+    }
+}
+
+/// What one connected block changed in a wallet's coins
+#[derive(Debug, Clone)]
+struct BlockUndo {
+    block_hash: String,
+    /// Wallet coins the block spent
+    spent: Vec<Utxo>,
+    /// Wallet coins the block created, as txid and output index
+    created: Vec<(String, u32)>,
+}
+
+/// Apply a connected block to a wallet's coins, returning how to undo it
+fn connect_block(utxos: &mut Vec<Utxo>, addresses: &HashSet<&str>, block: &Block) -> BlockUndo {
+    let mut undo = BlockUndo { block_hash: block.hash.clone(), spent: Vec::new(), created: Vec::new() };
+    for transaction in &block.transactions {
+        for input in &transaction.inputs {
+            let spent = utxos
+                .iter()
+                .position(|utxo| utxo.txid == input.previous_txid && utxo.vout == input.previous_output_index);
+            if let Some(index) = spent {
+                undo.spent.push(utxos.remove(index));
+            }
+        }
+        for (vout, output) in transaction.outputs.iter().enumerate() {
+            if addresses.contains(output.address.as_str()) {
+                utxos.push(Utxo {
+                    txid: transaction.txid.clone(),
+                    vout: vout as u32,
+                    value: output.value,
+                    script_pubkey: output.script_pubkey.clone(),
+                    address: output.address.clone(),
+                    is_change: false,
+                    height: Some(block.height as u32),
+                });
+                undo.created.push((transaction.txid.clone(), vout as u32));
+            }
+        }
+    }
+    undo
+}
+
+/// Roll a wallet's coins back to before the block of `undo`. Spent coins go back first, so a
+/// coin created and spent within the block is removed again with the created ones.
+fn disconnect_block(utxos: &mut Vec<Utxo>, undo: BlockUndo) {
+    utxos.extend(undo.spent);
+    utxos.retain(|utxo| !undo.created.iter().any(|(txid, vout)| *txid == utxo.txid && *vout == utxo.vout));
 }
 
 /// Wallet sync service for individual wallet synchronization
+#[derive(Clone)]
 pub struct WalletSyncService {
     blockchain_db: Arc<AsyncBlockchainDatabase>,
     wallet_manager: Option<AsyncWalletManager>,
@@ -81,6 +183,7 @@ impl WalletSyncService {    /// Create new wallet sync service
             current_balance: 0,
             transaction_count: 0,
             utxo_count: 0,
+            blocks_per_second: 0.0,
         };
 
         {
@@ -134,6 +237,14 @@ impl WalletSyncService {    /// Create new wallet sync service
         Ok(())
     }
 
+    /// Forget a wallet's sync state, cancelling any sync in progress
+    pub async fn remove_wallet(&self, wallet_id: &str) -> Option<WalletSyncStatus> {
+        debug!("Removing sync state for wallet: {}", wallet_id);
+
+        let mut active_syncs = self.active_syncs.write().await;
+        active_syncs.remove(wallet_id)
+    }
+
     /// Get sync status for a wallet
     pub async fn get_wallet_sync_status(&self, wallet_id: &str) -> Option<WalletSyncStatus> {
         let active_syncs = self.active_syncs.read().await;
@@ -161,55 +272,51 @@ impl WalletSyncService {    /// Create new wallet sync service
         let mut total_utxos = 0u32;
         let mut all_utxos = Vec::new();
         let transaction_count = 0u32;
-        let _processed_blocks = 0u64;
-
-        // Sync each address
-        for (addr_index, address) in addresses.iter().enumerate() {
-            debug!("Syncing address {}: {}", addr_index + 1, address);
-
-            // Get UTXOs for this address
-            let utxos = blockchain_db.get_address_utxos(address).await
-                .map_err(|e| AppError::Generic(format!("Failed to get UTXOs for address {}: {}", address, e)))?;            let address_balance = utxos.iter().map(|utxo| utxo.value).sum::<u64>();
-            total_balance += address_balance;
-            total_utxos += utxos.len() as u32;
-            all_utxos.extend(utxos);
-
-            debug!("Address {} has {} UTXOs with total value {}", address, all_utxos.len(), address_balance);
 
-            // Update progress
-            let progress = (addr_index + 1) as f64 / addresses.len() as f64;
-            {
-                let mut syncs = active_syncs.write().await;
-                if let Some(status) = syncs.get_mut(&wallet_id) {
-                    if !status.is_syncing {
-                        info!("Wallet sync cancelled for {}", wallet_id);
-                        return Ok(());
-                    }
-                    
-                    status.sync_progress = progress;
-                    status.current_balance = total_balance;
-                    status.utxo_count = total_utxos;
-                    status.last_sync_block = current_height;
+        let reporter = SyncReporter { wallet_id: &wallet_id, active_syncs: &active_syncs, app_handle: &app_handle };
+        let use_filters = config_manager
+            .as_ref()
+            .is_some_and(|config| config.get_config().app_settings.compact_filter_sync);
+        if use_filters {
+            let Some(utxos) = Self::scan_with_filters(&blockchain_db, &addresses, current_height, &reporter).await? else {
+                info!("Wallet sync stopped for {}", wallet_id);
+                return Ok(());
+            };
+            all_utxos = utxos;
+            total_balance = all_utxos.iter().map(|utxo| utxo.value).sum();
+            total_utxos = all_utxos.len() as u32;
+        } else {
+            // Addresses are looked up in batches, the addresses of a batch concurrently
+            let unique: Vec<&String> = addresses.iter().collect::<HashSet<_>>().into_iter().collect();
+            for (batch_index, batch) in unique.chunks(ADDRESS_BATCH).enumerate() {
+                let lookups = batch.iter().map(|address| blockchain_db.get_address_utxos(address));
+                for (address, utxos) in batch.iter().zip(futures_util::future::join_all(lookups).await) {
+                    let utxos = utxos
+                        .map_err(|e| AppError::Generic(format!("Failed to get UTXOs for address {}: {}", address, e)))?;
+                    total_balance += utxos.iter().map(|utxo| utxo.value).sum::<u64>();
+                    total_utxos += utxos.len() as u32;
+                    all_utxos.extend(utxos);
                 }
-            }
-
-            // Emit progress update
-            if let Some(ref app) = app_handle {
-                let status = {
-                    let syncs = active_syncs.read().await;
-                    syncs.get(&wallet_id).cloned()
-                };
-                
-                if let Some(status) = status {
-                    if let Err(e) = app.emit("wallet-sync-status", &status) {
-                        warn!("Failed to emit wallet sync status: {}", e);
-                    }
+                debug!("Synced {} addresses of {} for {}", batch_index * ADDRESS_BATCH + batch.len(), unique.len(), wallet_id);
+
+                let progress = (batch_index * ADDRESS_BATCH + batch.len()) as f64 / unique.len() as f64;
+                let (balance, utxo_count) = (total_balance, total_utxos);
+                let running = reporter
+                    .report(|status| {
+                        status.sync_progress = progress;
+                        status.current_balance = balance;
+                        status.utxo_count = utxo_count;
+                        status.last_sync_block = current_height;
+                    })
+                    .await;
+                if !running {
+                    info!("Wallet sync stopped for {}", wallet_id);
+                    return Ok(());
                 }
             }
+        }
 
-            // Small delay to prevent overwhelming the system
-            tokio::time::sleep(Duration::from_millis(100)).await;
-        }        // Mark sync as completed
+        // Mark sync as completed
         {
             let mut syncs = active_syncs.write().await;
             if let Some(status) = syncs.get_mut(&wallet_id) {                status.is_syncing = false;
@@ -224,67 +331,32 @@ impl WalletSyncService {    /// Create new wallet sync service
         // Update wallet data in memory and save to disk
         info!("Updating wallet data for {} with balance: {}, UTXOs: {}", wallet_id, total_balance, total_utxos);
         
+        // Price incoming and outgoing coins for cost basis reports
+        let rate = match app_handle {
+            Some(ref app) => crate::price_service::display_rate(app).await,
+            None => None,
+        };
+
         let mut manager = wallet_manager.get_manager().await;
-        if let Some(wallet) = manager.get_current_wallet_mut() {
-            if wallet.name == wallet_id {
-                // Convert blockchain UTXOs to wallet UTXOs
-                let wallet_utxos: Vec<Utxo> = all_utxos.into_iter().map(|blockchain_utxo| {
-                    Utxo {
-                        txid: blockchain_utxo.txid,
-                        vout: blockchain_utxo.output_index,
-                        value: blockchain_utxo.value,
-                        script_pubkey: blockchain_utxo.script_pubkey,
-                        address: blockchain_utxo.address,
-                        is_change: false, // Assume not change for now
-                        height: Some(blockchain_utxo.block_height as u32),
-                    }
-                }).collect();
-
-                // Update wallet data
-                wallet.data.balance = total_balance;
-                wallet.data.utxos = wallet_utxos;
-                wallet.data.block_height = current_height as u32;
-                wallet.data.modified_at = chrono::Utc::now().timestamp();
-
-                // Save wallet data to disk
-                let wallet_data_path = wallet.path.join("wallet.dat");
-                let password = if wallet.data.is_encrypted { 
-                    // In a real implementation, we'd need to securely get the password
-                    // For now, we'll skip saving encrypted wallets during sync to avoid password issues
-                    warn!("Skipping disk save for encrypted wallet {} during sync", wallet_id);
-                    None
-                } else { 
-                    None 
-                };                if !wallet.data.is_encrypted {
-                    if let Err(e) = wallet.data.save(&wallet_data_path, password) {
-                        warn!("Failed to save wallet data to disk: {}", e);
-                    } else {
-                        info!("Successfully saved updated wallet data for {}", wallet_id);
-                    }
+        if let Some(wallet) = manager.get_wallet_mut(Some(&wallet_id)) {
+            // Convert blockchain UTXOs to wallet UTXOs
+            let wallet_utxos: Vec<Utxo> = all_utxos.into_iter().map(|blockchain_utxo| {
+                Utxo {
+                    txid: blockchain_utxo.txid,
+                    vout: blockchain_utxo.output_index,
+                    value: blockchain_utxo.value,
+                    script_pubkey: blockchain_utxo.script_pubkey,
+                    address: blockchain_utxo.address,
+                    is_change: false, // Assume not change for now
+                    height: Some(blockchain_utxo.block_height as u32),
                 }
+            }).collect();
 
-                // Update wallet addresses and block height in config
-                if let Some(ref config_mgr) = config_manager {
-                    let wallet_addresses: Vec<String> = wallet.data.addresses.iter()
-                        .map(|addr_info| addr_info.address.clone())
-                        .collect();
-                    
-                    if let Err(e) = config_mgr.update_wallet_sync_info(
-                        &wallet_id,
-                        wallet_addresses,
-                        current_height,
-                        Some(chrono::Utc::now().timestamp()),
-                    ).await {
-                        warn!("Failed to update wallet config: {}", e);
-                    } else {
-                        info!("Successfully updated wallet config for {}", wallet_id);
-                    }
-                }
-            } else {
-                warn!("Current wallet name mismatch: expected {}, found {}", wallet_id, wallet.name);
-            }
+            Self::store_wallet_utxos(wallet, &wallet_id, wallet_utxos, current_height, rate.as_ref(), &app_handle);
+            drop(manager);
+            Self::persist_wallet(&wallet_manager, &wallet_id, current_height, &config_manager).await;
         } else {
-            warn!("No current wallet found to update after sync");
+            warn!("Wallet {} was closed before sync finished, skipping update", wallet_id);
         }
 
         // Emit final status
@@ -295,7 +367,7 @@ impl WalletSyncService {    /// Create new wallet sync service
             };
             
             if let Some(status) = status {
-                if let Err(e) = app.emit("wallet-sync-status", &status) {
+                if let Err(e) = app.emit_event(events::WALLET_SYNC_STATUS, &status) {
                     warn!("Failed to emit final wallet sync status: {}", e);
                 }
             }
@@ -305,12 +377,415 @@ impl WalletSyncService {    /// Create new wallet sync service
         Ok(())
     }
 
+    /// Store a wallet's coins as of `height` in memory. Coins not seen before are reported as
+    /// received and the cost basis and payment requests are updated; `persist_wallet` saves the
+    /// wallet itself.
+    fn store_wallet_utxos(
+        wallet: &mut Wallet,
+        wallet_id: &str,
+        utxos: Vec<Utxo>,
+        height: u64,
+        rate: Option<&ExchangeRate>,
+        app_handle: &Option<AppHandle>,
+    ) {
+        // Coins not seen at the previous sync; the first sync of a wallet reports nothing
+        let mut received: Vec<(String, u64, Option<u32>)> = Vec::new();
+        if wallet.data.block_height > 0 {
+            for utxo in &utxos {
+                let is_new = !wallet.data.utxos.iter().any(|old| old.txid == utxo.txid && old.vout == utxo.vout);
+                if !is_new {
+                    continue;
+                }
+                match received.iter_mut().find(|(txid, _, _)| *txid == utxo.txid) {
+                    Some((_, amount, _)) => *amount += utxo.value,
+                    None => received.push((utxo.txid.clone(), utxo.value, utxo.height)),
+                }
+            }
+        }
+        for (txid, amount, _) in &received {
+            let entry = ActivityEntry {
+                txid: Some(txid.clone()),
+                amount: Some(*amount),
+                ..ActivityEntry::now(ActivityKind::TransactionReceived)
+            };
+            wallet_activity::record(&wallet.path, entry);
+        }
+        if let Some(ref app) = app_handle {
+            for (txid, amount, block_height) in received {
+                let payload = events::WalletCoinsReceived { wallet_name: wallet_id.to_string(), txid, amount, block_height };
+                if let Err(e) = app.emit_event(events::WALLET_COINS_RECEIVED, &payload) {
+                    warn!("Failed to emit wallet-coins-received event: {}", e);
+                }
+            }
+        }
+
+        // Update wallet data
+        wallet.data.balance = utxos.iter().map(|utxo| utxo.value).sum();
+        wallet.data.utxos = utxos;
+        wallet.data.block_height = height as u32;
+        wallet.data.modified_at = chrono::Utc::now().timestamp();
+
+        match CostBasisLedger::load(&wallet.path) {
+            Ok(mut ledger) => {
+                if ledger.record_sync(&wallet.data.utxos, wallet.data.modified_at, rate) {
+                    if let Err(e) = ledger.save(&wallet.path) {
+                        warn!("Failed to save cost basis ledger for {}: {}", wallet_id, e);
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to load cost basis ledger for {}: {}", wallet_id, e),
+        }
+
+        match PaymentRequestBook::load(&wallet.path) {
+            Ok(mut book) => {
+                let changed = book.record_sync(&wallet.data.utxos, wallet.data.modified_at);
+                if !changed.is_empty() {
+                    if let Err(e) = book.save(&wallet.path) {
+                        warn!("Failed to save payment requests for {}: {}", wallet_id, e);
+                    }
+                }
+                if let Some(ref app) = app_handle {
+                    for request in changed {
+                        let event = if request.status == PaymentRequestStatus::Paid {
+                            info!("Payment request {} of wallet {} was paid", request.id, wallet_id);
+                            events::PAYMENT_REQUEST_PAID
+                        } else {
+                            events::PAYMENT_REQUEST_UPDATED
+                        };
+                        let payload = PaymentRequestEvent { wallet_name: wallet_id.to_string(), request };
+                        if let Err(e) = app.emit_event(event, &payload) {
+                            warn!("Failed to emit {} event: {}", event, e);
+                        }
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to load payment requests for {}: {}", wallet_id, e),
+        }
+
+    }
+
+    /// Save a wallet updated by `store_wallet_utxos` and record the sync in its config entry.
+    /// Called once the wallet manager is released, so the disk write holds up no other command.
+    async fn persist_wallet(
+        wallet_manager: &AsyncWalletManager,
+        wallet_id: &str,
+        height: u64,
+        config_manager: &Option<Arc<ConfigManager>>,
+    ) {
+        let Some(wallet_addresses) = wallet_manager.read_manager().await.get_wallet(Some(wallet_id)).map(|wallet| {
+            wallet.data.addresses.iter().map(|addr_info| addr_info.address.clone()).collect::<Vec<String>>()
+        }) else {
+            return;
+        };
+
+        // Saved by the wallet persister, once per burst of synced blocks
+        wallet_manager.mark_dirty(wallet_id);
+
+        // Update wallet addresses and block height in config
+        if let Some(ref config_mgr) = config_manager {
+            if let Err(e) = config_mgr.update_wallet_sync_info(
+                wallet_id,
+                wallet_addresses,
+                height,
+                Some(chrono::Utc::now().timestamp()),
+            ).await {
+                warn!("Failed to update wallet config: {}", e);
+            } else {
+                info!("Successfully updated wallet config for {}", wallet_id);
+            }
+        }
+    }
+
+    /// Find the wallet's unspent coins with compact filters, reading only the blocks that match.
+    /// The first pass finds the blocks paying the wallet's addresses, the second the blocks
+    /// spending the coins found, from the height of the oldest. Returns None if the sync was
+    /// stopped.
+    async fn scan_with_filters(
+        blockchain_db: &Arc<AsyncBlockchainDatabase>,
+        addresses: &[String],
+        tip: u64,
+        reporter: &SyncReporter<'_>,
+    ) -> AppResult<Option<Vec<UTXO>>> {
+        let db_error = |e: anyhow::Error| AppError::Generic(format!("Compact filter scan failed: {}", e));
+        let wallet: HashSet<&str> = addresses.iter().map(String::as_str).collect();
+        let address_items = wallet.iter().map(|address| address.as_bytes().to_vec()).collect();
+        let Some(paying) = Self::matching_blocks(blockchain_db, 0, tip, address_items, reporter, 0.0..0.5).await? else {
+            return Ok(None);
+        };
+
+        let mut coins: HashMap<(String, u32), UTXO> = HashMap::new();
+        for &height in &paying {
+            let Some(block) = blockchain_db.get_block_by_height(height).await.map_err(db_error)? else {
+                continue;
+            };
+            for transaction in &block.transactions {
+                for input in &transaction.inputs {
+                    coins.remove(&(input.previous_txid.clone(), input.previous_output_index));
+                }
+                for (index, output) in transaction.outputs.iter().enumerate() {
+                    if wallet.contains(output.address.as_str()) {
+                        let utxo = UTXO {
+                            txid: transaction.txid.clone(),
+                            output_index: index as u32,
+                            value: output.value,
+                            script_pubkey: output.script_pubkey.clone(),
+                            address: output.address.clone(),
+                            block_height: block.height,
+                        };
+                        coins.insert((utxo.txid.clone(), utxo.output_index), utxo);
+                    }
+                }
+            }
+        }
+
+        // Spends of the wallet's coins show up in filters as their outpoints
+        let Some(oldest) = coins.values().map(|utxo| utxo.block_height).min() else {
+            return Ok(Some(Vec::new()));
+        };
+        let outpoint_items = coins.keys().map(|(txid, vout)| compact_filters::outpoint_item(txid, *vout)).collect();
+        let Some(spending) = Self::matching_blocks(blockchain_db, oldest, tip, outpoint_items, reporter, 0.5..1.0).await? else {
+            return Ok(None);
+        };
+        for &height in &spending {
+            let Some(block) = blockchain_db.get_block_by_height(height).await.map_err(db_error)? else {
+                continue;
+            };
+            for input in block.transactions.iter().flat_map(|transaction| &transaction.inputs) {
+                coins.remove(&(input.previous_txid.clone(), input.previous_output_index));
+            }
+        }
+
+        debug!("Compact filter scan read {} blocks up to height {}", paying.len() + spending.len(), tip);
+        Ok(Some(coins.into_values().collect()))
+    }
+
+    /// Heights from `from` to `to` whose compact filter matches any of `items`, in order. A reader
+    /// task fetches filters in batches through a bounded queue while each batch is matched on the
+    /// rayon pool. Progress is reported within `progress_span` of the whole sync. Returns None if
+    /// the sync was stopped.
+    async fn matching_blocks(
+        blockchain_db: &Arc<AsyncBlockchainDatabase>,
+        from: u64,
+        to: u64,
+        items: Vec<Vec<u8>>,
+        reporter: &SyncReporter<'_>,
+        progress_span: std::ops::Range<f64>,
+    ) -> AppResult<Option<Vec<u64>>> {
+        let (batches, mut queue) = mpsc::channel::<Vec<(u64, String, Vec<u8>)>>(SCAN_QUEUE_DEPTH);
+        let reader_db = blockchain_db.clone();
+        let reader = tokio::spawn(async move {
+            let mut start = from;
+            while start <= to {
+                let end = to.min(start + SCAN_BATCH_BLOCKS - 1);
+                let mut batch = Vec::with_capacity((end - start + 1) as usize);
+                for height in start..=end {
+                    match reader_db.get_block_filter(height).await? {
+                        Some((block_hash, filter)) => batch.push((height, block_hash, filter)),
+                        None => break,
+                    }
+                }
+                // A missing block ends the scan, as the chain above it cannot be read either
+                let complete = batch.len() as u64 == end - start + 1;
+                if batches.send(batch).await.is_err() || !complete {
+                    break;
+                }
+                start = end + 1;
+            }
+            Ok::<_, anyhow::Error>(())
+        });
+
+        let items = Arc::new(items);
+        let total = (to + 1).saturating_sub(from).max(1);
+        let started = Instant::now();
+        let mut scanned = 0u64;
+        let mut matched = Vec::new();
+        while let Some(batch) = queue.recv().await {
+            scanned += batch.len() as u64;
+            let items = items.clone();
+            let heights = tokio::task::spawn_blocking(move || {
+                batch
+                    .par_iter()
+                    .filter(|(_, block_hash, filter)| compact_filters::matches_any(filter, block_hash, &items))
+                    .map(|(height, _, _)| *height)
+                    .collect::<Vec<u64>>()
+            })
+            .await
+            .map_err(|e| AppError::Generic(format!("Compact filter matching failed: {}", e)))?;
+            matched.extend(heights);
+
+            let blocks_per_second = scanned as f64 / started.elapsed().as_secs_f64().max(0.001);
+            let progress = progress_span.start + (progress_span.end - progress_span.start) * scanned as f64 / total as f64;
+            let running = reporter
+                .report(|status| {
+                    status.sync_progress = progress;
+                    status.blocks_per_second = blocks_per_second;
+                })
+                .await;
+            if !running {
+                reader.abort();
+                return Ok(None);
+            }
+        }
+
+        reader
+            .await
+            .map_err(|e| AppError::Generic(format!("Compact filter reader failed: {}", e)))?
+            .map_err(|e| AppError::Generic(format!("Compact filter scan failed: {}", e)))?;
+        Ok(Some(matched))
+    }
+
+    /// Keep open wallets up to date from the block events on `event_bus`. Each connected block
+    /// is applied to the coins of the wallets synced to the block below it, and a replaced block
+    /// is rolled back from its undo record. Wallets that fall out of step, or miss events, get a
+    /// full sync.
+    pub fn follow_chain(&self, event_bus: &EventBus) -> JoinHandle<()> {
+        let service = self.clone();
+        let mut events = event_bus.subscribe();
+        tokio::spawn(async move {
+            let mut undo: HashMap<String, VecDeque<BlockUndo>> = HashMap::new();
+            loop {
+                match events.recv().await {
+                    Ok(event @ (BackendEvent::BlockConnected(_) | BackendEvent::BlockDisconnected(_))) => {
+                        service.apply_chain_event(&event, &mut undo).await
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("Wallet sync missed {} chain events, rescanning open wallets", missed);
+                        undo.clear();
+                        service.resync_open_wallets(None).await;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Apply one block event to every open wallet not in a full sync
+    async fn apply_chain_event(&self, event: &BackendEvent, undo: &mut HashMap<String, VecDeque<BlockUndo>>) {
+        let Some(wallet_manager) = &self.wallet_manager else {
+            return;
+        };
+        let rate = match self.app_handle {
+            Some(ref app) => crate::price_service::display_rate(app).await,
+            None => None,
+        };
+        // A wallet in a full sync is skipped; if that misses a block, the next one finds a gap
+        let syncing: HashSet<String> = {
+            let syncs = self.active_syncs.read().await;
+            syncs.values().filter(|status| status.is_syncing).map(|status| status.wallet_id.clone()).collect()
+        };
+
+        let mut out_of_step = Vec::new();
+        let mut stored = Vec::new();
+        let mut manager = wallet_manager.get_manager().await;
+        let open = manager.open_wallet_names();
+        undo.retain(|wallet_id, _| open.contains(wallet_id));
+        for wallet_id in open {
+            if syncing.contains(&wallet_id) {
+                continue;
+            }
+            let Some(wallet) = manager.get_wallet_mut(Some(&wallet_id)) else {
+                continue;
+            };
+            let synced = u64::from(wallet.data.block_height);
+            let history = undo.entry(wallet_id.clone()).or_default();
+            let mut utxos = wallet.data.utxos.clone();
+            let height = match event {
+                BackendEvent::BlockConnected(block) if block.height == synced + 1 => {
+                    let addresses: HashSet<&str> = wallet.data.addresses.iter().map(|a| a.address.as_str()).collect();
+                    history.push_back(connect_block(&mut utxos, &addresses, block));
+                    if history.len() > UNDO_DEPTH {
+                        history.pop_front();
+                    }
+                    block.height
+                }
+                // Already covered by a full sync, or by the rescan of an earlier replacement
+                BackendEvent::BlockConnected(block) if block.height <= synced => continue,
+                // Never applied to this wallet
+                BackendEvent::BlockDisconnected(block) if block.height > synced => continue,
+                BackendEvent::BlockDisconnected(block)
+                    if block.height == synced && history.back().is_some_and(|last| last.block_hash == block.hash) =>
+                {
+                    if let Some(last) = history.pop_back() {
+                        disconnect_block(&mut utxos, last);
+                    }
+                    block.height - 1
+                }
+                _ => {
+                    history.clear();
+                    out_of_step.push(wallet_id);
+                    continue;
+                }
+            };
+
+            debug!("Applied chain event at height {} to wallet {}", height, wallet_id);
+            Self::store_wallet_utxos(wallet, &wallet_id, utxos, height, rate.as_ref(), &self.app_handle);
+            stored.push((wallet_id.clone(), height));
+            let status = {
+                let mut syncs = self.active_syncs.write().await;
+                let status = syncs.entry(wallet_id.clone()).or_insert_with(|| WalletSyncStatus {
+                    wallet_id: wallet_id.clone(),
+                    is_syncing: false,
+                    sync_progress: 1.0,
+                    last_sync_block: 0,
+                    current_balance: 0,
+                    transaction_count: 0,
+                    utxo_count: 0,
+                    blocks_per_second: 0.0,
+                });
+                status.last_sync_block = height;
+                status.current_balance = wallet.data.balance;
+                status.utxo_count = wallet.data.utxos.len() as u32;
+                status.clone()
+            };
+            if let Some(ref app) = self.app_handle {
+                if let Err(e) = app.emit_event(events::WALLET_SYNC_STATUS, &status) {
+                    warn!("Failed to emit wallet sync status: {}", e);
+                }
+            }
+        }
+        drop(manager);
+
+        for (wallet_id, height) in stored {
+            Self::persist_wallet(wallet_manager, &wallet_id, height, &self.config_manager).await;
+        }
+        if !out_of_step.is_empty() {
+            info!("Wallets out of step with the chain, rescanning: {:?}", out_of_step);
+            self.resync_open_wallets(Some(&out_of_step)).await;
+        }
+    }
+
+    /// Start a full sync of the open wallets, or of those named in `only`
+    async fn resync_open_wallets(&self, only: Option<&[String]>) {
+        let Some(wallet_manager) = &self.wallet_manager else {
+            return;
+        };
+        let wallets: Vec<(String, Vec<String>)> = {
+            let manager = wallet_manager.read_manager().await;
+            manager
+                .open_wallet_names()
+                .into_iter()
+                .filter(|wallet_id| only.is_none_or(|only| only.contains(wallet_id)))
+                .filter_map(|wallet_id| {
+                    let wallet = manager.get_wallet(Some(&wallet_id))?;
+                    let addresses = wallet.data.addresses.iter().map(|a| a.address.clone()).collect();
+                    Some((wallet_id, addresses))
+                })
+                .collect()
+        };
+        for (wallet_id, addresses) in wallets {
+            if let Err(e) = self.start_wallet_sync(wallet_id.clone(), addresses).await {
+                warn!("Failed to rescan wallet {}: {}", wallet_id, e);
+            }
+        }
+    }
+
     /// Emit wallet sync status event
     async fn emit_wallet_sync_status(&self, wallet_id: &str) {
         if let Some(ref app) = self.app_handle {
             let status = self.get_wallet_sync_status(wallet_id).await;
             if let Some(status) = status {
-                if let Err(e) = app.emit("wallet-sync-status", &status) {
+                if let Err(e) = app.emit_event(events::WALLET_SYNC_STATUS, &status) {
                     warn!("Failed to emit wallet sync status: {}", e);
                 }
             }
@@ -321,6 +796,7 @@ impl WalletSyncService {    /// Create new wallet sync service
 /// Thread-safe wrapper for WalletSyncService
 pub struct AsyncWalletSyncService {
     inner: Arc<Mutex<WalletSyncService>>,
+    follower: Mutex<Option<JoinHandle<()>>>,
 }
 
 impl AsyncWalletSyncService {
@@ -328,6 +804,7 @@ impl AsyncWalletSyncService {
     pub fn new(blockchain_db: Arc<AsyncBlockchainDatabase>) -> Self {
         Self {
             inner: Arc::new(Mutex::new(WalletSyncService::new(blockchain_db))),
+            follower: Mutex::new(None),
         }
     }
 
@@ -349,6 +826,12 @@ impl AsyncWalletSyncService {
         service.stop_wallet_sync(wallet_id).await
     }
 
+    /// Forget a wallet's sync state, cancelling any sync in progress
+    pub async fn remove_wallet(&self, wallet_id: &str) -> Option<WalletSyncStatus> {
+        let service = self.inner.lock().await;
+        service.remove_wallet(wallet_id).await
+    }
+
     /// Get sync status for a wallet
     pub async fn get_wallet_sync_status(&self, wallet_id: &str) -> Option<WalletSyncStatus> {
         let service = self.inner.lock().await;
@@ -370,4 +853,123 @@ impl AsyncWalletSyncService {
         let mut service = self.inner.lock().await;
         service.set_config_manager(config_manager).await;
     }
+
+    /// Update open wallets as blocks are connected; call once the managers are set
+    pub async fn follow_chain(&self, event_bus: &EventBus) {
+        let follower = self.inner.lock().await.follow_chain(event_bus);
+        if let Some(previous) = self.follower.lock().await.replace(follower) {
+            previous.abort();
+        }
+    }
+
+    /// Stop updating open wallets from block events
+    pub async fn stop_following(&self) {
+        if let Some(follower) = self.follower.lock().await.take() {
+            follower.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain_database::{Transaction, TransactionInput, TransactionOutput};
+    use crate::test_support::TestNode;
+
+    fn coin(txid: &str, address: &str, value: u64) -> Utxo {
+        Utxo {
+            txid: txid.to_string(),
+            vout: 0,
+            value,
+            script_pubkey: String::new(),
+            address: address.to_string(),
+            is_change: false,
+            height: Some(1),
+        }
+    }
+
+    fn transaction(txid: &str, spends: &str, outputs: &[(&str, u64)]) -> Transaction {
+        Transaction {
+            txid: txid.to_string(),
+            inputs: vec![TransactionInput {
+                previous_txid: spends.to_string(),
+                previous_output_index: 0,
+                script_sig: String::new(),
+                sequence: 0,
+            }],
+            outputs: outputs
+                .iter()
+                .map(|(address, value)| TransactionOutput { value: *value, script_pubkey: String::new(), address: address.to_string() })
+                .collect(),
+            timestamp: 0,
+            fee: 0,
+        }
+    }
+
+    #[test]
+    fn test_disconnecting_a_block_restores_the_coins_before_it() {
+        let before = vec![coin("a", "alice", 10), coin("x", "alice", 4)];
+        let mut utxos = before.clone();
+        let block = Block {
+            height: 2,
+            hash: "block2".to_string(),
+            previous_hash: "block1".to_string(),
+            timestamp: 0,
+            nonce: 0,
+            difficulty: 1,
+            // Change paid back to alice is spent again within the block
+            transactions: vec![
+                transaction("b", "a", &[("alice", 5), ("bob", 5)]),
+                transaction("c", "b", &[("bob", 4), ("alice", 1)]),
+            ],
+            merkle_root: String::new(),
+        };
+
+        let undo = connect_block(&mut utxos, &HashSet::from(["alice"]), &block);
+        let mut kept: Vec<(String, u32)> = utxos.iter().map(|utxo| (utxo.txid.clone(), utxo.vout)).collect();
+        kept.sort();
+        assert_eq!(kept, vec![("c".to_string(), 1), ("x".to_string(), 0)]);
+
+        disconnect_block(&mut utxos, undo);
+        let mut restored: Vec<&str> = utxos.iter().map(|utxo| utxo.txid.as_str()).collect();
+        restored.sort();
+        assert_eq!(restored, vec!["a", "x"]);
+    }
+
+    #[tokio::test]
+    async fn test_filter_scan_finds_the_same_coins_as_the_utxo_set() {
+        let node = TestNode::start("filter_scan").await;
+        let alice = node.create_wallet("alice");
+        let bob = node.create_wallet("bob");
+        node.mine_blocks(3, &alice.address).await;
+        node.send(&alice, &bob.address, 1_000).await.unwrap();
+        node.mine_blocks(1, &bob.address).await;
+
+        let active_syncs = Arc::new(RwLock::new(HashMap::from([(
+            "alice".to_string(),
+            WalletSyncStatus {
+                wallet_id: "alice".to_string(),
+                is_syncing: true,
+                sync_progress: 0.0,
+                last_sync_block: 0,
+                current_balance: 0,
+                transaction_count: 0,
+                utxo_count: 0,
+                blocks_per_second: 0.0,
+            },
+        )])));
+        let reporter = SyncReporter { wallet_id: "alice", active_syncs: &active_syncs, app_handle: &None };
+        let tip = node.height().await;
+        let mut found = WalletSyncService::scan_with_filters(&node.blockchain_db, &[alice.address.clone()], tip, &reporter)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let mut expected = node.blockchain_db.get_address_utxos(&alice.address).await.unwrap();
+        let key = |utxo: &UTXO| (utxo.txid.clone(), utxo.output_index);
+        found.sort_by_key(key);
+        expected.sort_by_key(key);
+        assert_eq!(found.iter().map(key).collect::<Vec<_>>(), expected.iter().map(key).collect::<Vec<_>>());
+        assert_eq!(active_syncs.read().await["alice"].sync_progress, 1.0);
+    }
 }
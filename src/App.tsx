@@ -1,8 +1,9 @@
 import { useState, useMemo, useEffect } from "react";
 import { BrowserRouter, Routes, Route, useLocation } from "react-router-dom";
-import { listen } from "@tauri-apps/api/event";
+import { listenEvent, type BlockchainSetupRequired } from "./lib/events";
 import { invoke } from "@tauri-apps/api/core";
 import "./App.css";
+import type { CommandError } from "./lib/errors";
 
 // Material UI imports
 import { 
@@ -20,6 +21,7 @@ import Sidebar from "./components/Sidebar";
 import AppHeader from "./components/AppHeader";
 import OpenCreateWalletDialog from "./components/OpenCreateWalletDialog";
 import { BlockchainSetupDialog } from "./components/BlockchainSetupDialog";
+import { usePaymentLinks } from "./hooks/usePaymentLinks";
 
 // Page components
 import Account from "./pages/Account";
@@ -66,27 +68,27 @@ function App() {
       console.log('Setting up blockchain event listeners');
       
       // Listen for blockchain setup required event
-      const unlistenSetupRequired = await listen('blockchain-setup-required', () => {
-        console.log('Frontend: Received blockchain-setup-required event');
+      const unlistenSetupRequired = await listenEvent<BlockchainSetupRequired>('blockchain-setup-required', (payload) => {
+        console.log('Frontend: Received blockchain-setup-required event:', payload.reason);
         setBlockchainSetupOpen(true);
         setBlockchainReady(false);
       });
 
       // Listen for blockchain setup error event
-      const unlistenSetupError = await listen<string>('blockchain-setup-error', (event) => {
-        console.error('Frontend: Received blockchain-setup-error event:', event.payload);
-        setAppError(event.payload);
+      const unlistenSetupError = await listenEvent<CommandError>('blockchain-setup-error', (payload) => {
+        console.error('Frontend: Received blockchain-setup-error event:', payload);
+        setAppError(payload.message);
         setBlockchainReady(false);
       });
 
       // Listen for app initialization error event
-      const unlistenInitError = await listen<string>('app-initialization-error', (event) => {
-        console.error('Frontend: Received app-initialization-error event:', event.payload);
-        setAppError(event.payload);
+      const unlistenInitError = await listenEvent<CommandError>('app-initialization-error', (payload) => {
+        console.error('Frontend: Received app-initialization-error event:', payload);
+        setAppError(payload.message);
       });
 
       // Listen for blockchain services ready event
-      const unlistenServicesReady = await listen('blockchain-services-ready', () => {
+      const unlistenServicesReady = await listenEvent('blockchain-services-ready', () => {
         console.log('Frontend: Received blockchain-services-ready event');
         setBlockchainReady(true);
         setBlockchainSetupOpen(false);
@@ -367,6 +369,7 @@ interface AppContentProps {
 // Separate component to use React Router hooks
 function AppContent({ mode, toggleColorMode, mobileOpen, handleDrawerToggle }: AppContentProps) {
   const location = useLocation();
+  usePaymentLinks();
 
   return (
     <Box sx={{ display: 'flex', height: '100vh', overflow: 'hidden' }}>
@@ -21,6 +21,7 @@ import {
 import { AccountBalanceWallet } from '@mui/icons-material';
 import { invoke } from '@tauri-apps/api/core';
 import { WalletAddress } from '../types/mining';
+import { getErrorMessage } from '../lib/errors';
 
 interface AddressSelectionDialogProps {
   open: boolean;
@@ -64,7 +65,7 @@ export default function AddressSelectionDialog({
         setSelectedAddress(result[0].address);
       }
     } catch (err) {
-      setError(err instanceof Error ? err.message : 'Failed to load wallet addresses');
+      setError(getErrorMessage(err));
     } finally {
       setLoading(false);
     }
@@ -18,6 +18,7 @@ import {
 } from '@mui/material';
 import FolderOpenIcon from '@mui/icons-material/FolderOpen';
 import DriveFileMoveIcon from '@mui/icons-material/DriveFileMove';
+import { getErrorMessage } from '../lib/errors';
 
 interface BlockchainMoveDialogProps {
   isOpen: boolean;
@@ -137,7 +138,7 @@ export const BlockchainMoveDialog: React.FC<BlockchainMoveDialogProps> = ({
       
       // Provide more user-friendly error messages
       let errorMessage = 'Failed to move blockchain database';
-      const errorStr = err?.toString() || '';
+      const errorStr = getErrorMessage(err);
       
       if (errorStr.includes('already contains blockchain') || errorStr.includes('already exists')) {
         errorMessage = 'The selected location already contains blockchain data. Please choose a different folder.';
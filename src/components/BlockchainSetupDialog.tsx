@@ -14,6 +14,7 @@ import {
   Fade
 } from '@mui/material';
 import DatabaseIcon from '@mui/icons-material/Storage';
+import { getErrorMessage } from '../lib/errors';
 
 interface BlockchainSetupDialogProps {
   isOpen: boolean;
@@ -62,7 +63,7 @@ export const BlockchainSetupDialog: React.FC<BlockchainSetupDialogProps> = ({
       }
     } catch (err: any) {
       console.error('Failed to start services after setup:', err);
-      onError('Blockchain setup completed but failed to start services: ' + (err?.toString() || 'Unknown error'));
+      onError('Blockchain setup completed but failed to start services: ' + getErrorMessage(err));
     }
   };
 
@@ -93,7 +94,7 @@ export const BlockchainSetupDialog: React.FC<BlockchainSetupDialogProps> = ({
       
       // Provide more user-friendly error messages
       let errorMessage = 'Failed to create blockchain database';
-      const errorStr = err?.toString() || '';
+      const errorStr = getErrorMessage(err);
       
       if (errorStr.includes('failed to acquire lock') || errorStr.includes('lock')) {
         errorMessage = 'Database is currently in use by another process. Please ensure no other instances of B-Rad Coin are running and try again.';
@@ -138,7 +139,7 @@ export const BlockchainSetupDialog: React.FC<BlockchainSetupDialogProps> = ({
       
       // Provide more user-friendly error messages
       let errorMessage = 'Failed to load blockchain database';
-      const errorStr = err?.toString() || '';
+      const errorStr = getErrorMessage(err);
       
       if (errorStr.includes('failed to acquire lock') || errorStr.includes('lock')) {
         errorMessage = 'Database is currently in use by another process. Please ensure no other instances of B-Rad Coin are running and try again.';
@@ -179,7 +180,7 @@ export const BlockchainSetupDialog: React.FC<BlockchainSetupDialogProps> = ({
       
       // Provide more user-friendly error messages
       let errorMessage = 'Failed to create blockchain database';
-      const errorStr = err?.toString() || '';
+      const errorStr = getErrorMessage(err);
       
       if (errorStr.includes('failed to acquire lock') || errorStr.includes('lock')) {
         errorMessage = 'Database is currently in use by another process. Please ensure no other instances of B-Rad Coin are running and try again.';
@@ -20,6 +20,7 @@ import {
 import { AccountBalanceWallet, ContentCopy } from '@mui/icons-material';
 import { invoke } from '@tauri-apps/api/core';
 import { WalletAddress } from '../types/mining';
+import { getErrorMessage } from '../lib/errors';
 
 interface MiningAddressDialogProps {
   open: boolean;
@@ -60,7 +61,7 @@ export default function MiningAddressDialog({
       }
     } catch (err) {
       console.error('Failed to load wallet addresses:', err);
-      setError(err instanceof Error ? err.message : 'Failed to load wallet addresses');
+      setError(getErrorMessage(err));
     } finally {
       setLoading(false);
     }
@@ -8,20 +8,13 @@ import CloudOffIcon from '@mui/icons-material/CloudOff';
 import SyncIcon from '@mui/icons-material/Sync';
 import PeopleIcon from '@mui/icons-material/People';
 import { invoke } from '@tauri-apps/api/core';
-import { listen } from '@tauri-apps/api/event';
+import { listenEvent } from '../lib/events';
+import { getNetworkStatus, NodeStatus } from '../lib/wallet';
 
 interface NetworkStatusProps {
   className?: string;
 }
 
-interface BlockchainInfo {
-  current_height: number;
-  network_height: number;
-  is_connected: boolean;
-  is_syncing: boolean;
-  peer_count: number;
-}
-
 interface WalletSyncStatus {
   wallet_id: string;
   is_syncing: boolean;
@@ -30,24 +23,31 @@ interface WalletSyncStatus {
   current_balance: number;
   transaction_count: number;
   utxo_count: number;
+  blocks_per_second: number;
 }
 
 export const NetworkStatus: React.FC<NetworkStatusProps> = ({ className }) => {
   const { isDarkMode } = useThemeMode();
   const { currentWallet, isWalletOpen } = useWallet();
-  const [blockchainInfo, setBlockchainInfo] = useState<BlockchainInfo>({
-    current_height: 0,
+  const [blockchainInfo, setBlockchainInfo] = useState<NodeStatus>({
+    chain_height: 0,
+    header_height: 0,
     network_height: 0,
-    is_connected: false,
-    is_syncing: false,
     peer_count: 0,
+    is_connected: false,
+    sync_state: 'disconnected',
+    mempool_transactions: 0,
+    mempool_bytes: 0,
+    mining_wallets: 0,
+    hash_rate: 0,
   });
+  const isSyncing = blockchainInfo.sync_state === 'syncing';
   const [walletSyncStatus, setWalletSyncStatus] = useState<WalletSyncStatus | null>(null);
   const [loading, setLoading] = useState(true);  useEffect(() => {
     const fetchNetworkStatus = async () => {
       try {
         // Get network status from the blockchain sync service
-        const networkStatus = await invoke<BlockchainInfo>('get_network_status');
+        const networkStatus = await getNetworkStatus();
         setBlockchainInfo(networkStatus);
         setLoading(false);
       } catch (error) {
@@ -73,14 +73,14 @@ export const NetworkStatus: React.FC<NetworkStatusProps> = ({ className }) => {
     };// Listen for blockchain status events
     const setupListener = async () => {
       try {
-        const unlistenBlockchain = await listen<BlockchainInfo>('blockchain-status', (event) => {
-          setBlockchainInfo(event.payload);
+        const unlistenBlockchain = await listenEvent<NodeStatus>('blockchain-status', (payload) => {
+          setBlockchainInfo(payload);
           setLoading(false);
         });
 
         // Listen for wallet sync status events
-        const unlistenWalletSync = await listen<WalletSyncStatus>('wallet-sync-status', (event) => {
-          setWalletSyncStatus(event.payload);
+        const unlistenWalletSync = await listenEvent<WalletSyncStatus>('wallet-sync-status', (payload) => {
+          setWalletSyncStatus(payload);
         });
         
         // Return cleanup function for both listeners
@@ -188,7 +188,7 @@ export const NetworkStatus: React.FC<NetworkStatusProps> = ({ className }) => {
                   Local Height:
                 </Typography>
                 <Typography variant="caption" fontWeight="medium">
-                  {blockchainInfo.current_height.toLocaleString()}
+                  {blockchainInfo.chain_height.toLocaleString()}
                 </Typography>
               </Stack>
               <Stack direction="row" justifyContent="space-between" alignItems="center">
@@ -207,20 +207,20 @@ export const NetworkStatus: React.FC<NetworkStatusProps> = ({ className }) => {
                   variant="caption" 
                   fontWeight="medium"
                   color={
-                    blockchainInfo.network_height - blockchainInfo.current_height > 0 
+                    blockchainInfo.network_height - blockchainInfo.chain_height > 0 
                       ? "warning.main" 
                       : "success.main"
                   }
                 >
-                  {Math.max(0, blockchainInfo.network_height - blockchainInfo.current_height).toLocaleString()}
+                  {Math.max(0, blockchainInfo.network_height - blockchainInfo.chain_height).toLocaleString()}
                 </Typography>
               </Stack>
             </Stack>
 
             {/* Sync Progress */}
             <LinearProgress 
-              variant={blockchainInfo.is_syncing ? "indeterminate" : "determinate"}
-              value={blockchainInfo.is_syncing ? undefined : 100}
+              variant={isSyncing ? "indeterminate" : "determinate"}
+              value={isSyncing ? undefined : 100}
               sx={{ 
                 height: 8, 
                 borderRadius: 2,
@@ -232,12 +232,12 @@ export const NetworkStatus: React.FC<NetworkStatusProps> = ({ className }) => {
             />
             <Stack direction="row" justifyContent="space-between" alignItems="center" sx={{ mt: 1 }}>
               <Typography variant="caption">
-                {blockchainInfo.is_syncing ? 
-                  `Syncing block ${blockchainInfo.current_height}...` : 
-                  `Synchronized at block ${blockchainInfo.current_height}`}
+                {isSyncing ? 
+                  `Syncing block ${blockchainInfo.chain_height}...` : 
+                  `Synchronized at block ${blockchainInfo.chain_height}`}
               </Typography>
               <Typography variant="caption">
-                {blockchainInfo.is_syncing ? 'Syncing' : 'Complete'}
+                {isSyncing ? 'Syncing' : 'Complete'}
               </Typography>
             </Stack>
 
@@ -263,12 +263,12 @@ export const NetworkStatus: React.FC<NetworkStatusProps> = ({ className }) => {
                     variant="caption" 
                     fontWeight="medium"
                     color={
-                      blockchainInfo.current_height - walletSyncStatus.last_sync_block > 0 
+                      blockchainInfo.chain_height - walletSyncStatus.last_sync_block > 0 
                         ? "warning.main" 
                         : "success.main"
                     }
                   >
-                    {Math.max(0, blockchainInfo.current_height - walletSyncStatus.last_sync_block).toLocaleString()}
+                    {Math.max(0, blockchainInfo.chain_height - walletSyncStatus.last_sync_block).toLocaleString()}
                   </Typography>
                 </Stack>
               </Stack>
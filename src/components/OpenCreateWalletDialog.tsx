@@ -37,6 +37,7 @@ import RestoreIcon from '@mui/icons-material/Restore';
 import SecureWalletDialog from './SecureWalletDialog';
 import SeedPhraseDialog from './SeedPhraseDialog';
 import VerifySeedPhraseDialog from './VerifySeedPhraseDialog';
+import { getErrorMessage } from '../lib/errors';
 
 // Interface for tab panel props
 interface TabPanelProps {
@@ -296,7 +297,7 @@ export default function OpenCreateWalletDialog({ blockchainReady = true }: { blo
       }
     } catch (error) {
       console.error('Failed to open wallet:', error);
-      setErrorMessage(`Error: ${error instanceof Error ? error.message : String(error)}`);
+      setErrorMessage(`Error: ${getErrorMessage(error)}`);
     } finally {
       setIsLoading(false);
     }
@@ -580,7 +581,7 @@ export default function OpenCreateWalletDialog({ blockchainReady = true }: { blo
       }
     } catch (error) {
       console.error('Failed to recover wallet:', error);
-      setErrorMessage(`Error: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      setErrorMessage(`Error: ${getErrorMessage(error)}`);
     } finally {
       setIsLoading(false);
     }
@@ -14,6 +14,7 @@ import {
 } from '@mui/material';
 import LockIcon from '@mui/icons-material/Lock';
 import { invoke } from '@tauri-apps/api/core';
+import { getErrorMessage } from '../lib/errors';
 
 interface SecureWalletDialogProps {
   open: boolean;
@@ -73,7 +74,7 @@ export default function SecureWalletDialog({
       }
     } catch (error) {
       console.error('Error securing wallet:', error);
-      setErrorMessage(`Error: ${error instanceof Error ? error.message : String(error)}`);
+      setErrorMessage(`Error: ${getErrorMessage(error)}`);
     } finally {
       setIsLoading(false);
     }
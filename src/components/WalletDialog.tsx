@@ -34,6 +34,7 @@ import LockOpenIcon from '@mui/icons-material/LockOpen';
 import SecureWalletDialog from './SecureWalletDialog';
 import SeedPhraseDialog from './SeedPhraseDialog';
 import VerifySeedPhraseDialog from './VerifySeedPhraseDialog';
+import { getErrorMessage } from '../lib/errors';
 
 // Interface for tab panel props
 interface TabPanelProps {
@@ -187,7 +188,7 @@ export default function WalletDialog() {
       }
     } catch (error) {
       console.error('Failed to open wallet:', error);
-      setErrorMessage(`Error: ${error instanceof Error ? error.message : String(error)}`);
+      setErrorMessage(`Error: ${getErrorMessage(error)}`);
     } finally {
       setIsLoading(false);
     }
@@ -234,7 +235,7 @@ export default function WalletDialog() {
       }
     } catch (error) {
       console.error('Failed to start wallet creation:', error);
-      setErrorMessage(`Error: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      setErrorMessage(`Error: ${getErrorMessage(error)}`);
     } finally {
       setIsLoading(false);
     }
@@ -269,7 +270,7 @@ export default function WalletDialog() {
       }
     } catch (error) {
       console.error('Failed to create wallet:', error);
-      setErrorMessage(`Error: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      setErrorMessage(`Error: ${getErrorMessage(error)}`);
     } finally {
       setIsLoading(false);
     }
@@ -1,9 +1,10 @@
 import { createContext, useContext, useState, useEffect, useCallback } from 'react';
 import type { ReactNode } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { listen } from '@tauri-apps/api/event';
+import { listenEvent } from '../lib/events';
 import { useTrayIntegration } from '../hooks/useTrayIntegration';
 import { useWalletDialog } from './WalletDialogContext';
+import { getErrorMessage } from '../lib/errors';
 
 // Define the wallet type
 interface WalletInfo {
@@ -209,7 +210,7 @@ export function WalletProvider({ children }: { children: ReactNode }) {
 
   // Effect to listen for wallet deletion events from backend
   useEffect(() => {
-    const unlisten = listen('wallets-deleted', () => {
+    const unlisten = listenEvent('wallets-deleted', () => {
       console.log('WalletContext: Received wallets-deleted event, clearing state');
       setCurrentWallet(null);
       setIsWalletOpen(false);
@@ -234,7 +235,7 @@ export function WalletProvider({ children }: { children: ReactNode }) {
     } catch (error) {
       console.error('Failed to get current wallet path:', error);
       // Re-throw the error so the caller can handle it appropriately
-      throw new Error(`Failed to get current wallet path: ${error instanceof Error ? error.message : String(error)}`);
+      throw new Error(`Failed to get current wallet path: ${getErrorMessage(error)}`);
     }
   };
   // Function to open a folder in the system's file explorer
@@ -309,7 +310,7 @@ export function WalletProvider({ children }: { children: ReactNode }) {
       return result;
     } catch (error) {
       console.error(`Failed to delete wallet ${walletName}:`, error);
-      throw new Error(`Failed to delete wallet: ${error instanceof Error ? error.message : String(error)}`);
+      throw new Error(`Failed to delete wallet: ${getErrorMessage(error)}`);
     }
   };
   return (
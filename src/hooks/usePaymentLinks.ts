@@ -0,0 +1,32 @@
+import { useEffect } from 'react';
+import { listenEvent } from '../lib/events';
+import { useLocation, useNavigate } from 'react-router-dom';
+
+/**
+ * Hook to route brad:// payment links to the send screen.
+ * The send screen takes the pending request itself once it is shown.
+ */
+export const usePaymentLinks = (onError?: (error: string) => void) => {
+  const navigate = useNavigate();
+  const location = useLocation();
+
+  useEffect(() => {
+    const unlistenReceived = listenEvent('payment-uri-received', () => {
+      console.log('Payment link received, showing send screen');
+      if (location.pathname !== '/send-receive') {
+        navigate('/send-receive');
+      }
+    });
+    const unlistenError = listenEvent<{ uri: string; error: string }>('payment-uri-error', (payload) => {
+      console.error('Invalid payment link:', payload.error);
+      if (onError) {
+        onError(payload.error);
+      }
+    });
+
+    return () => {
+      unlistenReceived.then((unlisten) => unlisten());
+      unlistenError.then((unlisten) => unlisten());
+    };
+  }, [navigate, location.pathname, onError]);
+};
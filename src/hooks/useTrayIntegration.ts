@@ -1,5 +1,5 @@
 import { useEffect } from 'react';
-import { listen } from '@tauri-apps/api/event';
+import { listenEvent } from '../lib/events';
 import { invoke } from '@tauri-apps/api/core';
 
 interface TrayIntegrationOptions {
@@ -20,13 +20,13 @@ export const useTrayIntegration = (options: TrayIntegrationOptions = {}) => {
     const setupTrayListeners = async () => {
       try {
         // Listen for tray open wallet event
-        const unlistenOpenWallet = await listen('tray-open-wallet', () => {
+        const unlistenOpenWallet = await listenEvent('tray-open-wallet', () => {
           console.log('Tray requested to open wallet dialog');
           if (onOpenWallet) {
             onOpenWallet();
           }
         });        // Listen for tray create wallet event
-        const unlistenCreateWallet = await listen('tray-create-wallet', async () => {
+        const unlistenCreateWallet = await listenEvent('tray-create-wallet', async () => {
           console.log('Tray requested to create wallet dialog');
           
           // If there's a callback to close current wallet, call it first
@@ -46,7 +46,7 @@ export const useTrayIntegration = (options: TrayIntegrationOptions = {}) => {
         });
 
         // Listen for wallet closed event
-        const unlistenWalletClosed = await listen('wallet-closed', () => {
+        const unlistenWalletClosed = await listenEvent('wallet-closed', () => {
           console.log('Wallet was closed from tray');
           if (onWalletClosed) {
             onWalletClosed();
@@ -0,0 +1,66 @@
+/** Error kinds returned by backend commands; mirrors AppErrorCode in errors.rs */
+export type AppErrorCode =
+  | 'WALLET_NOT_FOUND'
+  | 'WALLET_ALREADY_EXISTS'
+  | 'WALLET_IN_USE'
+  | 'WALLET_READ_ONLY'
+  | 'NO_WALLET_OPEN'
+  | 'PASSWORD_REQUIRED'
+  | 'INVALID_PASSWORD'
+  | 'ACCESS_DENIED'
+  | 'KEY_DERIVATION_FAILED'
+  | 'INVALID_OPERATION'
+  | 'CONFIG_LOAD_FAILED'
+  | 'CONFIG_SAVE_FAILED'
+  | 'CONFIG_INVALID'
+  | 'CONFIG_UNSUPPORTED_VERSION'
+  | 'AUTHENTICATION_FAILED'
+  | 'ENCRYPTION_FAILED'
+  | 'NETWORK_ERROR'
+  | 'SERVICE_UNAVAILABLE'
+  | 'NOT_FOUND'
+  | 'FILE_NOT_FOUND'
+  | 'PERMISSION_DENIED'
+  | 'IO_ERROR'
+  | 'SERIALIZATION_ERROR'
+  | 'INVALID_INPUT'
+  | 'MISSING_ADDRESS'
+  | 'INVALID_ADDRESS'
+  | 'WRONG_NETWORK'
+  | 'INVALID_AMOUNT'
+  | 'ZERO_AMOUNT'
+  | 'AMOUNT_TOO_LARGE'
+  | 'INSUFFICIENT_FUNDS'
+  | 'UTXO_FROZEN'
+  | 'WEAK_PASSWORD'
+  | 'SPENDING_LIMIT_EXCEEDED'
+  | 'SPEND_CONFIRMATION_REQUIRED'
+  | 'COOLING_OFF_PENDING'
+  | 'INTERNAL';
+
+/** Error every backend command rejects with */
+export interface CommandError {
+  code: AppErrorCode;
+  message: string;
+  details?: string;
+}
+
+export function isCommandError(error: unknown): error is CommandError {
+  return typeof error === 'object' && error !== null && 'code' in error && 'message' in error;
+}
+
+/** Readable message for anything a command or other code may throw */
+export function getErrorMessage(error: unknown): string {
+  if (isCommandError(error)) {
+    return error.message;
+  }
+  if (error instanceof Error) {
+    return error.message;
+  }
+  return String(error);
+}
+
+/** Whether a thrown value is a command error with the given code */
+export function hasErrorCode(error: unknown, code: AppErrorCode): boolean {
+  return isCommandError(error) && error.code === code;
+}
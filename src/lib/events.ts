@@ -0,0 +1,83 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+/** Events the backend emits; mirrors the names in events.rs */
+export type BackendEvent =
+  | 'address-index-progress'
+  | 'app-initialization-error'
+  | 'app-ready'
+  | 'app-shutdown-complete'
+  | 'blockchain-services-ready'
+  | 'blockchain-setup-error'
+  | 'blockchain-setup-required'
+  | 'blockchain-status'
+  | 'clipboard-cleared'
+  | 'clock-skew-changed'
+  | 'config-recovered'
+  | 'config-version-unsupported'
+  | 'database-migration-progress'
+  | 'exchange-rates-updated'
+  | 'mempool-update'
+  | 'mining-status'
+  | 'network-stats'
+  | 'payment-request-paid'
+  | 'payment-request-updated'
+  | 'payment-uri-error'
+  | 'payment-uri-received'
+  | 'power-state-changed'
+  | 'private-key-revealed'
+  | 'scheduled-payment-due'
+  | 'scheduled-payment-sent'
+  | 'scheduled-payment-skipped'
+  | 'second-instance'
+  | 'sync-stalled'
+  | 'tray-create-wallet'
+  | 'tray-open-wallet'
+  | 'update-available'
+  | 'update-download-progress'
+  | 'update-ready'
+  | 'wallet-closed'
+  | 'wallet-coins-received'
+  | 'wallet-locked'
+  | 'wallet-renamed'
+  | 'wallet-session-expired'
+  | 'wallet-sync-status'
+  | 'wallets-deleted';
+
+/** Wrapper every backend event arrives in */
+export interface EventEnvelope<T> {
+  event: BackendEvent;
+  /** Version of the payload format */
+  version: number;
+  /** Milliseconds since the Unix epoch */
+  timestamp: number;
+  payload: T;
+}
+
+/** An event as listed by subscribe_events */
+export interface EventDescriptor {
+  name: BackendEvent;
+  version: number;
+  /** Rust type of the payload, or "null" for events that only signal something */
+  payload: string;
+  description: string;
+}
+
+/** Payload of blockchain-setup-required */
+export interface BlockchainSetupRequired {
+  reason: 'no_default_location' | 'auto_create_failed';
+  message: string;
+}
+
+/** Listen to a backend event, receiving its payload unwrapped from the envelope */
+export function listenEvent<T = null>(
+  event: BackendEvent,
+  handler: (payload: T, envelope: EventEnvelope<T>) => void
+): Promise<UnlistenFn> {
+  return listen<EventEnvelope<T>>(event, (e) => handler(e.payload.payload, e.payload));
+}
+
+/** Every event the backend emits (developer command) */
+export async function subscribeEvents(): Promise<EventDescriptor[]> {
+  return invoke('subscribe_events');
+}
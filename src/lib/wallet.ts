@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
+import type { AddressPage, ExchangeRate, FiatAmount } from '../types/wallet';
 
 export interface WalletStatus {
   isOpen: boolean;
@@ -15,8 +16,16 @@ export async function openWallet(name: string, password?: string): Promise<boole
   return invoke('open_wallet', { walletName: name, password });
 }
 
-export async function closeWallet(): Promise<boolean> {
-  return invoke('close_wallet');
+export async function closeWallet(walletName?: string): Promise<boolean> {
+  return invoke('close_wallet', { walletName });
+}
+
+export async function getOpenWallets(): Promise<string[]> {
+  return invoke('get_open_wallets');
+}
+
+export async function setActiveWallet(walletName: string): Promise<boolean> {
+  return invoke('set_active_wallet', { walletName });
 }
 
 export async function getWalletStatus(): Promise<WalletStatus> {
@@ -36,13 +45,15 @@ export async function createWallet(
   name: string, 
   password: string, 
   usePassword: boolean, 
-  seedPhrase?: string
+  seedPhrase?: string,
+  passphrase?: string
 ): Promise<boolean> {
   return invoke('create_wallet', { 
     walletName: name, 
     password, 
     usePassword, 
-    seedPhrase
+    seedPhrase,
+    passphrase
   });
 }
 
@@ -54,20 +65,974 @@ export async function isCurrentWalletSecured(): Promise<boolean | null> {
   return invoke('is_current_wallet_secured');
 }
 
-export async function generateSeedPhrase(): Promise<string> {
-  return invoke('generate_seed_phrase');
+export async function generateSeedPhrase(wordCount?: 12 | 15 | 18 | 21 | 24): Promise<string> {
+  return invoke('generate_seed_phrase', { wordCount });
 }
 
 export async function recoverWallet(
   name: string, 
   seedPhrase: string, 
   password: string, 
-  usePassword: boolean
+  usePassword: boolean,
+  passphrase?: string
 ): Promise<boolean> {
   return invoke('recover_wallet', { 
     walletName: name, 
     seedPhrase, 
     password, 
-    usePassword 
+    usePassword,
+    passphrase
+  });
+}
+
+export async function checkWalletPassphrase(passphrase: string): Promise<boolean> {
+  return invoke('check_wallet_passphrase', { passphrase });
+}
+
+export async function getSeedVerificationChallenge(walletName: string): Promise<number[]> {
+  return invoke('get_seed_verification_challenge', { walletName });
+}
+
+export async function verifySeedChallenge(
+  answers: Record<number, string>,
+  password?: string
+): Promise<boolean> {
+  return invoke('verify_seed_challenge', { answers, password });
+}
+
+export async function isSeedBackupVerified(): Promise<boolean> {
+  return invoke('is_seed_backup_verified');
+}
+
+export interface WalletSettings {
+  auto_sync_on_open: boolean;
+  mining_address: string | null;
+  /** Confirmations a received coin needs before it can be spent */
+  min_confirmations: number;
+  spending_policy: SpendingPolicy;
+}
+
+/** Limits on sends from a wallet, in satoshis; null turns a limit off */
+export interface SpendingPolicy {
+  daily_limit: number | null;
+  /** Sends above this need the wallet password typed again */
+  confirm_password_above: number | null;
+  /** Sends above this are held back for cooling_off_secs and go through when repeated afterwards */
+  cooling_off_above: number | null;
+  cooling_off_secs: number;
+}
+
+export async function getWalletSettings(walletName?: string): Promise<WalletSettings> {
+  return invoke('get_wallet_settings', { walletName });
+}
+
+/** Relaxing the spending policy of a secured wallet needs its password */
+export async function updateWalletSettings(
+  settings: WalletSettings,
+  walletName?: string,
+  password?: string
+): Promise<boolean> {
+  return invoke('update_wallet_settings', { settings, walletName, password });
+}
+
+export interface WalletUtxo {
+  txid: string;
+  vout: number;
+  value: number;
+  script_pubkey: string;
+  address: string;
+  is_change: boolean;
+  height: number | null;
+}
+
+export interface CoinSelection {
+  inputs: WalletUtxo[];
+  total_input: number;
+  fee: number;
+  /** Value returned to the wallet; zero when the inputs match the payment exactly */
+  change: number;
+}
+
+/**
+ * Coins that would pay `amount` satoshis, skipping frozen coins and coins below the minimum
+ * confirmations. Pass `outpoints` ("txid:vout") to spend exactly those coins.
+ */
+export async function selectCoinsForPayment(
+  amount: number,
+  feeRate: number,
+  outpoints?: string[],
+  walletName?: string
+): Promise<CoinSelection> {
+  return invoke('select_coins_for_payment', { amount, feeRate, outpoints, walletName });
+}
+
+export interface TransactionPreview {
+  /** Pass to commitTransaction */
+  id: string;
+  wallet_name: string;
+  amount: number;
+  inputs: WalletUtxo[];
+  total_input: number;
+  /** Recipient first, then change if there is any */
+  outputs: { value: number; script_pubkey: string; address: string }[];
+  change: number;
+  fee: number;
+  /** Estimated size in bytes */
+  size: number;
+  /** Fee per byte actually paid, at least the requested rate */
+  effective_fee_rate: number;
+  created_at: number;
+  expires_at: number;
+}
+
+/** Choose the coins for a payment without signing, to show on the confirmation screen */
+export async function previewTransaction(
+  recipient: string,
+  amount: number,
+  feeRate: number,
+  outpoints?: string[],
+  walletName?: string
+): Promise<TransactionPreview> {
+  return invoke('preview_transaction', { recipient, amount, feeRate, outpoints, walletName });
+}
+
+/** Sign and broadcast a preview, returning the txid; a failed commit can be retried until it expires */
+export async function commitTransaction(previewId: string, confirmPassword?: string): Promise<string> {
+  return invoke('commit_transaction', { previewId, confirmPassword });
+}
+
+export interface WalletUtxoInfo {
+  /** "txid:vout" */
+  outpoint: string;
+  txid: string;
+  vout: number;
+  address: string;
+  amount: number;
+  confirmations: number;
+  /** Label of the receiving address */
+  label: string | null;
+  is_change: boolean;
+  frozen: boolean;
+  spendable: boolean;
+}
+
+export async function listUtxos(walletName?: string): Promise<WalletUtxoInfo[]> {
+  return invoke('list_utxos', { walletName });
+}
+
+export async function freezeUtxo(outpoint: string, walletName?: string): Promise<boolean> {
+  return invoke('freeze_utxo', { outpoint, walletName });
+}
+
+export async function unfreezeUtxo(outpoint: string, walletName?: string): Promise<boolean> {
+  return invoke('unfreeze_utxo', { outpoint, walletName });
+}
+
+export interface ConsolidationResult {
+  /** null for a dry run */
+  txid: string | null;
+  destination_address: string;
+  inputs_consolidated: number;
+  total_input: number;
+  fee: number;
+  output_value: number;
+  utxo_count_before: number;
+  utxo_count_after: number;
+}
+
+/** Merge small coins into one; pass dryRun to preview the fee and resulting coin count first */
+export async function consolidateUtxos(
+  feeRate: number,
+  maxInputs?: number,
+  dryRun?: boolean,
+  walletName?: string
+): Promise<ConsolidationResult> {
+  return invoke('consolidate_utxos', { feeRate, maxInputs, dryRun, walletName });
+}
+
+export interface RekeyResult {
+  /** null for a dry run or when nothing on the retired addresses was worth sweeping */
+  txid: string | null;
+  destination_address: string;
+  addresses_retired: number;
+  inputs_swept: number;
+  total_input: number;
+  fee: number;
+  amount_sent: number;
+  /** Coins still on retired addresses: frozen, unconfirmed or worth less than their fee */
+  coins_left_behind: number;
+}
+
+/**
+ * Move the wallet onto a new seed (from generateSeedPhrase, written down by the user first) and
+ * sweep its funds there. Pass dryRun to preview the sweep; secured wallets need their password.
+ */
+export async function rekeyWallet(
+  newSeedPhrase: string,
+  feeRate: number,
+  passphrase?: string,
+  password?: string,
+  dryRun?: boolean,
+  walletName?: string
+): Promise<RekeyResult> {
+  return invoke('rekey_wallet', { newSeedPhrase, passphrase, feeRate, password, dryRun, walletName });
+}
+
+/** Sweep coins that reached the retired addresses of a re-keyed wallet to its current seed */
+export async function sweepRetiredAddresses(
+  feeRate: number,
+  dryRun?: boolean,
+  walletName?: string
+): Promise<RekeyResult> {
+  return invoke('sweep_retired_addresses', { feeRate, dryRun, walletName });
+}
+
+export async function renameWallet(oldName: string, newName: string): Promise<boolean> {
+  return invoke('rename_wallet', { oldName, newName });
+}
+
+export interface SettingChange {
+  key: string;
+  current: unknown;
+  incoming: unknown;
+}
+
+export interface SettingsImportResult {
+  applied: boolean;
+  changes: SettingChange[];
+}
+
+export async function exportSettings(path: string, includeMachinePaths?: boolean): Promise<boolean> {
+  return invoke('export_settings', { path, includeMachinePaths });
+}
+
+export async function importSettings(
+  path: string,
+  dryRun?: boolean,
+  includeMachinePaths?: boolean
+): Promise<SettingsImportResult> {
+  return invoke('import_settings', { path, dryRun, includeMachinePaths });
+}
+
+/** Replace the push API token; clients using the old token are disconnected */
+export async function regeneratePushApiToken(): Promise<string> {
+  return invoke('regenerate_push_api_token');
+}
+
+export interface CommandStats {
+  command: string;
+  calls: number;
+  errors: number;
+  /** Share of calls that returned an error, from 0 to 1 */
+  error_rate: number;
+  total_ms: number;
+  mean_ms: number;
+  max_ms: number;
+  /** Calls per duration bucket; `le_ms` is null for the last, unbounded bucket */
+  buckets: { le_ms: number | null; count: number }[];
+}
+
+/** Call counts, latencies and error rates of backend commands since startup, slowest in total first */
+export async function getCommandMetrics(): Promise<CommandStats[]> {
+  return invoke('get_command_metrics');
+}
+
+export interface AddressTx {
+  txid: string;
+  block_height: number;
+}
+
+export interface AddressIndexStatus {
+  enabled: boolean;
+  /** Blocks below this height are indexed */
+  indexed_height: number;
+  tip_height: number;
+  /** Enabled and covering every block */
+  complete: boolean;
+}
+
+/** Transactions touching an address, newest first. Fails unless the address index is enabled and built. */
+export async function getAddressHistory(address: string, limit?: number): Promise<AddressTx[]> {
+  return invoke('get_address_history', { address, limit });
+}
+
+export async function getAddressIndexStatus(): Promise<AddressIndexStatus> {
+  return invoke('get_address_index_status');
+}
+
+export interface BlockCacheStats {
+  entries: number;
+  bytes: number;
+  capacity_bytes: number;
+  hits: number;
+  misses: number;
+  /** Share of lookups answered from the cache, 0 before the first lookup */
+  hit_rate: number;
+}
+
+export interface DatabaseStats {
+  backend: 'sled' | 'redb';
+  /** Committed records per tree */
+  trees: Record<string, number>;
+  size_on_disk: number;
+  /** Cache of the storage engine, as opened */
+  store_cache_bytes: number;
+  block_cache: BlockCacheStats;
+  /** Bytes of writes staged for the next commit */
+  staged_bytes: number;
+  /** Caches are shrunk because the system is short of memory */
+  low_memory: boolean;
+}
+
+/** Size and cache use of the blockchain database */
+export async function getDatabaseStats(): Promise<DatabaseStats> {
+  return invoke('get_database_stats');
+}
+
+export interface RelayPolicy {
+  /** sat/byte */
+  min_relay_fee_rate: number;
+  /** Bytes */
+  max_standard_tx_size: number;
+  /** Satoshis; data carrier outputs are exempt */
+  dust_threshold: number;
+  relay_non_standard_scripts: boolean;
+  /** Transactions from peers are refused */
+  blocks_only: boolean;
+}
+
+export interface ServiceHealth {
+  blockchain_services_running: boolean;
+  mempool_transactions: number;
+  /** Fee rate a new transaction needs now, in sat/byte */
+  mempool_min_fee_rate: number;
+  relay_policy: RelayPolicy;
+}
+
+/** Whether the blockchain services run, and the relay policy in force */
+export async function getServiceHealth(): Promise<ServiceHealth> {
+  return invoke('get_service_health');
+}
+
+export async function getExchangeRate(currency: string): Promise<ExchangeRate | null> {
+  return invoke('get_exchange_rate', { currency });
+}
+
+export async function convertToFiat(amounts: number[]): Promise<(FiatAmount | null)[]> {
+  return invoke('convert_to_fiat', { amounts });
+}
+
+export interface CostBasisLot {
+  acquired_at: number | null;
+  disposed_at: number;
+  amount: number;
+  cost_basis: number;
+  proceeds: number;
+  gain: number;
+  price_missing: boolean;
+}
+
+export interface CostBasisReport {
+  method: 'Fifo' | 'AverageCost';
+  currency: string;
+  total_proceeds: number;
+  total_cost_basis: number;
+  total_gain: number;
+  remaining_amount: number;
+  lots_missing_price: number;
+  lots: CostBasisLot[];
+}
+
+export async function generateCostBasisReport(request: {
+  method: 'fifo' | 'average_cost';
+  currency?: string;
+  from?: number;
+  to?: number;
+  exportPath?: string;
+  walletName?: string;
+}): Promise<CostBasisReport> {
+  const { exportPath, walletName, ...rest } = request;
+  return invoke('generate_cost_basis_report', {
+    request: { ...rest, export_path: exportPath, wallet_name: walletName },
   });
 }
+
+export interface UpdateInfo {
+  version: string;
+  current_version: string;
+  channel: 'stable' | 'beta';
+  notes: string | null;
+  date: string | null;
+  downloaded: boolean;
+}
+
+export async function checkForUpdates(channel?: 'stable' | 'beta'): Promise<UpdateInfo | null> {
+  return invoke('check_for_updates', { channel });
+}
+
+export async function getPendingUpdate(): Promise<UpdateInfo | null> {
+  return invoke('get_pending_update');
+}
+
+/** Installs the pending update and restarts the app. Only call after the user confirmed. */
+export async function installPendingUpdate(): Promise<boolean> {
+  return invoke('install_pending_update');
+}
+
+export interface PaymentUri {
+  address: string;
+  /** Requested amount in satoshis */
+  amount: number | null;
+  label: string | null;
+  message: string | null;
+}
+
+export async function parsePaymentUri(uri: string): Promise<PaymentUri> {
+  return invoke('parse_payment_uri', { uri });
+}
+
+/** Payment link opened from outside the app that the send screen has not taken yet */
+export async function takePendingPaymentUri(): Promise<PaymentUri | null> {
+  return invoke('take_pending_payment_uri');
+}
+
+export interface PaymentDetails {
+  address: string;
+  /** Amount in satoshis */
+  amount: number;
+}
+
+/** Check a recipient and decimal amount; errors start with a code such as "WRONG_NETWORK:" */
+export async function validatePaymentDetails(address: string, amount: string): Promise<PaymentDetails> {
+  return invoke('validate_payment_details', { address, amount });
+}
+
+/** Where the node is in following the network */
+export type SyncState = 'disconnected' | 'syncing' | 'synced';
+
+/** State of the node, as returned by get_network_status and sent with blockchain-status */
+export interface NodeStatus {
+  /** Height of the local chain tip */
+  chain_height: number;
+  /** Height of the best validated header; ahead of the chain while blocks download */
+  header_height: number;
+  /** Best height reported by peers */
+  network_height: number;
+  peer_count: number;
+  is_connected: boolean;
+  sync_state: SyncState;
+  mempool_transactions: number;
+  mempool_bytes: number;
+  /** Wallets currently mining */
+  mining_wallets: number;
+  /** Combined hash rate of every wallet mining, in hashes per second */
+  hash_rate: number;
+}
+
+export async function getNetworkStatus(): Promise<NodeStatus> {
+  return invoke('get_network_status');
+}
+
+export interface PeerDetails {
+  address: string;
+  direction: 'inbound' | 'outbound';
+  user_agent: string | null;
+  protocol_version: string | null;
+  height: number | null;
+  connected_at: number;
+  last_seen: number;
+  /** Moving average ping in milliseconds, 0 until measured */
+  ping_ms: number;
+  blocks_received: number;
+  transactions_received: number;
+  invalid_messages: number;
+  connection_failures: number;
+  /** Times block download stalled on this peer and was moved elsewhere */
+  sync_stalls: number;
+  last_valid_block: number;
+  uptime_percentage: number;
+  base_score: number;
+  /** Overall score (0-1000) used to choose and drop peers */
+  total_score: number;
+  /** Features negotiated in the handshake, once it happened */
+  features: PeerFeatures | null;
+}
+
+export interface PeerFeatures {
+  protocol_version: number;
+  /** Service bits the peer advertised */
+  services: number;
+  /** Serves the whole chain */
+  full_blocks: boolean;
+  /** Serves recent blocks only */
+  limited_blocks: boolean;
+  compact_filters: boolean;
+  compact_blocks: boolean;
+}
+
+/** Connected peers with their score breakdown, best first */
+export async function getPeerDetails(): Promise<PeerDetails[]> {
+  return invoke('get_peer_details');
+}
+
+export interface ReachabilityStatus {
+  /** Address peers see this node at, once enough of them agree */
+  external_address: string | null;
+  /** Peers that reported `external_address` */
+  reports: number;
+  /** Whether any inbound connection has been accepted since the node started */
+  inbound_ever_succeeded: boolean;
+  inbound_connections: number;
+  /** Unix time of the last accepted inbound connection */
+  last_inbound_at: number | null;
+}
+
+/** Whether this node is reachable from the internet, and at which address */
+export async function getExternalAddress(): Promise<ReachabilityStatus> {
+  return invoke('get_external_address');
+}
+
+/** Payload of `clock-skew-changed` */
+export interface ClockSkewStatus {
+  /** Median of peer clocks minus ours once enough peers reported; positive means ours is behind */
+  peer_offset_secs: number | null;
+  /** Peers that reported their clock */
+  peer_samples: number;
+  /** How far the newest block's timestamp is ahead of our clock, if it is */
+  block_time_ahead_secs: number | null;
+  /** Offset applied to the local clock when checking block timestamps */
+  applied_offset_secs: number;
+  /** Whether the local clock is off by more than five minutes */
+  skewed: boolean;
+}
+
+/** How the local clock compares with the clocks of peers */
+export async function getClockStatus(): Promise<ClockSkewStatus> {
+  return invoke('get_clock_status');
+}
+
+/** Lowest fee rate (sat/byte) the mempool accepts right now; fees below it are rejected */
+export async function getMempoolMinFee(): Promise<number> {
+  return invoke('get_mempool_min_fee');
+}
+
+export interface SupplyInfo {
+  height: number;
+  /** Subsidy of the next block, in satoshis */
+  block_subsidy: number;
+  halvings: number;
+  next_halving_height: number | null;
+  expected_supply: number;
+  utxo_supply: number;
+  utxo_count: number;
+  max_supply: number;
+  /** Issued but not in the UTXO set, e.g. fees no coinbase claimed */
+  unclaimed: number;
+  /** False if the UTXO set holds more coins than the schedule has issued */
+  within_schedule: boolean;
+}
+
+/** Audit the coin supply in the UTXO set against the subsidy schedule */
+export async function getSupplyInfo(): Promise<SupplyInfo> {
+  return invoke('get_supply_info');
+}
+
+export interface ScheduledPayment {
+  id: string;
+  wallet_name: string;
+  recipient: string;
+  amount: number;
+  /** sat/byte */
+  fee_rate: number;
+  interval_secs: number;
+  label: string | null;
+  /** Wait for confirmScheduledPayment instead of sending when due */
+  require_confirmation: boolean;
+  enabled: boolean;
+  /** Unix timestamp the next payment is due */
+  next_due: number;
+  last_sent_at: number | null;
+  last_txid: string | null;
+  due_notified: boolean;
+  /** Why the due payment is held back, e.g. "wallet_locked" or "not_synced" */
+  skip_reason: string | null;
+}
+
+export interface NewScheduledPayment {
+  wallet_name: string;
+  recipient: string;
+  amount: number;
+  fee_rate: number;
+  interval_secs: number;
+  label?: string;
+  require_confirmation?: boolean;
+  /** Unix timestamp of the first payment; now when omitted */
+  first_due?: number;
+}
+
+/** Payload of the scheduled-payment-due, -sent and -skipped events */
+export interface ScheduledPaymentEvent {
+  id: string;
+  wallet_name: string;
+  recipient: string;
+  amount: number;
+  due_at: number;
+  txid: string | null;
+  reason: 'wallet_locked' | 'not_synced' | 'payment_failed' | 'skipped_by_user' | null;
+  message: string | null;
+  awaiting_confirmation: boolean;
+}
+
+export async function listScheduledPayments(): Promise<ScheduledPayment[]> {
+  return invoke('list_scheduled_payments');
+}
+
+export async function createScheduledPayment(payment: NewScheduledPayment): Promise<ScheduledPayment> {
+  return invoke('create_scheduled_payment', { payment });
+}
+
+export async function deleteScheduledPayment(id: string): Promise<boolean> {
+  return invoke('delete_scheduled_payment', { id });
+}
+
+export async function setScheduledPaymentEnabled(id: string, enabled: boolean): Promise<ScheduledPayment> {
+  return invoke('set_scheduled_payment_enabled', { id, enabled });
+}
+
+/** Send a due payment that waits for confirmation; resolves to the txid */
+export async function confirmScheduledPayment(id: string): Promise<string> {
+  return invoke('confirm_scheduled_payment', { id });
+}
+
+export async function skipScheduledPayment(id: string): Promise<ScheduledPayment> {
+  return invoke('skip_scheduled_payment', { id });
+}
+
+export type PaymentRequestStatus = 'pending' | 'partial' | 'paid' | 'expired';
+
+export interface PaymentRequest {
+  id: string;
+  address: string;
+  /** Requested amount in satoshis */
+  amount: number;
+  label: string | null;
+  message: string | null;
+  /** brad:// URI to share with the payer */
+  uri: string;
+  created_at: number;
+  expires_at: number | null;
+  status: PaymentRequestStatus;
+  /** Satoshis received so far */
+  received: number;
+  paid_at: number | null;
+  outputs: string[];
+}
+
+/** Payload of the payment-request-paid and payment-request-updated events */
+export interface PaymentRequestEvent {
+  wallet_name: string;
+  request: PaymentRequest;
+}
+
+export async function createPaymentRequest(
+  amount: number,
+  label?: string,
+  message?: string,
+  expirySecs?: number,
+  walletName?: string
+): Promise<PaymentRequest> {
+  return invoke('create_payment_request', { amount, label, message, expirySecs, walletName });
+}
+
+export async function listPaymentRequests(walletName?: string): Promise<PaymentRequest[]> {
+  return invoke('list_payment_requests', { walletName });
+}
+
+export async function deletePaymentRequest(id: string, walletName?: string): Promise<boolean> {
+  return invoke('delete_payment_request', { id, walletName });
+}
+
+export type ActivityKind =
+  | 'wallet_opened'
+  | 'address_created'
+  | 'transaction_sent'
+  | 'transaction_received'
+  | 'backup_verified'
+  | 'password_changed';
+
+/** One thing that happened to a wallet, from get_wallet_activity */
+export interface ActivityEntry {
+  kind: ActivityKind;
+  timestamp: number;
+  address: string | null;
+  txid: string | null;
+  /** Amount in satoshis */
+  amount: number | null;
+}
+
+/** A wallet's activity, newest first */
+export async function getWalletActivity(walletId: string, limit?: number): Promise<ActivityEntry[]> {
+  return invoke('get_wallet_activity', { walletId, limit });
+}
+
+/** Someone the user pays, by name */
+export interface Contact {
+  name: string;
+  address: string;
+  note: string | null;
+}
+
+/** What importWalletLabels changed */
+export interface LabelImportSummary {
+  labels_updated: number;
+  notes_updated: number;
+  contacts_updated: number;
+  /** Labels and notes for addresses or transactions this copy does not know yet */
+  skipped: number;
+}
+
+/** Write address labels, transaction notes and contacts to a signed file, without any keys */
+export async function exportWalletLabels(path: string, walletName?: string): Promise<boolean> {
+  return invoke('export_wallet_labels', { path, walletName });
+}
+
+/** Merge a label file exported from another copy of the same wallet */
+export async function importWalletLabels(path: string, walletName?: string): Promise<LabelImportSummary> {
+  return invoke('import_wallet_labels', { path, walletName });
+}
+
+export interface OwnershipProof {
+  address: string;
+  message: string;
+  /** Base64 BIP322 signature */
+  signature: string;
+}
+
+/** Sign a message with the key of one of the wallet's addresses (BIP322) */
+export async function createOwnershipProof(
+  address: string,
+  message: string,
+  walletName?: string
+): Promise<OwnershipProof> {
+  return invoke('create_ownership_proof', { address, message, walletName });
+}
+
+/** Check a BIP322 ownership proof from any wallet */
+export async function verifyOwnershipProof(proof: OwnershipProof): Promise<boolean> {
+  return invoke('verify_ownership_proof', { proof });
+}
+
+export async function listContacts(walletName?: string): Promise<Contact[]> {
+  return invoke('list_contacts', { walletName });
+}
+
+/** Add a contact, replacing any contact with the same address */
+export async function saveContact(contact: Contact, walletName?: string): Promise<boolean> {
+  return invoke('save_contact', { contact, walletName });
+}
+
+export async function removeContact(address: string, walletName?: string): Promise<boolean> {
+  return invoke('remove_contact', { address, walletName });
+}
+
+/** One security-sensitive operation in the audit log */
+export interface AuditRecord {
+  seq: number;
+  timestamp: number;
+  event: string;
+  details: string;
+  prev_hash: string;
+  hash: string;
+}
+
+export interface AuditVerification {
+  valid: boolean;
+  entries: number;
+  /** Line number, from 1, of the first record that breaks the hash chain */
+  first_invalid_line: number | null;
+  error: string | null;
+}
+
+export interface AuditLogView {
+  /** Newest first */
+  records: AuditRecord[];
+  verification: AuditVerification;
+}
+
+/** Read the security audit log and verify its hash chain */
+export async function getAuditLog(limit?: number): Promise<AuditLogView> {
+  return invoke('get_audit_log', { limit });
+}
+
+/** Estimated strength of a wallet password */
+export interface PasswordStrength {
+  /** 0 (too guessable) to 4 (very unguessable) */
+  score: number;
+  entropy_bits: number;
+  /** Seconds an offline attacker needs at 10,000 guesses per second */
+  crack_time_seconds: number;
+  /** The same estimate for display, e.g. "3 hours" */
+  crack_time_display: string;
+  warning: string | null;
+  suggestions: string[];
+  /** Whether the score reaches the minimum configured in settings */
+  meets_minimum: boolean;
+}
+
+/** Estimate how hard a password is to guess; the wallet name counts against it */
+export async function evaluatePasswordStrength(password: string, walletName?: string): Promise<PasswordStrength> {
+  return invoke('evaluate_password_strength', { password, walletName });
+}
+
+/**
+ * Give a secured wallet a duress password. Unlocking with it opens a decoy wallet derived from
+ * `decoySeedPhrase` instead of the real one.
+ */
+export async function setDuressPassword(
+  walletName: string,
+  password: string,
+  duressPassword: string,
+  decoySeedPhrase: string
+): Promise<boolean> {
+  return invoke('set_duress_password', { walletName, password, duressPassword, decoySeedPhrase });
+}
+
+/** Remove a wallet's duress password and decoy wallet */
+export async function removeDuressPassword(walletName: string, password: string): Promise<boolean> {
+  return invoke('remove_duress_password', { walletName, password });
+}
+
+/** Why mining is paused by the power monitor */
+export type MiningPauseReason = 'sleep' | 'battery';
+
+/** Payload of `power-state-changed` */
+export interface PowerStatus {
+  /** null when the power source is unknown, e.g. on a desktop */
+  on_battery: boolean | null;
+  mining_paused: MiningPauseReason | null;
+  /** Wallets that resume mining once the pause ends */
+  paused_wallets: string[];
+}
+
+/** Get the power source and whether mining is paused for sleep or battery power */
+export async function getPowerStatus(): Promise<PowerStatus> {
+  return invoke('get_power_status');
+}
+
+/** A candidate block for an external miner */
+export interface BlockTemplate {
+  height: number;
+  previous_hash: string;
+  timestamp: number;
+  difficulty: number;
+  /** Highest acceptable value of the first 8 bytes of the header hash, as hex */
+  target: string;
+  bits: number;
+  /** Coinbase first, then mempool transactions */
+  transactions: unknown[];
+  merkle_root: string;
+  coinbase_value: number;
+  size_limit: number;
+}
+
+/** Get a candidate block paying the reward to `miningAddress` */
+export async function getBlockTemplate(miningAddress: string): Promise<BlockTemplate> {
+  return invoke('get_block_template', { miningAddress });
+}
+
+/** Submit an externally mined block (hex of its bincode encoding), returning its hash */
+export async function submitBlock(blockHex: string): Promise<string> {
+  return invoke('submit_block', { blockHex });
+}
+
+export interface StratumWorker {
+  worker: string;
+  /** Connections currently authorized as this worker */
+  connections: number;
+  accepted_shares: number;
+  rejected_shares: number;
+  blocks_found: number;
+  last_share_at: number | null;
+}
+
+/** Share statistics of miners connected through the stratum server */
+export async function getStratumWorkers(): Promise<StratumWorker[]> {
+  return invoke('get_stratum_workers');
+}
+
+export interface MinedBlock {
+  height: number;
+  hash: string;
+  /** Address the coinbase pays */
+  address: string;
+  timestamp: number;
+  /** Subsidy plus fees, in satoshis */
+  reward: number;
+  /** Replaced at its height by another block, so the reward was lost */
+  orphaned: boolean;
+}
+
+export interface MiningRewards {
+  /** Newest first */
+  blocks: MinedBlock[];
+  /** Rewards of the blocks still on the chain */
+  total_reward: number;
+  orphaned_blocks: number;
+}
+
+/** Blocks a wallet mined between `from` and `to` (Unix timestamps, inclusive) */
+export async function getMiningRewards(walletId: string, from?: number, to?: number): Promise<MiningRewards> {
+  return invoke('get_mining_rewards', { walletId, from: from ?? null, to: to ?? null });
+}
+
+export interface MiningIntensity {
+  /** Share of the time mining threads hash, in percent */
+  duty_cycle: number;
+  /** Hash at the lowest OS thread priority */
+  low_priority: boolean;
+}
+
+/** Change how hard mining works the CPU; applies to wallets already mining and is saved */
+export async function setMiningIntensity(dutyCycle: number, lowPriority: boolean): Promise<MiningIntensity> {
+  return invoke('set_mining_intensity', { dutyCycle, lowPriority });
+}
+
+/** Change how long wallets stay unlocked without use, and how long the app may sit idle before
+ * every wallet locks; applies immediately and is saved */
+export async function setAuthTimeout(timeoutMinutes: number): Promise<void> {
+  return invoke('set_auth_timeout', { timeoutMinutes });
+}
+
+export interface BlockStats {
+  height: number;
+  timestamp: number;
+  difficulty: number;
+  /** Seconds since the parent block */
+  solve_time: number;
+  transaction_count: number;
+}
+
+export interface NetworkHashRate {
+  from_height: number;
+  to_height: number;
+  /** Hashes per second */
+  hash_rate: number;
+  /** Seconds between blocks over the window */
+  average_solve_time: number;
+}
+
+/** Difficulty and solve time per block, by default of the last 1000 blocks */
+export async function getDifficultyHistory(fromHeight?: number, toHeight?: number): Promise<BlockStats[]> {
+  return invoke('get_difficulty_history', { fromHeight: fromHeight ?? null, toHeight: toHeight ?? null });
+}
+
+/** Network hash rate estimated over the last `window` blocks */
+export async function getNetworkHashrateEstimate(window?: number): Promise<NetworkHashRate> {
+  return invoke('get_network_hashrate_estimate', { window: window ?? null });
+}
+
+/** One page of an open wallet's addresses, the active one by default: receiving addresses first,
+ * Native SegWit before other types, then by derivation path */
+export async function getWalletAddresses(page: number, walletName?: string): Promise<AddressPage> {
+  return invoke('get_wallet_addresses', { walletName, page });
+}
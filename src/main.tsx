@@ -3,7 +3,8 @@ import ReactDOM from 'react-dom/client';
 import App from './App';
 import './App.css';
 import { invoke } from '@tauri-apps/api/core';
-import { listen } from '@tauri-apps/api/event';
+import { listenEvent } from './lib/events';
+import { getErrorMessage } from './lib/errors';
 
 // Application state
 const AppInitializer = ({ children }: { children: React.ReactNode }) => {
@@ -14,7 +15,7 @@ const AppInitializer = ({ children }: { children: React.ReactNode }) => {
     // Setup application shutdown listener
     const setupShutdownListener = async () => {
       console.log('Setting up shutdown event listener');
-      return await listen('app-shutdown-complete', () => {
+      return await listenEvent('app-shutdown-complete', () => {
         console.log('App shutdown sequence completed, window will be closed by Rust backend...');
         // Let the Rust backend handle window closing
       });
@@ -41,7 +42,7 @@ const AppInitializer = ({ children }: { children: React.ReactNode }) => {
         setIsInitialized(true);
       } catch (error) {
         console.error('Failed to initialize application:', error);
-        setInitError(`Failed to initialize: ${error instanceof Error ? error.message : String(error)}`);
+        setInitError(`Failed to initialize: ${getErrorMessage(error)}`);
       }
     };
 
@@ -40,7 +40,8 @@ import {
   Label
 } from '@mui/icons-material';
 import { useWallet } from '../context/WalletContext';
-import type { CurrentWalletInfo } from '../types/wallet';
+import type { AddressDetails, CurrentWalletInfo } from '../types/wallet';
+import { getWalletAddresses } from '../lib/wallet';
 
 export default function Account() {
   const theme = useTheme();
@@ -48,6 +49,9 @@ export default function Account() {
   const { isWalletOpen, currentWallet } = useWallet();
   
   const [walletInfo, setWalletInfo] = useState<CurrentWalletInfo | null>(null);
+  const [addresses, setAddresses] = useState<AddressDetails[]>([]);
+  const [addressPages, setAddressPages] = useState(0);
+  const [isLoadingAddresses, setIsLoadingAddresses] = useState(false);
   const [isAddingKey, setIsAddingKey] = useState(false);
   const [editingLabel, setEditingLabel] = useState<string | null>(null);
   const [labelValue, setLabelValue] = useState('');
@@ -59,21 +63,46 @@ export default function Account() {
   // not in the configuration file, ensuring proper key separation
   useEffect(() => {
     if (isWalletOpen && currentWallet) {
-      loadWalletInfo();
+      loadWalletInfo(1);
     } else {
       setWalletInfo(null);
+      setAddresses([]);
+      setAddressPages(0);
     }
   }, [isWalletOpen, currentWallet]);
 
-  const loadWalletInfo = async () => {
+  // Addresses come a page at a time; a reload fetches as many pages as were shown, so editing a
+  // label does not collapse the list
+  const loadWalletInfo = async (pages = Math.max(addressPages, 1)) => {
     try {
       const info = await invoke<CurrentWalletInfo | null>('get_current_wallet_info');
       setWalletInfo(info);
+      let loaded: AddressDetails[] = [];
+      if (info) {
+        for (let page = 0; page < pages; page++) {
+          loaded = loaded.concat((await getWalletAddresses(page)).addresses);
+        }
+      }
+      setAddresses(loaded);
+      setAddressPages(info ? pages : 0);
     } catch (error) {
       console.error('Failed to load wallet info:', error);
     }
   };
 
+  const loadMoreAddresses = async () => {
+    setIsLoadingAddresses(true);
+    try {
+      const next = await getWalletAddresses(addressPages);
+      setAddresses(addresses.concat(next.addresses));
+      setAddressPages(addressPages + 1);
+    } catch (error) {
+      console.error('Failed to load addresses:', error);
+    } finally {
+      setIsLoadingAddresses(false);
+    }
+  };
+
   const copyToClipboard = async (text: string, description: string) => {
     try {
       await navigator.clipboard.writeText(text);
@@ -211,6 +240,12 @@ export default function Account() {
               >
                 Balance: {formatBalance(walletInfo.balance)} BRAD
               </Typography>
+              {walletInfo.pending_balance > 0 && (
+                <Typography variant="body2" color="text.secondary" sx={{ mt: -1, mb: 2 }}>
+                  Spendable: {formatBalance(walletInfo.spendable_balance)} BRAD · Pending:{' '}
+                  {formatBalance(walletInfo.pending_balance)} BRAD
+                </Typography>
+              )}
             </CardContent>
           </Card>
 
@@ -233,7 +268,7 @@ export default function Account() {
                 Addresses
               </Typography>
               
-              {walletInfo.addresses.map((address, index) => (
+              {addresses.map((address, index) => (
                 <Accordion key={index} sx={{ mb: 1 }}>
                   <AccordionSummary expandIcon={<ExpandMore />}>
                     <Box sx={{ width: '100%', display: 'flex', alignItems: 'center' }}>
@@ -365,6 +400,19 @@ export default function Account() {
                 </Accordion>
               ))}
               
+              {addresses.length < walletInfo.address_count && (
+                <Box sx={{ mt: 1, display: 'flex', justifyContent: 'center' }}>
+                  <Button
+                    size="small"
+                    onClick={loadMoreAddresses}
+                    disabled={isLoadingAddresses}
+                    startIcon={isLoadingAddresses ? <CircularProgress size={16} /> : <ExpandMore />}
+                  >
+                    Show more ({walletInfo.address_count - addresses.length} remaining)
+                  </Button>
+                </Box>
+              )}
+              
               {/* Add New Address Button */}
               <Box sx={{ mt: 2, display: 'flex', justifyContent: 'center' }}>
                 <Button
@@ -466,7 +514,7 @@ export default function Account() {
                   <Box sx={{ width: '100%', display: 'flex', alignItems: 'center' }}>
                     <AccountTree sx={{ mr: 2, color: 'primary.main' }} />
                     <Typography sx={{ fontSize: '0.9rem', flex: 1, fontWeight: 500 }}>
-                      Derived Child Keys ({walletInfo.addresses.length})
+                      Derived Child Keys ({walletInfo.address_count})
                     </Typography>
                     <Chip 
                       label="BIP32" 
@@ -478,7 +526,7 @@ export default function Account() {
                 </AccordionSummary>
                 <AccordionDetails>
                   <List dense>
-                    {walletInfo.addresses.map((address, index) => (
+                    {addresses.map((address, index) => (
                       <ListItem key={index}>
                         <ListItemText 
                           primary={
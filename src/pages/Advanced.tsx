@@ -39,6 +39,7 @@ import SecureWalletDialog from '../components/SecureWalletDialog';
 import AddressSelectionDialog from '../components/AddressSelectionDialog';
 import { invoke } from '@tauri-apps/api/core';
 import { MiningConfiguration } from '../types/mining';
+import { getErrorMessage } from '../lib/errors';
 
 export default function Advanced() {
   const theme = useTheme();
@@ -156,7 +157,7 @@ function SystemTraySettingsSection() {
     try {
       await updateMinimizeToSystemTray(enabled);
     } catch (err) {
-      setError(err instanceof Error ? err.message : 'Failed to update setting');
+      setError(getErrorMessage(err));
       console.error('Error updating minimize to system tray setting:', err);
     } finally {
       setIsUpdating(false);
@@ -271,6 +272,9 @@ function WalletLocationSection() {  const {
   const [deleteError, setDeleteError] = useState<string | null>(null);  const [isDeleting, setIsDeleting] = useState(false);  const [secureDialogOpen, setSecureDialogOpen] = useState(false);
   const [privateKey, setPrivateKey] = useState('');
   const [privateKeyLoading, setPrivateKeyLoading] = useState(false);
+  const [privateKeyDialogOpen, setPrivateKeyDialogOpen] = useState(false);
+  const [privateKeyPassword, setPrivateKeyPassword] = useState('');
+  const [privateKeyError, setPrivateKeyError] = useState<string | null>(null);
   const [showCopySuccess, setShowCopySuccess] = useState(false);
   const theme = useTheme(); // Add this line to get the theme object
   const isDarkMode = theme.palette.mode === 'dark'; // Add this line
@@ -345,7 +349,7 @@ function WalletLocationSection() {  const {
       setDeleteDialogOpen(false);
     } catch (error) {
       console.error('Error deleting wallet:', error);
-      setDeleteError(`Failed to delete wallet: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      setDeleteError(`Failed to delete wallet: ${getErrorMessage(error)}`);
     } finally {
       setIsDeleting(false);
     }
@@ -365,25 +369,36 @@ function WalletLocationSection() {  const {
     const handleSuccessfulSecurity = async () => {
     // Refresh wallet details to update the security status
     await refreshWalletDetails();
-  };  const handleShowPrivateKey = async () => {
+  };  const handleShowPrivateKey = () => {
     if (!currentWallet) return;
     
-    // Reset previous state
+    // Reset previous state and ask the user to confirm (and re-enter the password for secured wallets)
     setPrivateKey('');
-    
-    // Since the wallet data is already decrypted in memory when opened, 
-    // we can directly get the private key without asking for password again
-    await fetchPrivateKey();
+    setPrivateKeyPassword('');
+    setPrivateKeyError(null);
+    setPrivateKeyDialogOpen(true);
+  };
+
+  const handleClosePrivateKeyDialog = () => {
+    setPrivateKeyDialogOpen(false);
+    setPrivateKeyPassword('');
+    setPrivateKeyError(null);
   };  const fetchPrivateKey = async () => {
     if (!currentWallet) return;
     
     setPrivateKeyLoading(true);
+    setPrivateKeyError(null);
     
     try {
-      const key = await invoke<string>('get_wallet_private_key');
+      const key = await invoke<string>('get_wallet_private_key', {
+        password: isWalletSecured ? privateKeyPassword : null
+      });
+      setPrivateKeyDialogOpen(false);
+      setPrivateKeyPassword('');
       setPrivateKey(key);
     } catch (error) {
       console.error('Failed to get private key:', error);
+      setPrivateKeyError(getErrorMessage(error));
     } finally {
       setPrivateKeyLoading(false);
     }
@@ -569,6 +584,75 @@ function WalletLocationSection() {  const {
           </Button>
         </DialogActions>      </Dialog>
       
+      {/* Private Key Confirmation Dialog */}
+      <Dialog
+        open={privateKeyDialogOpen}
+        onClose={handleClosePrivateKeyDialog}
+        aria-labelledby="private-key-confirm-dialog-title"
+        TransitionComponent={Fade}
+        TransitionProps={{ timeout: 500 }}
+        PaperProps={{
+          sx: {
+            background: isDarkMode 
+              ? 'linear-gradient(145deg, #0a1929 0%, #132f4c 100%)' 
+              : 'linear-gradient(145deg, #ffffff 0%, #f5f7fa 100%)',
+            borderRadius: '12px',
+            boxShadow: '0 8px 32px rgba(0, 0, 0, 0.3)',
+            border: isDarkMode ? '1px solid rgba(255, 255, 255, 0.1)' : '1px solid rgba(0, 0, 0, 0.08)',
+            minWidth: { xs: '90%', sm: '500px' },
+          }
+        }}
+      >
+        <DialogTitle id="private-key-confirm-dialog-title" sx={{ pb: 1, display: 'flex', alignItems: 'center' }}>
+          <VisibilityIcon color="primary" sx={{ mr: 1 }} />
+          <Typography variant="h6" component="div" fontWeight={600}>
+            Reveal Private Key
+          </Typography>
+        </DialogTitle>
+        <DialogContent>
+          <DialogContentText sx={{ mb: 2 }}>
+            Anyone who sees the private key for "<strong>{currentWallet?.name}</strong>" can take your funds.
+            {isWalletSecured ? ' Enter the wallet password to continue.' : ' Make sure nobody is watching your screen.'}
+          </DialogContentText>
+          {privateKeyError && (
+            <Alert severity="error" sx={{ mb: 2 }} variant="filled">
+              {privateKeyError}
+            </Alert>
+          )}
+          {isWalletSecured && (
+            <TextField
+              autoFocus
+              margin="dense"
+              id="private-key-password"
+              label="Wallet password"
+              type="password"
+              fullWidth
+              variant="outlined"
+              value={privateKeyPassword}
+              onChange={(e) => setPrivateKeyPassword(e.target.value)}
+              onKeyDown={(e) => {
+                if (e.key === 'Enter' && privateKeyPassword) {
+                  fetchPrivateKey();
+                }
+              }}
+              sx={{ mb: 2 }}
+            />
+          )}
+        </DialogContent>
+        <DialogActions sx={{ px: 3, pb: 2 }}>
+          <Button onClick={handleClosePrivateKeyDialog} variant="outlined" sx={{ mr: 1 }}>Cancel</Button>
+          <Button 
+            onClick={fetchPrivateKey} 
+            color="primary"
+            variant="contained"
+            disabled={(isWalletSecured && !privateKeyPassword) || privateKeyLoading}
+            startIcon={privateKeyLoading ? <CircularProgress size={20} color="inherit" /> : null}
+          >
+            {privateKeyLoading ? 'Verifying...' : 'Reveal'}
+          </Button>
+        </DialogActions>
+      </Dialog>
+      
       {/* Private Key Display Dialog */}
       <Dialog
         open={!!privateKey}
@@ -732,7 +816,7 @@ function MiningControlSection() {
       } catch (err) {
         console.error('Failed to initialize mining:', err);
         setMaxCores(1); // fallback to 1 core
-        setError(err instanceof Error ? err.message : 'Failed to initialize mining');
+        setError(getErrorMessage(err));
       }
     };
     initializeMining();
@@ -746,7 +830,7 @@ function MiningControlSection() {
     try {
       await updateMiningThreads(threads);
     } catch (err) {
-      setError(err instanceof Error ? err.message : 'Failed to update mining threads');
+      setError(getErrorMessage(err));
     } finally {
       setIsUpdating(false);
     }
@@ -777,7 +861,7 @@ function MiningControlSection() {
       setMiningConfig(updatedConfig);
     } catch (err) {
       console.error('Failed to toggle mining:', err);
-      setError(err instanceof Error ? err.message : 'Failed to toggle mining');
+      setError(getErrorMessage(err));
     } finally {
       setIsMiningToggling(false);
     }
@@ -806,7 +890,7 @@ function MiningControlSection() {
       setMiningConfig(updatedConfig);
     } catch (err) {
       console.error('Failed to change mining address:', err);
-      setError(err instanceof Error ? err.message : 'Failed to change mining address');
+      setError(getErrorMessage(err));
     }
   };
 
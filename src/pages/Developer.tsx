@@ -7,6 +7,7 @@ import { SettingsItem } from '../components/ui/SettingsItem';
 import SecurityIcon from '@mui/icons-material/Security';
 import { useAppSettings } from '../context/AppSettingsContext';
 import { useWallet } from '../context/WalletContext';
+import { getErrorMessage } from '../lib/errors';
 
 export default function Developer() {
   const theme = useTheme();
@@ -45,7 +46,7 @@ export default function Developer() {
       setResult(JSON.stringify(response, null, 2));
     } catch (err) {
       console.error(err);
-      setError(`Error executing command: ${err}`);
+      setError(`Error executing command: ${getErrorMessage(err)}`);
     } finally {
       setLoading(false);
     }
@@ -58,7 +59,7 @@ export default function Developer() {
       setLogOutput(logs as string);
     } catch (err) {
       console.error(err);
-      setError(`Error fetching logs: ${err}`);
+      setError(`Error fetching logs: ${getErrorMessage(err)}`);
     } finally {
       setLoading(false);
     }
@@ -76,7 +77,7 @@ export default function Developer() {
       }
     } catch (err) {
       console.error('Error cleaning up orphaned wallets:', err);
-      setError(`Error cleaning up orphaned wallets: ${err}`);
+      setError(`Error cleaning up orphaned wallets: ${getErrorMessage(err)}`);
     } finally {
       setCleanupLoading(false);
     }
@@ -123,7 +124,7 @@ export default function Developer() {
       }
     } catch (err) {
       console.error('Error deleting all wallets:', err);
-      setError(`Error deleting all wallets: ${err}`);
+      setError(`Error deleting all wallets: ${getErrorMessage(err)}`);
     } finally {
       setDeleteAllLoading(false);
     }  };
@@ -166,7 +167,7 @@ export default function Developer() {
         console.log('Skip seed phrase dialogs setting updated successfully and persisted');
       }    } catch (err) {
       console.error('Failed to update skip seed phrase dialogs setting:', err);
-      if (err instanceof Error && err.message.includes('Developer mode must be enabled')) {
+      if (getErrorMessage(err).includes('Developer mode must be enabled')) {
         setError('Developer mode must be enabled in Settings before you can skip seed phrase dialogs.');
       } else {
         setError('Failed to update skip seed phrase dialogs setting. Changes will not persist across app restarts.');
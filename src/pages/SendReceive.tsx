@@ -14,6 +14,9 @@ import {
 } from '@mui/material';
 import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listenEvent } from '../lib/events';
+import { getWalletAddresses, takePendingPaymentUri, PaymentUri } from '../lib/wallet';
+import type { CurrentWalletInfo } from '../types/wallet';
 
 // Icons
 import SendIcon from '@mui/icons-material/Send';
@@ -47,13 +50,42 @@ export default function SendReceive() {
     loadWalletInfo();
   }, []);
 
+  // Pre-fill the send form from a brad:// payment link
+  useEffect(() => {
+    const applyPaymentUri = (payment: PaymentUri) => {
+      setTabValue(0);
+      setRecipientAddress(payment.address);
+      setSendAmount(payment.amount !== null ? (payment.amount / 100_000_000).toString() : '');
+      setSendNote(payment.message ?? payment.label ?? '');
+    };
+
+    takePendingPaymentUri()
+      .then((payment) => payment && applyPaymentUri(payment))
+      .catch((error) => console.error('Failed to read payment link:', error));
+
+    const unlisten = listenEvent('payment-uri-received', async () => {
+      const payment = await takePendingPaymentUri();
+      if (payment) {
+        applyPaymentUri(payment);
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   const loadWalletInfo = async () => {
     try {
-      const info = await invoke<any>('get_current_wallet_info');
-      if (info && info.addresses && info.addresses.length > 0) {
-        // Use the most recent address
-        const latestAddress = info.addresses[info.addresses.length - 1];
-        setWalletAddress(latestAddress.address);
+      const info = await invoke<CurrentWalletInfo | null>('get_current_wallet_info');
+      if (info && info.address_count > 0) {
+        // Use the last address listed, on the last page
+        const first = await getWalletAddresses(0);
+        const lastPage = Math.ceil(info.address_count / first.page_size) - 1;
+        const page = lastPage > 0 ? await getWalletAddresses(lastPage) : first;
+        const latestAddress = page.addresses[page.addresses.length - 1];
+        if (latestAddress) {
+          setWalletAddress(latestAddress.address);
+        }
       }
     } catch (error) {
       console.error('Failed to load wallet info:', error);
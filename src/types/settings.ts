@@ -14,4 +14,80 @@ export interface AppSettings {
   minimize_to_system_tray: boolean;
   /** Number of threads to use for mining (1 to available CPU cores) */
   mining_threads: number;
+  /** Whether to fetch exchange rates and show fiat equivalents */
+  price_feed_enabled: boolean;
+  /** HTTP source for exchange rates */
+  price_feed_url: string | null;
+  /** Seconds between exchange rate refreshes */
+  price_feed_interval_secs: number;
+  /** Currency code used for fiat equivalents, e.g. "USD" */
+  display_currency: string;
+  /** Whether to check for application updates periodically */
+  auto_update_check: boolean;
+  /** Release channel to update from */
+  update_channel: 'stable' | 'beta';
+  /** Record recent P2P messages per peer for debugging */
+  network_trace_enabled: boolean;
+  /** Periodically write the network trace to the logs directory */
+  network_trace_dump: boolean;
+  /** Outbound peers the node keeps connected */
+  max_outbound_peers: number;
+  /** Inbound peers accepted before the lowest-scoring one is evicted */
+  max_inbound_peers: number;
+  /** Peers announcing an older protocol version are disconnected during the handshake */
+  min_peer_protocol_version: number;
+  /** IP addresses of the interfaces to accept peers on; empty listens on all IPv4 and IPv6 interfaces */
+  p2p_bind_addresses: string[];
+  /** Memory the mempool may use before the lowest fee-rate transactions are evicted, in MB */
+  max_mempool_size_mb: number;
+  /** Trust built-in checkpoints during sync; off means every block is fully validated */
+  checkpoints_enabled: boolean;
+  /** Language of tray labels and error messages from the backend */
+  language: 'en' | 'es' | 'de' | 'fr';
+  /** Stream node events to external apps over a local WebSocket */
+  push_api_enabled: boolean;
+  /** Loopback port of the push API */
+  push_api_port: number;
+  /** Token external apps must present; generated when the push API is enabled */
+  push_api_token: string;
+  /** Serve Prometheus-style metrics at http://127.0.0.1:<port>/metrics */
+  metrics_enabled: boolean;
+  /** Loopback port of the metrics endpoint */
+  metrics_port: number;
+  /** Find wallet coins by matching compact block filters instead of the address index */
+  compact_filter_sync: boolean;
+  /** Keep an address to transaction index for address history lookups, at some disk cost */
+  address_index_enabled: boolean;
+  /** Lowest password strength score, 0 to 4, accepted when securing a wallet */
+  min_password_score: number;
+  /** Keep mining on battery power; off pauses mining until the machine is plugged in */
+  mine_on_battery: boolean;
+  /** Share of the time mining threads hash, in percent (10 to 100) */
+  mining_duty_cycle: number;
+  /** Run mining threads at the lowest OS priority */
+  mining_low_priority: boolean;
+  /** Serve mining work to miner processes on the local network */
+  stratum_enabled: boolean;
+  /** Port of the stratum server, reachable from other machines */
+  stratum_port: number;
+  /** Address blocks found through the stratum server pay to */
+  stratum_payout_address: string | null;
+  /** Minutes an unlocked wallet stays unlocked without use; after as long with no activity at all, every wallet locks */
+  auth_timeout_minutes: number;
+  /** Cache of the blockchain storage engine, in MiB; applied when the database next opens */
+  db_cache_capacity_mb: number;
+  /** Decoded blocks kept in memory, in MiB */
+  db_block_cache_mb: number;
+  /** Shrink the blockchain database caches while the system is short of memory */
+  db_low_memory_mode: boolean;
+  /** Lowest fee rate of a transaction the mempool accepts and relays, in sat/byte */
+  min_relay_fee_rate: number;
+  /** Largest transaction the mempool accepts and relays, in bytes */
+  max_standard_tx_size: number;
+  /** Outputs worth less than this many satoshis are dust and not relayed */
+  dust_threshold: number;
+  /** Relay transactions with outputs whose scripts are not of a standard form */
+  relay_non_standard_scripts: boolean;
+  /** Skip transaction relay to save bandwidth: no transactions from peers, fewer peers, fees estimated from blocks alone */
+  blocks_only: boolean;
 }
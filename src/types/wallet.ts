@@ -2,10 +2,39 @@
 
 export interface CurrentWalletInfo {
   name: string;
-  addresses: AddressDetails[];
+  /** Addresses in the wallet; getWalletAddresses lists them a page at a time */
+  address_count: number;
   master_public_key: string;
   balance: number;
+  /** Part of the balance with the wallet's minimum confirmations */
+  spendable_balance: number;
+  /** Part of the balance still waiting for confirmations */
+  pending_balance: number;
+  /** Part of the balance in coins the user froze */
+  frozen_balance: number;
+  /** Fiat equivalent of the balance when the price feed is enabled */
+  balance_fiat: FiatAmount | null;
   is_secured: boolean;
+  /** Set when the wallet was opened from read-only or removable media; changes are not saved */
+  read_only: 'read_only_file' | 'read_only_media' | 'removable_media' | null;
+}
+
+export interface FiatAmount {
+  currency: string;
+  amount: number;
+  /** Amount formatted for display, e.g. "$1,234.56" */
+  formatted: string;
+  /** Unix timestamp of the rate used */
+  as_of: number;
+  /** Whether the rate is out of date, e.g. while offline */
+  is_stale: boolean;
+}
+
+export interface ExchangeRate {
+  currency: string;
+  /** Price of one BRAD in the currency */
+  rate: number;
+  as_of: number;
 }
 
 export interface AddressDetails {
@@ -14,6 +43,17 @@ export interface AddressDetails {
   derivation_path: string;
   address_type: string;
   label?: string;
+  /** Left behind by a re-key; not offered for receiving */
+  retired: boolean;
+}
+
+export interface AddressPage {
+  addresses: AddressDetails[];
+  /** Zero-based */
+  page: number;
+  page_size: number;
+  /** Addresses across all pages */
+  total: number;
 }
 
 export interface WalletInfo {